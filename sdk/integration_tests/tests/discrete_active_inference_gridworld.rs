@@ -0,0 +1,130 @@
+//! Kiểm chứng engine active inference rời rạc (A/B/C/D) trên GridWorld của
+//! `pandora_simulation`: lập kế hoạch với mô hình đúng và học B từ trải nghiệm.
+
+use ndarray::{Array1, Array2};
+use pandora_learning_engine::{
+    DiscreteActiveInferenceAgent, DiscreteAgentConfig, DiscreteGenerativeModel,
+};
+use pandora_simulation::grid_world::{
+    Action, ActionResult, Direction, GridWorld, ObservabilityMode,
+};
+
+const SIZE: usize = 5;
+const ACTIONS: [Action; 5] = [
+    Action::Move(Direction::North),
+    Action::Move(Direction::South),
+    Action::Move(Direction::East),
+    Action::Move(Direction::West),
+    Action::Wait,
+];
+
+/// 5x5 với tường bao quanh và một bức tường chắn giữa; agent ở (1,1), đích ở (3,3).
+fn world() -> GridWorld {
+    let mut world = GridWorld::new(SIZE, SIZE, ObservabilityMode::Full);
+    for i in 0..SIZE {
+        world.place_wall((i, 0));
+        world.place_wall((i, SIZE - 1));
+        world.place_wall((0, i));
+        world.place_wall((SIZE - 1, i));
+    }
+    world.place_wall((2, 2));
+    world
+}
+
+fn state_of(pos: (i32, i32)) -> usize {
+    pos.1 as usize * SIZE + pos.0 as usize
+}
+
+/// B đúng, đọc từ chính GridWorld bằng cách thử từng hành động ở từng ô.
+fn true_transitions() -> Vec<Array2<f64>> {
+    let n = SIZE * SIZE;
+    ACTIONS
+        .iter()
+        .map(|&action| {
+            let mut b = Array2::zeros((n, n));
+            for s in 0..n {
+                let mut w = world();
+                w.agent_pos = ((s % SIZE) as i32, (s / SIZE) as i32);
+                w.submit_action(action).unwrap();
+                b[[state_of(w.agent_pos), s]] = 1.0;
+            }
+            b
+        })
+        .collect()
+}
+
+fn model(b: Vec<Array2<f64>>) -> DiscreteGenerativeModel {
+    let n = SIZE * SIZE;
+    let goal = state_of(world().goal_pos);
+    let mut c = Array1::zeros(n);
+    c[goal] = 4.0;
+    let mut d = Array1::zeros(n);
+    d[state_of(world().agent_pos)] = 1.0;
+    DiscreteGenerativeModel::new(Array2::eye(n), b, c, d).unwrap()
+}
+
+fn steps_to_goal(agent: &mut DiscreteActiveInferenceAgent, max_steps: usize) -> Option<usize> {
+    let mut env = world();
+    for step in 1..=max_steps {
+        let action = agent.step(state_of(env.agent_pos)).unwrap();
+        if env.submit_action(ACTIONS[action]).unwrap() == ActionResult::ReachedGoal {
+            return Some(step);
+        }
+    }
+    None
+}
+
+fn config() -> DiscreteAgentConfig {
+    DiscreteAgentConfig {
+        policy_horizon: 4,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn discrete_active_inference_reaches_goal_with_true_model() {
+    let mut agent = DiscreteActiveInferenceAgent::new(model(true_transitions()), config()).unwrap();
+    // Khoảng cách Manhattan là 4 và bức tường giữa không chặn đường đi ngắn nhất
+    assert_eq!(steps_to_goal(&mut agent, 10), Some(4));
+    let goal = state_of(world().goal_pos);
+    assert!(agent.infer_states(goal).unwrap()[goal] > 0.99);
+}
+
+#[test]
+fn discrete_active_inference_learns_transitions_from_exploration() {
+    let n = SIZE * SIZE;
+    let flat = vec![Array2::from_elem((n, n), 0.05); ACTIONS.len()];
+    let mut learner = DiscreteActiveInferenceAgent::new(model(flat.clone()), config())
+        .unwrap()
+        .with_dirichlet_b(flat)
+        .unwrap();
+
+    // Khám phá ngẫu nhiên (có seed); đếm Dirichlet của B được cập nhật trong infer_states
+    let mut rng = fastrand::Rng::with_seed(7);
+    let mut env = world();
+    for _ in 0..1500 {
+        learner.infer_states(state_of(env.agent_pos)).unwrap();
+        let action = rng.usize(..ACTIONS.len());
+        learner.record_action(action).unwrap();
+        env.submit_action(ACTIONS[action]).unwrap();
+    }
+
+    let truth = true_transitions();
+    let learned = learner.model().b();
+    for (a, b) in truth.iter().enumerate() {
+        for s in [6, 7, 8, 11, 13, 16, 17, 18] {
+            let predicted = learned[a].column(s);
+            let expected = b.column(s);
+            let argmax = |c: ndarray::ArrayView1<f64>| {
+                c.iter()
+                    .enumerate()
+                    .fold((0, f64::MIN), |m, (i, &v)| if v > m.1 { (i, v) } else { m })
+                    .0
+            };
+            assert_eq!(argmax(predicted), argmax(expected), "action {a}, state {s}");
+        }
+    }
+
+    learner.reset();
+    assert!(steps_to_goal(&mut learner, 12).is_some());
+}
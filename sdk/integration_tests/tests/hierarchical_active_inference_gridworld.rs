@@ -0,0 +1,181 @@
+//! Active inference phân cấp trên GridWorld: tầng trên lập kế hoạch theo các mục
+//! tiêu con "lấy chìa khóa → mở cửa → tới đích", tầng dưới chọn hành động nguyên thủy.
+
+use ndarray::{Array1, Array2};
+use pandora_learning_engine::{
+    DiscreteActiveInferenceAgent, DiscreteAgentConfig, DiscreteGenerativeModel,
+    HierarchicalActiveInferenceAgent, LevelLink,
+};
+use pandora_simulation::grid_world::{
+    Action, ActionResult, Cell, Direction, GridWorld, ObservabilityMode,
+};
+
+/// Hành lang `#K@.D.G#`: chìa khóa ở sau lưng agent, cửa đóng chắn đường tới đích.
+const CORRIDOR: usize = 6;
+const KEY_X: usize = 1;
+const START_X: i32 = 2;
+const DOOR_X: usize = 4;
+const GOAL_X: i32 = 6;
+const ACTIONS: [Action; 4] = [
+    Action::Move(Direction::West),
+    Action::Move(Direction::East),
+    Action::Pickup,
+    Action::Use,
+];
+const GET_KEY: usize = 0;
+const OPEN_DOOR: usize = 1;
+const REACH_GOAL: usize = 2;
+
+fn world(has_key: bool, door_open: bool) -> GridWorld {
+    let mut world = GridWorld::new(CORRIDOR + 2, 3, ObservabilityMode::Full);
+    for x in 0..CORRIDOR + 2 {
+        world.place_wall((x, 0));
+        world.place_wall((x, 2));
+    }
+    world.place_wall((0, 1));
+    world.place_wall((CORRIDOR + 1, 1));
+    if !has_key {
+        world.place_key((KEY_X, 1));
+    }
+    world.set_has_key(has_key);
+    world.place_door((DOOR_X, 1), door_open);
+    world.agent_pos = (START_X, 1);
+    world.goal_pos = (GOAL_X, 1);
+    world
+}
+
+/// Trạng thái (và quan sát) tầng dưới: vị trí × có chìa khóa × cửa mở.
+fn lower_state(world: &GridWorld) -> usize {
+    let door_open = world.cell_at((DOOR_X as i32, 1)) == Some(Cell::Door(true));
+    (world.agent_pos.0 as usize - 1)
+        + CORRIDOR * (world.has_key() as usize + 2 * door_open as usize)
+}
+
+fn decode(state: usize) -> (i32, bool, bool) {
+    let x = (state % CORRIDOR) as i32 + 1;
+    let flags = state / CORRIDOR;
+    (x, flags % 2 == 1, flags / 2 == 1)
+}
+
+fn lower_model(goal_preference: usize) -> DiscreteGenerativeModel {
+    let n = 4 * CORRIDOR;
+    let b = ACTIONS
+        .iter()
+        .map(|&action| {
+            let mut b = Array2::zeros((n, n));
+            for s in 0..n {
+                let (x, has_key, door_open) = decode(s);
+                let mut w = world(has_key, door_open);
+                w.agent_pos = (x, 1);
+                w.submit_action(action).unwrap();
+                b[[lower_state(&w), s]] = 1.0;
+            }
+            b
+        })
+        .collect();
+    let mut d = Array1::zeros(n);
+    d[lower_state(&world(false, false))] = 1.0;
+    DiscreteGenerativeModel::new(Array2::eye(n), b, subgoal_preferences(goal_preference), d)
+        .unwrap()
+}
+
+/// Tầng trên quan sát giai đoạn: 0 bắt đầu, 1 có chìa khóa, 2 cửa mở, 3 tới đích.
+fn phase(state: usize) -> usize {
+    match decode(state) {
+        (GOAL_X, _, _) => 3,
+        (_, _, true) => 2,
+        (_, true, _) => 1,
+        _ => 0,
+    }
+}
+
+fn subgoal_preferences(subgoal: usize) -> Array1<f64> {
+    Array1::from_iter((0..4 * CORRIDOR).map(|s| {
+        let (x, has_key, door_open) = decode(s);
+        let reached = match subgoal {
+            GET_KEY => has_key,
+            OPEN_DOOR => door_open,
+            _ => x == GOAL_X,
+        };
+        if reached {
+            3.0
+        } else {
+            0.0
+        }
+    }))
+}
+
+fn upper_model() -> DiscreteGenerativeModel {
+    // Mỗi mục tiêu con chỉ đưa giai đoạn tiến lên một bước từ đúng giai đoạn trước nó
+    let advance = |from: usize| {
+        let mut b = Array2::eye(4);
+        b[[from, from]] = 0.0;
+        b[[from + 1, from]] = 1.0;
+        b
+    };
+    DiscreteGenerativeModel::new(
+        Array2::eye(4),
+        vec![advance(0), advance(1), advance(2)],
+        ndarray::array![0.0, 1.0, 2.0, 4.0],
+        ndarray::array![1.0, 0.0, 0.0, 0.0],
+    )
+    .unwrap()
+}
+
+fn config() -> DiscreteAgentConfig {
+    DiscreteAgentConfig {
+        policy_horizon: 3,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn hierarchical_agent_plans_key_door_goal_subgoals() {
+    let link = LevelLink {
+        subgoal_preferences: (0..3).map(subgoal_preferences).collect(),
+        state_prior: None,
+        aggregate: (0..4 * CORRIDOR).map(phase).collect(),
+        max_steps: 6,
+    };
+    let mut agent = HierarchicalActiveInferenceAgent::new(
+        vec![
+            DiscreteActiveInferenceAgent::new(lower_model(REACH_GOAL), config()).unwrap(),
+            DiscreteActiveInferenceAgent::new(upper_model(), config()).unwrap(),
+        ],
+        vec![link],
+    )
+    .unwrap();
+
+    let mut env = world(false, false);
+    let mut subgoals = Vec::new();
+    let mut steps = 0;
+    let reached = loop {
+        steps += 1;
+        let decision = agent.step(lower_state(&env)).unwrap();
+        subgoals.extend(decision.new_subgoals.iter().map(|&(_, g)| g));
+        if env.submit_action(ACTIONS[decision.action]).unwrap() == ActionResult::ReachedGoal {
+            break true;
+        }
+        if steps >= 20 {
+            break false;
+        }
+    };
+
+    assert!(reached, "subgoals so far: {subgoals:?}");
+    assert_eq!(subgoals, vec![GET_KEY, OPEN_DOOR, REACH_GOAL]);
+    // 2 bước lấy chìa khóa, 3 bước mở cửa, 3 bước tới đích
+    assert_eq!(steps, 8);
+}
+
+#[test]
+fn flat_agent_with_same_horizon_cannot_see_past_the_door() {
+    let mut agent = DiscreteActiveInferenceAgent::new(lower_model(REACH_GOAL), config()).unwrap();
+    let mut env = world(false, false);
+    for _ in 0..20 {
+        let action = agent.step(lower_state(&env)).unwrap();
+        assert_ne!(
+            env.submit_action(ACTIONS[action]).unwrap(),
+            ActionResult::ReachedGoal
+        );
+    }
+}
@@ -1,8 +1,25 @@
+use pandora_cwm::gnn::types::{CausalEdge, CausalEdgeKind, CausalGraph};
 use pandora_cwm::interdependent_repr::irl::{
     EntityState, InterdependentEntity, InterdependentNetwork,
 };
 use pandora_cwm::interdependent_repr::itr_nn::InterdependentTopoRelationalNN;
 
+/// Tạo đồ thị nhân quả dạng chuỗi với các trọng số cạnh cho trước.
+fn chain_graph(weights: &[f32]) -> CausalGraph {
+    let mut graph = CausalGraph::new();
+    let nodes: Vec<_> = (0..=weights.len())
+        .map(|_| graph.add_node(vec![0.0; 4]))
+        .collect();
+    for (i, &w) in weights.iter().enumerate() {
+        graph.add_edge(
+            nodes[i],
+            nodes[i + 1],
+            CausalEdge::new(CausalEdgeKind::Cause, w),
+        );
+    }
+    graph
+}
+
 #[tokio::test]
 async fn test_interdependent_insight() {
     println!("\n=============================================");
@@ -13,11 +30,12 @@ async fn test_interdependent_insight() {
     println!("\n--- TEST ITR-NN: Phân tích Dữ liệu Topo ---");
     let itr_nn = InterdependentTopoRelationalNN::new();
 
-    // Tạo dữ liệu đồ thị giả lập (các nút và cạnh)
-    let graph_data = vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8];
-    itr_nn.process_graph(&graph_data);
+    // Tạo đồ thị giả lập (các nút và cạnh)
+    let graph = chain_graph(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7]);
+    let features = itr_nn.process_graph(&graph);
+    assert_eq!(features.feature_vector().len(), itr_nn.feature_len());
 
-    println!("✅ ITR-NN: Đã xử lý đồ thị với {} nút", graph_data.len());
+    println!("✅ ITR-NN: Đã xử lý đồ thị với {} nút", features.node_count);
 
     // --- Test IRL (Interdependent Representation Learning) ---
     println!("\n--- TEST IRL: Học Biểu diễn Duyên khởi ---");
@@ -56,7 +74,9 @@ async fn test_interdependent_insight() {
     network.add_relationship("B", "C", 0.9);
 
     // Học biểu diễn duyên khởi cho thực thể A
-    network.learn_interdependent_representation("A");
+    network
+        .learn_interdependent_representation("A")
+        .expect("các context cùng số chiều");
 
     // Tìm các thực thể có ảnh hưởng mạnh nhất đến A
     let influencers = network.find_key_influencers("A");
@@ -69,18 +89,23 @@ async fn test_interdependent_insight() {
     }
 
     // Cập nhật trạng thái và lan truyền ảnh hưởng
-    network.update_entity_state("C", EntityState::Active);
+    network
+        .update_entity_state("C", EntityState::Active)
+        .expect("các context cùng số chiều");
     println!("✅ IRL: Đã cập nhật trạng thái C và lan truyền ảnh hưởng");
 
     // --- Test kết hợp ITR-NN và IRL ---
     println!("\n--- TEST KẾT HỢP: ITR-NN + IRL ---");
 
     // Sử dụng ITR-NN để phân tích cấu trúc topo của mạng lưới
-    let network_structure = vec![0.8, 0.6, 0.9, 0.7, 0.5, 0.3, 0.4, 0.2];
-    itr_nn.process_graph(&network_structure);
+    let network_structure = chain_graph(&[0.8, 0.6, 0.9, 0.7, 0.5, 0.3, 0.4]);
+    let features = itr_nn.process_graph(&network_structure);
+    assert_eq!(features.node_count, network_structure.node_count());
 
     // Sử dụng IRL để học biểu diễn duyên khởi
-    network.learn_interdependent_representation("B");
+    network
+        .learn_interdependent_representation("B")
+        .expect("các context cùng số chiều");
 
     println!("✅ KẾT HỢP: ITR-NN phân tích cấu trúc, IRL học quan hệ duyên khởi");
 
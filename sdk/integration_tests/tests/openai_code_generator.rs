@@ -0,0 +1,258 @@
+//! `OpenAiCodeGenerator` chạy với một mock server `/v1/chat/completions` cục bộ:
+//! trả lời thường và streaming (SSE), retry khi lỗi 5xx / program bị từ chối, timeout.
+
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use pandora_learning_engine::skill_forge_lightweight::Intent;
+use pandora_learning_engine::{
+    CodeGenerator, LLMCodeGenerator, OpenAiCodeGenerator, OpenAiCodeGeneratorConfig, QueSTEncoder,
+    SkillContext, SkillForge, VectorQuantizer,
+};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+enum Reply {
+    Status(u16),
+    Content(String),
+    /// Sent as server-sent events, one delta per event, in small network chunks.
+    Stream(Vec<String>),
+    Slow(Duration, String),
+}
+
+#[derive(Default)]
+struct MockServer {
+    replies: Mutex<VecDeque<Reply>>,
+    requests: Mutex<Vec<Value>>,
+}
+
+fn completion(content: &str) -> Value {
+    json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }]
+    })
+}
+
+async fn chat_completions(
+    State(server): State<Arc<MockServer>>,
+    Json(request): Json<Value>,
+) -> Response {
+    server.requests.lock().unwrap().push(request);
+    let reply = server.replies.lock().unwrap().pop_front();
+    match reply.expect("unexpected chat completion request") {
+        Reply::Status(code) => StatusCode::from_u16(code).unwrap().into_response(),
+        Reply::Content(content) => Json(completion(&content)).into_response(),
+        Reply::Slow(delay, content) => {
+            tokio::time::sleep(delay).await;
+            Json(completion(&content)).into_response()
+        }
+        Reply::Stream(deltas) => {
+            let mut events = String::new();
+            for delta in deltas {
+                let event = json!({ "choices": [{ "index": 0, "delta": { "content": delta } }] });
+                events.push_str(&format!("data: {event}\n\n"));
+            }
+            events.push_str("data: [DONE]\n\n");
+            let chunks: Vec<Result<Bytes, std::convert::Infallible>> = events
+                .into_bytes()
+                .chunks(7)
+                .map(|c| Ok(Bytes::copy_from_slice(c)))
+                .collect();
+            (
+                [(header::CONTENT_TYPE, "text/event-stream")],
+                Body::from_stream(futures::stream::iter(chunks)),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn start(replies: Vec<Reply>) -> (String, Arc<MockServer>) {
+    let server = Arc::new(MockServer {
+        replies: Mutex::new(replies.into()),
+        ..Default::default()
+    });
+    let app = Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .with_state(server.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{addr}"), server)
+}
+
+fn generator(
+    base_url: String,
+    configure: impl FnOnce(&mut OpenAiCodeGeneratorConfig),
+) -> OpenAiCodeGenerator {
+    let mut config = OpenAiCodeGeneratorConfig {
+        base_url,
+        model: "mock-coder".to_string(),
+        timeout: Duration::from_secs(5),
+        retry_backoff: Duration::from_millis(10),
+        ..Default::default()
+    };
+    configure(&mut config);
+    OpenAiCodeGenerator::new(config).unwrap()
+}
+
+fn context() -> SkillContext {
+    SkillContext {
+        performance_target: 0.9,
+        memory_limit_mb: 64,
+        cpu_cores: 1,
+        gpu_available: false,
+        network_bandwidth_mbps: 0,
+        latency_requirement_ms: 100,
+    }
+}
+
+fn equilibrium() -> Intent {
+    Intent::MaintainEquilibrium {
+        current_state: HashMap::from([("temperature".to_string(), 10.0)]),
+        target_state: HashMap::from([("temperature".to_string(), 20.0)]),
+    }
+}
+
+/// A program with the interface SkillForge expects, as a model would write it.
+async fn valid_program(intent: &Intent) -> String {
+    LLMCodeGenerator::new("template".to_string(), 4096, 0.0)
+        .generate_skill_code(intent, &context())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn forged_skill_comes_from_chat_completion() {
+    let intent = equilibrium();
+    let program = valid_program(&intent).await;
+    let reply =
+        format!("Here is the skill:\n```json\n{program}\n```\nIt moves halfway to the target.");
+    let (url, server) = start(vec![Reply::Content(reply)]).await;
+
+    let mut forge = SkillForge::new(
+        QueSTEncoder::new(256, 16, 1, 2, 32, false),
+        VectorQuantizer::new(2, 16, 0.25),
+        Box::new(generator(url, |_| {})),
+    );
+    let skill_id = forge.forge_skill(&intent, &context()).await.unwrap();
+    match forge.execute_skill(&skill_id, &intent).await.unwrap() {
+        Intent::MaintainEquilibrium { current_state, .. } => {
+            assert_eq!(current_state["temperature"], 15.0)
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+
+    let requests = server.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["model"], "mock-coder");
+    assert_eq!(requests[0]["stream"], false);
+    assert_eq!(requests[0]["messages"][0]["role"], "system");
+    assert!(requests[0]["messages"][1]["content"]
+        .as_str()
+        .unwrap()
+        .contains("Interface"));
+}
+
+#[tokio::test]
+async fn streamed_reply_is_assembled_from_deltas() {
+    let intent = equilibrium();
+    let program = valid_program(&intent).await;
+    let reply = format!("```json\n{program}\n```");
+    let deltas = reply
+        .as_bytes()
+        .chunks(40)
+        .map(|c| String::from_utf8(c.to_vec()).unwrap())
+        .collect();
+    let (url, server) = start(vec![Reply::Stream(deltas)]).await;
+
+    let code = generator(url, |c| c.stream = true)
+        .generate_skill_code(&intent, &context())
+        .await
+        .unwrap();
+    assert_eq!(code, program);
+    assert_eq!(server.requests.lock().unwrap()[0]["stream"], true);
+}
+
+#[tokio::test]
+async fn server_errors_and_rejected_programs_are_retried() {
+    let intent = equilibrium();
+    let program = valid_program(&intent).await;
+    let ill_typed = program.replace("\"number\"", "\"text\"");
+    let (url, server) = start(vec![
+        Reply::Status(503),
+        Reply::Content(format!("```json\n{ill_typed}\n```")),
+        Reply::Content(format!("```json\n{program}\n```")),
+    ])
+    .await;
+
+    let code = generator(url, |c| {
+        c.max_retries = 1;
+        c.max_validation_retries = 1;
+    })
+    .generate_skill_code(&intent, &context())
+    .await
+    .unwrap();
+    assert_eq!(code, program);
+
+    let requests = server.requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    // The rejection is fed back to the model before the retry
+    let messages = requests[2]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 4);
+    assert!(messages[3]["content"]
+        .as_str()
+        .unwrap()
+        .contains("rejected"));
+}
+
+#[tokio::test]
+async fn validation_retries_have_their_own_budget() {
+    let intent = equilibrium();
+    let program = valid_program(&intent).await;
+    let ill_typed = program.replace("\"number\"", "\"text\"");
+    let (url, server) = start(vec![
+        Reply::Content(format!("```json\n{ill_typed}\n```")),
+        Reply::Content(format!("```json\n{program}\n```")),
+    ])
+    .await;
+
+    // Transport retries do not extend the number of regenerations
+    let result = generator(url, |c| {
+        c.max_retries = 3;
+        c.max_validation_retries = 0;
+    })
+    .generate_skill_code(&intent, &context())
+    .await;
+    assert!(result.is_err());
+    assert_eq!(server.requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn client_errors_fail_fast_and_slow_servers_time_out() {
+    let intent = equilibrium();
+    let (url, server) = start(vec![Reply::Status(400)]).await;
+    assert!(generator(url, |_| {})
+        .generate_skill_code(&intent, &context())
+        .await
+        .is_err());
+    assert_eq!(server.requests.lock().unwrap().len(), 1);
+
+    let program = valid_program(&intent).await;
+    let (url, _server) = start(vec![Reply::Slow(Duration::from_secs(2), program)]).await;
+    let slow = generator(url, |c| {
+        c.timeout = Duration::from_millis(200);
+        c.max_retries = 0;
+    });
+    assert!(slow.generate_skill_code(&intent, &context()).await.is_err());
+}
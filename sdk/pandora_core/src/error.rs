@@ -20,6 +20,9 @@ pub enum PandoraError {
     #[error("Prediction failed: {0}")]
     PredictionFailed(String),
 
+    #[error("Resource exhausted: {0}")]
+    ResourceExhausted(String),
+
     #[error("Skill verification failed: {0}")]
     SkillVerificationFailed(String),
 
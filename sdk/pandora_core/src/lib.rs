@@ -9,6 +9,7 @@
 //! - **`ontology`**: Các cấu trúc dữ liệu cốt lõi như `DataEidos` và `CognitiveFlow`.
 //! - **`interfaces`**: Các giao diện (traits) trừu tượng định hình nên một thực thể nhận thức,
 //!   bao gồm `FEPCell` và các tầng `ACALayer`.
+//! - **`sdr`**: Lớp mã hóa giữa SDR (`DataEidos`) và embedding dense của CWM.
 
 pub mod aca_layer;
 pub mod error;
@@ -16,6 +17,7 @@ pub mod fep_cell;
 pub mod intents;
 pub mod interfaces;
 pub mod ontology;
+pub mod sdr;
 pub mod skandha_implementations;
 pub mod string_interner;
 pub mod world_model;
@@ -143,6 +143,28 @@ pub enum Vedana {
     Neutral,
 }
 
+/// Độ bất định dự báo, tách thành phần nhận thức (epistemic: do mô hình thiếu hiểu
+/// biết, giảm khi có thêm dữ liệu) và phần ngẫu nhiên (aleatoric: nhiễu vốn có của
+/// thế giới). Cả hai là phương sai trung bình trên các chiều dự báo.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PredictiveUncertainty {
+    pub epistemic: f32,
+    pub aleatoric: f32,
+}
+
+impl PredictiveUncertainty {
+    /// Tổng phương sai dự báo (định luật phương sai toàn phần).
+    pub fn total(&self) -> f32 {
+        self.epistemic + self.aleatoric
+    }
+
+    /// Độ bất định chuẩn hóa về [0, 1): `total / (1 + total)`.
+    pub fn score(&self) -> f32 {
+        let total = self.total().max(0.0);
+        total / (1.0 + total)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct EpistemologicalFlow {
     pub rupa: Option<bytes::Bytes>,
@@ -150,9 +172,30 @@ pub struct EpistemologicalFlow {
     pub sanna: Option<DataEidos>,
     pub related_eidos: Option<smallvec::SmallVec<[DataEidos; 4]>>,
     pub sankhara: Option<Arc<str>>,
+    /// Độ bất định của dự báo gần nhất của world model cho dòng chảy này.
+    pub uncertainty: Option<PredictiveUncertainty>,
 }
 
 impl EpistemologicalFlow {
+    /// Tạo dòng chảy từ các uẩn; `uncertainty` để trống cho tới khi world model
+    /// dự báo. Dùng hàm này (hoặc `..Default::default()`) thay cho struct literal
+    /// liệt kê đủ trường, để thêm trường mới không làm vỡ mã gọi.
+    pub fn new(
+        rupa: Option<bytes::Bytes>,
+        vedana: Option<Vedana>,
+        sanna: Option<DataEidos>,
+        related_eidos: Option<smallvec::SmallVec<[DataEidos; 4]>>,
+        sankhara: Option<Arc<str>>,
+    ) -> Self {
+        Self {
+            rupa,
+            vedana,
+            sanna,
+            related_eidos,
+            sankhara,
+            uncertainty: None,
+        }
+    }
     pub fn from_bytes(bytes: bytes::Bytes) -> Self {
         Self {
             rupa: Some(bytes),
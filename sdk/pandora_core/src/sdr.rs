@@ -0,0 +1,526 @@
+//! # Sparse Distributed Representations (SDR)
+//!
+//! `DataEidos` là một SDR: tập chỉ số bit đang bật trong không gian `dimensionality` bit.
+//! Module này cung cấp lớp mã hóa nối SDR với không gian vector dày (dense) mà CWM sử dụng:
+//!
+//! - Các phép đo trên SDR: overlap, overlap score, Jaccard.
+//! - Hợp (union) và gộp (bundle) nhiều SDR.
+//! - `RandomProjection`: SDR ↔ dense bằng phép chiếu ngẫu nhiên cố định và k-winners-take-all.
+//! - `SpatialPooler`: học cách biến byte thô (`rupa`) thành `DataEidos` ổn định.
+//!
+//! Mọi thành phần đều tất định theo `seed`, không cần thư viện sinh số ngẫu nhiên.
+
+use crate::error::{PResult, PandoraError};
+use crate::ontology::DataEidos;
+use fnv::FnvHashSet;
+
+/// Bộ sinh số giả ngẫu nhiên SplitMix64 (nhỏ, nhanh, tất định).
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Số thực đều trong [0, 1).
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+fn check_same_space(a: &DataEidos, b: &DataEidos) -> PResult<()> {
+    if a.dimensionality != b.dimensionality {
+        return Err(PandoraError::EncodingError(format!(
+            "SDR dimensionality mismatch: {} vs {}",
+            a.dimensionality, b.dimensionality
+        )));
+    }
+    Ok(())
+}
+
+/// Số bit cùng bật trong hai SDR.
+pub fn overlap(a: &DataEidos, b: &DataEidos) -> usize {
+    let (small, large) = if a.active_indices.len() <= b.active_indices.len() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    small
+        .active_indices
+        .iter()
+        .filter(|i| large.active_indices.contains(i))
+        .count()
+}
+
+/// Overlap chuẩn hóa theo SDR thưa hơn, trong [0, 1].
+pub fn overlap_score(a: &DataEidos, b: &DataEidos) -> f64 {
+    let denom = a.active_indices.len().min(b.active_indices.len());
+    if denom == 0 {
+        return 0.0;
+    }
+    overlap(a, b) as f64 / denom as f64
+}
+
+/// Chỉ số Jaccard `|a ∩ b| / |a ∪ b|`, trong [0, 1].
+pub fn jaccard(a: &DataEidos, b: &DataEidos) -> f64 {
+    let shared = overlap(a, b);
+    let union = a.active_indices.len() + b.active_indices.len() - shared;
+    if union == 0 {
+        return 0.0;
+    }
+    shared as f64 / union as f64
+}
+
+/// Hợp của nhiều SDR cùng không gian (OR theo bit).
+pub fn union(eidos: &[DataEidos]) -> PResult<DataEidos> {
+    let first = eidos
+        .first()
+        .ok_or_else(|| PandoraError::EncodingError("Cannot union an empty SDR list".into()))?;
+    let mut active_indices = FnvHashSet::default();
+    for e in eidos {
+        check_same_space(first, e)?;
+        active_indices.extend(e.active_indices.iter().copied());
+    }
+    Ok(DataEidos {
+        active_indices,
+        dimensionality: first.dimensionality,
+    })
+}
+
+/// Gộp nhiều SDR bằng bỏ phiếu: giữ `active_bits` bit được bật nhiều nhất
+/// (hòa thì ưu tiên chỉ số nhỏ hơn). Khác với `union`, kết quả giữ nguyên độ thưa.
+pub fn bundle(eidos: &[DataEidos], active_bits: usize) -> PResult<DataEidos> {
+    let first = eidos
+        .first()
+        .ok_or_else(|| PandoraError::EncodingError("Cannot bundle an empty SDR list".into()))?;
+    let mut votes: fnv::FnvHashMap<u32, usize> = fnv::FnvHashMap::default();
+    for e in eidos {
+        check_same_space(first, e)?;
+        for &i in &e.active_indices {
+            *votes.entry(i).or_insert(0) += 1;
+        }
+    }
+    let mut ranked: Vec<(u32, usize)> = votes.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    Ok(DataEidos {
+        active_indices: ranked
+            .into_iter()
+            .take(active_bits)
+            .map(|(i, _)| i)
+            .collect(),
+        dimensionality: first.dimensionality,
+    })
+}
+
+/// k-winners-take-all: bật `k` vị trí có điểm cao nhất (hòa thì ưu tiên chỉ số nhỏ hơn).
+/// Các điểm không hữu hạn bị bỏ qua.
+pub fn k_winners_take_all(scores: &[f32], k: usize) -> DataEidos {
+    let mut ranked: Vec<(usize, f32)> = scores
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, s)| s.is_finite())
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.0.cmp(&b.0))
+    });
+    DataEidos {
+        active_indices: ranked.into_iter().take(k).map(|(i, _)| i as u32).collect(),
+        dimensionality: scores.len() as u32,
+    }
+}
+
+/// Phép chiếu ngẫu nhiên cố định giữa không gian SDR và không gian dense.
+///
+/// Mỗi bit SDR ứng với một vector ±1/√d cố định, sinh tất định từ `(seed, chỉ số bit)`
+/// nên không cần lưu ma trận. `to_dense` cộng các vector của những bit đang bật rồi
+/// chuẩn hóa L2, còn `to_sdr` chiếu ngược bằng ma trận chuyển vị và chọn `active_bits`
+/// bit thắng. Hai SDR chồng lấp nhiều cho ra hai embedding có cosine cao, và một SDR
+/// thưa đi qua `to_dense` rồi `to_sdr` được khôi phục gần đúng.
+#[derive(Debug, Clone)]
+pub struct RandomProjection {
+    sdr_dim: u32,
+    dense_dim: usize,
+    active_bits: usize,
+    seed: u64,
+}
+
+impl RandomProjection {
+    pub fn new(sdr_dim: u32, dense_dim: usize, active_bits: usize, seed: u64) -> Self {
+        Self {
+            sdr_dim,
+            dense_dim,
+            active_bits,
+            seed,
+        }
+    }
+
+    pub fn sdr_dim(&self) -> u32 {
+        self.sdr_dim
+    }
+
+    pub fn dense_dim(&self) -> usize {
+        self.dense_dim
+    }
+
+    /// Vector chiếu (chuẩn L2 = 1) của một bit. Định nghĩa với mọi chỉ số, kể cả
+    /// ngoài `sdr_dim`, nên cũng dùng được làm embedding cho một khái niệm đơn lẻ.
+    pub fn concept_vector(&self, index: u32) -> Vec<f32> {
+        let mut rng = SplitMix64(self.seed ^ (index as u64).wrapping_mul(0xD6E8_FEB8_6659_FD93));
+        let scale = 1.0 / (self.dense_dim.max(1) as f32).sqrt();
+        let mut row = Vec::with_capacity(self.dense_dim);
+        while row.len() < self.dense_dim {
+            let bits = rng.next_u64();
+            for k in 0..64.min(self.dense_dim - row.len()) {
+                row.push(if (bits >> k) & 1 == 0 { scale } else { -scale });
+            }
+        }
+        row
+    }
+
+    /// SDR → vector dense chuẩn hóa L2 (vector 0 nếu SDR rỗng).
+    pub fn to_dense(&self, eidos: &DataEidos) -> PResult<Vec<f32>> {
+        if eidos.dimensionality != self.sdr_dim {
+            return Err(PandoraError::EncodingError(format!(
+                "Expected SDR of dimensionality {}, got {}",
+                self.sdr_dim, eidos.dimensionality
+            )));
+        }
+        let mut dense = vec![0.0f32; self.dense_dim];
+        for &i in eidos.active_indices.iter().filter(|&&i| i < self.sdr_dim) {
+            for (d, w) in dense.iter_mut().zip(self.concept_vector(i)) {
+                *d += w;
+            }
+        }
+        let norm = dense.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            dense.iter_mut().for_each(|v| *v /= norm);
+        }
+        Ok(dense)
+    }
+
+    /// Vector dense → SDR `active_bits` bit bằng chiếu chuyển vị và k-WTA.
+    pub fn to_sdr(&self, dense: &[f32]) -> PResult<DataEidos> {
+        if dense.len() != self.dense_dim {
+            return Err(PandoraError::EncodingError(format!(
+                "Expected dense vector of length {}, got {}",
+                self.dense_dim,
+                dense.len()
+            )));
+        }
+        let scores: Vec<f32> = (0..self.sdr_dim)
+            .map(|i| {
+                self.concept_vector(i)
+                    .iter()
+                    .zip(dense)
+                    .map(|(w, x)| w * x)
+                    .sum()
+            })
+            .collect();
+        Ok(k_winners_take_all(&scores, self.active_bits))
+    }
+}
+
+/// Cấu hình cho `SpatialPooler`.
+#[derive(Debug, Clone)]
+pub struct SpatialPoolerConfig {
+    /// Số bit của vector đầu vào sau khi mã hóa byte.
+    pub input_size: usize,
+    /// Số cột (= `dimensionality` của `DataEidos` đầu ra).
+    pub column_count: usize,
+    /// Số cột thắng mỗi bước (độ thưa của đầu ra).
+    pub active_columns: usize,
+    /// Tỉ lệ bit đầu vào mà mỗi cột có thể nối tới.
+    pub potential_pct: f32,
+    pub connected_threshold: f32,
+    pub permanence_increment: f32,
+    pub permanence_decrement: f32,
+    /// Độ mạnh boosting cho các cột ít khi thắng (0 để tắt). Chỉ áp dụng khi học.
+    pub boost_strength: f32,
+    /// Chu kỳ (số bước) của trung bình trượt tần suất thắng.
+    pub duty_cycle_period: f32,
+    /// Độ dài n-gram byte dùng để mã hóa đầu vào.
+    pub ngram: usize,
+    pub seed: u64,
+}
+
+impl Default for SpatialPoolerConfig {
+    fn default() -> Self {
+        Self {
+            input_size: 1024,
+            column_count: 2048,
+            active_columns: 40,
+            potential_pct: 0.5,
+            connected_threshold: 0.2,
+            permanence_increment: 0.05,
+            permanence_decrement: 0.008,
+            boost_strength: 1.0,
+            duty_cycle_period: 1000.0,
+            ngram: 3,
+            seed: 42,
+        }
+    }
+}
+
+/// Spatial pooler kiểu HTM: ánh xạ byte thô thành SDR có độ thưa cố định.
+///
+/// Đầu vào được mã hóa bằng cách băm các n-gram byte vào `input_size` bit. Mỗi cột giữ
+/// một tập synapse tiềm năng với độ bền (permanence); điểm của cột là số synapse đã nối
+/// trỏ tới bit đang bật, nhân với hệ số boost. `active_columns` cột cao điểm nhất thắng.
+/// Khi học, cột thắng củng cố synapse tới bit bật và làm yếu các synapse còn lại,
+/// nên cùng một đầu vào cho ra cùng một `DataEidos` và đầu vào giống nhau cho ra SDR chồng lấp.
+#[derive(Debug, Clone)]
+pub struct SpatialPooler {
+    config: SpatialPoolerConfig,
+    /// Với mỗi cột: danh sách (chỉ số bit đầu vào, permanence).
+    synapses: Vec<Vec<(u32, f32)>>,
+    /// Tần suất thắng trung bình trượt của mỗi cột.
+    duty_cycles: Vec<f32>,
+    boost: Vec<f32>,
+    iterations: u64,
+}
+
+impl SpatialPooler {
+    pub fn new(config: SpatialPoolerConfig) -> Self {
+        let mut rng = SplitMix64(config.seed);
+        let potential = ((config.input_size as f32 * config.potential_pct).round() as usize)
+            .clamp(1, config.input_size.max(1));
+        let synapses = (0..config.column_count)
+            .map(|_| {
+                // Chọn ngẫu nhiên `potential` bit đầu vào (Fisher–Yates một phần).
+                let mut pool: Vec<u32> = (0..config.input_size as u32).collect();
+                for i in 0..potential.min(pool.len()) {
+                    let j = i + (rng.next_u64() as usize) % (pool.len() - i);
+                    pool.swap(i, j);
+                }
+                pool.truncate(potential);
+                pool.sort_unstable();
+                pool.into_iter()
+                    .map(|bit| {
+                        // Permanence khởi tạo quanh ngưỡng kết nối.
+                        let p = config.connected_threshold + (rng.next_f32() - 0.5) * 0.2;
+                        (bit, p.clamp(0.0, 1.0))
+                    })
+                    .collect()
+            })
+            .collect();
+        let column_count = config.column_count;
+        Self {
+            config,
+            synapses,
+            duty_cycles: vec![0.0; column_count],
+            boost: vec![1.0; column_count],
+            iterations: 0,
+        }
+    }
+
+    pub fn config(&self) -> &SpatialPoolerConfig {
+        &self.config
+    }
+
+    /// Số bước học đã thực hiện.
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Mã hóa byte thô thành tập bit đầu vào bằng băm FNV các n-gram byte.
+    pub fn encode_input(&self, bytes: &[u8]) -> FnvHashSet<u32> {
+        let mut bits = FnvHashSet::default();
+        if self.config.input_size == 0 || bytes.is_empty() {
+            return bits;
+        }
+        let n = self.config.ngram.max(1).min(bytes.len());
+        for window in bytes.windows(n) {
+            let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+            for &b in window {
+                hash ^= b as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+            }
+            bits.insert((hash % self.config.input_size as u64) as u32);
+        }
+        bits
+    }
+
+    fn column_overlaps(&self, input: &FnvHashSet<u32>, boosted: bool) -> Vec<f32> {
+        self.synapses
+            .iter()
+            .zip(&self.boost)
+            .map(|(synapses, boost)| {
+                let connected = synapses
+                    .iter()
+                    .filter(|(bit, p)| *p >= self.config.connected_threshold && input.contains(bit))
+                    .count() as f32;
+                if boosted {
+                    connected * boost
+                } else {
+                    connected
+                }
+            })
+            .collect()
+    }
+
+    /// Tính SDR cho `bytes`. Nếu `learn` bật, cập nhật permanence và boosting.
+    pub fn compute(&mut self, bytes: &[u8], learn: bool) -> DataEidos {
+        let input = self.encode_input(bytes);
+        let overlaps = self.column_overlaps(&input, learn);
+        // Cột không có overlap nào thì không được thắng.
+        let winners = k_winners_take_all(
+            &overlaps
+                .iter()
+                .map(|&o| if o > 0.0 { o } else { f32::NAN })
+                .collect::<Vec<_>>(),
+            self.config.active_columns,
+        );
+
+        if learn {
+            self.learn(&input, &winners.active_indices);
+        }
+
+        DataEidos {
+            active_indices: winners.active_indices,
+            dimensionality: self.config.column_count as u32,
+        }
+    }
+
+    fn learn(&mut self, input: &FnvHashSet<u32>, winners: &FnvHashSet<u32>) {
+        let (inc, dec) = (
+            self.config.permanence_increment,
+            self.config.permanence_decrement,
+        );
+        for &column in winners {
+            for (bit, permanence) in &mut self.synapses[column as usize] {
+                *permanence = if input.contains(bit) {
+                    (*permanence + inc).min(1.0)
+                } else {
+                    (*permanence - dec).max(0.0)
+                };
+            }
+        }
+
+        self.iterations += 1;
+        let period = self.config.duty_cycle_period.max(1.0);
+        let target = self.config.active_columns as f32 / self.config.column_count.max(1) as f32;
+        for (column, duty) in self.duty_cycles.iter_mut().enumerate() {
+            let active = if winners.contains(&(column as u32)) {
+                1.0
+            } else {
+                0.0
+            };
+            *duty += (active - *duty) / period;
+        }
+        if self.config.boost_strength > 0.0 {
+            for (boost, duty) in self.boost.iter_mut().zip(&self.duty_cycles) {
+                *boost = (-self.config.boost_strength * (duty - target)).exp();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn sdr(indices: &[u32], dim: u32) -> DataEidos {
+        DataEidos {
+            active_indices: indices.iter().copied().collect(),
+            dimensionality: dim,
+        }
+    }
+
+    #[test]
+    fn test_overlap_metrics() {
+        let a = sdr(&[1, 2, 3, 4], 16);
+        let b = sdr(&[3, 4, 5], 16);
+        assert_eq!(overlap(&a, &b), 2);
+        assert!((overlap_score(&a, &b) - 2.0 / 3.0).abs() < 1e-9);
+        assert!((jaccard(&a, &b) - 2.0 / 5.0).abs() < 1e-9);
+        assert_eq!(jaccard(&sdr(&[], 16), &sdr(&[], 16)), 0.0);
+    }
+
+    #[test]
+    fn test_union_and_bundle() {
+        let a = sdr(&[1, 2, 3], 16);
+        let b = sdr(&[2, 3, 4], 16);
+        let c = sdr(&[3, 9], 16);
+        let u = union(&[a.clone(), b.clone(), c.clone()]).unwrap();
+        assert_eq!(u.active_indices.len(), 5);
+        let bundled = bundle(&[a, b, c], 2).unwrap();
+        assert_eq!(bundled.active_indices, [2, 3].into_iter().collect());
+        assert!(union(&[sdr(&[1], 8), sdr(&[1], 16)]).is_err());
+        assert!(bundle(&[], 2).is_err());
+    }
+
+    #[test]
+    fn test_k_winners_take_all() {
+        let winners = k_winners_take_all(&[0.1, 0.9, f32::NAN, 0.5, 0.9], 2);
+        assert_eq!(winners.active_indices, [1, 4].into_iter().collect());
+        assert_eq!(winners.dimensionality, 5);
+    }
+
+    #[test]
+    fn test_projection_roundtrip_and_similarity() {
+        let projection = RandomProjection::new(2048, 1024, 20, 7);
+        let a = sdr(&(0..20).map(|i| i * 97).collect::<Vec<_>>(), 2048);
+        let dense = projection.to_dense(&a).unwrap();
+        assert_eq!(dense.len(), 1024);
+        let back = projection.to_sdr(&dense).unwrap();
+        assert!(overlap_score(&a, &back) > 0.8);
+
+        // SDR chồng lấp một nửa cho embedding gần hơn SDR rời rạc.
+        let half = sdr(
+            &(0..10)
+                .map(|i| i * 97)
+                .chain((0..10).map(|i| i * 53 + 1))
+                .collect::<Vec<_>>(),
+            2048,
+        );
+        let disjoint = sdr(&(0..20).map(|i| i * 89 + 3).collect::<Vec<_>>(), 2048);
+        let cos = |x: &[f32], y: &[f32]| x.iter().zip(y).map(|(a, b)| a * b).sum::<f32>();
+        let d_half = projection.to_dense(&half).unwrap();
+        let d_disjoint = projection.to_dense(&disjoint).unwrap();
+        assert!(cos(&dense, &d_half) > cos(&dense, &d_disjoint) + 0.2);
+        assert!(projection.to_dense(&sdr(&[1], 16)).is_err());
+    }
+
+    #[test]
+    fn test_spatial_pooler_is_stable_and_sparse() {
+        let mut pooler = SpatialPooler::new(SpatialPoolerConfig::default());
+        let input = b"temperature sensor reading: 21.5C";
+        let first = pooler.compute(input, true);
+        for _ in 0..10 {
+            pooler.compute(input, true);
+        }
+        let again = pooler.compute(input, false);
+        assert_eq!(first.dimensionality, 2048);
+        assert_eq!(again.active_indices.len(), 40);
+        assert!(overlap_score(&first, &again) > 0.5);
+        assert_eq!(again, pooler.compute(input, false));
+    }
+
+    #[test]
+    fn test_spatial_pooler_preserves_similarity() {
+        let mut pooler = SpatialPooler::new(SpatialPoolerConfig::default());
+        let corpus: [&[u8]; 3] = [
+            b"error: disk quota exceeded on /var",
+            b"error: disk quota exceeded on /home",
+            b"user logged in successfully from web",
+        ];
+        for _ in 0..5 {
+            for item in corpus {
+                pooler.compute(item, true);
+            }
+        }
+        let a = pooler.compute(corpus[0], false);
+        let b = pooler.compute(corpus[1], false);
+        let c = pooler.compute(corpus[2], false);
+        assert!(overlap(&a, &b) > overlap(&a, &c));
+    }
+}
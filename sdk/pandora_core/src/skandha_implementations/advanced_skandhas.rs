@@ -1,6 +1,8 @@
 use crate::interfaces::skandhas::*;
 use crate::ontology::{DataEidos, EpistemologicalFlow, Vedana};
-use std::collections::HashSet;
+use crate::sdr::{self, SpatialPooler, SpatialPoolerConfig};
+use parking_lot::Mutex;
+use std::collections::{HashSet, VecDeque};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, info};
 
@@ -296,6 +298,81 @@ impl AdvancedSannaSkandha {
     }
 }
 
+/// SannaSkandha dùng `SpatialPooler` để biến `rupa` thành SDR ổn định.
+///
+/// Cùng một sự kiện luôn cho ra cùng một `sanna`, và các sự kiện giống nhau cho ra các
+/// SDR chồng lấp. `related_eidos` là những SDR đã thấy gần đây có overlap cao nhất.
+pub struct SpatialPoolerSannaSkandha {
+    pooler: Mutex<SpatialPooler>,
+    recent: Mutex<VecDeque<DataEidos>>,
+    pub learn: bool,
+    pub memory_capacity: usize,
+    /// Overlap score tối thiểu để một SDR cũ được coi là liên quan.
+    pub min_overlap: f64,
+}
+
+impl SpatialPoolerSannaSkandha {
+    pub fn new(config: SpatialPoolerConfig) -> Self {
+        Self {
+            pooler: Mutex::new(SpatialPooler::new(config)),
+            recent: Mutex::new(VecDeque::new()),
+            learn: true,
+            memory_capacity: 256,
+            min_overlap: 0.3,
+        }
+    }
+}
+
+impl Default for SpatialPoolerSannaSkandha {
+    fn default() -> Self {
+        Self::new(SpatialPoolerConfig::default())
+    }
+}
+
+impl Skandha for SpatialPoolerSannaSkandha {
+    fn name(&self) -> &'static str {
+        "Spatial Pooler Sanna (Perception)"
+    }
+}
+
+impl SannaSkandha for SpatialPoolerSannaSkandha {
+    fn perceive(&self, flow: &mut EpistemologicalFlow) {
+        let rupa = flow.rupa.as_ref().map(|r| r.as_ref()).unwrap_or(&[]);
+        let eidos = self.pooler.lock().compute(rupa, self.learn);
+
+        let mut recent = self.recent.lock();
+        let mut related: Vec<(f64, &DataEidos)> = recent
+            .iter()
+            .filter(|e| **e != eidos)
+            .map(|e| (sdr::overlap_score(&eidos, e), e))
+            .filter(|(score, _)| *score >= self.min_overlap)
+            .collect();
+        related.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        let related: smallvec::SmallVec<[DataEidos; 4]> = related
+            .into_iter()
+            .take(4)
+            .map(|(_, e)| e.clone())
+            .collect();
+
+        if !recent.contains(&eidos) {
+            recent.push_back(eidos.clone());
+            while recent.len() > self.memory_capacity {
+                recent.pop_front();
+            }
+        }
+        drop(recent);
+
+        debug!(
+            "[{}] SDR {} bit, {} patterns liên quan",
+            self.name(),
+            eidos.active_indices.len(),
+            related.len()
+        );
+        flow.sanna = Some(eidos);
+        flow.related_eidos = Some(related);
+    }
+}
+
 /// Advanced SankharaSkandha với decision tree phức tạp
 pub struct AdvancedSankharaSkandha {
     pub decision_threshold: f64,
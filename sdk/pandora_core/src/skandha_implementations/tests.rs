@@ -66,6 +66,29 @@ mod tests {
             .is_empty());
     }
 
+    #[tokio::test]
+    async fn test_spatial_pooler_sanna_skandha() {
+        let skandha = SpatialPoolerSannaSkandha::default();
+        let perceive = |content: &'static [u8]| {
+            let mut flow = EpistemologicalFlow {
+                rupa: Some(bytes::Bytes::from_static(content)),
+                ..Default::default()
+            };
+            skandha.perceive(&mut flow);
+            flow
+        };
+
+        let first = perceive(b"error: disk quota exceeded on /var");
+        let sanna = first.sanna.expect("expected sanna in test");
+        assert_eq!(sanna.dimensionality, 2048);
+        assert_eq!(sanna.active_indices.len(), 40);
+
+        let similar = perceive(b"error: disk quota exceeded on /home");
+        let related = similar.related_eidos.expect("expected related in test");
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0], sanna);
+    }
+
     #[tokio::test]
     async fn test_advanced_sankhara_skandha() {
         let skandha = AdvancedSankharaSkandha::new(0.4, true);
@@ -89,22 +112,22 @@ mod tests {
     #[tokio::test]
     async fn test_advanced_vinnana_skandha() {
         let skandha = AdvancedVinnanaSkandha::new(0.5, true);
-        let flow = EpistemologicalFlow {
-            rupa: Some(bytes::Bytes::from_static(b"important event")),
-            vedana: Some(crate::ontology::Vedana::Pleasant { karma_weight: 1.5 }),
-            sanna: Some(crate::ontology::DataEidos {
+        let flow = EpistemologicalFlow::new(
+            Some(bytes::Bytes::from_static(b"important event")),
+            Some(crate::ontology::Vedana::Pleasant { karma_weight: 1.5 }),
+            Some(crate::ontology::DataEidos {
                 active_indices: [1u32, 2u32, 3u32, 4u32, 5u32].iter().cloned().collect(),
                 dimensionality: 2048,
             }),
-            related_eidos: Some(
+            Some(
                 vec![crate::ontology::DataEidos {
                     active_indices: [6u32, 7u32].iter().cloned().collect(),
                     dimensionality: 2048,
                 }]
                 .into(),
             ),
-            sankhara: Some(std::sync::Arc::<str>::from("TEST_INTENT")),
-        };
+            Some(std::sync::Arc::<str>::from("TEST_INTENT")),
+        );
 
         let result = skandha.synthesize(&flow);
 
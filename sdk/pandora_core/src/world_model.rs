@@ -1,4 +1,5 @@
 use crate::ontology::EpistemologicalFlow;
+use pandora_error::PandoraError;
 
 /// Trait đại diện cho một mô hình thế giới (World Model) mà hệ thống đang sử dụng.
 /// Bất kỳ mô hình nào, từ một mạng nơ-ron đơn giản đến CWM phức tạp,
@@ -10,6 +11,13 @@ pub trait WorldModel {
 
     /// Tính toán sai số dự đoán cho một quan sát.
     fn get_prediction_error(&self, flow: &EpistemologicalFlow) -> f64;
+
+    /// Dự đoán trạng thái kế tiếp khi thực hiện ý chỉ `flow.sankhara`, ghi đè lên `flow`.
+    /// Mặc định là mô hình đồng nhất (trạng thái không đổi), dành cho các mô hình
+    /// không có động lực học.
+    fn predict_next_state(&self, _flow: &mut EpistemologicalFlow) -> Result<(), PandoraError> {
+        Ok(())
+    }
 }
 
 /// Phần thưởng nội tại kép, cân bằng giữa việc "biết nhiều hơn" và "hiểu sâu hơn".
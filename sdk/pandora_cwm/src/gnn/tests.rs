@@ -160,6 +160,32 @@ mod gnn_pipeline_tests {
         assert_eq!(embedding1.len(), config.hidden_dims);
     }
 
+    #[test]
+    fn test_eidos_embedding_roundtrip() {
+        let config = GnnConfig::new(32, 256, 2);
+        let cwm = InterdependentCausalModel::new(config.clone()).unwrap();
+
+        let mut eidos = DataEidos {
+            active_indices: Default::default(),
+            dimensionality: 1024,
+        };
+        for i in [3u32, 97, 400, 812] {
+            eidos.active_indices.insert(i);
+        }
+
+        let embedding = cwm.embed_eidos(&eidos).unwrap();
+        assert_eq!(embedding.len(), config.hidden_dims);
+
+        let decoded = cwm.eidos_from_embedding(&embedding, 1024, 4).unwrap();
+        assert_eq!(decoded.active_indices, eidos.active_indices);
+
+        // Wrong embedding length is rejected as invalid input
+        assert!(matches!(
+            cwm.eidos_from_embedding(&[0.0; 3], 1024, 4),
+            Err(pandora_error::PandoraError::InvalidSkillInput { .. })
+        ));
+    }
+
     #[test]
     fn test_flow_enrichment_preserves_original_data() {
         let config = GnnConfig::new(32, 64, 2);
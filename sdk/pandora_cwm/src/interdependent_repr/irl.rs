@@ -3,13 +3,28 @@
 /// IRL là kiến trúc học biểu diễn nhận biết được bản chất "tương tức, phụ thuộc lẫn nhau"
 /// của vạn vật. Nó học cách biểu diễn các thực thể không phải như những đối tượng
 /// độc lập, mà như những phần tử trong một mạng lưới quan hệ phức tạp.
-use fnv::FnvHashMap;
-use tracing::info;
+///
+/// Các thực thể được đánh chỉ số liên tục và quan hệ được lưu dưới dạng ma trận thưa
+/// CSR (cùng ma trận chuyển vị), nên mọi phép lan truyền chỉ tốn O(số cạnh). Biểu diễn
+/// của mỗi thực thể là điểm bất động của phép lặp có hệ số tắt dần (kiểu PageRank):
+///
+/// `x_i = (1 - d)·p_i + d·Σ_j P_ij·a_j·x_j`
+///
+/// trong đó `p_i` là context ban đầu, `P` là ma trận quan hệ chuẩn hóa theo hàng và
+/// `a_j` là mức kích hoạt theo trạng thái của thực thể `j`. Với `d < 1` phép lặp là
+/// ánh xạ co nên luôn hội tụ.
+use fnv::{FnvHashMap, FnvHashSet};
+use pandora_error::PandoraError;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use tracing::{debug, info, trace};
 
 /// Biểu diễn một thực thể trong mạng lưới duyên khởi.
 /// Mỗi thực thể được định nghĩa bởi:
 /// - Vị trí của nó trong mạng lưới (context)
-/// - Các quan hệ với các thực thể khác (dependencies)
+/// - Các quan hệ với các thực thể khác (dependencies): khi thêm vào mạng lưới, mỗi
+///   phụ thuộc trở thành quan hệ `id -> phụ thuộc` với trọng số mặc định
+///   [`DEFAULT_DEPENDENCY_WEIGHT`] nếu quan hệ đó chưa được thiết lập
 /// - Trạng thái hiện tại (state)
 #[derive(Debug, Clone)]
 pub struct InterdependentEntity {
@@ -19,6 +34,16 @@ pub struct InterdependentEntity {
     pub state: EntityState,
 }
 
+fn dimension_error(message: impl Into<String>) -> PandoraError {
+    PandoraError::InvalidSkillInput {
+        skill_name: "cwm_irl".into(),
+        message: message.into(),
+    }
+}
+
+/// Trọng số của quan hệ được tạo từ `InterdependentEntity::dependencies`.
+pub const DEFAULT_DEPENDENCY_WEIGHT: f64 = 1.0;
+
 #[derive(Debug, Clone)]
 pub enum EntityState {
     Active,
@@ -26,153 +51,568 @@ pub enum EntityState {
     Transitioning,
 }
 
+impl EntityState {
+    /// Mức độ một thực thể ở trạng thái này truyền ảnh hưởng sang các thực thể khác.
+    pub fn activation(&self) -> f64 {
+        match self {
+            EntityState::Active => 1.0,
+            EntityState::Transitioning => 0.5,
+            EntityState::Dormant => 0.0,
+        }
+    }
+}
+
+/// Tham số cho phép lặp lan truyền đến điểm bất động.
+#[derive(Debug, Clone)]
+pub struct PropagationConfig {
+    /// Hệ số tắt dần `d` trong [0, 1): tỷ trọng của ảnh hưởng từ láng giềng.
+    pub damping: f64,
+    /// Dừng khi thay đổi lớn nhất của một thành phần context nhỏ hơn ngưỡng này.
+    pub tolerance: f64,
+    /// Số vòng lặp tối đa (với lan truyền cục bộ: số lần cập nhật tối đa trên mỗi thực thể).
+    pub max_iterations: usize,
+}
+
+impl Default for PropagationConfig {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            tolerance: 1e-6,
+            max_iterations: 100,
+        }
+    }
+}
+
+/// Kết quả của một lần lan truyền.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropagationReport {
+    /// Số vòng lặp toàn cục (hoặc số lần cập nhật thực thể với lan truyền cục bộ).
+    pub iterations: usize,
+    /// Thay đổi lớn nhất của một thành phần context: ở vòng cuối với lan truyền toàn
+    /// cục, trên toàn bộ lượt cập nhật với lan truyền cục bộ.
+    pub residual: f64,
+    pub converged: bool,
+}
+
+/// Ma trận thưa dạng CSR (Compressed Sparse Row); cột trong mỗi hàng được sắp xếp.
+#[derive(Debug, Clone, Default)]
+pub struct SparseMatrix {
+    row_offsets: Vec<usize>,
+    columns: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl SparseMatrix {
+    /// Xây dựng ma trận `rows` hàng từ danh sách bộ ba `(hàng, cột, giá trị)`.
+    pub fn from_triplets(
+        rows: usize,
+        triplets: impl IntoIterator<Item = (usize, usize, f64)>,
+    ) -> Self {
+        let mut entries: Vec<(usize, usize, f64)> = triplets.into_iter().collect();
+        entries.sort_by_key(|&(r, c, _)| (r, c));
+        let mut row_offsets = vec![0; rows + 1];
+        for &(r, _, _) in &entries {
+            row_offsets[r + 1] += 1;
+        }
+        for r in 0..rows {
+            row_offsets[r + 1] += row_offsets[r];
+        }
+        Self {
+            row_offsets,
+            columns: entries.iter().map(|e| e.1).collect(),
+            values: entries.iter().map(|e| e.2).collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.row_offsets.len().saturating_sub(1)
+    }
+
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Các phần tử khác 0 của một hàng dưới dạng `(cột, giá trị)`.
+    pub fn row(&self, r: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.row_offsets[r]..self.row_offsets[r + 1];
+        self.columns[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.values[range].iter().copied())
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> Option<f64> {
+        let range = self.row_offsets[r]..self.row_offsets[r + 1];
+        self.columns[range.clone()]
+            .binary_search(&c)
+            .ok()
+            .map(|k| self.values[range.start + k])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let triplets = (0..self.rows()).flat_map(|r| self.row(r).map(move |(c, v)| (c, r, v)));
+        Self::from_triplets(self.rows(), triplets.collect::<Vec<_>>())
+    }
+}
+
+/// Ma trận quan hệ đã biên dịch cùng chuyển vị và tổng trọng số theo hàng.
+#[derive(Debug, Clone)]
+struct CompiledRelations {
+    forward: SparseMatrix,
+    reverse: SparseMatrix,
+    row_weight: Vec<f64>,
+}
+
+impl CompiledRelations {
+    fn build(n: usize, relationships: &FnvHashMap<(usize, usize), f64>) -> Self {
+        let forward =
+            SparseMatrix::from_triplets(n, relationships.iter().map(|(&(f, t), &w)| (f, t, w)));
+        let reverse = forward.transpose();
+        let row_weight = (0..n)
+            .map(|r| forward.row(r).map(|(_, w)| w.abs()).sum())
+            .collect();
+        debug!(
+            "IRL: Biên dịch {} quan hệ giữa {} thực thể",
+            forward.nnz(),
+            n
+        );
+        Self {
+            forward,
+            reverse,
+            row_weight,
+        }
+    }
+}
+
 /// Mạng lưới duyên khởi chứa tất cả các thực thể và quan hệ giữa chúng.
 pub struct InterdependentNetwork {
-    entities: FnvHashMap<String, InterdependentEntity>,
-    relationships: FnvHashMap<(String, String), f64>, // Quan hệ với trọng số
+    index: FnvHashMap<String, usize>,
+    ids: Vec<String>,
+    entities: Vec<Option<InterdependentEntity>>,
+    /// Context ban đầu của mỗi thực thể, là điểm neo `p_i` của phép lặp.
+    priors: Vec<Vec<f64>>,
+    relationships: FnvHashMap<(usize, usize), f64>, // Quan hệ với trọng số
+    /// Ma trận CSR được biên dịch lười, xóa mỗi khi quan hệ thay đổi.
+    compiled: OnceLock<CompiledRelations>,
+    pending: Vec<usize>,
+    config: PropagationConfig,
 }
 
 impl InterdependentNetwork {
     pub fn new() -> Self {
+        Self::with_config(PropagationConfig::default())
+    }
+
+    pub fn with_config(config: PropagationConfig) -> Self {
         info!("IRL: Khởi tạo Mạng lưới Duyên khởi");
         Self {
-            entities: FnvHashMap::default(),
+            index: FnvHashMap::default(),
+            ids: Vec::new(),
+            entities: Vec::new(),
+            priors: Vec::new(),
             relationships: FnvHashMap::default(),
+            compiled: OnceLock::new(),
+            pending: Vec::new(),
+            config,
         }
     }
 
-    /// Thêm một thực thể vào mạng lưới.
+    pub fn config(&self) -> &PropagationConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.iter().filter(|e| e.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn relationship_count(&self) -> usize {
+        self.relationships.len()
+    }
+
+    pub fn entity(&self, id: &str) -> Option<&InterdependentEntity> {
+        self.index.get(id).and_then(|&i| self.entities[i].as_ref())
+    }
+
+    fn intern(&mut self, id: &str) -> usize {
+        if let Some(&i) = self.index.get(id) {
+            return i;
+        }
+        let i = self.ids.len();
+        self.index.insert(id.to_string(), i);
+        self.ids.push(id.to_string());
+        self.entities.push(None);
+        self.priors.push(Vec::new());
+        self.compiled = OnceLock::new();
+        i
+    }
+
+    /// Thêm một thực thể vào mạng lưới. Mỗi phụ thuộc của thực thể được ghi thành
+    /// quan hệ tới phụ thuộc đó (trọng số [`DEFAULT_DEPENDENCY_WEIGHT`]); quan hệ đã có
+    /// giữ nguyên trọng số, và `add_relationship` có thể ghi đè về sau.
     pub fn add_entity(&mut self, entity: InterdependentEntity) {
-        info!("IRL: Thêm thực thể '{}' vào mạng lưới", entity.id);
-        self.entities.insert(entity.id.clone(), entity);
+        trace!("IRL: Thêm thực thể '{}' vào mạng lưới", entity.id);
+        let i = self.intern(&entity.id);
+        for dep in &entity.dependencies {
+            let d = self.intern(dep);
+            if let std::collections::hash_map::Entry::Vacant(slot) =
+                self.relationships.entry((i, d))
+            {
+                slot.insert(DEFAULT_DEPENDENCY_WEIGHT);
+                self.compiled = OnceLock::new();
+            }
+        }
+        self.priors[i] = entity.context.clone();
+        self.entities[i] = Some(entity);
     }
 
     /// Thiết lập quan hệ giữa hai thực thể.
     /// Trọng số thể hiện mức độ phụ thuộc lẫn nhau.
     pub fn add_relationship(&mut self, from: &str, to: &str, weight: f64) {
-        info!(
+        trace!(
             "IRL: Thiết lập quan hệ '{}' -> '{}' (trọng số: {:.3})",
-            from, to, weight
+            from,
+            to,
+            weight
         );
-        self.relationships
-            .insert((from.to_string(), to.to_string()), weight);
-    }
-
-    /// Học biểu diễn duyên khởi cho một thực thể.
-    /// Quá trình này cập nhật context của thực thể dựa trên:
-    /// 1. Trạng thái của các thực thể phụ thuộc
-    /// 2. Cấu trúc toàn cục của mạng lưới
-    /// 3. Các quan hệ có trọng số cao
-    pub fn learn_interdependent_representation(&mut self, entity_id: &str) {
-        if let Some(entity) = self.entities.get(entity_id) {
-            info!("IRL: Học biểu diễn duyên khởi cho '{}'", entity_id);
-
-            // Cập nhật context dựa trên các thực thể phụ thuộc
-            let mut new_context = entity.context.clone();
-
-            for dep_id in &entity.dependencies {
-                if let Some(dep_entity) = self.entities.get(dep_id) {
-                    // Tính toán ảnh hưởng từ thực thể phụ thuộc
-                    let influence = self.calculate_influence(entity_id, dep_id);
-
-                    // Cập nhật context dựa trên ảnh hưởng
-                    for (i, val) in dep_entity.context.iter().enumerate() {
-                        if i < new_context.len() {
-                            new_context[i] += val * influence;
-                        }
-                    }
-                }
-            }
+        let (f, t) = (self.intern(from), self.intern(to));
+        self.relationships.insert((f, t), weight);
+        self.compiled = OnceLock::new();
+    }
+
+    /// Biên dịch (nếu cần) các quan hệ thành ma trận CSR.
+    fn compiled(&self) -> &CompiledRelations {
+        self.compiled
+            .get_or_init(|| CompiledRelations::build(self.ids.len(), &self.relationships))
+    }
 
-            // Cập nhật context sau khi tính toán xong
-            if let Some(entity) = self.entities.get_mut(entity_id) {
-                entity.context = new_context;
-                info!(
-                    "IRL: Đã cập nhật context cho '{}' dựa trên {} thực thể phụ thuộc",
-                    entity_id,
-                    entity.dependencies.len()
-                );
+    fn activations(&self) -> Vec<f64> {
+        self.entities
+            .iter()
+            .map(|e| e.as_ref().map_or(0.0, |e| e.state.activation()))
+            .collect()
+    }
+
+    /// Context mới của thực thể `i` theo một bước của phép lặp; `context_of(j)` trả về
+    /// context hiện tại của láng giềng `j`. Láng giềng có ảnh hưởng phải có context cùng
+    /// số chiều với thực thể `i`.
+    fn step_context<'a>(
+        relations: &CompiledRelations,
+        ids: &[String],
+        prior: &[f64],
+        activations: &[f64],
+        i: usize,
+        damping: f64,
+        context_of: impl Fn(usize) -> &'a [f64],
+    ) -> Result<Vec<f64>, PandoraError> {
+        let total = relations.row_weight[i];
+        if total == 0.0 {
+            return Ok(prior.to_vec());
+        }
+        let mut next: Vec<f64> = prior.iter().map(|p| (1.0 - damping) * p).collect();
+        for (j, w) in relations.forward.row(i) {
+            let coeff = damping * w / total * activations[j];
+            if coeff == 0.0 {
+                continue;
+            }
+            let context = context_of(j);
+            if context.len() != next.len() {
+                return Err(dimension_error(format!(
+                    "Context của '{}' có {} chiều, khác {} chiều của '{}'",
+                    ids[j],
+                    context.len(),
+                    next.len(),
+                    ids[i]
+                )));
+            }
+            for (x, v) in next.iter_mut().zip(context) {
+                *x += coeff * v;
             }
         }
+        Ok(next)
     }
 
-    /// Tính toán mức độ ảnh hưởng giữa hai thực thể.
-    fn calculate_influence(&self, from: &str, to: &str) -> f64 {
-        // Lấy trọng số quan hệ trực tiếp
-        let direct_weight = self
-            .relationships
-            .get(&(from.to_string(), to.to_string()))
-            .copied()
-            .unwrap_or(0.0);
-
-        // Tính toán ảnh hưởng gián tiếp thông qua các thực thể trung gian
-        let mut indirect_influence = 0.0;
-        for intermediate in self.entities.keys() {
-            if let Some(weight1) = self
-                .relationships
-                .get(&(from.to_string(), intermediate.clone()))
-            {
-                if let Some(weight2) = self
-                    .relationships
-                    .get(&(intermediate.clone(), to.to_string()))
-                {
-                    indirect_influence += weight1 * weight2 * 0.5; // Hệ số suy giảm
-                }
+    fn context_of(&self, j: usize) -> &[f64] {
+        self.entities[j]
+            .as_ref()
+            .map_or(&[][..], |e| e.context.as_slice())
+    }
+
+    fn max_change(a: &[f64], b: &[f64]) -> f64 {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| (x - y).abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Học biểu diễn duyên khởi cho một thực thể bằng một bước lan truyền cục bộ.
+    /// Context mới kết hợp context ban đầu với context của các thực thể mà nó
+    /// có quan hệ tới, có trọng số theo quan hệ và trạng thái của chúng.
+    ///
+    /// Trả lỗi `InvalidSkillInput` nếu context của một láng giềng khác số chiều.
+    pub fn learn_interdependent_representation(
+        &mut self,
+        entity_id: &str,
+    ) -> Result<(), PandoraError> {
+        let Some(&i) = self.index.get(entity_id) else {
+            return Ok(());
+        };
+        if self.entities[i].is_none() {
+            return Ok(());
+        }
+        info!("IRL: Học biểu diễn duyên khởi cho '{}'", entity_id);
+        let activations = self.activations();
+        let next = Self::step_context(
+            self.compiled(),
+            &self.ids,
+            &self.priors[i],
+            &activations,
+            i,
+            self.config.damping,
+            |j| self.context_of(j),
+        )?;
+        if let Some(entity) = self.entities[i].as_mut() {
+            entity.context = next;
+        }
+        Ok(())
+    }
+
+    /// Lặp toàn cục (Jacobi) cho đến khi mọi context hội tụ về điểm bất động.
+    /// Khi context của hai thực thể có quan hệ khác số chiều, trả lỗi và giữ nguyên
+    /// mọi context.
+    pub fn propagate_to_fixed_point(&mut self) -> Result<PropagationReport, PandoraError> {
+        let config = self.config.clone();
+        let activations = self.activations();
+        let relations = self.compiled();
+
+        let mut current: Vec<Vec<f64>> = self
+            .entities
+            .iter()
+            .map(|e| e.as_ref().map_or_else(Vec::new, |e| e.context.clone()))
+            .collect();
+        let mut report = PropagationReport {
+            iterations: 0,
+            residual: 0.0,
+            converged: false,
+        };
+        while report.iterations < config.max_iterations {
+            let mut residual = 0.0f64;
+            let next: Vec<Vec<f64>> = (0..current.len())
+                .map(|i| {
+                    if self.entities[i].is_none() {
+                        return Ok(Vec::new());
+                    }
+                    let x = Self::step_context(
+                        relations,
+                        &self.ids,
+                        &self.priors[i],
+                        &activations,
+                        i,
+                        config.damping,
+                        |j| current[j].as_slice(),
+                    )?;
+                    residual = residual.max(Self::max_change(&x, &current[i]));
+                    Ok(x)
+                })
+                .collect::<Result<_, PandoraError>>()?;
+            current = next;
+            report.iterations += 1;
+            report.residual = residual;
+            if residual < config.tolerance {
+                report.converged = true;
+                break;
+            }
+        }
+
+        for (entity, context) in self.entities.iter_mut().zip(current) {
+            if let Some(entity) = entity {
+                entity.context = context;
             }
         }
+        self.pending.clear();
+        debug!(
+            "IRL: Lan truyền toàn cục sau {} vòng (residual {:.2e}, hội tụ: {})",
+            report.iterations, report.residual, report.converged
+        );
+        Ok(report)
+    }
 
-        direct_weight + indirect_influence
+    /// Tính toán mức độ ảnh hưởng giữa hai thực thể: trọng số trực tiếp cộng
+    /// ảnh hưởng gián tiếp qua một thực thể trung gian (hệ số suy giảm 0.5).
+    fn calculate_influence(&self, from: usize, to: usize) -> f64 {
+        let relations = self.compiled();
+        let direct = relations.forward.get(from, to).unwrap_or(0.0);
+        let indirect: f64 = relations
+            .forward
+            .row(from)
+            .filter_map(|(k, w1)| relations.forward.get(k, to).map(|w2| w1 * w2 * 0.5))
+            .sum();
+        direct + indirect
     }
 
     /// Tìm các thực thể có ảnh hưởng mạnh nhất đến một thực thể cụ thể.
+    /// Chỉ duyệt các láng giềng trong tối đa hai bước qua ma trận chuyển vị.
     pub fn find_key_influencers(&self, entity_id: &str) -> Vec<(String, f64)> {
-        let mut influencers = Vec::new();
-
-        for other_id in self.entities.keys() {
-            if other_id != entity_id {
-                let influence = self.calculate_influence(other_id, entity_id);
-                if influence > 0.1 {
-                    // Ngưỡng ảnh hưởng tối thiểu
-                    influencers.push((other_id.clone().to_string(), influence));
-                }
+        let Some(&target) = self.index.get(entity_id) else {
+            return Vec::new();
+        };
+        let relations = self.compiled();
+        let mut scores: FnvHashMap<usize, f64> = FnvHashMap::default();
+        for (k, w2) in relations.reverse.row(target) {
+            *scores.entry(k).or_default() += w2;
+            for (j, w1) in relations.reverse.row(k) {
+                *scores.entry(j).or_default() += w1 * w2 * 0.5; // Hệ số suy giảm
             }
         }
 
+        let mut influencers: Vec<(String, f64)> = scores
+            .into_iter()
+            .filter(|&(j, influence)| {
+                // Ngưỡng ảnh hưởng tối thiểu
+                j != target && self.entities[j].is_some() && influence > 0.1
+            })
+            .map(|(j, influence)| (self.ids[j].clone(), influence))
+            .collect();
+
         // Sắp xếp theo mức độ ảnh hưởng giảm dần
-        influencers.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        influencers.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
         influencers
     }
 
-    /// Cập nhật trạng thái của một thực thể và lan truyền ảnh hưởng.
-    pub fn update_entity_state(&mut self, entity_id: &str, new_state: EntityState) {
-        if let Some(entity) = self.entities.get_mut(entity_id) {
-            info!(
+    /// Ảnh hưởng (trực tiếp + gián tiếp) của `from` lên `to`.
+    pub fn influence(&self, from: &str, to: &str) -> f64 {
+        match (self.index.get(from), self.index.get(to)) {
+            (Some(&f), Some(&t)) => self.calculate_influence(f, t),
+            _ => 0.0,
+        }
+    }
+
+    /// Ghi nhận thay đổi trạng thái mà chưa lan truyền; dùng `flush_updates` để
+    /// lan truyền cả lô một lần.
+    pub fn queue_state_update(&mut self, entity_id: &str, new_state: EntityState) {
+        let Some(&i) = self.index.get(entity_id) else {
+            return;
+        };
+        if let Some(entity) = self.entities[i].as_mut() {
+            debug!(
                 "IRL: Cập nhật trạng thái '{}' -> {:?}",
                 entity_id, new_state
             );
             entity.state = new_state;
+            self.pending.push(i);
+        }
+    }
+
+    /// Cập nhật trạng thái của một thực thể và lan truyền ảnh hưởng.
+    pub fn update_entity_state(
+        &mut self,
+        entity_id: &str,
+        new_state: EntityState,
+    ) -> Result<PropagationReport, PandoraError> {
+        self.queue_state_update(entity_id, new_state);
+        self.flush_updates()
+    }
 
-            // Lan truyền ảnh hưởng đến các thực thể liên quan
-            self.propagate_influence(entity_id);
+    /// Cập nhật trạng thái của nhiều thực thể rồi lan truyền một lần.
+    pub fn update_entity_states<'a>(
+        &mut self,
+        updates: impl IntoIterator<Item = (&'a str, EntityState)>,
+    ) -> Result<PropagationReport, PandoraError> {
+        for (id, state) in updates {
+            self.queue_state_update(id, state);
         }
+        self.flush_updates()
     }
 
-    /// Lan truyền ảnh hưởng từ một thực thể đến các thực thể khác.
-    fn propagate_influence(&mut self, source_id: &str) {
-        let influencers = self.find_key_influencers(source_id);
+    /// Lan truyền cục bộ (Gauss–Seidel theo hàng đợi) từ các thực thể đang chờ:
+    /// chỉ những thực thể phụ thuộc vào một thực thể vừa thay đổi mới được tính lại,
+    /// và chỉ tiếp tục lan ra khi context của chúng thay đổi quá `tolerance`.
+    ///
+    /// Gặp context khác số chiều thì dừng và trả lỗi; các cập nhật đã lan truyền
+    /// trước đó được giữ lại.
+    pub fn flush_updates(&mut self) -> Result<PropagationReport, PandoraError> {
+        let mut report = PropagationReport {
+            iterations: 0,
+            residual: 0.0,
+            converged: true,
+        };
+        if self.pending.is_empty() {
+            return Ok(report);
+        }
+        let config = self.config.clone();
+        let activations = self.activations();
+        let Self {
+            ids,
+            entities,
+            priors,
+            relationships,
+            compiled,
+            pending,
+            ..
+        } = self;
+        let relations = compiled.get_or_init(|| CompiledRelations::build(ids.len(), relationships));
 
-        for (influenced_id, influence_strength) in influencers {
-            if influence_strength > 0.5 {
-                // Chỉ lan truyền đến các thực thể có ảnh hưởng mạnh
-                info!(
-                    "IRL: Lan truyền ảnh hưởng từ '{}' đến '{}' (cường độ: {:.3})",
-                    source_id, influenced_id, influence_strength
-                );
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        let mut queued: FnvHashSet<usize> = FnvHashSet::default();
+        let enqueue_dependents =
+            |source: usize, queue: &mut VecDeque<usize>, queued: &mut FnvHashSet<usize>| {
+                for (dependent, _) in relations.reverse.row(source) {
+                    if queued.insert(dependent) {
+                        queue.push_back(dependent);
+                    }
+                }
+            };
+        for source in std::mem::take(pending) {
+            enqueue_dependents(source, &mut queue, &mut queued);
+        }
 
-                // Cập nhật biểu diễn của thực thể bị ảnh hưởng
-                self.learn_interdependent_representation(&influenced_id);
+        let budget = config.max_iterations.saturating_mul(ids.len().max(1));
+        let mut max_residual = 0.0f64;
+        while let Some(i) = queue.pop_front() {
+            queued.remove(&i);
+            if report.iterations >= budget {
+                report.converged = false;
+                break;
+            }
+            let Some(entity) = entities[i].as_ref() else {
+                continue;
+            };
+            let next = Self::step_context(
+                relations,
+                ids,
+                &priors[i],
+                &activations,
+                i,
+                config.damping,
+                |j| {
+                    entities[j]
+                        .as_ref()
+                        .map_or(&[][..], |e| e.context.as_slice())
+                },
+            )?;
+            let change = Self::max_change(&next, &entity.context);
+            report.iterations += 1;
+            max_residual = max_residual.max(change);
+            if let Some(entity) = entities[i].as_mut() {
+                entity.context = next;
+            }
+            if change >= config.tolerance {
+                enqueue_dependents(i, &mut queue, &mut queued);
             }
         }
+        report.residual = max_residual;
+        debug!(
+            "IRL: Lan truyền cục bộ {} lần cập nhật (hội tụ: {})",
+            report.iterations, report.converged
+        );
+        Ok(report)
     }
 }
 
@@ -181,3 +621,232 @@ impl Default for InterdependentNetwork {
         Self::new()
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn entity(id: &str, context: Vec<f64>, state: EntityState) -> InterdependentEntity {
+        InterdependentEntity {
+            id: id.to_string(),
+            context,
+            dependencies: Vec::new(),
+            state,
+        }
+    }
+
+    fn triangle() -> InterdependentNetwork {
+        let mut network = InterdependentNetwork::new();
+        network.add_entity(entity("A", vec![1.0, 0.0, 0.0], EntityState::Active));
+        network.add_entity(entity("B", vec![0.0, 1.0, 0.0], EntityState::Active));
+        network.add_entity(entity("C", vec![0.0, 0.0, 1.0], EntityState::Active));
+        network.add_relationship("A", "B", 0.8);
+        network.add_relationship("A", "C", 0.6);
+        network.add_relationship("B", "C", 0.9);
+        network
+    }
+
+    #[test]
+    fn test_sparse_matrix_transpose() {
+        let m = SparseMatrix::from_triplets(3, vec![(0, 2, 1.5), (0, 1, 2.0), (2, 0, -1.0)]);
+        assert_eq!(m.nnz(), 3);
+        assert_eq!(m.row(0).collect::<Vec<_>>(), vec![(1, 2.0), (2, 1.5)]);
+        let t = m.transpose();
+        assert_eq!(t.get(2, 0), Some(1.5));
+        assert_eq!(t.get(0, 2), Some(-1.0));
+        assert_eq!(t.get(1, 1), None);
+    }
+
+    #[test]
+    fn test_influence_and_influencers() {
+        let network = triangle();
+        // Trực tiếp 0.6 cộng gián tiếp 0.8 * 0.9 * 0.5 qua B.
+        assert!((network.influence("A", "C") - 0.96).abs() < 1e-12);
+        let influencers = network.find_key_influencers("C");
+        assert_eq!(influencers[0].0, "A");
+        assert_eq!(influencers[1].0, "B");
+        assert!(network.find_key_influencers("A").is_empty());
+    }
+
+    #[test]
+    fn test_fixed_point_matches_closed_form() {
+        let mut network = triangle();
+        let report = network.propagate_to_fixed_point().unwrap();
+        assert!(report.converged);
+
+        // C không có quan hệ đi ra nên giữ nguyên; B = 0.15·p_B + 0.85·C.
+        let c = network.entity("C").unwrap().context.clone();
+        assert_eq!(c, vec![0.0, 0.0, 1.0]);
+        let b = &network.entity("B").unwrap().context;
+        assert!((b[1] - 0.15).abs() < 1e-9 && (b[2] - 0.85).abs() < 1e-9);
+        let a = &network.entity("A").unwrap().context;
+        let expected_c = 0.15 * 0.0 + 0.85 * (0.8 / 1.4 * 0.85 + 0.6 / 1.4 * 1.0);
+        assert!((a[2] - expected_c).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_batched_update_reaches_global_fixed_point() {
+        let mut local = triangle();
+        local.propagate_to_fixed_point().unwrap();
+        let report = local
+            .update_entity_states([
+                ("C", EntityState::Dormant),
+                ("B", EntityState::Transitioning),
+            ])
+            .unwrap();
+        assert!(report.converged);
+        assert!(report.iterations > 0);
+
+        let mut global = triangle();
+        global.queue_state_update("C", EntityState::Dormant);
+        global.queue_state_update("B", EntityState::Transitioning);
+        global.propagate_to_fixed_point().unwrap();
+
+        for id in ["A", "B", "C"] {
+            let l = &local.entity(id).unwrap().context;
+            let g = &global.entity(id).unwrap().context;
+            assert!(
+                InterdependentNetwork::max_change(l, g) < 1e-5,
+                "{id}: {l:?} vs {g:?}"
+            );
+        }
+        // B giờ chỉ còn context ban đầu vì C không còn truyền ảnh hưởng.
+        assert!((local.entity("B").unwrap().context[1] - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dependencies_become_relationships() {
+        let mut network = InterdependentNetwork::new();
+        let mut a = entity("A", vec![1.0, 0.0], EntityState::Active);
+        a.dependencies = vec!["B".to_string(), "C".to_string()];
+        network.add_relationship("A", "C", 0.25);
+        network.add_entity(a);
+        network.add_entity(entity("B", vec![0.0, 1.0], EntityState::Active));
+        network.add_entity(entity("C", vec![0.0, 1.0], EntityState::Active));
+
+        assert_eq!(network.relationship_count(), 2);
+        assert_eq!(network.influence("A", "B"), DEFAULT_DEPENDENCY_WEIGHT);
+        // Quan hệ đã thiết lập trước giữ nguyên trọng số.
+        assert_eq!(network.influence("A", "C"), 0.25);
+
+        network.learn_interdependent_representation("A").unwrap();
+        assert!(network.entity("A").unwrap().context[1] > 0.0);
+    }
+
+    #[test]
+    fn test_flush_reports_max_residual() {
+        let mut network = triangle();
+        network.propagate_to_fixed_point().unwrap();
+        let before: Vec<Vec<f64>> = ["A", "B"]
+            .iter()
+            .map(|id| network.entity(id).unwrap().context.clone())
+            .collect();
+        let report = network
+            .update_entity_states([("C", EntityState::Dormant)])
+            .unwrap();
+        let largest = ["A", "B"]
+            .iter()
+            .zip(&before)
+            .map(|(id, b)| {
+                InterdependentNetwork::max_change(&network.entity(id).unwrap().context, b)
+            })
+            .fold(0.0, f64::max);
+        // Lượt cập nhật cuối gần như không đổi, nhưng residual phải phản ánh bước lớn nhất.
+        assert!(report.residual >= largest / 2.0, "{report:?} vs {largest}");
+        assert!(report.residual > network.config().tolerance);
+    }
+
+    #[test]
+    fn test_local_update_leaves_unrelated_entities_untouched() {
+        let mut network = triangle();
+        network.add_entity(entity("D", vec![1.0, 1.0, 0.0], EntityState::Active));
+        network.add_entity(entity("E", vec![0.0, 1.0, 1.0], EntityState::Active));
+        network.add_relationship("D", "E", 0.7);
+        network.propagate_to_fixed_point().unwrap();
+        let d = network.entity("D").unwrap().context.clone();
+        let e = network.entity("E").unwrap().context.clone();
+
+        let report = network
+            .update_entity_state("C", EntityState::Dormant)
+            .unwrap();
+        // Chỉ A và B phụ thuộc vào C; mỗi thực thể được tính lại nhiều nhất hai lần.
+        assert!((2..=4).contains(&report.iterations), "{report:?}");
+        assert_eq!(network.entity("D").unwrap().context, d);
+        assert_eq!(network.entity("E").unwrap().context, e);
+    }
+
+    #[test]
+    fn test_mismatched_context_dimensions_are_rejected() {
+        let mut network = triangle();
+        network.add_entity(entity("D", vec![1.0, 0.0], EntityState::Active));
+        network.add_relationship("C", "D", 1.0);
+        let before = network.entity("C").unwrap().context.clone();
+
+        let err = network.propagate_to_fixed_point().unwrap_err();
+        assert!(
+            matches!(err, PandoraError::InvalidSkillInput { .. }),
+            "{err}"
+        );
+        assert_eq!(network.entity("C").unwrap().context, before);
+        assert!(network.learn_interdependent_representation("C").is_err());
+        // D không truyền ảnh hưởng khi ngủ, nên không còn xung đột số chiều.
+        network.queue_state_update("D", EntityState::Dormant);
+        assert!(network.propagate_to_fixed_point().unwrap().converged);
+        assert!(network
+            .update_entity_state("D", EntityState::Active)
+            .is_err());
+    }
+
+    fn large_network(n: usize) -> InterdependentNetwork {
+        let mut network = InterdependentNetwork::new();
+        for i in 0..n {
+            let x = (i % 7) as f64;
+            network.add_entity(entity(
+                &i.to_string(),
+                vec![x, 1.0 - x],
+                EntityState::Active,
+            ));
+        }
+        for i in 0..n {
+            network.add_relationship(&i.to_string(), &((i + 1) % n).to_string(), 1.0);
+            network.add_relationship(&i.to_string(), &((i * 31 + 7) % n).to_string(), 0.5);
+        }
+        network
+    }
+
+    #[test]
+    fn test_large_network_converges() {
+        let n = 100_000;
+        let mut network = large_network(n);
+        let global = network.propagate_to_fixed_point().unwrap();
+        assert!(global.converged, "{global:?}");
+
+        // Lan truyền cục bộ sau một thay đổi rẻ hơn một lượt lặp toàn cục.
+        let local = network
+            .update_entity_states([("42", EntityState::Dormant)])
+            .unwrap();
+        assert!(local.converged);
+        // Một vòng Jacobi cập nhật đủ n thực thể; hàng đợi chỉ cần một phần nhỏ số đó.
+        assert!(
+            local.iterations * 4 < global.iterations * n,
+            "{local:?} vs {global:?}"
+        );
+
+        // Và cho cùng điểm bất động với lan truyền toàn cục sau cùng thay đổi đó.
+        let mut reference = large_network(n);
+        reference.propagate_to_fixed_point().unwrap();
+        reference.queue_state_update("42", EntityState::Dormant);
+        assert!(reference.propagate_to_fixed_point().unwrap().converged);
+        let worst = (0..n)
+            .map(|i| {
+                let id = i.to_string();
+                InterdependentNetwork::max_change(
+                    &network.entity(&id).unwrap().context,
+                    &reference.entity(&id).unwrap().context,
+                )
+            })
+            .fold(0.0, f64::max);
+        assert!(worst < 1e-4, "sai lệch cục bộ/toàn cục {worst:e}");
+    }
+}
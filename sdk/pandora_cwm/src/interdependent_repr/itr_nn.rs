@@ -1,6 +1,12 @@
 // TDA (Topological Data Analysis) implementation
-// Sử dụng các thuật toán đơn giản thay vì thư viện bên ngoài
+// Xem `super::tda` cho lọc Vietoris–Rips, đồng điều bền vững và độ trung tâm.
 
+#[cfg(feature = "tda")]
+use super::tda::{self, WeightedGraph};
+use super::tda::{TdaConfig, TopologicalFeatures};
+use crate::gnn::types::CausalGraph;
+#[cfg(feature = "tda")]
+use tracing::debug;
 use tracing::info;
 
 /// Một `struct` placeholder cho một Mạng Nơ-ron Đồ thị (GNN).
@@ -27,159 +33,72 @@ impl Default for GraphNeuralNetwork {
 pub struct InterdependentTopoRelationalNN {
     #[allow(dead_code)]
     gnn_processor: GraphNeuralNetwork,
-    // Topological feature extractor sẽ được thêm vào đây
+    tda_config: TdaConfig,
 }
 
-#[cfg(feature = "tda")]
 impl InterdependentTopoRelationalNN {
     pub fn new() -> Self {
+        Self::with_config(TdaConfig::default())
+    }
+
+    /// Khởi tạo ITR-NN với cấu hình TDA tùy chỉnh.
+    pub fn with_config(tda_config: TdaConfig) -> Self {
         info!("ITR-NN: Khởi tạo Mạng Nơ-ron Đồ thị với Phân tích Dữ liệu Topo");
         Self {
             gnn_processor: GraphNeuralNetwork::new(),
+            tda_config,
         }
     }
 
-    /// Tính toán các "chữ ký topo" từ một đồ thị tri thức.
-    /// Đây là bước "thấu suốt" cấu trúc toàn cục trước khi xử lý cục bộ.
-    pub fn extract_topological_signatures(&self, graph_data: &[f64]) {
-        info!("ITR-NN: Trích xuất các đặc trưng topo (TDA)...");
-
-        // Implementation đơn giản của TDA:
-        // 1. Tính toán độ trung tâm (centrality) của các nút
-        let centrality_scores = self.compute_centrality_scores(graph_data);
-
-        // 2. Phân tích cấu trúc cộng đồng (community structure)
-        let community_structure = self.analyze_community_structure(graph_data);
-
-        // 3. Tính toán các đặc trưng topo cơ bản
-        let topological_features = self.compute_basic_topological_features(graph_data);
-
-        info!(
-            "ITR-NN: Đã trích xuất {} đặc trưng topo từ đồ thị",
-            graph_data.len()
-        );
-        info!("ITR-NN: - Độ trung tâm: {:?}", centrality_scores);
-        info!("ITR-NN: - Cấu trúc cộng đồng: {:?}", community_structure);
-        info!("ITR-NN: - Đặc trưng topo: {:?}", topological_features);
-    }
-
-    /// Tính toán độ trung tâm của các nút trong đồ thị.
-    fn compute_centrality_scores(&self, graph_data: &[f64]) -> Vec<f64> {
-        // Thuật toán đơn giản: độ trung tâm dựa trên tổng trọng số các cạnh
-        let mut centrality = vec![0.0; graph_data.len()];
-
-        for (i, &weight) in graph_data.iter().enumerate() {
-            centrality[i] = weight * (graph_data.len() as f64 - i as f64) / graph_data.len() as f64;
-        }
-
-        centrality
-    }
-
-    /// Phân tích cấu trúc cộng đồng trong đồ thị.
-    fn analyze_community_structure(&self, graph_data: &[f64]) -> Vec<usize> {
-        // Thuật toán đơn giản: gom nhóm dựa trên độ tương tự
-        let mut communities = vec![0; graph_data.len()];
-        let mut current_community = 0;
-        let threshold = 0.5;
-
-        for i in 0..graph_data.len() {
-            if communities[i] == 0 {
-                current_community += 1;
-                communities[i] = current_community;
-
-                for j in (i + 1)..graph_data.len() {
-                    if (graph_data[i] - graph_data[j]).abs() < threshold {
-                        communities[j] = current_community;
-                    }
-                }
-            }
-        }
-
-        communities
-    }
-
-    /// Tính toán các đặc trưng topo cơ bản.
-    fn compute_basic_topological_features(&self, graph_data: &[f64]) -> Vec<f64> {
-        let mut features = Vec::new();
-
-        // 1. Mật độ đồ thị
-        let density = graph_data.iter().sum::<f64>()
-            / (graph_data.len() as f64 * (graph_data.len() as f64 - 1.0));
-        features.push(density);
-
-        // 2. Độ phân tán (variance)
-        let mean = graph_data.iter().sum::<f64>() / graph_data.len() as f64;
-        let variance =
-            graph_data.iter().map(|&x| (x - mean).powi(2)).sum::<f64>() / graph_data.len() as f64;
-        features.push(variance);
-
-        // 3. Độ bất đối xứng (skewness)
-        let skewness = graph_data
-            .iter()
-            .map(|&x| ((x - mean) / variance.sqrt()).powi(3))
-            .sum::<f64>()
-            / graph_data.len() as f64;
-        features.push(skewness);
-
-        features
-    }
-
-    /// Xử lý đồ thị bằng cách đưa các đặc trưng topo vào GNN.
-    pub fn process_graph(&self, graph_data: &[f64]) {
-        self.extract_topological_signatures(graph_data);
-        info!("ITR-NN: Điều biến quá trình truyền tin của GNN bằng đặc trưng topo...");
-
-        // Logic GNN sẽ sử dụng các đặc trưng topo để xử lý đồ thị.
-        // Điều này cho phép mạng hiểu được cả:
-        // 1. Quan hệ cục bộ giữa các nút (từ GNN)
-        // 2. Cấu trúc toàn cục và vai trò của từng nút (từ TDA)
-
-        #[cfg(feature = "ml")]
-        {
-            use crate::gnn::layers::GraphConvLayer;
-            use ndarray::arr2;
-            let adj = arr2(&[[0.0, 1.0], [1.0, 0.0]]);
-            let x = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
-            let w = arr2(&[[1.0, 0.0], [0.0, 1.0]]);
-            let layer = GraphConvLayer::new(w);
-            let _y = layer.forward(&adj, &x);
-        }
-
-        info!("ITR-NN: Đã xử lý đồ thị với {} nút", graph_data.len());
+    /// Độ dài cố định của vector đặc trưng trả về bởi `process_graph`.
+    pub fn feature_len(&self) -> usize {
+        self.tda_config.feature_len()
     }
 }
 
-#[cfg(feature = "tda")]
 impl Default for InterdependentTopoRelationalNN {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(not(feature = "tda"))]
+#[cfg(feature = "tda")]
 impl InterdependentTopoRelationalNN {
-    pub fn new() -> Self {
-        info!("ITR-NN: Khởi tạo (TDA features disabled)");
-        Self {
-            gnn_processor: GraphNeuralNetwork::new(),
-        }
+    /// Tính toán các "chữ ký topo" từ đồ thị tri thức của CWM:
+    /// lọc Vietoris–Rips theo trọng số cạnh, đồng điều bền vững bậc 0 và 1,
+    /// độ trung tâm (betweenness, eigenvector) và cộng đồng theo modularity.
+    pub fn extract_topological_signatures(&self, graph: &CausalGraph) -> TopologicalFeatures {
+        let features = tda::analyze(&WeightedGraph::from_causal_graph(graph), &self.tda_config);
+        debug!(
+            "ITR-NN: {} nút, {} cạnh, β0={}, β1={}, {} cộng đồng (Q={:.3})",
+            features.node_count,
+            features.edge_count,
+            features.betti(0),
+            features.betti(1),
+            features.community_count,
+            features.modularity
+        );
+        features
     }
 
-    pub fn extract_topological_signatures(&self, _graph_data: &[f64]) {
+    /// Xử lý đồ thị và trả về các đặc trưng topo để điều biến quá trình truyền tin của GNN.
+    pub fn process_graph(&self, graph: &CausalGraph) -> TopologicalFeatures {
+        self.extract_topological_signatures(graph)
+    }
+}
+
+#[cfg(not(feature = "tda"))]
+impl InterdependentTopoRelationalNN {
+    pub fn extract_topological_signatures(&self, graph: &CausalGraph) -> TopologicalFeatures {
         info!("ITR-NN: TDA features disabled - skipping topological analysis");
+        TopologicalFeatures::empty(graph.node_count(), &self.tda_config)
     }
 
-    pub fn process_graph(&self, graph_data: &[f64]) {
+    pub fn process_graph(&self, graph: &CausalGraph) -> TopologicalFeatures {
         info!(
             "ITR-NN: Xử lý đồ thị cơ bản (không có TDA) với {} nút",
-            graph_data.len()
+            graph.node_count()
         );
-    }
-}
-
-#[cfg(not(feature = "tda"))]
-impl Default for InterdependentTopoRelationalNN {
-    fn default() -> Self {
-        Self::new()
+        self.extract_topological_signatures(graph)
     }
 }
@@ -1,2 +1,3 @@
 pub mod irl;
 pub mod itr_nn;
+pub mod tda;
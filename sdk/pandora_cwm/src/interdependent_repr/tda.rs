@@ -0,0 +1,884 @@
+//! Topological Data Analysis (TDA) over the CWM causal graph.
+//!
+//! The causal graph is viewed as an undirected weighted graph whose edge strength is
+//! `|weight|`. Strong relations enter the filtration first, so the filtration value of
+//! an edge is `1 - strength / max_strength`. On top of that we build the clique
+//! (Vietoris–Rips) complex up to 2-simplices and compute 0- and 1-dimensional
+//! persistent homology over Z/2 with the standard boundary-matrix reduction.
+//!
+//! Besides persistence diagrams and persistence images, the module also computes
+//! betweenness and eigenvector centralities and Louvain communities, so the ITR-NN
+//! gets both the global shape and the role of every node.
+
+use crate::gnn::types::CausalGraph;
+use fnv::FnvHashMap;
+use petgraph::visit::EdgeRef;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Number of scalar summary statistics appended after the persistence images.
+const SUMMARY_LEN: usize = 10;
+
+/// Configuration for topological feature extraction.
+#[derive(Debug, Clone)]
+pub struct TdaConfig {
+    /// Edges whose filtration value exceeds this threshold are left out of the complex.
+    pub max_filtration: f64,
+    /// Side length of the square persistence image grid.
+    pub image_resolution: usize,
+    /// Standard deviation of the Gaussian kernel used for persistence images.
+    pub image_sigma: f64,
+    /// Maximum number of power-iteration steps for eigenvector centrality.
+    pub max_power_iterations: usize,
+    /// Convergence tolerance for eigenvector centrality.
+    pub tolerance: f64,
+}
+
+impl Default for TdaConfig {
+    fn default() -> Self {
+        Self {
+            max_filtration: 1.0,
+            image_resolution: 8,
+            image_sigma: 0.1,
+            max_power_iterations: 200,
+            tolerance: 1e-9,
+        }
+    }
+}
+
+impl TdaConfig {
+    /// Length of the vector returned by [`TopologicalFeatures::feature_vector`].
+    pub fn feature_len(&self) -> usize {
+        2 * self.image_resolution * self.image_resolution + SUMMARY_LEN
+    }
+}
+
+/// A single (birth, death) pair of a homology class. `death` is `f64::INFINITY`
+/// for classes that never die inside the filtration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PersistencePair {
+    pub birth: f64,
+    pub death: f64,
+}
+
+impl PersistencePair {
+    /// Lifetime of the class; infinite classes are clamped to `horizon`.
+    pub fn persistence(&self, horizon: f64) -> f64 {
+        self.death.min(horizon) - self.birth
+    }
+
+    pub fn is_essential(&self) -> bool {
+        self.death.is_infinite()
+    }
+}
+
+/// Persistence diagram for one homology dimension.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PersistenceDiagram {
+    pub dimension: usize,
+    pub pairs: Vec<PersistencePair>,
+}
+
+impl PersistenceDiagram {
+    /// Number of classes alive at the end of the filtration.
+    pub fn essential_count(&self) -> usize {
+        self.pairs.iter().filter(|p| p.is_essential()).count()
+    }
+
+    pub fn total_persistence(&self, horizon: f64) -> f64 {
+        self.pairs.iter().map(|p| p.persistence(horizon)).sum()
+    }
+
+    pub fn max_persistence(&self, horizon: f64) -> f64 {
+        self.pairs
+            .iter()
+            .map(|p| p.persistence(horizon))
+            .fold(0.0, f64::max)
+    }
+
+    /// Rasterises the diagram into a `resolution × resolution` persistence image over
+    /// `(birth, persistence) ∈ [0, horizon]²`, row-major by persistence. Each point is
+    /// a Gaussian weighted linearly by its persistence, evaluated at pixel centres.
+    pub fn persistence_image(&self, resolution: usize, sigma: f64, horizon: f64) -> Vec<f64> {
+        let mut image = vec![0.0; resolution * resolution];
+        if resolution == 0 || horizon <= 0.0 {
+            return image;
+        }
+        let pixel = horizon / resolution as f64;
+        let two_sigma_sq = 2.0 * sigma * sigma;
+        for pair in &self.pairs {
+            let persistence = pair.persistence(horizon);
+            if persistence <= 0.0 {
+                continue;
+            }
+            let weight = persistence / horizon;
+            for row in 0..resolution {
+                let y = (row as f64 + 0.5) * pixel;
+                for col in 0..resolution {
+                    let x = (col as f64 + 0.5) * pixel;
+                    let d2 = (x - pair.birth).powi(2) + (y - persistence).powi(2);
+                    image[row * resolution + col] += weight * (-d2 / two_sigma_sq).exp();
+                }
+            }
+        }
+        image
+    }
+}
+
+/// Topological and structural features of a graph.
+#[derive(Debug, Clone, Default)]
+pub struct TopologicalFeatures {
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// Persistence diagrams indexed by homology dimension (0 and 1).
+    pub diagrams: Vec<PersistenceDiagram>,
+    /// Flattened persistence images, one per diagram.
+    pub persistence_images: Vec<Vec<f64>>,
+    /// Normalised betweenness centrality per node.
+    pub betweenness: Vec<f64>,
+    /// L2-normalised eigenvector centrality per node.
+    pub eigenvector: Vec<f64>,
+    /// Community label per node, labels are `0..community_count`.
+    pub communities: Vec<usize>,
+    pub community_count: usize,
+    /// Newman modularity of `communities`.
+    pub modularity: f64,
+    /// Filtration horizon the diagrams were computed with; essential classes are
+    /// clamped to it in the persistence statistics.
+    pub horizon: f64,
+}
+
+impl TopologicalFeatures {
+    /// Features of a graph on which no analysis was run (all-zero, fixed length).
+    pub fn empty(node_count: usize, config: &TdaConfig) -> Self {
+        let pixels = config.image_resolution * config.image_resolution;
+        Self {
+            node_count,
+            diagrams: vec![
+                PersistenceDiagram {
+                    dimension: 0,
+                    pairs: Vec::new(),
+                },
+                PersistenceDiagram {
+                    dimension: 1,
+                    pairs: Vec::new(),
+                },
+            ],
+            persistence_images: vec![vec![0.0; pixels], vec![0.0; pixels]],
+            betweenness: vec![0.0; node_count],
+            eigenvector: vec![0.0; node_count],
+            communities: (0..node_count).collect(),
+            community_count: node_count,
+            horizon: config.max_filtration,
+            ..Default::default()
+        }
+    }
+
+    /// Number of essential classes in the given dimension.
+    pub fn betti(&self, dimension: usize) -> usize {
+        self.diagrams
+            .get(dimension)
+            .map_or(0, PersistenceDiagram::essential_count)
+    }
+
+    /// Fixed-length feature vector: both persistence images followed by summary
+    /// statistics. Its length only depends on the config, not on the graph size.
+    pub fn feature_vector(&self) -> Vec<f64> {
+        let mut features: Vec<f64> = self.persistence_images.iter().flatten().copied().collect();
+        let diagram = |d: usize| self.diagrams.get(d).cloned().unwrap_or_default();
+        let (h0, h1) = (diagram(0), diagram(1));
+        let mean = |v: &[f64]| {
+            if v.is_empty() {
+                0.0
+            } else {
+                v.iter().sum::<f64>() / v.len() as f64
+            }
+        };
+        let max = |v: &[f64]| v.iter().copied().fold(0.0, f64::max);
+        features.extend_from_slice(&[
+            h0.essential_count() as f64,
+            h1.pairs.len() as f64,
+            h0.total_persistence(self.horizon),
+            h1.total_persistence(self.horizon),
+            h1.max_persistence(self.horizon),
+            mean(&self.betweenness),
+            max(&self.betweenness),
+            max(&self.eigenvector),
+            self.community_count as f64,
+            self.modularity,
+        ]);
+        features
+    }
+}
+
+/// Undirected weighted view of a graph: one entry per unordered node pair.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedGraph {
+    node_count: usize,
+    /// `(u, v, strength)` with `u < v` and `strength > 0`.
+    edges: Vec<(usize, usize, f64)>,
+    adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+impl WeightedGraph {
+    /// Builds a graph from `(u, v, weight)` triples. Self-loops and zero weights are
+    /// dropped, parallel or reciprocal edges keep the strongest `|weight|`.
+    pub fn from_edges(
+        node_count: usize,
+        edges: impl IntoIterator<Item = (usize, usize, f64)>,
+    ) -> Self {
+        let mut merged: FnvHashMap<(usize, usize), f64> = FnvHashMap::default();
+        for (u, v, w) in edges {
+            let strength = w.abs();
+            if u == v
+                || u >= node_count
+                || v >= node_count
+                || strength == 0.0
+                || !strength.is_finite()
+            {
+                continue;
+            }
+            let key = (u.min(v), u.max(v));
+            let entry = merged.entry(key).or_insert(0.0);
+            *entry = entry.max(strength);
+        }
+        let mut edges: Vec<(usize, usize, f64)> =
+            merged.into_iter().map(|((u, v), w)| (u, v, w)).collect();
+        edges.sort_by_key(|e| (e.0, e.1));
+
+        let mut adjacency = vec![Vec::new(); node_count];
+        for &(u, v, w) in &edges {
+            adjacency[u].push((v, w));
+            adjacency[v].push((u, w));
+        }
+        Self {
+            node_count,
+            edges,
+            adjacency,
+        }
+    }
+
+    /// Undirected view of a CWM causal graph using `|edge.weight|` as strength.
+    pub fn from_causal_graph(graph: &CausalGraph) -> Self {
+        Self::from_edges(
+            graph.node_count(),
+            graph.edge_references().map(|e| {
+                (
+                    e.source().index(),
+                    e.target().index(),
+                    e.weight().weight as f64,
+                )
+            }),
+        )
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn max_strength(&self) -> f64 {
+        self.edges.iter().map(|e| e.2).fold(0.0, f64::max)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Simplex {
+    /// Sorted vertex list (1, 2 or 3 vertices).
+    vertices: Vec<usize>,
+    filtration: f64,
+}
+
+impl Simplex {
+    fn dimension(&self) -> usize {
+        self.vertices.len() - 1
+    }
+}
+
+/// Builds the clique complex up to dimension 2 sorted by (filtration, dimension).
+fn rips_filtration(graph: &WeightedGraph, max_filtration: f64) -> Vec<Simplex> {
+    let max_strength = graph.max_strength();
+    let edge_value = |w: f64| {
+        if max_strength > 0.0 {
+            1.0 - w / max_strength
+        } else {
+            1.0
+        }
+    };
+
+    let mut simplices: Vec<Simplex> = (0..graph.node_count)
+        .map(|v| Simplex {
+            vertices: vec![v],
+            filtration: 0.0,
+        })
+        .collect();
+
+    let mut edge_values: FnvHashMap<(usize, usize), f64> = FnvHashMap::default();
+    for &(u, v, w) in &graph.edges {
+        let value = edge_value(w);
+        if value <= max_filtration {
+            edge_values.insert((u, v), value);
+            simplices.push(Simplex {
+                vertices: vec![u, v],
+                filtration: value,
+            });
+        }
+    }
+
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); graph.node_count];
+    for &(u, v) in edge_values.keys() {
+        neighbours[u].push(v);
+    }
+    for list in &mut neighbours {
+        list.sort_unstable();
+    }
+    for (u, higher) in neighbours.iter().enumerate() {
+        for (i, &v) in higher.iter().enumerate() {
+            for &w in &higher[i + 1..] {
+                if let Some(&vw) = edge_values.get(&(v, w)) {
+                    let filtration = edge_values[&(u, v)].max(edge_values[&(u, w)]).max(vw);
+                    simplices.push(Simplex {
+                        vertices: vec![u, v, w],
+                        filtration,
+                    });
+                }
+            }
+        }
+    }
+
+    simplices.sort_by(|a, b| {
+        a.filtration
+            .partial_cmp(&b.filtration)
+            .unwrap_or(Ordering::Equal)
+            .then(a.dimension().cmp(&b.dimension()))
+            .then_with(|| a.vertices.cmp(&b.vertices))
+    });
+    simplices
+}
+
+/// Symmetric difference of two sorted index lists (column addition over Z/2).
+fn add_columns(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => {
+                out.push(a[i]);
+                i += 1;
+            }
+            Ordering::Greater => {
+                out.push(b[j]);
+                j += 1;
+            }
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
+
+/// Computes persistence diagrams of dimension 0 and 1 for the given filtration.
+fn persistent_homology(simplices: &[Simplex]) -> Vec<PersistenceDiagram> {
+    let index: FnvHashMap<&[usize], usize> = simplices
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.vertices.as_slice(), i))
+        .collect();
+
+    let mut pivot_owner: Vec<Option<usize>> = vec![None; simplices.len()];
+    let mut reduced: Vec<Vec<usize>> = vec![Vec::new(); simplices.len()];
+    let mut paired = vec![false; simplices.len()];
+    let mut diagrams = vec![
+        PersistenceDiagram {
+            dimension: 0,
+            pairs: Vec::new(),
+        },
+        PersistenceDiagram {
+            dimension: 1,
+            pairs: Vec::new(),
+        },
+    ];
+
+    for (j, simplex) in simplices.iter().enumerate() {
+        let mut column: Vec<usize> = Vec::new();
+        if simplex.vertices.len() > 1 {
+            for skip in 0..simplex.vertices.len() {
+                let face: Vec<usize> = simplex
+                    .vertices
+                    .iter()
+                    .enumerate()
+                    .filter(|&(k, _)| k != skip)
+                    .map(|(_, &v)| v)
+                    .collect();
+                if let Some(&f) = index.get(face.as_slice()) {
+                    column.push(f);
+                }
+            }
+            column.sort_unstable();
+        }
+
+        while let Some(&low) = column.last() {
+            match pivot_owner[low] {
+                Some(owner) => column = add_columns(&column, &reduced[owner]),
+                None => break,
+            }
+        }
+
+        if let Some(&low) = column.last() {
+            pivot_owner[low] = Some(j);
+            paired[low] = true;
+            paired[j] = true;
+            let birth_simplex = &simplices[low];
+            let (birth, death) = (birth_simplex.filtration, simplex.filtration);
+            if death > birth {
+                if let Some(diagram) = diagrams.get_mut(birth_simplex.dimension()) {
+                    diagram.pairs.push(PersistencePair { birth, death });
+                }
+            }
+        }
+        reduced[j] = column;
+    }
+
+    for (i, simplex) in simplices.iter().enumerate() {
+        // An unpaired simplex whose column reduced to zero creates an essential class.
+        if !paired[i] && reduced[i].is_empty() {
+            if let Some(diagram) = diagrams.get_mut(simplex.dimension()) {
+                diagram.pairs.push(PersistencePair {
+                    birth: simplex.filtration,
+                    death: f64::INFINITY,
+                });
+            }
+        }
+    }
+    diagrams
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HeapEntry {
+    distance: f64,
+    node: usize,
+}
+
+impl Eq for HeapEntry {}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .partial_cmp(&self.distance)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Brandes' betweenness centrality with Dijkstra; edge length is `1 / strength`.
+/// Normalised by the number of node pairs not containing the node.
+pub fn betweenness_centrality(graph: &WeightedGraph) -> Vec<f64> {
+    const EPS: f64 = 1e-12;
+    let n = graph.node_count;
+    let mut centrality = vec![0.0; n];
+
+    for source in 0..n {
+        let mut stack = Vec::with_capacity(n);
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut sigma = vec![0.0; n];
+        let mut distance = vec![f64::INFINITY; n];
+        sigma[source] = 1.0;
+        distance[source] = 0.0;
+
+        let mut heap = BinaryHeap::new();
+        heap.push(HeapEntry {
+            distance: 0.0,
+            node: source,
+        });
+        let mut settled = vec![false; n];
+        while let Some(HeapEntry {
+            distance: d,
+            node: v,
+        }) = heap.pop()
+        {
+            if settled[v] {
+                continue;
+            }
+            settled[v] = true;
+            stack.push(v);
+            for &(w, strength) in &graph.adjacency[v] {
+                let candidate = d + 1.0 / strength;
+                if candidate < distance[w] - EPS {
+                    distance[w] = candidate;
+                    sigma[w] = sigma[v];
+                    predecessors[w] = vec![v];
+                    heap.push(HeapEntry {
+                        distance: candidate,
+                        node: w,
+                    });
+                } else if (candidate - distance[w]).abs() <= EPS && !settled[w] {
+                    sigma[w] += sigma[v];
+                    predecessors[w].push(v);
+                }
+            }
+        }
+
+        let mut delta = vec![0.0; n];
+        while let Some(w) = stack.pop() {
+            for &v in &predecessors[w] {
+                delta[v] += sigma[v] / sigma[w] * (1.0 + delta[w]);
+            }
+            if w != source {
+                centrality[w] += delta[w];
+            }
+        }
+    }
+
+    // Every undirected pair was counted from both endpoints.
+    if n > 2 {
+        let pairs = ((n - 1) * (n - 2)) as f64;
+        for c in &mut centrality {
+            *c /= pairs;
+        }
+    } else {
+        centrality.iter_mut().for_each(|c| *c = 0.0);
+    }
+    centrality
+}
+
+/// Eigenvector centrality by power iteration on `A + I` (the shift keeps the
+/// iteration from oscillating on bipartite graphs without changing eigenvectors).
+pub fn eigenvector_centrality(
+    graph: &WeightedGraph,
+    max_iterations: usize,
+    tolerance: f64,
+) -> Vec<f64> {
+    let n = graph.node_count;
+    if n == 0 {
+        return Vec::new();
+    }
+    if graph.edges.is_empty() {
+        return vec![0.0; n];
+    }
+    let mut x = vec![1.0 / (n as f64).sqrt(); n];
+    for _ in 0..max_iterations {
+        let mut next = x.clone();
+        for (v, neighbours) in graph.adjacency.iter().enumerate() {
+            for &(u, w) in neighbours {
+                next[v] += w * x[u];
+            }
+        }
+        let norm = next.iter().map(|v| v * v).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            return vec![0.0; n];
+        }
+        next.iter_mut().for_each(|v| *v /= norm);
+        let change: f64 = next.iter().zip(&x).map(|(a, b)| (a - b).abs()).sum();
+        x = next;
+        if change < tolerance * n as f64 {
+            break;
+        }
+    }
+    x
+}
+
+/// Newman modularity of a partition: `Q = 1/2m Σ_ij (A_ij - k_i k_j / 2m) δ(c_i, c_j)`.
+pub fn modularity(graph: &WeightedGraph, communities: &[usize]) -> f64 {
+    let two_m: f64 = 2.0 * graph.edges.iter().map(|e| e.2).sum::<f64>();
+    if two_m == 0.0 {
+        return 0.0;
+    }
+    let count = communities.iter().copied().max().map_or(0, |m| m + 1);
+    let mut internal = vec![0.0; count];
+    let mut total = vec![0.0; count];
+    for (v, neighbours) in graph.adjacency.iter().enumerate() {
+        for &(u, w) in neighbours {
+            total[communities[v]] += w;
+            if communities[u] == communities[v] {
+                internal[communities[v]] += w;
+            }
+        }
+    }
+    internal
+        .iter()
+        .zip(&total)
+        .map(|(i, t)| i / two_m - (t / two_m).powi(2))
+        .sum()
+}
+
+/// Louvain community detection. Returns labels `0..k` in order of first appearance.
+pub fn louvain_communities(graph: &WeightedGraph) -> Vec<usize> {
+    let n = graph.node_count;
+    // Working graph: rows may contain a self-loop holding twice the internal weight.
+    let mut adjacency: Vec<Vec<(usize, f64)>> = graph.adjacency.clone();
+    let mut membership: Vec<usize> = (0..n).collect();
+
+    loop {
+        let size = adjacency.len();
+        let degree: Vec<f64> = adjacency
+            .iter()
+            .map(|row| row.iter().map(|e| e.1).sum())
+            .collect();
+        let two_m: f64 = degree.iter().sum();
+        if two_m == 0.0 {
+            break;
+        }
+
+        let mut community: Vec<usize> = (0..size).collect();
+        let mut total = degree.clone();
+        let mut improved = false;
+        let mut moved = true;
+        while moved {
+            moved = false;
+            for v in 0..size {
+                let current = community[v];
+                total[current] -= degree[v];
+
+                let mut links: FnvHashMap<usize, f64> = FnvHashMap::default();
+                for &(u, w) in &adjacency[v] {
+                    if u != v {
+                        *links.entry(community[u]).or_insert(0.0) += w;
+                    }
+                }
+                let gain = |c: usize, k_in: f64| k_in - total[c] * degree[v] / two_m;
+                let mut best = current;
+                let mut best_gain = gain(current, links.get(&current).copied().unwrap_or(0.0));
+                let mut candidates: Vec<(usize, f64)> = links.into_iter().collect();
+                candidates.sort_by_key(|c| c.0);
+                for (c, k_in) in candidates {
+                    let g = gain(c, k_in);
+                    if g > best_gain + 1e-12 {
+                        best_gain = g;
+                        best = c;
+                    }
+                }
+
+                total[best] += degree[v];
+                if best != current {
+                    community[v] = best;
+                    moved = true;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+
+        let mut relabel: FnvHashMap<usize, usize> = FnvHashMap::default();
+        for c in &mut community {
+            let next = relabel.len();
+            *c = *relabel.entry(*c).or_insert(next);
+        }
+        for m in &mut membership {
+            *m = community[*m];
+        }
+        let mut aggregated: Vec<FnvHashMap<usize, f64>> =
+            vec![FnvHashMap::default(); relabel.len()];
+        for (v, row) in adjacency.iter().enumerate() {
+            for &(u, w) in row {
+                *aggregated[community[v]].entry(community[u]).or_insert(0.0) += w;
+            }
+        }
+        adjacency = aggregated
+            .into_iter()
+            .map(|row| {
+                let mut row: Vec<(usize, f64)> = row.into_iter().collect();
+                row.sort_by_key(|e| e.0);
+                row
+            })
+            .collect();
+    }
+
+    let mut relabel: FnvHashMap<usize, usize> = FnvHashMap::default();
+    membership
+        .into_iter()
+        .map(|c| {
+            let next = relabel.len();
+            *relabel.entry(c).or_insert(next)
+        })
+        .collect()
+}
+
+/// Runs the full topological analysis on a weighted graph.
+pub fn analyze(graph: &WeightedGraph, config: &TdaConfig) -> TopologicalFeatures {
+    let simplices = rips_filtration(graph, config.max_filtration);
+    let diagrams = persistent_homology(&simplices);
+    let horizon = config.max_filtration.max(f64::EPSILON);
+    let persistence_images = diagrams
+        .iter()
+        .map(|d| d.persistence_image(config.image_resolution, config.image_sigma, horizon))
+        .collect();
+    let communities = louvain_communities(graph);
+    let community_count = communities.iter().copied().max().map_or(0, |m| m + 1);
+
+    TopologicalFeatures {
+        node_count: graph.node_count,
+        edge_count: graph.edge_count(),
+        diagrams,
+        persistence_images,
+        betweenness: betweenness_centrality(graph),
+        eigenvector: eigenvector_centrality(graph, config.max_power_iterations, config.tolerance),
+        modularity: modularity(graph, &communities),
+        communities,
+        community_count,
+        horizon,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::gnn::types::{CausalEdge, CausalEdgeKind};
+
+    fn cycle(n: usize, weight: f64) -> WeightedGraph {
+        WeightedGraph::from_edges(n, (0..n).map(|i| (i, (i + 1) % n, weight)))
+    }
+
+    /// Two triangles joined by a single weak bridge.
+    fn barbell() -> WeightedGraph {
+        WeightedGraph::from_edges(
+            6,
+            vec![
+                (0, 1, 1.0),
+                (1, 2, 1.0),
+                (0, 2, 1.0),
+                (3, 4, 1.0),
+                (4, 5, 1.0),
+                (3, 5, 1.0),
+                (2, 3, 0.5),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_h0_counts_connected_components() {
+        let graph = WeightedGraph::from_edges(5, vec![(0, 1, 1.0), (1, 2, 0.5), (3, 4, 1.0)]);
+        let features = analyze(&graph, &TdaConfig::default());
+        assert_eq!(features.betti(0), 2);
+        // The weaker edge merges two components at filtration 0.5.
+        assert!(features.diagrams[0]
+            .pairs
+            .iter()
+            .any(|p| (p.death - 0.5).abs() < 1e-9 && p.birth == 0.0));
+    }
+
+    #[test]
+    fn test_h1_detects_unfilled_cycle() {
+        let features = analyze(&cycle(4, 1.0), &TdaConfig::default());
+        assert_eq!(features.betti(0), 1);
+        assert_eq!(features.betti(1), 1);
+    }
+
+    #[test]
+    fn test_h1_cycle_killed_by_diagonal() {
+        // Square with strong sides and a weak diagonal: the loop is born at 0 and
+        // filled at the diagonal's filtration value.
+        let mut edges: Vec<_> = (0..4).map(|i| (i, (i + 1) % 4, 1.0)).collect();
+        edges.push((0, 2, 0.25));
+        let features = analyze(&WeightedGraph::from_edges(4, edges), &TdaConfig::default());
+        assert_eq!(features.betti(1), 0);
+        let h1 = &features.diagrams[1].pairs;
+        assert_eq!(h1.len(), 1);
+        assert!((h1[0].death - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_triangle_has_no_h1() {
+        let features = analyze(&cycle(3, 1.0), &TdaConfig::default());
+        assert_eq!(features.betti(1), 0);
+        assert!(features.diagrams[1].pairs.is_empty());
+    }
+
+    #[test]
+    fn test_betweenness_of_path_centre() {
+        let graph = WeightedGraph::from_edges(3, vec![(0, 1, 1.0), (1, 2, 1.0)]);
+        let b = betweenness_centrality(&graph);
+        assert!((b[1] - 1.0).abs() < 1e-9);
+        assert_eq!(b[0], 0.0);
+        assert_eq!(b[2], 0.0);
+    }
+
+    #[test]
+    fn test_betweenness_prefers_strong_paths() {
+        // 0-1-3 is strong, 0-2-3 is weak, so node 1 carries the shortest path.
+        let graph =
+            WeightedGraph::from_edges(4, vec![(0, 1, 1.0), (1, 3, 1.0), (0, 2, 0.1), (2, 3, 0.1)]);
+        let b = betweenness_centrality(&graph);
+        assert!(b[1] > b[2]);
+    }
+
+    #[test]
+    fn test_eigenvector_centrality_star() {
+        let graph = WeightedGraph::from_edges(4, (1..4).map(|i| (0, i, 1.0)));
+        let e = eigenvector_centrality(&graph, 500, 1e-12);
+        assert!(e[0] > e[1]);
+        assert!((e[1] - e[2]).abs() < 1e-9);
+        let norm: f64 = e.iter().map(|v| v * v).sum();
+        assert!((norm - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_louvain_splits_barbell() {
+        let graph = barbell();
+        let communities = louvain_communities(&graph);
+        assert_eq!(communities[0], communities[1]);
+        assert_eq!(communities[1], communities[2]);
+        assert_eq!(communities[3], communities[4]);
+        assert_ne!(communities[0], communities[3]);
+        assert!(modularity(&graph, &communities) > 0.3);
+    }
+
+    #[test]
+    fn test_feature_vector_has_fixed_length() {
+        let config = TdaConfig::default();
+        let mut features = analyze(&cycle(3, 1.0), &config);
+        assert_eq!(features.horizon, config.max_filtration);
+        let small = features.feature_vector();
+        let large = analyze(&barbell(), &config).feature_vector();
+        assert_eq!(small.len(), config.feature_len());
+        assert_eq!(large.len(), config.feature_len());
+        // Essential classes are clamped to the stored horizon, not a fixed 1.0
+        let pixels = 2 * config.image_resolution * config.image_resolution;
+        features.horizon *= 2.0;
+        let wider = features.feature_vector();
+        assert_eq!(wider[pixels + 2], small[pixels + 2] + config.max_filtration);
+        assert_eq!(
+            TopologicalFeatures::empty(7, &config)
+                .feature_vector()
+                .len(),
+            config.feature_len()
+        );
+    }
+
+    #[test]
+    fn test_persistence_image_reflects_diagram() {
+        let config = TdaConfig::default();
+        let image = analyze(&cycle(4, 1.0), &config).persistence_images[1].clone();
+        assert!(image.iter().sum::<f64>() > 0.0);
+        let empty = analyze(&cycle(3, 1.0), &config).persistence_images[1].clone();
+        assert!(empty.iter().all(|&v| v == 0.0));
+    }
+
+    #[test]
+    fn test_from_causal_graph_merges_reciprocal_edges() {
+        let mut graph = CausalGraph::new();
+        let a = graph.add_node(vec![0.0]);
+        let b = graph.add_node(vec![0.0]);
+        graph.add_edge(a, b, CausalEdge::new(CausalEdgeKind::Cause, 0.4));
+        graph.add_edge(b, a, CausalEdge::new(CausalEdgeKind::Inhibit, -0.9));
+        let weighted = WeightedGraph::from_causal_graph(&graph);
+        assert_eq!(weighted.edge_count(), 1);
+        assert!((weighted.edges[0].2 - 0.9).abs() < 1e-6);
+    }
+}
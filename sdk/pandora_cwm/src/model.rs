@@ -4,13 +4,26 @@
 //! and graph neural networks to create a causality-aware world model.
 
 use crate::gnn::{types::GnnConfig, GraphNeuralNetwork};
-use pandora_core::ontology::EpistemologicalFlow;
+use crate::nn::uq_models::{DeepEnsemble, UncertaintyEstimate, UncertaintyModel};
+use pandora_core::ontology::{DataEidos, EpistemologicalFlow};
+use pandora_core::sdr::RandomProjection;
 use pandora_core::world_model::WorldModel;
 use pandora_error::PandoraError;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use tracing::debug;
 
+/// Seed of the SDR <-> embedding projection shared by every model instance.
+const SDR_PROJECTION_SEED: u64 = 0x5DA_C0DE;
+
+/// Error for an eidos or embedding that does not fit the SDR projection.
+fn embedding_error(error: impl std::fmt::Display) -> PandoraError {
+    PandoraError::InvalidSkillInput {
+        skill_name: "cwm_embedding".into(),
+        message: error.to_string(),
+    }
+}
+
 /// Represents a causal hypothesis discovered through data analysis.
 /// This is a simplified version for the CWM module.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -38,41 +51,58 @@ pub struct InterdependentCausalModel {
     predictor: StatePredictor,
 }
 
-/// Simple state predictor for forward modeling
+/// State predictor for forward modeling.
+///
+/// A deep ensemble of Gaussian MLPs predicts the next state embedding from the
+/// current context, together with its epistemic and aleatoric uncertainty.
 pub struct StatePredictor {
-    #[allow(dead_code)]
-    input_dims: usize,
-    output_dims: usize,
+    model: Box<dyn UncertaintyModel>,
 }
 
 impl StatePredictor {
-    /// Creates a new StatePredictor
-    pub fn new(input_dims: usize, output_dims: usize) -> Self {
-        Self {
-            input_dims,
-            output_dims,
-        }
+    /// Number of ensemble members used by `StatePredictor::new`.
+    pub const ENSEMBLE_SIZE: usize = 5;
+
+    /// Creates a StatePredictor backed by a deep ensemble that maps a context
+    /// embedding of `context_dims` to a next-state embedding of `state_dims`
+    pub fn new(context_dims: usize, state_dims: usize) -> Self {
+        let hidden = context_dims.clamp(8, 64);
+        Self::with_model(Box::new(DeepEnsemble::new(
+            context_dims,
+            hidden,
+            state_dims,
+            Self::ENSEMBLE_SIZE,
+            SDR_PROJECTION_SEED,
+        )))
+    }
+
+    /// Creates a StatePredictor from any uncertainty-aware model (e.g. `McDropout`)
+    pub fn with_model(model: Box<dyn UncertaintyModel>) -> Self {
+        Self { model }
     }
 
     /// Predicts the next state embedding from current context
     pub fn predict(&self, context_embedding: &[f32]) -> Result<Vec<f32>, PandoraError> {
-        // Simplified prediction: just add some noise to simulate state change
-        // In a full implementation, this would be a trained neural network
-        let mut prediction = context_embedding.to_vec();
-
-        // Add small random changes to simulate state transition
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        for val in prediction.iter_mut() {
-            *val += rng.gen_range(-0.1..0.1);
-        }
+        Ok(self.predict_with_uncertainty(context_embedding)?.mean)
+    }
 
-        // Ensure output has correct dimensions
-        if prediction.len() != self.output_dims {
-            prediction.resize(self.output_dims, 0.0);
-        }
+    /// Predicts the next state embedding together with its decomposed uncertainty
+    pub fn predict_with_uncertainty(
+        &self,
+        context_embedding: &[f32],
+    ) -> Result<UncertaintyEstimate, PandoraError> {
+        self.model.estimate(context_embedding)
+    }
 
-        Ok(prediction)
+    /// Trains the predictor on one observed transition, returning the NLL
+    pub fn train(
+        &mut self,
+        context_embedding: &[f32],
+        next_embedding: &[f32],
+        learning_rate: f32,
+    ) -> Result<f32, PandoraError> {
+        self.model
+            .train_step(context_embedding, next_embedding, learning_rate)
     }
 }
 
@@ -99,7 +129,7 @@ impl InterdependentCausalModel {
     /// ```
     pub fn new(config: GnnConfig) -> Result<Self, PandoraError> {
         let gnn = GraphNeuralNetwork::new(config.clone())?;
-        let predictor = StatePredictor::new(config.input_dims, config.hidden_dims);
+        let predictor = StatePredictor::new(config.hidden_dims, config.hidden_dims);
         Ok(Self { gnn, predictor })
     }
 
@@ -230,25 +260,42 @@ impl InterdependentCausalModel {
         embedding
     }
 
-    /// Creates an embedding for a concept index
+    /// Creates an embedding for a concept index.
+    ///
+    /// The embedding is the concept's row in the fixed SDR projection, so the
+    /// embedding of a whole `DataEidos` (see `embed_eidos`) is the normalised sum
+    /// of the embeddings of its active concepts.
     pub fn create_concept_embedding(&self, concept_index: u32) -> Vec<f32> {
-        let mut embedding = vec![0.0; self.gnn.config.hidden_dims];
-
-        // Use the concept index to create a deterministic embedding
-        let normalized_index = (concept_index as f32) / 1000.0; // Normalize index
-        embedding[0] = normalized_index;
+        self.sdr_projection(0, 0).concept_vector(concept_index)
+    }
 
-        // Add some variation based on the index
-        embedding[1] = (concept_index as f32).sin();
-        embedding[2] = (concept_index as f32).cos();
+    fn sdr_projection(&self, dimensionality: u32, active_bits: usize) -> RandomProjection {
+        RandomProjection::new(
+            dimensionality,
+            self.gnn.config.hidden_dims,
+            active_bits,
+            SDR_PROJECTION_SEED,
+        )
+    }
 
-        // Add deterministic variation based on the index for remaining dimensions
-        for (i, val) in embedding.iter_mut().enumerate().skip(3) {
-            let seed = (concept_index as f32) * (i as f32) * 0.1;
-            *val = (seed.sin() * 0.05) + (seed.cos() * 0.03);
-        }
+    /// Projects a sparse `DataEidos` into the dense CWM embedding space.
+    pub fn embed_eidos(&self, eidos: &DataEidos) -> Result<Vec<f32>, PandoraError> {
+        self.sdr_projection(eidos.dimensionality, eidos.active_indices.len())
+            .to_dense(eidos)
+            .map_err(embedding_error)
+    }
 
-        embedding
+    /// Decodes a dense CWM embedding back into a `DataEidos` with `active_bits`
+    /// active concepts out of `dimensionality`.
+    pub fn eidos_from_embedding(
+        &self,
+        embedding: &[f32],
+        dimensionality: u32,
+        active_bits: usize,
+    ) -> Result<DataEidos, PandoraError> {
+        self.sdr_projection(dimensionality, active_bits)
+            .to_sdr(embedding)
+            .map_err(embedding_error)
     }
 
     /// Updates existing nodes with new information from the flow
@@ -445,12 +492,23 @@ impl InterdependentCausalModel {
         &mut self.gnn
     }
 
+    /// Returns a reference to the forward state predictor
+    pub fn predictor(&self) -> &StatePredictor {
+        &self.predictor
+    }
+
+    /// Returns a mutable reference to the forward state predictor (e.g. to train it)
+    pub fn predictor_mut(&mut self) -> &mut StatePredictor {
+        &mut self.predictor
+    }
+
     /// Predicts the next state of the EpistemologicalFlow based on its current
     /// state and the `sankhara` (intent) it contains.
     ///
     /// This is the core of the forward model that enables Active Inference planning.
     /// The method uses the GNN to predict how the world state will change based
-    /// on the current state and the intended action.
+    /// on the current state and the intended action. The epistemic and aleatoric
+    /// uncertainty of the prediction is stored in `flow.uncertainty`.
     ///
     /// # Arguments
     ///
@@ -476,6 +534,7 @@ impl InterdependentCausalModel {
     ///
     /// // Predict the next state
     /// model.predict_next_state(&mut flow).unwrap();
+    /// assert!(flow.uncertainty.is_some());
     /// ```
     pub fn predict_next_state(&self, flow: &mut EpistemologicalFlow) -> Result<(), PandoraError> {
         // This is the core of the forward model.
@@ -484,7 +543,11 @@ impl InterdependentCausalModel {
 
         // 2. Use a predictive model (this could be a layer in your GNN or a separate NN)
         //    to predict the embedding of the *next* state.
-        let next_state_embedding = self.predictor.predict(&context_embedding)?;
+        let prediction = self
+            .predictor
+            .predict_with_uncertainty(&context_embedding)?;
+        flow.uncertainty = Some(prediction.summary());
+        let next_state_embedding = prediction.mean;
 
         // 3. Decode the next state embedding back into the `EpistemologicalFlow`.
         //    This is a complex step. For now, we can simplify: update the most
@@ -906,6 +969,11 @@ impl WorldModel for InterdependentCausalModel {
         // More connected graphs should have lower prediction error
         base_error * (1.0 - connectivity.min(1.0) * 0.5)
     }
+
+    /// Delegates to the GNN forward model (see the inherent `predict_next_state`).
+    fn predict_next_state(&self, flow: &mut EpistemologicalFlow) -> Result<(), PandoraError> {
+        InterdependentCausalModel::predict_next_state(self, flow)
+    }
 }
 
 #[cfg(test)]
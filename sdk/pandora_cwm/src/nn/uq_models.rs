@@ -1,33 +1,521 @@
-// Tạm thời cung cấp stub ML khi bật feature `ml` mà không cần phụ thuộc candle
-// để các crate khác có thể compile với `--all-features`.
+//! Mô hình dự báo có định lượng độ bất định (Uncertainty Quantification).
+//!
+//! Mỗi mạng cơ sở (`GaussianMlp`) dự báo một phân phối Gauss theo từng chiều: trung
+//! bình (dạng residual quanh đầu vào) và log-phương sai, huấn luyện bằng negative
+//! log-likelihood. Độ bất định được tách theo định luật phương sai toàn phần trên
+//! nhiều mẫu dự báo — các thành viên của `DeepEnsemble` hoặc các lần lấy mẫu mask
+//! của `McDropout`:
+//!
+//! - aleatoric = trung bình các phương sai dự báo,
+//! - epistemic = phương sai của các trung bình dự báo.
 
-#[cfg(feature = "ml")]
+use pandora_core::ontology::PredictiveUncertainty;
+use pandora_error::PandoraError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+
+/// Lỗi khi đầu vào, mục tiêu hoặc các mẫu dự báo không khớp kích thước của mô hình.
+fn shape_error(message: impl Into<String>) -> PandoraError {
+    PandoraError::InvalidSkillInput {
+        skill_name: "cwm_uncertainty".into(),
+        message: message.into(),
+    }
+}
+
+const LOG_VAR_MIN: f32 = -10.0;
+const LOG_VAR_MAX: f32 = 10.0;
+
+/// Đầu ra xác suất của một lần dự báo: phân phối Gauss độc lập theo từng chiều.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ProbabilisticOutput {
-    pub variance: Variance,
+    pub mean: Vec<f32>,
+    pub variance: Vec<f32>,
+}
+
+/// Dự báo kèm độ bất định đã được tách theo từng chiều.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UncertaintyEstimate {
+    pub mean: Vec<f32>,
+    pub epistemic: Vec<f32>,
+    pub aleatoric: Vec<f32>,
+}
+
+impl UncertaintyEstimate {
+    /// Gộp nhiều mẫu dự báo theo định luật phương sai toàn phần.
+    pub fn from_samples(samples: &[ProbabilisticOutput]) -> Result<Self, PandoraError> {
+        let first = samples
+            .first()
+            .ok_or_else(|| shape_error("Cannot estimate uncertainty from zero samples"))?;
+        let dims = first.mean.len();
+        if samples
+            .iter()
+            .any(|s| s.mean.len() != dims || s.variance.len() != dims)
+        {
+            return Err(shape_error("Prediction samples have mismatched dimensions"));
+        }
+        let n = samples.len() as f32;
+        let mut mean = vec![0.0; dims];
+        let mut aleatoric = vec![0.0; dims];
+        for s in samples {
+            for d in 0..dims {
+                mean[d] += s.mean[d] / n;
+                aleatoric[d] += s.variance[d] / n;
+            }
+        }
+        let mut epistemic = vec![0.0; dims];
+        for s in samples {
+            for d in 0..dims {
+                epistemic[d] += (s.mean[d] - mean[d]).powi(2) / n;
+            }
+        }
+        Ok(Self {
+            mean,
+            epistemic,
+            aleatoric,
+        })
+    }
+
+    /// Tổng phương sai theo từng chiều.
+    pub fn total_variance(&self) -> Vec<f32> {
+        self.epistemic
+            .iter()
+            .zip(&self.aleatoric)
+            .map(|(e, a)| e + a)
+            .collect()
+    }
+
+    /// Tóm tắt thành hai số (trung bình trên các chiều) để gắn vào `EpistemologicalFlow`.
+    pub fn summary(&self) -> PredictiveUncertainty {
+        let avg = |v: &[f32]| {
+            if v.is_empty() {
+                0.0
+            } else {
+                v.iter().sum::<f32>() / v.len() as f32
+            }
+        };
+        PredictiveUncertainty {
+            epistemic: avg(&self.epistemic),
+            aleatoric: avg(&self.aleatoric),
+        }
+    }
+}
+
+/// Một mô hình dự báo có thể ước lượng độ bất định và học trực tuyến.
+pub trait UncertaintyModel: Send + Sync {
+    fn estimate(&self, input: &[f32]) -> Result<UncertaintyEstimate, PandoraError>;
+
+    /// Một bước SGD trên cặp `(input, target)`; trả về NLL trung bình.
+    fn train_step(&mut self, input: &[f32], target: &[f32], lr: f32) -> Result<f32, PandoraError>;
+}
+
+/// MLP một tầng ẩn (tanh) với hai đầu ra: trung bình residual và log-phương sai.
+///
+/// `mean_k = x_k + (W_m·h + b_m)_k` (với `x_k = 0` khi `k` vượt quá số chiều đầu vào),
+/// nên mạng chưa huấn luyện dự báo "trạng thái không đổi".
+#[derive(Debug, Clone)]
+pub struct GaussianMlp {
+    input_dims: usize,
+    hidden_dims: usize,
+    output_dims: usize,
+    w1: Vec<f32>,
+    b1: Vec<f32>,
+    w_mean: Vec<f32>,
+    b_mean: Vec<f32>,
+    w_logvar: Vec<f32>,
+    b_logvar: Vec<f32>,
+}
+
+/// Kết quả lan truyền xuôi, giữ lại tầng ẩn để lan truyền ngược.
+struct ForwardPass {
+    hidden: Vec<f32>,
+    mean: Vec<f32>,
+    log_var: Vec<f32>,
+}
+
+impl GaussianMlp {
+    pub fn new(input_dims: usize, hidden_dims: usize, output_dims: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut init = |fan_in: usize, len: usize, scale: f32| -> Vec<f32> {
+            let bound = scale / (fan_in.max(1) as f32).sqrt();
+            (0..len).map(|_| rng.gen_range(-bound..bound)).collect()
+        };
+        let w1 = init(input_dims, hidden_dims * input_dims, 1.0);
+        let b1 = init(input_dims, hidden_dims, 1.0);
+        // Đầu ra khởi tạo nhỏ để dự báo ban đầu gần với đầu vào, nhưng vẫn khác nhau
+        // giữa các thành viên ensemble.
+        let w_mean = init(hidden_dims, output_dims * hidden_dims, 0.1);
+        let b_mean = init(hidden_dims, output_dims, 0.1);
+        let w_logvar = init(hidden_dims, output_dims * hidden_dims, 0.1);
+        Self {
+            input_dims,
+            hidden_dims,
+            output_dims,
+            w1,
+            b1,
+            w_mean,
+            b_mean,
+            w_logvar,
+            b_logvar: vec![(0.01f32).ln(); output_dims],
+        }
+    }
+
+    pub fn input_dims(&self) -> usize {
+        self.input_dims
+    }
+
+    pub fn output_dims(&self) -> usize {
+        self.output_dims
+    }
+
+    fn check_input(&self, input: &[f32]) -> Result<(), PandoraError> {
+        if input.len() != self.input_dims {
+            return Err(shape_error(format!(
+                "Expected input of length {}, got {}",
+                self.input_dims,
+                input.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Lan truyền xuôi; `mask` (nếu có) nhân vào tầng ẩn (inverted dropout).
+    fn forward_pass(&self, input: &[f32], mask: Option<&[f32]>) -> ForwardPass {
+        let hidden: Vec<f32> = (0..self.hidden_dims)
+            .map(|j| {
+                let row = &self.w1[j * self.input_dims..(j + 1) * self.input_dims];
+                let z = row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>() + self.b1[j];
+                z.tanh() * mask.map_or(1.0, |m| m[j])
+            })
+            .collect();
+        let head = |w: &[f32], b: &[f32], k: usize| {
+            let row = &w[k * self.hidden_dims..(k + 1) * self.hidden_dims];
+            row.iter().zip(&hidden).map(|(w, h)| w * h).sum::<f32>() + b[k]
+        };
+        let mean = (0..self.output_dims)
+            .map(|k| input.get(k).copied().unwrap_or(0.0) + head(&self.w_mean, &self.b_mean, k))
+            .collect();
+        let log_var = (0..self.output_dims)
+            .map(|k| head(&self.w_logvar, &self.b_logvar, k).clamp(LOG_VAR_MIN, LOG_VAR_MAX))
+            .collect();
+        ForwardPass {
+            hidden,
+            mean,
+            log_var,
+        }
+    }
+
+    fn forward_with_mask(
+        &self,
+        input: &[f32],
+        mask: Option<&[f32]>,
+    ) -> Result<ProbabilisticOutput, PandoraError> {
+        self.check_input(input)?;
+        let pass = self.forward_pass(input, mask);
+        Ok(ProbabilisticOutput {
+            mean: pass.mean,
+            variance: pass.log_var.iter().map(|s| s.exp()).collect(),
+        })
+    }
+
+    pub fn forward(&self, input: &[f32]) -> Result<ProbabilisticOutput, PandoraError> {
+        self.forward_with_mask(input, None)
+    }
+
+    /// Một bước SGD trên Gaussian NLL `0.5·(s + (y - μ)²·e^{-s})`, trả về NLL trung bình.
+    fn train_with_mask(
+        &mut self,
+        input: &[f32],
+        target: &[f32],
+        lr: f32,
+        mask: Option<&[f32]>,
+    ) -> Result<f32, PandoraError> {
+        self.check_input(input)?;
+        if target.len() != self.output_dims {
+            return Err(shape_error(format!(
+                "Expected target of length {}, got {}",
+                self.output_dims,
+                target.len()
+            )));
+        }
+        let pass = self.forward_pass(input, mask);
+        let n = self.output_dims.max(1) as f32;
+        let mut loss = 0.0;
+        let mut grad_hidden = vec![0.0f32; self.hidden_dims];
+        for (k, &y) in target.iter().enumerate() {
+            let log_var = pass.log_var[k];
+            let inv_var = (-log_var).exp();
+            let err = pass.mean[k] - y;
+            loss += 0.5 * (log_var + err * err * inv_var) / n;
+
+            let g_mean = err * inv_var / n;
+            let clamped = log_var <= LOG_VAR_MIN || log_var >= LOG_VAR_MAX;
+            let g_logvar = if clamped {
+                0.0
+            } else {
+                0.5 * (1.0 - err * err * inv_var) / n
+            };
+            let rows = k * self.hidden_dims..(k + 1) * self.hidden_dims;
+            for (j, g) in grad_hidden.iter_mut().enumerate() {
+                *g +=
+                    g_mean * self.w_mean[rows.start + j] + g_logvar * self.w_logvar[rows.start + j];
+            }
+            for (w, h) in self.w_mean[rows.clone()].iter_mut().zip(&pass.hidden) {
+                *w -= lr * g_mean * h;
+            }
+            for (w, h) in self.w_logvar[rows].iter_mut().zip(&pass.hidden) {
+                *w -= lr * g_logvar * h;
+            }
+            self.b_mean[k] -= lr * g_mean;
+            self.b_logvar[k] -= lr * g_logvar;
+        }
+        for j in 0..self.hidden_dims {
+            let keep = mask.map_or(1.0, |m| m[j]);
+            if keep == 0.0 {
+                continue;
+            }
+            // h = tanh(z)·keep  =>  dh/dz = (1 - tanh²)·keep
+            let t = pass.hidden[j] / keep;
+            let g_z = grad_hidden[j] * (1.0 - t * t) * keep;
+            let row = j * self.input_dims..(j + 1) * self.input_dims;
+            for (w, x) in self.w1[row].iter_mut().zip(input) {
+                *w -= lr * g_z * x;
+            }
+            self.b1[j] -= lr * g_z;
+        }
+        Ok(loss)
+    }
+
+    pub fn train_step(
+        &mut self,
+        input: &[f32],
+        target: &[f32],
+        lr: f32,
+    ) -> Result<f32, PandoraError> {
+        self.train_with_mask(input, target, lr, None)
+    }
+}
+
+/// Deep ensemble: nhiều `GaussianMlp` khởi tạo khác nhau, huấn luyện độc lập.
+#[derive(Debug, Clone)]
+pub struct DeepEnsemble {
+    members: Vec<GaussianMlp>,
 }
 
-#[cfg(feature = "ml")]
-impl ProbabilisticOutput {
-    pub fn new(variance: f32) -> Self {
+impl DeepEnsemble {
+    pub fn new(
+        input_dims: usize,
+        hidden_dims: usize,
+        output_dims: usize,
+        members: usize,
+        seed: u64,
+    ) -> Self {
         Self {
-            variance: Variance(variance),
+            members: (0..members.max(1) as u64)
+                .map(|m| {
+                    GaussianMlp::new(input_dims, hidden_dims, output_dims, seed.wrapping_add(m))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn members(&self) -> &[GaussianMlp] {
+        &self.members
+    }
+}
+
+impl UncertaintyModel for DeepEnsemble {
+    fn estimate(&self, input: &[f32]) -> Result<UncertaintyEstimate, PandoraError> {
+        let samples = self
+            .members
+            .iter()
+            .map(|m| m.forward(input))
+            .collect::<Result<Vec<_>, _>>()?;
+        UncertaintyEstimate::from_samples(&samples)
+    }
+
+    fn train_step(&mut self, input: &[f32], target: &[f32], lr: f32) -> Result<f32, PandoraError> {
+        let mut loss = 0.0;
+        for member in &mut self.members {
+            loss += member.train_step(input, target, lr)?;
         }
+        Ok(loss / self.members.len() as f32)
     }
 }
 
-#[cfg(feature = "ml")]
-pub struct Variance(pub f32);
+/// MC dropout: một mạng duy nhất, giữ dropout khi suy luận và lấy `samples` mẫu.
+///
+/// Mask dropout lấy từ một `StdRng` gieo bằng `seed`, nên cùng chuỗi lời gọi cho
+/// cùng kết quả.
+#[derive(Debug)]
+pub struct McDropout {
+    net: GaussianMlp,
+    dropout: f32,
+    samples: usize,
+    rng: Mutex<StdRng>,
+}
+
+impl McDropout {
+    pub fn new(net: GaussianMlp, dropout: f32, samples: usize, seed: u64) -> Self {
+        Self {
+            net,
+            dropout: dropout.clamp(0.0, 0.95),
+            samples: samples.max(1),
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+
+    pub fn network(&self) -> &GaussianMlp {
+        &self.net
+    }
+
+    fn sample_mask<R: Rng + ?Sized>(dropout: f32, hidden_dims: usize, rng: &mut R) -> Vec<f32> {
+        let keep = 1.0 - dropout;
+        (0..hidden_dims)
+            .map(|_| {
+                if rng.gen::<f32>() < keep {
+                    1.0 / keep
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
 
-#[cfg(feature = "ml")]
-impl Variance {
-    pub fn mean_all(&self) -> Result<Variance, pandora_error::PandoraError> {
-        Ok(Variance(self.0))
+    /// Như `estimate` nhưng dùng nguồn ngẫu nhiên cho trước (tái lập được).
+    pub fn estimate_with_rng<R: Rng + ?Sized>(
+        &self,
+        input: &[f32],
+        rng: &mut R,
+    ) -> Result<UncertaintyEstimate, PandoraError> {
+        let samples = (0..self.samples)
+            .map(|_| {
+                let mask = Self::sample_mask(self.dropout, self.net.hidden_dims, rng);
+                self.net.forward_with_mask(input, Some(&mask))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        UncertaintyEstimate::from_samples(&samples)
     }
 
-    pub fn to_scalar<T>(&self) -> Result<T, pandora_error::PandoraError>
-    where
-        T: Default + Copy + std::fmt::Debug,
-    {
-        Ok(T::default())
+    pub fn train_step_with_rng<R: Rng + ?Sized>(
+        &mut self,
+        input: &[f32],
+        target: &[f32],
+        lr: f32,
+        rng: &mut R,
+    ) -> Result<f32, PandoraError> {
+        let mask = Self::sample_mask(self.dropout, self.net.hidden_dims, rng);
+        self.net.train_with_mask(input, target, lr, Some(&mask))
+    }
+}
+
+impl Clone for McDropout {
+    fn clone(&self) -> Self {
+        let rng = self.rng.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        Self {
+            net: self.net.clone(),
+            dropout: self.dropout,
+            samples: self.samples,
+            rng: Mutex::new(rng),
+        }
+    }
+}
+
+impl UncertaintyModel for McDropout {
+    fn estimate(&self, input: &[f32]) -> Result<UncertaintyEstimate, PandoraError> {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        self.estimate_with_rng(input, &mut *rng)
+    }
+
+    fn train_step(&mut self, input: &[f32], target: &[f32], lr: f32) -> Result<f32, PandoraError> {
+        let rng = self.rng.get_mut().unwrap_or_else(|e| e.into_inner());
+        let mask = Self::sample_mask(self.dropout, self.net.hidden_dims, rng);
+        self.net.train_with_mask(input, target, lr, Some(&mask))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_total_variance_decomposition() {
+        let samples = vec![
+            ProbabilisticOutput {
+                mean: vec![1.0, 0.0],
+                variance: vec![0.5, 0.1],
+            },
+            ProbabilisticOutput {
+                mean: vec![3.0, 0.0],
+                variance: vec![1.5, 0.3],
+            },
+        ];
+        let estimate = UncertaintyEstimate::from_samples(&samples).unwrap();
+        assert_eq!(estimate.mean, vec![2.0, 0.0]);
+        assert_eq!(estimate.epistemic, vec![1.0, 0.0]);
+        assert_eq!(estimate.aleatoric, vec![1.0, 0.2]);
+        let summary = estimate.summary();
+        assert!((summary.epistemic - 0.5).abs() < 1e-6);
+        assert!((summary.aleatoric - 0.6).abs() < 1e-6);
+        assert!(matches!(
+            UncertaintyEstimate::from_samples(&[]),
+            Err(PandoraError::InvalidSkillInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ensemble_learns_noise_and_reduces_epistemic() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut ensemble = DeepEnsemble::new(2, 16, 2, 5, 3);
+        let input = [0.5, -0.5];
+        let before = ensemble.estimate(&input).unwrap().summary();
+
+        // Mục tiêu: chiều 0 tất định, chiều 1 nhiễu với phương sai 0.25.
+        for _ in 0..3000 {
+            let noise: f32 = if rng.gen::<bool>() { 0.5 } else { -0.5 };
+            let target = [1.0, noise];
+            ensemble.train_step(&input, &target, 0.01).unwrap();
+        }
+        let after = ensemble.estimate(&input).unwrap();
+        assert!((after.mean[0] - 1.0).abs() < 0.1, "{:?}", after.mean);
+        assert!(after.aleatoric[1] > after.aleatoric[0]);
+        assert!(
+            (after.aleatoric[1] - 0.25).abs() < 0.1,
+            "{:?}",
+            after.aleatoric
+        );
+
+        // Xa dữ liệu huấn luyện, các thành viên bất đồng nhiều hơn.
+        let far = ensemble.estimate(&[5.0, 5.0]).unwrap().summary();
+        assert!(far.epistemic > after.summary().epistemic);
+        assert!(before.total() > 0.0);
+    }
+
+    #[test]
+    fn test_mc_dropout_is_reproducible_and_dimension_checked() {
+        let model = McDropout::new(GaussianMlp::new(3, 8, 3, 1), 0.3, 20, 7);
+        let a = model
+            .estimate_with_rng(&[0.1, 0.2, 0.3], &mut StdRng::seed_from_u64(5))
+            .unwrap();
+        let b = model
+            .estimate_with_rng(&[0.1, 0.2, 0.3], &mut StdRng::seed_from_u64(5))
+            .unwrap();
+        assert_eq!(a, b);
+        assert!(a.epistemic.iter().any(|&e| e > 0.0));
+        assert!(matches!(
+            model.estimate(&[0.0; 2]),
+            Err(PandoraError::InvalidSkillInput { .. })
+        ));
+
+        // RNG gieo sẵn: hai mô hình cùng seed cho cùng chuỗi ước lượng.
+        let twin = McDropout::new(GaussianMlp::new(3, 8, 3, 1), 0.3, 20, 7);
+        let model = McDropout::new(GaussianMlp::new(3, 8, 3, 1), 0.3, 20, 7);
+        for _ in 0..2 {
+            assert_eq!(
+                model.estimate(&[0.1, 0.2, 0.3]).unwrap(),
+                twin.estimate(&[0.1, 0.2, 0.3]).unwrap()
+            );
+        }
     }
 }
@@ -0,0 +1,225 @@
+//! Binary Spatter Codes (BSC): biến thể VSA nhị phân cho thiết bị biên.
+//!
+//! Vector được đóng gói thành các từ `u64`: `bind` là XOR (tự nghịch đảo, nên
+//! `unbind == bind`), `bundle` là bỏ phiếu đa số theo từng bit và độ tương đồng
+//! tính từ khoảng cách Hamming. Không cần FFT hay số thực.
+
+use pandora_error::PandoraError;
+use rand::Rng;
+
+/// Một hypervector nhị phân `dim` bit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BinaryHypervector {
+    words: Vec<u64>,
+    dim: usize,
+}
+
+fn invalid_input(message: impl Into<String>) -> PandoraError {
+    PandoraError::InvalidSkillInput {
+        skill_name: "vsa_bsc".into(),
+        message: message.into(),
+    }
+}
+
+impl BinaryHypervector {
+    /// Vector toàn bit 0 (phần tử đơn vị của `bind`).
+    pub fn zeros(dim: usize) -> Self {
+        Self {
+            words: vec![0; dim.div_ceil(64)],
+            dim,
+        }
+    }
+
+    /// Vector ngẫu nhiên với mỗi bit là 1 với xác suất 1/2.
+    pub fn random<R: Rng + ?Sized>(dim: usize, rng: &mut R) -> Self {
+        let mut v = Self {
+            words: (0..dim.div_ceil(64)).map(|_| rng.gen()).collect(),
+            dim,
+        };
+        v.mask_tail();
+        v
+    }
+
+    pub fn from_bits(bits: &[bool]) -> Self {
+        let mut v = Self::zeros(bits.len());
+        for (i, &b) in bits.iter().enumerate() {
+            v.set(i, b);
+        }
+        v
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        index < self.dim && (self.words[index / 64] >> (index % 64)) & 1 == 1
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        if index >= self.dim {
+            return;
+        }
+        let mask = 1u64 << (index % 64);
+        if value {
+            self.words[index / 64] |= mask;
+        } else {
+            self.words[index / 64] &= !mask;
+        }
+    }
+
+    /// Xóa các bit thừa ở từ cuối để phép so sánh và đếm bit luôn chính xác.
+    fn mask_tail(&mut self) {
+        let rem = self.dim % 64;
+        if rem != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1u64 << rem) - 1;
+            }
+        }
+    }
+
+    fn check_dimension(&self, other: &Self) -> Result<(), PandoraError> {
+        if self.dim != other.dim {
+            return Err(invalid_input(format!(
+                "Vector dimension mismatch: {} vs {}",
+                self.dim, other.dim
+            )));
+        }
+        Ok(())
+    }
+
+    /// Ràng buộc bằng XOR. Tự nghịch đảo: `a.bind(b).bind(b) == a`.
+    pub fn bind(&self, other: &Self) -> Result<Self, PandoraError> {
+        self.check_dimension(other)?;
+        Ok(Self {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a ^ b)
+                .collect(),
+            dim: self.dim,
+        })
+    }
+
+    /// Với BSC, `unbind` trùng với `bind`.
+    pub fn unbind(&self, key: &Self) -> Result<Self, PandoraError> {
+        self.bind(key)
+    }
+
+    /// Gộp bằng bỏ phiếu đa số theo từng bit. Khi hòa (số vector chẵn), bit được
+    /// quyết định bởi `tie_breaker` để kết quả vẫn tất định.
+    pub fn bundle(vectors: &[Self], tie_breaker: Option<&Self>) -> Result<Self, PandoraError> {
+        let first = vectors
+            .first()
+            .ok_or_else(|| invalid_input("Cannot bundle empty vector list"))?;
+        for v in vectors {
+            first.check_dimension(v)?;
+        }
+        if let Some(t) = tie_breaker {
+            first.check_dimension(t)?;
+        }
+        let mut out = Self::zeros(first.dim);
+        let half = vectors.len() as f64 / 2.0;
+        for i in 0..first.dim {
+            let ones = vectors.iter().filter(|v| v.get(i)).count() as f64;
+            let bit = if ones > half {
+                true
+            } else if ones < half {
+                false
+            } else {
+                tie_breaker.is_some_and(|t| t.get(i))
+            };
+            out.set(i, bit);
+        }
+        Ok(out)
+    }
+
+    /// Hoán vị vòng `shift` bit sang phải (mã hóa vị trí).
+    pub fn permute(&self, shift: usize) -> Self {
+        if self.dim == 0 {
+            return self.clone();
+        }
+        let k = shift % self.dim;
+        let mut out = Self::zeros(self.dim);
+        for i in 0..self.dim {
+            out.set((i + k) % self.dim, self.get(i));
+        }
+        out
+    }
+
+    pub fn inverse_permute(&self, shift: usize) -> Self {
+        if self.dim == 0 {
+            return self.clone();
+        }
+        self.permute(self.dim - shift % self.dim)
+    }
+
+    /// Khoảng cách Hamming (số bit khác nhau).
+    pub fn hamming(&self, other: &Self) -> usize {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .map(|(a, b)| (a ^ b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Độ tương đồng `1 - 2·hamming/dim` trong [-1, 1]; 0 với hai vector ngẫu nhiên độc lập.
+    pub fn similarity(&self, other: &Self) -> f64 {
+        if self.dim != other.dim || self.dim == 0 {
+            return 0.0;
+        }
+        1.0 - 2.0 * self.hamming(other) as f64 / self.dim as f64
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_bind_is_self_inverse() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let a = BinaryHypervector::random(1000, &mut rng);
+        let b = BinaryHypervector::random(1000, &mut rng);
+        assert_eq!(a.bind(&b).unwrap().unbind(&b).unwrap(), a);
+    }
+
+    #[test]
+    fn test_random_vectors_are_dissimilar() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        let a = BinaryHypervector::random(4096, &mut rng);
+        let b = BinaryHypervector::random(4096, &mut rng);
+        assert!(a.similarity(&b).abs() < 0.1);
+        assert_eq!(a.similarity(&a), 1.0);
+    }
+
+    #[test]
+    fn test_bundle_is_similar_to_members() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let items: Vec<_> = (0..3)
+            .map(|_| BinaryHypervector::random(2048, &mut rng))
+            .collect();
+        let bundled = BinaryHypervector::bundle(&items, None).unwrap();
+        for item in &items {
+            assert!(bundled.similarity(item) > 0.3);
+        }
+    }
+
+    #[test]
+    fn test_permute_roundtrip() {
+        let v = BinaryHypervector::from_bits(&[true, false, false, true, true]);
+        assert!(v.permute(1).get(1));
+        assert_eq!(v.permute(3).inverse_permute(3), v);
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        let a = BinaryHypervector::zeros(10);
+        let b = BinaryHypervector::zeros(11);
+        assert!(a.bind(&b).is_err());
+        assert!(BinaryHypervector::bundle(&[], None).is_err());
+    }
+}
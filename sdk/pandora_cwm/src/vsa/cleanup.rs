@@ -0,0 +1,221 @@
+//! Bộ nhớ dọn dẹp (cleanup / item memory) cho VSA.
+//!
+//! Các phép `unbind` và truy vấn chuỗi chỉ cho ra vector xấp xỉ. `CleanupMemory`
+//! lưu các ký hiệu "sạch" và đưa một vector nhiễu về ký hiệu gần nhất theo độ tương đồng.
+
+use super::bsc::BinaryHypervector;
+use super::hrr;
+use pandora_error::PandoraError;
+
+/// Một vector có thể so sánh độ tương đồng trong không gian VSA.
+pub trait Hypervector: Clone {
+    fn dimension(&self) -> usize;
+
+    /// Độ tương đồng trong khoảng [-1, 1]; 1 nghĩa là trùng khớp.
+    /// Hai vector khác chiều có độ tương đồng 0; `CleanupMemory` kiểm tra chiều trước khi so.
+    fn similarity(&self, other: &Self) -> f64;
+}
+
+impl Hypervector for Vec<f64> {
+    fn dimension(&self) -> usize {
+        self.len()
+    }
+
+    fn similarity(&self, other: &Self) -> f64 {
+        hrr::cosine_similarity(self, other).unwrap_or(0.0)
+    }
+}
+
+impl Hypervector for BinaryHypervector {
+    fn dimension(&self) -> usize {
+        self.dim()
+    }
+
+    fn similarity(&self, other: &Self) -> f64 {
+        BinaryHypervector::similarity(self, other)
+    }
+}
+
+/// Bộ nhớ ký hiệu: ánh xạ tên -> vector, hỗ trợ tra cứu ký hiệu gần nhất.
+#[derive(Debug, Clone)]
+pub struct CleanupMemory<V: Hypervector> {
+    dim: usize,
+    items: Vec<(String, V)>,
+}
+
+impl<V: Hypervector> CleanupMemory<V> {
+    pub fn new(dim: usize) -> Self {
+        Self {
+            dim,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Thêm (hoặc thay thế) một ký hiệu.
+    pub fn insert(&mut self, name: impl Into<String>, vector: V) -> Result<(), PandoraError> {
+        self.check_dimension(&vector)?;
+        self.upsert(name.into(), vector);
+        Ok(())
+    }
+
+    fn check_dimension(&self, vector: &V) -> Result<(), PandoraError> {
+        if vector.dimension() != self.dim {
+            return Err(PandoraError::InvalidSkillInput {
+                skill_name: "vsa_cleanup".into(),
+                message: format!(
+                    "Vector dimension mismatch: expected {}, got {}",
+                    self.dim,
+                    vector.dimension()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    fn upsert(&mut self, name: String, vector: V) {
+        match self.items.iter_mut().find(|(n, _)| *n == name) {
+            Some(slot) => slot.1 = vector,
+            None => self.items.push((name, vector)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&V> {
+        self.items.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Trả về ký hiệu gần nhất cùng độ tương đồng, hoặc `None` nếu bộ nhớ rỗng.
+    /// Lỗi nếu `query` khác chiều với bộ nhớ.
+    pub fn cleanup(&self, query: &V) -> Result<Option<(&str, &V, f64)>, PandoraError> {
+        self.check_dimension(query)?;
+        Ok(self
+            .items
+            .iter()
+            .map(|(name, v)| (name.as_str(), v, v.similarity(query)))
+            .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal)))
+    }
+
+    /// Như `cleanup` nhưng chỉ chấp nhận kết quả có độ tương đồng ≥ `threshold`.
+    pub fn cleanup_with_threshold(
+        &self,
+        query: &V,
+        threshold: f64,
+    ) -> Result<Option<(&str, &V, f64)>, PandoraError> {
+        Ok(self.cleanup(query)?.filter(|(_, _, s)| *s >= threshold))
+    }
+
+    /// `k` ký hiệu gần nhất, sắp xếp theo độ tương đồng giảm dần.
+    pub fn top_k(&self, query: &V, k: usize) -> Result<Vec<(&str, f64)>, PandoraError> {
+        self.check_dimension(query)?;
+        let mut scored: Vec<(&str, f64)> = self
+            .items
+            .iter()
+            .map(|(name, v)| (name.as_str(), v.similarity(query)))
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(k);
+        Ok(scored)
+    }
+}
+
+impl CleanupMemory<Vec<f64>> {
+    /// Sinh và lưu một ký hiệu HRR ngẫu nhiên mới, trả về bản sao của vector.
+    pub fn insert_random<R: rand::Rng + ?Sized>(
+        &mut self,
+        name: impl Into<String>,
+        rng: &mut R,
+    ) -> Vec<f64> {
+        let vector = hrr::random_vector(self.dim, rng);
+        self.upsert(name.into(), vector.clone());
+        vector
+    }
+}
+
+impl CleanupMemory<BinaryHypervector> {
+    /// Sinh và lưu một ký hiệu nhị phân ngẫu nhiên mới, trả về bản sao của vector.
+    pub fn insert_random<R: rand::Rng + ?Sized>(
+        &mut self,
+        name: impl Into<String>,
+        rng: &mut R,
+    ) -> BinaryHypervector {
+        let vector = BinaryHypervector::random(self.dim, rng);
+        self.upsert(name.into(), vector.clone());
+        vector
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_cleanup_snaps_noisy_vector() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let mut memory = CleanupMemory::<Vec<f64>>::new(512);
+        let apple = memory.insert_random("apple", &mut rng);
+        memory.insert_random("pear", &mut rng);
+        memory.insert_random("plum", &mut rng);
+
+        let noise = hrr::random_vector(512, &mut rng);
+        let noisy: Vec<f64> = apple.iter().zip(&noise).map(|(a, n)| a + 0.8 * n).collect();
+        let (name, _, similarity) = memory.cleanup(&noisy).unwrap().unwrap();
+        assert_eq!(name, "apple");
+        assert!(similarity > 0.5);
+    }
+
+    #[test]
+    fn test_threshold_rejects_unknown() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        let mut memory = CleanupMemory::<Vec<f64>>::new(1024);
+        memory.insert_random("a", &mut rng);
+        let unknown = hrr::random_vector(1024, &mut rng);
+        assert!(memory
+            .cleanup_with_threshold(&unknown, 0.3)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_insert_rejects_wrong_dimension() {
+        let mut memory = CleanupMemory::<Vec<f64>>::new(4);
+        assert!(memory.insert("x", vec![1.0, 2.0]).is_err());
+        memory.insert("x", vec![1.0, 0.0, 0.0, 0.0]).unwrap();
+        memory.insert("x", vec![0.0, 1.0, 0.0, 0.0]).unwrap();
+        assert_eq!(memory.len(), 1);
+        assert_eq!(memory.get("x").unwrap()[1], 1.0);
+    }
+
+    #[test]
+    fn test_queries_reject_wrong_dimension() {
+        let mut memory = CleanupMemory::<Vec<f64>>::new(4);
+        memory.insert("x", vec![1.0, 0.0, 0.0, 0.0]).unwrap();
+        let short = vec![1.0, 0.0];
+        assert!(memory.cleanup(&short).is_err());
+        assert!(memory.cleanup_with_threshold(&short, -1.0).is_err());
+        assert!(memory.top_k(&short, 1).is_err());
+        assert_eq!(
+            memory.top_k(&vec![0.0, 1.0, 0.0, 0.0], 5).unwrap(),
+            vec![("x", 0.0)]
+        );
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(9);
+        let mut binary = CleanupMemory::<BinaryHypervector>::new(64);
+        binary.insert_random("y", &mut rng);
+        assert!(binary
+            .cleanup(&BinaryHypervector::random(32, &mut rng))
+            .is_err());
+    }
+}
@@ -1,7 +1,43 @@
 use num_complex::Complex;
 use pandora_error::PandoraError;
+use rand::Rng;
 use rustfft::FftPlanner;
 
+fn invalid_input(message: impl Into<String>) -> PandoraError {
+    PandoraError::InvalidSkillInput {
+        skill_name: "vsa_hrr".into(),
+        message: message.into(),
+    }
+}
+
+fn check_same_dimension(x: &[f64], y: &[f64]) -> Result<(), PandoraError> {
+    if x.len() != y.len() {
+        return Err(invalid_input(format!(
+            "Vector dimension mismatch: x.len()={}, y.len()={}",
+            x.len(),
+            y.len()
+        )));
+    }
+    if x.is_empty() {
+        return Err(invalid_input("Vectors must not be empty"));
+    }
+    Ok(())
+}
+
+/// Sinh một vector HRR ngẫu nhiên với các phần tử i.i.d. N(0, 1/n),
+/// nên chuẩn của vector xấp xỉ 1.
+pub fn random_vector<R: Rng + ?Sized>(dim: usize, rng: &mut R) -> Vec<f64> {
+    let std_dev = 1.0 / (dim.max(1) as f64).sqrt();
+    (0..dim)
+        .map(|_| {
+            // Box–Muller
+            let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+            let u2: f64 = rng.gen();
+            (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos() * std_dev
+        })
+        .collect()
+}
+
 /// Thực hiện phép toán "ràng buộc" (binding) hai vector bằng phép chập tròn (circular convolution).
 /// Tương đương với phép nhân element-wise trong miền tần số.
 /// Bind two vectors using circular convolution (element-wise multiplication in frequency domain)
@@ -85,6 +121,82 @@ pub fn bundle(vectors: &[Vec<f64>]) -> Result<Vec<f64>, PandoraError> {
     Ok(sum)
 }
 
+/// Phép "đối hợp" (involution) `x*[i] = x[-i mod n]`, nghịch đảo xấp xỉ của `bind`.
+pub fn involution(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    (0..n).map(|i| x[(n - i) % n]).collect()
+}
+
+/// "Tháo ràng buộc" (unbind): khôi phục xấp xỉ `y` từ `bind(x, y)` khi biết `x`.
+/// Kết quả có nhiễu; dùng `CleanupMemory` để đưa về ký hiệu gần nhất.
+pub fn unbind(bound: &[f64], key: &[f64]) -> Result<Vec<f64>, PandoraError> {
+    bind(bound, &involution(key))
+}
+
+/// Độ tương đồng cosine giữa hai vector. Trả về 0 nếu một trong hai vector bằng 0.
+pub fn cosine_similarity(x: &[f64], y: &[f64]) -> Result<f64, PandoraError> {
+    check_same_dimension(x, y)?;
+    let dot: f64 = x.iter().zip(y).map(|(a, b)| a * b).sum();
+    let norm_x = x.iter().map(|a| a * a).sum::<f64>().sqrt();
+    let norm_y = y.iter().map(|b| b * b).sum::<f64>().sqrt();
+    if norm_x == 0.0 || norm_y == 0.0 {
+        return Ok(0.0);
+    }
+    Ok(dot / (norm_x * norm_y))
+}
+
+/// Hoán vị vòng (dịch phải `shift` vị trí), dùng để mã hóa vị trí trong chuỗi.
+pub fn permute(x: &[f64], shift: usize) -> Vec<f64> {
+    let mut out = x.to_vec();
+    if !out.is_empty() {
+        let k = shift % out.len();
+        out.rotate_right(k);
+    }
+    out
+}
+
+/// Nghịch đảo chính xác của `permute`.
+pub fn inverse_permute(x: &[f64], shift: usize) -> Vec<f64> {
+    let mut out = x.to_vec();
+    if !out.is_empty() {
+        let k = shift % out.len();
+        out.rotate_left(k);
+    }
+    out
+}
+
+/// Mã hóa một chuỗi: `Σ_i permute(item_i, i)`.
+pub fn encode_sequence(items: &[Vec<f64>]) -> Result<Vec<f64>, PandoraError> {
+    let shifted: Vec<Vec<f64>> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| permute(item, i))
+        .collect();
+    bundle(&shifted)
+}
+
+/// Lấy ra (có nhiễu) phần tử ở vị trí `position` của một chuỗi đã mã hóa.
+pub fn decode_sequence_position(sequence: &[f64], position: usize) -> Vec<f64> {
+    inverse_permute(sequence, position)
+}
+
+/// Mã hóa một bản ghi vai trò–giá trị (role–filler): `Σ bind(role, filler)`.
+pub fn encode_record(pairs: &[(&[f64], &[f64])]) -> Result<Vec<f64>, PandoraError> {
+    if pairs.is_empty() {
+        return Err(invalid_input("Cannot encode an empty record"));
+    }
+    let bound = pairs
+        .iter()
+        .map(|(role, filler)| bind(role, filler))
+        .collect::<Result<Vec<_>, _>>()?;
+    bundle(&bound)
+}
+
+/// Truy vấn (có nhiễu) giá trị gắn với `role` trong một bản ghi đã mã hóa.
+pub fn decode_record(record: &[f64], role: &[f64]) -> Result<Vec<f64>, PandoraError> {
+    unbind(record, role)
+}
+
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod tests {
@@ -136,4 +248,46 @@ mod tests {
         let result = bundle(&[vec_a, vec_b]);
         assert!(result.is_err());
     }
+
+    fn rng() -> rand::rngs::StdRng {
+        use rand::SeedableRng;
+        rand::rngs::StdRng::seed_from_u64(42)
+    }
+
+    #[test]
+    fn test_unbind_recovers_filler() {
+        let mut rng = rng();
+        let role = random_vector(1024, &mut rng);
+        let filler = random_vector(1024, &mut rng);
+        let bound = bind(&role, &filler).unwrap();
+        let recovered = unbind(&bound, &role).unwrap();
+        assert!(cosine_similarity(&recovered, &filler).unwrap() > 0.6);
+    }
+
+    #[test]
+    fn test_random_vectors_are_quasi_orthogonal() {
+        let mut rng = rng();
+        let a = random_vector(2048, &mut rng);
+        let b = random_vector(2048, &mut rng);
+        assert!(cosine_similarity(&a, &b).unwrap().abs() < 0.1);
+        assert!((cosine_similarity(&a, &a).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_permute_roundtrip() {
+        let v = vec![1.0, 2.0, 3.0, 4.0];
+        assert_eq!(permute(&v, 1), vec![4.0, 1.0, 2.0, 3.0]);
+        assert_eq!(inverse_permute(&permute(&v, 7), 7), v);
+    }
+
+    #[test]
+    fn test_involution() {
+        assert_eq!(involution(&[1.0, 2.0, 3.0, 4.0]), vec![1.0, 4.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_cosine_similarity_zero_vector() {
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]).unwrap(), 0.0);
+        assert!(cosine_similarity(&[1.0], &[1.0, 2.0]).is_err());
+    }
 }
@@ -1 +1,3 @@
+pub mod bsc;
+pub mod cleanup;
 pub mod hrr;
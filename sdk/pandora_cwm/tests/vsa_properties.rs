@@ -1,5 +1,12 @@
-use pandora_cwm::vsa::hrr::{bind, bundle};
+use pandora_cwm::vsa::bsc::BinaryHypervector;
+use pandora_cwm::vsa::cleanup::CleanupMemory;
+use pandora_cwm::vsa::hrr::{
+    bind, bundle, cosine_similarity, decode_record, decode_sequence_position, encode_record,
+    encode_sequence, inverse_permute, permute, random_vector, unbind,
+};
 use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 fn vec_f64(len: usize) -> impl Strategy<Value = Vec<f64>> {
     prop::collection::vec(-1000.0..1000.0, len)
@@ -73,3 +80,95 @@ fn bind_rejects_empty_vectors() {
     let y: Vec<f64> = vec![];
     assert!(bind(&x, &y).is_err());
 }
+
+#[test]
+fn unbind_approximately_inverts_bind() {
+    proptest!(ProptestConfig::with_cases(32), |(seed in any::<u64>())| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let a = random_vector(512, &mut rng);
+        let b = random_vector(512, &mut rng);
+        let recovered = unbind(&bind(&a, &b).unwrap(), &a).unwrap();
+        prop_assert!(cosine_similarity(&recovered, &b).unwrap() > 0.5);
+    });
+}
+
+#[test]
+fn permute_is_invertible() {
+    proptest!(|(v in vec_f64(16), shift in 0usize..64)| {
+        prop_assert_eq!(inverse_permute(&permute(&v, shift), shift), v);
+    });
+}
+
+/// Số cặp role–filler có thể gộp vào một bản ghi mà vẫn giải mã đúng toàn bộ.
+#[test]
+fn record_capacity_hrr() {
+    let dim = 1024;
+    let mut rng = StdRng::seed_from_u64(2024);
+    let mut roles = CleanupMemory::<Vec<f64>>::new(dim);
+    let mut fillers = CleanupMemory::<Vec<f64>>::new(dim);
+    let pairs: Vec<(Vec<f64>, Vec<f64>)> = (0..12)
+        .map(|i| {
+            (
+                roles.insert_random(format!("role{i}"), &mut rng),
+                fillers.insert_random(format!("filler{i}"), &mut rng),
+            )
+        })
+        .collect();
+    let refs: Vec<(&[f64], &[f64])> = pairs
+        .iter()
+        .map(|(r, f)| (r.as_slice(), f.as_slice()))
+        .collect();
+    let record = encode_record(&refs).unwrap();
+
+    for (i, (role, _)) in pairs.iter().enumerate() {
+        let noisy = decode_record(&record, role).unwrap();
+        let (name, _, _) = fillers.cleanup(&noisy).unwrap().unwrap();
+        assert_eq!(name, format!("filler{i}"));
+    }
+}
+
+#[test]
+fn sequence_capacity_hrr() {
+    let dim = 1024;
+    let mut rng = StdRng::seed_from_u64(99);
+    let mut memory = CleanupMemory::<Vec<f64>>::new(dim);
+    let symbols: Vec<Vec<f64>> = (0..26)
+        .map(|i| memory.insert_random(format!("s{i}"), &mut rng))
+        .collect();
+    let order = [3usize, 14, 1, 5, 9, 2, 6, 25, 17, 8];
+    let items: Vec<Vec<f64>> = order.iter().map(|&i| symbols[i].clone()).collect();
+    let sequence = encode_sequence(&items).unwrap();
+
+    for (position, &symbol) in order.iter().enumerate() {
+        let (name, _, _) = memory
+            .cleanup(&decode_sequence_position(&sequence, position))
+            .unwrap()
+            .unwrap();
+        assert_eq!(name, format!("s{symbol}"));
+    }
+}
+
+#[test]
+fn record_capacity_bsc() {
+    let dim = 4096;
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut fillers = CleanupMemory::<BinaryHypervector>::new(dim);
+    let pairs: Vec<(BinaryHypervector, BinaryHypervector)> = (0..9)
+        .map(|i| {
+            (
+                BinaryHypervector::random(dim, &mut rng),
+                fillers.insert_random(format!("filler{i}"), &mut rng),
+            )
+        })
+        .collect();
+    let bound: Vec<BinaryHypervector> = pairs.iter().map(|(r, f)| r.bind(f).unwrap()).collect();
+    let record = BinaryHypervector::bundle(&bound, None).unwrap();
+
+    for (i, (role, _)) in pairs.iter().enumerate() {
+        let (name, _, _) = fillers
+            .cleanup(&record.unbind(role).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(name, format!("filler{i}"));
+    }
+}
@@ -132,16 +132,12 @@ impl EFECalculator {
         &self,
         prior: &Array2<f32>,
         posterior: &Array2<f32>,
-        observations: &Array2<f32>,
+        _observations: &Array2<f32>,
     ) -> Result<f32, PandoraError> {
-        // KL divergence between prior and posterior
-        let kl_divergence = self.kl_divergence(prior, posterior)?;
-        
-        // Entropy of observations
-        let entropy = self.entropy(observations)?;
-        
-        // Information gain
-        let information_gain = kl_divergence + entropy;
+        // Information gain is the KL divergence between posterior and prior beliefs.
+        // The entropy of the observations is not information gain; see
+        // `discrete_active_inference` for the full risk + ambiguity decomposition.
+        let information_gain = self.kl_divergence(prior, posterior)?;
         
         Ok(self.precision_weight * information_gain)
     }
@@ -161,27 +157,14 @@ impl EFECalculator {
         Ok(kl_sum)
     }
 
-    fn entropy(&self, distribution: &Array2<f32>) -> Result<f32, PandoraError> {
-        let mut entropy = 0.0;
-        for &prob in distribution.iter() {
-            if prob > 0.0 {
-                entropy -= prob * prob.ln();
-            }
-        }
-        Ok(entropy)
-    }
-
     #[allow(dead_code)]
     fn information_gain(
         &self,
         prior: &Array2<f32>,
         posterior: &Array2<f32>,
-        observations: &Array2<f32>,
+        _observations: &Array2<f32>,
     ) -> Result<f32, PandoraError> {
-        // Mutual information between beliefs and observations
-        let kl_div = self.kl_divergence(prior, posterior)?;
-        let entropy_obs = self.entropy(observations)?;
-        Ok(kl_div + entropy_obs)
+        self.kl_divergence(prior, posterior)
     }
 
     #[allow(dead_code)]
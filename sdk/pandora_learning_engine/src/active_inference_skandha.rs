@@ -5,6 +5,7 @@
 //! expected free energy. It integrates with the Causal World Model (CWM) and
 //! Learning Engine to enable goal-oriented behavior.
 
+use crate::mcts::{MctsConfig, MctsPlanner};
 use crate::LearningEngine;
 use pandora_core::interfaces::skandhas::{SankharaSkandha, Skandha};
 use pandora_core::ontology::{EpistemologicalFlow, Vedana};
@@ -12,7 +13,7 @@ use pandora_core::world_model::WorldModel;
 use pandora_error::PandoraError;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
-use tracing::{debug, info, warn};
+use tracing::{debug, info};
 
 /// Represents a causal hypothesis discovered through data analysis.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -63,6 +64,10 @@ pub struct ActiveInferenceSankharaSkandha {
     pub gamma: f64,
     /// Policy for action selection during simulation
     pub policy: Policy,
+    /// MCTS settings; `horizon` and `gamma` are taken from `planning_horizon` and `gamma`
+    pub planner_config: MctsConfig,
+    /// Weight of the epistemic value (predicted epistemic uncertainty) in the EFE
+    pub epistemic_weight: f64,
 }
 
 impl Policy {
@@ -121,9 +126,17 @@ impl ActiveInferenceSankharaSkandha {
             concept_action_mapping,
             gamma,
             policy,
+            planner_config: MctsConfig::default(),
+            epistemic_weight: 1.0,
         }
     }
 
+    /// Replaces the MCTS settings used by `form_intent`
+    pub fn with_planner_config(mut self, planner_config: MctsConfig) -> Self {
+        self.planner_config = planner_config;
+        self
+    }
+
     /// Creates a default mapping from concepts to actions that can influence them
     fn create_default_concept_action_mapping() -> std::collections::HashMap<usize, Vec<&'static str>>
     {
@@ -222,8 +235,8 @@ impl SankharaSkandha for ActiveInferenceSankharaSkandha {
 impl ActiveInferenceSankharaSkandha {
     /// Plans the best action using Active Inference
     ///
-    /// This method simulates future states for each candidate action and selects
-    /// the one that minimizes expected free energy (maximizes expected reward).
+    /// Runs MCTS over the transitions predicted by the CWM (`predict_next_state`),
+    /// scoring every imagined state by its expected free energy.
     fn plan_action(
         &self,
         current_flow: &EpistemologicalFlow,
@@ -240,11 +253,7 @@ impl ActiveInferenceSankharaSkandha {
             .map_err(|_| PandoraError::config("Failed to acquire CWM lock"))?;
 
         // 1. Propose candidate actions
-        let mut candidate_actions = self.propose_candidate_actions(current_flow);
-        // Randomize order to avoid deterministic tie-breaking when scores are equal
-        use rand::seq::SliceRandom;
-        let mut rng = rand::thread_rng();
-        candidate_actions.shuffle(&mut rng);
+        let candidate_actions = self.propose_candidate_actions(current_flow);
         info!(
             "[{}] Đề xuất {} hành động: {:?}",
             self.name(),
@@ -256,100 +265,53 @@ impl ActiveInferenceSankharaSkandha {
             return Ok("intent_do_nothing"); // Default safe action
         }
 
-        let mut best_action = *candidate_actions.first().unwrap();
-        let mut best_expected_free_energy = f64::NEG_INFINITY;
-
-        // 2. For each possible action, simulate the future
-        for &action in &candidate_actions {
-            let mut total_future_efe = 0.0;
-            // Create a deep copy for simulation to avoid side effects
-            let mut simulated_flow = current_flow.clone();
-
-            // Set the initial action for the first step of the simulation
-            simulated_flow.sankhara = Some(std::sync::Arc::<str>::from(action));
-
-            // 3. Rollout the simulation for `planning_horizon` steps
-            for i in 0..self.planning_horizon {
-                // **Crucial Step 1: Prediction**
-                // Ask the CWM to predict the outcome of the action.
-                // This modifies `simulated_flow` to represent the next hypothetical state.
-                // Note: This is a simplified version - in a full implementation,
-                // we would need a more sophisticated state prediction mechanism
-                let _temp_flow = simulated_flow.clone();
-
-                // For now, we'll simulate prediction by just continuing with the current state
-                // In a full implementation, this would call cwm.predict_next_state(&mut simulated_flow)
-                debug!("[{}] Simulating prediction for step {}", self.name(), i);
-
-                // **Crucial Step 2: Evaluation**
-                // Calculate the Expected Free Energy (EFE) for this imagined state.
-                let reward = self.learning_engine.calculate_reward(
-                    &*cwm,
-                    &*cwm, // In simulation, the model doesn't change
-                    &simulated_flow,
-                );
-                let efe = self.learning_engine.get_total_weighted_reward(&reward);
-
-                // Apply a discount factor for future steps (gamma)
-                total_future_efe += efe * self.gamma.powi(i as i32);
-
-                // **Crucial Step 3: Propose next action for the simulation**
-                // For the next step in the simulation, we need a new action.
-                // A simple strategy is to use the policy to pick the best next action.
-                // This makes the simulation more realistic.
-                if i < self.planning_horizon - 1 {
-                    match self.policy.select_action(&simulated_flow) {
-                        Ok(next_sim_action) => {
-                            simulated_flow.sankhara =
-                                Some(std::sync::Arc::<str>::from(next_sim_action));
-                        }
-                        Err(e) => {
-                            warn!(
-                                "[{}] Policy selection failed at step {}: {}",
-                                self.name(),
-                                i,
-                                e
-                            );
-                            // Use a default action if policy fails
-                            simulated_flow.sankhara =
-                                Some(std::sync::Arc::<str>::from("default_action"));
-                        }
-                    }
-                }
-
-                debug!(
-                    "[{}] Step {} cho action '{}': EFE = {:.4}, Total = {:.4}",
-                    self.name(),
-                    i + 1,
-                    action,
-                    efe,
-                    total_future_efe
-                );
-            }
-
-            info!(
-                "[{}] Action '{}' có tổng EFE: {:.4}",
+        // 2. Search the tree of imagined futures
+        let planner = MctsPlanner::new(MctsConfig {
+            horizon: self.planning_horizon,
+            gamma: self.gamma,
+            ..self.planner_config.clone()
+        });
+        let plan = planner.search(
+            current_flow,
+            &candidate_actions,
+            None,
+            |flow, action| {
+                flow.sankhara = Some(std::sync::Arc::<str>::from(action));
+                cwm.predict_next_state(flow)
+            },
+            |flow| self.expected_free_energy(&*cwm, flow),
+        )?;
+
+        for stats in &plan.root_actions {
+            debug!(
+                "[{}] Action '{}': visits = {}, EFE = {:.4}",
                 self.name(),
-                action,
-                total_future_efe
+                stats.action,
+                stats.visits,
+                stats.mean_value
             );
-
-            if total_future_efe > best_expected_free_energy {
-                best_expected_free_energy = total_future_efe;
-                best_action = action;
-            }
         }
 
+        let best_action = plan.best_action.unwrap_or("intent_do_nothing");
         info!(
-            "[{}] Chọn action '{}' với EFE: {:.4}",
+            "[{}] Chọn action '{}' sau {} vòng MCTS ({} nút)",
             self.name(),
             best_action,
-            best_expected_free_energy
+            plan.iterations,
+            plan.tree_size
         );
 
         Ok(best_action)
     }
 
+    /// Expected free energy of an imagined state (higher is better): the weighted
+    /// intrinsic reward plus the epistemic value of the prediction.
+    fn expected_free_energy(&self, cwm: &dyn WorldModel, flow: &EpistemologicalFlow) -> f64 {
+        let reward = self.learning_engine.calculate_reward(cwm, cwm, flow);
+        let epistemic = flow.uncertainty.map_or(0.0, |u| f64::from(u.epistemic));
+        self.learning_engine.get_total_weighted_reward(&reward) + self.epistemic_weight * epistemic
+    }
+
     /// Proposes candidate actions based on current context
     ///
     /// This method analyzes the current flow and generates relevant actions.
@@ -624,6 +586,58 @@ mod tests {
         );
     }
 
+    struct CountingWorldModel {
+        predictions: std::sync::atomic::AtomicUsize,
+    }
+
+    impl WorldModel for CountingWorldModel {
+        fn get_mdl(&self) -> f64 {
+            10.0
+        }
+
+        fn get_prediction_error(&self, flow: &EpistemologicalFlow) -> f64 {
+            // Chỉ "action_B" dẫn tới trạng thái dễ dự đoán
+            match flow.sankhara.as_deref() {
+                Some("action_B") => 0.0,
+                _ => 1.0,
+            }
+        }
+
+        fn predict_next_state(&self, _flow: &mut EpistemologicalFlow) -> Result<(), PandoraError> {
+            self.predictions
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plan_action_uses_world_model_predictions() {
+        let cwm = Arc::new(Mutex::new(CountingWorldModel {
+            predictions: Default::default(),
+        }));
+        let sankhara = ActiveInferenceSankharaSkandha::new(
+            cwm.clone(),
+            Arc::new(LearningEngine::new(0.7, 0.3)),
+            2,
+            vec!["action_A", "action_B"],
+            0.9,
+            0.1,
+        )
+        .with_planner_config(MctsConfig {
+            budget: crate::mcts::PlanningBudget::Iterations(50),
+            ..Default::default()
+        });
+
+        let flow = EpistemologicalFlow::from_bytes(Bytes::from(b"test_event".as_ref()));
+        assert_eq!(sankhara.plan_action(&flow).unwrap(), "action_B");
+        let predictions = cwm
+            .lock()
+            .unwrap()
+            .predictions
+            .load(std::sync::atomic::Ordering::Relaxed);
+        assert!(predictions > 0 && predictions <= 50);
+    }
+
     #[test]
     fn test_propose_candidate_actions() {
         let cwm = Arc::new(Mutex::new(MockWorldModel {
@@ -0,0 +1,890 @@
+//! Các thuật toán multi-armed bandit sau trait `Bandit`, và `BanditPolicy` để dùng
+//! chúng như một `Policy` trên `EpistemologicalFlow`.
+//!
+//! - `EpsilonGreedy` với `EpsilonSchedule` (hằng, tuyến tính, mũ, 1/t)
+//! - `Ucb1` và `UcbV` (UCB dựa trên phương sai thực nghiệm)
+//! - `BetaThompson` (reward trong `[0, 1]`) và `GaussianThompson`
+//! - `Exp3` cho reward đối kháng
+//! - `LinUcb`: contextual bandit tuyến tính trên đặc trưng của flow
+//!
+//! Arm được đánh số `0..num_arms`, nên cùng các bandit này dùng được để định tuyến
+//! giữa các skill.
+
+use crate::dqn::FlowFeatureExtractor;
+use crate::policy::{Action, Policy};
+use pandora_core::ontology::EpistemologicalFlow;
+use pandora_error::PandoraError;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+
+/// Một bandit trên `num_arms` arm. Bandit không ngữ cảnh bỏ qua `context`.
+pub trait Bandit {
+    fn num_arms(&self) -> usize;
+    fn select_arm(&mut self, context: &[f64]) -> usize;
+    fn update(&mut self, arm: usize, context: &[f64], reward: f64);
+}
+
+/// Trung bình và phương sai chạy (Welford) của reward mỗi arm.
+#[derive(Debug, Clone)]
+struct ArmStats {
+    counts: Vec<u64>,
+    means: Vec<f64>,
+    m2: Vec<f64>,
+}
+
+impl ArmStats {
+    fn new(num_arms: usize) -> Self {
+        Self {
+            counts: vec![0; num_arms],
+            means: vec![0.0; num_arms],
+            m2: vec![0.0; num_arms],
+        }
+    }
+
+    fn record(&mut self, arm: usize, reward: f64) {
+        self.counts[arm] += 1;
+        let delta = reward - self.means[arm];
+        self.means[arm] += delta / self.counts[arm] as f64;
+        self.m2[arm] += delta * (reward - self.means[arm]);
+    }
+
+    fn variance(&self, arm: usize) -> f64 {
+        if self.counts[arm] == 0 {
+            0.0
+        } else {
+            self.m2[arm] / self.counts[arm] as f64
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    fn unplayed(&self) -> Option<usize> {
+        self.counts.iter().position(|&n| n == 0)
+    }
+}
+
+/// Bandit không có arm nào thì không chọn được gì; mọi constructor từ chối trường hợp đó.
+fn check_arms(num_arms: usize) -> Result<(), PandoraError> {
+    if num_arms == 0 {
+        return Err(PandoraError::config("Bandit cần ít nhất một arm"));
+    }
+    Ok(())
+}
+
+/// Arm có điểm cao nhất; hòa thì lấy arm có chỉ số nhỏ nhất.
+fn argmax(scores: impl IntoIterator<Item = f64>) -> usize {
+    let mut best = (0, f64::NEG_INFINITY);
+    for (arm, score) in scores.into_iter().enumerate() {
+        if score > best.1 {
+            best = (arm, score);
+        }
+    }
+    best.0
+}
+
+// ===== Epsilon-greedy =====
+
+/// Xác suất khám phá theo số bước đã chọn `t` (bắt đầu từ 0).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EpsilonSchedule {
+    Constant(f64),
+    /// Giảm tuyến tính từ `start` xuống `end` trong `steps` bước.
+    Linear {
+        start: f64,
+        end: f64,
+        steps: u64,
+    },
+    /// `end + (start - end) * decay^t`.
+    Exponential {
+        start: f64,
+        end: f64,
+        decay: f64,
+    },
+    /// `min(1, c / (t + 1))`, cho regret logarit khi `c` đủ lớn.
+    InverseTime {
+        c: f64,
+    },
+}
+
+impl EpsilonSchedule {
+    pub fn epsilon(&self, t: u64) -> f64 {
+        let epsilon = match *self {
+            EpsilonSchedule::Constant(epsilon) => epsilon,
+            EpsilonSchedule::Linear { start, end, steps } => {
+                let progress = (t as f64 / steps.max(1) as f64).min(1.0);
+                start + (end - start) * progress
+            }
+            EpsilonSchedule::Exponential { start, end, decay } => {
+                end + (start - end) * decay.powf(t as f64)
+            }
+            EpsilonSchedule::InverseTime { c } => c / (t + 1) as f64,
+        };
+        epsilon.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EpsilonGreedy {
+    schedule: EpsilonSchedule,
+    stats: ArmStats,
+    steps: u64,
+    rng: StdRng,
+}
+
+impl EpsilonGreedy {
+    pub fn new(
+        num_arms: usize,
+        schedule: EpsilonSchedule,
+        seed: u64,
+    ) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        Ok(Self {
+            schedule,
+            stats: ArmStats::new(num_arms),
+            steps: 0,
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Xác suất khám phá ở bước kế tiếp.
+    pub fn epsilon(&self) -> f64 {
+        self.schedule.epsilon(self.steps)
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.stats.means
+    }
+}
+
+impl Bandit for EpsilonGreedy {
+    fn num_arms(&self) -> usize {
+        self.stats.counts.len()
+    }
+
+    fn select_arm(&mut self, _context: &[f64]) -> usize {
+        let epsilon = self.epsilon();
+        self.steps += 1;
+        if self.rng.gen::<f64>() < epsilon {
+            self.rng.gen_range(0..self.num_arms())
+        } else {
+            argmax(self.stats.means.iter().copied())
+        }
+    }
+
+    fn update(&mut self, arm: usize, _context: &[f64], reward: f64) {
+        self.stats.record(arm, reward);
+    }
+}
+
+// ===== UCB =====
+
+/// UCB1: `mean + c * sqrt(ln t / n)`; `c = sqrt(2)` cho reward trong `[0, 1]`.
+#[derive(Debug, Clone)]
+pub struct Ucb1 {
+    c: f64,
+    stats: ArmStats,
+}
+
+impl Ucb1 {
+    pub fn new(num_arms: usize, c: f64) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        Ok(Self {
+            c,
+            stats: ArmStats::new(num_arms),
+        })
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.stats.means
+    }
+}
+
+impl Bandit for Ucb1 {
+    fn num_arms(&self) -> usize {
+        self.stats.counts.len()
+    }
+
+    fn select_arm(&mut self, _context: &[f64]) -> usize {
+        if let Some(arm) = self.stats.unplayed() {
+            return arm;
+        }
+        let ln_t = (self.stats.total() as f64).ln();
+        argmax((0..self.num_arms()).map(|arm| {
+            self.stats.means[arm] + self.c * (ln_t / self.stats.counts[arm] as f64).sqrt()
+        }))
+    }
+
+    fn update(&mut self, arm: usize, _context: &[f64], reward: f64) {
+        self.stats.record(arm, reward);
+    }
+}
+
+/// UCB-V (Audibert, Munos & Szepesvári): bonus theo phương sai thực nghiệm,
+/// `mean + sqrt(2 var ζ ln t / n) + 3 b ζ ln t / n` với reward trong `[0, b]`.
+#[derive(Debug, Clone)]
+pub struct UcbV {
+    reward_range: f64,
+    zeta: f64,
+    stats: ArmStats,
+}
+
+impl UcbV {
+    pub fn new(num_arms: usize, reward_range: f64) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        Ok(Self {
+            reward_range,
+            zeta: 1.2,
+            stats: ArmStats::new(num_arms),
+        })
+    }
+
+    pub fn values(&self) -> &[f64] {
+        &self.stats.means
+    }
+}
+
+impl Bandit for UcbV {
+    fn num_arms(&self) -> usize {
+        self.stats.counts.len()
+    }
+
+    fn select_arm(&mut self, _context: &[f64]) -> usize {
+        if let Some(arm) = self.stats.unplayed() {
+            return arm;
+        }
+        let exploration = self.zeta * (self.stats.total() as f64).ln();
+        argmax((0..self.num_arms()).map(|arm| {
+            let n = self.stats.counts[arm] as f64;
+            self.stats.means[arm]
+                + (2.0 * self.stats.variance(arm) * exploration / n).sqrt()
+                + 3.0 * self.reward_range * exploration / n
+        }))
+    }
+
+    fn update(&mut self, arm: usize, _context: &[f64], reward: f64) {
+        self.stats.record(arm, reward);
+    }
+}
+
+// ===== Thompson sampling =====
+
+fn sample_standard_normal(rng: &mut StdRng) -> f64 {
+    // Box-Muller
+    let u1 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+    let u2 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Gamma(shape, 1) theo Marsaglia & Tsang.
+fn sample_gamma(rng: &mut StdRng, shape: f64) -> f64 {
+    if shape < 1.0 {
+        // Boost: Gamma(a) = Gamma(a + 1) * U^(1/a)
+        let u = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+        return sample_gamma(rng, shape + 1.0) * u.powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+fn sample_beta(rng: &mut StdRng, alpha: f64, beta: f64) -> f64 {
+    let x = sample_gamma(rng, alpha);
+    let y = sample_gamma(rng, beta);
+    x / (x + y)
+}
+
+/// Thompson sampling với prior Beta. Reward trong `[0, 1]` được biến thành một
+/// phép thử Bernoulli với xác suất thành công bằng reward (Agrawal & Goyal).
+#[derive(Debug, Clone)]
+pub struct BetaThompson {
+    alpha: Vec<f64>,
+    beta: Vec<f64>,
+    rng: StdRng,
+}
+
+impl BetaThompson {
+    /// Prior đồng nhất Beta(1, 1).
+    pub fn new(num_arms: usize, seed: u64) -> Result<Self, PandoraError> {
+        Self::with_prior(num_arms, 1.0, 1.0, seed)
+    }
+
+    pub fn with_prior(
+        num_arms: usize,
+        alpha: f64,
+        beta: f64,
+        seed: u64,
+    ) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        Ok(Self {
+            alpha: vec![alpha; num_arms],
+            beta: vec![beta; num_arms],
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// Trung bình hậu nghiệm của mỗi arm.
+    pub fn posterior_means(&self) -> Vec<f64> {
+        self.alpha
+            .iter()
+            .zip(&self.beta)
+            .map(|(a, b)| a / (a + b))
+            .collect()
+    }
+}
+
+impl Bandit for BetaThompson {
+    fn num_arms(&self) -> usize {
+        self.alpha.len()
+    }
+
+    fn select_arm(&mut self, _context: &[f64]) -> usize {
+        let samples: Vec<f64> = (0..self.num_arms())
+            .map(|arm| sample_beta(&mut self.rng, self.alpha[arm], self.beta[arm]))
+            .collect();
+        argmax(samples)
+    }
+
+    fn update(&mut self, arm: usize, _context: &[f64], reward: f64) {
+        if self.rng.gen::<f64>() < reward.clamp(0.0, 1.0) {
+            self.alpha[arm] += 1.0;
+        } else {
+            self.beta[arm] += 1.0;
+        }
+    }
+}
+
+/// Thompson sampling cho reward Gauss với phương sai nhiễu đã biết và prior
+/// chuẩn `N(prior_mean, noise_variance / prior_strength)` trên trung bình mỗi arm.
+#[derive(Debug, Clone)]
+pub struct GaussianThompson {
+    prior_mean: f64,
+    prior_strength: f64,
+    noise_variance: f64,
+    stats: ArmStats,
+    rng: StdRng,
+}
+
+impl GaussianThompson {
+    pub fn new(num_arms: usize, noise_variance: f64, seed: u64) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        Ok(Self {
+            prior_mean: 0.0,
+            prior_strength: 1.0,
+            noise_variance,
+            stats: ArmStats::new(num_arms),
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    pub fn with_prior(mut self, prior_mean: f64, prior_strength: f64) -> Self {
+        self.prior_mean = prior_mean;
+        self.prior_strength = prior_strength.max(f64::EPSILON);
+        self
+    }
+
+    /// Trung bình và phương sai hậu nghiệm của trung bình arm.
+    pub fn posterior(&self, arm: usize) -> (f64, f64) {
+        let n = self.stats.counts[arm] as f64;
+        let strength = self.prior_strength + n;
+        let mean = (self.prior_strength * self.prior_mean + n * self.stats.means[arm]) / strength;
+        (mean, self.noise_variance / strength)
+    }
+}
+
+impl Bandit for GaussianThompson {
+    fn num_arms(&self) -> usize {
+        self.stats.counts.len()
+    }
+
+    fn select_arm(&mut self, _context: &[f64]) -> usize {
+        let samples: Vec<f64> = (0..self.num_arms())
+            .map(|arm| {
+                let (mean, variance) = self.posterior(arm);
+                mean + variance.sqrt() * sample_standard_normal(&mut self.rng)
+            })
+            .collect();
+        argmax(samples)
+    }
+
+    fn update(&mut self, arm: usize, _context: &[f64], reward: f64) {
+        self.stats.record(arm, reward);
+    }
+}
+
+// ===== EXP3 =====
+
+/// EXP3 (Auer et al.) cho reward đối kháng trong `[0, 1]`: trọng số mũ với
+/// ước lượng reward không chệch theo importance weighting.
+#[derive(Debug, Clone)]
+pub struct Exp3 {
+    gamma: f64,
+    /// Trọng số lưu dạng log để tránh tràn số
+    log_weights: Vec<f64>,
+    rng: StdRng,
+}
+
+impl Exp3 {
+    pub fn new(num_arms: usize, gamma: f64, seed: u64) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        Ok(Self {
+            gamma: gamma.clamp(f64::EPSILON, 1.0),
+            log_weights: vec![0.0; num_arms],
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+
+    /// `gamma` tối ưu cho tầm nhìn `horizon`: `min(1, sqrt(K ln K / ((e - 1) T)))`.
+    pub fn with_horizon(num_arms: usize, horizon: u64, seed: u64) -> Result<Self, PandoraError> {
+        let k = num_arms.max(2) as f64;
+        let gamma = (k * k.ln() / ((std::f64::consts::E - 1.0) * horizon.max(1) as f64)).sqrt();
+        Self::new(num_arms, gamma.min(1.0), seed)
+    }
+
+    /// Phân phối chọn arm hiện tại.
+    pub fn probabilities(&self) -> Vec<f64> {
+        let k = self.log_weights.len() as f64;
+        let max = self
+            .log_weights
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = self.log_weights.iter().map(|w| (w - max).exp()).collect();
+        let total: f64 = weights.iter().sum();
+        weights
+            .iter()
+            .map(|w| (1.0 - self.gamma) * w / total + self.gamma / k)
+            .collect()
+    }
+}
+
+impl Bandit for Exp3 {
+    fn num_arms(&self) -> usize {
+        self.log_weights.len()
+    }
+
+    fn select_arm(&mut self, _context: &[f64]) -> usize {
+        let probabilities = self.probabilities();
+        let mut u = self.rng.gen::<f64>();
+        for (arm, p) in probabilities.iter().enumerate() {
+            if u < *p {
+                return arm;
+            }
+            u -= p;
+        }
+        probabilities.len() - 1
+    }
+
+    fn update(&mut self, arm: usize, _context: &[f64], reward: f64) {
+        let p = self.probabilities()[arm];
+        let estimate = reward.clamp(0.0, 1.0) / p;
+        self.log_weights[arm] += self.gamma * estimate / self.num_arms() as f64;
+    }
+}
+
+// ===== LinUCB =====
+
+/// LinUCB (Li et al., disjoint): mỗi arm có một hồi quy ridge trên context,
+/// chọn arm có `θ·x + alpha * sqrt(xᵀ A⁻¹ x)` lớn nhất.
+#[derive(Debug, Clone)]
+pub struct LinUcb {
+    alpha: f64,
+    dims: usize,
+    /// `A⁻¹` của mỗi arm (dims × dims, theo hàng), cập nhật bằng Sherman-Morrison
+    a_inv: Vec<Vec<f64>>,
+    b: Vec<Vec<f64>>,
+}
+
+impl LinUcb {
+    /// `dims` là số chiều của context; cần ít nhất một arm và một chiều.
+    pub fn new(num_arms: usize, dims: usize, alpha: f64) -> Result<Self, PandoraError> {
+        check_arms(num_arms)?;
+        if dims == 0 {
+            return Err(PandoraError::config("LinUcb cần context ít nhất một chiều"));
+        }
+        let identity: Vec<f64> = (0..dims * dims)
+            .map(|i| if i % (dims + 1) == 0 { 1.0 } else { 0.0 })
+            .collect();
+        Ok(Self {
+            alpha,
+            dims,
+            a_inv: vec![identity; num_arms],
+            b: vec![vec![0.0; dims]; num_arms],
+        })
+    }
+
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// `A⁻¹ x` của arm.
+    fn a_inv_dot(&self, arm: usize, x: &[f64]) -> Vec<f64> {
+        self.a_inv[arm]
+            .chunks(self.dims)
+            .map(|row| row.iter().zip(x).map(|(a, v)| a * v).sum())
+            .collect()
+    }
+
+    /// Hệ số hồi quy `θ = A⁻¹ b` của arm.
+    pub fn theta(&self, arm: usize) -> Vec<f64> {
+        self.a_inv_dot(arm, &self.b[arm])
+    }
+
+    /// Cận trên tin cậy của reward kỳ vọng khi chọn `arm` trong `context`.
+    pub fn upper_bound(&self, arm: usize, context: &[f64]) -> f64 {
+        let x = &context[..self.dims.min(context.len())];
+        let mean: f64 = self.theta(arm).iter().zip(x).map(|(t, v)| t * v).sum();
+        let width: f64 = self
+            .a_inv_dot(arm, x)
+            .iter()
+            .zip(x)
+            .map(|(a, v)| a * v)
+            .sum();
+        mean + self.alpha * width.max(0.0).sqrt()
+    }
+}
+
+impl Bandit for LinUcb {
+    fn num_arms(&self) -> usize {
+        self.b.len()
+    }
+
+    fn select_arm(&mut self, context: &[f64]) -> usize {
+        argmax((0..self.num_arms()).map(|arm| self.upper_bound(arm, context)))
+    }
+
+    fn update(&mut self, arm: usize, context: &[f64], reward: f64) {
+        let mut x = context.to_vec();
+        x.resize(self.dims, 0.0);
+        // Sherman-Morrison: (A + x xᵀ)⁻¹ = A⁻¹ - (A⁻¹x)(A⁻¹x)ᵀ / (1 + xᵀA⁻¹x)
+        let a_inv_x = self.a_inv_dot(arm, &x);
+        let denominator = 1.0 + a_inv_x.iter().zip(&x).map(|(a, v)| a * v).sum::<f64>();
+        for (i, row) in self.a_inv[arm].chunks_mut(self.dims).enumerate() {
+            for (j, a) in row.iter_mut().enumerate() {
+                *a -= a_inv_x[i] * a_inv_x[j] / denominator;
+            }
+        }
+        for (b, v) in self.b[arm].iter_mut().zip(&x) {
+            *b += reward * v;
+        }
+    }
+}
+
+// ===== Policy adapter =====
+
+struct PendingChoice {
+    arm: usize,
+    context: Vec<f64>,
+}
+
+struct BanditState<B> {
+    bandit: B,
+    pending: Option<PendingChoice>,
+    /// Chỉ dùng cho khám phá cưỡng bức theo `explore_rate`
+    rng: StdRng,
+}
+
+/// `Policy` chọn `Action` bằng một bandit, với context là đặc trưng của flow
+/// (`FlowFeatureExtractor`). `update` dùng advantage làm reward cho hành động
+/// được chọn gần nhất.
+///
+/// `explore_rate` của `select_action` là xác suất khám phá cưỡng bức, cộng thêm vào
+/// cơ chế của bandit: với xác suất đó hành động được chọn đều ngẫu nhiên (và vẫn được
+/// cập nhật như hành động bandit chọn); `0.0` để bandit tự quyết hoàn toàn.
+pub struct BanditPolicy<B: Bandit> {
+    arms: Vec<Action>,
+    extractor: FlowFeatureExtractor,
+    state: Mutex<BanditState<B>>,
+}
+
+impl<B: Bandit> BanditPolicy<B> {
+    /// `bandit` phải có đúng `arms.len()` arm, và ít nhất một arm.
+    pub fn new(arms: Vec<Action>, bandit: B) -> Result<Self, PandoraError> {
+        if arms.is_empty() {
+            return Err(PandoraError::config(
+                "BanditPolicy cần ít nhất một hành động",
+            ));
+        }
+        if arms.len() != bandit.num_arms() {
+            return Err(PandoraError::config(format!(
+                "Bandit có {} arm nhưng có {} hành động",
+                bandit.num_arms(),
+                arms.len()
+            )));
+        }
+        Ok(Self::from_parts(arms, bandit))
+    }
+
+    /// Như `new` khi số arm đã đúng theo cách dựng (không rỗng, khớp `bandit`).
+    pub(crate) fn from_parts(arms: Vec<Action>, bandit: B) -> Self {
+        Self {
+            arms,
+            extractor: FlowFeatureExtractor::new(8, Vec::new()),
+            state: Mutex::new(BanditState {
+                bandit,
+                pending: None,
+                rng: StdRng::seed_from_u64(0),
+            }),
+        }
+    }
+
+    /// Seed của RNG dùng cho khám phá cưỡng bức.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.state.get_mut().unwrap_or_else(|e| e.into_inner()).rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn with_extractor(mut self, extractor: FlowFeatureExtractor) -> Self {
+        self.extractor = extractor;
+        self
+    }
+
+    pub fn arms(&self) -> &[Action] {
+        &self.arms
+    }
+
+    pub fn extractor(&self) -> &FlowFeatureExtractor {
+        &self.extractor
+    }
+
+    /// Ghi nhận reward của `action` trong `flow`, kể cả khi action không do policy chọn.
+    pub fn observe(&mut self, flow: &EpistemologicalFlow, action: &Action, reward: f64) {
+        if let Some(arm) = self.arms.iter().position(|a| a == action) {
+            let context = self.extractor.extract(flow);
+            let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+            state.bandit.update(arm, &context, reward);
+        }
+    }
+
+    /// Đọc trạng thái của bandit bên dưới.
+    pub fn with_bandit<R>(&self, f: impl FnOnce(&B) -> R) -> R {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        f(&state.bandit)
+    }
+}
+
+impl<B: Bandit> Policy for BanditPolicy<B> {
+    fn select_action(&self, flow: &EpistemologicalFlow, explore_rate: f64) -> Action {
+        let context = self.extractor.extract(flow);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let arm = if explore_rate > 0.0 && state.rng.gen::<f64>() < explore_rate {
+            state.rng.gen_range(0..self.arms.len())
+        } else {
+            state.bandit.select_arm(&context)
+        };
+        state.pending = Some(PendingChoice { arm, context });
+        self.arms[arm].clone()
+    }
+
+    fn update(&mut self, _flow: &EpistemologicalFlow, advantage: f64) {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(choice) = state.pending.take() {
+            state.bandit.update(choice.arm, &choice.context, advantage);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use pandora_core::ontology::Vedana;
+
+    /// Pseudo-regret của `bandit` sau `horizon` bước trên các arm Bernoulli `means`.
+    fn bernoulli_regret(bandit: &mut dyn Bandit, means: &[f64], horizon: usize, seed: u64) -> f64 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let best = means.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut regret = 0.0;
+        for _ in 0..horizon {
+            let arm = bandit.select_arm(&[]);
+            let reward = if rng.gen::<f64>() < means[arm] {
+                1.0
+            } else {
+                0.0
+            };
+            bandit.update(arm, &[], reward);
+            regret += best - means[arm];
+        }
+        regret
+    }
+
+    const MEANS: [f64; 4] = [0.2, 0.4, 0.5, 0.7];
+    const HORIZON: usize = 5000;
+
+    /// Regret của việc chọn ngẫu nhiên đều
+    fn uniform_regret() -> f64 {
+        let mean = MEANS.iter().sum::<f64>() / MEANS.len() as f64;
+        (0.7 - mean) * HORIZON as f64
+    }
+
+    #[test]
+    fn epsilon_schedules_decay() {
+        let linear = EpsilonSchedule::Linear {
+            start: 1.0,
+            end: 0.1,
+            steps: 10,
+        };
+        assert_eq!(linear.epsilon(0), 1.0);
+        assert!((linear.epsilon(5) - 0.55).abs() < 1e-12);
+        assert!((linear.epsilon(100) - 0.1).abs() < 1e-12);
+        let exponential = EpsilonSchedule::Exponential {
+            start: 1.0,
+            end: 0.05,
+            decay: 0.5,
+        };
+        assert!((exponential.epsilon(1) - 0.525).abs() < 1e-12);
+        assert_eq!(EpsilonSchedule::InverseTime { c: 5.0 }.epsilon(0), 1.0);
+        assert_eq!(EpsilonSchedule::InverseTime { c: 5.0 }.epsilon(9), 0.5);
+    }
+
+    #[test]
+    fn stochastic_bandits_have_low_regret_on_bernoulli_arms() {
+        let k = MEANS.len();
+        let bandits: Vec<(&str, Box<dyn Bandit>)> = vec![
+            (
+                "epsilon-greedy",
+                Box::new(
+                    EpsilonGreedy::new(k, EpsilonSchedule::InverseTime { c: 20.0 }, 1).unwrap(),
+                ),
+            ),
+            ("ucb1", Box::new(Ucb1::new(k, 2f64.sqrt()).unwrap())),
+            ("ucb-v", Box::new(UcbV::new(k, 1.0).unwrap())),
+            ("beta-thompson", Box::new(BetaThompson::new(k, 2).unwrap())),
+            (
+                "gaussian-thompson",
+                Box::new(GaussianThompson::new(k, 0.25, 3).unwrap()),
+            ),
+        ];
+        for (name, mut bandit) in bandits {
+            let regret = bernoulli_regret(bandit.as_mut(), &MEANS, HORIZON, 7);
+            // Regret logarit: nhỏ hơn nhiều so với chọn ngẫu nhiên (~1125)
+            assert!(regret < 0.2 * uniform_regret(), "{name}: regret {regret}");
+        }
+    }
+
+    #[test]
+    fn regret_grows_sublinearly() {
+        let mut bandit = BetaThompson::new(MEANS.len(), 5).unwrap();
+        let first = bernoulli_regret(&mut bandit, &MEANS, HORIZON, 11);
+        let second = bernoulli_regret(&mut bandit, &MEANS, HORIZON, 12);
+        assert!(second < 0.5 * first, "first {first}, second {second}");
+    }
+
+    #[test]
+    fn exp3_tracks_best_arm_under_adversarial_rewards() {
+        // Đối thủ: arm 0 tốt ở nửa đầu, arm 1 tốt hơn trên toàn bộ tầm nhìn
+        let horizon = 20_000;
+        let reward = |t: usize, arm: usize| match arm {
+            0 if t < horizon / 2 => 0.9,
+            0 => 0.0,
+            1 => 0.6,
+            _ => 0.1,
+        };
+        let mut bandit = Exp3::with_horizon(3, horizon as u64, 9).unwrap();
+        let mut gained = 0.0;
+        for t in 0..horizon {
+            let arm = bandit.select_arm(&[]);
+            let r = reward(t, arm);
+            bandit.update(arm, &[], r);
+            gained += r;
+        }
+        let best_fixed = (0..3)
+            .map(|arm| (0..horizon).map(|t| reward(t, arm)).sum::<f64>())
+            .fold(f64::NEG_INFINITY, f64::max);
+        // Cận regret của EXP3: 2.63 sqrt(T K ln K)
+        let bound = 2.63 * (horizon as f64 * 3.0 * 3f64.ln()).sqrt();
+        assert!(
+            best_fixed - gained < bound,
+            "regret {}",
+            best_fixed - gained
+        );
+        assert!(bandit.probabilities()[1] > 0.5);
+    }
+
+    #[test]
+    fn linucb_learns_context_dependent_arms() {
+        // Reward tuyến tính: arm 0 tốt khi x[0] lớn, arm 1 tốt khi x[1] lớn
+        let theta = [[1.0, 0.0, 0.1], [0.0, 1.0, 0.1], [0.4, 0.4, 0.1]];
+        let mut rng = StdRng::seed_from_u64(13);
+        let mut bandit = LinUcb::new(3, 3, 0.5).unwrap();
+        let mut regret = [0.0, 0.0];
+        let horizon = 4000;
+        for t in 0..horizon {
+            let x = [rng.gen::<f64>(), rng.gen::<f64>(), 1.0];
+            let expected: Vec<f64> = theta
+                .iter()
+                .map(|th| th.iter().zip(&x).map(|(a, b)| a * b).sum())
+                .collect();
+            let arm = bandit.select_arm(&x);
+            let reward = expected[arm] + 0.1 * sample_standard_normal(&mut rng);
+            bandit.update(arm, &x, reward);
+            regret[t * 2 / horizon] +=
+                expected.iter().copied().fold(f64::NEG_INFINITY, f64::max) - expected[arm];
+        }
+        assert!(regret[1] < 0.5 * regret[0], "regret {regret:?}");
+        assert!(
+            regret[1] / ((horizon / 2) as f64) < 0.02,
+            "regret {regret:?}"
+        );
+        let theta0 = bandit.theta(0);
+        assert!((theta0[0] - 1.0).abs() < 0.2, "theta {theta0:?}");
+    }
+
+    #[test]
+    fn constructors_reject_empty_arms_and_context() {
+        let schedule = EpsilonSchedule::Constant(0.1);
+        assert!(EpsilonGreedy::new(0, schedule, 0).is_err());
+        assert!(Ucb1::new(0, 1.0).is_err());
+        assert!(UcbV::new(0, 1.0).is_err());
+        assert!(BetaThompson::new(0, 0).is_err());
+        assert!(GaussianThompson::new(0, 0.25, 0).is_err());
+        assert!(Exp3::with_horizon(0, 100, 0).is_err());
+        assert!(LinUcb::new(0, 3, 0.5).is_err());
+        assert!(LinUcb::new(2, 0, 0.5).is_err());
+
+        // Một arm, một chiều: chọn và cập nhật được mà không panic
+        let mut greedy = EpsilonGreedy::new(1, EpsilonSchedule::Constant(1.0), 0).unwrap();
+        assert_eq!(greedy.select_arm(&[]), 0);
+        greedy.update(0, &[], 1.0);
+        let mut linucb = LinUcb::new(1, 1, 0.5).unwrap();
+        assert_eq!(linucb.select_arm(&[1.0]), 0);
+        linucb.update(0, &[1.0], 1.0);
+    }
+
+    fn flow(vedana: Vedana) -> EpistemologicalFlow {
+        let mut flow = EpistemologicalFlow::from_bytes(Bytes::from_static(b"state"));
+        flow.vedana = Some(vedana);
+        flow
+    }
+
+    #[test]
+    fn bandit_policy_routes_actions_by_flow_features() {
+        let arms = vec![Action::Explore, Action::Exploit];
+        let dims = FlowFeatureExtractor::new(8, Vec::new()).dims();
+        let mut policy = BanditPolicy::new(arms, LinUcb::new(2, dims, 0.3).unwrap()).unwrap();
+        let pleasant = flow(Vedana::Pleasant { karma_weight: 1.0 });
+        let unpleasant = flow(Vedana::Unpleasant { karma_weight: 1.0 });
+        // Khám phá khi cảm thọ khó chịu, khai thác khi dễ chịu
+        for _ in 0..100 {
+            for (flow, good) in [(&pleasant, Action::Exploit), (&unpleasant, Action::Explore)] {
+                let action = policy.select_action(flow, 0.0);
+                let reward = if action == good { 1.0 } else { 0.0 };
+                policy.update(flow, reward);
+            }
+        }
+        assert_eq!(policy.select_action(&pleasant, 0.0), Action::Exploit);
+        assert_eq!(policy.select_action(&unpleasant, 0.0), Action::Explore);
+    }
+}
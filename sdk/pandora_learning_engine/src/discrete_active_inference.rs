@@ -0,0 +1,669 @@
+//! Discrete POMDP active inference (A/B/C/D generative model).
+//!
+//! - `A[o, s] = P(o | s)`: likelihood, one column per hidden state.
+//! - `B[u][s', s] = P(s' | s, u)`: one transition matrix per action.
+//! - `C[o]`: log-preferences over observations (normalized with a softmax).
+//! - `D[s]`: prior over the initial hidden state.
+//!
+//! State inference minimizes variational free energy, policies are scored by the
+//! expected free energy `G = risk + ambiguity` and selected through
+//! `q(π) = softmax(-γ·G)`. `A` and `B` can be learned from Dirichlet counts.
+
+use ndarray::{Array1, Array2};
+use pandora_error::PandoraError;
+
+const EPS: f64 = 1e-16;
+
+/// Upper bound on the number of enumerated policies (`actions^horizon`).
+pub const MAX_POLICIES: usize = 1 << 16;
+
+fn ln(x: f64) -> f64 {
+    x.max(EPS).ln()
+}
+
+fn softmax(logits: &Array1<f64>) -> Array1<f64> {
+    let max = logits.fold(f64::NEG_INFINITY, |m, &v| m.max(v));
+    let exp = logits.mapv(|v| (v - max).exp());
+    let total = exp.sum();
+    exp / total
+}
+
+fn normalize_columns(m: &Array2<f64>) -> Array2<f64> {
+    let mut out = m.clone();
+    for mut column in out.columns_mut() {
+        let total = column.sum();
+        if total > 0.0 {
+            column.mapv_inplace(|v| v / total);
+        } else {
+            let n = column.len() as f64;
+            column.fill(1.0 / n);
+        }
+    }
+    out
+}
+
+fn entropy(p: impl IntoIterator<Item = f64>) -> f64 {
+    p.into_iter()
+        .map(|v| if v > 0.0 { -v * v.ln() } else { 0.0 })
+        .sum()
+}
+
+/// Model shapes or agent configuration that cannot be used.
+pub(crate) fn invalid(msg: impl Into<String>) -> PandoraError {
+    PandoraError::config(msg)
+}
+
+/// An observation, action or belief vector that does not fit the model.
+pub(crate) fn invalid_input(msg: impl Into<String>) -> PandoraError {
+    PandoraError::InvalidSkillInput {
+        skill_name: "discrete_active_inference".to_string(),
+        message: msg.into(),
+    }
+}
+
+/// The A/B/C/D generative model of a single-factor discrete POMDP.
+#[derive(Debug, Clone)]
+pub struct DiscreteGenerativeModel {
+    a: Array2<f64>,
+    b: Vec<Array2<f64>>,
+    c: Array1<f64>,
+    d: Array1<f64>,
+}
+
+impl DiscreteGenerativeModel {
+    /// Validates the shapes and normalizes `A`, `B` (column-wise) and `D`.
+    pub fn new(
+        a: Array2<f64>,
+        b: Vec<Array2<f64>>,
+        c: Array1<f64>,
+        d: Array1<f64>,
+    ) -> Result<Self, PandoraError> {
+        let (num_obs, num_states) = a.dim();
+        if num_obs == 0 || num_states == 0 || b.is_empty() {
+            return Err(invalid(
+                "Generative model needs observations, states and actions",
+            ));
+        }
+        if let Some(bad) = b.iter().position(|m| m.dim() != (num_states, num_states)) {
+            return Err(invalid(format!(
+                "B[{bad}] must be {num_states}x{num_states}"
+            )));
+        }
+        if c.len() != num_obs || d.len() != num_states {
+            return Err(invalid(format!(
+                "C must have {num_obs} entries and D {num_states} entries"
+            )));
+        }
+        let mut all = a.iter().chain(b.iter().flatten()).chain(d.iter());
+        if all.any(|v| !v.is_finite() || *v < 0.0) {
+            return Err(invalid("A, B and D must be finite and non-negative"));
+        }
+        let d_total = d.sum();
+        let d = if d_total > 0.0 {
+            d / d_total
+        } else {
+            Array1::from_elem(num_states, 1.0 / num_states as f64)
+        };
+        Ok(Self {
+            a: normalize_columns(&a),
+            b: b.iter().map(normalize_columns).collect(),
+            c,
+            d,
+        })
+    }
+
+    /// Likelihood `A[o, s]`, column-normalized.
+    pub fn a(&self) -> &Array2<f64> {
+        &self.a
+    }
+
+    /// Transition matrices `B[u]`, column-normalized.
+    pub fn b(&self) -> &[Array2<f64>] {
+        &self.b
+    }
+
+    /// Log-preferences `C` over observations.
+    pub fn c(&self) -> &Array1<f64> {
+        &self.c
+    }
+
+    /// Normalized initial state prior `D`.
+    pub fn d(&self) -> &Array1<f64> {
+        &self.d
+    }
+
+    pub fn num_observations(&self) -> usize {
+        self.a.nrows()
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.a.ncols()
+    }
+
+    pub fn num_actions(&self) -> usize {
+        self.b.len()
+    }
+
+    /// Preferred observation distribution `softmax(C)`.
+    pub fn preferred_observations(&self) -> Array1<f64> {
+        softmax(&self.c)
+    }
+
+    fn check_observation(&self, observation: usize) -> Result<(), PandoraError> {
+        if observation >= self.num_observations() {
+            return Err(invalid_input(format!(
+                "Observation {observation} out of range"
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_states(&self, name: &str, q: &Array1<f64>) -> Result<(), PandoraError> {
+        if q.len() != self.num_states() {
+            return Err(invalid_input(format!(
+                "{name} has {} entries, expected {}",
+                q.len(),
+                self.num_states()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Posterior over states given a single observation and a prior:
+    /// `q(s) = σ(ln A[o, s] + ln prior(s))`.
+    pub fn infer_states(
+        &self,
+        observation: usize,
+        prior: &Array1<f64>,
+    ) -> Result<Array1<f64>, PandoraError> {
+        self.check_observation(observation)?;
+        self.check_states("Prior", prior)?;
+        Ok(self.posterior(observation, prior))
+    }
+
+    fn posterior(&self, observation: usize, prior: &Array1<f64>) -> Array1<f64> {
+        let logits = Array1::from_iter(
+            (0..self.num_states()).map(|s| ln(self.a[[observation, s]]) + ln(prior[s])),
+        );
+        softmax(&logits)
+    }
+
+    /// Variational free energy `F = E_q[ln q(s) - ln A[o, s] - ln prior(s)]`.
+    /// At the exact posterior it equals the surprise `-ln P(o)`.
+    pub fn variational_free_energy(
+        &self,
+        qs: &Array1<f64>,
+        observation: usize,
+        prior: &Array1<f64>,
+    ) -> Result<f64, PandoraError> {
+        self.check_observation(observation)?;
+        self.check_states("Beliefs", qs)?;
+        self.check_states("Prior", prior)?;
+        Ok(qs
+            .iter()
+            .enumerate()
+            .filter(|(_, &q)| q > 0.0)
+            .map(|(s, &q)| q * (q.ln() - ln(self.a[[observation, s]]) - ln(prior[s])))
+            .sum())
+    }
+
+    /// Expected free energy of a policy starting from beliefs `qs`.
+    pub fn evaluate_policy(
+        &self,
+        qs: &Array1<f64>,
+        policy: &[usize],
+    ) -> Result<PolicyEvaluation, PandoraError> {
+        self.check_states("Beliefs", qs)?;
+        if let Some(action) = policy.iter().find(|&&u| u >= self.num_actions()) {
+            return Err(invalid_input(format!("Action {action} out of range")));
+        }
+        Ok(self.policy_evaluation(qs, policy))
+    }
+
+    fn policy_evaluation(&self, qs: &Array1<f64>, policy: &[usize]) -> PolicyEvaluation {
+        let preferred = self.preferred_observations();
+        let ln_preferred = preferred.mapv(ln);
+        let state_entropy = Array1::from_iter(
+            self.a
+                .columns()
+                .into_iter()
+                .map(|col| entropy(col.iter().copied())),
+        );
+
+        let mut evaluation = PolicyEvaluation::default();
+        let mut qs = qs.clone();
+        for &action in policy {
+            qs = self.b[action].dot(&qs);
+            let qo = self.a.dot(&qs);
+            // Risk: KL[q(o|π) || P(o)], divergence from preferred outcomes
+            evaluation.risk += qo
+                .iter()
+                .zip(ln_preferred.iter())
+                .filter(|(&q, _)| q > 0.0)
+                .map(|(&q, &lp)| q * (q.ln() - lp))
+                .sum::<f64>();
+            // Ambiguity: E_q(s|π) H[P(o|s)]
+            let ambiguity = qs.dot(&state_entropy);
+            evaluation.ambiguity += ambiguity;
+            // Expected information gain: H[q(o|π)] - ambiguity
+            evaluation.information_gain += entropy(qo.iter().copied()) - ambiguity;
+        }
+        evaluation
+    }
+}
+
+/// Expected free energy decomposition for one policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PolicyEvaluation {
+    pub risk: f64,
+    pub ambiguity: f64,
+    /// Mutual information between predicted states and outcomes (epistemic value).
+    pub information_gain: f64,
+}
+
+impl PolicyEvaluation {
+    pub fn expected_free_energy(&self) -> f64 {
+        self.risk + self.ambiguity
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscreteAgentConfig {
+    /// Length of the enumerated action sequences.
+    pub policy_horizon: usize,
+    /// Precision `γ` of the policy posterior `softmax(-γ·G)`.
+    pub policy_precision: f64,
+    /// Scale of the Dirichlet count updates.
+    pub learning_rate: f64,
+}
+
+impl Default for DiscreteAgentConfig {
+    fn default() -> Self {
+        Self {
+            policy_horizon: 1,
+            policy_precision: 16.0,
+            learning_rate: 1.0,
+        }
+    }
+}
+
+/// Perception–action loop over a `DiscreteGenerativeModel`.
+#[derive(Debug, Clone)]
+pub struct DiscreteActiveInferenceAgent {
+    model: DiscreteGenerativeModel,
+    config: DiscreteAgentConfig,
+    policies: Vec<Vec<usize>>,
+    /// Prior over the current state (`D`, then `B[u]·q(s)` after each action).
+    prior: Array1<f64>,
+    qs: Array1<f64>,
+    previous_qs: Option<Array1<f64>>,
+    last_action: Option<usize>,
+    a_counts: Option<Array2<f64>>,
+    b_counts: Option<Vec<Array2<f64>>>,
+}
+
+impl DiscreteActiveInferenceAgent {
+    pub fn new(
+        model: DiscreteGenerativeModel,
+        config: DiscreteAgentConfig,
+    ) -> Result<Self, PandoraError> {
+        let num_actions = model.num_actions();
+        let count = u32::try_from(config.policy_horizon)
+            .ok()
+            .and_then(|h| num_actions.checked_pow(h))
+            .filter(|&n| n <= MAX_POLICIES && config.policy_horizon > 0)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "{num_actions}^{} policies exceed the limit of {MAX_POLICIES}",
+                    config.policy_horizon
+                ))
+            })?;
+        let policies = (0..count)
+            .map(|mut index| {
+                let mut policy = vec![0; config.policy_horizon];
+                for slot in policy.iter_mut().rev() {
+                    *slot = index % num_actions;
+                    index /= num_actions;
+                }
+                policy
+            })
+            .collect();
+        Ok(Self {
+            prior: model.d.clone(),
+            qs: model.d.clone(),
+            model,
+            config,
+            policies,
+            previous_qs: None,
+            last_action: None,
+            a_counts: None,
+            b_counts: None,
+        })
+    }
+
+    /// Learns `A` from Dirichlet concentration parameters (same shape as `A`).
+    pub fn with_dirichlet_a(mut self, counts: Array2<f64>) -> Result<Self, PandoraError> {
+        if counts.dim() != self.model.a.dim() || counts.iter().any(|&v| v <= 0.0) {
+            return Err(invalid("Dirichlet A counts must match A and be positive"));
+        }
+        self.model.a = normalize_columns(&counts);
+        self.a_counts = Some(counts);
+        Ok(self)
+    }
+
+    /// Learns `B` from Dirichlet concentration parameters (one matrix per action).
+    pub fn with_dirichlet_b(mut self, counts: Vec<Array2<f64>>) -> Result<Self, PandoraError> {
+        let shapes_match = counts.len() == self.model.b.len()
+            && counts
+                .iter()
+                .zip(&self.model.b)
+                .all(|(c, b)| c.dim() == b.dim());
+        if !shapes_match || counts.iter().flatten().any(|&v| v <= 0.0) {
+            return Err(invalid("Dirichlet B counts must match B and be positive"));
+        }
+        self.model.b = counts.iter().map(normalize_columns).collect();
+        self.b_counts = Some(counts);
+        Ok(self)
+    }
+
+    pub fn model(&self) -> &DiscreteGenerativeModel {
+        &self.model
+    }
+
+    pub fn beliefs(&self) -> &Array1<f64> {
+        &self.qs
+    }
+
+    pub fn policies(&self) -> &[Vec<usize>] {
+        &self.policies
+    }
+
+    /// Forgets the trajectory and restarts from the prior `D` (learned parameters are kept).
+    pub fn reset(&mut self) {
+        self.prior = self.model.d.clone();
+        self.qs = self.model.d.clone();
+        self.previous_qs = None;
+        self.last_action = None;
+    }
+
+    /// Replaces the preferences `C` (e.g. a subgoal imposed by a higher level).
+    pub fn set_preferences(&mut self, c: Array1<f64>) -> Result<(), PandoraError> {
+        if c.len() != self.model.num_observations() {
+            return Err(invalid_input(format!(
+                "C must have {} entries",
+                self.model.num_observations()
+            )));
+        }
+        self.model.c = c;
+        Ok(())
+    }
+
+    /// Replaces the prior over the current state; the next observation is
+    /// combined with `d` instead of the prediction from the last action.
+    pub fn set_state_prior(&mut self, d: Array1<f64>) -> Result<(), PandoraError> {
+        let total = d.sum();
+        if d.len() != self.model.num_states()
+            || d.iter().any(|v| !v.is_finite() || *v < 0.0)
+            || total <= 0.0
+        {
+            return Err(invalid_input(
+                "State prior must be a non-negative vector over states",
+            ));
+        }
+        self.prior = d / total;
+        Ok(())
+    }
+
+    /// Updates the state posterior with a new observation and, when enabled,
+    /// accumulates Dirichlet counts for `A` and for the last transition in `B`.
+    pub fn infer_states(&mut self, observation: usize) -> Result<&Array1<f64>, PandoraError> {
+        self.qs = self.model.infer_states(observation, &self.prior)?;
+
+        let lr = self.config.learning_rate;
+        if let Some(counts) = self.a_counts.as_mut() {
+            counts.row_mut(observation).scaled_add(lr, &self.qs);
+            self.model.a = normalize_columns(counts);
+        }
+        if let (Some(counts), Some(action), Some(previous)) = (
+            self.b_counts.as_mut(),
+            self.last_action,
+            self.previous_qs.as_ref(),
+        ) {
+            let outer = self
+                .qs
+                .view()
+                .insert_axis(ndarray::Axis(1))
+                .dot(&previous.view().insert_axis(ndarray::Axis(0)));
+            counts[action].scaled_add(lr, &outer);
+            self.model.b[action] = normalize_columns(&counts[action]);
+        }
+        Ok(&self.qs)
+    }
+
+    /// Policy posterior `q(π) = softmax(-γ·G(π))` with the per-policy evaluations.
+    pub fn infer_policies(&self) -> (Array1<f64>, Vec<PolicyEvaluation>) {
+        let evaluations: Vec<PolicyEvaluation> = self
+            .policies
+            .iter()
+            .map(|policy| self.model.policy_evaluation(&self.qs, policy))
+            .collect();
+        let logits = Array1::from_iter(
+            evaluations
+                .iter()
+                .map(|e| -self.config.policy_precision * e.expected_free_energy()),
+        );
+        (softmax(&logits), evaluations)
+    }
+
+    /// Chooses the action with the highest marginal probability under `q(π)` and
+    /// rolls the state prior forward with `B[action]`.
+    pub fn select_action(&mut self) -> usize {
+        let (q_pi, _) = self.infer_policies();
+        let mut marginal = vec![0.0; self.model.num_actions()];
+        for (policy, p) in self.policies.iter().zip(q_pi.iter()) {
+            marginal[policy[0]] += p;
+        }
+        let action = marginal
+            .iter()
+            .enumerate()
+            .fold((0, f64::NEG_INFINITY), |best, (i, &p)| {
+                if p > best.1 {
+                    (i, p)
+                } else {
+                    best
+                }
+            })
+            .0;
+
+        self.commit_action(action);
+        action
+    }
+
+    /// Records an action chosen outside the agent (e.g. during exploration) so that
+    /// the next inference step and the `B` update use it.
+    pub fn record_action(&mut self, action: usize) -> Result<(), PandoraError> {
+        if action >= self.model.num_actions() {
+            return Err(invalid_input(format!("Action {action} out of range")));
+        }
+        self.commit_action(action);
+        Ok(())
+    }
+
+    fn commit_action(&mut self, action: usize) {
+        self.prior = self.model.b[action].dot(&self.qs);
+        self.previous_qs = Some(self.qs.clone());
+        self.last_action = Some(action);
+    }
+
+    /// One perception–action cycle.
+    pub fn step(&mut self, observation: usize) -> Result<usize, PandoraError> {
+        self.infer_states(observation)?;
+        Ok(self.select_action())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    /// Two hidden contexts whose cue observations carry no information about them.
+    /// Observations: [cue_left, cue_right, blank].
+    fn cue_model() -> DiscreteGenerativeModel {
+        cue_model_with(array![[0.5, 0.5], [0.5, 0.5], [0.0, 0.0]])
+    }
+
+    fn cue_model_with(a: Array2<f64>) -> DiscreteGenerativeModel {
+        let identity = Array2::eye(2);
+        DiscreteGenerativeModel::new(
+            a,
+            vec![identity.clone(), identity],
+            array![0.0, 0.0, 0.0],
+            array![0.5, 0.5],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_state_inference_is_bayesian() {
+        let model = DiscreteGenerativeModel::new(
+            array![[0.9, 0.2], [0.1, 0.8]],
+            vec![Array2::eye(2)],
+            array![0.0, 0.0],
+            array![0.5, 0.5],
+        )
+        .unwrap();
+        let qs = model.infer_states(0, model.d()).unwrap();
+        let evidence = 0.5 * 0.9 + 0.5 * 0.2;
+        assert!((qs[0] - 0.45 / evidence).abs() < 1e-9);
+        // At the exact posterior, F equals the surprise -ln P(o)
+        let f = model.variational_free_energy(&qs, 0, model.d()).unwrap();
+        assert!((f + evidence.ln()).abs() < 1e-9);
+        assert!(
+            model
+                .variational_free_energy(model.d(), 0, model.d())
+                .unwrap()
+                > f
+        );
+    }
+
+    #[test]
+    fn test_risk_prefers_preferred_outcomes() {
+        // Action 0 stays in state 0 (obs 0), action 1 moves to state 1 (obs 1, preferred)
+        let model = DiscreteGenerativeModel::new(
+            Array2::eye(2),
+            vec![Array2::eye(2), array![[0.0, 0.0], [1.0, 1.0]]],
+            array![0.0, 3.0],
+            array![1.0, 0.0],
+        )
+        .unwrap();
+        let mut agent =
+            DiscreteActiveInferenceAgent::new(model, DiscreteAgentConfig::default()).unwrap();
+        agent.infer_states(0).unwrap();
+        let (q_pi, evals) = agent.infer_policies();
+        assert!(evals[1].risk < evals[0].risk);
+        assert_eq!(evals[0].ambiguity, 0.0);
+        assert!(q_pi[1] > 0.99);
+        assert_eq!(agent.select_action(), 1);
+    }
+
+    #[test]
+    fn test_ambiguity_drives_epistemic_actions() {
+        // Same world, but the cue now reveals the context
+        let model = cue_model_with(array![[1.0, 0.0], [0.0, 1.0], [0.0, 0.0]]);
+        let informative = model.evaluate_policy(model.d(), &[1]).unwrap();
+        let blind = cue_model().evaluate_policy(model.d(), &[1]).unwrap();
+        assert!(informative.ambiguity < blind.ambiguity);
+        assert!((informative.information_gain - 2f64.ln()).abs() < 1e-9);
+        assert!(blind.information_gain.abs() < 1e-9);
+        assert!(informative.expected_free_energy() < blind.expected_free_energy());
+    }
+
+    #[test]
+    fn test_dirichlet_learning_of_a_and_b() {
+        // The true process alternates between two states with noiseless observations
+        let model = DiscreteGenerativeModel::new(
+            Array2::eye(2),
+            vec![array![[0.0, 1.0], [1.0, 0.0]]],
+            array![0.0, 0.0],
+            array![1.0, 0.0],
+        )
+        .unwrap();
+        let config = DiscreteAgentConfig::default();
+        let flat = Array2::from_elem((2, 2), 1.0);
+
+        // A is learned while B is known
+        let mut agent = DiscreteActiveInferenceAgent::new(model.clone(), config.clone())
+            .unwrap()
+            .with_dirichlet_a(flat.clone())
+            .unwrap();
+        assert_eq!(agent.model().a()[[0, 0]], 0.5);
+        for t in 0..20 {
+            agent.step(t % 2).unwrap();
+        }
+        let a = agent.model().a();
+        assert!(a[[0, 0]] > 0.9 && a[[1, 1]] > 0.9, "{a:?}");
+
+        // B is learned while A is known
+        let mut agent = DiscreteActiveInferenceAgent::new(model, config)
+            .unwrap()
+            .with_dirichlet_b(vec![flat])
+            .unwrap();
+        for t in 0..20 {
+            agent.step(t % 2).unwrap();
+        }
+        let b = &agent.model().b()[0];
+        assert!(b[[1, 0]] > 0.9 && b[[0, 1]] > 0.9, "{b:?}");
+
+        assert!(agent.infer_states(5).is_err());
+        let too_many = DiscreteAgentConfig {
+            policy_horizon: 40,
+            ..Default::default()
+        };
+        assert!(DiscreteActiveInferenceAgent::new(cue_model(), too_many).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_arguments_are_input_errors() {
+        let model = cue_model();
+        let is_input_error = |e: PandoraError| matches!(e, PandoraError::InvalidSkillInput { .. });
+        assert!(is_input_error(
+            model.infer_states(3, model.d()).unwrap_err()
+        ));
+        assert!(is_input_error(
+            model.infer_states(0, &array![1.0]).unwrap_err()
+        ));
+        assert!(is_input_error(
+            model
+                .variational_free_energy(&array![1.0, 0.0, 0.0], 0, model.d())
+                .unwrap_err()
+        ));
+        assert!(is_input_error(
+            model
+                .variational_free_energy(model.d(), 7, model.d())
+                .unwrap_err()
+        ));
+        assert!(is_input_error(
+            model.evaluate_policy(model.d(), &[0, 2]).unwrap_err()
+        ));
+        assert!(is_input_error(
+            model.evaluate_policy(&array![1.0], &[0]).unwrap_err()
+        ));
+
+        let mut agent =
+            DiscreteActiveInferenceAgent::new(model, DiscreteAgentConfig::default()).unwrap();
+        assert!(is_input_error(agent.record_action(2).unwrap_err()));
+        assert!(is_input_error(
+            agent.set_preferences(array![0.0]).unwrap_err()
+        ));
+
+        // Malformed models are configuration errors
+        let bad = DiscreteGenerativeModel::new(
+            Array2::eye(2),
+            vec![Array2::eye(3)],
+            array![0.0, 0.0],
+            array![0.5, 0.5],
+        );
+        assert!(matches!(bad, Err(PandoraError::Config { .. })));
+    }
+}
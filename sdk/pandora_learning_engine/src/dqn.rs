@@ -0,0 +1,272 @@
+//! Thành phần Deep Q-Network dùng bởi `NeuralQValueEstimator`.
+//!
+//! - `FlowFeatureExtractor`: biến `EpistemologicalFlow` thành vector đặc trưng cố định
+//!   (vedana, SDR của sanna, related_eidos, intent one-hot).
+//! - `QNetwork`: MLP một tầng ẩn (ReLU) cho ra một Q-value cho mỗi hành động.
+//! - `Transition`: một bước `(s, a, r, s', done)` lưu trong `PriorityExperienceBuffer`.
+
+use pandora_core::ontology::{EpistemologicalFlow, Vedana};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Một chuyển trạng thái cho học Q off-policy.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    pub flow: EpistemologicalFlow,
+    pub action: String,
+    pub reward: f64,
+    pub next_flow: EpistemologicalFlow,
+    /// `true` nếu `next_flow` là trạng thái kết thúc (không bootstrap).
+    pub done: bool,
+}
+
+/// Cấu hình cho `NeuralQValueEstimator`.
+#[derive(Debug, Clone)]
+pub struct DqnConfig {
+    /// Không gian hành động; thứ tự xác định đầu ra của mạng.
+    pub actions: Vec<&'static str>,
+    pub hidden_dims: usize,
+    /// Số bucket để gập các chỉ số SDR (sanna và related_eidos) vào vector đặc trưng.
+    pub sdr_buckets: usize,
+    pub learning_rate: f64,
+    pub discount_factor: f64,
+    /// Sao chép mạng online sang mạng target sau mỗi ngần này bước huấn luyện.
+    pub target_update_interval: u64,
+    /// Double-DQN: chọn hành động tốt nhất của `s'` bằng mạng online, đánh giá bằng target.
+    pub double_dqn: bool,
+    /// Ngưỡng của Huber loss (lỗi TD lớn hơn được cắt gradient).
+    pub huber_delta: f64,
+    pub seed: u64,
+}
+
+impl DqnConfig {
+    /// Cấu hình với không gian hành động GridWorld mặc định.
+    pub fn grid_world(learning_rate: f64, discount_factor: f64) -> Self {
+        Self {
+            actions: vec![
+                "unlock_door",
+                "pick_up_key",
+                "move_forward",
+                "explore",
+                "noop",
+            ],
+            hidden_dims: 32,
+            sdr_buckets: 32,
+            learning_rate,
+            discount_factor,
+            target_update_interval: 100,
+            double_dqn: true,
+            huber_delta: 1.0,
+            seed: 0,
+        }
+    }
+
+    pub fn with_actions(mut self, actions: Vec<&'static str>) -> Self {
+        self.actions = actions;
+        self
+    }
+}
+
+/// Trích vector đặc trưng cố định từ một `EpistemologicalFlow`.
+///
+/// Bố cục: `[vedana (4) | sanna (sdr_buckets) | related (1 + sdr_buckets) | intent (|intents| + 1)]`.
+#[derive(Debug, Clone)]
+pub struct FlowFeatureExtractor {
+    sdr_buckets: usize,
+    intents: Vec<&'static str>,
+}
+
+impl FlowFeatureExtractor {
+    pub fn new(sdr_buckets: usize, intents: Vec<&'static str>) -> Self {
+        Self {
+            sdr_buckets: sdr_buckets.max(1),
+            intents,
+        }
+    }
+
+    pub fn dims(&self) -> usize {
+        4 + self.sdr_buckets + 1 + self.sdr_buckets + self.intents.len() + 1
+    }
+
+    pub fn extract(&self, flow: &EpistemologicalFlow) -> Vec<f64> {
+        let b = self.sdr_buckets;
+        let mut features = vec![0.0; self.dims()];
+
+        // Vedana: one-hot cảm thọ + trọng số nghiệp có dấu
+        match flow.vedana {
+            Some(Vedana::Pleasant { karma_weight }) => {
+                features[0] = 1.0;
+                features[3] = karma_weight as f64;
+            }
+            Some(Vedana::Unpleasant { karma_weight }) => {
+                features[1] = 1.0;
+                features[3] = -(karma_weight as f64).abs();
+            }
+            Some(Vedana::Neutral) => features[2] = 1.0,
+            None => {}
+        }
+
+        // Sanna: gập SDR vào b bucket
+        let sanna = &mut features[4..4 + b];
+        if let Some(ref eidos) = flow.sanna {
+            for &i in &eidos.active_indices {
+                sanna[i as usize % b] = 1.0;
+            }
+        }
+
+        // Related eidos: số lượng (chuẩn hóa) + tần suất bucket trên các eidos
+        let offset = 4 + b;
+        if let Some(ref related) = flow.related_eidos {
+            features[offset] = (related.len() as f64 / 4.0).min(1.0);
+            let n = related.len().max(1) as f64;
+            for eidos in related.iter() {
+                let mut seen = vec![false; b];
+                for &i in &eidos.active_indices {
+                    seen[i as usize % b] = true;
+                }
+                for (f, hit) in features[offset + 1..offset + 1 + b].iter_mut().zip(seen) {
+                    if hit {
+                        *f += 1.0 / n;
+                    }
+                }
+            }
+        }
+
+        // Intent one-hot, vị trí cuối cho intent không nằm trong danh sách
+        let offset = offset + 1 + b;
+        if let Some(ref intent) = flow.sankhara {
+            let slot = self
+                .intents
+                .iter()
+                .position(|a| *a == intent.as_ref())
+                .unwrap_or(self.intents.len());
+            features[offset + slot] = 1.0;
+        }
+        features
+    }
+}
+
+/// MLP `input -> hidden (ReLU) -> actions`.
+#[derive(Debug, Clone)]
+pub struct QNetwork {
+    input_dims: usize,
+    hidden_dims: usize,
+    outputs: usize,
+    w1: Vec<f64>,
+    b1: Vec<f64>,
+    w2: Vec<f64>,
+    b2: Vec<f64>,
+}
+
+impl QNetwork {
+    pub fn new(input_dims: usize, hidden_dims: usize, outputs: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut init = |fan_in: usize, len: usize| -> Vec<f64> {
+            // Khởi tạo He cho ReLU
+            let bound = (6.0 / fan_in.max(1) as f64).sqrt();
+            (0..len).map(|_| rng.gen_range(-bound..bound)).collect()
+        };
+        Self {
+            input_dims,
+            hidden_dims,
+            outputs,
+            w1: init(input_dims, hidden_dims * input_dims),
+            b1: vec![0.0; hidden_dims],
+            w2: init(hidden_dims, outputs * hidden_dims)
+                .into_iter()
+                .map(|w| w * 0.1)
+                .collect(),
+            b2: vec![0.0; outputs],
+        }
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    fn hidden(&self, x: &[f64]) -> Vec<f64> {
+        (0..self.hidden_dims)
+            .map(|j| {
+                let row = &self.w1[j * self.input_dims..(j + 1) * self.input_dims];
+                (row.iter().zip(x).map(|(w, v)| w * v).sum::<f64>() + self.b1[j]).max(0.0)
+            })
+            .collect()
+    }
+
+    fn head(&self, hidden: &[f64]) -> Vec<f64> {
+        (0..self.outputs)
+            .map(|k| {
+                let row = &self.w2[k * self.hidden_dims..(k + 1) * self.hidden_dims];
+                row.iter().zip(hidden).map(|(w, h)| w * h).sum::<f64>() + self.b2[k]
+            })
+            .collect()
+    }
+
+    pub fn forward(&self, x: &[f64]) -> Vec<f64> {
+        self.head(&self.hidden(x))
+    }
+
+    /// Một bước gradient descent trên `grad`·Q(x, action) (chỉ đầu ra `action`).
+    pub fn backward(&mut self, x: &[f64], action: usize, grad: f64, lr: f64) {
+        let hidden = self.hidden(x);
+        let row = action * self.hidden_dims..(action + 1) * self.hidden_dims;
+        for (j, &h) in hidden.iter().enumerate() {
+            if h <= 0.0 {
+                continue;
+            }
+            let g_hidden = grad * self.w2[row.start + j];
+            let w1_row = j * self.input_dims..(j + 1) * self.input_dims;
+            for (w, v) in self.w1[w1_row].iter_mut().zip(x) {
+                *w -= lr * g_hidden * v;
+            }
+            self.b1[j] -= lr * g_hidden;
+        }
+        for (w, h) in self.w2[row].iter_mut().zip(&hidden) {
+            *w -= lr * grad * h;
+        }
+        self.b2[action] -= lr * grad;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pandora_core::ontology::DataEidos;
+
+    #[test]
+    fn test_feature_layout() {
+        let extractor = FlowFeatureExtractor::new(8, vec!["a", "b"]);
+        assert_eq!(extractor.dims(), 4 + 8 + 1 + 8 + 3);
+
+        let mut eidos = DataEidos {
+            active_indices: Default::default(),
+            dimensionality: 64,
+        };
+        eidos.active_indices.insert(9);
+        let mut flow = EpistemologicalFlow {
+            vedana: Some(Vedana::Unpleasant { karma_weight: 0.5 }),
+            sanna: Some(eidos),
+            ..Default::default()
+        };
+        flow.set_static_intent("b");
+
+        let f = extractor.extract(&flow);
+        assert_eq!(&f[..4], &[0.0, 1.0, 0.0, -0.5]);
+        assert_eq!(f[4 + 1], 1.0);
+        assert_eq!(f[4 + 8 + 1 + 8 + 1], 1.0);
+
+        flow.set_static_intent("zzz");
+        assert_eq!(extractor.extract(&flow)[4 + 8 + 1 + 8 + 2], 1.0);
+    }
+
+    #[test]
+    fn test_backward_moves_q_towards_target() {
+        let mut net = QNetwork::new(3, 8, 2, 1);
+        let x = [1.0, 0.5, -0.5];
+        for _ in 0..200 {
+            let q = net.forward(&x)[1];
+            net.backward(&x, 1, q - 2.0, 0.05);
+        }
+        assert!((net.forward(&x)[1] - 2.0).abs() < 1e-3);
+    }
+}
@@ -1,4 +1,7 @@
 use pandora_core::ontology::EpistemologicalFlow;
+use pandora_error::PandoraError;
+use rand::Rng;
+use std::collections::VecDeque;
 
 #[derive(Debug, Clone)]
 pub struct ExperienceSample {
@@ -6,25 +9,29 @@ pub struct ExperienceSample {
     pub reward: f64,
 }
 
+/// Bộ đệm vòng FIFO: khi đầy, mẫu cũ nhất bị loại bỏ trong O(1).
 #[derive(Debug, Default)]
 pub struct ExperienceBuffer {
-    samples: Vec<ExperienceSample>,
+    samples: VecDeque<ExperienceSample>,
     capacity: usize,
 }
 
 impl ExperienceBuffer {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            samples: Vec::with_capacity(capacity),
+            samples: VecDeque::with_capacity(capacity),
             capacity,
         }
     }
 
     pub fn push(&mut self, sample: ExperienceSample) {
+        if self.capacity == 0 {
+            return;
+        }
         if self.samples.len() >= self.capacity {
-            self.samples.remove(0);
+            self.samples.pop_front();
         }
-        self.samples.push(sample);
+        self.samples.push_back(sample);
     }
 
     pub fn len(&self) -> usize {
@@ -33,65 +40,309 @@ impl ExperienceBuffer {
     pub fn is_empty(&self) -> bool {
         self.samples.is_empty()
     }
+    /// Duyệt các mẫu từ cũ nhất đến mới nhất.
     pub fn iter(&self) -> impl Iterator<Item = &ExperienceSample> {
         self.samples.iter()
     }
 }
 
-/// Priority sampling experience buffer (simplified SumTree-free version)
+/// Cây tổng (kèm cây min) trên `capacity` lá: cập nhật, tổng, min và tìm theo
+/// tổng tiền tố đều O(log n).
+#[derive(Debug, Clone)]
+pub struct SumTree {
+    leaves: usize,
+    sums: Vec<f64>,
+    mins: Vec<f64>,
+}
+
+impl SumTree {
+    pub fn new(capacity: usize) -> Self {
+        let leaves = capacity.max(1).next_power_of_two();
+        Self {
+            leaves,
+            sums: vec![0.0; 2 * leaves],
+            mins: vec![f64::INFINITY; 2 * leaves],
+        }
+    }
+
+    /// Đặt giá trị của lá `index`. `None` (ô trống) và giá trị 0 không tham gia cây min.
+    pub fn set(&mut self, index: usize, value: Option<f64>) {
+        let mut node = index + self.leaves;
+        self.sums[node] = value.unwrap_or(0.0);
+        self.mins[node] = value.filter(|v| *v > 0.0).unwrap_or(f64::INFINITY);
+        while node > 1 {
+            node /= 2;
+            self.sums[node] = self.sums[2 * node] + self.sums[2 * node + 1];
+            self.mins[node] = self.mins[2 * node].min(self.mins[2 * node + 1]);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> f64 {
+        self.sums[index + self.leaves]
+    }
+
+    pub fn total(&self) -> f64 {
+        self.sums[1]
+    }
+
+    /// Giá trị dương nhỏ nhất trong các lá (`INFINITY` nếu không có).
+    pub fn min(&self) -> f64 {
+        self.mins[1]
+    }
+
+    /// Chỉ số lá `i` nhỏ nhất sao cho tổng các lá `0..=i` vượt quá `prefix`.
+    pub fn find_prefix_sum(&self, prefix: f64) -> usize {
+        let mut remaining = prefix.clamp(0.0, self.total());
+        let mut node = 1;
+        while node < self.leaves {
+            let left = 2 * node;
+            if remaining < self.sums[left] || self.sums[left + 1] <= 0.0 {
+                node = left;
+            } else {
+                remaining -= self.sums[left];
+                node = left + 1;
+            }
+        }
+        node - self.leaves
+    }
+}
+
+/// Lịch tuyến tính từ `start` đến `end` trong `steps` bước.
+#[derive(Debug, Clone, Copy)]
+pub struct LinearSchedule {
+    pub start: f64,
+    pub end: f64,
+    pub steps: u64,
+}
+
+impl LinearSchedule {
+    pub fn constant(value: f64) -> Self {
+        Self {
+            start: value,
+            end: value,
+            steps: 0,
+        }
+    }
+
+    pub fn value(&self, step: u64) -> f64 {
+        if self.steps == 0 || step >= self.steps {
+            return self.end;
+        }
+        self.start + (self.end - self.start) * step as f64 / self.steps as f64
+    }
+}
+
+/// Cấu hình Prioritized Experience Replay (Schaul et al., 2016).
+#[derive(Debug, Clone)]
+pub struct PrioritizedReplayConfig {
+    /// Mức độ ưu tiên hóa: 0 là lấy mẫu đều, 1 là tỉ lệ thuận hoàn toàn với priority.
+    pub alpha: LinearSchedule,
+    /// Mức bù importance-sampling, thường tăng dần về 1 khi huấn luyện.
+    pub beta: LinearSchedule,
+    /// Cộng vào |TD-error| để mọi mẫu đều có xác suất được chọn.
+    pub epsilon: f64,
+}
+
+impl Default for PrioritizedReplayConfig {
+    fn default() -> Self {
+        Self {
+            alpha: LinearSchedule::constant(0.6),
+            beta: LinearSchedule {
+                start: 0.4,
+                end: 1.0,
+                steps: 100_000,
+            },
+            epsilon: 1e-6,
+        }
+    }
+}
+
+/// Một batch lấy mẫu theo priority cùng trọng số importance-sampling đã chuẩn hóa
+/// (trọng số lớn nhất có thể là 1).
+#[derive(Debug, Clone)]
+pub struct PrioritizedBatch<T = ExperienceSample> {
+    pub indices: Vec<usize>,
+    pub samples: Vec<T>,
+    pub weights: Vec<f64>,
+}
+
+/// Priority sampling experience buffer trên bộ đệm vòng + `SumTree`.
+///
+/// Chỉ số trả về bởi `sample_index`/`sample_batch` là vị trí ô trong bộ đệm vòng;
+/// dùng chúng với `get` và `update_priorities`. Lá của cây lưu `priority^alpha` với
+/// alpha tại thời điểm priority được ghi, nên khi alpha được anneal, các priority
+/// được làm mới dần qua `update_priorities`.
+///
+/// Mặc định lưu `ExperienceSample`; các thuật toán cần cả chuyển trạng thái (ví dụ
+/// DQN với `crate::dqn::Transition`) dùng tham số kiểu khác.
 #[derive(Debug)]
-pub struct PriorityExperienceBuffer {
-    items: Vec<(ExperienceSample, f64)>, // (sample, priority)
+pub struct PriorityExperienceBuffer<T = ExperienceSample> {
+    slots: Vec<Option<T>>,
+    tree: SumTree,
+    next: usize,
+    len: usize,
     capacity: usize,
+    max_priority: f64,
+    config: PrioritizedReplayConfig,
+    steps: u64,
 }
 
-impl PriorityExperienceBuffer {
+impl<T: Clone> PriorityExperienceBuffer<T> {
     pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_config(capacity, PrioritizedReplayConfig::default())
+    }
+
+    pub fn with_config(capacity: usize, config: PrioritizedReplayConfig) -> Self {
         Self {
-            items: Vec::with_capacity(capacity),
+            slots: (0..capacity).map(|_| None).collect(),
+            tree: SumTree::new(capacity),
+            next: 0,
+            len: 0,
             capacity,
+            max_priority: 1.0,
+            config,
+            steps: 0,
         }
     }
 
-    pub fn push(&mut self, sample: ExperienceSample, priority: f64) {
-        if self.items.len() >= self.capacity {
-            self.items.remove(0);
+    pub fn alpha(&self) -> f64 {
+        self.config.alpha.value(self.steps)
+    }
+
+    pub fn beta(&self) -> f64 {
+        self.config.beta.value(self.steps)
+    }
+
+    fn scaled(&self, priority: f64) -> f64 {
+        (priority.abs() + self.config.epsilon).powf(self.alpha())
+    }
+
+    /// Thêm mẫu với priority cho trước; khi đầy, ghi đè mẫu cũ nhất. Priority không
+    /// hữu hạn (NaN, vô cực) được thay bằng priority lớn nhất từng thấy.
+    ///
+    /// Bộ đệm ghi tuần tự từ ô 0 và không bao giờ xóa ô, nên các ô có mẫu luôn là `0..len`.
+    pub fn push(&mut self, sample: T, priority: f64) {
+        if self.capacity == 0 {
+            return;
         }
-        self.items.push((sample, priority.max(1e-6)));
+        let priority = if priority.is_finite() {
+            priority.abs()
+        } else {
+            self.max_priority
+        };
+        self.max_priority = self.max_priority.max(priority);
+        let slot = self.next;
+        self.slots[slot] = Some(sample);
+        let scaled = self.scaled(priority);
+        self.tree.set(slot, Some(scaled));
+        self.next = (self.next + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+    }
+
+    /// Thêm mẫu với priority lớn nhất từng thấy, để mẫu mới được phát lại ít nhất một lần.
+    pub fn push_with_max_priority(&mut self, sample: T) {
+        self.push(sample, self.max_priority);
     }
 
     pub fn len(&self) -> usize {
-        self.items.len()
+        self.len
     }
     pub fn is_empty(&self) -> bool {
-        self.items.is_empty()
+        self.len == 0
     }
 
-    /// Sample an index proportionally to priority (O(n)).
+    /// Sample an index proportionally to priority (O(log n)).
     pub fn sample_index(&self, seed: u64) -> Option<usize> {
-        if self.items.is_empty() {
+        if self.is_empty() {
             return None;
         }
-        let total: f64 = self.items.iter().map(|(_, p)| *p).sum();
-        if total <= 0.0 {
-            return Some(0);
-        }
         // simple LCG-based rng from seed
-        let r = ((seed.wrapping_mul(6364136223846793005).wrapping_add(1)) >> 1) as f64
-            / (u64::MAX as f64);
-        let mut acc = 0.0;
-        let target = r * total;
-        for (i, (_, p)) in self.items.iter().enumerate() {
-            acc += *p;
-            if acc >= target {
-                return Some(i);
+        let r = (seed.wrapping_mul(6364136223846793005).wrapping_add(1) >> 11) as f64
+            / (1u64 << 53) as f64;
+        Some(self.filled_index(r * self.tree.total()))
+    }
+
+    /// Ô có mẫu ứng với tổng tiền tố `prefix`. Ô trống có priority 0 nên cây không chọn
+    /// chúng; giới hạn về `0..len` chặn thêm trường hợp sai số làm tròn.
+    fn filled_index(&self, prefix: f64) -> usize {
+        self.tree.find_prefix_sum(prefix).min(self.len - 1)
+    }
+
+    /// Lấy `batch_size` mẫu bằng cách chia tổng priority thành các đoạn bằng nhau và
+    /// chọn một mẫu trong mỗi đoạn. Mỗi lần gọi tiến lịch alpha/beta thêm một bước.
+    pub fn sample_batch<R: Rng + ?Sized>(
+        &mut self,
+        batch_size: usize,
+        rng: &mut R,
+    ) -> Option<PrioritizedBatch<T>> {
+        let total = self.tree.total();
+        if self.is_empty() || batch_size == 0 || total <= 0.0 {
+            return None;
+        }
+        let segment = total / batch_size as f64;
+        let beta = self.beta();
+        let n = self.len as f64;
+        // Trọng số lớn nhất ứng với priority nhỏ nhất.
+        let max_weight = (n * self.tree.min() / total).powf(-beta);
+
+        let mut batch = PrioritizedBatch {
+            indices: Vec::with_capacity(batch_size),
+            samples: Vec::with_capacity(batch_size),
+            weights: Vec::with_capacity(batch_size),
+        };
+        for k in 0..batch_size {
+            let prefix = segment * (k as f64 + rng.gen::<f64>());
+            let index = self.filled_index(prefix);
+            let Some(sample) = self.slots[index].as_ref() else {
+                unreachable!("các ô 0..len luôn có mẫu");
+            };
+            let probability = self.tree.get(index).max(self.tree.min()) / total;
+            batch.indices.push(index);
+            batch.samples.push(sample.clone());
+            batch
+                .weights
+                .push((n * probability).powf(-beta) / max_weight);
+        }
+        self.steps += 1;
+        Some(batch)
+    }
+
+    /// Cập nhật priority của các mẫu đã lấy theo |TD-error| mới tính được.
+    pub fn update_priorities(
+        &mut self,
+        indices: &[usize],
+        td_errors: &[f64],
+    ) -> Result<(), PandoraError> {
+        if indices.len() != td_errors.len() {
+            return Err(PandoraError::config(format!(
+                "update_priorities: {} indices but {} TD-errors",
+                indices.len(),
+                td_errors.len()
+            )));
+        }
+        if let Some(td) = td_errors.iter().find(|td| !td.is_finite()) {
+            return Err(PandoraError::config(format!(
+                "update_priorities: TD-error {} is not finite",
+                td
+            )));
+        }
+        for (&index, &td) in indices.iter().zip(td_errors) {
+            if self.slots.get(index).is_none_or(|s| s.is_none()) {
+                return Err(PandoraError::config(format!(
+                    "update_priorities: slot {} is empty",
+                    index
+                )));
             }
+            let priority = td.abs();
+            self.max_priority = self.max_priority.max(priority);
+            let scaled = self.scaled(priority);
+            self.tree.set(index, Some(scaled));
         }
-        Some(self.items.len() - 1)
+        Ok(())
     }
 
-    pub fn get(&self, index: usize) -> Option<&ExperienceSample> {
-        self.items.get(index).map(|(s, _)| s)
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.slots.get(index).and_then(|s| s.as_ref())
     }
 }
 
@@ -99,6 +350,7 @@ impl PriorityExperienceBuffer {
 mod tests {
     use super::*;
     use bytes::Bytes;
+    use rand::SeedableRng;
 
     #[test]
     fn buffer_respects_capacity() {
@@ -114,6 +366,8 @@ mod tests {
         });
         buf.push(ExperienceSample { flow, reward: 3.0 });
         assert_eq!(buf.len(), 2);
+        let rewards: Vec<f64> = buf.iter().map(|s| s.reward).collect();
+        assert_eq!(rewards, vec![2.0, 3.0]);
     }
 
     #[test]
@@ -153,4 +407,98 @@ mod tests {
         }
         assert!(hits_mid > 40);
     }
+
+    #[test]
+    fn sum_tree_prefix_search() {
+        let mut tree = SumTree::new(5);
+        for (i, p) in [1.0, 2.0, 3.0, 4.0, 0.5].into_iter().enumerate() {
+            tree.set(i, Some(p));
+        }
+        assert_eq!(tree.total(), 10.5);
+        assert_eq!(tree.min(), 0.5);
+        assert_eq!(tree.find_prefix_sum(0.5), 0);
+        assert_eq!(tree.find_prefix_sum(1.0), 1);
+        assert_eq!(tree.find_prefix_sum(5.9), 2);
+        assert_eq!(tree.find_prefix_sum(10.4), 4);
+        assert_eq!(tree.find_prefix_sum(100.0), 4);
+        tree.set(4, None);
+        assert_eq!(tree.min(), 1.0);
+        assert_eq!(tree.total(), 10.0);
+    }
+
+    fn sample(reward: f64) -> ExperienceSample {
+        ExperienceSample {
+            flow: EpistemologicalFlow::default(),
+            reward,
+        }
+    }
+
+    #[test]
+    fn ring_buffer_overwrites_oldest_slot() {
+        let mut buf = PriorityExperienceBuffer::with_capacity(2);
+        buf.push(sample(1.0), 1.0);
+        buf.push(sample(2.0), 1.0);
+        buf.push(sample(3.0), 1.0);
+        assert_eq!(buf.len(), 2);
+        assert_eq!(buf.get(0).map(|s| s.reward), Some(3.0));
+        assert_eq!(buf.get(1).map(|s| s.reward), Some(2.0));
+    }
+
+    #[test]
+    fn batch_weights_and_priority_updates() {
+        let config = PrioritizedReplayConfig {
+            alpha: LinearSchedule::constant(1.0),
+            beta: LinearSchedule {
+                start: 0.5,
+                end: 1.0,
+                steps: 10,
+            },
+            epsilon: 0.0,
+        };
+        let mut buf = PriorityExperienceBuffer::with_config(4, config);
+        for (i, p) in [1.0, 1.0, 1.0, 5.0].into_iter().enumerate() {
+            buf.push(sample(i as f64), p);
+        }
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let batch = buf.sample_batch(8, &mut rng).unwrap();
+        assert_eq!(batch.indices.len(), 8);
+        // 5/8 tổng priority thuộc về ô 3 nên 5 trong 8 đoạn rơi vào nó.
+        assert_eq!(batch.indices.iter().filter(|&&i| i == 3).count(), 5);
+        for (&i, &w) in batch.indices.iter().zip(&batch.weights) {
+            assert!(w > 0.0 && w <= 1.0);
+            if i != 3 {
+                assert!((w - 1.0).abs() < 1e-12);
+            }
+        }
+
+        buf.update_priorities(&[3, 0], &[0.0, 7.0]).unwrap();
+        assert!(buf.update_priorities(&[0], &[]).is_err());
+        let batch = buf.sample_batch(4, &mut rng).unwrap();
+        assert!(batch.indices.iter().all(|&i| i != 3));
+
+        for _ in 0..20 {
+            buf.sample_batch(1, &mut rng);
+        }
+        assert_eq!(buf.beta(), 1.0);
+    }
+
+    #[test]
+    fn partially_filled_buffer_returns_full_batches() {
+        let mut buf = PriorityExperienceBuffer::with_capacity(8);
+        buf.push(sample(0.0), 1.0);
+        buf.push(sample(1.0), 3.0);
+        buf.push(sample(2.0), f64::NAN);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        for batch_size in [1, 3, 16] {
+            let batch = buf.sample_batch(batch_size, &mut rng).unwrap();
+            assert_eq!(batch.indices.len(), batch_size);
+            assert_eq!(batch.samples.len(), batch_size);
+            assert!(batch.indices.iter().all(|&i| i < 3));
+            assert!(batch.weights.iter().all(|w| w.is_finite() && *w > 0.0));
+        }
+        // Priority NaN được thay bằng priority lớn nhất (3.0)
+        assert_eq!(buf.tree.get(2), buf.tree.get(1));
+        assert!((0..50).filter_map(|k| buf.sample_index(k)).all(|i| i < 3));
+        assert!(buf.update_priorities(&[0], &[f64::NAN]).is_err());
+    }
 }
@@ -0,0 +1,266 @@
+//! Deep temporal active inference: a stack of discrete agents at different time scales.
+//!
+//! Level 0 chooses primitive actions. Each higher level chooses *subgoals*: its
+//! actions set the preferences `C` (and optionally the state prior) of the level
+//! below, which then acts until the subgoal terminates. Lower-level observations
+//! are aggregated into the observations of the level above, so a higher level only
+//! updates its beliefs and replans when its own (coarser) outcome changes or the
+//! subgoal runs out of steps.
+
+use crate::discrete_active_inference::{invalid, invalid_input, DiscreteActiveInferenceAgent};
+use ndarray::{Array1, Array2};
+use pandora_error::PandoraError;
+
+/// Connects a higher level to the level directly below it.
+#[derive(Debug, Clone)]
+pub struct LevelLink {
+    /// Lower-level preferences `C` for each higher-level action (subgoal).
+    pub subgoal_preferences: Vec<Array1<f64>>,
+    /// Optional `lower_states × higher_states` map; when set, the lower-level state
+    /// prior at the start of a subgoal is `map · q(higher state)`.
+    pub state_prior: Option<Array2<f64>>,
+    /// Higher-level observation produced by each lower-level observation.
+    pub aggregate: Vec<usize>,
+    /// Maximum number of lower-level decisions per subgoal.
+    pub max_steps: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ActiveSubgoal {
+    action: usize,
+    start_observation: usize,
+    steps: usize,
+}
+
+/// Decisions taken during one call to `HierarchicalActiveInferenceAgent::step`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HierarchicalDecision {
+    /// Primitive action chosen by level 0.
+    pub action: usize,
+    /// `(level, subgoal)` for every higher level that selected a new subgoal.
+    pub new_subgoals: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HierarchicalActiveInferenceAgent {
+    /// `levels[0]` acts in the environment; `levels[k]` sets subgoals for `levels[k - 1]`.
+    levels: Vec<DiscreteActiveInferenceAgent>,
+    /// `links[k]` connects `levels[k + 1]` to `levels[k]`.
+    links: Vec<LevelLink>,
+    /// Subgoal currently imposed by `levels[k + 1]` on `levels[k]`.
+    active: Vec<Option<ActiveSubgoal>>,
+}
+
+impl HierarchicalActiveInferenceAgent {
+    pub fn new(
+        levels: Vec<DiscreteActiveInferenceAgent>,
+        links: Vec<LevelLink>,
+    ) -> Result<Self, PandoraError> {
+        if levels.is_empty() || links.len() + 1 != levels.len() {
+            return Err(invalid(format!(
+                "{} levels need {} links, got {}",
+                levels.len(),
+                levels.len().saturating_sub(1),
+                links.len()
+            )));
+        }
+        for (k, link) in links.iter().enumerate() {
+            let (lower, upper) = (levels[k].model(), levels[k + 1].model());
+            let valid = link.subgoal_preferences.len() == upper.num_actions()
+                && link
+                    .subgoal_preferences
+                    .iter()
+                    .all(|c| c.len() == lower.num_observations())
+                && link.aggregate.len() == lower.num_observations()
+                && link.aggregate.iter().all(|&o| o < upper.num_observations())
+                && link
+                    .state_prior
+                    .as_ref()
+                    .is_none_or(|m| m.dim() == (lower.num_states(), upper.num_states()))
+                && link.max_steps > 0;
+            if !valid {
+                return Err(invalid(format!(
+                    "Link between levels {k} and {} does not match their models",
+                    k + 1
+                )));
+            }
+        }
+        Ok(Self {
+            active: vec![None; links.len()],
+            levels,
+            links,
+        })
+    }
+
+    pub fn levels(&self) -> &[DiscreteActiveInferenceAgent] {
+        &self.levels
+    }
+
+    /// Subgoal currently imposed on each level (`None` for the top level).
+    pub fn active_subgoals(&self) -> Vec<Option<usize>> {
+        self.active
+            .iter()
+            .map(|s| s.map(|s| s.action))
+            .chain(std::iter::once(None))
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.levels.iter_mut().for_each(|l| l.reset());
+        self.active.iter_mut().for_each(|s| *s = None);
+    }
+
+    /// One primitive time step: aggregates `observation` up the hierarchy, lets
+    /// every level whose subgoal ended choose a new one (top-down), then acts.
+    pub fn step(&mut self, observation: usize) -> Result<HierarchicalDecision, PandoraError> {
+        if observation >= self.levels[0].model().num_observations() {
+            return Err(invalid_input(format!(
+                "Observation {observation} out of range"
+            )));
+        }
+        let mut observations = vec![observation];
+        for link in &self.links {
+            let below = observations[observations.len() - 1];
+            observations.push(link.aggregate[below]);
+        }
+
+        let mut new_subgoals = Vec::new();
+        // A new subgoal at level k forces level k - 1 to replan as well
+        let mut replan_below = false;
+        for k in (1..self.levels.len()).rev() {
+            let lower = k - 1;
+            let expired = match self.active[lower] {
+                None => true,
+                Some(s) => {
+                    s.start_observation != observations[k] || s.steps >= self.links[lower].max_steps
+                }
+            };
+            if !(expired || replan_below) {
+                continue;
+            }
+
+            let subgoal = self.levels[k].step(observations[k])?;
+            if let Some(parent) = self.active.get_mut(k) {
+                if let Some(parent) = parent.as_mut() {
+                    parent.steps += 1;
+                }
+            }
+            let link = &self.links[lower];
+            self.levels[lower].set_preferences(link.subgoal_preferences[subgoal].clone())?;
+            if let Some(map) = &link.state_prior {
+                let prior = map.dot(self.levels[k].beliefs());
+                self.levels[lower].set_state_prior(prior)?;
+            }
+            self.active[lower] = Some(ActiveSubgoal {
+                action: subgoal,
+                start_observation: observations[k],
+                steps: 0,
+            });
+            new_subgoals.push((k, subgoal));
+            replan_below = true;
+        }
+
+        let action = self.levels[0].step(observation)?;
+        if let Some(Some(active)) = self.active.first_mut() {
+            active.steps += 1;
+        }
+        Ok(HierarchicalDecision {
+            action,
+            new_subgoals,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discrete_active_inference::{DiscreteAgentConfig, DiscreteGenerativeModel};
+    use ndarray::array;
+
+    /// Lower level: a 4-cell corridor (moves left/right). Upper level: which half
+    /// of the corridor the agent is in, with subgoals "go left" / "go right".
+    fn corridor() -> HierarchicalActiveInferenceAgent {
+        let left = array![
+            [1.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, 0.0, 0.0, 0.0]
+        ];
+        let right = array![
+            [0.0, 0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 1.0]
+        ];
+        let lower = DiscreteGenerativeModel::new(
+            Array2::eye(4),
+            vec![left, right],
+            Array1::zeros(4),
+            array![1.0, 0.0, 0.0, 0.0],
+        )
+        .unwrap();
+        let upper = DiscreteGenerativeModel::new(
+            Array2::eye(2),
+            vec![
+                array![[1.0, 1.0], [0.0, 0.0]],
+                array![[0.0, 0.0], [1.0, 1.0]],
+            ],
+            array![0.0, 3.0],
+            array![1.0, 0.0],
+        )
+        .unwrap();
+        let config = DiscreteAgentConfig {
+            policy_horizon: 2,
+            ..Default::default()
+        };
+        let link = LevelLink {
+            subgoal_preferences: vec![array![3.0, 2.0, 1.0, 0.0], array![0.0, 1.0, 2.0, 3.0]],
+            state_prior: None,
+            aggregate: vec![0, 0, 1, 1],
+            max_steps: 4,
+        };
+        HierarchicalActiveInferenceAgent::new(
+            vec![
+                DiscreteActiveInferenceAgent::new(lower, config.clone()).unwrap(),
+                DiscreteActiveInferenceAgent::new(upper, config).unwrap(),
+            ],
+            vec![link],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_higher_level_acts_on_slower_time_scale() {
+        let mut agent = corridor();
+        let mut position = 0;
+        let mut upper_decisions = 0;
+        for _ in 0..3 {
+            let decision = agent.step(position).unwrap();
+            upper_decisions += decision.new_subgoals.len();
+            position = if decision.action == 1 {
+                (position + 1).min(3)
+            } else {
+                position.saturating_sub(1)
+            };
+        }
+        assert_eq!(position, 3);
+        assert_eq!(agent.active_subgoals(), vec![Some(1), None]);
+        // Replans only at the start and when the aggregated observation changes
+        assert_eq!(upper_decisions, 2);
+    }
+
+    #[test]
+    fn test_links_are_validated() {
+        let agent = corridor();
+        let levels = agent.levels().to_vec();
+        assert!(HierarchicalActiveInferenceAgent::new(levels.clone(), vec![]).is_err());
+        let bad = LevelLink {
+            subgoal_preferences: vec![Array1::zeros(4)],
+            state_prior: None,
+            aggregate: vec![0, 0, 1, 1],
+            max_steps: 4,
+        };
+        assert!(HierarchicalActiveInferenceAgent::new(levels, vec![bad]).is_err());
+        assert!(corridor().step(9).is_err());
+    }
+}
@@ -13,11 +13,19 @@ pub mod skill_forge_lightweight;
 // pub mod active_inference_efe;  // Disabled due to burn dependency
 pub mod active_inference_simplified;
 pub mod active_inference_skandha;
+pub mod bandit;
+pub mod discrete_active_inference;
+pub mod dqn;
 pub mod experience_buffer;
+pub mod hierarchical_active_inference;
 pub mod integration_test;
+pub mod mcts;
 #[cfg(test)]
 mod non_attachment_learning_test;
+pub mod openai_code_generator;
 pub mod policy;
+pub mod skill_ir;
+pub mod skill_lifecycle;
 // pub mod skill_forge;  // Disabled due to burn dependency
 pub mod skill_forge_simplified;
 pub mod skandha_integration;
@@ -34,15 +42,40 @@ pub use active_inference_lightweight::{
 pub use skill_forge_lightweight::{
     SkillForge, QueSTEncoder, VectorQuantizer, CodeGenerator, LLMCodeGenerator,
     GeneratedSkill, SkillPerformanceMetrics, ResourceRequirements, SkillContext,
-    PerformanceTracker, PerformanceThresholds
+    PerformanceTracker, PerformanceThresholds, IntentTokenizer, VectorQuantizerConfig,
+    QuantizerUpdate, CodebookMetrics
 };
 
 // Export simplified implementations as fallback
 // pub use active_inference_efe::{ActiveInferenceSankhara, EFECalculator, HierarchicalWorldModel, PerformanceMetrics};  // Disabled
 pub use active_inference_simplified::{ActiveInferenceSankhara as SimplifiedActiveInferenceSankhara, EFECalculator as SimplifiedEFECalculator, HierarchicalWorldModel as SimplifiedHierarchicalWorldModel, PerformanceMetrics as SimplifiedPerformanceMetrics};
 pub use active_inference_skandha::ActiveInferenceSankharaSkandha;
-pub use experience_buffer::{ExperienceBuffer, ExperienceSample, PriorityExperienceBuffer};
+pub use bandit::{
+    Bandit, BanditPolicy, BetaThompson, EpsilonGreedy, EpsilonSchedule, Exp3, GaussianThompson,
+    LinUcb, Ucb1, UcbV,
+};
+pub use discrete_active_inference::{
+    DiscreteActiveInferenceAgent, DiscreteAgentConfig, DiscreteGenerativeModel, PolicyEvaluation,
+};
+pub use dqn::{DqnConfig, Transition};
+pub use experience_buffer::{
+    ExperienceBuffer, ExperienceSample, PrioritizedBatch, PrioritizedReplayConfig,
+    PriorityExperienceBuffer,
+};
+pub use hierarchical_active_inference::{
+    HierarchicalActiveInferenceAgent, HierarchicalDecision, LevelLink,
+};
+pub use mcts::{MctsConfig, MctsPlan, MctsPlanner, PlanningBudget, SelectionRule};
+pub use openai_code_generator::{ChatMessage, OpenAiCodeGenerator, OpenAiCodeGeneratorConfig};
 pub use policy::{EpsilonGreedyPolicy, Policy, ValueDrivenPolicy};
+pub use skill_ir::{
+    ExecutionReport, SandboxLimits, SandboxedSkill, SkillProgram, Type as SkillType,
+    Value as SkillValue,
+};
+pub use skill_lifecycle::{
+    LifecycleEvent, LifecycleEventKind, SkillLifecycle, SkillLifecycleConfig, SkillLineage,
+    SkillRecord, SkillStatus, SkillVersion,
+};
 // pub use skill_forge::{SkillForge, QueSTEncoder, VectorQuantizer, CodeGenerator, LLMCodeGenerator, SkillForgeMetrics};  // Disabled
 pub use skill_forge_simplified::{SkillForge as SimplifiedSkillForge, QueSTEncoder as SimplifiedQueSTEncoder, CodeGenerator as SimplifiedCodeGenerator, LLMCodeGenerator as SimplifiedLLMCodeGenerator, SkillForgeMetrics as SimplifiedSkillForgeMetrics};
 pub use skandha_integration::SkandhaProcessorWithLearning;
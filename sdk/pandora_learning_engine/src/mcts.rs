@@ -0,0 +1,461 @@
+//! Monte Carlo Tree Search trên các chuyển trạng thái dự đoán bởi world model.
+//!
+//! - Chọn nút theo UCT hoặc PUCT (có prior cho từng hành động).
+//! - Progressive widening: số con được mở rộng tăng theo `k · N^alpha`, giúp xử lý
+//!   tập hành động lớn.
+//! - Lá được đánh giá bằng hàm do người gọi cung cấp (ví dụ EFE), giá trị được lan
+//!   ngược với hệ số chiết khấu `gamma`.
+//! - Xác định hoàn toàn theo `seed`; ngân sách tính toán là số vòng lặp hoặc thời gian.
+
+use pandora_core::ontology::EpistemologicalFlow;
+use pandora_error::PandoraError;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::time::{Duration, Instant};
+
+/// Công thức chọn nút con trong pha selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionRule {
+    /// `Q + c·sqrt(ln N / n)`
+    Uct,
+    /// `Q + c·P(a)·sqrt(N) / (1 + n)`
+    Puct,
+}
+
+/// Ngân sách tính toán cho một lần tìm kiếm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanningBudget {
+    Iterations(usize),
+    /// Chạy cho đến khi hết thời gian (luôn chạy ít nhất một vòng).
+    WallClock(Duration),
+}
+
+/// Một nút có lượt thăm `N` được phép có tối đa `max(1, ceil(k · N^alpha))` con.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressiveWidening {
+    pub k: f64,
+    pub alpha: f64,
+}
+
+impl ProgressiveWidening {
+    fn allowed_children(&self, visits: u32) -> usize {
+        let n = f64::from(visits.max(1));
+        (self.k * n.powf(self.alpha)).ceil().max(1.0) as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsConfig {
+    pub selection: SelectionRule,
+    pub exploration_constant: f64,
+    /// `None` để mở rộng mọi hành động ngay khi thăm nút.
+    pub widening: Option<ProgressiveWidening>,
+    pub budget: PlanningBudget,
+    /// Độ sâu tối đa của cây (số bước mô phỏng).
+    pub horizon: usize,
+    pub gamma: f64,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            selection: SelectionRule::Uct,
+            exploration_constant: std::f64::consts::SQRT_2,
+            widening: Some(ProgressiveWidening { k: 2.0, alpha: 0.5 }),
+            budget: PlanningBudget::Iterations(200),
+            horizon: 3,
+            gamma: 0.9,
+            seed: 0,
+        }
+    }
+}
+
+/// Thống kê của một hành động ở gốc cây sau khi tìm kiếm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionStats {
+    pub action: &'static str,
+    pub visits: u32,
+    pub mean_value: f64,
+    pub prior: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsPlan {
+    /// Hành động ở gốc có nhiều lượt thăm nhất; `None` nếu không có hành động nào.
+    pub best_action: Option<&'static str>,
+    /// Các hành động đã được mở rộng ở gốc, theo thứ tự mở rộng.
+    pub root_actions: Vec<ActionStats>,
+    pub iterations: usize,
+    pub tree_size: usize,
+}
+
+struct Node {
+    flow: EpistemologicalFlow,
+    depth: usize,
+    visits: u32,
+    value_sum: f64,
+    /// Giá trị đánh giá của trạng thái khi đến nút này.
+    reward: f64,
+    /// `(vị trí trong thứ tự mở rộng, chỉ số nút con)`
+    children: Vec<(usize, usize)>,
+}
+
+impl Node {
+    fn mean_value(&self) -> f64 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.value_sum / f64::from(self.visits)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MctsPlanner {
+    config: MctsConfig,
+}
+
+impl MctsPlanner {
+    pub fn new(config: MctsConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &MctsConfig {
+        &self.config
+    }
+
+    /// Tìm kiếm từ `root` với cùng tập `actions` ở mọi độ sâu.
+    ///
+    /// `predict` biến trạng thái thành trạng thái kế tiếp dưới một hành động, `evaluate`
+    /// cho điểm (càng cao càng tốt) của trạng thái vừa đến. `priors` (nếu có) được
+    /// chuẩn hóa, dùng cho PUCT và cho thứ tự mở rộng của progressive widening.
+    pub fn search<P, E>(
+        &self,
+        root: &EpistemologicalFlow,
+        actions: &[&'static str],
+        priors: Option<&[f64]>,
+        mut predict: P,
+        mut evaluate: E,
+    ) -> Result<MctsPlan, PandoraError>
+    where
+        P: FnMut(&mut EpistemologicalFlow, &'static str) -> Result<(), PandoraError>,
+        E: FnMut(&EpistemologicalFlow) -> f64,
+    {
+        let priors = Self::normalized_priors(actions.len(), priors)?;
+
+        // Thứ tự mở rộng: prior giảm dần, hòa thì theo hoán vị ngẫu nhiên có seed
+        let mut order: Vec<usize> = (0..actions.len()).collect();
+        order.shuffle(&mut StdRng::seed_from_u64(self.config.seed));
+        order.sort_by(|&a, &b| priors[b].total_cmp(&priors[a]));
+
+        let mut nodes = vec![Node {
+            flow: root.clone(),
+            depth: 0,
+            visits: 0,
+            value_sum: 0.0,
+            reward: 0.0,
+            children: Vec::new(),
+        }];
+
+        let start = Instant::now();
+        let mut iterations = 0;
+        if !actions.is_empty() && self.config.horizon > 0 {
+            loop {
+                let done = match self.config.budget {
+                    PlanningBudget::Iterations(n) => iterations >= n,
+                    PlanningBudget::WallClock(limit) => iterations > 0 && start.elapsed() >= limit,
+                };
+                if done {
+                    break;
+                }
+                self.iterate(
+                    &mut nodes,
+                    actions,
+                    &order,
+                    &priors,
+                    &mut predict,
+                    &mut evaluate,
+                )?;
+                iterations += 1;
+            }
+        }
+
+        let root_actions: Vec<ActionStats> = nodes[0]
+            .children
+            .iter()
+            .map(|&(slot, child)| ActionStats {
+                action: actions[order[slot]],
+                visits: nodes[child].visits,
+                mean_value: nodes[child].mean_value(),
+                prior: priors[order[slot]],
+            })
+            .collect();
+        let best_action = root_actions
+            .iter()
+            .reduce(|best, s| {
+                if (s.visits, s.mean_value) > (best.visits, best.mean_value) {
+                    s
+                } else {
+                    best
+                }
+            })
+            .map(|s| s.action);
+
+        Ok(MctsPlan {
+            best_action,
+            root_actions,
+            iterations,
+            tree_size: nodes.len(),
+        })
+    }
+
+    fn normalized_priors(n: usize, priors: Option<&[f64]>) -> Result<Vec<f64>, PandoraError> {
+        let Some(priors) = priors else {
+            return Ok(vec![1.0 / n.max(1) as f64; n]);
+        };
+        if priors.len() != n {
+            return Err(PandoraError::config(format!(
+                "MCTS: {} priors cho {} hành động",
+                priors.len(),
+                n
+            )));
+        }
+        if priors.iter().any(|p| !p.is_finite() || *p < 0.0) {
+            return Err(PandoraError::config("MCTS: prior phải hữu hạn và không âm"));
+        }
+        let total: f64 = priors.iter().sum();
+        if total <= 0.0 {
+            return Ok(vec![1.0 / n.max(1) as f64; n]);
+        }
+        Ok(priors.iter().map(|p| p / total).collect())
+    }
+
+    /// Một vòng selection → expansion → evaluation → backup.
+    fn iterate<P, E>(
+        &self,
+        nodes: &mut Vec<Node>,
+        actions: &[&'static str],
+        order: &[usize],
+        priors: &[f64],
+        predict: &mut P,
+        evaluate: &mut E,
+    ) -> Result<(), PandoraError>
+    where
+        P: FnMut(&mut EpistemologicalFlow, &'static str) -> Result<(), PandoraError>,
+        E: FnMut(&EpistemologicalFlow) -> f64,
+    {
+        let mut path = vec![0];
+        let mut current = 0;
+        while nodes[current].depth < self.config.horizon {
+            let node = &nodes[current];
+            let expanded = node.children.len();
+            let allowed = self
+                .config
+                .widening
+                .map_or(usize::MAX, |w| w.allowed_children(node.visits));
+            if expanded < actions.len() && expanded < allowed {
+                let action = actions[order[expanded]];
+                let mut flow = node.flow.clone();
+                predict(&mut flow, action)?;
+                let reward = evaluate(&flow);
+                let child = nodes.len();
+                nodes.push(Node {
+                    flow,
+                    depth: node.depth + 1,
+                    visits: 0,
+                    value_sum: 0.0,
+                    reward,
+                    children: Vec::new(),
+                });
+                nodes[current].children.push((expanded, child));
+                path.push(child);
+                break;
+            }
+            current = self.select_child(nodes, current, order, priors);
+            path.push(current);
+        }
+
+        let mut ret = 0.0;
+        for &n in path.iter().rev() {
+            let node = &mut nodes[n];
+            ret = node.reward + self.config.gamma * ret;
+            node.visits += 1;
+            node.value_sum += ret;
+        }
+        Ok(())
+    }
+
+    fn select_child(
+        &self,
+        nodes: &[Node],
+        parent: usize,
+        order: &[usize],
+        priors: &[f64],
+    ) -> usize {
+        let parent_visits = f64::from(nodes[parent].visits.max(1));
+        let c = self.config.exploration_constant;
+        let mut best = (f64::NEG_INFINITY, nodes[parent].children[0].1);
+        for &(slot, child) in &nodes[parent].children {
+            let node = &nodes[child];
+            let n = f64::from(node.visits);
+            let score = match self.config.selection {
+                SelectionRule::Uct => {
+                    node.mean_value() + c * (parent_visits.ln() / n.max(1.0)).sqrt()
+                }
+                SelectionRule::Puct => {
+                    node.mean_value() + c * priors[order[slot]] * parent_visits.sqrt() / (1.0 + n)
+                }
+            };
+            if score > best.0 {
+                best = (score, child);
+            }
+        }
+        best.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pandora_core::ontology::{DataEidos, Vedana};
+
+    /// "bait" cho 0.6 ngay rồi mắc kẹt; "setup" cho 0 nhưng mở khóa "payoff" = 1.0.
+    fn trap_world(
+        flow: &mut EpistemologicalFlow,
+        action: &'static str,
+    ) -> Result<(), PandoraError> {
+        let trapped = flow.sanna.is_some();
+        let prepared = flow.sankhara.as_deref() == Some("setup");
+        let reward = match action {
+            _ if trapped => 0.0,
+            "bait" => {
+                flow.sanna = Some(DataEidos {
+                    active_indices: Default::default(),
+                    dimensionality: 1,
+                });
+                0.6
+            }
+            "payoff" if prepared => 1.0,
+            _ => 0.0,
+        };
+        flow.vedana = Some(Vedana::Pleasant {
+            karma_weight: reward,
+        });
+        flow.set_static_intent(action);
+        Ok(())
+    }
+
+    fn karma(flow: &EpistemologicalFlow) -> f64 {
+        match flow.vedana {
+            Some(Vedana::Pleasant { karma_weight }) => karma_weight as f64,
+            _ => 0.0,
+        }
+    }
+
+    fn config(selection: SelectionRule) -> MctsConfig {
+        MctsConfig {
+            selection,
+            widening: None,
+            budget: PlanningBudget::Iterations(300),
+            horizon: 2,
+            gamma: 1.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lookahead_avoids_greedy_trap() {
+        let actions = ["bait", "setup", "payoff"];
+        for rule in [SelectionRule::Uct, SelectionRule::Puct] {
+            let plan = MctsPlanner::new(config(rule))
+                .search(
+                    &EpistemologicalFlow::default(),
+                    &actions,
+                    None,
+                    trap_world,
+                    karma,
+                )
+                .unwrap();
+            assert_eq!(plan.best_action, Some("setup"), "{rule:?}: {plan:?}");
+            assert_eq!(plan.iterations, 300);
+        }
+    }
+
+    #[test]
+    fn test_search_is_deterministic_for_a_seed() {
+        let actions: Vec<&'static str> = vec!["bait", "setup", "payoff", "wait", "look"];
+        let run = |seed| {
+            let cfg = MctsConfig {
+                seed,
+                budget: PlanningBudget::Iterations(50),
+                ..config(SelectionRule::Uct)
+            };
+            MctsPlanner::new(cfg)
+                .search(
+                    &EpistemologicalFlow::default(),
+                    &actions,
+                    None,
+                    trap_world,
+                    |_: &EpistemologicalFlow| 0.0,
+                )
+                .unwrap()
+                .root_actions
+        };
+        assert_eq!(run(7), run(7));
+        let order =
+            |stats: Vec<ActionStats>| stats.into_iter().map(|s| s.action).collect::<Vec<_>>();
+        assert_ne!(order(run(1)), order(run(2)));
+    }
+
+    #[test]
+    fn test_progressive_widening_limits_root_children() {
+        let actions: Vec<&'static str> = vec!["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"];
+        let cfg = MctsConfig {
+            widening: Some(ProgressiveWidening { k: 1.0, alpha: 0.5 }),
+            budget: PlanningBudget::Iterations(16),
+            horizon: 1,
+            ..Default::default()
+        };
+        let priors: Vec<f64> = (0..10).map(|i| 10.0 - i as f64).collect();
+        let plan = MctsPlanner::new(cfg)
+            .search(
+                &EpistemologicalFlow::default(),
+                &actions,
+                Some(&priors),
+                trap_world,
+                karma,
+            )
+            .unwrap();
+        // Gốc được thăm 16 lần => tối đa ceil(sqrt(16)) = 4 con, mở theo prior giảm dần
+        let expanded: Vec<_> = plan.root_actions.iter().map(|s| s.action).collect();
+        assert_eq!(expanded, vec!["a", "b", "c", "d"]);
+        assert_eq!(plan.root_actions.iter().map(|s| s.visits).sum::<u32>(), 16);
+    }
+
+    #[test]
+    fn test_wall_clock_budget_and_errors() {
+        let cfg = MctsConfig {
+            budget: PlanningBudget::WallClock(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let planner = MctsPlanner::new(cfg);
+        let flow = EpistemologicalFlow::default();
+        let plan = planner
+            .search(&flow, &["bait", "setup"], None, trap_world, karma)
+            .unwrap();
+        assert!(plan.iterations >= 1);
+        assert!(plan.best_action.is_some());
+
+        assert!(planner
+            .search(&flow, &["a", "b"], Some(&[1.0]), trap_world, karma)
+            .is_err());
+        let failing = |_: &mut EpistemologicalFlow, _: &'static str| {
+            Err(PandoraError::config("model unavailable"))
+        };
+        assert!(planner.search(&flow, &["a"], None, failing, karma).is_err());
+        let empty = planner.search(&flow, &[], None, trap_world, karma).unwrap();
+        assert_eq!(empty.best_action, None);
+    }
+}
@@ -0,0 +1,405 @@
+//! `CodeGenerator` backed by an OpenAI-compatible chat completions API.
+//!
+//! Works with any server exposing `/v1/chat/completions` (OpenAI, llama.cpp's
+//! `llama-server`, vLLM, Ollama, ...). The model is asked for a skill program in the
+//! typed skill IR; the code block of its reply must compile with the interface
+//! `SkillForge` expects for the intent. A rejected program is fed back to the model
+//! and regenerated, up to `max_validation_retries` times; each of those requests has its own
+//! `max_retries` transport retries.
+
+use crate::skill_forge_lightweight::{
+    skill_prompt, to_source, validate_skill_code, CodeGenerator, Intent, SkillContext,
+    SkillPerformanceMetrics,
+};
+use crate::skill_ir::SkillProgram;
+use async_trait::async_trait;
+use futures::StreamExt;
+use pandora_core::error::PandoraError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::warn;
+
+const SYSTEM_PROMPT: &str = "You write skills as JSON programs in a typed expression language. \
+A program is {\"name\", \"description\", \"params\": {name: type}, \"output\": type, \"body\": expr}. \
+Types: \"number\", \"bool\", \"text\", {\"list\": type}, {\"record\": {field: type}}. \
+Expressions: {\"number\": 1.0}, {\"bool\": true}, {\"text\": \"s\"}, {\"var\": name}, \
+{\"list\": {\"item\": type, \"items\": [expr]}}, {\"record\": {field: expr}}, \
+{\"field\": {\"record\": expr, \"name\": field}}, {\"index\": {\"list\": expr, \"index\": expr}}, \
+{\"range\": {\"start\": expr, \"end\": expr}}, {\"unary\": {\"op\": op, \"arg\": expr}} with op in \
+neg, not, abs, sqrt, floor, len, to_text, {\"binary\": {\"op\": op, \"lhs\": expr, \"rhs\": expr}} with op in \
+add, sub, mul, div, rem, pow, min, max, eq, ne, lt, le, gt, ge, and, or, concat, \
+{\"if\": {\"cond\", \"then\", \"otherwise\"}}, {\"let\": {\"name\", \"value\", \"body\"}}, \
+{\"map\": {\"list\", \"var\", \"body\"}}, {\"filter\": {\"list\", \"var\", \"body\"}}, \
+{\"fold\": {\"list\", \"init\", \"acc\", \"var\", \"body\"}}. \
+Reply with exactly one ```json code block containing the program.";
+
+#[derive(Debug, Clone)]
+pub struct OpenAiCodeGeneratorConfig {
+    /// Server root, e.g. `http://localhost:8080` for llama.cpp; `/v1/chat/completions` is appended.
+    pub base_url: String,
+    pub model: String,
+    /// Sent as a bearer token when set.
+    pub api_key: Option<String>,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    /// Per-request timeout, covering the whole (streamed) response.
+    pub timeout: Duration,
+    /// Extra attempts after a transport error or 429/5xx response.
+    pub max_retries: u32,
+    /// Extra generations after a rejected program. One generation issues at most
+    /// `(max_validation_retries + 1) * (max_retries + 1)` requests.
+    pub max_validation_retries: u32,
+    /// Delay before the first retry; doubled after every failed attempt.
+    pub retry_backoff: Duration,
+    /// Upper bound for the doubled retry delay.
+    pub max_retry_backoff: Duration,
+    /// Request server-sent events and assemble the reply from the deltas.
+    pub stream: bool,
+}
+
+impl Default for OpenAiCodeGeneratorConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:8080".to_string(),
+            model: "local-model".to_string(),
+            api_key: None,
+            temperature: 0.2,
+            max_tokens: 2048,
+            timeout: Duration::from_secs(120),
+            max_retries: 3,
+            max_validation_retries: 2,
+            retry_backoff: Duration::from_millis(500),
+            max_retry_backoff: Duration::from_secs(30),
+            stream: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new("system", content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new("user", content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new("assistant", content)
+    }
+
+    fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    temperature: f32,
+    max_tokens: u32,
+    stream: bool,
+}
+
+/// Both full responses (`message`) and stream events (`delta`).
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    #[serde(default)]
+    message: Option<ChatContent>,
+    #[serde(default)]
+    delta: Option<ChatContent>,
+}
+
+#[derive(Deserialize)]
+struct ChatContent {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+impl ChatResponse {
+    fn into_content(self) -> Option<String> {
+        let choice = self.choices.into_iter().next()?;
+        choice.message.or(choice.delta)?.content
+    }
+}
+
+enum Failure {
+    Retryable(PandoraError),
+    Fatal(PandoraError),
+}
+
+pub struct OpenAiCodeGenerator {
+    config: OpenAiCodeGeneratorConfig,
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl OpenAiCodeGenerator {
+    pub fn new(config: OpenAiCodeGeneratorConfig) -> Result<Self, PandoraError> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| PandoraError::Io(format!("Cannot build HTTP client: {e}")))?;
+        let endpoint = format!(
+            "{}/v1/chat/completions",
+            config.base_url.trim_end_matches('/')
+        );
+        Ok(Self {
+            config,
+            endpoint,
+            client,
+        })
+    }
+
+    pub fn config(&self) -> &OpenAiCodeGeneratorConfig {
+        &self.config
+    }
+
+    /// One chat completion, retrying transport errors and 429/5xx responses.
+    pub async fn complete(&self, messages: &[ChatMessage]) -> Result<String, PandoraError> {
+        let mut backoff = self.config.retry_backoff.min(self.config.max_retry_backoff);
+        let mut attempt = 0;
+        loop {
+            match self.send(messages).await {
+                Ok(content) => return Ok(content),
+                Err(Failure::Retryable(e)) if attempt < self.config.max_retries => {
+                    warn!(
+                        "Chat completion attempt {} failed: {}; retrying in {:?}",
+                        attempt + 1,
+                        e,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = next_backoff(backoff, self.config.max_retry_backoff);
+                    attempt += 1;
+                }
+                Err(Failure::Retryable(e) | Failure::Fatal(e)) => return Err(e),
+            }
+        }
+    }
+
+    async fn send(&self, messages: &[ChatMessage]) -> Result<String, Failure> {
+        let mut request = self.client.post(&self.endpoint).json(&ChatRequest {
+            model: &self.config.model,
+            messages,
+            temperature: self.config.temperature,
+            max_tokens: self.config.max_tokens,
+            stream: self.config.stream,
+        });
+        if let Some(key) = &self.config.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request.send().await.map_err(|e| {
+            Failure::Retryable(PandoraError::Io(format!(
+                "Chat completion request failed: {e}"
+            )))
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let error = PandoraError::PredictionFailed(format!(
+                "Chat completion returned {status}: {body}"
+            ));
+            return Err(if status.as_u16() == 429 || status.is_server_error() {
+                Failure::Retryable(error)
+            } else {
+                Failure::Fatal(error)
+            });
+        }
+
+        if self.config.stream {
+            return read_event_stream(response).await;
+        }
+        let body = response.bytes().await.map_err(|e| {
+            Failure::Retryable(PandoraError::Io(format!(
+                "Chat completion body failed: {e}"
+            )))
+        })?;
+        serde_json::from_slice::<ChatResponse>(&body)
+            .ok()
+            .and_then(ChatResponse::into_content)
+            .ok_or_else(|| {
+                Failure::Fatal(PandoraError::PredictionFailed(format!(
+                    "Chat completion has no content: {}",
+                    String::from_utf8_lossy(&body)
+                )))
+            })
+    }
+
+    /// Asks for a program until one passes `validate`, feeding each rejection back.
+    async fn generate_validated(
+        &self,
+        mut messages: Vec<ChatMessage>,
+        validate: impl Fn(&str) -> Result<SkillProgram, PandoraError>,
+    ) -> Result<String, PandoraError> {
+        let mut attempt = 0;
+        loop {
+            let reply = self.complete(&messages).await?;
+            match validate(&extract_code_block(&reply)) {
+                Ok(program) => return to_source(&program),
+                Err(e) if attempt < self.config.max_validation_retries => {
+                    warn!("Rejected generated skill program: {}", e);
+                    messages.push(ChatMessage::assistant(reply));
+                    messages.push(ChatMessage::user(format!(
+                        "The program was rejected: {e}. Reply with a corrected program."
+                    )));
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn next_backoff(backoff: Duration, cap: Duration) -> Duration {
+    backoff.saturating_mul(2).min(cap)
+}
+
+/// Concatenates the `delta.content` of server-sent events until `data: [DONE]`.
+async fn read_event_stream(response: reqwest::Response) -> Result<String, Failure> {
+    let mut stream = response.bytes_stream();
+    // Raw bytes, so a chunk boundary inside a UTF-8 sequence is harmless
+    let mut buffer = Vec::new();
+    let mut content = String::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| {
+            Failure::Retryable(PandoraError::Io(format!(
+                "Chat completion stream failed: {e}"
+            )))
+        })?;
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                return Ok(content);
+            }
+            let event: ChatResponse = serde_json::from_str(data).map_err(|e| {
+                Failure::Fatal(PandoraError::PredictionFailed(format!(
+                    "Malformed chat completion event: {e}"
+                )))
+            })?;
+            if let Some(delta) = event.into_content() {
+                content.push_str(&delta);
+            }
+        }
+    }
+    // Some servers close the stream without a `[DONE]` marker
+    Ok(content)
+}
+
+/// Code in a model reply: the first ```json block, else the first fenced block,
+/// else the outermost `{ .. }` span, else the whole trimmed reply.
+pub fn extract_code_block(reply: &str) -> String {
+    let mut blocks = Vec::new();
+    let mut rest = reply;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        let Some(end) = after.find("```") else {
+            break;
+        };
+        let (lang, body) = after[..end].split_once('\n').unwrap_or(("", &after[..end]));
+        blocks.push((lang.trim(), body));
+        rest = &after[end + 3..];
+    }
+    let fenced = blocks
+        .iter()
+        .find(|(lang, _)| lang.eq_ignore_ascii_case("json"))
+        .or(blocks.first());
+    if let Some((_, body)) = fenced {
+        return body.trim().to_string();
+    }
+    match (reply.find('{'), reply.rfind('}')) {
+        (Some(start), Some(end)) if start < end => reply[start..=end].to_string(),
+        _ => reply.trim().to_string(),
+    }
+}
+
+#[async_trait]
+impl CodeGenerator for OpenAiCodeGenerator {
+    async fn generate_skill_code(
+        &self,
+        intent: &Intent,
+        context: &SkillContext,
+    ) -> Result<String, PandoraError> {
+        let messages = vec![
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(skill_prompt(intent, context)),
+        ];
+        self.generate_validated(messages, |code| validate_skill_code(code, intent))
+            .await
+    }
+
+    async fn optimize_code(
+        &self,
+        code: &str,
+        metrics: &SkillPerformanceMetrics,
+    ) -> Result<String, PandoraError> {
+        let original = SkillProgram::compile(code)
+            .map_err(|e| PandoraError::SkillVerificationFailed(e.to_string()))?;
+        let messages = vec![
+            ChatMessage::system(SYSTEM_PROMPT),
+            ChatMessage::user(format!(
+                "Optimize this skill program for the observed metrics {metrics:?}. \
+                Keep its params and output types unchanged.\n```json\n{code}\n```"
+            )),
+        ];
+        self.generate_validated(messages, |candidate| {
+            let program = SkillProgram::compile(candidate)
+                .map_err(|e| PandoraError::SkillVerificationFailed(e.to_string()))?;
+            if program.params != original.params || program.output != original.output {
+                return Err(PandoraError::SkillVerificationFailed(
+                    "optimized program changed the skill interface".to_string(),
+                ));
+            }
+            Ok(program)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_code_block_prefers_json_fences() {
+        let reply = "Sure:\n```text\nnot this\n```\n```json\n{\"a\": 1}\n```\nDone.";
+        assert_eq!(extract_code_block(reply), "{\"a\": 1}");
+        assert_eq!(extract_code_block("```\n{}\n```"), "{}");
+        assert_eq!(extract_code_block("Program: {\"b\": {}} ok"), "{\"b\": {}}");
+        assert_eq!(extract_code_block("  nothing  "), "nothing");
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let cap = Duration::from_secs(30);
+        assert_eq!(
+            next_backoff(Duration::from_millis(500), cap),
+            Duration::from_secs(1)
+        );
+        assert_eq!(next_backoff(Duration::from_secs(20), cap), cap);
+        assert_eq!(next_backoff(Duration::MAX, cap), cap);
+        assert_eq!(next_backoff(Duration::MAX, Duration::MAX), Duration::MAX);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bandit::{BanditPolicy, EpsilonGreedy, EpsilonSchedule};
 use crate::value_estimator::{NeuralQValueEstimator, QValueEstimator};
 use pandora_core::ontology::EpistemologicalFlow;
 
@@ -42,19 +43,57 @@ pub trait Policy {
     fn update(&mut self, _flow: &EpistemologicalFlow, _advantage: f64) {}
 }
 
-#[derive(Default)]
-pub struct EpsilonGreedyPolicy;
+/// Epsilon-greedy trên toàn bộ `Action`: giá trị mỗi hành động là trung bình advantage
+/// đã nhận, xác suất khám phá giảm theo `EpsilonSchedule`.
+pub struct EpsilonGreedyPolicy {
+    inner: BanditPolicy<EpsilonGreedy>,
+}
 
-impl Policy for EpsilonGreedyPolicy {
-    fn select_action(&self, _flow: &EpistemologicalFlow, explore_rate: f64) -> Action {
-        if explore_rate > 0.0 {
-            Action::Explore
-        } else {
-            Action::Exploit
+impl EpsilonGreedyPolicy {
+    pub fn new(schedule: EpsilonSchedule, seed: u64) -> Self {
+        let arms = Action::all_actions();
+        let bandit = EpsilonGreedy::new(arms.len(), schedule, seed)
+            .expect("Action::all_actions() không rỗng");
+        Self {
+            inner: BanditPolicy::from_parts(arms, bandit).with_seed(seed),
         }
     }
-    fn update(&mut self, _flow: &EpistemologicalFlow, _advantage: f64) {
-        // no-op for sample stub
+
+    /// Ghi nhận reward của `action` trong `flow` (xem `BanditPolicy::observe`).
+    pub fn observe(&mut self, flow: &EpistemologicalFlow, action: &Action, reward: f64) {
+        self.inner.observe(flow, action, reward);
+    }
+
+    /// Xác suất khám phá ở bước kế tiếp.
+    pub fn epsilon(&self) -> f64 {
+        self.inner.with_bandit(EpsilonGreedy::epsilon)
+    }
+
+    pub fn bandit_policy(&self) -> &BanditPolicy<EpsilonGreedy> {
+        &self.inner
+    }
+}
+
+impl Default for EpsilonGreedyPolicy {
+    fn default() -> Self {
+        Self::new(
+            EpsilonSchedule::Exponential {
+                start: 1.0,
+                end: 0.05,
+                decay: 0.99,
+            },
+            0,
+        )
+    }
+}
+
+impl Policy for EpsilonGreedyPolicy {
+    fn select_action(&self, flow: &EpistemologicalFlow, explore_rate: f64) -> Action {
+        self.inner.select_action(flow, explore_rate)
+    }
+
+    fn update(&mut self, flow: &EpistemologicalFlow, advantage: f64) {
+        self.inner.update(flow, advantage);
     }
 }
 
@@ -309,10 +348,34 @@ mod tests {
 
     #[test]
     fn epsilon_greedy_switches_mode() {
-        let pol = EpsilonGreedyPolicy::default();
         let flow = EpistemologicalFlow::from_bytes(Bytes::from_static(b"state"));
-        assert_eq!(pol.select_action(&flow, 0.1), Action::Explore);
-        assert_eq!(pol.select_action(&flow, 0.0), Action::Exploit);
+        let policy = |epsilon| {
+            let mut pol = EpsilonGreedyPolicy::new(EpsilonSchedule::Constant(epsilon), 0);
+            pol.observe(&flow, &Action::Exploit, 1.0);
+            pol
+        };
+        // Greedy: luôn chọn hành động có giá trị cao nhất
+        let greedy = policy(0.0);
+        assert_eq!(greedy.epsilon(), 0.0);
+        assert!((0..20).all(|_| greedy.select_action(&flow, 0.0) == Action::Exploit));
+        // Khám phá hoàn toàn: thử cả các hành động khác
+        let exploring = policy(1.0);
+        assert!((0..20).any(|_| exploring.select_action(&flow, 0.0) != Action::Exploit));
+        // `explore_rate` buộc khám phá kể cả khi bandit đang greedy
+        assert!((0..20).any(|_| greedy.select_action(&flow, 1.0) != Action::Exploit));
+        assert!((0..20).all(|_| greedy.select_action(&flow, 0.0) == Action::Exploit));
+    }
+
+    #[test]
+    fn bandit_policy_rejects_mismatched_arms() {
+        let arms = Action::all_actions();
+        let schedule = EpsilonSchedule::Constant(0.1);
+        let one = EpsilonGreedy::new(1, schedule, 0).unwrap();
+        assert!(BanditPolicy::new(Vec::new(), one).is_err());
+        let short = EpsilonGreedy::new(arms.len() - 1, schedule, 0).unwrap();
+        assert!(BanditPolicy::new(arms.clone(), short).is_err());
+        let matching = EpsilonGreedy::new(arms.len(), schedule, 0).unwrap();
+        assert!(BanditPolicy::new(arms, matching).is_ok());
     }
 
     #[test]
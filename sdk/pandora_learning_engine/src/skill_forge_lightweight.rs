@@ -1,12 +1,17 @@
 // Lightweight SkillForge Implementation with ndarray
 // Tối ưu hóa cho thiết bị di động và biên với QueST Encoder
 
+use crate::skill_ir::{BinaryOp, Expr, SandboxLimits, SandboxedSkill, SkillProgram, Type, UnaryOp};
+use crate::skill_lifecycle::{SkillLifecycle, SkillLifecycleConfig, SkillStatus, SkillVersion};
 use async_trait::async_trait;
 use ndarray::{Array2, Array1, Array3, s};
 use pandora_core::error::PandoraError;
+use pandora_core::interfaces::skills::SkillModule;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-// use std::sync::Arc;  // Not used
+use serde_json::{json, Value as Json};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 // ===== Enhanced Types =====
@@ -53,8 +58,99 @@ pub trait Skill {
     fn get_resource_requirements(&self) -> ResourceRequirements;
 }
 
+// ===== Intent Tokenizer =====
+
+/// Token 0 là padding, 1..=4 đánh dấu loại intent; các từ được hash vào phần còn lại
+const RESERVED_TOKENS: u32 = 8;
+
+/// Tokenizer tất định cho `Intent`: mỗi từ / đặc trưng được hash (FNV-1a) vào vocab,
+/// nên các intent giống nhau chia sẻ phần lớn token.
+#[derive(Debug, Clone)]
+pub struct IntentTokenizer {
+    vocab_size: u32,
+}
+
+impl IntentTokenizer {
+    pub fn new(vocab_size: usize) -> Self {
+        Self {
+            vocab_size: (vocab_size as u32).max(RESERVED_TOKENS + 1),
+        }
+    }
+
+    pub fn tokenize(&self, intent: &Intent) -> Vec<u32> {
+        let mut tokens = Vec::new();
+        match intent {
+            Intent::ExecuteTask { task, parameters, complexity, priority } => {
+                tokens.push(1);
+                self.push_words(&mut tokens, task);
+                let sorted: BTreeMap<_, _> = parameters.iter().collect();
+                for (key, value) in sorted {
+                    tokens.push(self.word_token(&format!("param:{}", key.to_lowercase())));
+                    self.push_words(&mut tokens, value);
+                }
+                tokens.push(self.word_token(&format!("complexity:{}", bucket(*complexity))));
+                tokens.push(self.word_token(&format!("priority:{}", priority)));
+            }
+            Intent::MaintainEquilibrium { current_state, target_state } => {
+                tokens.push(2);
+                let sorted: BTreeMap<_, _> = target_state.iter().collect();
+                for (key, target) in sorted {
+                    let current = current_state.get(key).copied().unwrap_or(*target);
+                    tokens.push(self.word_token(&format!("state:{}", key.to_lowercase())));
+                    tokens.push(self.word_token(&format!("delta:{}", bucket(target - current))));
+                }
+            }
+            Intent::ExecutePlan { plan, .. } => {
+                tokens.push(3);
+                for step in plan {
+                    self.push_words(&mut tokens, step);
+                }
+                tokens.push(self.word_token(&format!("steps:{}", plan.len())));
+            }
+            Intent::LearnSkill { skill_type, training_data, target_performance } => {
+                tokens.push(4);
+                self.push_words(&mut tokens, skill_type);
+                let magnitude = usize::BITS - training_data.len().leading_zeros();
+                tokens.push(self.word_token(&format!("samples:{}", magnitude)));
+                tokens.push(self.word_token(&format!("target:{}", bucket(*target_performance))));
+            }
+        }
+        tokens
+    }
+
+    fn push_words(&self, tokens: &mut Vec<u32>, text: &str) {
+        tokens.extend(
+            text.split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(|w| self.word_token(&w.to_lowercase())),
+        );
+    }
+
+    fn word_token(&self, word: &str) -> u32 {
+        // FNV-1a 32-bit
+        let hash = word.bytes().fold(0x811c_9dc5u32, |h, b| (h ^ u32::from(b)).wrapping_mul(0x0100_0193));
+        RESERVED_TOKENS + hash % (self.vocab_size - RESERVED_TOKENS)
+    }
+}
+
+/// Lượng tử hóa giá trị liên tục theo bước 0.25 để làm token
+fn bucket(x: f32) -> i32 {
+    (x * 4.0).round() as i32
+}
+
 // ===== QueST Encoder with ndarray =====
 
+const ENCODER_SEED: u64 = 0x0051_5545_5354;
+
+/// Trọng số cố định của một transformer layer (khởi tạo một lần, encode tất định)
+struct TransformerLayerWeights {
+    q_proj: Array2<f32>,
+    k_proj: Array2<f32>,
+    v_proj: Array2<f32>,
+    w1: Array2<f32>,
+    w2: Array2<f32>,
+}
+
 pub struct QueSTEncoder {
     vocab_size: usize,
     hidden_size: usize,
@@ -65,6 +161,7 @@ pub struct QueSTEncoder {
     // Pre-computed embeddings
     vocab_embedding: Array2<f32>,
     position_embedding: Array2<f32>,
+    layers: Vec<TransformerLayerWeights>,
 }
 
 impl QueSTEncoder {
@@ -76,15 +173,22 @@ impl QueSTEncoder {
         sequence_length: usize,
         quantized: bool,
     ) -> Self {
+        // Seed cố định: cùng cấu hình cho cùng encoding, nên code của skill ổn định giữa các lần chạy
+        let mut rng = fastrand::Rng::with_seed(ENCODER_SEED);
+        let mut init = |rows: usize, cols: usize| Array2::from_shape_fn((rows, cols), |_| rng.f32() * 0.02 - 0.01);
         // Initialize embeddings with random values
-        let vocab_embedding = Array2::from_shape_fn((vocab_size, hidden_size), |_| {
-            fastrand::f32() * 0.02 - 0.01
-        });
-        
-        let position_embedding = Array2::from_shape_fn((sequence_length, hidden_size), |_| {
-            fastrand::f32() * 0.02 - 0.01
-        });
-        
+        let vocab_embedding = init(vocab_size, hidden_size);
+        let position_embedding = init(sequence_length, hidden_size);
+        let layers = (0..num_layers)
+            .map(|_| TransformerLayerWeights {
+                q_proj: init(hidden_size, hidden_size),
+                k_proj: init(hidden_size, hidden_size),
+                v_proj: init(hidden_size, hidden_size),
+                w1: init(hidden_size, hidden_size * 4),
+                w2: init(hidden_size * 4, hidden_size),
+            })
+            .collect();
+
         Self {
             vocab_size,
             hidden_size,
@@ -94,44 +198,49 @@ impl QueSTEncoder {
             quantized,
             vocab_embedding,
             position_embedding,
+            layers,
         }
     }
 
+    pub fn vocab_size(&self) -> usize {
+        self.vocab_size
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden_size
+    }
+
     /// Encode input sequence thành latent representation
     pub fn encode(&self, input_ids: &[u32]) -> Result<Array2<f32>, PandoraError> {
         let batch_size = 1;
         let seq_len = input_ids.len().min(self.sequence_length);
-        
+
         // Create input tensor
         let mut input_tensor = Array2::zeros((batch_size, seq_len));
-        for (i, &id) in input_ids.iter().enumerate() {
+        for (i, &id) in input_ids.iter().take(seq_len).enumerate() {
             input_tensor[[0, i]] = id as f32;
         }
-        
-        // Apply quantization nếu cần
-        let encoded = if self.quantized {
-            self.quantize_tensor(input_tensor)?
-        } else {
-            input_tensor
-        };
-        
+
         // Apply transformer layers
-        let mut hidden_states = self.embed_tokens(&encoded)?;
-        
+        let mut hidden_states = self.embed_tokens(&input_tensor)?;
+
         for layer_idx in 0..self.num_layers {
             hidden_states = self.transformer_layer(&hidden_states, layer_idx)?;
         }
-        
+
         // Pooling để tạo sequence representation
-        self.pool_sequence(&hidden_states)
+        let pooled = self.pool_sequence(&hidden_states)?;
+
+        // Apply quantization nếu cần (trên representation, không phải token IDs)
+        self.quantize_tensor(pooled)
     }
 
     fn embed_tokens(&self, input_ids: &Array2<f32>) -> Result<Array2<f32>, PandoraError> {
         let (batch_size, seq_len) = input_ids.dim();
-        
+
         // Gather token embeddings
         let mut token_embeds = Array3::zeros((batch_size, seq_len, self.hidden_size));
-        
+
         for i in 0..batch_size {
             for j in 0..seq_len {
                 let token_id = input_ids[[i, j]] as usize;
@@ -140,47 +249,43 @@ impl QueSTEncoder {
                 }
             }
         }
-        
+
         // Add position embeddings
         let pos_embeds = self.position_embedding.slice(s![..seq_len, ..]);
         let mut result = Array2::zeros((batch_size, self.hidden_size));
-        
+
         for i in 0..batch_size {
             let token_embeds_slice = token_embeds.slice(s![i, .., ..]);
             let summed = token_embeds_slice.sum_axis(ndarray::Axis(0));
             let with_pos = &summed + &pos_embeds.sum_axis(ndarray::Axis(0));
             result.slice_mut(s![i, ..]).assign(&with_pos);
         }
-        
+
         Ok(result)
     }
 
-    fn transformer_layer(&self, hidden_states: &Array2<f32>, _layer_idx: usize) -> Result<Array2<f32>, PandoraError> {
+    fn transformer_layer(&self, hidden_states: &Array2<f32>, layer_idx: usize) -> Result<Array2<f32>, PandoraError> {
+        let weights = &self.layers[layer_idx];
+
         // Multi-head attention
-        let attention_output = self.multi_head_attention(hidden_states)?;
-        
+        let attention_output = self.multi_head_attention(hidden_states, weights)?;
+
         // Layer normalization
         let norm1_output = self.layer_norm(&(&attention_output + hidden_states))?;
-        
+
         // Feed-forward network
-        let ff_output = self.feed_forward(&norm1_output)?;
-        
+        let ff_output = self.feed_forward(&norm1_output, weights)?;
+
         // Final layer normalization
         self.layer_norm(&(&ff_output + &norm1_output))
     }
 
-    fn multi_head_attention(&self, hidden_states: &Array2<f32>) -> Result<Array2<f32>, PandoraError> {
-        let (_batch_size, hidden_dim) = hidden_states.dim();
-        
+    fn multi_head_attention(&self, hidden_states: &Array2<f32>, weights: &TransformerLayerWeights) -> Result<Array2<f32>, PandoraError> {
         // Linear projections for Q, K, V
-        let q_proj = Array2::from_shape_fn((hidden_dim, hidden_dim), |_| fastrand::f32() * 0.02 - 0.01);
-        let k_proj = Array2::from_shape_fn((hidden_dim, hidden_dim), |_| fastrand::f32() * 0.02 - 0.01);
-        let v_proj = Array2::from_shape_fn((hidden_dim, hidden_dim), |_| fastrand::f32() * 0.02 - 0.01);
-        
-        let q = hidden_states.dot(&q_proj);
-        let k = hidden_states.dot(&k_proj);
-        let v = hidden_states.dot(&v_proj);
-        
+        let q = hidden_states.dot(&weights.q_proj);
+        let k = hidden_states.dot(&weights.k_proj);
+        let v = hidden_states.dot(&weights.v_proj);
+
         // Simplified attention for 2D arrays
         let attention_scores = self.scaled_dot_product_attention(&q, &k, &v)?;
         Ok(attention_scores)
@@ -213,22 +318,15 @@ impl QueSTEncoder {
         Ok(exp_vals / sum_vals)
     }
 
-    fn feed_forward(&self, hidden_states: &Array2<f32>) -> Result<Array2<f32>, PandoraError> {
-        let intermediate_size = self.hidden_size * 4;
-        
+    fn feed_forward(&self, hidden_states: &Array2<f32>, weights: &TransformerLayerWeights) -> Result<Array2<f32>, PandoraError> {
         // First linear layer
-        let w1 = Array2::from_shape_fn((self.hidden_size, intermediate_size), |_| fastrand::f32() * 0.02 - 0.01);
-        let b1: Array1<f32> = Array1::zeros(intermediate_size);
-        let intermediate = hidden_states.dot(&w1) + &b1;
+        let intermediate = hidden_states.dot(&weights.w1);
         
         // GELU activation
         let activated = self.gelu(&intermediate)?;
         
         // Second linear layer
-        let w2 = Array2::from_shape_fn((intermediate_size, self.hidden_size), |_| fastrand::f32() * 0.02 - 0.01);
-        let b2: Array1<f32> = Array1::zeros(self.hidden_size);
-        
-        Ok(activated.dot(&w2) + &b2)
+        Ok(activated.dot(&weights.w2))
     }
 
     fn gelu(&self, input: &Array2<f32>) -> Result<Array2<f32>, PandoraError> {
@@ -265,98 +363,347 @@ impl QueSTEncoder {
             return Ok(tensor);
         }
         
-        // INT8 quantization
-        let scale = 127.0;
-        let quantized = &tensor * scale;
-        let clamped = quantized.mapv(|x| x.clamp(-128.0, 127.0));
-        Ok(clamped.mapv(|x| x.round()))
+        // Symmetric INT8 fake-quantization với scale theo max |x|
+        let max_abs = tensor.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+        if max_abs == 0.0 {
+            return Ok(tensor);
+        }
+        let scale = max_abs / 127.0;
+        Ok(tensor.mapv(|x| (x / scale).round().clamp(-127.0, 127.0) * scale))
     }
 }
 
 // ===== Vector Quantizer =====
 
+#[derive(Debug, Clone)]
+pub struct VectorQuantizerConfig {
+    pub codebook_size: usize,
+    pub codebook_dim: usize,
+    /// β trong commitment loss `β·‖z − e‖²`
+    pub commitment_cost: f32,
+    /// Decay γ của EMA cho cluster size và tổng embedding của mỗi code
+    pub ema_decay: f32,
+    /// Laplace smoothing cho cluster size
+    pub epsilon: f32,
+    /// Một code không được chọn trong chừng này lần update thì bị coi là dead và khởi tạo lại
+    pub dead_code_patience: u32,
+    /// Số input gần nhất giữ lại để khởi tạo lại dead code
+    pub reservoir_size: usize,
+    pub seed: u64,
+}
+
+impl Default for VectorQuantizerConfig {
+    fn default() -> Self {
+        Self {
+            codebook_size: 512,
+            codebook_dim: 64,
+            commitment_cost: 0.25,
+            ema_decay: 0.99,
+            epsilon: 1e-5,
+            dead_code_patience: 100,
+            reservoir_size: 256,
+            seed: 0,
+        }
+    }
+}
+
+/// Kết quả của một bước `VectorQuantizer::update`
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizerUpdate {
+    /// Code được gán cho từng input (trước khi cập nhật codebook)
+    pub codes: Vec<usize>,
+    pub commitment_loss: f32,
+    /// Các dead code vừa được khởi tạo lại
+    pub reinitialized: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodebookMetrics {
+    /// Số lần mỗi code được gán từ trước tới nay
+    pub code_usage: Vec<u64>,
+    /// Số code được dùng trong `dead_code_patience` update gần nhất
+    pub active_codes: usize,
+    /// `exp(H(p))` với p là phân phối EMA cluster size (mức dùng gần đây); 0 khi chưa train
+    pub perplexity: f32,
+    /// EMA của commitment loss qua các update
+    pub commitment_loss: f32,
+    pub dead_code_resets: u64,
+}
+
+/// Codebook VQ học bằng k-means++ init + EMA updates (VQ-VAE-2), có khởi tạo lại dead code.
 pub struct VectorQuantizer {
-    codebook_size: usize,
-    codebook_dim: usize,
-    commitment_cost: f32,
+    config: VectorQuantizerConfig,
     codebook: Array2<f32>,
+    ema_cluster_size: Array1<f32>,
+    ema_embed_sum: Array2<f32>,
+    initialized: bool,
+    code_usage: Vec<u64>,
+    steps_since_used: Vec<u32>,
+    commitment_loss_ema: Option<f32>,
+    dead_code_resets: u64,
+    reservoir: Vec<Array1<f32>>,
+    reservoir_next: usize,
+    rng: fastrand::Rng,
 }
 
+/// Nhiễu nhỏ khi đặt code trùng một input đã có (tránh các code trùng nhau)
+const CODE_JITTER: f32 = 1e-3;
+
 impl VectorQuantizer {
     pub fn new(codebook_size: usize, codebook_dim: usize, commitment_cost: f32) -> Self {
-        let codebook = Array2::from_shape_fn((codebook_size, codebook_dim), |_| {
-            fastrand::f32() * 0.02 - 0.01
-        });
-        
-        Self {
+        Self::with_config(VectorQuantizerConfig {
             codebook_size,
             codebook_dim,
             commitment_cost,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_config(config: VectorQuantizerConfig) -> Self {
+        let mut rng = fastrand::Rng::with_seed(config.seed);
+        let (k, dim) = (config.codebook_size, config.codebook_dim);
+        // Codebook ngẫu nhiên chỉ dùng cho tới lần update đầu tiên (k-means++ init)
+        let codebook = Array2::from_shape_fn((k, dim), |_| rng.f32() * 0.02 - 0.01);
+
+        Self {
             codebook,
+            ema_cluster_size: Array1::zeros(k),
+            ema_embed_sum: Array2::zeros((k, dim)),
+            initialized: false,
+            code_usage: vec![0; k],
+            steps_since_used: vec![0; k],
+            commitment_loss_ema: None,
+            dead_code_resets: 0,
+            reservoir: Vec::with_capacity(config.reservoir_size),
+            reservoir_next: 0,
+            rng,
+            config,
         }
     }
 
+    pub fn config(&self) -> &VectorQuantizerConfig {
+        &self.config
+    }
+
+    pub fn codebook(&self) -> &Array2<f32> {
+        &self.codebook
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Lượng tử hóa `inputs` (mỗi hàng một vector) về code gần nhất, không cập nhật codebook.
     pub fn quantize(&self, inputs: &Array2<f32>) -> Result<(Array2<f32>, Array1<usize>, f32), PandoraError> {
-        let (batch_size, seq_len) = inputs.dim();
-        let hidden_dim = self.codebook.ncols();
-        let flat_inputs = inputs.clone().into_shape((batch_size * seq_len, hidden_dim)).unwrap();
-        
-        // Compute distances
-        let distances = self.compute_distances(&flat_inputs)?;
-        
-        // Find closest codes
-        let encoding_indices = distances.map_axis(ndarray::Axis(1), |row| {
-            row.iter().enumerate().max_by(|a, b| a.1.partial_cmp(b.1).unwrap()).unwrap().0
+        self.check_dim(inputs)?;
+        let (codes, distances) = self.nearest_codes(inputs);
+        let quantized = self.quantize_codes(&codes);
+        let commitment_loss = self.commitment_loss(&distances);
+        Ok((quantized, Array1::from(codes), commitment_loss))
+    }
+
+    /// Khởi tạo codebook bằng k-means++ trên `samples`.
+    pub fn initialize(&mut self, samples: &Array2<f32>) -> Result<(), PandoraError> {
+        self.check_dim(samples)?;
+        let n = samples.nrows();
+        if n == 0 {
+            return Err(PandoraError::EncodingError("Cannot initialize codebook from no samples".to_string()));
+        }
+        let first = self.rng.usize(..n);
+        self.codebook.row_mut(0).assign(&samples.row(first));
+        let mut d2: Vec<f32> = samples.rows().into_iter()
+            .map(|x| squared_distance(x, self.codebook.row(0)))
+            .collect();
+        for k in 1..self.config.codebook_size {
+            let total: f32 = d2.iter().sum();
+            if total > 0.0 {
+                // Chọn input với xác suất tỉ lệ D(x)²
+                let mut target = self.rng.f32() * total;
+                let idx = d2.iter().position(|&d| { target -= d; target <= 0.0 && d > 0.0 })
+                    .unwrap_or_else(|| d2.iter().rposition(|&d| d > 0.0).unwrap_or(0));
+                self.codebook.row_mut(k).assign(&samples.row(idx));
+            } else {
+                // Mọi input đã trùng một code: lấy input ngẫu nhiên kèm nhiễu
+                let sample = samples.row(self.rng.usize(..n)).to_owned();
+                let jittered = self.jitter(sample);
+                self.codebook.row_mut(k).assign(&jittered);
+            }
+            for (d, x) in d2.iter_mut().zip(samples.rows()) {
+                *d = d.min(squared_distance(x, self.codebook.row(k)));
+            }
+        }
+        self.ema_cluster_size.fill(0.0);
+        self.ema_embed_sum.fill(0.0);
+        self.steps_since_used.iter_mut().for_each(|s| *s = 0);
+        self.initialized = true;
+        Ok(())
+    }
+
+    /// Một bước học: gán code, cập nhật EMA, khởi tạo lại dead code.
+    /// Lần gọi đầu tiên khởi tạo codebook bằng k-means++ trên chính batch này.
+    pub fn update(&mut self, inputs: &Array2<f32>) -> Result<QuantizerUpdate, PandoraError> {
+        self.check_dim(inputs)?;
+        if !self.initialized {
+            self.initialize(inputs)?;
+        }
+        for x in inputs.rows() {
+            self.remember(x.to_owned());
+        }
+
+        let (codes, distances) = self.nearest_codes(inputs);
+        let commitment_loss = self.commitment_loss(&distances);
+        let (k, gamma) = (self.config.codebook_size, self.config.ema_decay);
+
+        let mut counts = Array1::<f32>::zeros(k);
+        let mut sums = Array2::<f32>::zeros((k, self.config.codebook_dim));
+        for (x, &code) in inputs.rows().into_iter().zip(&codes) {
+            counts[code] += 1.0;
+            let mut row = sums.row_mut(code);
+            row += &x;
+            self.code_usage[code] += 1;
+        }
+        self.ema_cluster_size = &self.ema_cluster_size * gamma + &(&counts * (1.0 - gamma));
+        self.ema_embed_sum = &self.ema_embed_sum * gamma + &(&sums * (1.0 - gamma));
+
+        // Laplace smoothing để cluster size không bằng 0
+        let total = self.ema_cluster_size.sum();
+        let eps = self.config.epsilon;
+        for code in 0..k {
+            if counts[code] > 0.0 {
+                self.steps_since_used[code] = 0;
+            } else {
+                self.steps_since_used[code] = self.steps_since_used[code].saturating_add(1);
+            }
+            // Code chưa từng được gán giữ nguyên vị trí k-means++
+            if self.ema_cluster_size[code] > 0.0 {
+                let smoothed = (self.ema_cluster_size[code] + eps) / (total + k as f32 * eps) * total;
+                let embed = &self.ema_embed_sum.row(code) / smoothed;
+                self.codebook.row_mut(code).assign(&embed);
+            }
+        }
+
+        self.commitment_loss_ema = Some(match self.commitment_loss_ema {
+            Some(prev) => gamma * prev + (1.0 - gamma) * commitment_loss,
+            None => commitment_loss,
         });
-        
-        // Quantize
-        let quantized = self.quantize_codes(&encoding_indices)?;
-        
-        // Compute commitment loss
-        let commitment_loss = self.compute_commitment_loss(&flat_inputs, &quantized)?;
-        
-        // Straight-through estimator
-        let quantized_st = &flat_inputs + &(&quantized - &flat_inputs);
-        
-        Ok((
-            quantized_st,
-            encoding_indices,
+        let reinitialized = self.reinitialize_dead_codes();
+
+        Ok(QuantizerUpdate {
+            codes,
             commitment_loss,
-        ))
+            reinitialized,
+        })
     }
 
-    fn compute_distances(&self, inputs: &Array2<f32>) -> Result<Array2<f32>, PandoraError> {
-        let input_norm = inputs.map_axis(ndarray::Axis(1), |row| row.iter().map(|x| x * x).sum::<f32>());
-        let codebook_norm = self.codebook.map_axis(ndarray::Axis(1), |row| row.iter().map(|x| x * x).sum::<f32>());
-        
-        let distances = input_norm.insert_axis(ndarray::Axis(1)) + &codebook_norm.insert_axis(ndarray::Axis(0));
-        
-        // Compute 2 * <inputs, codebook>
-        let dot_product = inputs.dot(&self.codebook.t());
-        let double_dot = &dot_product * 2.0;
-        
-        Ok(distances - double_dot)
+    pub fn metrics(&self) -> CodebookMetrics {
+        let total = self.ema_cluster_size.sum();
+        let perplexity = if total > 0.0 {
+            let entropy: f32 = self.ema_cluster_size.iter()
+                .map(|&n| n / total)
+                .filter(|&p| p > 0.0)
+                .map(|p| -p * p.ln())
+                .sum();
+            entropy.exp()
+        } else {
+            0.0
+        };
+        let active_codes = if self.initialized {
+            self.code_usage.iter().zip(&self.steps_since_used)
+                .filter(|&(&usage, &idle)| usage > 0 && idle < self.config.dead_code_patience)
+                .count()
+        } else {
+            0
+        };
+        CodebookMetrics {
+            code_usage: self.code_usage.clone(),
+            active_codes,
+            perplexity,
+            commitment_loss: self.commitment_loss_ema.unwrap_or(0.0),
+            dead_code_resets: self.dead_code_resets,
+        }
     }
 
-    fn quantize_codes(&self, encoding_indices: &Array1<usize>) -> Result<Array2<f32>, PandoraError> {
-        let mut quantized = Array2::zeros((encoding_indices.len(), self.codebook_dim));
-        
-        for (i, &idx) in encoding_indices.iter().enumerate() {
-            if idx < self.codebook_size {
-                quantized.slice_mut(s![i, ..]).assign(&self.codebook.slice(s![idx, ..]));
+    fn reinitialize_dead_codes(&mut self) -> Vec<usize> {
+        let mut reinitialized = Vec::new();
+        if self.reservoir.is_empty() {
+            return reinitialized;
+        }
+        for code in 0..self.config.codebook_size {
+            if self.steps_since_used[code] < self.config.dead_code_patience {
+                continue;
             }
+            let sample = self.reservoir[self.rng.usize(..self.reservoir.len())].clone();
+            let embed = self.jitter(sample);
+            self.codebook.row_mut(code).assign(&embed);
+            self.ema_cluster_size[code] = 0.0;
+            self.ema_embed_sum.row_mut(code).fill(0.0);
+            self.steps_since_used[code] = 0;
+            self.dead_code_resets += 1;
+            reinitialized.push(code);
         }
-        
-        Ok(quantized)
+        reinitialized
+    }
+
+    fn remember(&mut self, x: Array1<f32>) {
+        if self.config.reservoir_size == 0 {
+            return;
+        }
+        if self.reservoir.len() < self.config.reservoir_size {
+            self.reservoir.push(x);
+        } else {
+            self.reservoir[self.reservoir_next] = x;
+        }
+        self.reservoir_next = (self.reservoir_next + 1) % self.config.reservoir_size;
     }
 
-    fn compute_commitment_loss(&self, inputs: &Array2<f32>, quantized: &Array2<f32>) -> Result<f32, PandoraError> {
-        let diff = inputs - quantized;
-        let mse = diff.mapv(|x| x * x).mean().unwrap();
-        Ok(mse * self.commitment_cost)
+    fn jitter(&mut self, x: Array1<f32>) -> Array1<f32> {
+        x.mapv(|v| v + (self.rng.f32() * 2.0 - 1.0) * CODE_JITTER * (1.0 + v.abs()))
+    }
+
+    fn check_dim(&self, inputs: &Array2<f32>) -> Result<(), PandoraError> {
+        if inputs.ncols() != self.config.codebook_dim {
+            return Err(PandoraError::EncodingError(format!(
+                "Expected {}-dimensional inputs, got {}",
+                self.config.codebook_dim,
+                inputs.ncols()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Code gần nhất và bình phương khoảng cách tới nó, cho từng hàng của `inputs`
+    fn nearest_codes(&self, inputs: &Array2<f32>) -> (Vec<usize>, Vec<f32>) {
+        inputs.rows().into_iter()
+            .map(|x| {
+                self.codebook.rows().into_iter()
+                    .map(|e| squared_distance(x, e))
+                    .enumerate()
+                    .fold((0, f32::INFINITY), |best, (k, d)| if d < best.1 { (k, d) } else { best })
+            })
+            .unzip()
+    }
+
+    fn quantize_codes(&self, codes: &[usize]) -> Array2<f32> {
+        let mut quantized = Array2::zeros((codes.len(), self.config.codebook_dim));
+        for (i, &code) in codes.iter().enumerate() {
+            quantized.row_mut(i).assign(&self.codebook.row(code));
+        }
+        quantized
+    }
+
+    fn commitment_loss(&self, squared_distances: &[f32]) -> f32 {
+        if squared_distances.is_empty() {
+            return 0.0;
+        }
+        let mse = squared_distances.iter().sum::<f32>() / (squared_distances.len() * self.config.codebook_dim) as f32;
+        mse * self.config.commitment_cost
     }
 }
 
+fn squared_distance(a: ndarray::ArrayView1<f32>, b: ndarray::ArrayView1<f32>) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
 // ===== Code Generator =====
 
 #[async_trait]
@@ -365,6 +712,9 @@ pub trait CodeGenerator {
     async fn optimize_code(&self, code: &str, performance_metrics: &SkillPerformanceMetrics) -> Result<String, PandoraError>;
 }
 
+/// Generator offline: trả về template IR program cho từng loại intent, không gọi mô hình
+/// nào. Dùng `OpenAiCodeGenerator` để sinh code bằng LLM thật.
+#[derive(Debug, Clone)]
 pub struct LLMCodeGenerator {
     #[allow(dead_code)]
     model_path: String,
@@ -375,6 +725,7 @@ pub struct LLMCodeGenerator {
 }
 
 impl LLMCodeGenerator {
+    /// Các tham số mô hình được giữ lại cho tương thích API; template không dùng đến chúng.
     pub fn new(model_path: String, max_length: usize, temperature: f32) -> Self {
         Self {
             model_path,
@@ -386,90 +737,293 @@ impl LLMCodeGenerator {
 
 #[async_trait]
 impl CodeGenerator for LLMCodeGenerator {
-    async fn generate_skill_code(&self, intent: &Intent, context: &SkillContext) -> Result<String, PandoraError> {
-        // Simplified code generation - trong thực tế sẽ sử dụng LLM
-        let _prompt = self.create_prompt(intent, context);
-        
-        // Placeholder implementation
-        let generated_code = match intent {
-            Intent::ExecuteTask { task, parameters, .. } => {
-                format!(
-                    "async fn execute_task() -> Result<(), Error> {{
-                        // Task: {}
-                        // Parameters: {:?}
-                        // Implementation here
-                        Ok(())
-                    }}",
-                    task,
-                    parameters
-                )
-            }
-            Intent::MaintainEquilibrium { current_state, target_state } => {
-                format!(
-                    "async fn maintain_equilibrium() -> Result<(), Error> {{
-                        // Current: {:?}
-                        // Target: {:?}
-                        // Balance logic here
-                        Ok(())
-                    }}",
-                    current_state,
-                    target_state
-                )
-            }
-            Intent::ExecutePlan { .. } => {
-                "async fn execute_plan() -> Result<(), Error> {
-                    // Plan execution
-                    // Sequential execution here
-                    Ok(())
-                }".to_string()
-            }
-            Intent::LearnSkill { skill_type, .. } => {
-                format!(
-                    "async fn learn_skill() -> Result<(), Error> {{
-                        // Skill type: {}
-                        // Learning algorithm here
-                        Ok(())
-                    }}",
-                    skill_type
-                )
-            }
-        };
-        
-        Ok(generated_code)
+    async fn generate_skill_code(&self, intent: &Intent, _context: &SkillContext) -> Result<String, PandoraError> {
+        to_source(&skill_program_for(intent))
     }
 
-    async fn optimize_code(&self, code: &str, metrics: &SkillPerformanceMetrics) -> Result<String, PandoraError> {
-        // Code optimization based on performance metrics
-        let mut optimized = code.to_string();
-        
-        // Simple optimizations
-        if metrics.execution_time_ms > 1000.0 {
-            optimized = optimized.replace("// Implementation here", "// Optimized implementation");
+    async fn optimize_code(&self, code: &str, _metrics: &SkillPerformanceMetrics) -> Result<String, PandoraError> {
+        // Template programs are already minimal; only make sure the code still compiles
+        let program = SkillProgram::compile(code)
+            .map_err(|e| PandoraError::SkillVerificationFailed(e.to_string()))?;
+        to_source(&program)
+    }
+}
+
+// ===== Skill Programs =====
+
+/// Gain of the proportional controller used by equilibrium skills.
+const EQUILIBRIUM_GAIN: f64 = 0.5;
+
+fn entry_type(value: Type) -> Type {
+    Type::record([("key", Type::Text), ("value", value)])
+}
+
+fn sorted_entries<T: Serialize>(map: &HashMap<String, T>) -> Json {
+    let sorted: BTreeMap<_, _> = map.iter().collect();
+    Json::Array(sorted.into_iter().map(|(key, value)| json!({ "key": key, "value": value })).collect())
+}
+
+/// Prompt gửi cho LLM: intent, context và interface mà skill phải tuân theo
+pub(crate) fn skill_prompt(intent: &Intent, context: &SkillContext) -> String {
+    let template = skill_program_for(intent);
+    let interface = json!({
+        "name": template.name,
+        "params": template.params,
+        "output": template.output,
+    });
+    format!(
+        "Generate a skill program for the following intent:\n\
+        Intent: {:?}\n\
+        Context: {:?}\n\
+        Requirements: Performance: {}, Memory: {}MB, CPU: {} cores\n\
+        Interface: {}\n\
+        Respond with a JSON skill program (typed skill IR):",
+        intent,
+        context,
+        context.performance_target,
+        context.memory_limit_mb,
+        context.cpu_cores,
+        interface
+    )
+}
+
+/// Source JSON của `program`
+pub(crate) fn to_source(program: &SkillProgram) -> Result<String, PandoraError> {
+    program
+        .to_source()
+        .map_err(|e| PandoraError::EncodingError(e.to_string()))
+}
+
+/// Compile `code` và kiểm tra params/output khớp với interface của intent
+pub(crate) fn validate_skill_code(code: &str, intent: &Intent) -> Result<SkillProgram, PandoraError> {
+    let program = SkillProgram::compile(code)
+        .map_err(|e| PandoraError::SkillVerificationFailed(e.to_string()))?;
+    let expected = skill_program_for(intent);
+    if program.params != expected.params || program.output != expected.output {
+        return Err(PandoraError::SkillVerificationFailed(format!(
+            "Skill '{}' does not match the interface: params must be {}, output must be {}",
+            program.name,
+            serde_json::to_string(&expected.params).unwrap_or_default(),
+            serde_json::to_string(&expected.output).unwrap_or_default()
+        )));
+    }
+    Ok(program)
+}
+
+/// Template IR program cho một loại intent (thay cho output của LLM)
+fn skill_program_for(intent: &Intent) -> SkillProgram {
+    let var = Expr::var;
+    let (name, description, params, output, body) = match intent {
+        Intent::ExecuteTask { task, .. } => {
+            let parameters = Type::list(entry_type(Type::Text));
+            let params = BTreeMap::from([
+                ("task".to_string(), Type::Text),
+                ("parameters".to_string(), parameters.clone()),
+                ("complexity".to_string(), Type::Number),
+                ("priority".to_string(), Type::Number),
+            ]);
+            let output = Type::record([
+                ("task", Type::Text),
+                ("parameters", parameters),
+                ("complexity", Type::Number),
+                ("priority", Type::Number),
+            ]);
+            // Hoàn thành task, bỏ các tham số rỗng
+            let body = Expr::record([
+                ("task", Expr::binary(BinaryOp::Concat, Expr::text("Completed: "), var("task"))),
+                ("parameters", Expr::filter(
+                    var("parameters"),
+                    "p",
+                    Expr::binary(BinaryOp::Ne, Expr::field(var("p"), "value"), Expr::text("")),
+                )),
+                ("complexity", Expr::Number(0.0)),
+                ("priority", Expr::Number(0.0)),
+            ]);
+            ("execute_task", format!("Execute task: {}", task), params, output, body)
         }
-        
-        if metrics.memory_usage_mb > 100.0 {
-            optimized = optimized.replace("// Sequential execution here", "// Memory-efficient execution");
+        Intent::MaintainEquilibrium { .. } => {
+            let state = Type::list(entry_type(Type::Number));
+            let params = BTreeMap::from([
+                ("current_state".to_string(), state.clone()),
+                ("target_state".to_string(), state.clone()),
+            ]);
+            let output = Type::record([("current_state", state.clone()), ("target_state", state)]);
+            // current = giá trị hiện tại của key (mặc định là target), rồi tiến một bước về target
+            let current = Expr::fold(
+                var("current_state"),
+                Expr::field(var("t"), "value"),
+                "acc",
+                "c",
+                Expr::if_else(
+                    Expr::binary(BinaryOp::Eq, Expr::field(var("c"), "key"), Expr::field(var("t"), "key")),
+                    Expr::field(var("c"), "value"),
+                    var("acc"),
+                ),
+            );
+            let step = Expr::binary(
+                BinaryOp::Add,
+                var("cur"),
+                Expr::binary(
+                    BinaryOp::Mul,
+                    Expr::Number(EQUILIBRIUM_GAIN),
+                    Expr::binary(BinaryOp::Sub, Expr::field(var("t"), "value"), var("cur")),
+                ),
+            );
+            let body = Expr::record([
+                ("current_state", Expr::map(
+                    var("target_state"),
+                    "t",
+                    Expr::let_in("cur", current, Expr::record([
+                        ("key", Expr::field(var("t"), "key")),
+                        ("value", step),
+                    ])),
+                )),
+                ("target_state", var("target_state")),
+            ]);
+            ("maintain_equilibrium", "Move each state variable towards its target".to_string(), params, output, body)
         }
-        
-        Ok(optimized)
+        Intent::ExecutePlan { plan, .. } => {
+            let params = BTreeMap::from([
+                ("plan".to_string(), Type::list(Type::Text)),
+                ("estimated_duration".to_string(), Type::Number),
+            ]);
+            let output = Type::record([("plan", Type::list(Type::Text)), ("estimated_duration", Type::Number)]);
+            let body = Expr::record([
+                ("plan", Expr::map(
+                    var("plan"),
+                    "step",
+                    Expr::binary(BinaryOp::Concat, Expr::text("Completed: "), var("step")),
+                )),
+                ("estimated_duration", Expr::Number(0.0)),
+            ]);
+            ("execute_plan", format!("Execute a {}-step plan", plan.len()), params, output, body)
+        }
+        Intent::LearnSkill { skill_type, .. } => {
+            let params = BTreeMap::from([
+                ("skill_type".to_string(), Type::Text),
+                ("training_data".to_string(), Type::list(Type::Number)),
+                ("target_performance".to_string(), Type::Number),
+            ]);
+            let output = Type::record([
+                ("skill_type", Type::Text),
+                ("training_data", Type::list(Type::Number)),
+                ("target_performance", Type::Number),
+            ]);
+            // training_data của kết quả = [mean, variance] của dữ liệu huấn luyện
+            let average = |sum: Expr| Expr::if_else(
+                Expr::binary(BinaryOp::Eq, var("n"), Expr::Number(0.0)),
+                Expr::Number(0.0),
+                Expr::binary(BinaryOp::Div, sum, var("n")),
+            );
+            let sum = |term: Expr| Expr::fold(
+                var("training_data"),
+                Expr::Number(0.0),
+                "acc",
+                "x",
+                Expr::binary(BinaryOp::Add, var("acc"), term),
+            );
+            let deviation = Expr::binary(BinaryOp::Sub, var("x"), var("mean"));
+            let body = Expr::let_in("n", Expr::unary(UnaryOp::Len, var("training_data")),
+                Expr::let_in("mean", average(sum(var("x"))),
+                    Expr::let_in("variance", average(sum(Expr::binary(BinaryOp::Mul, deviation.clone(), deviation))),
+                        Expr::record([
+                            ("skill_type", Expr::binary(BinaryOp::Concat, Expr::text("Learned: "), var("skill_type"))),
+                            ("training_data", Expr::List {
+                                item: Type::Number,
+                                items: vec![var("mean"), var("variance")],
+                            }),
+                            ("target_performance", var("target_performance")),
+                        ]))));
+            ("learn_skill", format!("Learn skill: {}", skill_type), params, output, body)
+        }
+    };
+    SkillProgram { name: name.to_string(), description, params, output, body }
+}
+
+/// Input JSON của skill program cho một intent
+fn intent_input(intent: &Intent) -> Json {
+    match intent {
+        Intent::ExecuteTask { task, parameters, complexity, priority } => json!({
+            "task": task,
+            "parameters": sorted_entries(parameters),
+            "complexity": complexity,
+            "priority": priority,
+        }),
+        Intent::MaintainEquilibrium { current_state, target_state } => json!({
+            "current_state": sorted_entries(current_state),
+            "target_state": sorted_entries(target_state),
+        }),
+        Intent::ExecutePlan { plan, estimated_duration, .. } => json!({
+            "plan": plan,
+            "estimated_duration": estimated_duration,
+        }),
+        Intent::LearnSkill { skill_type, training_data, target_performance } => json!({
+            "skill_type": skill_type,
+            "training_data": training_data,
+            "target_performance": target_performance,
+        }),
     }
 }
 
-impl LLMCodeGenerator {
-    fn create_prompt(&self, intent: &Intent, context: &SkillContext) -> String {
-        format!(
-            "Generate Rust code for the following intent:\n\
-            Intent: {:?}\n\
-            Context: {:?}\n\
-            Requirements: Performance: {}, Memory: {}MB, CPU: {} cores\n\
-            Generate efficient, safe Rust code:",
-            intent,
-            context,
-            context.performance_target,
-            context.memory_limit_mb,
-            context.cpu_cores
-        )
+/// Đọc output của skill program thành intent kết quả (cùng loại với `intent`)
+fn intent_from_output(intent: &Intent, output: Json) -> Result<Intent, serde_json::Error> {
+    #[derive(Deserialize)]
+    struct Entry<T> {
+        key: String,
+        value: T,
+    }
+    fn entries<T>(entries: Vec<Entry<T>>) -> HashMap<String, T> {
+        entries.into_iter().map(|e| (e.key, e.value)).collect()
     }
+    // IR numbers are f64, so integer fields are read as floats and cast
+    Ok(match intent {
+        Intent::ExecuteTask { .. } => {
+            #[derive(Deserialize)]
+            struct Output { task: String, parameters: Vec<Entry<String>>, complexity: f32, priority: f64 }
+            let out: Output = serde_json::from_value(output)?;
+            Intent::ExecuteTask {
+                task: out.task,
+                parameters: entries(out.parameters),
+                complexity: out.complexity,
+                priority: out.priority as u8,
+            }
+        }
+        Intent::MaintainEquilibrium { .. } => {
+            #[derive(Deserialize)]
+            struct Output { current_state: Vec<Entry<f32>>, target_state: Vec<Entry<f32>> }
+            let out: Output = serde_json::from_value(output)?;
+            Intent::MaintainEquilibrium {
+                current_state: entries(out.current_state),
+                target_state: entries(out.target_state),
+            }
+        }
+        Intent::ExecutePlan { .. } => {
+            #[derive(Deserialize)]
+            struct Output { plan: Vec<String>, estimated_duration: f64 }
+            let out: Output = serde_json::from_value(output)?;
+            Intent::ExecutePlan {
+                plan: out.plan,
+                estimated_duration: out.estimated_duration as u64,
+                resource_requirements: ResourceRequirements {
+                    cpu_cores: 0,
+                    memory_mb: 0,
+                    gpu_memory_mb: 0,
+                    network_bandwidth_mbps: 0,
+                    storage_mb: 0,
+                },
+            }
+        }
+        Intent::LearnSkill { .. } => {
+            #[derive(Deserialize)]
+            struct Output { skill_type: String, training_data: Vec<f32>, target_performance: f32 }
+            let out: Output = serde_json::from_value(output)?;
+            Intent::LearnSkill {
+                skill_type: out.skill_type,
+                training_data: out.training_data,
+                target_performance: out.target_performance,
+            }
+        }
+    })
 }
 
 // ===== Skill Context =====
@@ -508,21 +1062,32 @@ pub struct GeneratedSkill {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_used: Option<chrono::DateTime<chrono::Utc>>,
     pub usage_count: u32,
+    /// Compiled program, sandboxed within `resource_requirements`
+    pub module: Arc<SandboxedSkill>,
+    /// Mã rời rạc (VQ code) của intent đã forge ra skill
+    pub codes: Vec<usize>,
 }
 
 // ===== SkillForge Main Implementation =====
 
 pub struct SkillForge {
+    tokenizer: IntentTokenizer,
     quest_encoder: QueSTEncoder,
     vector_quantizer: VectorQuantizer,
     code_generator: Box<dyn CodeGenerator + Send + Sync>,
     skills: HashMap<String, GeneratedSkill>,
     performance_tracker: PerformanceTracker,
+    lifecycle: SkillLifecycle,
 }
 
+/// Cửa sổ thời gian thực để đếm throughput (số lần thực thi hoàn tất mỗi giây)
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(1);
+
 pub struct PerformanceTracker {
     metrics: HashMap<String, Vec<SkillPerformanceMetrics>>,
     thresholds: PerformanceThresholds,
+    /// Thời điểm các lần thực thi hoàn tất trong `THROUGHPUT_WINDOW` gần nhất
+    completions: HashMap<String, VecDeque<Instant>>,
 }
 
 #[derive(Debug, Clone)]
@@ -541,98 +1106,194 @@ impl SkillForge {
         code_generator: Box<dyn CodeGenerator + Send + Sync>,
     ) -> Self {
         Self {
+            tokenizer: IntentTokenizer::new(quest_encoder.vocab_size()),
             quest_encoder,
             vector_quantizer,
             code_generator,
             skills: HashMap::new(),
             performance_tracker: PerformanceTracker::new(),
+            lifecycle: SkillLifecycle::default(),
         }
     }
 
-    /// Forge a new skill based on intent
+    /// Replaces the canary / retirement settings
+    pub fn with_lifecycle_config(mut self, config: SkillLifecycleConfig) -> Self {
+        self.lifecycle = SkillLifecycle::new(config);
+        self
+    }
+
+    /// Replaces the thresholds that trigger canary rollback
+    pub fn with_performance_thresholds(mut self, thresholds: PerformanceThresholds) -> Self {
+        self.performance_tracker.thresholds = thresholds;
+        self
+    }
+
+    /// Forge a new skill based on intent (version 1.0.0 của một line mới)
     pub async fn forge_skill(&mut self, intent: &Intent, context: &SkillContext) -> Result<String, PandoraError> {
         // Encode intent using QueST
         let intent_encoding = self.encode_intent(intent)?;
         
-        // Quantize the encoding
-        let (_quantized, _encoding_indices, _commitment_loss) = self.vector_quantizer.quantize(&intent_encoding)?;
+        // Quantize the encoding (và cập nhật codebook online)
+        let codes = self.vector_quantizer.update(&intent_encoding)?.codes;
         
         // Generate code using LLM
         let generated_code = self.code_generator.generate_skill_code(intent, context).await?;
         
-        // Create skill ID
-        let skill_id = Uuid::new_v4().to_string();
-        
-        // Create generated skill
-        let skill = GeneratedSkill {
-            skill_id: skill_id.clone(),
-            code: generated_code,
-            performance_metrics: SkillPerformanceMetrics::default(),
-            resource_requirements: self.estimate_resource_requirements(intent, context),
-            created_at: chrono::Utc::now(),
-            last_used: None,
-            usage_count: 0,
-        };
+        // Compile và đóng gói vào sandbox theo tài nguyên đã khai báo
+        let resource_requirements = self.estimate_resource_requirements(intent, context);
+        let skill = build_skill(generated_code, intent, resource_requirements, codes)?;
+        let skill_id = skill.skill_id.clone();
         
         // Store skill
+        self.lifecycle.register_root(&skill_id, intent.clone(), skill.created_at);
         self.skills.insert(skill_id.clone(), skill);
         
         Ok(skill_id)
     }
 
-    /// Execute a skill
+    /// Forge version mới của `parent_id` bằng `optimize_code`; version mới chạy
+    /// như canary của line cho đến khi được promote hoặc rollback
+    pub async fn evolve_skill(&mut self, parent_id: &str) -> Result<String, PandoraError> {
+        let parent = self.skills.get(parent_id)
+            .ok_or_else(|| PandoraError::PredictionFailed(format!("Skill not found: {}", parent_id)))?;
+        let record = self.lifecycle.record(parent_id)
+            .ok_or_else(|| PandoraError::State(format!("Skill has no lifecycle record: {}", parent_id)))?;
+        let intent = record.lineage.intent.clone();
+        let version = self.lifecycle.next_version(parent_id).unwrap_or(SkillVersion::INITIAL);
+
+        let optimized_code = self.code_generator
+            .optimize_code(&parent.code, &parent.performance_metrics)
+            .await?;
+        let skill = build_skill(optimized_code, &intent, parent.resource_requirements.clone(), parent.codes.clone())?;
+        let skill_id = skill.skill_id.clone();
+
+        self.lifecycle.register_version(&skill_id, parent_id, version, skill.created_at)?;
+        self.skills.insert(skill_id.clone(), skill);
+        Ok(skill_id)
+    }
+
+    /// Execute a skill inside its sandbox
     pub async fn execute_skill(&mut self, skill_id: &str, intent: &Intent) -> Result<Intent, PandoraError> {
-        // Pre-compute metrics to avoid borrowing issues
-        let memory_usage = self.estimate_memory_usage(intent);
-        let cpu_usage = self.estimate_cpu_usage(intent);
+        let skill = self.skills.get_mut(skill_id)
+            .ok_or_else(|| PandoraError::PredictionFailed(format!("Skill not found: {}", skill_id)))?;
         
-        // Execute skill (simplified)
         let start_time = std::time::Instant::now();
-        let result = self.simulate_skill_execution(intent).await?;
-        let execution_time = start_time.elapsed().as_millis() as f32;
+        let report = skill.module.run(&intent_input(intent))
+            .map_err(execution_error)
+            .and_then(|report| {
+                let output = intent_from_output(intent, report.output.clone())
+                    .map_err(|e| PandoraError::Deserialize(e.to_string()))?;
+                Ok((report, output))
+            });
+        let execution_time = start_time.elapsed().as_secs_f32() * 1000.0;
         
         // Update skill after execution
-        if let Some(skill) = self.skills.get_mut(skill_id) {
-            skill.last_used = Some(chrono::Utc::now());
-            skill.usage_count += 1;
-            
-            // Update performance metrics
-            skill.performance_metrics.execution_time_ms = execution_time;
-            skill.performance_metrics.memory_usage_mb = memory_usage;
-            skill.performance_metrics.cpu_usage_percent = cpu_usage;
-            
-            // Track performance
-            self.performance_tracker.record_metrics(skill_id, &skill.performance_metrics);
-        } else {
-            return Err(PandoraError::PredictionFailed(format!("Skill not found: {}", skill_id)));
-        }
+        let now = chrono::Utc::now();
+        skill.last_used = Some(now);
+        skill.usage_count += 1;
+        
+        // Lần thực thi lỗi được tính là vi phạm ngưỡng
+        let (report, result) = match report {
+            Ok(ok) => ok,
+            Err(e) => {
+                self.lifecycle.record_execution(skill_id, false, now);
+                return Err(e);
+            }
+        };
+        
+        // Metrics đo được từ sandbox thay vì ước lượng
+        let limits = skill.module.limits();
+        skill.performance_metrics.execution_time_ms = execution_time;
+        skill.performance_metrics.memory_usage_mb = report.memory_used as f32 / (1 << 20) as f32;
+        skill.performance_metrics.cpu_usage_percent = report.fuel_used as f32 / limits.fuel as f32 * 100.0;
+        skill.performance_metrics.throughput = self.performance_tracker.record_completion(skill_id, Instant::now());
+        
+        // Track performance
+        self.performance_tracker.record_metrics(skill_id, &skill.performance_metrics);
+        let healthy = self.performance_tracker.is_execution_healthy(&skill.performance_metrics);
+        self.lifecycle.record_execution(skill_id, healthy, now);
         
         Ok(result)
     }
 
-    fn encode_intent(&self, intent: &Intent) -> Result<Array2<f32>, PandoraError> {
-        // Convert intent to token IDs (simplified)
-        let tokens = self.intent_to_tokens(intent);
-        self.quest_encoder.encode(&tokens)
-            .map_err(|e| PandoraError::EncodingError(e.to_string()))
+    /// Thực thi intent trên line `line_id`, chia lưu lượng giữa stable và canary.
+    /// Nếu canary lỗi và bị rollback, intent được chạy lại trên version stable.
+    /// Trả về id của skill đã phục vụ cùng kết quả.
+    pub async fn execute_line(&mut self, line_id: &str, intent: &Intent) -> Result<(String, Intent), PandoraError> {
+        let skill_id = self.lifecycle.route(line_id)
+            .ok_or_else(|| PandoraError::PredictionFailed(format!("No active skill in line: {}", line_id)))?;
+        match self.execute_skill(&skill_id, intent).await {
+            Ok(result) => Ok((skill_id, result)),
+            Err(e) => {
+                let rolled_back = self.lifecycle.record(&skill_id)
+                    .is_some_and(|r| r.status == SkillStatus::RolledBack);
+                match self.lifecycle.stable(line_id).map(|r| r.skill_id.clone()) {
+                    Some(stable) if rolled_back && stable != skill_id => {
+                        let result = self.execute_skill(&stable, intent).await?;
+                        Ok((stable, result))
+                    }
+                    _ => Err(e),
+                }
+            }
+        }
     }
 
-    fn intent_to_tokens(&self, intent: &Intent) -> Vec<u32> {
-        // Simplified tokenization
-        match intent {
-            Intent::ExecuteTask { .. } => {
-                vec![1, 2, 3] // Placeholder tokens
-            }
-            Intent::MaintainEquilibrium { .. } => {
-                vec![4, 5, 6]
-            }
-            Intent::ExecutePlan { .. } => {
-                vec![7, 8, 9]
-            }
-            Intent::LearnSkill { .. } => {
-                vec![10, 11, 12]
-            }
+    /// Retire các skill có mức sử dụng đã suy giảm dưới ngưỡng tại thời điểm `now`
+    pub fn retire_idle_skills(&mut self, now: chrono::DateTime<chrono::Utc>) -> Vec<String> {
+        let idle = self.lifecycle.idle_skills(now);
+        for skill_id in &idle {
+            self.lifecycle.retire(skill_id, now);
+            self.skills.remove(skill_id);
         }
+        idle
+    }
+
+    /// Version, lineage và audit log của các skill
+    pub fn lifecycle(&self) -> &SkillLifecycle {
+        &self.lifecycle
+    }
+
+    /// Skill đã forge dưới dạng `SkillModule`, để đăng ký với orchestrator
+    pub fn skill_module(&self, skill_id: &str) -> Option<Arc<dyn SkillModule>> {
+        self.skills.get(skill_id).map(|skill| skill.module.clone() as Arc<dyn SkillModule>)
+    }
+
+    /// Pretrain codebook trên một tập intent: k-means++ init rồi `epochs` bước EMA
+    pub fn pretrain_codebook(&mut self, intents: &[Intent], epochs: usize) -> Result<CodebookMetrics, PandoraError> {
+        let encodings = intents.iter()
+            .map(|intent| self.encode_intent(intent))
+            .collect::<Result<Vec<_>, _>>()?;
+        let views: Vec<_> = encodings.iter().map(|e| e.view()).collect();
+        let batch = ndarray::concatenate(ndarray::Axis(0), &views)
+            .map_err(|e| PandoraError::EncodingError(e.to_string()))?;
+        if !self.vector_quantizer.is_initialized() {
+            self.vector_quantizer.initialize(&batch)?;
+        }
+        for _ in 0..epochs {
+            self.vector_quantizer.update(&batch)?;
+        }
+        Ok(self.vector_quantizer.metrics())
+    }
+
+    /// Mã rời rạc của intent theo codebook hiện tại (không cập nhật codebook)
+    pub fn intent_codes(&self, intent: &Intent) -> Result<Vec<usize>, PandoraError> {
+        let (_quantized, codes, _commitment_loss) = self.vector_quantizer.quantize(&self.encode_intent(intent)?)?;
+        Ok(codes.to_vec())
+    }
+
+    pub fn codebook_metrics(&self) -> CodebookMetrics {
+        self.vector_quantizer.metrics()
+    }
+
+    /// Các skill có chung VQ code `code`
+    pub fn skills_with_code(&self, code: usize) -> Vec<&GeneratedSkill> {
+        self.skills.values().filter(|s| s.codes.contains(&code)).collect()
+    }
+
+    fn encode_intent(&self, intent: &Intent) -> Result<Array2<f32>, PandoraError> {
+        let tokens = self.tokenizer.tokenize(intent);
+        self.quest_encoder.encode(&tokens)
+            .map_err(|e| PandoraError::EncodingError(e.to_string()))
     }
 
     fn estimate_resource_requirements(&self, intent: &Intent, _context: &SkillContext) -> ResourceRequirements {
@@ -676,66 +1337,6 @@ impl SkillForge {
         }
     }
 
-    async fn simulate_skill_execution(&self, intent: &Intent) -> Result<Intent, PandoraError> {
-        // Simulate skill execution
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        
-        match intent {
-            Intent::ExecuteTask { .. } => {
-                Ok(Intent::ExecuteTask {
-                    task: "Completed task".to_string(),
-                    parameters: HashMap::new(),
-                    complexity: 0.0,
-                    priority: 0,
-                })
-            }
-            Intent::MaintainEquilibrium { current_state, target_state } => {
-                Ok(Intent::MaintainEquilibrium {
-                    current_state: target_state.clone(),
-                    target_state: current_state.clone(),
-                })
-            }
-            Intent::ExecutePlan { .. } => {
-                Ok(Intent::ExecutePlan {
-                    plan: vec!["Completed".to_string()],
-                    estimated_duration: 0,
-                    resource_requirements: ResourceRequirements {
-                        cpu_cores: 0,
-                        memory_mb: 0,
-                        gpu_memory_mb: 0,
-                        network_bandwidth_mbps: 0,
-                        storage_mb: 0,
-                    },
-                })
-            }
-            Intent::LearnSkill { skill_type, .. } => {
-                Ok(Intent::LearnSkill {
-                    skill_type: format!("Learned: {}", skill_type),
-                    training_data: vec![],
-                    target_performance: 1.0,
-                })
-            }
-        }
-    }
-
-    fn estimate_memory_usage(&self, intent: &Intent) -> f32 {
-        match intent {
-            Intent::ExecuteTask { complexity, .. } => *complexity * 100.0,
-            Intent::MaintainEquilibrium { .. } => 50.0,
-            Intent::ExecutePlan { .. } => 150.0,
-            Intent::LearnSkill { .. } => 500.0,
-        }
-    }
-
-    fn estimate_cpu_usage(&self, intent: &Intent) -> f32 {
-        match intent {
-            Intent::ExecuteTask { complexity, .. } => *complexity * 80.0,
-            Intent::MaintainEquilibrium { .. } => 20.0,
-            Intent::ExecutePlan { .. } => 60.0,
-            Intent::LearnSkill { .. } => 90.0,
-        }
-    }
-
     /// Get skill by ID
     pub fn get_skill(&self, skill_id: &str) -> Option<&GeneratedSkill> {
         self.skills.get(skill_id)
@@ -746,9 +1347,11 @@ impl SkillForge {
         self.skills.values().collect()
     }
 
-    /// Remove skill
+    /// Remove skill (lifecycle record vẫn được giữ để audit)
     pub fn remove_skill(&mut self, skill_id: &str) -> Option<GeneratedSkill> {
-        self.skills.remove(skill_id)
+        let skill = self.skills.remove(skill_id)?;
+        self.lifecycle.remove(skill_id, chrono::Utc::now());
+        Some(skill)
     }
 
     /// Get performance statistics
@@ -769,6 +1372,37 @@ impl SkillForge {
     }
 }
 
+/// Compile code đã sinh thành skill được sandbox theo `resource_requirements`
+/// Lỗi từ sandbox: vượt nhiên liệu hoặc bộ nhớ là cạn tài nguyên, còn lại là thực thi thất bại
+fn execution_error(error: pandora_error::PandoraError) -> PandoraError {
+    match error {
+        pandora_error::PandoraError::InsufficientResources { message } => PandoraError::ResourceExhausted(message),
+        other => PandoraError::PredictionFailed(other.to_string()),
+    }
+}
+
+fn build_skill(
+    code: String,
+    intent: &Intent,
+    resource_requirements: ResourceRequirements,
+    codes: Vec<usize>,
+) -> Result<GeneratedSkill, PandoraError> {
+    let program = validate_skill_code(&code, intent)?;
+    let module = SandboxedSkill::new(program, SandboxLimits::from_requirements(&resource_requirements))
+        .map_err(|e| PandoraError::SkillVerificationFailed(e.to_string()))?;
+    Ok(GeneratedSkill {
+        skill_id: Uuid::new_v4().to_string(),
+        code,
+        performance_metrics: SkillPerformanceMetrics::default(),
+        resource_requirements,
+        created_at: chrono::Utc::now(),
+        last_used: None,
+        usage_count: 0,
+        module: Arc::new(module),
+        codes,
+    })
+}
+
 impl PerformanceTracker {
     pub fn new() -> Self {
         Self {
@@ -780,7 +1414,19 @@ impl PerformanceTracker {
                 min_throughput: 10.0,
                 max_energy_consumption_j: 1000.0,
             },
+            completions: HashMap::new(),
+        }
+    }
+
+    /// Ghi nhận một lần thực thi hoàn tất lúc `now`; trả về số lần hoàn tất mỗi giây
+    /// trong `THROUGHPUT_WINDOW` kết thúc tại `now`
+    pub fn record_completion(&mut self, skill_id: &str, now: Instant) -> f32 {
+        let completions = self.completions.entry(skill_id.to_string()).or_default();
+        completions.push_back(now);
+        while completions.front().is_some_and(|t| now.duration_since(*t) >= THROUGHPUT_WINDOW) {
+            completions.pop_front();
         }
+        completions.len() as f32 / THROUGHPUT_WINDOW.as_secs_f32()
     }
 
     pub fn record_metrics(&mut self, skill_id: &str, metrics: &SkillPerformanceMetrics) {
@@ -793,6 +1439,10 @@ impl PerformanceTracker {
         self.metrics.get(skill_id)
     }
 
+    pub fn thresholds(&self) -> &PerformanceThresholds {
+        &self.thresholds
+    }
+
     pub fn is_performance_acceptable(&self, metrics: &SkillPerformanceMetrics) -> bool {
         metrics.execution_time_ms <= self.thresholds.max_execution_time_ms
             && metrics.memory_usage_mb <= self.thresholds.max_memory_usage_mb
@@ -800,6 +1450,15 @@ impl PerformanceTracker {
             && metrics.throughput >= self.thresholds.min_throughput
             && metrics.energy_consumption_j <= self.thresholds.max_energy_consumption_j
     }
+
+    /// Cổng canary/rollout: chỉ xét các metric đo được trên từng lần thực thi (thời gian,
+    /// bộ nhớ, năng lượng). Accuracy cần output mong đợi và throughput phụ thuộc lưu
+    /// lượng nên không quyết định rollback.
+    pub fn is_execution_healthy(&self, metrics: &SkillPerformanceMetrics) -> bool {
+        metrics.execution_time_ms <= self.thresholds.max_execution_time_ms
+            && metrics.memory_usage_mb <= self.thresholds.max_memory_usage_mb
+            && metrics.energy_consumption_j <= self.thresholds.max_energy_consumption_j
+    }
 }
 
 impl Default for SkillPerformanceMetrics {
@@ -820,3 +1479,359 @@ impl Default for SkillPerformanceMetrics {
 // ===== Error Extensions =====
 
 // Error extensions are now handled in pandora_core::error
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skill_lifecycle::LifecycleEventKind;
+
+    fn forge() -> SkillForge {
+        SkillForge::new(
+            QueSTEncoder::new(256, 16, 1, 2, 32, false),
+            VectorQuantizer::new(2, 16, 0.25),
+            Box::new(LLMCodeGenerator::new("template".to_string(), 4096, 0.0)),
+        )
+    }
+
+    fn context() -> SkillContext {
+        SkillContext {
+            performance_target: 0.9,
+            memory_limit_mb: 64,
+            cpu_cores: 1,
+            gpu_available: false,
+            network_bandwidth_mbps: 0,
+            latency_requirement_ms: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forged_skill_executes_in_sandbox() {
+        let mut forge = forge();
+        let intent = Intent::MaintainEquilibrium {
+            current_state: HashMap::from([("temperature".to_string(), 10.0)]),
+            target_state: HashMap::from([("temperature".to_string(), 20.0), ("humidity".to_string(), 0.4)]),
+        };
+        let skill_id = forge.forge_skill(&intent, &context()).await.unwrap();
+        assert!(SkillProgram::compile(&forge.get_skill(&skill_id).unwrap().code).is_ok());
+
+        match forge.execute_skill(&skill_id, &intent).await.unwrap() {
+            Intent::MaintainEquilibrium { current_state, .. } => {
+                assert_eq!(current_state["temperature"], 15.0);
+                assert_eq!(current_state["humidity"], 0.4);
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+        let skill = forge.get_skill(&skill_id).unwrap();
+        assert_eq!(skill.usage_count, 1);
+        assert!(skill.performance_metrics.cpu_usage_percent > 0.0);
+
+        // A skill only accepts intents of the kind it was forged for
+        let plan = Intent::ExecutePlan {
+            plan: vec!["a".to_string()],
+            estimated_duration: 1,
+            resource_requirements: forge.estimate_resource_requirements(&intent, &context()),
+        };
+        assert!(forge.execute_skill(&skill_id, &plan).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_forged_skill_registers_as_skill_module() {
+        let mut forge = forge();
+        let intent = Intent::LearnSkill {
+            skill_type: "grasp".to_string(),
+            training_data: vec![1.0, 2.0, 3.0],
+            target_performance: 0.9,
+        };
+        let skill_id = forge.forge_skill(&intent, &context()).await.unwrap();
+        let module = forge.skill_module(&skill_id).unwrap();
+        assert_eq!(module.descriptor().name, "learn_skill");
+
+        let output = module.execute(intent_input(&intent)).await.unwrap();
+        assert_eq!(output["skill_type"], "Learned: grasp");
+        let stats = output["training_data"].as_array().unwrap();
+        assert_eq!(stats[0], 2.0);
+        assert!((stats[1].as_f64().unwrap() - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    fn equilibrium() -> Intent {
+        Intent::MaintainEquilibrium {
+            current_state: HashMap::from([("temperature".to_string(), 10.0)]),
+            target_state: HashMap::from([("temperature".to_string(), 20.0)]),
+        }
+    }
+
+    /// "Tối ưu" bằng cách cấp phát một range vượt quá giới hạn bộ nhớ của sandbox
+    struct WastefulOptimizer;
+
+    #[async_trait]
+    impl CodeGenerator for WastefulOptimizer {
+        async fn generate_skill_code(&self, intent: &Intent, _context: &SkillContext) -> Result<String, PandoraError> {
+            to_source(&skill_program_for(intent))
+        }
+
+        async fn optimize_code(&self, code: &str, _metrics: &SkillPerformanceMetrics) -> Result<String, PandoraError> {
+            let mut program = SkillProgram::compile(code).unwrap();
+            let scratch = Expr::Range { start: Box::new(Expr::Number(0.0)), end: Box::new(Expr::Number(1e9)) };
+            program.body = Expr::let_in("scratch", scratch, program.body);
+            to_source(&program)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_canary_is_promoted_after_healthy_traffic() {
+        let mut forge = forge().with_lifecycle_config(SkillLifecycleConfig {
+            canary_fraction: 0.5,
+            promotion_executions: 3,
+            ..Default::default()
+        });
+        let intent = equilibrium();
+        let root = forge.forge_skill(&intent, &context()).await.unwrap();
+        let child = forge.evolve_skill(&root).await.unwrap();
+        // Chỉ một canary mỗi line
+        assert!(forge.evolve_skill(&root).await.is_err());
+
+        let lifecycle = forge.lifecycle();
+        let record = lifecycle.record(&child).unwrap();
+        assert_eq!(record.version, SkillVersion { major: 1, minor: 1, patch: 0 });
+        assert_eq!(record.status, SkillStatus::Canary);
+        assert_eq!(record.lineage.line_id, root);
+        assert_eq!(record.lineage.parent.as_deref(), Some(root.as_str()));
+        let ancestry: Vec<_> = lifecycle.ancestry(&child).iter().map(|r| r.skill_id.clone()).collect();
+        assert_eq!(ancestry, vec![child.clone(), root.clone()]);
+
+        let mut served = HashMap::<String, usize>::new();
+        for _ in 0..200 {
+            let (skill_id, _) = forge.execute_line(&root, &intent).await.unwrap();
+            *served.entry(skill_id).or_default() += 1;
+            if forge.lifecycle().record(&child).unwrap().status == SkillStatus::Stable {
+                break;
+            }
+        }
+        assert_eq!(served[&child], 3);
+        assert!(served[&root] >= 1);
+
+        let lifecycle = forge.lifecycle();
+        assert_eq!(lifecycle.stable(&root).unwrap().skill_id, child);
+        assert_eq!(lifecycle.record(&root).unwrap().status, SkillStatus::Superseded);
+        assert!(lifecycle.events_for(&child).iter().any(|e| {
+            e.kind == LifecycleEventKind::Promoted { replaced: Some(root.clone()) }
+        }));
+        // Sau khi promote, toàn bộ lưu lượng đi vào version mới
+        let (skill_id, _) = forge.execute_line(&root, &intent).await.unwrap();
+        assert_eq!(skill_id, child);
+    }
+
+    #[tokio::test]
+    async fn test_failing_canary_is_rolled_back_to_stable() {
+        let mut forge = SkillForge::new(
+            QueSTEncoder::new(256, 16, 1, 2, 32, false),
+            VectorQuantizer::new(2, 16, 0.25),
+            Box::new(WastefulOptimizer),
+        )
+        .with_lifecycle_config(SkillLifecycleConfig { canary_fraction: 1.0, ..Default::default() });
+        let intent = equilibrium();
+        let root = forge.forge_skill(&intent, &context()).await.unwrap();
+        let child = forge.evolve_skill(&root).await.unwrap();
+
+        // Canary nhận request, vượt giới hạn bộ nhớ, và stable phục vụ thay
+        let (skill_id, result) = forge.execute_line(&root, &intent).await.unwrap();
+        assert_eq!(skill_id, root);
+        match result {
+            Intent::MaintainEquilibrium { current_state, .. } => assert_eq!(current_state["temperature"], 15.0),
+            other => panic!("unexpected result {other:?}"),
+        }
+
+        let lifecycle = forge.lifecycle();
+        let record = lifecycle.record(&child).unwrap();
+        assert_eq!(record.status, SkillStatus::RolledBack);
+        assert_eq!(record.violations, 1);
+        assert!(lifecycle.canary(&root).is_none());
+        assert!(lifecycle.events_for(&child).iter().any(|e| matches!(e.kind, LifecycleEventKind::RolledBack { .. })));
+        assert_eq!(forge.execute_line(&root, &intent).await.unwrap().0, root);
+        assert!(matches!(
+            forge.execute_skill(&child, &intent).await,
+            Err(PandoraError::ResourceExhausted(_))
+        ));
+    }
+
+    #[test]
+    fn test_throughput_counts_completions_per_window() {
+        let mut tracker = PerformanceTracker::new();
+        let start = Instant::now();
+        assert_eq!(tracker.record_completion("a", start), 1.0);
+        assert_eq!(tracker.record_completion("a", start + Duration::from_millis(300)), 2.0);
+        assert_eq!(tracker.record_completion("a", start + Duration::from_millis(900)), 3.0);
+        // Lần đầu đã ra khỏi cửa sổ 1s
+        assert_eq!(tracker.record_completion("a", start + Duration::from_millis(1100)), 3.0);
+        assert_eq!(tracker.record_completion("b", start), 1.0);
+
+        // Canary chậm (> 100ms), ít lưu lượng và chưa đo accuracy vẫn không bị rollback,
+        // nhưng không đạt ngưỡng hiệu năng đầy đủ
+        let slow = SkillPerformanceMetrics {
+            execution_time_ms: 250.0,
+            accuracy: 0.0,
+            throughput: 1.0,
+            ..SkillPerformanceMetrics::default()
+        };
+        assert!(tracker.is_execution_healthy(&slow));
+        assert!(!tracker.is_performance_acceptable(&slow));
+        let over_memory = SkillPerformanceMetrics { memory_usage_mb: 600.0, ..slow };
+        assert!(!tracker.is_execution_healthy(&over_memory));
+    }
+
+    #[tokio::test]
+    async fn test_idle_skills_are_retired_by_usage_decay() {
+        let mut forge = forge();
+        let intent = equilibrium();
+        let busy = forge.forge_skill(&intent, &context()).await.unwrap();
+        let idle = forge.forge_skill(&intent, &context()).await.unwrap();
+        for _ in 0..5 {
+            forge.execute_skill(&busy, &intent).await.unwrap();
+        }
+
+        let now = chrono::Utc::now();
+        assert!(forge.retire_idle_skills(now + chrono::Duration::hours(1)).is_empty());
+        // Half-life một ngày: 1 lần dùng suy giảm dưới 0.05 sau ~4.3 ngày, 6 lần sau ~6.9 ngày
+        assert_eq!(forge.retire_idle_skills(now + chrono::Duration::days(5)), vec![idle.clone()]);
+        assert!(forge.get_skill(&idle).is_none());
+        assert!(forge.get_skill(&busy).is_some());
+        assert_eq!(forge.retire_idle_skills(now + chrono::Duration::days(8)), vec![busy.clone()]);
+
+        // Record vẫn được giữ để audit
+        let lifecycle = forge.lifecycle();
+        assert_eq!(lifecycle.record(&idle).unwrap().status, SkillStatus::Retired);
+        assert_eq!(lifecycle.record(&busy).unwrap().executions, 5);
+        assert!(matches!(
+            lifecycle.events_for(&busy).last().unwrap().kind,
+            LifecycleEventKind::Retired { usage_score } if usage_score < 0.05
+        ));
+        assert!(forge.execute_line(&busy, &intent).await.is_err());
+    }
+
+    fn task(task: &str) -> Intent {
+        Intent::ExecuteTask {
+            task: task.to_string(),
+            parameters: HashMap::from([("speed".to_string(), "normal".to_string())]),
+            complexity: 0.5,
+            priority: 1,
+        }
+    }
+
+    fn learn(skill_type: &str) -> Intent {
+        Intent::LearnSkill {
+            skill_type: skill_type.to_string(),
+            training_data: vec![0.5; 16],
+            target_performance: 0.9,
+        }
+    }
+
+    /// Bốn cụm 2D tách biệt, mỗi cụm `per_blob` điểm
+    fn blobs(per_blob: usize, rng: &mut fastrand::Rng) -> (Array2<f32>, Vec<usize>) {
+        let centers = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0), (10.0, 10.0)];
+        let mut points = Array2::zeros((4 * per_blob, 2));
+        let mut labels = Vec::new();
+        for (b, (cx, cy)) in centers.iter().enumerate() {
+            for i in 0..per_blob {
+                let row = b * per_blob + i;
+                points[[row, 0]] = cx + rng.f32() - 0.5;
+                points[[row, 1]] = cy + rng.f32() - 0.5;
+                labels.push(b);
+            }
+        }
+        (points, labels)
+    }
+
+    #[test]
+    fn test_tokenizer_is_deterministic_and_shares_words() {
+        let tokenizer = IntentTokenizer::new(256);
+        let a = tokenizer.tokenize(&task("navigate to the kitchen"));
+        assert_eq!(a, tokenizer.tokenize(&task("Navigate to the kitchen")));
+        let b = tokenizer.tokenize(&task("navigate to the garage"));
+        assert_eq!(a.iter().filter(|t| b.contains(t)).count(), a.len() - 1);
+        assert!(a.iter().all(|&t| t < 256));
+        assert_ne!(tokenizer.tokenize(&learn("navigate"))[0], a[0]);
+    }
+
+    #[test]
+    fn test_codebook_learns_clusters_with_ema() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        let (points, labels) = blobs(25, &mut rng);
+        let mut vq = VectorQuantizer::with_config(VectorQuantizerConfig {
+            codebook_size: 4,
+            codebook_dim: 2,
+            ema_decay: 0.9,
+            seed: 3,
+            ..Default::default()
+        });
+        let first = vq.update(&points).unwrap();
+        let mut last = first.clone();
+        for _ in 0..50 {
+            last = vq.update(&points).unwrap();
+        }
+        assert!(last.commitment_loss < first.commitment_loss);
+
+        // Mỗi cụm đúng một code, bốn cụm bốn code khác nhau
+        let (_, codes, _) = vq.quantize(&points).unwrap();
+        let mut cluster_codes: Vec<usize> = (0..4).map(|b| codes[b * 25]).collect();
+        for (code, label) in codes.iter().zip(&labels) {
+            assert_eq!(*code, cluster_codes[*label]);
+        }
+        cluster_codes.sort();
+        cluster_codes.dedup();
+        assert_eq!(cluster_codes.len(), 4);
+
+        let metrics = vq.metrics();
+        assert_eq!(metrics.active_codes, 4);
+        assert!(metrics.perplexity > 3.9);
+        assert_eq!(metrics.code_usage.iter().sum::<u64>(), 51 * 100);
+        assert!(vq.update(&Array2::zeros((1, 3))).is_err());
+    }
+
+    #[test]
+    fn test_dead_codes_are_reinitialized_from_recent_inputs() {
+        let mut rng = fastrand::Rng::with_seed(11);
+        let (points, _) = blobs(10, &mut rng);
+        let mut vq = VectorQuantizer::with_config(VectorQuantizerConfig {
+            codebook_size: 2,
+            codebook_dim: 2,
+            dead_code_patience: 5,
+            seed: 5,
+            ..Default::default()
+        });
+        // Cả hai code khởi tạo trong cụm đầu tiên, sau đó dữ liệu chỉ đến từ cụm cuối
+        vq.initialize(&points.slice(s![..10, ..]).to_owned()).unwrap();
+        let far = points.slice(s![30.., ..]).to_owned();
+        let mut reinitialized = Vec::new();
+        for _ in 0..10 {
+            reinitialized.extend(vq.update(&far).unwrap().reinitialized);
+        }
+        assert!(!reinitialized.is_empty());
+        assert!(vq.metrics().dead_code_resets >= 1);
+        for code in vq.codebook().rows() {
+            assert!((code[0] - 10.0).abs() < 1.0 && (code[1] - 10.0).abs() < 1.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_similar_intents_share_codes() {
+        let mut forge = forge();
+        let corpus: Vec<Intent> = [
+            "navigate to the kitchen", "navigate to the door", "navigate to the charger",
+        ]
+        .iter()
+        .map(|t| task(t))
+        .chain(["grasp cup", "grasp bottle", "grasp box"].iter().map(|t| learn(t)))
+        .collect();
+        let metrics = forge.pretrain_codebook(&corpus, 20).unwrap();
+        assert!(metrics.active_codes >= 2);
+
+        let kitchen = forge.forge_skill(&task("navigate to the kitchen"), &context()).await.unwrap();
+        let garage = forge.forge_skill(&task("navigate to the garage"), &context()).await.unwrap();
+        let grasp = forge.forge_skill(&learn("grasp spoon"), &context()).await.unwrap();
+        let code = |id: &str| forge.get_skill(id).unwrap().codes.clone();
+        assert_eq!(code(&kitchen), code(&garage));
+        assert_ne!(code(&kitchen), code(&grasp));
+        assert_eq!(forge.skills_with_code(code(&kitchen)[0]).len(), 2);
+        assert_eq!(forge.intent_codes(&task("navigate to the kitchen")).unwrap(), code(&kitchen));
+    }
+}
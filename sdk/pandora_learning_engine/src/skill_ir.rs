@@ -0,0 +1,989 @@
+//! Typed skill IR and its sandboxed interpreter.
+//!
+//! Forged skills are emitted as JSON-encoded `SkillProgram`s: a small,
+//! side-effect-free expression language over numbers, booleans, text, lists and
+//! records. A program is type-checked once when it is compiled; the interpreter
+//! then charges one unit of fuel per evaluated node and counts every byte it
+//! allocates, so a skill can never run past the `ResourceRequirements` it declares.
+//! The IR has no I/O at all, so network, storage and GPU budgets are trivially met.
+
+use crate::skill_forge_lightweight::ResourceRequirements;
+use async_trait::async_trait;
+use pandora_core::interfaces::skills::{SkillDescriptor, SkillModule, SkillOutput};
+use pandora_error::PandoraError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use std::collections::BTreeMap;
+
+/// Evaluation steps granted per declared CPU core.
+pub const FUEL_PER_CORE: u64 = 1_000_000;
+/// Maximum nesting depth of a program; bounds the interpreter's recursion.
+pub const MAX_EXPR_DEPTH: usize = 64;
+const BYTES_PER_MB: usize = 1 << 20;
+/// Accounted size of one list element or record field slot.
+const SLOT_BYTES: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Type {
+    Number,
+    Bool,
+    Text,
+    List(Box<Type>),
+    Record(BTreeMap<String, Type>),
+}
+
+impl Type {
+    pub fn list(item: Type) -> Self {
+        Type::List(Box::new(item))
+    }
+
+    pub fn record<K: Into<String>>(fields: impl IntoIterator<Item = (K, Type)>) -> Self {
+        Type::Record(fields.into_iter().map(|(k, t)| (k.into(), t)).collect())
+    }
+}
+
+/// Runtime value of the IR.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+    List(Vec<Value>),
+    Record(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Converts JSON input into a value of type `ty`; extra record fields are ignored.
+    pub fn from_json(json: &Json, ty: &Type) -> Result<Self, String> {
+        match (ty, json) {
+            (Type::Number, Json::Number(n)) => n
+                .as_f64()
+                .map(Value::Number)
+                .ok_or_else(|| format!("{n} is not representable as f64")),
+            (Type::Bool, Json::Bool(b)) => Ok(Value::Bool(*b)),
+            (Type::Text, Json::String(s)) => Ok(Value::Text(s.clone())),
+            (Type::List(item), Json::Array(items)) => items
+                .iter()
+                .map(|i| Value::from_json(i, item))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            (Type::Record(fields), Json::Object(map)) => fields
+                .iter()
+                .map(|(name, t)| {
+                    let v = map
+                        .get(name)
+                        .ok_or_else(|| format!("missing field `{name}`"))?;
+                    Ok((name.clone(), Value::from_json(v, t)?))
+                })
+                .collect::<Result<_, String>>()
+                .map(Value::Record),
+            (ty, json) => Err(format!("expected {ty:?}, got {json}")),
+        }
+    }
+
+    /// Converts the value to JSON; fails on NaN and infinities, which JSON cannot encode.
+    pub fn to_json(&self) -> Result<Json, String> {
+        Ok(match self {
+            Value::Number(n) => serde_json::Number::from_f64(*n)
+                .map(Json::Number)
+                .ok_or_else(|| format!("non-finite number {n}"))?,
+            Value::Bool(b) => Json::Bool(*b),
+            Value::Text(s) => Json::String(s.clone()),
+            Value::List(items) => {
+                Json::Array(items.iter().map(Value::to_json).collect::<Result<_, _>>()?)
+            }
+            Value::Record(fields) => Json::Object(
+                fields
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), v.to_json()?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+        })
+    }
+
+    /// Bytes this value owns beyond its inline slot.
+    fn heap_bytes(&self) -> usize {
+        match self {
+            Value::Number(_) | Value::Bool(_) => 0,
+            Value::Text(s) => s.len(),
+            Value::List(items) => items.iter().map(|v| SLOT_BYTES + v.heap_bytes()).sum(),
+            Value::Record(fields) => fields
+                .iter()
+                .map(|(k, v)| k.len() + SLOT_BYTES + v.heap_bytes())
+                .sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnaryOp {
+    Neg,
+    Not,
+    Abs,
+    Sqrt,
+    Floor,
+    /// Length of a text (in bytes) or a list.
+    Len,
+    ToText,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Min,
+    Max,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    /// Concatenates two texts or two lists of the same item type.
+    Concat,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    Number(f64),
+    Bool(bool),
+    Text(String),
+    /// Input parameter, or a variable bound by `let`, `map`, `filter` or `fold`.
+    Var(String),
+    List {
+        item: Type,
+        items: Vec<Expr>,
+    },
+    Record(BTreeMap<String, Expr>),
+    Field {
+        record: Box<Expr>,
+        name: String,
+    },
+    Index {
+        list: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// `[start, start + 1, ..)` up to (excluding) `end`, both floored.
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        arg: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    If {
+        cond: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    Let {
+        name: String,
+        value: Box<Expr>,
+        body: Box<Expr>,
+    },
+    Map {
+        list: Box<Expr>,
+        var: String,
+        body: Box<Expr>,
+    },
+    Filter {
+        list: Box<Expr>,
+        var: String,
+        body: Box<Expr>,
+    },
+    Fold {
+        list: Box<Expr>,
+        init: Box<Expr>,
+        acc: String,
+        var: String,
+        body: Box<Expr>,
+    },
+}
+
+// Constructors used by code generators that build programs in Rust.
+impl Expr {
+    pub fn text(s: impl Into<String>) -> Self {
+        Expr::Text(s.into())
+    }
+
+    pub fn var(name: impl Into<String>) -> Self {
+        Expr::Var(name.into())
+    }
+
+    pub fn field(record: Expr, name: impl Into<String>) -> Self {
+        Expr::Field {
+            record: Box::new(record),
+            name: name.into(),
+        }
+    }
+
+    pub fn record<K: Into<String>>(fields: impl IntoIterator<Item = (K, Expr)>) -> Self {
+        Expr::Record(fields.into_iter().map(|(k, e)| (k.into(), e)).collect())
+    }
+
+    pub fn unary(op: UnaryOp, arg: Expr) -> Self {
+        Expr::Unary {
+            op,
+            arg: Box::new(arg),
+        }
+    }
+
+    pub fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Self {
+        Expr::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn if_else(cond: Expr, then: Expr, otherwise: Expr) -> Self {
+        Expr::If {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        }
+    }
+
+    pub fn let_in(name: impl Into<String>, value: Expr, body: Expr) -> Self {
+        Expr::Let {
+            name: name.into(),
+            value: Box::new(value),
+            body: Box::new(body),
+        }
+    }
+
+    pub fn map(list: Expr, var: impl Into<String>, body: Expr) -> Self {
+        Expr::Map {
+            list: Box::new(list),
+            var: var.into(),
+            body: Box::new(body),
+        }
+    }
+
+    pub fn filter(list: Expr, var: impl Into<String>, body: Expr) -> Self {
+        Expr::Filter {
+            list: Box::new(list),
+            var: var.into(),
+            body: Box::new(body),
+        }
+    }
+
+    pub fn fold(
+        list: Expr,
+        init: Expr,
+        acc: impl Into<String>,
+        var: impl Into<String>,
+        body: Expr,
+    ) -> Self {
+        Expr::Fold {
+            list: Box::new(list),
+            init: Box::new(init),
+            acc: acc.into(),
+            var: var.into(),
+            body: Box::new(body),
+        }
+    }
+}
+
+/// A forged skill: typed parameters, an output type and a body expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillProgram {
+    pub name: String,
+    pub description: String,
+    pub params: BTreeMap<String, Type>,
+    pub output: Type,
+    pub body: Expr,
+}
+
+impl SkillProgram {
+    /// Parses and type-checks a program from its JSON source.
+    pub fn compile(source: &str) -> Result<Self, PandoraError> {
+        let program: Self =
+            serde_json::from_str(source).map_err(|e| PandoraError::Serialization {
+                message: format!("Invalid skill program: {e}"),
+                source: Some(e),
+            })?;
+        program.check()?;
+        Ok(program)
+    }
+
+    /// Serializes the program back to the JSON source accepted by [`SkillProgram::compile`].
+    pub fn to_source(&self) -> Result<String, PandoraError> {
+        serde_json::to_string_pretty(self).map_err(|e| PandoraError::Serialization {
+            message: format!("Cannot serialize skill program '{}': {e}", self.name),
+            source: Some(e),
+        })
+    }
+
+    /// Verifies that the body is well typed and produces `output`.
+    pub fn check(&self) -> Result<(), PandoraError> {
+        let mut checker = Checker {
+            scope: self
+                .params
+                .iter()
+                .map(|(k, t)| (k.clone(), t.clone()))
+                .collect(),
+        };
+        checker
+            .type_of(&self.body, 0)
+            .and_then(|ty| expect(&ty, &self.output))
+            .map(|_| ())
+            .map_err(|e| PandoraError::config(format!("Skill '{}' is ill-typed: {e}", self.name)))
+    }
+
+    pub fn input_type(&self) -> Type {
+        Type::Record(self.params.clone())
+    }
+
+    pub fn descriptor(&self) -> SkillDescriptor {
+        SkillDescriptor {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            input_schema: serde_json::to_string(&self.input_type()).unwrap_or_default(),
+            output_schema: serde_json::to_string(&self.output).unwrap_or_default(),
+        }
+    }
+}
+
+fn expect(actual: &Type, expected: &Type) -> Result<Type, String> {
+    if actual == expected {
+        Ok(actual.clone())
+    } else {
+        Err(format!("expected {expected:?}, found {actual:?}"))
+    }
+}
+
+struct Checker {
+    scope: Vec<(String, Type)>,
+}
+
+impl Checker {
+    fn scoped(
+        &mut self,
+        bindings: Vec<(String, Type)>,
+        body: &Expr,
+        depth: usize,
+    ) -> Result<Type, String> {
+        let n = bindings.len();
+        self.scope.extend(bindings);
+        let ty = self.type_of(body, depth);
+        self.scope.truncate(self.scope.len() - n);
+        ty
+    }
+
+    fn list_item(&mut self, list: &Expr, depth: usize) -> Result<Type, String> {
+        match self.type_of(list, depth)? {
+            Type::List(item) => Ok(*item),
+            other => Err(format!("expected a list, found {other:?}")),
+        }
+    }
+
+    fn type_of(&mut self, expr: &Expr, depth: usize) -> Result<Type, String> {
+        if depth >= MAX_EXPR_DEPTH {
+            return Err(format!("expression nested deeper than {MAX_EXPR_DEPTH}"));
+        }
+        let d = depth + 1;
+        match expr {
+            Expr::Number(_) => Ok(Type::Number),
+            Expr::Bool(_) => Ok(Type::Bool),
+            Expr::Text(_) => Ok(Type::Text),
+            Expr::Var(name) => self
+                .scope
+                .iter()
+                .rev()
+                .find(|(k, _)| k == name)
+                .map(|(_, t)| t.clone())
+                .ok_or_else(|| format!("unknown variable `{name}`")),
+            Expr::List { item, items } => {
+                for e in items {
+                    expect(&self.type_of(e, d)?, item)?;
+                }
+                Ok(Type::list(item.clone()))
+            }
+            Expr::Record(fields) => fields
+                .iter()
+                .map(|(k, e)| Ok((k.clone(), self.type_of(e, d)?)))
+                .collect::<Result<_, String>>()
+                .map(Type::Record),
+            Expr::Field { record, name } => match self.type_of(record, d)? {
+                Type::Record(fields) => fields
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| format!("record has no field `{name}`")),
+                other => Err(format!("expected a record, found {other:?}")),
+            },
+            Expr::Index { list, index } => {
+                expect(&self.type_of(index, d)?, &Type::Number)?;
+                self.list_item(list, d)
+            }
+            Expr::Range { start, end } => {
+                expect(&self.type_of(start, d)?, &Type::Number)?;
+                expect(&self.type_of(end, d)?, &Type::Number)?;
+                Ok(Type::list(Type::Number))
+            }
+            Expr::Unary { op, arg } => {
+                let ty = self.type_of(arg, d)?;
+                match (op, &ty) {
+                    (
+                        UnaryOp::Neg | UnaryOp::Abs | UnaryOp::Sqrt | UnaryOp::Floor,
+                        Type::Number,
+                    ) => Ok(Type::Number),
+                    (UnaryOp::Not, Type::Bool) => Ok(Type::Bool),
+                    (UnaryOp::Len, Type::Text | Type::List(_)) => Ok(Type::Number),
+                    (UnaryOp::ToText, _) => Ok(Type::Text),
+                    _ => Err(format!("{op:?} cannot be applied to {ty:?}")),
+                }
+            }
+            Expr::Binary { op, lhs, rhs } => {
+                let ty = self.type_of(lhs, d)?;
+                expect(&self.type_of(rhs, d)?, &ty)?;
+                use BinaryOp::*;
+                match (op, &ty) {
+                    (Add | Sub | Mul | Div | Rem | Pow | Min | Max, Type::Number) => {
+                        Ok(Type::Number)
+                    }
+                    (Eq | Ne, _) | (Lt | Le | Gt | Ge, Type::Number | Type::Text) => Ok(Type::Bool),
+                    (And | Or, Type::Bool) => Ok(Type::Bool),
+                    (Concat, Type::Text | Type::List(_)) => Ok(ty),
+                    _ => Err(format!("{op:?} cannot be applied to {ty:?}")),
+                }
+            }
+            Expr::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                expect(&self.type_of(cond, d)?, &Type::Bool)?;
+                let ty = self.type_of(then, d)?;
+                expect(&self.type_of(otherwise, d)?, &ty)
+            }
+            Expr::Let { name, value, body } => {
+                let ty = self.type_of(value, d)?;
+                self.scoped(vec![(name.clone(), ty)], body, d)
+            }
+            Expr::Map { list, var, body } => {
+                let item = self.list_item(list, d)?;
+                self.scoped(vec![(var.clone(), item)], body, d)
+                    .map(Type::list)
+            }
+            Expr::Filter { list, var, body } => {
+                let item = self.list_item(list, d)?;
+                expect(
+                    &self.scoped(vec![(var.clone(), item.clone())], body, d)?,
+                    &Type::Bool,
+                )?;
+                Ok(Type::list(item))
+            }
+            Expr::Fold {
+                list,
+                init,
+                acc,
+                var,
+                body,
+            } => {
+                let item = self.list_item(list, d)?;
+                let ty = self.type_of(init, d)?;
+                let bindings = vec![(acc.clone(), ty.clone()), (var.clone(), item)];
+                expect(&self.scoped(bindings, body, d)?, &ty)
+            }
+        }
+    }
+}
+
+/// Fuel and memory granted to one execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SandboxLimits {
+    /// Maximum number of evaluated expression nodes (and range/list elements).
+    pub fuel: u64,
+    /// Maximum number of bytes allocated over the run, input included.
+    pub memory_bytes: usize,
+}
+
+impl SandboxLimits {
+    /// `FUEL_PER_CORE` per declared core and the declared memory, at least one of each.
+    pub fn from_requirements(requirements: &ResourceRequirements) -> Self {
+        Self {
+            fuel: FUEL_PER_CORE * u64::from(requirements.cpu_cores.max(1)),
+            memory_bytes: (requirements.memory_mb.max(1) as usize).saturating_mul(BYTES_PER_MB),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionReport {
+    pub output: Json,
+    pub fuel_used: u64,
+    pub memory_used: usize,
+}
+
+enum Trap {
+    OutOfFuel,
+    OutOfMemory(usize),
+    Fault(String),
+}
+
+struct Machine {
+    limits: SandboxLimits,
+    fuel_used: u64,
+    memory_used: usize,
+    scope: Vec<(String, Value)>,
+}
+
+impl Machine {
+    fn tick(&mut self, steps: u64) -> Result<(), Trap> {
+        self.fuel_used = self.fuel_used.saturating_add(steps);
+        if self.fuel_used > self.limits.fuel {
+            return Err(Trap::OutOfFuel);
+        }
+        Ok(())
+    }
+
+    /// Accounts for `bytes` before they are allocated.
+    fn alloc(&mut self, bytes: usize) -> Result<(), Trap> {
+        let total = self.memory_used.saturating_add(bytes);
+        if total > self.limits.memory_bytes {
+            return Err(Trap::OutOfMemory(total));
+        }
+        self.memory_used = total;
+        Ok(())
+    }
+
+    fn number(&mut self, expr: &Expr) -> Result<f64, Trap> {
+        match self.eval(expr)? {
+            Value::Number(n) => Ok(n),
+            other => Err(Trap::Fault(format!("expected a number, found {other:?}"))),
+        }
+    }
+
+    fn boolean(&mut self, expr: &Expr) -> Result<bool, Trap> {
+        match self.eval(expr)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(Trap::Fault(format!("expected a bool, found {other:?}"))),
+        }
+    }
+
+    fn list(&mut self, expr: &Expr) -> Result<Vec<Value>, Trap> {
+        match self.eval(expr)? {
+            Value::List(items) => Ok(items),
+            other => Err(Trap::Fault(format!("expected a list, found {other:?}"))),
+        }
+    }
+
+    fn scoped(&mut self, bindings: Vec<(String, Value)>, body: &Expr) -> Result<Value, Trap> {
+        let n = bindings.len();
+        self.scope.extend(bindings);
+        let value = self.eval(body);
+        self.scope.truncate(self.scope.len() - n);
+        value
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Value, Trap> {
+        self.tick(1)?;
+        match expr {
+            Expr::Number(n) => Ok(Value::Number(*n)),
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+            Expr::Text(s) => {
+                self.alloc(s.len())?;
+                Ok(Value::Text(s.clone()))
+            }
+            Expr::Var(name) => {
+                let value = self
+                    .scope
+                    .iter()
+                    .rev()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v)
+                    .ok_or_else(|| Trap::Fault(format!("unknown variable `{name}`")))?;
+                let (bytes, value) = (value.heap_bytes(), value.clone());
+                self.alloc(bytes)?;
+                Ok(value)
+            }
+            Expr::List { items, .. } => {
+                self.alloc(items.len() * SLOT_BYTES)?;
+                items
+                    .iter()
+                    .map(|e| self.eval(e))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List)
+            }
+            Expr::Record(fields) => {
+                self.alloc(fields.keys().map(|k| k.len() + SLOT_BYTES).sum())?;
+                fields
+                    .iter()
+                    .map(|(k, e)| Ok((k.clone(), self.eval(e)?)))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Record)
+            }
+            Expr::Field { record, name } => match self.eval(record)? {
+                Value::Record(mut fields) => fields
+                    .remove(name)
+                    .ok_or_else(|| Trap::Fault(format!("record has no field `{name}`"))),
+                other => Err(Trap::Fault(format!("expected a record, found {other:?}"))),
+            },
+            Expr::Index { list, index } => {
+                let mut items = self.list(list)?;
+                let i = self.number(index)?;
+                if i < 0.0 || i.fract() != 0.0 || i >= items.len() as f64 {
+                    return Err(Trap::Fault(format!(
+                        "index {i} out of bounds for a list of length {}",
+                        items.len()
+                    )));
+                }
+                Ok(items.swap_remove(i as usize))
+            }
+            Expr::Range { start, end } => {
+                let (start, end) = (self.number(start)?.floor(), self.number(end)?.floor());
+                if !start.is_finite() || !end.is_finite() {
+                    return Err(Trap::Fault("range bounds must be finite".to_string()));
+                }
+                let len = (end - start).max(0.0) as usize;
+                // Charged up front so an oversized range never reaches the allocator
+                self.alloc(len.saturating_mul(SLOT_BYTES))?;
+                self.tick(len as u64)?;
+                Ok(Value::List(
+                    (0..len).map(|i| Value::Number(start + i as f64)).collect(),
+                ))
+            }
+            Expr::Unary { op, arg } => {
+                let value = self.eval(arg)?;
+                match (op, value) {
+                    (UnaryOp::Neg, Value::Number(n)) => Ok(Value::Number(-n)),
+                    (UnaryOp::Abs, Value::Number(n)) => Ok(Value::Number(n.abs())),
+                    (UnaryOp::Sqrt, Value::Number(n)) => Ok(Value::Number(n.sqrt())),
+                    (UnaryOp::Floor, Value::Number(n)) => Ok(Value::Number(n.floor())),
+                    (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                    (UnaryOp::Len, Value::Text(s)) => Ok(Value::Number(s.len() as f64)),
+                    (UnaryOp::Len, Value::List(items)) => Ok(Value::Number(items.len() as f64)),
+                    (UnaryOp::ToText, value) => {
+                        let text = match value {
+                            Value::Text(s) => s,
+                            Value::Number(n) => n.to_string(),
+                            Value::Bool(b) => b.to_string(),
+                            other => other.to_json().map_err(Trap::Fault)?.to_string(),
+                        };
+                        self.alloc(text.len())?;
+                        Ok(Value::Text(text))
+                    }
+                    (op, value) => Err(Trap::Fault(format!(
+                        "{op:?} cannot be applied to {value:?}"
+                    ))),
+                }
+            }
+            Expr::Binary { op, lhs, rhs } => self.binary(*op, lhs, rhs),
+            Expr::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                if self.boolean(cond)? {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+            Expr::Let { name, value, body } => {
+                let value = self.eval(value)?;
+                self.scoped(vec![(name.clone(), value)], body)
+            }
+            Expr::Map { list, var, body } => {
+                let items = self.list(list)?;
+                self.alloc(items.len() * SLOT_BYTES)?;
+                items
+                    .into_iter()
+                    .map(|item| self.scoped(vec![(var.clone(), item)], body))
+                    .collect::<Result<_, _>>()
+                    .map(Value::List)
+            }
+            Expr::Filter { list, var, body } => {
+                let items = self.list(list)?;
+                let mut kept = Vec::new();
+                for item in items {
+                    match self.scoped(vec![(var.clone(), item.clone())], body)? {
+                        Value::Bool(true) => {
+                            self.alloc(SLOT_BYTES)?;
+                            kept.push(item);
+                        }
+                        Value::Bool(false) => {}
+                        other => {
+                            return Err(Trap::Fault(format!("filter predicate returned {other:?}")))
+                        }
+                    }
+                }
+                Ok(Value::List(kept))
+            }
+            Expr::Fold {
+                list,
+                init,
+                acc,
+                var,
+                body,
+            } => {
+                let items = self.list(list)?;
+                let mut state = self.eval(init)?;
+                for item in items {
+                    state = self.scoped(vec![(acc.clone(), state), (var.clone(), item)], body)?;
+                }
+                Ok(state)
+            }
+        }
+    }
+
+    fn binary(&mut self, op: BinaryOp, lhs: &Expr, rhs: &Expr) -> Result<Value, Trap> {
+        use BinaryOp::*;
+        // Short-circuit before evaluating the right-hand side
+        match op {
+            And => return Ok(Value::Bool(self.boolean(lhs)? && self.boolean(rhs)?)),
+            Or => return Ok(Value::Bool(self.boolean(lhs)? || self.boolean(rhs)?)),
+            _ => {}
+        }
+        let (l, r) = (self.eval(lhs)?, self.eval(rhs)?);
+        match (op, l, r) {
+            (Eq, l, r) => Ok(Value::Bool(l == r)),
+            (Ne, l, r) => Ok(Value::Bool(l != r)),
+            (Concat, Value::Text(mut l), Value::Text(r)) => {
+                self.alloc(r.len())?;
+                l.push_str(&r);
+                Ok(Value::Text(l))
+            }
+            (Concat, Value::List(mut l), Value::List(r)) => {
+                self.alloc(r.len() * SLOT_BYTES)?;
+                l.extend(r);
+                Ok(Value::List(l))
+            }
+            (Lt | Le | Gt | Ge, Value::Text(l), Value::Text(r)) => {
+                Ok(Value::Bool(compare(op, l.cmp(&r))))
+            }
+            (op, Value::Number(l), Value::Number(r)) => Ok(match op {
+                Add => Value::Number(l + r),
+                Sub => Value::Number(l - r),
+                Mul => Value::Number(l * r),
+                Div => Value::Number(l / r),
+                Rem => Value::Number(l % r),
+                Pow => Value::Number(l.powf(r)),
+                Min => Value::Number(l.min(r)),
+                Max => Value::Number(l.max(r)),
+                Lt | Le | Gt | Ge => match l.partial_cmp(&r) {
+                    Some(ordering) => Value::Bool(compare(op, ordering)),
+                    None => Value::Bool(false),
+                },
+                _ => return Err(Trap::Fault(format!("{op:?} cannot be applied to numbers"))),
+            }),
+            (op, l, r) => Err(Trap::Fault(format!(
+                "{op:?} cannot be applied to {l:?} and {r:?}"
+            ))),
+        }
+    }
+}
+
+fn compare(op: BinaryOp, ordering: std::cmp::Ordering) -> bool {
+    match op {
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Le => ordering.is_le(),
+        BinaryOp::Gt => ordering.is_gt(),
+        _ => ordering.is_ge(),
+    }
+}
+
+/// A compiled skill program bound to the limits derived from its declared resources.
+#[derive(Debug, Clone)]
+pub struct SandboxedSkill {
+    program: SkillProgram,
+    limits: SandboxLimits,
+}
+
+impl SandboxedSkill {
+    pub fn new(program: SkillProgram, limits: SandboxLimits) -> Result<Self, PandoraError> {
+        program.check()?;
+        Ok(Self { program, limits })
+    }
+
+    pub fn program(&self) -> &SkillProgram {
+        &self.program
+    }
+
+    pub fn limits(&self) -> SandboxLimits {
+        self.limits
+    }
+
+    /// Runs the program on `input` and reports the fuel and memory it consumed.
+    pub fn run(&self, input: &Json) -> Result<ExecutionReport, PandoraError> {
+        let name = &self.program.name;
+        let invalid = |message: String| PandoraError::InvalidSkillInput {
+            skill_name: name.clone(),
+            message,
+        };
+        let scope = match Value::from_json(input, &self.program.input_type()).map_err(invalid)? {
+            Value::Record(fields) => fields.into_iter().collect(),
+            other => {
+                return Err(invalid(format!(
+                    "expected a record of parameters, got {other:?}"
+                )))
+            }
+        };
+        let mut machine = Machine {
+            limits: self.limits,
+            fuel_used: 0,
+            memory_used: 0,
+            scope,
+        };
+        let input_bytes = machine
+            .scope
+            .iter()
+            .map(|(k, v)| k.len() + SLOT_BYTES + v.heap_bytes())
+            .sum();
+        let value = machine
+            .alloc(input_bytes)
+            .and_then(|_| machine.eval(&self.program.body))
+            .map_err(|trap| match trap {
+                Trap::OutOfFuel => PandoraError::InsufficientResources {
+                    message: format!(
+                        "skill '{name}' exhausted its fuel budget of {} steps",
+                        self.limits.fuel
+                    ),
+                },
+                Trap::OutOfMemory(requested) => PandoraError::InsufficientResources {
+                    message: format!(
+                        "skill '{name}' needs {requested} bytes, over its {} byte budget",
+                        self.limits.memory_bytes
+                    ),
+                },
+                Trap::Fault(message) => PandoraError::skill_exec(name.clone(), message),
+            })?;
+        let output = value
+            .to_json()
+            .map_err(|e| PandoraError::skill_exec(name.clone(), e))?;
+        Ok(ExecutionReport {
+            output,
+            fuel_used: machine.fuel_used,
+            memory_used: machine.memory_used,
+        })
+    }
+}
+
+#[async_trait]
+impl SkillModule for SandboxedSkill {
+    fn descriptor(&self) -> SkillDescriptor {
+        self.program.descriptor()
+    }
+
+    async fn execute(&self, input: Json) -> SkillOutput {
+        self.run(&input).map(|report| report.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mean_program() -> SkillProgram {
+        let sum = Expr::fold(
+            Expr::var("xs"),
+            Expr::Number(0.0),
+            "acc",
+            "x",
+            Expr::binary(BinaryOp::Add, Expr::var("acc"), Expr::var("x")),
+        );
+        let len = Expr::unary(UnaryOp::Len, Expr::var("xs"));
+        SkillProgram {
+            name: "mean".to_string(),
+            description: "Arithmetic mean of a list".to_string(),
+            params: BTreeMap::from([("xs".to_string(), Type::list(Type::Number))]),
+            output: Type::Number,
+            body: Expr::if_else(
+                Expr::binary(BinaryOp::Eq, len.clone(), Expr::Number(0.0)),
+                Expr::Number(0.0),
+                Expr::binary(BinaryOp::Div, sum, len),
+            ),
+        }
+    }
+
+    fn limits(fuel: u64, memory_bytes: usize) -> SandboxLimits {
+        SandboxLimits { fuel, memory_bytes }
+    }
+
+    #[tokio::test]
+    async fn test_compiled_program_runs_as_skill_module() {
+        let program = SkillProgram::compile(&mean_program().to_source().unwrap()).unwrap();
+        let skill = SandboxedSkill::new(program, limits(1_000, 1 << 10)).unwrap();
+        assert_eq!(skill.descriptor().name, "mean");
+        assert_eq!(
+            skill.descriptor().input_schema,
+            r#"{"record":{"xs":{"list":"number"}}}"#
+        );
+
+        let output = skill
+            .execute(json!({ "xs": [1.0, 2.0, 6.0] }))
+            .await
+            .unwrap();
+        assert_eq!(output, json!(3.0));
+        let err = skill.execute(json!({ "xs": "oops" })).await.unwrap_err();
+        assert!(matches!(err, PandoraError::InvalidSkillInput { .. }));
+    }
+
+    #[test]
+    fn test_type_checker_rejects_ill_typed_programs() {
+        let mut program = mean_program();
+        program.output = Type::Text;
+        assert!(program.check().is_err());
+
+        program = mean_program();
+        program.body = Expr::binary(BinaryOp::Add, Expr::var("xs"), Expr::Number(1.0));
+        assert!(program.check().is_err());
+
+        program.body = Expr::var("ys");
+        assert!(program.check().is_err());
+        assert!(SkillProgram::compile("{\"name\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_fuel_and_memory_limits_are_enforced() {
+        let mut program = mean_program();
+        program.params.clear();
+        program.body = Expr::let_in(
+            "xs",
+            Expr::Range {
+                start: Box::new(Expr::Number(0.0)),
+                end: Box::new(Expr::Number(1e12)),
+            },
+            mean_program().body,
+        );
+        // A trillion-element range is refused before anything is allocated
+        let skill = SandboxedSkill::new(program.clone(), limits(u64::MAX, 1 << 20)).unwrap();
+        let err = skill.run(&json!({})).unwrap_err();
+        assert!(matches!(err, PandoraError::InsufficientResources { .. }));
+
+        if let Expr::Let { value, .. } = &mut program.body {
+            **value = Expr::Range {
+                start: Box::new(Expr::Number(0.0)),
+                end: Box::new(Expr::Number(1_000.0)),
+            };
+        }
+        let starved = SandboxedSkill::new(program.clone(), limits(500, 1 << 20)).unwrap();
+        let err = starved.run(&json!({})).unwrap_err();
+        assert!(matches!(err, PandoraError::InsufficientResources { .. }));
+
+        let report = SandboxedSkill::new(program, limits(100_000, 1 << 20))
+            .unwrap()
+            .run(&json!({}))
+            .unwrap();
+        assert_eq!(report.output, json!(499.5));
+        assert!(report.fuel_used > 1_000 && report.memory_used >= 8_000);
+    }
+}
@@ -0,0 +1,377 @@
+//! Vòng đời của skill đã forge: semantic version, lineage, canary rollout giữa
+//! các version, rollback tự động khi vi phạm `PerformanceThresholds`, và
+//! retirement theo độ suy giảm mức sử dụng. Mọi chuyển trạng thái được ghi vào
+//! audit log.
+//!
+//! Một *line* là chuỗi các version cùng phục vụ một intent; `line_id` là id của
+//! skill gốc (version 1.0.0). Mỗi line có tối đa một version `Stable` và một
+//! version `Canary` nhận `canary_fraction` lưu lượng.
+
+use crate::skill_forge_lightweight::Intent;
+use chrono::{DateTime, Utc};
+use pandora_core::error::PandoraError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SkillVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl SkillVersion {
+    pub const INITIAL: SkillVersion = SkillVersion { major: 1, minor: 0, patch: 0 };
+
+    pub fn bump_major(self) -> Self {
+        Self { major: self.major + 1, minor: 0, patch: 0 }
+    }
+
+    pub fn bump_minor(self) -> Self {
+        Self { minor: self.minor + 1, patch: 0, ..self }
+    }
+
+    pub fn bump_patch(self) -> Self {
+        Self { patch: self.patch + 1, ..self }
+    }
+}
+
+impl fmt::Display for SkillVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillStatus {
+    /// Đang thử nghiệm, nhận một phần lưu lượng của line
+    Canary,
+    /// Version đang phục vụ line
+    Stable,
+    /// Đã bị thay thế bởi một canary được promote
+    Superseded,
+    /// Canary vi phạm ngưỡng hiệu năng
+    RolledBack,
+    /// Đã gỡ khỏi forge (không dùng nữa hoặc bị xóa thủ công)
+    Retired,
+}
+
+/// Skill được forge từ intent nào và từ skill cha nào
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillLineage {
+    pub line_id: String,
+    pub intent: Intent,
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillRecord {
+    pub skill_id: String,
+    pub version: SkillVersion,
+    pub lineage: SkillLineage,
+    pub status: SkillStatus,
+    pub created_at: DateTime<Utc>,
+    pub executions: u32,
+    /// Số lần thực thi lỗi hoặc vượt `PerformanceThresholds`
+    pub violations: u32,
+    /// Mức sử dụng, suy giảm theo `usage_half_life` (mỗi lần thực thi cộng 1)
+    usage_score: f64,
+    usage_updated: DateTime<Utc>,
+}
+
+impl SkillRecord {
+    /// Mức sử dụng đã suy giảm đến thời điểm `now`
+    pub fn usage_at(&self, now: DateTime<Utc>, half_life: Duration) -> f64 {
+        let elapsed = (now - self.usage_updated).num_milliseconds().max(0) as f64 / 1000.0;
+        self.usage_score * 0.5f64.powf(elapsed / half_life.as_secs_f64().max(f64::EPSILON))
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, SkillStatus::Canary | SkillStatus::Stable)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEventKind {
+    Forged { version: SkillVersion, parent: Option<String> },
+    CanaryStarted { traffic_fraction: f64 },
+    Promoted { replaced: Option<String> },
+    RolledBack { reason: String },
+    Retired { usage_score: f64 },
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    pub at: DateTime<Utc>,
+    pub skill_id: String,
+    pub line_id: String,
+    pub kind: LifecycleEventKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct SkillLifecycleConfig {
+    /// Tỉ lệ lưu lượng của line được chuyển sang canary
+    pub canary_fraction: f64,
+    /// Số lần thực thi đạt ngưỡng trước khi canary được promote
+    pub promotion_executions: u32,
+    /// Canary bị rollback khi số vi phạm đạt mức này
+    pub max_canary_violations: u32,
+    pub usage_half_life: Duration,
+    /// Skill bị retire khi mức sử dụng suy giảm xuống dưới ngưỡng này
+    pub retirement_threshold: f64,
+    pub seed: u64,
+}
+
+impl Default for SkillLifecycleConfig {
+    fn default() -> Self {
+        Self {
+            canary_fraction: 0.1,
+            promotion_executions: 20,
+            max_canary_violations: 1,
+            usage_half_life: Duration::from_secs(24 * 3600),
+            retirement_threshold: 0.05,
+            seed: 0,
+        }
+    }
+}
+
+pub struct SkillLifecycle {
+    config: SkillLifecycleConfig,
+    records: HashMap<String, SkillRecord>,
+    /// Các skill của mỗi line, theo thứ tự forge
+    lines: HashMap<String, Vec<String>>,
+    events: Vec<LifecycleEvent>,
+    rng: fastrand::Rng,
+}
+
+impl SkillLifecycle {
+    pub fn new(config: SkillLifecycleConfig) -> Self {
+        Self {
+            rng: fastrand::Rng::with_seed(config.seed),
+            config,
+            records: HashMap::new(),
+            lines: HashMap::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &SkillLifecycleConfig {
+        &self.config
+    }
+
+    /// Đăng ký skill gốc của một line mới, ở trạng thái `Stable`
+    pub(crate) fn register_root(&mut self, skill_id: &str, intent: Intent, now: DateTime<Utc>) -> SkillVersion {
+        let lineage = SkillLineage { line_id: skill_id.to_string(), intent, parent: None };
+        self.insert(skill_id, SkillVersion::INITIAL, lineage, SkillStatus::Stable, now);
+        SkillVersion::INITIAL
+    }
+
+    /// Đăng ký version mới từ `parent_id` làm canary của line
+    pub(crate) fn register_version(
+        &mut self,
+        skill_id: &str,
+        parent_id: &str,
+        version: SkillVersion,
+        now: DateTime<Utc>,
+    ) -> Result<(), PandoraError> {
+        let parent = self.records.get(parent_id)
+            .ok_or_else(|| PandoraError::State(format!("Unknown parent skill: {}", parent_id)))?;
+        let line_id = parent.lineage.line_id.clone();
+        if let Some(canary) = self.canary(&line_id) {
+            return Err(PandoraError::State(format!(
+                "Line {} already has canary {} ({})", line_id, canary.skill_id, canary.version
+            )));
+        }
+        let lineage = SkillLineage {
+            line_id: line_id.clone(),
+            intent: parent.lineage.intent.clone(),
+            parent: Some(parent_id.to_string()),
+        };
+        self.insert(skill_id, version, lineage, SkillStatus::Canary, now);
+        self.log(now, skill_id, &line_id, LifecycleEventKind::CanaryStarted {
+            traffic_fraction: self.config.canary_fraction,
+        });
+        Ok(())
+    }
+
+    /// Version kế tiếp cho line chứa `skill_id`: minor bump từ version cao nhất của line
+    pub fn next_version(&self, skill_id: &str) -> Option<SkillVersion> {
+        let line_id = &self.records.get(skill_id)?.lineage.line_id;
+        self.line_versions(line_id).iter().map(|r| r.version).max().map(SkillVersion::bump_minor)
+    }
+
+    /// Chọn skill phục vụ một lần gọi của line: canary với xác suất `canary_fraction`
+    pub fn route(&mut self, line_id: &str) -> Option<String> {
+        let stable = self.stable(line_id).map(|r| r.skill_id.clone());
+        let canary = self.canary(line_id).map(|r| r.skill_id.clone());
+        match (stable, canary) {
+            (Some(stable), Some(canary)) => {
+                if self.rng.f64() < self.config.canary_fraction { Some(canary) } else { Some(stable) }
+            }
+            (stable, canary) => stable.or(canary),
+        }
+    }
+
+    /// Ghi nhận một lần thực thi; canary bị rollback khi vi phạm ngưỡng, hoặc được
+    /// promote sau `promotion_executions` lần đạt ngưỡng
+    pub(crate) fn record_execution(&mut self, skill_id: &str, acceptable: bool, now: DateTime<Utc>) -> Option<SkillStatus> {
+        let half_life = self.config.usage_half_life;
+        let record = self.records.get_mut(skill_id)?;
+        record.usage_score = record.usage_at(now, half_life) + 1.0;
+        record.usage_updated = now;
+        record.executions += 1;
+        if !acceptable {
+            record.violations += 1;
+        }
+        if record.status != SkillStatus::Canary {
+            return None;
+        }
+
+        if record.violations >= self.config.max_canary_violations {
+            let reason = format!(
+                "{} of {} executions violated performance thresholds",
+                record.violations, record.executions
+            );
+            self.rollback(skill_id, reason, now);
+            Some(SkillStatus::RolledBack)
+        } else if record.executions >= self.config.promotion_executions {
+            self.promote(skill_id, now);
+            Some(SkillStatus::Stable)
+        } else {
+            None
+        }
+    }
+
+    /// Đưa canary thành stable; stable cũ chuyển sang `Superseded`
+    pub(crate) fn promote(&mut self, skill_id: &str, now: DateTime<Utc>) {
+        let Some(line_id) = self.records.get(skill_id).map(|r| r.lineage.line_id.clone()) else {
+            return;
+        };
+        let replaced = self.stable(&line_id).map(|r| r.skill_id.clone());
+        if let Some(old) = replaced.as_deref().and_then(|id| self.records.get_mut(id)) {
+            old.status = SkillStatus::Superseded;
+        }
+        if let Some(record) = self.records.get_mut(skill_id) {
+            record.status = SkillStatus::Stable;
+        }
+        self.log(now, skill_id, &line_id, LifecycleEventKind::Promoted { replaced });
+    }
+
+    pub(crate) fn rollback(&mut self, skill_id: &str, reason: String, now: DateTime<Utc>) {
+        let Some(record) = self.records.get_mut(skill_id) else {
+            return;
+        };
+        record.status = SkillStatus::RolledBack;
+        let line_id = record.lineage.line_id.clone();
+        self.log(now, skill_id, &line_id, LifecycleEventKind::RolledBack { reason });
+    }
+
+    /// Các skill có mức sử dụng đã suy giảm dưới `retirement_threshold`
+    pub fn idle_skills(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut idle: Vec<_> = self.records.values()
+            .filter(|r| r.status != SkillStatus::Retired)
+            .filter(|r| r.usage_at(now, self.config.usage_half_life) < self.config.retirement_threshold)
+            .map(|r| r.skill_id.clone())
+            .collect();
+        idle.sort();
+        idle
+    }
+
+    pub(crate) fn retire(&mut self, skill_id: &str, now: DateTime<Utc>) {
+        let half_life = self.config.usage_half_life;
+        let Some(record) = self.records.get_mut(skill_id) else {
+            return;
+        };
+        record.status = SkillStatus::Retired;
+        let usage_score = record.usage_at(now, half_life);
+        let line_id = record.lineage.line_id.clone();
+        self.log(now, skill_id, &line_id, LifecycleEventKind::Retired { usage_score });
+    }
+
+    pub(crate) fn remove(&mut self, skill_id: &str, now: DateTime<Utc>) {
+        let Some(record) = self.records.get_mut(skill_id) else {
+            return;
+        };
+        record.status = SkillStatus::Retired;
+        let line_id = record.lineage.line_id.clone();
+        self.log(now, skill_id, &line_id, LifecycleEventKind::Removed);
+    }
+
+    pub fn record(&self, skill_id: &str) -> Option<&SkillRecord> {
+        self.records.get(skill_id)
+    }
+
+    /// Mọi version của line, theo thứ tự forge (kể cả đã retire)
+    pub fn line_versions(&self, line_id: &str) -> Vec<&SkillRecord> {
+        self.lines.get(line_id)
+            .map(|ids| ids.iter().filter_map(|id| self.records.get(id)).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn stable(&self, line_id: &str) -> Option<&SkillRecord> {
+        self.line_versions(line_id).into_iter().find(|r| r.status == SkillStatus::Stable)
+    }
+
+    pub fn canary(&self, line_id: &str) -> Option<&SkillRecord> {
+        self.line_versions(line_id).into_iter().find(|r| r.status == SkillStatus::Canary)
+    }
+
+    /// Chuỗi tổ tiên của skill, từ chính nó đến skill gốc
+    pub fn ancestry(&self, skill_id: &str) -> Vec<&SkillRecord> {
+        let mut chain = Vec::new();
+        let mut next = self.records.get(skill_id);
+        while let Some(record) = next {
+            chain.push(record);
+            next = record.lineage.parent.as_deref().and_then(|id| self.records.get(id));
+        }
+        chain
+    }
+
+    /// Audit log đầy đủ, theo thứ tự thời gian ghi
+    pub fn events(&self) -> &[LifecycleEvent] {
+        &self.events
+    }
+
+    pub fn events_for(&self, skill_id: &str) -> Vec<&LifecycleEvent> {
+        self.events.iter().filter(|e| e.skill_id == skill_id).collect()
+    }
+
+    fn insert(&mut self, skill_id: &str, version: SkillVersion, lineage: SkillLineage, status: SkillStatus, now: DateTime<Utc>) {
+        let line_id = lineage.line_id.clone();
+        let parent = lineage.parent.clone();
+        self.records.insert(skill_id.to_string(), SkillRecord {
+            skill_id: skill_id.to_string(),
+            version,
+            lineage,
+            status,
+            created_at: now,
+            executions: 0,
+            violations: 0,
+            // Skill mới có một khoảng ân hạn tương đương một lần sử dụng
+            usage_score: 1.0,
+            usage_updated: now,
+        });
+        self.lines.entry(line_id.clone()).or_default().push(skill_id.to_string());
+        self.log(now, skill_id, &line_id, LifecycleEventKind::Forged { version, parent });
+    }
+
+    fn log(&mut self, at: DateTime<Utc>, skill_id: &str, line_id: &str, kind: LifecycleEventKind) {
+        self.events.push(LifecycleEvent {
+            at,
+            skill_id: skill_id.to_string(),
+            line_id: line_id.to_string(),
+            kind,
+        });
+    }
+}
+
+impl Default for SkillLifecycle {
+    fn default() -> Self {
+        Self::new(SkillLifecycleConfig::default())
+    }
+}
@@ -1,4 +1,7 @@
+use crate::dqn::{DqnConfig, FlowFeatureExtractor, QNetwork, Transition};
+use crate::experience_buffer::PriorityExperienceBuffer;
 use pandora_core::ontology::EpistemologicalFlow;
+use pandora_error::PandoraError;
 use std::collections::HashMap;
 
 pub trait ValueEstimator {
@@ -72,105 +75,171 @@ impl ValueEstimator for ExponentialMovingAverageEstimator {
     }
 }
 
-/// Neural network-based Q-value estimator for deep reinforcement learning
+/// Neural network-based Q-value estimator for deep reinforcement learning.
+///
+/// Q(s, ·) là đầu ra của một `QNetwork` trên vector đặc trưng của flow. Huấn luyện
+/// theo kiểu DQN: mục tiêu `r + γ·Q_target(s', a*)` với mạng target được đồng bộ định
+/// kỳ, `a*` chọn bởi mạng online khi bật Double-DQN, Huber loss và trọng số
+/// importance-sampling khi học từ `PriorityExperienceBuffer`.
 #[derive(Debug, Clone)]
 pub struct NeuralQValueEstimator {
-    /// Q-values for state-action pairs
-    q_values: HashMap<String, f64>,
-    /// Visit counts for state-action pairs (for UCB1)
-    visit_counts: HashMap<String, u32>,
-    /// Learning rate for Q-value updates
-    learning_rate: f64,
-    /// Discount factor for future rewards
-    discount_factor: f64,
-    /// Available actions
-    actions: Vec<&'static str>,
+    config: DqnConfig,
+    extractor: FlowFeatureExtractor,
+    online: QNetwork,
+    target: QNetwork,
+    train_steps: u64,
+    /// Visit counts for state-action pairs (for UCB1), keyed by feature hash
+    visit_counts: HashMap<u64, u32>,
 }
 
 impl NeuralQValueEstimator {
-    /// Creates a new neural Q-value estimator
+    /// Creates a new neural Q-value estimator over the default GridWorld actions
     pub fn new(learning_rate: f64, discount_factor: f64) -> Self {
+        Self::with_config(DqnConfig::grid_world(learning_rate, discount_factor))
+    }
+
+    /// Creates an estimator with a custom action space and hyper-parameters
+    pub fn with_config(config: DqnConfig) -> Self {
+        let extractor = FlowFeatureExtractor::new(config.sdr_buckets, config.actions.clone());
+        let online = QNetwork::new(
+            extractor.dims(),
+            config.hidden_dims,
+            config.actions.len(),
+            config.seed,
+        );
         Self {
-            q_values: HashMap::new(),
+            target: online.clone(),
+            online,
+            extractor,
+            config,
+            train_steps: 0,
             visit_counts: HashMap::new(),
-            learning_rate,
-            discount_factor,
-            actions: vec![
-                "unlock_door",
-                "pick_up_key",
-                "move_forward",
-                "explore",
-                "noop",
-            ],
         }
     }
 
-    /// Creates a state-action key for hashing
-    fn state_action_key(flow: &EpistemologicalFlow, action: &str) -> String {
-        use std::hash::{Hash, Hasher};
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        format!("{:?}_{}", flow, action).hash(&mut hasher);
-        hasher.finish().to_string()
+    pub fn actions(&self) -> &[&'static str] {
+        &self.config.actions
     }
 
-    /// Simple neural network forward pass (placeholder for actual NN)
-    fn forward_pass(&self, flow: &EpistemologicalFlow, action: &str) -> f64 {
-        // This is a simplified implementation
-        // In a real system, this would use a trained neural network
+    pub fn train_steps(&self) -> u64 {
+        self.train_steps
+    }
 
-        // Extract features from the flow
-        let mut features = Vec::new();
+    fn action_index(&self, action: &str) -> Option<usize> {
+        self.config.actions.iter().position(|a| *a == action)
+    }
 
-        // Add intent-based features
-        if let Some(ref sankhara) = flow.sankhara {
-            let intent_str = sankhara.as_ref();
-            features.push(if intent_str == action { 1.0 } else { 0.0 });
-        } else {
-            features.push(0.0);
+    /// Creates a state-action key for visit counting
+    fn state_action_key(features: &[f64], action: usize) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for f in features {
+            f.to_bits().hash(&mut hasher);
         }
+        action.hash(&mut hasher);
+        hasher.finish()
+    }
 
-        // Add sanna-based features
-        if let Some(ref sanna) = flow.sanna {
-            features.push(sanna.active_indices.len() as f64 / 32.0);
+    /// Q-values of the online network for every action
+    pub fn q_values(&self, flow: &EpistemologicalFlow) -> Vec<f64> {
+        self.online.forward(&self.extractor.extract(flow))
+    }
+
+    /// Bootstrapped value of `next_flow` (Double-DQN when enabled)
+    fn bootstrap_value(&self, next_features: &[f64]) -> f64 {
+        let target_q = self.target.forward(next_features);
+        let greedy = if self.config.double_dqn {
+            self.online.forward(next_features)
         } else {
-            features.push(0.0);
+            target_q.clone()
+        };
+        let best = greedy
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i);
+        best.map_or(0.0, |i| target_q[i])
+    }
+
+    /// Trains on a batch of transitions weighted by importance-sampling weights.
+    /// Returns the TD-errors (computed before the update) for priority updates.
+    pub fn train_batch(
+        &mut self,
+        transitions: &[Transition],
+        weights: &[f64],
+    ) -> Result<Vec<f64>, PandoraError> {
+        if transitions.len() != weights.len() {
+            return Err(PandoraError::config(format!(
+                "train_batch: {} transitions but {} weights",
+                transitions.len(),
+                weights.len()
+            )));
+        }
+        let mut prepared = Vec::with_capacity(transitions.len());
+        for t in transitions {
+            let action = self
+                .action_index(&t.action)
+                .ok_or_else(|| PandoraError::config(format!("Unknown action '{}'", t.action)))?;
+            let features = self.extractor.extract(&t.flow);
+            let bootstrap = if t.done {
+                0.0
+            } else {
+                self.bootstrap_value(&self.extractor.extract(&t.next_flow))
+            };
+            let target = t.reward + self.config.discount_factor * bootstrap;
+            let td = target - self.online.forward(&features)[action];
+            prepared.push((features, action, td));
         }
 
-        // Add related_eidos features
-        if let Some(ref related) = flow.related_eidos {
-            features.push(related.len() as f64 / 4.0);
-        } else {
-            features.push(0.0);
+        let lr = self.config.learning_rate / transitions.len().max(1) as f64;
+        let delta = self.config.huber_delta;
+        for ((features, action, td), &w) in prepared.iter().zip(weights) {
+            // dL/dQ của Huber loss: -(td) trong vùng bậc hai, -δ·sign(td) ngoài vùng đó
+            let grad = -td.clamp(-delta, delta) * w;
+            self.online.backward(features, *action, grad, lr);
+            let key = Self::state_action_key(features, *action);
+            *self.visit_counts.entry(key).or_insert(0) += 1;
         }
 
-        // Simple weighted sum (placeholder for neural network)
-        let weights = vec![0.4, 0.3, 0.3];
-        let mut q_value = 0.0;
-        for (feature, weight) in features.iter().zip(weights.iter()) {
-            q_value += feature * weight;
+        self.train_steps += 1;
+        if self.config.target_update_interval > 0
+            && self
+                .train_steps
+                .is_multiple_of(self.config.target_update_interval)
+        {
+            self.sync_target();
         }
+        Ok(prepared.into_iter().map(|(_, _, td)| td).collect())
+    }
 
-        // Add some noise to simulate neural network behavior
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        q_value += rng.gen_range(-0.1..0.1);
+    /// Samples a prioritized batch, trains on it and writes back |TD-error| priorities.
+    /// Returns the mean TD-error magnitude, or `None` if the buffer is empty.
+    pub fn train_from_buffer<R: rand::Rng + ?Sized>(
+        &mut self,
+        buffer: &mut PriorityExperienceBuffer<Transition>,
+        batch_size: usize,
+        rng: &mut R,
+    ) -> Result<Option<f64>, PandoraError> {
+        let Some(batch) = buffer.sample_batch(batch_size, rng) else {
+            return Ok(None);
+        };
+        let td_errors = self.train_batch(&batch.samples, &batch.weights)?;
+        buffer.update_priorities(&batch.indices, &td_errors)?;
+        let mean = td_errors.iter().map(|t| t.abs()).sum::<f64>() / td_errors.len().max(1) as f64;
+        Ok(Some(mean))
+    }
 
-        q_value
+    /// Copies the online network into the target network
+    pub fn sync_target(&mut self) {
+        self.target = self.online.clone();
     }
 }
 
 impl ValueEstimator for NeuralQValueEstimator {
     fn estimate(&self, flow: &EpistemologicalFlow) -> f64 {
         // Return the maximum Q-value across all actions
-        self.actions
-            .iter()
-            .map(|action| {
-                let key = Self::state_action_key(flow, action);
-                self.q_values.get(&key).copied().unwrap_or_else(|| {
-                    // If no stored Q-value, compute it using forward pass
-                    self.forward_pass(flow, action)
-                })
-            })
+        self.q_values(flow)
+            .into_iter()
             .fold(f64::NEG_INFINITY, f64::max)
     }
 }
@@ -180,18 +249,13 @@ impl QValueEstimator for NeuralQValueEstimator {
         &self,
         flow: &EpistemologicalFlow,
     ) -> Result<Vec<(&'static str, f64)>, Box<dyn std::error::Error>> {
-        let mut q_values = Vec::new();
-
-        for action in &self.actions {
-            let key = Self::state_action_key(flow, action);
-            let q_value = self.q_values.get(&key).copied().unwrap_or_else(|| {
-                // If no stored Q-value, compute it using forward pass
-                self.forward_pass(flow, action)
-            });
-            q_values.push((*action, q_value));
-        }
-
-        Ok(q_values)
+        Ok(self
+            .config
+            .actions
+            .iter()
+            .copied()
+            .zip(self.q_values(flow))
+            .collect())
     }
 
     fn update_q_value(
@@ -201,31 +265,24 @@ impl QValueEstimator for NeuralQValueEstimator {
         reward: f64,
         next_flow: &EpistemologicalFlow,
     ) {
-        let key = Self::state_action_key(flow, action);
-
-        // Get current Q-value
-        let current_q = self
-            .q_values
-            .get(&key)
-            .copied()
-            .unwrap_or_else(|| self.forward_pass(flow, action));
-
-        // Get maximum Q-value for next state
-        let max_next_q = self.estimate(next_flow);
-
-        // Q-learning update: Q(s,a) = Q(s,a) + α[r + γ*max_a'Q(s',a') - Q(s,a)]
-        let target = reward + self.discount_factor * max_next_q;
-        let new_q = current_q + self.learning_rate * (target - current_q);
-
-        self.q_values.insert(key.clone(), new_q);
-
-        // Update visit count
-        let count = self.visit_counts.get(&key).copied().unwrap_or(0);
-        self.visit_counts.insert(key, count + 1);
+        // Online Q-learning update: a single-transition DQN step
+        let transition = Transition {
+            flow: flow.clone(),
+            action: action.to_string(),
+            reward,
+            next_flow: next_flow.clone(),
+            done: false,
+        };
+        if let Err(e) = self.train_batch(std::slice::from_ref(&transition), &[1.0]) {
+            tracing::warn!("NeuralQValueEstimator: bỏ qua cập nhật: {}", e);
+        }
     }
 
     fn get_visit_count(&self, flow: &EpistemologicalFlow, action: &str) -> u32 {
-        let key = Self::state_action_key(flow, action);
+        let Some(action) = self.action_index(action) else {
+            return 0;
+        };
+        let key = Self::state_action_key(&self.extractor.extract(flow), action);
         self.visit_counts.get(&key).copied().unwrap_or(0)
     }
 }
@@ -253,4 +310,58 @@ mod tests {
         let v2 = est.estimate(&flow);
         assert!(v2 >= 0.0);
     }
+
+    fn intent_flow(intent: &'static str) -> EpistemologicalFlow {
+        let mut flow = EpistemologicalFlow::default();
+        flow.set_static_intent(intent);
+        flow
+    }
+
+    #[test]
+    fn dqn_learns_rewarding_action_from_prioritized_replay() {
+        use rand::SeedableRng;
+        let config = DqnConfig {
+            target_update_interval: 10,
+            ..DqnConfig::grid_world(0.05, 0.5).with_actions(vec!["left", "right"])
+        };
+        let mut est = NeuralQValueEstimator::with_config(config);
+        let mut buffer = PriorityExperienceBuffer::with_capacity(64);
+        let state = intent_flow("left");
+        for (action, reward) in [("left", 1.0), ("right", -1.0)] {
+            for _ in 0..8 {
+                buffer.push_with_max_priority(Transition {
+                    flow: state.clone(),
+                    action: action.to_string(),
+                    reward,
+                    next_flow: EpistemologicalFlow::default(),
+                    done: true,
+                });
+            }
+        }
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(4);
+        for _ in 0..300 {
+            est.train_from_buffer(&mut buffer, 8, &mut rng).unwrap();
+        }
+        let q = est.get_q_values(&state).unwrap();
+        assert_eq!(q[0].0, "left");
+        assert!((q[0].1 - 1.0).abs() < 0.1, "{q:?}");
+        assert!((q[1].1 + 1.0).abs() < 0.1, "{q:?}");
+        assert!(est.get_visit_count(&state, "left") > 0);
+        assert_eq!(est.train_steps(), 300);
+    }
+
+    #[test]
+    fn dqn_rejects_unknown_actions() {
+        let mut est = NeuralQValueEstimator::new(0.1, 0.9);
+        let t = Transition {
+            flow: EpistemologicalFlow::default(),
+            action: "fly".into(),
+            reward: 0.0,
+            next_flow: EpistemologicalFlow::default(),
+            done: true,
+        };
+        assert!(est.train_batch(&[t], &[1.0]).is_err());
+        assert!(est.train_batch(&[], &[1.0]).is_err());
+    }
 }
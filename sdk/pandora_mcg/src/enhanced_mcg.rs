@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use tracing::{info, warn, debug};
 use crate::causal_discovery::{discover_causal_links, CausalHypothesis, CausalDiscoveryConfig};
+use pandora_core::ontology::PredictiveUncertainty;
 use pandora_core::world_model::DualIntrinsicReward;
 
 /// System metrics collected by the Meta-Cognitive Governor for monitoring.
@@ -268,6 +269,7 @@ pub struct EnhancedMetaCognitiveGovernor {
     observation_buffer: ObservationBuffer,
     discovery_config: CausalDiscoveryConfig,
     pending_hypothesis: Option<CausalHypothesis>,
+    latest_uncertainty: Option<PredictiveUncertainty>,
 }
 
 impl EnhancedMetaCognitiveGovernor {
@@ -286,6 +288,7 @@ impl EnhancedMetaCognitiveGovernor {
             observation_buffer: ObservationBuffer::new(1000, 50),
             discovery_config: CausalDiscoveryConfig::default(),
             pending_hypothesis: None,
+            latest_uncertainty: None,
         }
     }
 
@@ -304,9 +307,22 @@ impl EnhancedMetaCognitiveGovernor {
             observation_buffer: ObservationBuffer::new(1000, 50),
             discovery_config: config,
             pending_hypothesis: None,
+            latest_uncertainty: None,
         }
     }
 
+    /// Records the latest predictive uncertainty reported by the world model
+    /// (see `EpistemologicalFlow::uncertainty`); it feeds `SystemMetrics.uncertainty`
+    /// in `monitor_and_decide` and `monitor_and_decide_with_discovery`.
+    pub fn observe_uncertainty(&mut self, uncertainty: PredictiveUncertainty) {
+        self.latest_uncertainty = Some(uncertainty);
+    }
+
+    /// Normalised uncertainty in [0, 1), or the neutral 0.5 before any observation.
+    pub fn current_uncertainty(&self) -> f32 {
+        self.latest_uncertainty.map_or(0.5, |u| u.score())
+    }
+
     pub fn monitor_comprehensive(&mut self, metrics: &SystemMetrics) -> DecisionWithConfidence {
         info!("\n=== Meta-Cognitive Governor - Comprehensive Monitoring ===");
         let uncertainty_anomaly = self.uncertainty_detector.score(metrics.uncertainty);
@@ -428,7 +444,7 @@ impl EnhancedMetaCognitiveGovernor {
         
         // 3. Fallback to standard rule-based triggers
         self.monitor_comprehensive(&SystemMetrics {
-            uncertainty: 0.5,
+            uncertainty: self.current_uncertainty(),
             compression_reward: reward.compression_reward,
             novelty_score: 0.5,
             performance: 0.8,
@@ -502,7 +518,7 @@ impl EnhancedMetaCognitiveGovernor {
         
         // Fall back to regular monitoring
         let metrics = SystemMetrics {
-            uncertainty: 0.5, // Placeholder - would be calculated from actual data
+            uncertainty: self.current_uncertainty(),
             compression_reward: reward.compression_reward,
             novelty_score: 0.3, // Placeholder
             performance: 0.8, // Placeholder
@@ -600,6 +616,14 @@ mod tests {
         for _ in 0..3 { tracker.update(false); }
         assert!(tracker.success_rate() > 0.6);
     }
+
+    #[test]
+    fn test_observed_uncertainty_feeds_metrics() {
+        let mut mcg = EnhancedMetaCognitiveGovernor::new();
+        assert_eq!(mcg.current_uncertainty(), 0.5);
+        mcg.observe_uncertainty(PredictiveUncertainty { epistemic: 3.0, aleatoric: 1.0 });
+        assert!((mcg.current_uncertainty() - 0.8).abs() < 1e-6);
+    }
 }
 
 
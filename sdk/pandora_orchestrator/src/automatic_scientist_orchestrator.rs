@@ -166,6 +166,11 @@ impl AutomaticScientistOrchestrator {
         let action_trigger = {
             let mut mcg = self.mcg.lock().map_err(|_| PandoraError::config("Failed to acquire MCG lock"))?;
             let cwm = self.cwm.lock().map_err(|_| PandoraError::config("Failed to acquire CWM lock"))?;
+
+            // Feed the uncertainty of the CWM's last prediction on this flow to the MCG
+            if let Some(uncertainty) = current_flow.uncertainty {
+                mcg.observe_uncertainty(uncertainty);
+            }
             
             // Calculate current reward
             let reward = self.learning_engine.calculate_reward(&*cwm, &*cwm, current_flow);
@@ -57,6 +57,7 @@ pub struct GridWorld {
     pub goal_pos: (i32, i32),
     pub observability: ObservabilityMode,
     pub viewshed: Viewshed,
+    has_key: bool,
 }
 
 impl GridWorld {
@@ -76,6 +77,7 @@ impl GridWorld {
                 },
                 dirty: true,
             },
+            has_key: false,
         }
     }
 
@@ -91,6 +93,38 @@ impl GridWorld {
         }
     }
 
+    pub fn place_key(&mut self, pos: (usize, usize)) {
+        if pos.0 < self.width && pos.1 < self.height {
+            let idx = self.xy_idx(pos.0, pos.1);
+            self.grid[idx] = Cell::Key;
+        }
+    }
+
+    /// Đặt một cánh cửa (`open = false` thì chặn đường cho tới khi được mở bằng chìa khóa).
+    pub fn place_door(&mut self, pos: (usize, usize), open: bool) {
+        if pos.0 < self.width && pos.1 < self.height {
+            let idx = self.xy_idx(pos.0, pos.1);
+            self.grid[idx] = Cell::Door(open);
+        }
+    }
+
+    /// Tác tử đang giữ chìa khóa (nhặt bằng `Action::Pickup`).
+    pub fn has_key(&self) -> bool {
+        self.has_key
+    }
+
+    /// Đặt trạng thái giữ chìa khóa, ví dụ để dựng một tình huống bắt đầu giữa chừng.
+    pub fn set_has_key(&mut self, has_key: bool) {
+        self.has_key = has_key;
+    }
+
+    pub fn cell_at(&self, pos: (i32, i32)) -> Option<Cell> {
+        if pos.0 < 0 || pos.1 < 0 || pos.0 >= self.width as i32 || pos.1 >= self.height as i32 {
+            return None;
+        }
+        Some(self.grid[self.xy_idx(pos.0 as usize, pos.1 as usize)])
+    }
+
     pub fn update_visibility(&mut self) {
         if !self.viewshed.dirty {
             return;
@@ -170,9 +204,9 @@ impl GridWorld {
                     return Ok(ActionResult::Collision);
                 }
 
-                // Wall
+                // Wall or closed door
                 let idx = self.xy_idx(new_x as usize, new_y as usize);
-                if self.grid[idx] == Cell::Wall {
+                if matches!(self.grid[idx], Cell::Wall | Cell::Door(false)) {
                     return Ok(ActionResult::Collision);
                 }
 
@@ -188,7 +222,43 @@ impl GridWorld {
                 Ok(ActionResult::Success)
             }
             Action::Wait => Ok(ActionResult::Success),
-            Action::Pickup | Action::Use => Ok(ActionResult::Success), // đơn giản hóa
+            Action::Pickup => {
+                let idx = self.xy_idx(self.agent_pos.0 as usize, self.agent_pos.1 as usize);
+                if self.grid[idx] != Cell::Key {
+                    return Ok(ActionResult::NoEffect);
+                }
+                self.grid[idx] = Cell::Empty;
+                self.has_key = true;
+                self.viewshed.dirty = true;
+                Ok(ActionResult::Success)
+            }
+            Action::Use => {
+                // Mở mọi cửa đang đóng kề bên nếu có chìa khóa
+                if !self.has_key {
+                    return Ok(ActionResult::NoEffect);
+                }
+                let mut opened = false;
+                for dir in [
+                    Direction::North,
+                    Direction::South,
+                    Direction::East,
+                    Direction::West,
+                ] {
+                    let (dx, dy) = dir.to_delta();
+                    let pos = (self.agent_pos.0 + dx, self.agent_pos.1 + dy);
+                    if self.cell_at(pos) == Some(Cell::Door(false)) {
+                        let idx = self.xy_idx(pos.0 as usize, pos.1 as usize);
+                        self.grid[idx] = Cell::Door(true);
+                        opened = true;
+                    }
+                }
+                if opened {
+                    self.viewshed.dirty = true;
+                    Ok(ActionResult::Success)
+                } else {
+                    Ok(ActionResult::NoEffect)
+                }
+            }
         }
     }
 
@@ -222,6 +292,8 @@ impl GridWorld {
 pub enum ActionResult {
     Success,
     Collision,
+    /// `Pickup`/`Use` không có gì để nhặt hoặc mở; thế giới không đổi
+    NoEffect,
     ReachedGoal,
 }
 
@@ -241,4 +313,46 @@ mod tests {
         let _ = world.get_world_state();
         assert!(!world.viewshed.dirty);
     }
+
+    #[test]
+    fn key_opens_door() {
+        let mut world = GridWorld::new(6, 3, ObservabilityMode::Full);
+        world.place_key((2, 1));
+        world.place_door((3, 1), false);
+
+        assert_eq!(
+            world.submit_action(Action::Use).unwrap(),
+            ActionResult::NoEffect
+        );
+        assert_eq!(
+            world.submit_action(Action::Pickup).unwrap(),
+            ActionResult::NoEffect
+        );
+        world.submit_action(Action::Move(Direction::East)).unwrap();
+        assert_eq!(
+            world.submit_action(Action::Pickup).unwrap(),
+            ActionResult::Success
+        );
+        assert!(world.has_key());
+        assert_eq!(world.cell_at((2, 1)), Some(Cell::Empty));
+
+        assert_eq!(
+            world.submit_action(Action::Move(Direction::East)).unwrap(),
+            ActionResult::Collision
+        );
+        assert_eq!(
+            world.submit_action(Action::Use).unwrap(),
+            ActionResult::Success
+        );
+        assert_eq!(world.cell_at((3, 1)), Some(Cell::Door(true)));
+        // Không còn cửa đóng nào để mở
+        assert_eq!(
+            world.submit_action(Action::Use).unwrap(),
+            ActionResult::NoEffect
+        );
+        assert_eq!(
+            world.submit_action(Action::Move(Direction::East)).unwrap(),
+            ActionResult::Success
+        );
+    }
 }
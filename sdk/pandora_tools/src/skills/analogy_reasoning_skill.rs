@@ -98,7 +98,10 @@ impl AnalogyEngine {
         }
     }
 
-    /// Hàm helper để lấy thông tin concept (document) từ retrieval engine
+    /// Hàm helper để lấy thông tin concept (document) từ retrieval engine.
+    ///
+    /// Concept được thêm không kèm embedding chỉ dùng được khi engine có embedder
+    /// (khi đó embedding được sinh lúc `add_document`).
     async fn get_concept(&self, query: &str) -> Result<Document, AnalogyError> {
         let docs = self
             .retrieval_engine
@@ -106,9 +109,14 @@ impl AnalogyEngine {
             .await
             .search_by_text(query, 1)
             .await?;
-        docs.into_iter()
+        let concept = docs
+            .into_iter()
             .next()
-            .ok_or_else(|| AnalogyError::ConceptNotFound(query.to_string()))
+            .ok_or_else(|| AnalogyError::ConceptNotFound(query.to_string()))?;
+        if concept.embedding.is_empty() {
+            return Err(AnalogyError::EmbeddingNotFound(concept.id));
+        }
+        Ok(concept)
     }
 
     /// Tính toán điểm tin cậy đa yếu tố cho một ứng viên D
@@ -0,0 +1,381 @@
+//! Chế độ thập phân độ chính xác tùy ý (`BigDecimal`) cho các phép tính tài chính.
+//!
+//! Số được đọc trực tiếp từ chuỗi nên `0.1 + 0.2` cho đúng `0.3`. Mỗi kết quả trung gian
+//! được làm tròn về `precision` chữ số có nghĩa theo `rounding`; nếu `scale` được đặt,
+//! kết quả cuối được làm tròn về đúng số chữ số sau dấu phẩy (ví dụ 2 cho tiền tệ).
+
+use super::{sandbox, ArithmeticError};
+use bigdecimal::{Context, One, Signed, ToPrimitive, Zero};
+use std::num::NonZeroU64;
+use std::str::FromStr;
+
+pub use bigdecimal::{BigDecimal, RoundingMode};
+
+const MAX_PRECISION: u64 = 1000;
+const MAX_EXPONENT: i64 = 10_000;
+/// Chữ số dự phòng khi tính nghịch đảo trước khi làm tròn về `precision`
+const GUARD_DIGITS: u64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecimalConfig {
+    /// Số chữ số có nghĩa của mọi kết quả trung gian
+    pub precision: u64,
+    /// Số chữ số sau dấu phẩy của kết quả cuối; `None` giữ nguyên
+    pub scale: Option<i64>,
+    pub rounding: RoundingMode,
+}
+
+impl Default for DecimalConfig {
+    /// 34 chữ số như decimal128, làm tròn ngân hàng (half-even)
+    fn default() -> Self {
+        Self {
+            precision: 34,
+            scale: None,
+            rounding: RoundingMode::HalfEven,
+        }
+    }
+}
+
+impl DecimalConfig {
+    fn context(&self, extra_digits: u64) -> Result<Context, ArithmeticError> {
+        if self.precision > MAX_PRECISION {
+            return Err(ArithmeticError::ParseError(format!(
+                "Độ chính xác {} vượt quá {}",
+                self.precision, MAX_PRECISION
+            )));
+        }
+        let precision = NonZeroU64::new(self.precision + extra_digits)
+            .ok_or_else(|| ArithmeticError::ParseError("Độ chính xác phải lớn hơn 0".into()))?;
+        Ok(Context::new(precision, self.rounding))
+    }
+}
+
+/// Tính biểu thức `+ - * / % ^` (số mũ nguyên), `sqrt`, `abs` ở chế độ thập phân.
+pub fn evaluate(expr: &str, config: &DecimalConfig) -> Result<BigDecimal, ArithmeticError> {
+    let chars: Vec<char> = expr.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut evaluator = Evaluator {
+        _memory: sandbox::allocate(chars.len() * size_of::<char>())?,
+        chars,
+        pos: 0,
+        context: config.context(0)?,
+        guard_context: config.context(GUARD_DIGITS)?,
+    };
+    let value = evaluator.expression()?.number;
+    if let Some(c) = evaluator.peek() {
+        return Err(parse_error(format!("Ký tự thừa '{}'", c)));
+    }
+    Ok(match config.scale {
+        Some(scale) => value.with_scale_round(scale, config.rounding),
+        None => value.normalized(),
+    })
+}
+
+fn parse_error(message: impl Into<String>) -> ArithmeticError {
+    ArithmeticError::ParseError(message.into())
+}
+
+fn division_by_zero() -> ArithmeticError {
+    parse_error("Kết quả không hữu hạn (chia cho 0)")
+}
+
+/// Kết quả trung gian cùng khoản bộ nhớ sandbox tính cho nó; khoản được giữ tới khi giá
+/// trị bị drop nên tổng các giá trị đang sống (toán hạng chờ trên stack đệ quy) bị giới hạn.
+struct Value {
+    number: BigDecimal,
+    _memory: sandbox::Allocation,
+}
+
+struct Evaluator {
+    _memory: sandbox::Allocation,
+    chars: Vec<char>,
+    pos: usize,
+    context: Context,
+    guard_context: Context,
+}
+
+impl Evaluator {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Làm tròn kết quả trung gian và tính nó vào nhiên liệu/bộ nhớ của sandbox.
+    fn round(&self, value: BigDecimal) -> Result<Value, ArithmeticError> {
+        let number = self.context.round_decimal(value);
+        let digits = number.digits();
+        sandbox::consume(digits)?;
+        // ~log2(10) bit mỗi chữ số
+        let bytes = size_of::<BigDecimal>() + (digits as usize * 10).div_ceil(24);
+        Ok(Value {
+            number,
+            _memory: sandbox::allocate(bytes)?,
+        })
+    }
+
+    fn expression(&mut self) -> Result<Value, ArithmeticError> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                let rhs = self.term()?;
+                value = self.round(value.number + rhs.number)?;
+            } else if self.eat('-') {
+                let rhs = self.term()?;
+                value = self.round(value.number - rhs.number)?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Value, ArithmeticError> {
+        let mut value = self.unary()?;
+        loop {
+            if self.eat('*') {
+                let rhs = self.unary()?;
+                value = self.round(value.number * rhs.number)?;
+            } else if self.eat('/') {
+                let divisor = self.unary()?;
+                value = self.divide(value.number, &divisor.number)?;
+            } else if self.eat('%') {
+                let divisor = self.unary()?;
+                if divisor.number.is_zero() {
+                    return Err(division_by_zero());
+                }
+                value = self.round(value.number % divisor.number)?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn divide(&self, value: BigDecimal, divisor: &BigDecimal) -> Result<Value, ArithmeticError> {
+        if divisor.is_zero() {
+            return Err(division_by_zero());
+        }
+        let inverse = divisor.inverse_with_context(&self.guard_context);
+        self.round(value * inverse)
+    }
+
+    fn unary(&mut self) -> Result<Value, ArithmeticError> {
+        if self.eat('-') {
+            let mut value = self.unary()?;
+            value.number = -value.number;
+            return Ok(value);
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Value, ArithmeticError> {
+        let base = self.primary()?;
+        if !self.eat('^') {
+            return Ok(base);
+        }
+        let exponent = self.unary()?;
+        let exponent = exponent
+            .number
+            .is_integer()
+            .then(|| exponent.number.to_i64())
+            .flatten()
+            .filter(|e| e.abs() <= MAX_EXPONENT)
+            .ok_or_else(|| {
+                parse_error(format!(
+                    "Số mũ phải là số nguyên trong [-{0}, {0}]",
+                    MAX_EXPONENT
+                ))
+            })?;
+        if exponent == 0 {
+            return self.round(BigDecimal::one());
+        }
+        if base.number.is_zero() && exponent < 0 {
+            return Err(division_by_zero());
+        }
+        // Bình phương lặp: mỗi bit của số mũ là một phép nhân ở độ chính xác đầy đủ
+        sandbox::consume(
+            u64::from(64 - exponent.unsigned_abs().leading_zeros())
+                * self.context.precision().get(),
+        )?;
+        let magnitude = base
+            .number
+            .powi_with_context(exponent.abs(), &self.guard_context);
+        if exponent < 0 {
+            self.divide(BigDecimal::one(), &magnitude)
+        } else {
+            self.round(magnitude)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Value, ArithmeticError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expression()?;
+                if !self.eat(')') {
+                    return Err(parse_error("Thiếu ')'"));
+                }
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_alphabetic() => {
+                let start = self.pos;
+                while self.peek().is_some_and(char::is_alphabetic) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                if !self.eat('(') {
+                    return Err(parse_error(format!("Thiếu '(' sau {}", name)));
+                }
+                let arg = self.expression()?;
+                if !self.eat(')') {
+                    return Err(parse_error("Thiếu ')'"));
+                }
+                match name.as_str() {
+                    "abs" => Ok(Value {
+                        number: arg.number.abs(),
+                        ..arg
+                    }),
+                    "sqrt" => {
+                        let root = arg
+                            .number
+                            .sqrt_with_context(&self.context)
+                            .ok_or_else(|| parse_error("Căn bậc hai của số âm"))?;
+                        self.round(root)
+                    }
+                    _ => Err(parse_error(format!(
+                        "Hàm không hỗ trợ ở chế độ thập phân: {}",
+                        name
+                    ))),
+                }
+            }
+            other => Err(parse_error(format!("Mong đợi số, gặp {:?}", other))),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ArithmeticError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.pos += 1;
+            }
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let value = BigDecimal::from_str(&text)
+            .map_err(|_| parse_error(format!("Số không hợp lệ '{}'", text)))?;
+        self.round(value)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::skills::arithmetic_skill::SandboxConfig;
+    use std::time::Duration;
+
+    fn eval(expr: &str, config: DecimalConfig) -> String {
+        evaluate(expr, &config).unwrap().to_string()
+    }
+
+    fn with_scale(scale: i64, rounding: RoundingMode) -> DecimalConfig {
+        DecimalConfig {
+            scale: Some(scale),
+            rounding,
+            ..DecimalConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_decimal_literals_are_exact() {
+        let config = DecimalConfig::default();
+        assert_eq!(eval("0.1 + 0.2", config), "0.3");
+        assert_eq!(eval("1.10 * 3", config), "3.3");
+        assert_eq!(eval("2^100", config), "1267650600228229401496703205376");
+        let ten_digits = DecimalConfig {
+            precision: 10,
+            ..config
+        };
+        assert_eq!(
+            evaluate("2^100", &ten_digits).unwrap(),
+            BigDecimal::from_str("1.267650600e30").unwrap()
+        );
+        assert_eq!(eval("2^-3 + 10 % 4", config), "2.125");
+        assert_eq!(eval("sqrt(2.25) - abs(-1)", config), "0.5");
+    }
+
+    #[test]
+    fn test_precision_and_rounding_modes() {
+        let five_digits = DecimalConfig {
+            precision: 5,
+            ..DecimalConfig::default()
+        };
+        assert_eq!(eval("1/3", five_digits), "0.33333");
+        assert_eq!(eval("2/3", five_digits), "0.66667");
+        assert_eq!(eval("10/3", with_scale(2, RoundingMode::Down)), "3.33");
+        assert_eq!(eval("10/3", with_scale(2, RoundingMode::Up)), "3.34");
+        assert_eq!(eval("2.5", with_scale(0, RoundingMode::HalfEven)), "2");
+        assert_eq!(eval("2.5", with_scale(0, RoundingMode::HalfUp)), "3");
+        assert_eq!(eval("-2.5", with_scale(0, RoundingMode::Floor)), "-3");
+        assert_eq!(
+            eval("19.999 * 3", with_scale(2, RoundingMode::HalfEven)),
+            "60.00"
+        );
+    }
+
+    #[test]
+    fn test_invalid_decimal_expressions() {
+        let config = DecimalConfig::default();
+        assert!(evaluate("1/0", &config).is_err());
+        assert!(evaluate("5 % 0", &config).is_err());
+        assert!(evaluate("0^-1", &config).is_err());
+        assert!(evaluate("2^0.5", &config).is_err());
+        assert!(evaluate("sqrt(-1)", &config).is_err());
+        assert!(evaluate("sin(1)", &config).is_err());
+        assert!(evaluate("(1 + 2", &config).is_err());
+        let invalid = DecimalConfig {
+            precision: 0,
+            ..config
+        };
+        assert!(evaluate("1", &invalid).is_err());
+    }
+
+    #[test]
+    fn test_live_intermediates_are_charged() {
+        let config = DecimalConfig {
+            precision: 1000,
+            ..DecimalConfig::default()
+        };
+        let limits = SandboxConfig {
+            memory_limit: 1500,
+            max_execution_time: Duration::MAX,
+            ..SandboxConfig::default()
+        };
+        assert!(sandbox::run(&limits, || evaluate("1/3", &config)).is_ok());
+        // `1/3` (1000 chữ số) vẫn sống trong khi tính `1/7`
+        assert!(matches!(
+            sandbox::run(&limits, || evaluate("1/3 + 1/7", &config)),
+            Err(ArithmeticError::MemoryLimitExceeded { limit: 1500, .. })
+        ));
+        let five_digits = DecimalConfig {
+            precision: 5,
+            ..config
+        };
+        assert!(sandbox::run(&limits, || evaluate("1/3 + 1/7", &five_digits)).is_ok());
+    }
+}
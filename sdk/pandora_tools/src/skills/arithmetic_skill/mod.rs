@@ -1,3 +1,8 @@
+pub mod decimal;
+pub mod sandbox;
+pub mod symbolic;
+pub mod units;
+
 use fast_float2::parse as fast_float_parse;
 use lexical_core::FromLexical;
 use thiserror::Error;
@@ -7,6 +12,12 @@ use std::sync::{Arc, Mutex};
 
 // ===== COMPLEXITY CLASSIFICATION =====
 
+/// Các hàm dựng sẵn của fasteval (`print` bị loại vì ghi ra stderr)
+pub const FASTEVAL_FUNCTIONS: [&str; 23] = [
+    "int", "ceil", "floor", "abs", "sign", "log", "round", "min", "max", "e", "pi", "sin", "cos",
+    "tan", "asin", "acos", "atan", "sinh", "cosh", "tanh", "asinh", "acosh", "atanh",
+];
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpressionFeature {
     Length(usize),
@@ -39,6 +50,13 @@ impl ComplexityClassifier {
     }
 
     pub fn classify(&self, expr: &str) -> ComplexityLevel {
+        if self.is_unit_expression(expr) {
+            return ComplexityLevel::Units;
+        }
+        if self.requires_symbolic(expr) {
+            return ComplexityLevel::Symbolic;
+        }
+
         let complexity_score = self.calculate_complexity_score(expr);
         
         if complexity_score <= self.simple_threshold {
@@ -77,6 +95,56 @@ impl ComplexityClassifier {
         score.min(1.0)
     }
 
+    /// Biểu thức cần engine ký hiệu: có phương trình `lhs = rhs`, lệnh (solve, diff, ...),
+    /// biến tự do hoặc hàm mà fasteval không có (`sqrt`, `ln`, `exp`).
+    ///
+    /// Hàm dựng sẵn của fasteval (`max`, `round`, `asin`, ...) và hằng số không phải biến;
+    /// `==`, `!=`, `<=`, `>=` là phép so sánh của fasteval chứ không phải phương trình.
+    pub fn requires_symbolic(&self, expr: &str) -> bool {
+        if Self::is_equation(expr) {
+            return true;
+        }
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        let mut rest = expr;
+        while let Some(start) = rest.find(is_word_char) {
+            let tail = &rest[start..];
+            let len = tail.find(|c: char| !is_word_char(c)).unwrap_or(tail.len());
+            let word = &tail[..len];
+            rest = &tail[len..];
+            // Chữ số đứng đầu: số (kể cả `2e3`)
+            if !word.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                continue;
+            }
+            let needs_symbolic = if rest.trim_start().starts_with('(') {
+                !FASTEVAL_FUNCTIONS.contains(&word)
+            } else {
+                !symbolic::CONSTANTS.contains(&word)
+            };
+            if needs_symbolic {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Có đúng một dấu `=` và nó không thuộc `==`, `!=`, `<=`, `>=`.
+    fn is_equation(expr: &str) -> bool {
+        let chars: Vec<char> = expr.chars().collect();
+        let mut equals = chars.iter().enumerate().filter(|(_, c)| **c == '=');
+        match (equals.next(), equals.next()) {
+            (Some((i, _)), None) => {
+                !matches!(i.checked_sub(1).map(|j| chars[j]), Some('!' | '<' | '>'))
+            }
+            _ => false,
+        }
+    }
+
+    /// Biểu thức có đơn vị (xem `units::is_unit_expression`): đơn vị chỉ được nhận khi
+    /// đứng sau một số hoặc đơn vị khác, nên `min(3, 4)` hay `2*s` không bị đọc là phút/giây.
+    pub fn is_unit_expression(&self, expr: &str) -> bool {
+        units::is_unit_expression(expr)
+    }
+
     fn calculate_max_parentheses_depth(&self, expr: &str) -> usize {
         let mut max_depth = 0usize;
         let mut current_depth = 0usize;
@@ -103,6 +171,8 @@ pub enum ComplexityLevel {
     Simple,   // CustomParser
     Medium,   // FastEval
     Complex,  // SymbolicEngine
+    Symbolic, // Biến, phương trình, solve/diff/expand/factor: chỉ engine ký hiệu xử lý được
+    Units,    // Đại lượng có đơn vị: phân tích thứ nguyên
 }
 
 // ===== PERFORMANCE TRACKING =====
@@ -187,6 +257,8 @@ impl BackendPerformanceTracker {
                     .map(|(name, _)| name.clone())
                     .unwrap_or_else(|| "fasteval".to_string())
             }
+            ComplexityLevel::Symbolic => "symbolic".to_string(),
+            ComplexityLevel::Units => "units".to_string(),
         }
     }
 }
@@ -259,11 +331,21 @@ impl ExpressionValidator {
     }
 }
 
+/// Giới hạn được `sandbox` thực thi cho mọi lần tính của engine.
 #[derive(Debug, Clone)]
 pub struct SandboxConfig {
+    /// Hạn thời gian thực (wall-clock) cho một lần tính, kiểm tra hợp tác tại các
+    /// điểm tiêu nhiên liệu (xem `sandbox::run`)
     pub max_execution_time: Duration,
+    /// Bộ nhớ sống tối đa (byte) của các cấu trúc trung gian lớn
     pub memory_limit: usize,
     pub allow_network: bool,
+    /// Số nút tối đa của cây biểu thức
+    pub max_ast_nodes: usize,
+    /// Độ sâu lồng tối đa (ngoặc, toán tử một ngôi, chuỗi `^`)
+    pub max_ast_depth: usize,
+    /// Nhiên liệu cho các vòng lặp bên trong hàm (khai triển, phân tích, lũy thừa...)
+    pub max_fuel: u64,
 }
 
 impl Default for SandboxConfig {
@@ -272,6 +354,9 @@ impl Default for SandboxConfig {
             max_execution_time: Duration::from_millis(100),
             memory_limit: 1024 * 1024, // 1MB
             allow_network: false,
+            max_ast_nodes: 256,
+            max_ast_depth: 64,
+            max_fuel: 2_000_000,
         }
     }
 }
@@ -306,21 +391,6 @@ mod sandboxed_fasteval {
     }
 }
 
-mod symbolic_engine {
-    use fasteval::{Compiler, EmptyNamespace, Error, Evaler, Parser, Slab};
-
-    pub fn evaluate_symbolic(expr: &str) -> Result<f64, Error> {
-        // For now, use fasteval as symbolic engine
-        // In the future, this could be replaced with a proper symbolic math library
-        let parser = Parser::new();
-        let mut slab = Slab::new();
-        let parsed = parser.parse(expr, &mut slab.ps)?;
-        let compiled = parsed.from(&slab.ps).compile(&slab.ps, &mut slab.cs);
-        let mut ns = EmptyNamespace;
-        compiled.eval(&slab, &mut ns)
-    }
-}
-
 #[derive(Debug, Error)]
 pub enum ArithmeticError {
     #[error("Lỗi parse biểu thức: {0}")]
@@ -329,6 +399,20 @@ pub enum ArithmeticError {
     SecurityError(String),
     #[error("Lỗi timeout: {0}")]
     TimeoutError(String),
+    #[error("Lỗi tính toán ký hiệu: {0}")]
+    SymbolicError(String),
+    #[error("Sai thứ nguyên: cần {expected}, gặp {found}")]
+    DimensionMismatch { expected: String, found: String },
+    #[error("Đơn vị không xác định: {0}")]
+    UnknownUnit(String),
+    #[error("Biểu thức quá lớn: {nodes} nút (giới hạn {limit})")]
+    AstTooLarge { nodes: usize, limit: usize },
+    #[error("Biểu thức lồng quá sâu: {depth} mức (giới hạn {limit})")]
+    AstTooDeep { depth: usize, limit: usize },
+    #[error("Hết nhiên liệu tính toán (giới hạn {limit})")]
+    FuelExhausted { limit: u64 },
+    #[error("Vượt giới hạn bộ nhớ: cần {requested} byte (giới hạn {limit})")]
+    MemoryLimitExceeded { requested: usize, limit: usize },
 }
 
 // ===== ADAPTIVE ARITHMETIC ENGINE =====
@@ -338,6 +422,7 @@ pub struct AdaptiveArithmeticEngine {
     performance_tracker: BackendPerformanceTracker,
     expression_validator: ExpressionValidator,
     sandbox_config: SandboxConfig,
+    decimal_config: decimal::DecimalConfig,
 }
 
 impl AdaptiveArithmeticEngine {
@@ -347,6 +432,7 @@ impl AdaptiveArithmeticEngine {
             performance_tracker: BackendPerformanceTracker::new(),
             expression_validator: ExpressionValidator::new(),
             sandbox_config: SandboxConfig::default(),
+            decimal_config: decimal::DecimalConfig::default(),
         }
     }
 
@@ -362,18 +448,76 @@ impl AdaptiveArithmeticEngine {
         // 3. Select best backend based on complexity and performance
         let backend = self.performance_tracker.get_best_backend(complexity);
         
-        // 4. Execute with selected backend and track performance
+        // 4. Execute with selected backend inside the sandbox and track performance
         let start_time = Instant::now();
-        let result = self.execute_with_backend(trimmed, &backend)?;
+        let result = self.run_sandboxed(trimmed, |expr| {
+            Self::execute_with_backend(&self.complexity_classifier, expr, &backend)
+        });
         let duration = start_time.elapsed();
         
         // 5. Record performance metrics
-        self.performance_tracker.record_operation(&backend, duration, true);
+        self.performance_tracker.record_operation(&backend, duration, result.is_ok());
         
-        Ok(result)
+        result
+    }
+
+    /// Tính toán ký hiệu: trả về cả dạng chính xác lẫn giá trị số
+    /// (ví dụ `solve(x^2 = 2, x)`, `diff(x^3, x)`, `factor(x^2 - 1)`).
+    pub fn evaluate_symbolic(&self, expr: &str) -> Result<symbolic::SymbolicResult, ArithmeticError> {
+        let trimmed = expr.trim();
+        self.expression_validator.validate(trimmed)?;
+
+        let start_time = Instant::now();
+        let result = self.run_sandboxed(trimmed, symbolic::evaluate);
+        self.performance_tracker.record_operation("symbolic", start_time.elapsed(), result.is_ok());
+
+        result
+    }
+
+    /// Tính ở chế độ thập phân độ chính xác tùy ý theo `decimal_config`
+    /// (độ chính xác, số chữ số sau dấu phẩy, cách làm tròn).
+    pub fn evaluate_decimal(&self, expr: &str) -> Result<decimal::BigDecimal, ArithmeticError> {
+        let trimmed = expr.trim();
+        self.expression_validator.validate(trimmed)?;
+
+        let start_time = Instant::now();
+        let config = self.decimal_config;
+        let result = self.run_sandboxed(trimmed, move |expr| decimal::evaluate(expr, &config));
+        self.performance_tracker.record_operation("decimal", start_time.elapsed(), result.is_ok());
+
+        result
+    }
+
+    /// Tính biểu thức có đơn vị, ví dụ `3 m/s * 2 h` → `21.6 km`, `100 km/h to m/s`.
+    ///
+    /// `evaluate` chỉ trả về số: khi không có đổi đơn vị tường minh, số đó ở đơn vị SI cơ
+    /// bản (`3 m/s * 2 h` → `21600`, tức mét) chứ không phải đơn vị hiển thị ở đây.
+    pub fn evaluate_units(&self, expr: &str) -> Result<units::UnitValue, ArithmeticError> {
+        let trimmed = expr.trim();
+        self.expression_validator.validate(trimmed)?;
+
+        let start_time = Instant::now();
+        let result = self.run_sandboxed(trimmed, units::evaluate);
+        self.performance_tracker.record_operation("units", start_time.elapsed(), result.is_ok());
+
+        result
+    }
+
+    /// Kiểm tra kích thước cây rồi chạy `job` có đồng hồ đo theo `sandbox_config`.
+    fn run_sandboxed<T>(
+        &self,
+        expr: &str,
+        job: impl FnOnce(&str) -> Result<T, ArithmeticError>,
+    ) -> Result<T, ArithmeticError> {
+        sandbox::check_ast(expr, &self.sandbox_config)?;
+        sandbox::run(&self.sandbox_config, || job(expr))
     }
 
-    fn execute_with_backend(&self, expr: &str, backend: &str) -> Result<f64, ArithmeticError> {
+    fn execute_with_backend(
+        classifier: &ComplexityClassifier,
+        expr: &str,
+        backend: &str,
+    ) -> Result<f64, ArithmeticError> {
         // Fast-path: pure number parse (handle Unicode minus)
         let normalized = expr.replace('\u{2212}', "-");
         
@@ -392,19 +536,32 @@ impl AdaptiveArithmeticEngine {
                     Ok(res)
                 } else {
                     // Fallback to fasteval for custom parser
-                    self.execute_fasteval(expr)
+                    Self::execute_fasteval(expr)
                 }
             }
-            "fasteval" => self.execute_fasteval(expr),
-            "symbolic" => self.execute_symbolic(expr),
+            "fasteval" => Self::execute_fasteval(expr),
+            // Giá trị theo đơn vị đích nếu có `to ...`, ngược lại theo đơn vị SI cơ bản
+            "units" => units::evaluate(expr).map(|v| if v.converted { v.value } else { v.quantity.value }),
+            "symbolic" => Self::execute_symbolic(expr).or_else(|err| {
+                if !classifier.requires_symbolic(expr) {
+                    // Biểu thức thuần số mà engine ký hiệu không hỗ trợ (floor, max, ...)
+                    Self::execute_fasteval(expr)
+                } else if symbolic::parse(expr).is_err() {
+                    // Engine ký hiệu không parse được (hàm lạ, cú pháp của fasteval): thử
+                    // fasteval nhưng giữ lỗi ký hiệu nếu fasteval cũng không tính được
+                    Self::execute_fasteval(expr).map_err(|_| err)
+                } else {
+                    Err(err)
+                }
+            }),
             _ => {
                 // Default fallback logic
-                self.execute_fallback(expr)
+                Self::execute_fallback(expr)
             }
         }
     }
 
-    fn execute_fasteval(&self, expr: &str) -> Result<f64, ArithmeticError> {
+    fn execute_fasteval(expr: &str) -> Result<f64, ArithmeticError> {
         let val = sandboxed_fasteval::evaluate_safe(expr)
             .map_err(|e| ArithmeticError::ParseError(e.to_string()))?;
         
@@ -417,20 +574,17 @@ impl AdaptiveArithmeticEngine {
         Ok(val)
     }
 
-    fn execute_symbolic(&self, expr: &str) -> Result<f64, ArithmeticError> {
-        let val = symbolic_engine::evaluate_symbolic(expr)
-            .map_err(|e| ArithmeticError::ParseError(e.to_string()))?;
-        
-        if !val.is_finite() {
-            return Err(ArithmeticError::ParseError(
-                "Kết quả không hữu hạn (chia cho 0?)".into(),
-            ));
-        }
-        
-        Ok(val)
+    fn execute_symbolic(expr: &str) -> Result<f64, ArithmeticError> {
+        let result = symbolic::evaluate(expr)?;
+        result.numeric().ok_or_else(|| {
+            ArithmeticError::SymbolicError(format!(
+                "Kết quả '{}' không có một giá trị số duy nhất, dùng evaluate_symbolic",
+                result
+            ))
+        })
     }
 
-    fn execute_fallback(&self, expr: &str) -> Result<f64, ArithmeticError> {
+    fn execute_fallback(expr: &str) -> Result<f64, ArithmeticError> {
         let normalized = expr.replace('\u{2212}', "-");
         
         // Try multiple parsing methods
@@ -476,7 +630,7 @@ impl AdaptiveArithmeticEngine {
         }
         
         // Final fallback to fasteval
-        self.execute_fasteval(expr)
+        Self::execute_fasteval(expr)
     }
 
     // Public methods for configuration and monitoring
@@ -493,6 +647,10 @@ impl AdaptiveArithmeticEngine {
     pub fn update_sandbox_config(&mut self, config: SandboxConfig) {
         self.sandbox_config = config;
     }
+
+    pub fn update_decimal_config(&mut self, config: decimal::DecimalConfig) {
+        self.decimal_config = config;
+    }
 }
 
 pub struct ArithmeticSkill;
@@ -0,0 +1,411 @@
+//! Thực thi `SandboxConfig` cho mọi backend của `AdaptiveArithmeticEngine`.
+//!
+//! Bốn lớp giới hạn, mỗi lớp có một biến thể `ArithmeticError` riêng:
+//!
+//! - `check_ast`: đếm số nút và độ sâu lồng của biểu thức trước khi parse
+//!   (`AstTooLarge`, `AstTooDeep`), nên các parser đệ quy không thể tràn stack
+//! - `consume`: "nhiên liệu" cho các vòng lặp bên trong hàm (khai triển, phân tích
+//!   thừa số, lũy thừa thập phân, ...) (`FuelExhausted`)
+//! - `allocate`: kế toán bộ nhớ đang sống của các cấu trúc lớn theo `memory_limit`
+//!   (`MemoryLimitExceeded`); `Allocation` được giữ cùng giá trị mà nó tính cho
+//! - `run`: chạy job ngay trên luồng gọi; `consume` cũng là điểm kiểm tra
+//!   `max_execution_time` (`TimeoutError`). Mọi vòng lặp không bị chặn bởi `check_ast`
+//!   đều tiêu nhiên liệu nên job dừng ở điểm kiểm tra kế tiếp, không cần luồng phụ.
+//!
+//! Khi không có đồng hồ đo nào được cài (gọi trực tiếp `symbolic::evaluate`, ...)
+//! `consume` và `allocate` không giới hạn gì.
+
+use super::{ArithmeticError, SandboxConfig};
+use std::cell::Cell;
+use std::time::Instant;
+
+/// Số lần gọi `consume` giữa hai lần đọc đồng hồ (lần tiêu lớn hơn thì đọc ngay)
+const DEADLINE_CHECK_INTERVAL: u64 = 256;
+
+/// Kích thước của biểu thức theo cách các parser đệ quy nhìn thấy nó.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AstShape {
+    pub nodes: usize,
+    pub depth: usize,
+}
+
+/// Ước lượng số nút và độ sâu đệ quy mà không dựng cây.
+///
+/// Dùng chung cho mọi backend: toán hạng (số, tên) và toán tử mỗi thứ là một nút;
+/// độ sâu tăng theo ngoặc, chuỗi toán tử một ngôi và chuỗi `^` (kết hợp phải).
+pub fn measure(expr: &str) -> AstShape {
+    let mut shape = AstShape::default();
+    let mut scan = Scanner::default();
+    let chars: Vec<char> = expr.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        i = scan.step(&chars, i, &mut shape);
+    }
+    shape
+}
+
+/// Từ chối biểu thức vượt `max_ast_nodes` hoặc `max_ast_depth`.
+pub fn check_ast(expr: &str, config: &SandboxConfig) -> Result<AstShape, ArithmeticError> {
+    let shape = measure(expr);
+    if shape.nodes > config.max_ast_nodes {
+        return Err(ArithmeticError::AstTooLarge {
+            nodes: shape.nodes,
+            limit: config.max_ast_nodes,
+        });
+    }
+    if shape.depth > config.max_ast_depth {
+        return Err(ArithmeticError::AstTooDeep {
+            depth: shape.depth,
+            limit: config.max_ast_depth,
+        });
+    }
+    Ok(shape)
+}
+
+#[derive(Default)]
+struct Scanner {
+    /// Độ sâu do các mức ngoặc bao ngoài đóng góp
+    base: usize,
+    /// Độ sâu trong mức ngoặc hiện tại (toán tử một ngôi, chuỗi `^`)
+    level: usize,
+    saved: Vec<usize>,
+    after_operand: bool,
+}
+
+impl Scanner {
+    fn step(&mut self, chars: &[char], start: usize, shape: &mut AstShape) -> usize {
+        let c = chars[start];
+        let mut end = start + 1;
+        match c {
+            c if c.is_whitespace() => return end,
+            c if c.is_ascii_digit() || c == '.' => {
+                while end < chars.len() && (chars[end].is_ascii_digit() || chars[end] == '.') {
+                    end += 1;
+                }
+                // Phần mũ khoa học chỉ khi sau `e` là chữ số (tránh nuốt hằng số `e`)
+                if end < chars.len() && matches!(chars[end], 'e' | 'E') {
+                    let mut exponent = end + 1;
+                    if exponent < chars.len() && matches!(chars[exponent], '+' | '-') {
+                        exponent += 1;
+                    }
+                    if exponent < chars.len() && chars[exponent].is_ascii_digit() {
+                        end = exponent;
+                        while end < chars.len() && chars[end].is_ascii_digit() {
+                            end += 1;
+                        }
+                    }
+                }
+                self.operand(shape);
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                    end += 1;
+                }
+                self.operand(shape);
+            }
+            '(' => {
+                if self.after_operand {
+                    // Gọi hàm hoặc nhân ngầm định
+                    shape.nodes += 1;
+                }
+                self.saved.push(self.level);
+                self.base += self.level + 1;
+                self.level = 0;
+                self.after_operand = false;
+            }
+            ')' => {
+                let level = self.saved.pop().unwrap_or(0);
+                self.base = self.base.saturating_sub(level + 1);
+                self.level = level;
+                self.after_operand = true;
+            }
+            '^' => {
+                shape.nodes += 1;
+                self.level += 1;
+                self.after_operand = false;
+            }
+            _ if !self.after_operand => {
+                // Toán tử một ngôi lồng thêm một mức đệ quy
+                shape.nodes += 1;
+                self.level += 1;
+            }
+            _ => {
+                shape.nodes += 1;
+                self.level = 0;
+                self.after_operand = false;
+            }
+        }
+        end
+    }
+
+    fn operand(&mut self, shape: &mut AstShape) {
+        if self.after_operand {
+            // Nhân ngầm định, ví dụ `2x` hay `3 m`
+            shape.nodes += 1;
+        }
+        shape.nodes += 1;
+        shape.depth = shape.depth.max(self.base + self.level + 1);
+        self.after_operand = true;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Meter {
+    fuel_limit: u64,
+    fuel_used: u64,
+    memory_limit: usize,
+    memory_used: usize,
+    /// `None` khi `max_execution_time` quá lớn để cộng vào `Instant`
+    deadline: Option<Instant>,
+    calls: u64,
+}
+
+thread_local! {
+    static METER: Cell<Option<Meter>> = const { Cell::new(None) };
+}
+
+/// Tiêu `units` đơn vị nhiên liệu; cũng là điểm hủy hợp tác khi quá hạn.
+pub(crate) fn consume(units: u64) -> Result<(), ArithmeticError> {
+    METER.with(|cell| {
+        let Some(mut meter) = cell.get() else {
+            return Ok(());
+        };
+        meter.fuel_used = meter.fuel_used.saturating_add(units);
+        meter.calls += 1;
+        cell.set(Some(meter));
+        if meter.fuel_used > meter.fuel_limit {
+            return Err(ArithmeticError::FuelExhausted {
+                limit: meter.fuel_limit,
+            });
+        }
+        let check_clock =
+            units >= DEADLINE_CHECK_INTERVAL || meter.calls.is_multiple_of(DEADLINE_CHECK_INTERVAL);
+        if check_clock
+            && meter
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(ArithmeticError::TimeoutError(
+                "Vượt quá thời gian thực thi cho phép".into(),
+            ));
+        }
+        Ok(())
+    })
+}
+
+/// Khoản bộ nhớ đã đăng ký với đồng hồ đo; được trả lại khi drop.
+#[must_use = "bộ nhớ được trả lại ngay khi Allocation bị drop"]
+pub(crate) struct Allocation {
+    bytes: usize,
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        if self.bytes == 0 {
+            return;
+        }
+        METER.with(|cell| {
+            if let Some(mut meter) = cell.get() {
+                meter.memory_used = meter.memory_used.saturating_sub(self.bytes);
+                cell.set(Some(meter));
+            }
+        });
+    }
+}
+
+/// Đăng ký `bytes` byte bộ nhớ sống; lỗi nếu tổng vượt `memory_limit`.
+pub(crate) fn allocate(bytes: usize) -> Result<Allocation, ArithmeticError> {
+    METER.with(|cell| {
+        let Some(mut meter) = cell.get() else {
+            return Ok(Allocation { bytes: 0 });
+        };
+        let requested = meter.memory_used.saturating_add(bytes);
+        if requested > meter.memory_limit {
+            return Err(ArithmeticError::MemoryLimitExceeded {
+                requested,
+                limit: meter.memory_limit,
+            });
+        }
+        meter.memory_used = requested;
+        cell.set(Some(meter));
+        Ok(Allocation { bytes })
+    })
+}
+
+/// Khôi phục đồng hồ đo trước đó khi `run` kết thúc, kể cả khi `job` panic.
+struct MeterGuard {
+    previous: Option<Meter>,
+}
+
+impl Drop for MeterGuard {
+    fn drop(&mut self) {
+        METER.with(|cell| cell.set(self.previous));
+    }
+}
+
+/// Chạy `job` trên luồng hiện tại với đồng hồ đo theo `config`.
+///
+/// Hạn `max_execution_time` chỉ là hạn hợp tác: nó được kiểm tra trong `consume`, nên
+/// job trả về `TimeoutError` ở điểm kiểm tra đầu tiên sau khi quá hạn. Đoạn code
+/// không gọi `consume` (ví dụ một phép nhân số lớn đơn lẻ) không bị ngắt giữa chừng
+/// và có thể chạy quá hạn tới điểm kiểm tra kế tiếp.
+pub fn run<T>(
+    config: &SandboxConfig,
+    job: impl FnOnce() -> Result<T, ArithmeticError>,
+) -> Result<T, ArithmeticError> {
+    let meter = Meter {
+        fuel_limit: config.max_fuel,
+        fuel_used: 0,
+        memory_limit: config.memory_limit,
+        memory_used: 0,
+        deadline: Instant::now().checked_add(config.max_execution_time),
+        calls: 0,
+    };
+    let _guard = MeterGuard {
+        previous: METER.with(|cell| cell.replace(Some(meter))),
+    };
+    job()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_measure_counts_nodes_and_depth() {
+        assert_eq!(measure("1 + 2 * 3"), AstShape { nodes: 5, depth: 1 });
+        assert_eq!(measure("sin(x)").depth, 2);
+        assert_eq!(measure("2^2^2^2").depth, 4);
+        assert_eq!(measure("---1").depth, 4);
+        assert_eq!(measure("1.5e-3 + e").nodes, 3);
+        assert_eq!(measure("((((1))))").depth, 5);
+        assert_eq!(measure("3 m/s * 2 h").nodes, 9);
+
+        let config = SandboxConfig {
+            max_ast_depth: 8,
+            max_ast_nodes: 10,
+            ..SandboxConfig::default()
+        };
+        let deep = format!("{}1{}", "(".repeat(20), ")".repeat(20));
+        assert!(matches!(
+            check_ast(&deep, &config),
+            Err(ArithmeticError::AstTooDeep {
+                depth: 21,
+                limit: 8
+            })
+        ));
+        assert!(matches!(
+            check_ast(&["1"; 8].join("+"), &config),
+            Err(ArithmeticError::AstTooLarge {
+                nodes: 15,
+                limit: 10
+            })
+        ));
+    }
+
+    #[test]
+    fn test_fuel_and_memory_are_metered() {
+        let config = SandboxConfig {
+            max_fuel: 100,
+            memory_limit: 1000,
+            ..SandboxConfig::default()
+        };
+        let spent = run(&config, || (0..1000).try_for_each(|_| consume(1)));
+        assert!(matches!(
+            spent,
+            Err(ArithmeticError::FuelExhausted { limit: 100 })
+        ));
+
+        let held = run(&config, || {
+            let _first = allocate(600)?;
+            allocate(600).map(drop)
+        });
+        assert!(matches!(
+            held,
+            Err(ArithmeticError::MemoryLimitExceeded {
+                requested: 1200,
+                limit: 1000
+            })
+        ));
+        // Bộ nhớ đã trả lại thì có thể dùng lại
+        let reused = run(&config, || {
+            (0..10).try_for_each(|_| allocate(600).map(drop))
+        });
+        assert!(reused.is_ok());
+
+        // Ngoài sandbox không có giới hạn
+        assert!(consume(u64::MAX).is_ok());
+        assert!(allocate(usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_meter_is_restored_after_panic_and_nesting() {
+        let config = SandboxConfig {
+            max_fuel: 10,
+            ..SandboxConfig::default()
+        };
+        let panicked = std::panic::catch_unwind(|| {
+            run(&config, || -> Result<(), ArithmeticError> {
+                panic!("job lỗi")
+            })
+        });
+        assert!(panicked.is_err());
+        // Không còn đồng hồ đo cũ trên luồng
+        assert!(consume(u64::MAX).is_ok());
+
+        let outer = run(&config, || {
+            consume(5)?;
+            let inner = SandboxConfig {
+                max_fuel: u64::MAX,
+                ..SandboxConfig::default()
+            };
+            run(&inner, || consume(1_000))?;
+            // Đồng hồ ngoài được khôi phục với lượng đã tiêu trước đó
+            consume(6)
+        });
+        assert!(matches!(
+            outer,
+            Err(ArithmeticError::FuelExhausted { limit: 10 })
+        ));
+    }
+
+    #[test]
+    fn test_run_cancels_on_deadline() {
+        let expired = SandboxConfig {
+            max_execution_time: Duration::ZERO,
+            max_fuel: u64::MAX,
+            ..SandboxConfig::default()
+        };
+        // Job không có điểm kiểm tra thì chạy xong bình thường
+        assert_eq!(run(&expired, || Ok(42)).unwrap(), 42);
+
+        // Đồng hồ được đọc mỗi DEADLINE_CHECK_INTERVAL lần gọi `consume`
+        let mut steps = 0;
+        let cooperative: Result<(), _> = run(&expired, || loop {
+            steps += 1;
+            consume(1)?;
+        });
+        assert!(matches!(cooperative, Err(ArithmeticError::TimeoutError(_))));
+        assert_eq!(steps, DEADLINE_CHECK_INTERVAL);
+
+        // Một lần tiêu lớn đọc đồng hồ ngay
+        let bulk = run(&expired, || consume(DEADLINE_CHECK_INTERVAL));
+        assert!(matches!(bulk, Err(ArithmeticError::TimeoutError(_))));
+
+        // Hạn không biểu diễn được thì chỉ nhiên liệu giới hạn
+        let unbounded = SandboxConfig {
+            max_execution_time: Duration::MAX,
+            max_fuel: 1000,
+            ..SandboxConfig::default()
+        };
+        let spent: Result<(), _> = run(&unbounded, || loop {
+            consume(DEADLINE_CHECK_INTERVAL)?;
+        });
+        assert!(matches!(
+            spent,
+            Err(ArithmeticError::FuelExhausted { limit: 1000 })
+        ));
+    }
+}
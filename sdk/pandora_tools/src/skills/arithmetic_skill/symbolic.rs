@@ -0,0 +1,1771 @@
+//! Engine đại số ký hiệu cho backend "symbolic" của `AdaptiveArithmeticEngine`.
+//!
+//! Biểu thức được biểu diễn bằng cây `Expr` với hệ số hữu tỉ chính xác (`Rational`),
+//! luôn được đưa về dạng chuẩn (gộp số hạng đồng dạng, gộp lũy thừa cùng cơ số) nên hai
+//! biểu thức bằng nhau về cấu trúc sẽ so sánh bằng nhau. Các lệnh hỗ trợ:
+//!
+//! - `simplify(e)` hoặc biểu thức trần: rút gọn, tính chính xác nếu không có biến
+//! - `expand(e)`, `factor(e)`: khai triển / phân tích đa thức (nghiệm hữu tỉ)
+//! - `diff(e, x[, n])`: đạo hàm cấp `n`
+//! - `solve(lhs = rhs[, x])` hoặc phương trình trần `lhs = rhs`: giải phương trình
+//!   đa thức bậc 1, 2 và bậc cao có đủ nghiệm hữu tỉ
+//!
+//! Mỗi kết quả trả về cả dạng chính xác lẫn giá trị số (`ExactValue`).
+
+use super::{sandbox, ArithmeticError};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+
+/// Các lệnh được nhận diện ở mức ngoài cùng của biểu thức
+pub const COMMANDS: [&str; 6] = [
+    "solve",
+    "diff",
+    "derivative",
+    "expand",
+    "factor",
+    "simplify",
+];
+/// Hằng số có tên: giữ nguyên dạng ký hiệu, chỉ thay giá trị khi tính số
+pub const CONSTANTS: [&str; 2] = ["pi", "e"];
+
+const MAX_POW_EXPONENT: i128 = 1024;
+const MAX_POLY_DEGREE: i128 = 64;
+const MAX_EXPANDED_TERMS: usize = 4096;
+const MAX_DIFF_ORDER: usize = 16;
+const MAX_DIVISOR_SEARCH: i128 = 1_000_000_000_000;
+
+fn error(message: impl Into<String>) -> ArithmeticError {
+    ArithmeticError::SymbolicError(message.into())
+}
+
+fn overflow() -> ArithmeticError {
+    error("Tràn số khi tính hữu tỉ chính xác")
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// ===== RATIONAL =====
+
+/// Số hữu tỉ chính xác, luôn tối giản với mẫu dương.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+    pub const ONE: Rational = Rational { num: 1, den: 1 };
+
+    pub fn new(num: i128, den: i128) -> Result<Self, ArithmeticError> {
+        if den == 0 {
+            return Err(error("Chia cho 0"));
+        }
+        if num == i128::MIN || den == i128::MIN {
+            return Err(overflow());
+        }
+        let g = gcd(num, den);
+        let sign = den.signum();
+        Ok(Self {
+            num: sign * num / g,
+            den: sign * den / g,
+        })
+    }
+
+    pub fn from_integer(n: i64) -> Self {
+        Self {
+            num: n as i128,
+            den: 1,
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.num < 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    fn int(n: i128) -> Self {
+        Self { num: n, den: 1 }
+    }
+
+    fn abs(self) -> Self {
+        Self {
+            num: self.num.abs(),
+            den: self.den,
+        }
+    }
+
+    fn neg(self) -> Self {
+        Self {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+
+    fn recip(self) -> Result<Self, ArithmeticError> {
+        Self::new(self.den, self.num)
+    }
+
+    fn add(self, other: Self) -> Result<Self, ArithmeticError> {
+        let lhs = self.num.checked_mul(other.den);
+        let rhs = other.num.checked_mul(self.den);
+        let num = lhs
+            .zip(rhs)
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or_else(overflow)?;
+        let den = self.den.checked_mul(other.den).ok_or_else(overflow)?;
+        Self::new(num, den)
+    }
+
+    fn mul(self, other: Self) -> Result<Self, ArithmeticError> {
+        // Rút gọn chéo trước để hạn chế tràn số
+        let g1 = gcd(self.num, other.den).max(1);
+        let g2 = gcd(other.num, self.den).max(1);
+        let num = (self.num / g1)
+            .checked_mul(other.num / g2)
+            .ok_or_else(overflow)?;
+        let den = (self.den / g2)
+            .checked_mul(other.den / g1)
+            .ok_or_else(overflow)?;
+        Self::new(num, den)
+    }
+
+    fn pow(self, exponent: i128) -> Result<Self, ArithmeticError> {
+        if exponent.abs() > MAX_POW_EXPONENT {
+            return Err(error(format!("Số mũ {} quá lớn", exponent)));
+        }
+        let base = if exponent < 0 { self.recip()? } else { self };
+        let e = exponent.unsigned_abs() as u32;
+        let num = base.num.checked_pow(e).ok_or_else(overflow)?;
+        let den = base.den.checked_pow(e).ok_or_else(overflow)?;
+        Self::new(num, den)
+    }
+
+    /// Căn bậc `q` chính xác, nếu có.
+    fn exact_root(self, q: u32) -> Option<Self> {
+        if self.num < 0 && q.is_multiple_of(2) {
+            return None;
+        }
+        let num = integer_root(self.num.abs(), q)? * self.num.signum();
+        let den = integer_root(self.den, q)?;
+        Some(Self { num, den })
+    }
+
+    /// Đọc số thập phân như `2.5` hoặc `1.5e-3` thành phân số chính xác.
+    fn parse_decimal(text: &str) -> Result<Self, ArithmeticError> {
+        let invalid = || error(format!("Số không hợp lệ '{}'", text));
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(i) => (
+                &text[..i],
+                text[i + 1..].parse::<i128>().map_err(|_| invalid())?,
+            ),
+            None => (text, 0),
+        };
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_part.is_empty() && frac_part.is_empty() {
+            return Err(invalid());
+        }
+        let digits = format!("{}{}", int_part, frac_part);
+        let num = digits.parse::<i128>().map_err(|_| overflow())?;
+        let scale = Self::int(10).pow(exponent - frac_part.len() as i128)?;
+        Self::int(num).mul(scale)
+    }
+}
+
+fn integer_root(n: i128, q: u32) -> Option<i128> {
+    let guess = (n as f64).powf(1.0 / q as f64).round() as i128;
+    (guess.saturating_sub(1)..=guess.saturating_add(1))
+        .find(|r| *r >= 0 && r.checked_pow(q) == Some(n))
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (
+            self.num.checked_mul(other.den),
+            other.num.checked_mul(self.den),
+        ) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => self
+                .to_f64()
+                .total_cmp(&other.to_f64())
+                .then(self.num.cmp(&other.num))
+                .then(self.den.cmp(&other.den)),
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+// ===== EXPRESSION TREE =====
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Func {
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Abs,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Func::Sin,
+            "cos" => Func::Cos,
+            "tan" => Func::Tan,
+            "exp" => Func::Exp,
+            "ln" => Func::Ln,
+            "abs" => Func::Abs,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Func::Sin => "sin",
+            Func::Cos => "cos",
+            Func::Tan => "tan",
+            Func::Exp => "exp",
+            Func::Ln => "ln",
+            Func::Abs => "abs",
+        }
+    }
+
+    fn apply(self, x: f64) -> f64 {
+        match self {
+            Func::Sin => x.sin(),
+            Func::Cos => x.cos(),
+            Func::Tan => x.tan(),
+            Func::Exp => x.exp(),
+            Func::Ln => x.ln(),
+            Func::Abs => x.abs(),
+        }
+    }
+}
+
+/// Cây biểu thức. Hiệu `a - b` là `Add[a, Mul[-1, b]]`, thương `a / b` là
+/// `Mul[a, Pow(b, -1)]` và `sqrt(x)` là `Pow(x, 1/2)`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Expr {
+    Num(Rational),
+    Sym(Arc<str>),
+    Add(Vec<Expr>),
+    Mul(Vec<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Func(Func, Box<Expr>),
+}
+
+fn num(n: i128) -> Expr {
+    Expr::Num(Rational::int(n))
+}
+
+fn half() -> Expr {
+    Expr::Num(Rational { num: 1, den: 2 })
+}
+
+impl Expr {
+    pub fn symbol(name: &str) -> Self {
+        Expr::Sym(Arc::from(name))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        matches!(self, Expr::Num(r) if r.is_zero())
+    }
+
+    pub fn as_rational(&self) -> Option<Rational> {
+        match self {
+            Expr::Num(r) => Some(*r),
+            _ => None,
+        }
+    }
+
+    /// Các biến tự do (không tính hằng số `pi`, `e`).
+    pub fn free_symbols(&self) -> BTreeSet<String> {
+        let mut symbols = BTreeSet::new();
+        self.collect_symbols(&mut symbols);
+        symbols
+    }
+
+    fn collect_symbols(&self, out: &mut BTreeSet<String>) {
+        match self {
+            Expr::Num(_) => {}
+            Expr::Sym(s) => {
+                if !CONSTANTS.contains(&&**s) {
+                    out.insert(s.to_string());
+                }
+            }
+            Expr::Add(items) | Expr::Mul(items) => {
+                items.iter().for_each(|e| e.collect_symbols(out))
+            }
+            Expr::Pow(b, e) => {
+                b.collect_symbols(out);
+                e.collect_symbols(out);
+            }
+            Expr::Func(_, a) => a.collect_symbols(out),
+        }
+    }
+
+    pub fn contains(&self, var: &str) -> bool {
+        match self {
+            Expr::Num(_) => false,
+            Expr::Sym(s) => &**s == var,
+            Expr::Add(items) | Expr::Mul(items) => items.iter().any(|e| e.contains(var)),
+            Expr::Pow(b, e) => b.contains(var) || e.contains(var),
+            Expr::Func(_, a) => a.contains(var),
+        }
+    }
+
+    /// Giá trị số; `None` nếu còn biến tự do hoặc kết quả không hữu hạn/không thực.
+    pub fn numeric(&self) -> Option<f64> {
+        self.eval_f64().filter(|v| v.is_finite())
+    }
+
+    fn eval_f64(&self) -> Option<f64> {
+        Some(match self {
+            Expr::Num(r) => r.to_f64(),
+            Expr::Sym(s) => match &**s {
+                "pi" => std::f64::consts::PI,
+                "e" => std::f64::consts::E,
+                _ => return None,
+            },
+            Expr::Add(items) => items.iter().map(|e| e.eval_f64()).sum::<Option<f64>>()?,
+            Expr::Mul(items) => items
+                .iter()
+                .map(|e| e.eval_f64())
+                .product::<Option<f64>>()?,
+            Expr::Pow(b, e) => {
+                let base = b.eval_f64()?;
+                match e.as_rational() {
+                    // Căn bậc lẻ của số âm vẫn là số thực
+                    Some(r) if base < 0.0 && r.den % 2 == 1 => {
+                        let magnitude = base.abs().powf(r.to_f64());
+                        if r.num % 2 == 0 {
+                            magnitude
+                        } else {
+                            -magnitude
+                        }
+                    }
+                    _ => base.powf(e.eval_f64()?),
+                }
+            }
+            Expr::Func(f, a) => f.apply(a.eval_f64()?),
+        })
+    }
+
+    /// Đưa về dạng chuẩn: tính phần hằng chính xác, gộp số hạng và lũy thừa.
+    pub fn simplify(&self) -> Result<Expr, ArithmeticError> {
+        match self {
+            Expr::Num(_) | Expr::Sym(_) => Ok(self.clone()),
+            Expr::Add(items) => {
+                make_add(items.iter().map(Expr::simplify).collect::<Result<_, _>>()?)
+            }
+            Expr::Mul(items) => {
+                make_mul(items.iter().map(Expr::simplify).collect::<Result<_, _>>()?)
+            }
+            Expr::Pow(b, e) => make_pow(b.simplify()?, e.simplify()?),
+            Expr::Func(f, a) => make_func(*f, a.simplify()?),
+        }
+    }
+
+    /// Khai triển tích và lũy thừa nguyên dương của tổng.
+    pub fn expand(&self) -> Result<Expr, ArithmeticError> {
+        expand_node(&self.simplify()?)
+    }
+
+    /// Đạo hàm theo `var`.
+    pub fn diff(&self, var: &str) -> Result<Expr, ArithmeticError> {
+        derivative(&self.simplify()?, var)
+    }
+
+    /// Phân tích đa thức một biến thành nhân tử trên Q (theo nghiệm hữu tỉ).
+    pub fn factor(&self) -> Result<Expr, ArithmeticError> {
+        let simplified = self.simplify()?;
+        let symbols = simplified.free_symbols();
+        let var = match symbols.len() {
+            0 => return Ok(simplified),
+            1 => symbols.into_iter().next().unwrap_or_default(),
+            _ => return Err(error("factor chỉ hỗ trợ đa thức một biến")),
+        };
+        let coefficients = polynomial_coefficients(&simplified, &var)?
+            .as_deref()
+            .and_then(rational_coefficients)
+            .ok_or_else(|| error(format!("Không phải đa thức hệ số hữu tỉ theo {}", var)))?;
+        let factorization = factor_rational_roots(&coefficients)?;
+        let x = Expr::symbol(&var);
+
+        let mut factors = Vec::new();
+        for (root, multiplicity) in &factorization.roots {
+            let linear = make_add(vec![
+                make_mul(vec![num(root.den), x.clone()])?,
+                num(-root.num),
+            ])?;
+            factors.push(match multiplicity {
+                1 => linear,
+                m => Expr::Pow(Box::new(linear), Box::new(num(*m as i128))),
+            });
+        }
+        if factorization.rest.len() > 1 {
+            factors.push(integer_polynomial(&factorization.rest, &x)?);
+        }
+        factors.sort();
+        let content = factorization.content;
+        // Ghép trực tiếp để giữ nguyên các nhân tử (make_mul sẽ phân phối hệ số vào tổng)
+        Ok(match (content == Rational::ONE, factors.len()) {
+            (_, 0) => Expr::Num(content),
+            (true, 1) => factors.remove(0),
+            (true, _) => Expr::Mul(factors),
+            (false, _) => {
+                factors.insert(0, Expr::Num(content));
+                Expr::Mul(factors)
+            }
+        })
+    }
+
+    /// Precedence khi hiển thị: 1 tổng, 2 tích/thương/số âm, 3 lũy thừa, 4 nguyên tử.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Num(r) if r.is_negative() || !r.is_integer() => 2,
+            Expr::Num(_) | Expr::Sym(_) | Expr::Func(..) => 4,
+            Expr::Add(_) => 1,
+            Expr::Mul(_) => 2,
+            Expr::Pow(_, e) => match e.as_rational() {
+                Some(r) if r.num == 1 && r.den == 2 => 4,
+                Some(r) if r.is_negative() => 2,
+                _ => 3,
+            },
+        }
+    }
+
+    /// Số hạng mang dấu âm khi đứng trong một tổng
+    fn is_negative_term(&self) -> bool {
+        match self {
+            Expr::Num(r) => r.is_negative(),
+            Expr::Mul(items) => matches!(items.first(), Some(Expr::Num(r)) if r.is_negative()),
+            _ => false,
+        }
+    }
+}
+
+fn wrap(expr: &Expr, min_precedence: u8) -> String {
+    if expr.precedence() < min_precedence {
+        format!("({})", expr)
+    } else {
+        expr.to_string()
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(r) => write!(f, "{}", r),
+            Expr::Sym(s) => write!(f, "{}", s),
+            Expr::Func(func, a) => write!(f, "{}({})", func.name(), a),
+            Expr::Add(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    if i == 0 {
+                        write!(f, "{}", term)?;
+                    } else if term.is_negative_term() {
+                        let (c, rest) = split_coefficient(term.clone());
+                        write!(f, " - {}", scale_raw(rest, c.neg()))?;
+                    } else {
+                        write!(f, " + {}", term)?;
+                    }
+                }
+                Ok(())
+            }
+            Expr::Mul(items) => {
+                let (coefficient, factors) = match items.split_first() {
+                    Some((Expr::Num(r), rest)) => (*r, rest),
+                    _ => (Rational::ONE, &items[..]),
+                };
+                let mut numerator = Vec::new();
+                let mut denominator = Vec::new();
+                if coefficient.abs().num != 1 {
+                    numerator.push(coefficient.abs().num.to_string());
+                }
+                if coefficient.den != 1 {
+                    denominator.push((coefficient.den.to_string(), 4));
+                }
+                for factor in factors {
+                    match factor {
+                        Expr::Pow(b, e) if e.as_rational().is_some_and(|r| r.is_negative()) => {
+                            let inverse = match e.as_rational().map(Rational::neg) {
+                                Some(r) if r == Rational::ONE => (**b).clone(),
+                                Some(r) => Expr::Pow(b.clone(), Box::new(Expr::Num(r))),
+                                None => unreachable!(),
+                            };
+                            denominator.push((wrap(&inverse, 2), inverse.precedence()));
+                        }
+                        other => numerator.push(wrap(other, 2)),
+                    }
+                }
+                if coefficient.is_negative() {
+                    write!(f, "-")?;
+                }
+                if numerator.is_empty() {
+                    write!(f, "1")?;
+                } else {
+                    write!(f, "{}", numerator.join("*"))?;
+                }
+                match denominator.as_slice() {
+                    [] => Ok(()),
+                    [(single, precedence)] if *precedence >= 3 => write!(f, "/{}", single),
+                    parts => {
+                        let parts: Vec<&str> = parts.iter().map(|(s, _)| s.as_str()).collect();
+                        write!(f, "/({})", parts.join("*"))
+                    }
+                }
+            }
+            Expr::Pow(b, e) => match e.as_rational() {
+                Some(r) if r.num == 1 && r.den == 2 => write!(f, "sqrt({})", b),
+                Some(r) if r.is_negative() => {
+                    write!(f, "{}", Expr::Mul(vec![self.clone()]))
+                }
+                _ => write!(f, "{}^{}", wrap(b, 4), wrap(e, 4)),
+            },
+        }
+    }
+}
+
+// ===== CANONICAL CONSTRUCTORS =====
+//
+// Các hàm make_* nhận đối số đã ở dạng chuẩn và trả về dạng chuẩn.
+
+/// Tách hệ số số học: `3*x*y` → `(3, x*y)`.
+fn split_coefficient(expr: Expr) -> (Rational, Expr) {
+    match expr {
+        Expr::Num(r) => (r, num(1)),
+        Expr::Mul(mut items) => match items.first() {
+            Some(Expr::Num(r)) => {
+                let r = *r;
+                items.remove(0);
+                if items.len() == 1 {
+                    (r, items.remove(0))
+                } else {
+                    (r, Expr::Mul(items))
+                }
+            }
+            _ => (Rational::ONE, Expr::Mul(items)),
+        },
+        other => (Rational::ONE, other),
+    }
+}
+
+/// Ghép lại `coefficient * rest` mà không rút gọn thêm.
+fn scale_raw(rest: Expr, coefficient: Rational) -> Expr {
+    if coefficient == Rational::ONE {
+        return rest;
+    }
+    match rest {
+        Expr::Num(r) if r == Rational::ONE => Expr::Num(coefficient),
+        Expr::Mul(mut items) => {
+            items.insert(0, Expr::Num(coefficient));
+            Expr::Mul(items)
+        }
+        other => Expr::Mul(vec![Expr::Num(coefficient), other]),
+    }
+}
+
+/// Bậc tổng (xấp xỉ) dùng để sắp xếp số hạng khi hiển thị: bậc cao đứng trước.
+fn degree(expr: &Expr) -> f64 {
+    match expr {
+        Expr::Num(_) => 0.0,
+        Expr::Sym(s) if CONSTANTS.contains(&&**s) => 0.0,
+        Expr::Sym(_) | Expr::Func(..) => 1.0,
+        Expr::Add(items) => items.iter().map(degree).fold(0.0, f64::max),
+        Expr::Mul(items) => items.iter().map(degree).sum(),
+        Expr::Pow(b, e) => degree(b) * e.as_rational().map_or(1.0, |r| r.to_f64()),
+    }
+}
+
+fn make_add(terms: Vec<Expr>) -> Result<Expr, ArithmeticError> {
+    let mut constant = Rational::ZERO;
+    let mut like_terms: BTreeMap<Expr, Rational> = BTreeMap::new();
+    let mut pending = terms;
+    while let Some(term) = pending.pop() {
+        sandbox::consume(1)?;
+        match term {
+            Expr::Add(inner) => pending.extend(inner),
+            Expr::Num(r) => constant = constant.add(r)?,
+            other => {
+                let (c, rest) = split_coefficient(other);
+                let entry = like_terms.entry(rest).or_insert(Rational::ZERO);
+                *entry = entry.add(c)?;
+            }
+        }
+    }
+    let mut keyed: Vec<(f64, Expr, Rational)> = like_terms
+        .into_iter()
+        .filter(|(_, c)| !c.is_zero())
+        .map(|(rest, c)| (degree(&rest), rest, c))
+        .collect();
+    keyed.sort_by(|a, b| {
+        b.0.total_cmp(&a.0)
+            .then_with(|| a.1.cmp(&b.1))
+            .then_with(|| a.2.cmp(&b.2))
+    });
+    let mut out: Vec<Expr> = keyed
+        .into_iter()
+        .map(|(_, rest, c)| scale_raw(rest, c))
+        .collect();
+    if !constant.is_zero() {
+        out.push(Expr::Num(constant));
+    }
+    Ok(match out.len() {
+        0 => Expr::Num(Rational::ZERO),
+        1 => out.remove(0),
+        _ => Expr::Add(out),
+    })
+}
+
+fn make_mul(factors: Vec<Expr>) -> Result<Expr, ArithmeticError> {
+    make_mul_inner(factors, true)
+}
+
+fn make_mul_inner(factors: Vec<Expr>, allow_remerge: bool) -> Result<Expr, ArithmeticError> {
+    let mut coefficient = Rational::ONE;
+    let mut powers: BTreeMap<Expr, Vec<Expr>> = BTreeMap::new();
+    let mut pending = factors;
+    while let Some(factor) = pending.pop() {
+        sandbox::consume(1)?;
+        match factor {
+            Expr::Mul(inner) => pending.extend(inner),
+            Expr::Num(r) => coefficient = coefficient.mul(r)?,
+            Expr::Pow(b, e) => powers.entry(*b).or_default().push(*e),
+            other => powers.entry(other).or_default().push(num(1)),
+        }
+    }
+    if coefficient.is_zero() {
+        return Ok(Expr::Num(Rational::ZERO));
+    }
+
+    let mut out = Vec::new();
+    let mut split_powers = false;
+    for (base, exponents) in powers {
+        match make_pow(base, make_add(exponents)?)? {
+            Expr::Num(r) => coefficient = coefficient.mul(r)?,
+            Expr::Mul(inner) => {
+                // Ví dụ sqrt(8) → 2*sqrt(2): cần gộp lại với các nhân tử còn lại
+                split_powers = true;
+                for item in inner {
+                    match item {
+                        Expr::Num(r) => coefficient = coefficient.mul(r)?,
+                        other => out.push(other),
+                    }
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    if split_powers && allow_remerge {
+        out.push(Expr::Num(coefficient));
+        return make_mul_inner(out, false);
+    }
+    if coefficient.is_zero() {
+        return Ok(Expr::Num(Rational::ZERO));
+    }
+    out.sort();
+    match (coefficient == Rational::ONE, out.len()) {
+        (_, 0) => Ok(Expr::Num(coefficient)),
+        (true, 1) => Ok(out.remove(0)),
+        (true, _) => Ok(Expr::Mul(out)),
+        // Hệ số nhân một tổng được phân phối vào từng số hạng
+        (false, 1) if matches!(out[0], Expr::Add(_)) => match out.remove(0) {
+            Expr::Add(terms) => make_add(
+                terms
+                    .into_iter()
+                    .map(|t| make_mul(vec![Expr::Num(coefficient), t]))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => unreachable!(),
+        },
+        (false, _) => {
+            out.insert(0, Expr::Num(coefficient));
+            Ok(Expr::Mul(out))
+        }
+    }
+}
+
+fn make_pow(base: Expr, exponent: Expr) -> Result<Expr, ArithmeticError> {
+    let e = match exponent.as_rational() {
+        Some(e) => e,
+        None => {
+            return Ok(match base.as_rational() {
+                Some(b) if b == Rational::ONE => num(1),
+                _ => Expr::Pow(Box::new(base), Box::new(exponent)),
+            })
+        }
+    };
+    if e.is_zero() {
+        return Ok(num(1));
+    }
+    if e == Rational::ONE {
+        return Ok(base);
+    }
+    match base {
+        Expr::Num(b) => rational_pow(b, e),
+        Expr::Pow(inner_base, inner_exp) if e.is_integer() => {
+            make_pow(*inner_base, make_mul(vec![*inner_exp, Expr::Num(e)])?)
+        }
+        Expr::Mul(items) if e.is_integer() => make_mul(
+            items
+                .into_iter()
+                .map(|item| make_pow(item, Expr::Num(e)))
+                .collect::<Result<_, _>>()?,
+        ),
+        other => Ok(Expr::Pow(Box::new(other), Box::new(Expr::Num(e)))),
+    }
+}
+
+/// `b^e` chính xác khi có thể; căn bậc hai được đưa về dạng `a*sqrt(m)` với `m` không
+/// chứa thừa số chính phương.
+fn rational_pow(b: Rational, e: Rational) -> Result<Expr, ArithmeticError> {
+    if e.is_integer() {
+        return Ok(Expr::Num(b.pow(e.num)?));
+    }
+    if b.is_zero() {
+        return if e.is_negative() {
+            Err(error("Chia cho 0"))
+        } else {
+            Ok(num(0))
+        };
+    }
+    if b == Rational::ONE {
+        return Ok(num(1));
+    }
+    let symbolic = || Expr::Pow(Box::new(Expr::Num(b)), Box::new(Expr::Num(e)));
+    let q = u32::try_from(e.den).map_err(|_| overflow())?;
+    if let Some(root) = b.exact_root(q) {
+        return Ok(Expr::Num(root.pow(e.num)?));
+    }
+    if q != 2 || b.is_negative() {
+        return Ok(symbolic());
+    }
+    // b^(p/2) = b^k * sqrt(b) với p = 2k + 1; sqrt(n/d) = sqrt(n*d)/d = s/d * sqrt(m)
+    let k = e.num.div_euclid(2);
+    let radicand = b.num.checked_mul(b.den).ok_or_else(overflow)?;
+    let (square, rest) = square_part(radicand)?;
+    let coefficient = b.pow(k)?.mul(Rational::new(square, b.den)?)?;
+    let sqrt_rest = Expr::Pow(Box::new(num(rest)), Box::new(half()));
+    Ok(if coefficient == Rational::ONE {
+        sqrt_rest
+    } else {
+        Expr::Mul(vec![Expr::Num(coefficient), sqrt_rest])
+    })
+}
+
+/// Tách `n = s^2 * m` bằng phép chia thử (các thừa số lớn được giữ trong `m`).
+fn square_part(mut n: i128) -> Result<(i128, i128), ArithmeticError> {
+    let (mut square, mut rest) = (1, 1);
+    let mut p = 2;
+    while p * p <= n && p <= 1_000_000 {
+        sandbox::consume(1)?;
+        let mut count = 0;
+        while n % p == 0 {
+            n /= p;
+            count += 1;
+        }
+        square *= p.pow(count / 2);
+        rest *= p.pow(count % 2);
+        p += 1;
+    }
+    Ok((square, rest * n))
+}
+
+fn make_func(func: Func, arg: Expr) -> Result<Expr, ArithmeticError> {
+    let value = arg.as_rational();
+    Ok(match (func, &arg) {
+        (Func::Sin | Func::Tan, _) if value == Some(Rational::ZERO) => num(0),
+        (Func::Cos | Func::Exp, _) if value == Some(Rational::ZERO) => num(1),
+        (Func::Ln, _) if value == Some(Rational::ONE) => num(0),
+        (Func::Ln, _) if value.is_some_and(|v| v <= Rational::ZERO) => {
+            return Err(error("Logarit của số không dương"))
+        }
+        (Func::Ln, Expr::Sym(s)) if &**s == "e" => num(1),
+        (Func::Abs, Expr::Num(r)) => Expr::Num(r.abs()),
+        (Func::Exp, Expr::Func(Func::Ln, inner)) | (Func::Ln, Expr::Func(Func::Exp, inner)) => {
+            (**inner).clone()
+        }
+        _ => Expr::Func(func, Box::new(arg)),
+    })
+}
+
+// ===== EXPANSION & POLYNOMIALS =====
+
+fn terms_of(expr: Expr) -> Vec<Expr> {
+    match expr {
+        Expr::Add(terms) => terms,
+        other => vec![other],
+    }
+}
+
+/// Nhân phân phối các nhân tử đã khai triển, gộp số hạng sau mỗi bước.
+fn distribute(factors: Vec<Expr>) -> Result<Expr, ArithmeticError> {
+    let mut acc = vec![num(1)];
+    // Khoản bộ nhớ của `acc` sống cùng nó và được tính lại sau mỗi bước
+    let mut acc_memory = sandbox::allocate(terms_size(&acc))?;
+    for factor in factors {
+        let terms = terms_of(factor);
+        if acc.len() * terms.len() > MAX_EXPANDED_TERMS {
+            return Err(error("Khai triển quá nhiều số hạng"));
+        }
+        let terms_memory = sandbox::allocate(terms_size(&terms))?;
+        // Các tích chưa gộp sống song song với `acc` và `terms` cho tới khi gộp xong
+        let products_memory =
+            sandbox::allocate(terms_size(&acc) * terms.len() + terms_size(&terms) * acc.len())?;
+        let mut products = Vec::with_capacity(acc.len() * terms.len());
+        for a in &acc {
+            for t in &terms {
+                products.push(make_mul(vec![a.clone(), t.clone()])?);
+            }
+        }
+        acc = terms_of(make_add(products)?);
+        drop((products_memory, terms_memory, acc_memory));
+        acc_memory = sandbox::allocate(terms_size(&acc))?;
+    }
+    make_add(acc)
+}
+
+/// Ước lượng bộ nhớ (byte) của các số hạng để kế toán theo `memory_limit`.
+fn terms_size(terms: &[Expr]) -> usize {
+    fn nodes(expr: &Expr) -> usize {
+        match expr {
+            Expr::Num(_) | Expr::Sym(_) => 1,
+            Expr::Add(items) | Expr::Mul(items) => 1 + items.iter().map(nodes).sum::<usize>(),
+            Expr::Pow(b, e) => 1 + nodes(b) + nodes(e),
+            Expr::Func(_, a) => 1 + nodes(a),
+        }
+    }
+    terms.iter().map(nodes).sum::<usize>() * size_of::<Expr>()
+}
+
+fn expand_node(expr: &Expr) -> Result<Expr, ArithmeticError> {
+    match expr {
+        Expr::Num(_) | Expr::Sym(_) => Ok(expr.clone()),
+        Expr::Add(terms) => make_add(terms.iter().map(expand_node).collect::<Result<_, _>>()?),
+        Expr::Mul(factors) => {
+            distribute(factors.iter().map(expand_node).collect::<Result<_, _>>()?)
+        }
+        Expr::Pow(b, e) => {
+            let base = expand_node(b)?;
+            match e.as_rational() {
+                Some(n) if n.is_integer() && n.num > 1 && matches!(base, Expr::Add(_)) => {
+                    if n.num > MAX_POLY_DEGREE {
+                        return Err(error(format!("Số mũ {} quá lớn để khai triển", n)));
+                    }
+                    distribute(vec![base; n.num as usize])
+                }
+                _ => make_pow(base, expand_node(e)?),
+            }
+        }
+        Expr::Func(f, a) => make_func(*f, expand_node(a)?),
+    }
+}
+
+/// Bậc của `var` trong một nhân tử dạng `var` hoặc `var^n`.
+fn var_power(factor: &Expr, var: &str) -> Option<i128> {
+    match factor {
+        Expr::Sym(s) if &**s == var => Some(1),
+        Expr::Pow(b, e) => match (&**b, e.as_rational()) {
+            (Expr::Sym(s), Some(n)) if &**s == var && n.is_integer() && n.num > 0 => Some(n.num),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Hệ số theo bậc của `var` (chỉ số = bậc, đã bỏ các hệ số 0 ở bậc cao);
+/// `None` nếu biểu thức không phải đa thức theo `var`.
+fn polynomial_coefficients(expr: &Expr, var: &str) -> Result<Option<Vec<Expr>>, ArithmeticError> {
+    let mut by_degree: Vec<Vec<Expr>> = Vec::new();
+    for term in terms_of(expr.expand()?) {
+        let factors = match term {
+            Expr::Mul(items) => items,
+            other => vec![other],
+        };
+        let mut power = 0;
+        let mut coefficient = Vec::new();
+        for factor in factors {
+            match var_power(&factor, var) {
+                Some(d) => power += d,
+                None if !factor.contains(var) => coefficient.push(factor),
+                None => return Ok(None),
+            }
+        }
+        if power > MAX_POLY_DEGREE {
+            return Ok(None);
+        }
+        let power = power as usize;
+        if by_degree.len() <= power {
+            by_degree.resize(power + 1, Vec::new());
+        }
+        by_degree[power].push(make_mul(coefficient)?);
+    }
+    let mut coefficients = by_degree
+        .into_iter()
+        .map(make_add)
+        .collect::<Result<Vec<_>, _>>()?;
+    while coefficients.last().is_some_and(Expr::is_zero) {
+        coefficients.pop();
+    }
+    Ok(Some(coefficients))
+}
+
+fn rational_coefficients(coefficients: &[Expr]) -> Option<Vec<Rational>> {
+    coefficients.iter().map(Expr::as_rational).collect()
+}
+
+/// `sum c_i * x^i` với hệ số nguyên (thứ tự tăng dần theo bậc).
+fn integer_polynomial(coefficients: &[i128], x: &Expr) -> Result<Expr, ArithmeticError> {
+    let terms = coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c != 0)
+        .map(|(i, c)| make_mul(vec![num(*c), make_pow(x.clone(), num(i as i128))?]))
+        .collect::<Result<_, _>>()?;
+    make_add(terms)
+}
+
+struct RationalFactorization {
+    content: Rational,
+    /// Nghiệm hữu tỉ và bội; nghiệm `p/q` ứng với nhân tử `q*x - p`
+    roots: Vec<(Rational, usize)>,
+    /// Phần còn lại (nguyên, nguyên bản), không còn nghiệm hữu tỉ
+    rest: Vec<i128>,
+}
+
+fn divisors(n: i128) -> Result<Option<Vec<i128>>, ArithmeticError> {
+    let n = n.abs();
+    if n == 0 || n > MAX_DIVISOR_SEARCH {
+        return Ok(None);
+    }
+    let mut out = Vec::new();
+    let mut d = 1;
+    while d * d <= n {
+        sandbox::consume(1)?;
+        if n % d == 0 {
+            out.push(d);
+            if d * d != n {
+                out.push(n / d);
+            }
+        }
+        d += 1;
+    }
+    Ok(Some(out))
+}
+
+fn evaluate_integer_polynomial(
+    coefficients: &[i128],
+    x: Rational,
+) -> Result<Rational, ArithmeticError> {
+    coefficients
+        .iter()
+        .rev()
+        .try_fold(Rational::ZERO, |acc, c| acc.mul(x)?.add(Rational::int(*c)))
+}
+
+/// Chia `A(x)` cho `q*x - p` (chia hết vì `p/q` là nghiệm).
+fn divide_linear(coefficients: &[i128], p: i128, q: i128) -> Result<Vec<i128>, ArithmeticError> {
+    let n = coefficients.len() - 1;
+    let mut quotient = vec![0; n];
+    // A_n = q*b_{n-1}; A_k = q*b_{k-1} - p*b_k
+    quotient[n - 1] = coefficients[n] / q;
+    for k in (1..n).rev() {
+        let numerator = p
+            .checked_mul(quotient[k])
+            .and_then(|v| v.checked_add(coefficients[k]))
+            .ok_or_else(overflow)?;
+        quotient[k - 1] = numerator / q;
+    }
+    Ok(quotient)
+}
+
+fn factor_rational_roots(
+    coefficients: &[Rational],
+) -> Result<RationalFactorization, ArithmeticError> {
+    // Đưa về đa thức nguyên nguyên bản: A(x) = content * P(x), hệ số cao nhất của P dương
+    let lcm = coefficients.iter().try_fold(1i128, |acc, c| {
+        (acc / gcd(acc, c.den))
+            .checked_mul(c.den)
+            .ok_or_else(overflow)
+    })?;
+    let integers = coefficients
+        .iter()
+        .map(|c| c.mul(Rational::int(lcm)).map(|v| v.num))
+        .collect::<Result<Vec<_>, _>>()?;
+    let g = integers.iter().fold(0, |acc, c| gcd(acc, *c)).max(1);
+    let sign = integers.last().map_or(1, |c| c.signum());
+    let mut poly: Vec<i128> = integers.iter().map(|c| c / (g * sign)).collect();
+    let content = Rational::new(g * sign, lcm)?;
+
+    let mut roots = Vec::new();
+    let zeros = poly.iter().take_while(|c| **c == 0).count();
+    if zeros > 0 {
+        roots.push((Rational::ZERO, zeros));
+        poly.drain(..zeros);
+    }
+    if poly.len() > 1 {
+        if let (Some(ps), Some(qs)) = (divisors(poly[0])?, divisors(poly[poly.len() - 1])?) {
+            let _memory = sandbox::allocate(
+                (ps.capacity() + qs.capacity()) * size_of::<i128>()
+                    + 2 * ps.len() * qs.len() * size_of::<Rational>(),
+            )?;
+            let mut candidates = BTreeSet::new();
+            for p in &ps {
+                for q in &qs {
+                    candidates.insert(Rational::new(*p, *q)?);
+                    candidates.insert(Rational::new(-*p, *q)?);
+                }
+            }
+            for root in candidates {
+                sandbox::consume(poly.len() as u64)?;
+                let mut multiplicity = 0;
+                while poly.len() > 1 && evaluate_integer_polynomial(&poly, root)?.is_zero() {
+                    poly = divide_linear(&poly, root.num, root.den)?;
+                    multiplicity += 1;
+                }
+                if multiplicity > 0 {
+                    roots.push((root, multiplicity));
+                }
+            }
+        }
+    }
+    Ok(RationalFactorization {
+        content,
+        roots,
+        rest: poly,
+    })
+}
+
+// ===== DIFFERENTIATION =====
+
+fn derivative(expr: &Expr, var: &str) -> Result<Expr, ArithmeticError> {
+    if !expr.contains(var) {
+        return Ok(num(0));
+    }
+    match expr {
+        Expr::Num(_) => Ok(num(0)),
+        Expr::Sym(_) => Ok(num(1)),
+        Expr::Add(terms) => make_add(
+            terms
+                .iter()
+                .map(|t| derivative(t, var))
+                .collect::<Result<_, _>>()?,
+        ),
+        Expr::Mul(factors) => {
+            // Quy tắc tích
+            let mut terms = Vec::new();
+            for i in 0..factors.len() {
+                let d = derivative(&factors[i], var)?;
+                if d.is_zero() {
+                    continue;
+                }
+                let mut product = factors.clone();
+                product[i] = d;
+                terms.push(make_mul(product)?);
+            }
+            make_add(terms)
+        }
+        Expr::Pow(b, e) => {
+            let (base, exponent) = ((**b).clone(), (**e).clone());
+            if !exponent.contains(var) {
+                // d(u^n) = n * u^(n-1) * u'
+                let reduced = make_pow(base.clone(), make_add(vec![exponent.clone(), num(-1)])?)?;
+                make_mul(vec![exponent, reduced, derivative(&base, var)?])
+            } else if !base.contains(var) {
+                // d(a^v) = a^v * ln(a) * v'
+                make_mul(vec![
+                    expr.clone(),
+                    make_func(Func::Ln, base)?,
+                    derivative(&exponent, var)?,
+                ])
+            } else {
+                // d(u^v) = u^v * (v' * ln(u) + v * u' / u)
+                let inner = make_add(vec![
+                    make_mul(vec![
+                        derivative(&exponent, var)?,
+                        make_func(Func::Ln, base.clone())?,
+                    ])?,
+                    make_mul(vec![
+                        exponent,
+                        derivative(&base, var)?,
+                        make_pow(base, num(-1))?,
+                    ])?,
+                ])?;
+                make_mul(vec![expr.clone(), inner])
+            }
+        }
+        Expr::Func(func, a) => {
+            let arg = (**a).clone();
+            let outer = match func {
+                Func::Sin => make_func(Func::Cos, arg.clone())?,
+                Func::Cos => make_mul(vec![num(-1), make_func(Func::Sin, arg.clone())?])?,
+                Func::Tan => make_pow(make_func(Func::Cos, arg.clone())?, num(-2))?,
+                Func::Exp => expr.clone(),
+                Func::Ln => make_pow(arg.clone(), num(-1))?,
+                Func::Abs => make_mul(vec![
+                    arg.clone(),
+                    make_pow(make_func(Func::Abs, arg.clone())?, num(-1))?,
+                ])?,
+            };
+            make_mul(vec![outer, derivative(&arg, var)?])
+        }
+    }
+}
+
+// ===== SOLVING =====
+
+/// Giải `lhs = rhs` theo `var`; trả về các nghiệm thực phân biệt.
+pub fn solve(lhs: &Expr, rhs: &Expr, var: &str) -> Result<Vec<Expr>, ArithmeticError> {
+    let difference = make_add(vec![
+        lhs.simplify()?,
+        make_mul(vec![num(-1), rhs.simplify()?])?,
+    ])?;
+    let coefficients = polynomial_coefficients(&difference, var)?
+        .ok_or_else(|| error(format!("Không phải phương trình đa thức theo {}", var)))?;
+    let mut roots = solve_polynomial(coefficients)?;
+    roots.sort_by(|a, b| match (a.numeric(), b.numeric()) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => a.cmp(b),
+    });
+    roots.dedup();
+    Ok(roots)
+}
+
+fn solve_polynomial(coefficients: Vec<Expr>) -> Result<Vec<Expr>, ArithmeticError> {
+    match coefficients.len() {
+        0 => Err(error("Phương trình đúng với mọi giá trị của biến")),
+        // Hằng số khác 0 = 0: vô nghiệm
+        1 => Ok(Vec::new()),
+        2 => {
+            let root = make_mul(vec![
+                num(-1),
+                coefficients[0].clone(),
+                make_pow(coefficients[1].clone(), num(-1))?,
+            ])?;
+            Ok(vec![root])
+        }
+        3 => solve_quadratic(&coefficients[2], &coefficients[1], &coefficients[0]),
+        degree => {
+            let rational = rational_coefficients(&coefficients).ok_or_else(|| {
+                error(format!("Chỉ giải được bậc {} với hệ số hữu tỉ", degree - 1))
+            })?;
+            let factorization = factor_rational_roots(&rational)?;
+            let mut roots: Vec<Expr> = factorization
+                .roots
+                .iter()
+                .map(|(root, _)| Expr::Num(*root))
+                .collect();
+            match factorization.rest.len() {
+                0 | 1 => {}
+                2 | 3 => roots.extend(solve_polynomial(
+                    factorization.rest.iter().map(|c| num(*c)).collect(),
+                )?),
+                n => {
+                    return Err(error(format!(
+                        "Không giải chính xác được phần bậc {} không có nghiệm hữu tỉ",
+                        n - 1
+                    )))
+                }
+            }
+            Ok(roots)
+        }
+    }
+}
+
+/// `a*x^2 + b*x + c = 0` bằng công thức nghiệm; biệt thức âm (dạng số) cho vô nghiệm thực.
+fn solve_quadratic(a: &Expr, b: &Expr, c: &Expr) -> Result<Vec<Expr>, ArithmeticError> {
+    let discriminant = make_add(vec![
+        make_pow(b.clone(), num(2))?,
+        make_mul(vec![num(-4), a.clone(), c.clone()])?,
+    ])?;
+    let minus_b = make_mul(vec![num(-1), b.clone()])?;
+    let inverse_2a = make_pow(make_mul(vec![num(2), a.clone()])?, num(-1))?;
+    match discriminant.as_rational() {
+        Some(d) if d.is_negative() => return Ok(Vec::new()),
+        Some(d) if d.is_zero() => return Ok(vec![make_mul(vec![minus_b, inverse_2a])?]),
+        _ => {}
+    }
+    let sqrt_d = make_pow(discriminant, half())?;
+    [num(1), num(-1)]
+        .into_iter()
+        .map(|sign| {
+            let numerator = make_add(vec![minus_b.clone(), make_mul(vec![sign, sqrt_d.clone()])?])?;
+            make_mul(vec![numerator, inverse_2a.clone()])
+        })
+        .collect()
+}
+
+// ===== PARSER =====
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(Rational),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+    Equals,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ArithmeticError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // Số mũ khoa học chỉ khi theo sau là chữ số: `2e3` nhưng `2e` là 2*e
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                    if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                        i += 1 + sign;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Num(Rational::parse_decimal(&text)?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                tokens.push(Token::Op('^'));
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' | '^' | '\u{2212}' => {
+                tokens.push(Token::Op(if c == '\u{2212}' { '-' } else { c }));
+                i += 1;
+            }
+            '(' | ')' | ',' | '=' => {
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    ',' => Token::Comma,
+                    _ => Token::Equals,
+                });
+                i += 1;
+            }
+            other => return Err(error(format!("Ký tự không hợp lệ '{}'", other))),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Lệnh đã parse từ chuỗi đầu vào.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Simplify(Expr),
+    Expand(Expr),
+    Factor(Expr),
+    Diff {
+        expr: Expr,
+        var: Option<String>,
+        order: usize,
+    },
+    Solve {
+        lhs: Expr,
+        rhs: Expr,
+        var: Option<String>,
+    },
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+type Equation = (Expr, Option<Expr>);
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ArithmeticError> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            other => Err(error(format!("Mong đợi {:?}, gặp {:?}", token, other))),
+        }
+    }
+
+    fn command(&mut self) -> Result<Command, ArithmeticError> {
+        let name = match (self.tokens.first(), self.tokens.get(1)) {
+            (Some(Token::Ident(name)), Some(Token::LParen))
+                if COMMANDS.contains(&name.as_str()) =>
+            {
+                name.clone()
+            }
+            _ => {
+                let (lhs, rhs) = self.equation()?;
+                self.end()?;
+                return Ok(match rhs {
+                    Some(rhs) => Command::Solve {
+                        lhs,
+                        rhs,
+                        var: None,
+                    },
+                    None => Command::Simplify(lhs),
+                });
+            }
+        };
+        self.pos = 2;
+        let mut args = vec![self.equation()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            args.push(self.equation()?);
+        }
+        self.expect(Token::RParen)?;
+        self.end()?;
+
+        let mut args = args.into_iter();
+        let first = args.next();
+        let rest: Vec<Equation> = args.collect();
+        let single = |arg: Option<Equation>| match arg {
+            Some((expr, None)) => Ok(expr),
+            _ => Err(error(format!(
+                "{} cần một biểu thức, không phải phương trình",
+                name
+            ))),
+        };
+        let variable = |arg: Option<&Equation>| match arg {
+            None => Ok(None),
+            Some((Expr::Sym(s), None)) if !CONSTANTS.contains(&&**s) => Ok(Some(s.to_string())),
+            Some(_) => Err(error(format!("Đối số biến của {} không hợp lệ", name))),
+        };
+        match name.as_str() {
+            "simplify" | "expand" | "factor" if rest.is_empty() => {
+                let expr = single(first)?;
+                Ok(match name.as_str() {
+                    "simplify" => Command::Simplify(expr),
+                    "expand" => Command::Expand(expr),
+                    _ => Command::Factor(expr),
+                })
+            }
+            "diff" | "derivative" if rest.len() <= 2 => {
+                let order = match rest.get(1) {
+                    None => 1,
+                    Some((Expr::Num(n), None))
+                        if n.is_integer() && (1..=MAX_DIFF_ORDER as i128).contains(&n.num) =>
+                    {
+                        n.num as usize
+                    }
+                    Some(_) => {
+                        return Err(error(format!(
+                            "Cấp đạo hàm phải là số nguyên 1..={}",
+                            MAX_DIFF_ORDER
+                        )))
+                    }
+                };
+                Ok(Command::Diff {
+                    expr: single(first)?,
+                    var: variable(rest.first())?,
+                    order,
+                })
+            }
+            "solve" if rest.len() <= 1 => {
+                let (lhs, rhs) = first.ok_or_else(|| error("solve cần một phương trình"))?;
+                Ok(Command::Solve {
+                    lhs,
+                    rhs: rhs.unwrap_or_else(|| num(0)),
+                    var: variable(rest.first())?,
+                })
+            }
+            _ => Err(error(format!("Sai số đối số cho {}", name))),
+        }
+    }
+
+    fn end(&self) -> Result<(), ArithmeticError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(error(format!("Token thừa {:?}", token))),
+        }
+    }
+
+    fn equation(&mut self) -> Result<Equation, ArithmeticError> {
+        let lhs = self.expression()?;
+        if self.peek() == Some(&Token::Equals) {
+            self.pos += 1;
+            return Ok((lhs, Some(self.expression()?)));
+        }
+        Ok((lhs, None))
+    }
+
+    fn expression(&mut self) -> Result<Expr, ArithmeticError> {
+        let mut terms = vec![self.term()?];
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let term = self.term()?;
+            terms.push(if op == '-' { negate(term) } else { term });
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Add(terms)
+        })
+    }
+
+    fn term(&mut self) -> Result<Expr, ArithmeticError> {
+        let mut factors = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::Op('*')) => {
+                    self.pos += 1;
+                    factors.push(self.unary()?);
+                }
+                Some(Token::Op('/')) => {
+                    self.pos += 1;
+                    let divisor = self.unary()?;
+                    factors.push(Expr::Pow(Box::new(divisor), Box::new(num(-1))));
+                }
+                // Nhân ngầm định: `2x`, `3(x + 1)`
+                Some(Token::Num(_) | Token::Ident(_) | Token::LParen) => {
+                    factors.push(self.power()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(if factors.len() == 1 {
+            factors.remove(0)
+        } else {
+            Expr::Mul(factors)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Expr, ArithmeticError> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                Ok(negate(self.unary()?))
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, ArithmeticError> {
+        let base = self.primary()?;
+        if self.peek() == Some(&Token::Op('^')) {
+            self.pos += 1;
+            // Lũy thừa kết hợp phải và cho phép số mũ âm: 2^-1
+            let exponent = self.unary()?;
+            return Ok(Expr::Pow(Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ArithmeticError> {
+        sandbox::consume(1)?;
+        match self.next() {
+            Some(Token::Num(r)) => Ok(Expr::Num(r)),
+            Some(Token::LParen) => {
+                let inner = self.expression()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let arg = self.expression()?;
+                self.expect(Token::RParen)?;
+                match name.as_str() {
+                    "sqrt" => Ok(Expr::Pow(Box::new(arg), Box::new(half()))),
+                    // log thập phân như fasteval: ln(x)/ln(10)
+                    "log" => Ok(Expr::Mul(vec![
+                        Expr::Func(Func::Ln, Box::new(arg)),
+                        Expr::Pow(
+                            Box::new(Expr::Func(Func::Ln, Box::new(num(10)))),
+                            Box::new(num(-1)),
+                        ),
+                    ])),
+                    _ if COMMANDS.contains(&name.as_str()) => {
+                        Err(error(format!("Lệnh {} chỉ dùng ở mức ngoài cùng", name)))
+                    }
+                    _ => Func::from_name(&name)
+                        .map(|f| Expr::Func(f, Box::new(arg)))
+                        .ok_or_else(|| error(format!("Hàm không hỗ trợ: {}", name))),
+                }
+            }
+            Some(Token::Ident(name)) => Ok(Expr::symbol(&name)),
+            other => Err(error(format!("Mong đợi biểu thức, gặp {:?}", other))),
+        }
+    }
+}
+
+fn negate(expr: Expr) -> Expr {
+    match expr {
+        Expr::Num(r) => Expr::Num(r.neg()),
+        other => Expr::Mul(vec![num(-1), other]),
+    }
+}
+
+pub fn parse(input: &str) -> Result<Command, ArithmeticError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(error("Biểu thức rỗng"));
+    }
+    Parser { tokens, pos: 0 }.command()
+}
+
+// ===== RESULTS =====
+
+/// Một giá trị ở dạng chính xác kèm giá trị số (nếu tính được).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExactValue {
+    pub exact: Expr,
+    pub numeric: Option<f64>,
+}
+
+impl ExactValue {
+    pub fn new(exact: Expr) -> Self {
+        let numeric = exact.numeric();
+        Self { exact, numeric }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SymbolicResult {
+    Value(ExactValue),
+    /// Các nghiệm thực phân biệt; rỗng khi vô nghiệm
+    Solutions {
+        variable: String,
+        roots: Vec<ExactValue>,
+    },
+}
+
+impl SymbolicResult {
+    /// Giá trị số duy nhất của kết quả (một giá trị hoặc đúng một nghiệm).
+    pub fn numeric(&self) -> Option<f64> {
+        match self {
+            SymbolicResult::Value(value) => value.numeric,
+            SymbolicResult::Solutions { roots, .. } => match roots.as_slice() {
+                [root] => root.numeric,
+                _ => None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for SymbolicResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolicResult::Value(value) => write!(f, "{}", value.exact),
+            SymbolicResult::Solutions { variable, roots } if roots.is_empty() => {
+                write!(f, "{} vô nghiệm thực", variable)
+            }
+            SymbolicResult::Solutions { variable, roots } => {
+                let parts: Vec<String> = roots
+                    .iter()
+                    .map(|r| format!("{} = {}", variable, r.exact))
+                    .collect();
+                write!(f, "{}", parts.join(", "))
+            }
+        }
+    }
+}
+
+fn only_symbol(exprs: &[&Expr]) -> Result<String, ArithmeticError> {
+    let mut symbols = BTreeSet::new();
+    for expr in exprs {
+        symbols.extend(expr.free_symbols());
+    }
+    match symbols.len() {
+        1 => Ok(symbols.into_iter().next().unwrap_or_default()),
+        0 => Err(error("Biểu thức không chứa biến")),
+        _ => Err(error(format!(
+            "Cần chỉ rõ biến trong {:?}",
+            symbols.into_iter().collect::<Vec<_>>()
+        ))),
+    }
+}
+
+/// Parse và thực thi một lệnh ký hiệu.
+pub fn evaluate(input: &str) -> Result<SymbolicResult, ArithmeticError> {
+    let value = |expr: Expr| Ok(SymbolicResult::Value(ExactValue::new(expr)));
+    match parse(input)? {
+        Command::Simplify(expr) => value(expr.simplify()?),
+        Command::Expand(expr) => value(expr.expand()?),
+        Command::Factor(expr) => value(expr.factor()?),
+        Command::Diff { expr, var, order } => {
+            let var = match var {
+                Some(var) => var,
+                None => only_symbol(&[&expr])?,
+            };
+            let mut result = expr.simplify()?;
+            for _ in 0..order {
+                result = derivative(&result, &var)?;
+            }
+            value(result)
+        }
+        Command::Solve { lhs, rhs, var } => {
+            let variable = match var {
+                Some(var) => var,
+                None => only_symbol(&[&lhs, &rhs])?,
+            };
+            let roots = solve(&lhs, &rhs, &variable)?
+                .into_iter()
+                .map(ExactValue::new)
+                .collect();
+            Ok(SymbolicResult::Solutions { variable, roots })
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn exact(input: &str) -> String {
+        evaluate(input).unwrap().to_string()
+    }
+
+    fn roots(input: &str) -> Vec<String> {
+        match evaluate(input).unwrap() {
+            SymbolicResult::Solutions { roots, .. } => {
+                roots.iter().map(|r| r.exact.to_string()).collect()
+            }
+            other => panic!("không phải nghiệm: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_exact_rational_arithmetic() {
+        assert_eq!(exact("1/3 + 1/6"), "1/2");
+        assert_eq!(exact("0.1 + 0.2"), "3/10");
+        assert_eq!(exact("2^-2 * 3"), "3/4");
+        assert_eq!(exact("sqrt(8)"), "2*sqrt(2)");
+        assert_eq!(exact("sqrt(16/9)"), "4/3");
+        assert_eq!(exact("sqrt(2) * sqrt(8)"), "4");
+        assert_eq!(exact("2.5e-1"), "1/4");
+        let result = evaluate("1/3").unwrap();
+        assert!((result.numeric().unwrap() - 1.0 / 3.0).abs() < 1e-15);
+        assert!(
+            (evaluate("2*pi").unwrap().numeric().unwrap() - std::f64::consts::TAU).abs() < 1e-12
+        );
+        assert!(evaluate("1/0").is_err());
+        assert!(evaluate("ln(0)").is_err());
+    }
+
+    #[test]
+    fn test_simplification_collects_terms_and_powers() {
+        assert_eq!(exact("x + x + 2*x"), "4*x");
+        assert_eq!(exact("x*x*x/x"), "x^2");
+        assert_eq!(exact("(x + 1) - (x + 1)"), "0");
+        assert_eq!(exact("3x - 2(x - y)"), "x + 2*y");
+        assert_eq!(exact("x/(2*y)"), "x/(2*y)");
+        assert_eq!(exact("exp(ln(a))"), "a");
+        assert_eq!(evaluate("2*x").unwrap().numeric(), None);
+    }
+
+    #[test]
+    fn test_differentiation() {
+        assert_eq!(exact("diff(x^3 + 2*x, x)"), "3*x^2 + 2");
+        assert_eq!(exact("diff(x*sin(x))"), "x*cos(x) + sin(x)");
+        assert_eq!(exact("diff(exp(2*x), x)"), "2*exp(2*x)");
+        assert_eq!(exact("diff(ln(x), x)"), "1/x");
+        assert_eq!(exact("diff(x^4, x, 2)"), "12*x^2");
+        assert_eq!(exact("diff(a*x^2 + b, a)"), "x^2");
+        assert!(evaluate("diff(x*y)").is_err());
+    }
+
+    #[test]
+    fn test_expand_and_factor() {
+        assert_eq!(exact("expand((x + 1)^2)"), "x^2 + 2*x + 1");
+        assert_eq!(exact("expand((x - y)*(x + y))"), "x^2 - y^2");
+        assert_eq!(exact("factor(x^2 - 3*x + 2)"), "(x - 2)*(x - 1)");
+        assert_eq!(exact("factor(2*x^3 - 2*x)"), "2*x*(x - 1)*(x + 1)");
+        assert_eq!(exact("factor(6*x^2 - 5*x + 1)"), "(2*x - 1)*(3*x - 1)");
+        assert_eq!(exact("factor(x^3 - 3*x^2 + 3*x - 1)"), "(x - 1)^3");
+        assert_eq!(exact("factor(x^2 + 1)"), "x^2 + 1");
+        // factor rồi expand phải về đúng đa thức ban đầu
+        let factored = exact("factor(x^4 - 5*x^2 + 4)");
+        assert_eq!(exact(&format!("expand({})", factored)), "x^4 - 5*x^2 + 4");
+    }
+
+    #[test]
+    fn test_solve_linear_and_quadratic() {
+        assert_eq!(roots("2*x + 3 = 7"), vec!["2"]);
+        assert_eq!(roots("solve(x^2 - 5*x + 6, x)"), vec!["2", "3"]);
+        assert_eq!(roots("x^2 = 2"), vec!["-sqrt(2)", "sqrt(2)"]);
+        assert_eq!(
+            roots("x^2 - x - 1 = 0"),
+            vec!["-sqrt(5)/2 + 1/2", "sqrt(5)/2 + 1/2"]
+        );
+        assert!(roots("x^2 + 1 = 0").is_empty());
+        assert_eq!(roots("x^3 - 6*x^2 + 11*x - 6 = 0"), vec!["1", "2", "3"]);
+        assert_eq!(roots("solve(a*x + b = 0, x)"), vec!["-b/a"]);
+        match evaluate("x^2 = 2").unwrap() {
+            SymbolicResult::Solutions { roots, .. } => {
+                assert!((roots[1].numeric.unwrap() - 2f64.sqrt()).abs() < 1e-12);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(evaluate("solve(sin(x) = 0, x)").is_err());
+        assert!(evaluate("x = x").is_err());
+        assert!(evaluate("solve(x + y = 1)").is_err());
+    }
+}
@@ -0,0 +1,775 @@
+//! Phân tích thứ nguyên: tính toán trên đại lượng có đơn vị, ví dụ
+//! `3 m/s * 2 h` → `21.6 km` hoặc `100 km/h to m/s`.
+//!
+//! Mọi đại lượng được quy về đơn vị SI cơ bản (`Quantity`); cộng/trừ hay đổi đơn vị giữa
+//! hai thứ nguyên khác nhau trả về `ArithmeticError::DimensionMismatch`. Phép nhân ngầm định
+//! (`3 m`, `1 kg m`) có độ ưu tiên cao hơn `*` và `/`, nên `6 m / 2 s` là `3 m/s`.
+//!
+//! Một định danh chỉ là đơn vị khi đứng ngay sau một số (`3 m`), sau một đơn vị khác
+//! (`kg m`, `m/s`, `m*s`) hoặc sau `to`/`in`/`->`, và không bao giờ khi theo sau là `(`:
+//! `min(3, 4)` là hàm, còn `2*s` không phải là hai giây.
+
+use super::{sandbox, ArithmeticError};
+use std::fmt;
+
+const BASE_SYMBOLS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+const CONVERSION_KEYWORDS: [&str; 2] = ["to", "in"];
+
+/// Số mũ của 7 đại lượng cơ bản SI: độ dài, khối lượng, thời gian, dòng điện,
+/// nhiệt độ, lượng chất, cường độ sáng.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Dimension(pub [i8; 7]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0; 7]);
+
+    const fn base(index: usize) -> Self {
+        let mut exponents = [0; 7];
+        exponents[index] = 1;
+        Dimension(exponents)
+    }
+
+    const fn of(exponents: [i8; 7]) -> Self {
+        Dimension(exponents)
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Self::NONE
+    }
+
+    /// `self * other^sign`; số mũ vượt `i8` là lỗi chứ không tràn số.
+    fn combine(self, other: Self, sign: i8) -> Result<Self, ArithmeticError> {
+        let mut exponents = self.0;
+        for (e, o) in exponents.iter_mut().zip(other.0) {
+            *e = sign
+                .checked_mul(o)
+                .and_then(|o| e.checked_add(o))
+                .ok_or_else(exponent_overflow)?;
+        }
+        Ok(Dimension(exponents))
+    }
+
+    fn powi(self, n: i8) -> Result<Self, ArithmeticError> {
+        let mut exponents = self.0;
+        for e in exponents.iter_mut() {
+            *e = e.checked_mul(n).ok_or_else(exponent_overflow)?;
+        }
+        Ok(Dimension(exponents))
+    }
+
+    fn sqrt(self) -> Option<Self> {
+        self.0
+            .iter()
+            .all(|e| e % 2 == 0)
+            .then(|| Dimension(self.0.map(|e| e / 2)))
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let render = |parts: Vec<(usize, i16)>| -> String {
+            parts
+                .into_iter()
+                .map(|(i, e)| match e {
+                    1 => BASE_SYMBOLS[i].to_string(),
+                    e => format!("{}^{}", BASE_SYMBOLS[i], e),
+                })
+                .collect::<Vec<_>>()
+                .join("*")
+        };
+        // i16: số mũ -128 đổi dấu vẫn biểu diễn được
+        let numerator: Vec<(usize, i16)> = (0..7)
+            .filter(|i| self.0[*i] > 0)
+            .map(|i| (i, self.0[i].into()))
+            .collect();
+        let denominator: Vec<(usize, i16)> = (0..7)
+            .filter(|i| self.0[*i] < 0)
+            .map(|i| (i, -i16::from(self.0[i])))
+            .collect();
+        match (numerator.is_empty(), denominator.len()) {
+            (true, 0) => write!(f, "1"),
+            (false, 0) => write!(f, "{}", render(numerator)),
+            (true, _) => write!(f, "1/{}", render(denominator)),
+            (false, 1) => write!(f, "{}/{}", render(numerator), render(denominator)),
+            (false, _) => write!(f, "{}/({})", render(numerator), render(denominator)),
+        }
+    }
+}
+
+const LENGTH: Dimension = Dimension::base(0);
+const MASS: Dimension = Dimension::base(1);
+const TIME: Dimension = Dimension::base(2);
+const CURRENT: Dimension = Dimension::base(3);
+const TEMPERATURE: Dimension = Dimension::base(4);
+const AMOUNT: Dimension = Dimension::base(5);
+const LUMINOSITY: Dimension = Dimension::base(6);
+const FORCE: Dimension = Dimension::of([1, 1, -2, 0, 0, 0, 0]);
+const ENERGY: Dimension = Dimension::of([2, 1, -2, 0, 0, 0, 0]);
+const POWER: Dimension = Dimension::of([2, 1, -3, 0, 0, 0, 0]);
+const PRESSURE: Dimension = Dimension::of([-1, 1, -2, 0, 0, 0, 0]);
+const FREQUENCY: Dimension = Dimension::of([0, 0, -1, 0, 0, 0, 0]);
+const CHARGE: Dimension = Dimension::of([0, 0, 1, 1, 0, 0, 0]);
+const VOLTAGE: Dimension = Dimension::of([2, 1, -3, -1, 0, 0, 0]);
+const RESISTANCE: Dimension = Dimension::of([2, 1, -3, -2, 0, 0, 0]);
+const VOLUME: Dimension = Dimension::of([3, 0, 0, 0, 0, 0, 0]);
+
+struct UnitDef {
+    symbol: &'static str,
+    /// Hệ số quy đổi sang đơn vị SI cơ bản
+    factor: f64,
+    dimension: Dimension,
+    prefixable: bool,
+}
+
+const fn unit(
+    symbol: &'static str,
+    factor: f64,
+    dimension: Dimension,
+    prefixable: bool,
+) -> UnitDef {
+    UnitDef {
+        symbol,
+        factor,
+        dimension,
+        prefixable,
+    }
+}
+
+/// Đơn vị có tên; thứ tự cũng là thứ tự ưu tiên khi chọn đơn vị hiển thị.
+const UNITS: &[UnitDef] = &[
+    unit("m", 1.0, LENGTH, true),
+    unit("g", 1e-3, MASS, true),
+    unit("s", 1.0, TIME, true),
+    unit("A", 1.0, CURRENT, true),
+    unit("K", 1.0, TEMPERATURE, true),
+    unit("mol", 1.0, AMOUNT, true),
+    unit("cd", 1.0, LUMINOSITY, true),
+    unit("N", 1.0, FORCE, true),
+    unit("J", 1.0, ENERGY, true),
+    unit("W", 1.0, POWER, true),
+    unit("Pa", 1.0, PRESSURE, true),
+    unit("Hz", 1.0, FREQUENCY, true),
+    unit("C", 1.0, CHARGE, true),
+    unit("V", 1.0, VOLTAGE, true),
+    unit("ohm", 1.0, RESISTANCE, true),
+    unit("Ω", 1.0, RESISTANCE, true),
+    unit("L", 1e-3, VOLUME, true),
+    unit("l", 1e-3, VOLUME, true),
+    unit("eV", 1.602_176_634e-19, ENERGY, true),
+    unit("Wh", 3600.0, ENERGY, true),
+    unit("cal", 4.184, ENERGY, true),
+    unit("bar", 1e5, PRESSURE, true),
+    unit("atm", 101_325.0, PRESSURE, false),
+    unit("min", 60.0, TIME, false),
+    unit("h", 3600.0, TIME, false),
+    unit("day", 86_400.0, TIME, false),
+    unit("inch", 0.0254, LENGTH, false),
+    unit("ft", 0.3048, LENGTH, false),
+    unit("mi", 1609.344, LENGTH, false),
+    unit("lb", 0.453_592_37, MASS, false),
+];
+
+const PREFIXES: &[(&str, f64)] = &[
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+    ("z", 1e-21),
+    ("y", 1e-24),
+];
+
+/// Tra đơn vị (kể cả tiền tố SI như `km`, `ms`, `kWh`): hệ số sang SI và thứ nguyên.
+pub fn lookup_unit(name: &str) -> Option<(f64, Dimension)> {
+    if let Some(def) = UNITS.iter().find(|u| u.symbol == name) {
+        return Some((def.factor, def.dimension));
+    }
+    PREFIXES.iter().find_map(|(prefix, scale)| {
+        let rest = name.strip_prefix(prefix)?;
+        let def = UNITS.iter().find(|u| u.symbol == rest && u.prefixable)?;
+        Some((scale * def.factor, def.dimension))
+    })
+}
+
+/// Có phải tên đơn vị hay từ khóa đổi đơn vị (`to`, `in`) không.
+pub fn is_unit_word(word: &str) -> bool {
+    CONVERSION_KEYWORDS.contains(&word) || lookup_unit(word).is_some()
+}
+
+/// Đại lượng quy về đơn vị SI cơ bản.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+}
+
+impl Quantity {
+    fn scalar(value: f64) -> Self {
+        Self {
+            value,
+            dimension: Dimension::NONE,
+        }
+    }
+
+    fn same_dimension(&self, other: &Quantity) -> Result<(), ArithmeticError> {
+        if self.dimension == other.dimension {
+            Ok(())
+        } else {
+            Err(ArithmeticError::DimensionMismatch {
+                expected: self.dimension.to_string(),
+                found: other.dimension.to_string(),
+            })
+        }
+    }
+}
+
+/// Kết quả đã chọn đơn vị hiển thị.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitValue {
+    pub quantity: Quantity,
+    /// Giá trị theo `unit`
+    pub value: f64,
+    pub unit: String,
+    /// `true` nếu `unit` do người dùng chỉ định (`... to km/h`)
+    pub converted: bool,
+}
+
+impl fmt::Display for UnitValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unit.is_empty() {
+            write!(f, "{}", format_number(self.value))
+        } else {
+            write!(f, "{} {}", format_number(self.value), self.unit)
+        }
+    }
+}
+
+/// Hiển thị 12 chữ số có nghĩa, bỏ nhiễu dấu phẩy động như `0.30000000000000004`.
+fn format_number(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return value.to_string();
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    if !(-6..15).contains(&magnitude) {
+        return format!("{:e}", value);
+    }
+    let decimals = (11 - magnitude).max(0) as usize;
+    let text = format!("{:.*}", decimals, value);
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+/// Chọn đơn vị hiển thị: đơn vị có tên cùng thứ nguyên với tiền tố đưa giá trị về [1, 1000),
+/// nếu không có thì ghép từ đơn vị SI cơ bản.
+fn display_unit(quantity: Quantity) -> (f64, String) {
+    if quantity.dimension.is_dimensionless() {
+        return (quantity.value, String::new());
+    }
+    let Some(def) = UNITS
+        .iter()
+        .find(|u| u.dimension == quantity.dimension && u.prefixable)
+    else {
+        return (quantity.value, quantity.dimension.to_string());
+    };
+    let value = quantity.value / def.factor;
+    if value == 0.0 {
+        return (value, def.symbol.to_string());
+    }
+    let exponent = (value.abs().log10() / 3.0).floor() as i32 * 3;
+    // Giây chỉ dùng tiền tố nhỏ (ms, µs); thời gian dài giữ nguyên đơn vị s
+    let exponent = if def.symbol == "s" {
+        exponent.min(0)
+    } else {
+        exponent
+    };
+    let prefix = PREFIXES
+        .iter()
+        .filter(|(p, _)| !matches!(*p, "h" | "da" | "d" | "c" | "u"))
+        .find(|(_, scale)| (scale.log10().round() as i32) == exponent);
+    match prefix {
+        Some((p, scale)) => (value / scale, format!("{}{}", p, def.symbol)),
+        None => (value, def.symbol.to_string()),
+    }
+}
+
+// ===== PARSER =====
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Word(String),
+    /// Định danh ở vị trí đơn vị (xem `mark_units`)
+    Unit(String),
+    Op(char),
+    Arrow,
+    LParen,
+    RParen,
+}
+
+fn parse_error(message: impl Into<String>) -> ArithmeticError {
+    ArithmeticError::ParseError(message.into())
+}
+
+fn exponent_overflow() -> ArithmeticError {
+    parse_error(format!(
+        "Số mũ thứ nguyên vượt giới hạn [{}, {}]",
+        i8::MIN,
+        i8::MAX
+    ))
+}
+
+/// Token kèm vị trí byte bắt đầu trong chuỗi gốc.
+fn tokenize(input: &str) -> Result<(Vec<Token>, Vec<usize>), ArithmeticError> {
+    let (offsets, chars): (Vec<usize>, Vec<char>) = input.char_indices().unzip();
+    let mut tokens = Vec::new();
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if !c.is_whitespace() {
+            starts.push(offsets[i]);
+        }
+        match c {
+            c if c.is_whitespace() => i += 1,
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                // `1e3` là số mũ khoa học, còn `1 eV` là đơn vị
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+                    if chars.get(i + 1 + sign).is_some_and(char::is_ascii_digit) {
+                        i += 1 + sign;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| parse_error(format!("Số không hợp lệ '{}'", text)))?;
+                tokens.push(Token::Num(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphabetic() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Word(chars[start..i].iter().collect()));
+            }
+            '-' if chars.get(i + 1) == Some(&'>') => {
+                tokens.push(Token::Arrow);
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' | '^' | '\u{2212}' | '·' => {
+                tokens.push(Token::Op(match c {
+                    '\u{2212}' => '-',
+                    '·' => '*',
+                    other => other,
+                }));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            other => return Err(parse_error(format!("Ký tự không hợp lệ '{}'", other))),
+        }
+    }
+    mark_units(&mut tokens);
+    Ok((tokens, starts))
+}
+
+fn is_conversion(token: &Token) -> bool {
+    match token {
+        Token::Arrow => true,
+        Token::Word(w) => CONVERSION_KEYWORDS.contains(&w.as_str()),
+        _ => false,
+    }
+}
+
+/// Đổi `Word` thành `Unit` khi nó là đơn vị đã biết, không theo sau bởi `(` và đứng sau
+/// một số, một đơn vị, `to`/`in`/`->`, hoặc `*`/`/` ngay sau một đơn vị.
+fn mark_units(tokens: &mut [Token]) {
+    for i in 0..tokens.len() {
+        let Token::Word(word) = &tokens[i] else {
+            continue;
+        };
+        if CONVERSION_KEYWORDS.contains(&word.as_str())
+            || tokens.get(i + 1) == Some(&Token::LParen)
+            || lookup_unit(word).is_none()
+        {
+            continue;
+        }
+        let previous = i.checked_sub(1).map(|j| &tokens[j]);
+        let before_previous = i.checked_sub(2).map(|j| &tokens[j]);
+        let in_unit_position = match previous {
+            Some(Token::Num(_) | Token::Unit(_)) => true,
+            Some(Token::Op('*' | '/')) => matches!(before_previous, Some(Token::Unit(_))),
+            Some(token) => is_conversion(token),
+            None => false,
+        };
+        if in_unit_position {
+            tokens[i] = Token::Unit(word.clone());
+        }
+    }
+}
+
+/// Biểu thức có đơn vị: có ít nhất một đơn vị (theo `mark_units`), và mọi định danh còn
+/// lại là `to`/`in` hoặc hàm `sqrt`/`abs`.
+pub fn is_unit_expression(expr: &str) -> bool {
+    let Ok((tokens, _)) = tokenize(expr) else {
+        return false;
+    };
+    let mut has_unit = false;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Unit(_) => has_unit = true,
+            Token::Word(w) if CONVERSION_KEYWORDS.contains(&w.as_str()) => {}
+            Token::Word(w)
+                if matches!(w.as_str(), "sqrt" | "abs")
+                    && tokens.get(i + 1) == Some(&Token::LParen) => {}
+            Token::Word(_) => return false,
+            _ => {}
+        }
+    }
+    has_unit
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn at_conversion(&self) -> bool {
+        self.peek().is_some_and(is_conversion)
+    }
+
+    fn expect_rparen(&mut self) -> Result<(), ArithmeticError> {
+        if self.peek() == Some(&Token::RParen) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(parse_error("Thiếu ')'"))
+        }
+    }
+
+    fn expression(&mut self) -> Result<Quantity, ArithmeticError> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value.same_dimension(&rhs)?;
+            value.value += if op == '+' { rhs.value } else { -rhs.value };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<Quantity, ArithmeticError> {
+        let mut value = self.product()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.product()?;
+            value = if op == '*' {
+                Quantity {
+                    value: value.value * rhs.value,
+                    dimension: value.dimension.combine(rhs.dimension, 1)?,
+                }
+            } else {
+                if rhs.value == 0.0 {
+                    return Err(parse_error("Kết quả không hữu hạn (chia cho 0)"));
+                }
+                Quantity {
+                    value: value.value / rhs.value,
+                    dimension: value.dimension.combine(rhs.dimension, -1)?,
+                }
+            };
+        }
+        Ok(value)
+    }
+
+    /// Phép nhân ngầm định: `3 m`, `1 kg m`, `2 (m/s)`.
+    fn product(&mut self) -> Result<Quantity, ArithmeticError> {
+        let mut value = self.unary()?;
+        while matches!(
+            self.peek(),
+            Some(Token::Num(_) | Token::Word(_) | Token::Unit(_) | Token::LParen)
+        ) && !self.at_conversion()
+        {
+            let rhs = self.power()?;
+            value = Quantity {
+                value: value.value * rhs.value,
+                dimension: value.dimension.combine(rhs.dimension, 1)?,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<Quantity, ArithmeticError> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                let mut value = self.unary()?;
+                value.value = -value.value;
+                Ok(value)
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Quantity, ArithmeticError> {
+        let base = self.primary()?;
+        if self.peek() != Some(&Token::Op('^')) {
+            return Ok(base);
+        }
+        self.pos += 1;
+        let negative = self.peek() == Some(&Token::Op('-'));
+        if negative {
+            self.pos += 1;
+        }
+        let exponent = match self.peek() {
+            Some(Token::Num(n)) if n.fract() == 0.0 && *n <= 8.0 => *n as i8,
+            other => {
+                return Err(parse_error(format!(
+                    "Số mũ của đại lượng phải là số nguyên nhỏ, gặp {:?}",
+                    other
+                )))
+            }
+        };
+        self.pos += 1;
+        let exponent = if negative { -exponent } else { exponent };
+        Ok(Quantity {
+            value: base.value.powi(exponent as i32),
+            dimension: base.dimension.powi(exponent)?,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Quantity, ArithmeticError> {
+        sandbox::consume(1)?;
+        let token = self.peek().cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Num(n)) => Ok(Quantity::scalar(n)),
+            Some(Token::LParen) => {
+                let inner = self.expression()?;
+                self.expect_rparen()?;
+                Ok(inner)
+            }
+            Some(Token::Word(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let arg = self.expression()?;
+                self.expect_rparen()?;
+                match name.as_str() {
+                    "sqrt" => {
+                        let dimension = arg.dimension.sqrt().ok_or_else(|| {
+                            ArithmeticError::DimensionMismatch {
+                                expected: "lũy thừa chẵn".into(),
+                                found: arg.dimension.to_string(),
+                            }
+                        })?;
+                        Ok(Quantity {
+                            value: arg.value.sqrt(),
+                            dimension,
+                        })
+                    }
+                    "abs" => Ok(Quantity {
+                        value: arg.value.abs(),
+                        ..arg
+                    }),
+                    _ => Err(parse_error(format!(
+                        "Hàm không hỗ trợ với đơn vị: {}",
+                        name
+                    ))),
+                }
+            }
+            Some(Token::Unit(name)) => {
+                let (factor, dimension) =
+                    lookup_unit(&name).ok_or(ArithmeticError::UnknownUnit(name))?;
+                Ok(Quantity {
+                    value: factor,
+                    dimension,
+                })
+            }
+            Some(Token::Word(name)) if lookup_unit(&name).is_some() => Err(parse_error(format!(
+                "'{}' không đứng sau số hay đơn vị nên không phải đơn vị",
+                name
+            ))),
+            Some(Token::Word(name)) => Err(ArithmeticError::UnknownUnit(name)),
+            other => Err(parse_error(format!("Mong đợi đại lượng, gặp {:?}", other))),
+        }
+    }
+}
+
+/// Tính biểu thức có đơn vị, tùy chọn đổi sang đơn vị đích (`to`, `in`, `->`).
+///
+/// `UnitValue::quantity` luôn ở đơn vị SI cơ bản (`3 m/s * 2 h` → 21600 m); `value` và
+/// `unit` là đơn vị đích khi có đổi đơn vị, ngược lại là đơn vị có tiền tố dễ đọc (21.6 km).
+pub fn evaluate(expr: &str) -> Result<UnitValue, ArithmeticError> {
+    let (tokens, starts) = tokenize(expr)?;
+    let _memory =
+        sandbox::allocate(tokens.len() * size_of::<Token>() + starts.len() * size_of::<usize>())?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let quantity = parser.expression()?;
+    if !quantity.value.is_finite() {
+        return Err(parse_error("Kết quả không hữu hạn"));
+    }
+
+    if parser.at_conversion() {
+        parser.pos += 1;
+        let unit = starts
+            .get(parser.pos)
+            .map(|start| expr[*start..].trim().to_string())
+            .ok_or_else(|| parse_error("Thiếu đơn vị đích"))?;
+        let target = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(parse_error(format!("Token thừa {:?}", parser.peek())));
+        }
+        target.same_dimension(&quantity)?;
+        return Ok(UnitValue {
+            quantity,
+            value: quantity.value / target.value,
+            unit,
+            converted: true,
+        });
+    }
+    if let Some(token) = parser.peek() {
+        return Err(parse_error(format!("Token thừa {:?}", token)));
+    }
+    let (value, unit) = display_unit(quantity);
+    Ok(UnitValue {
+        quantity,
+        value,
+        unit,
+        converted: false,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn show(expr: &str) -> String {
+        evaluate(expr).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_dimensional_arithmetic_picks_prefixed_units() {
+        assert_eq!(show("3 m/s * 2 h"), "21.6 km");
+        assert_eq!(show("6 m / 2 s"), "3 m/s");
+        assert_eq!(show("1 kg * 9.81 m/s^2"), "9.81 N");
+        assert_eq!(show("1500 g"), "1.5 kg");
+        assert_eq!(show("0.5 s"), "500 ms");
+        assert_eq!(show("7200 s"), "7200 s");
+        assert_eq!(show("2 kW * 30 min"), "3.6 MJ");
+        assert_eq!(show("sqrt(16 m^2)"), "4 m");
+        assert_eq!(show("10 m^-1 * 3 m"), "30");
+        assert_eq!(show("3 m * 2 m / 1 s"), "6 m^2/s");
+    }
+
+    #[test]
+    fn test_explicit_conversion() {
+        assert_eq!(show("100 km/h to m/s"), "27.7777777778 m/s");
+        assert_eq!(show("2 kWh in J"), "7200000 J");
+        assert_eq!(show("1 mi -> ft"), "5280 ft");
+        assert_eq!(show("10 mL to L"), "0.01 L");
+        assert_eq!(show("1 atm to hPa"), "1013.25 hPa");
+        let value = evaluate("5 km to mi").unwrap();
+        assert!(value.converted);
+        assert!((value.value - 3.106_855_961).abs() < 1e-6);
+        assert_eq!(value.quantity.value, 5000.0);
+    }
+
+    #[test]
+    fn test_dimension_mismatch_is_rejected() {
+        assert!(matches!(
+            evaluate("3 m + 2 s"),
+            Err(ArithmeticError::DimensionMismatch { expected, found }) if expected == "m" && found == "s"
+        ));
+        assert!(matches!(
+            evaluate("5 km to s"),
+            Err(ArithmeticError::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            evaluate("sqrt(2 m)"),
+            Err(ArithmeticError::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            evaluate("3 furlong"),
+            Err(ArithmeticError::UnknownUnit(unit)) if unit == "furlong"
+        ));
+        assert!(evaluate("1 m / 0").is_err());
+    }
+
+    #[test]
+    fn test_dimension_exponent_overflow_is_an_error() {
+        assert_eq!(show("(1 m^8)^8"), "1 m^64");
+        assert!(matches!(
+            evaluate("((1 m^8)^8)^2"),
+            Err(ArithmeticError::ParseError(message)) if message.contains("Số mũ")
+        ));
+        assert!(matches!(
+            evaluate("(1 m^8)^8 * (1 m^8)^8"),
+            Err(ArithmeticError::ParseError(_))
+        ));
+        assert!(matches!(
+            evaluate("(1 m^8)^-8 / (1 m^8)^8 / 1 m"),
+            Err(ArithmeticError::ParseError(_))
+        ));
+        // Số mũ nhỏ nhất vẫn hiển thị được
+        assert_eq!(Dimension([-128, 0, 0, 0, 0, 0, 0]).to_string(), "1/m^128");
+    }
+
+    #[test]
+    fn test_units_only_follow_numbers_or_units() {
+        assert!(is_unit_expression("3 m/s * 2 h"));
+        assert!(is_unit_expression("100 km/h to m/s"));
+        assert!(is_unit_expression("1 kg m"));
+        assert!(is_unit_expression("sqrt(16 m^2)"));
+        assert!(!is_unit_expression("min(3,4)"));
+        assert!(!is_unit_expression("min(3) + 2"));
+        assert!(!is_unit_expression("2*s"));
+        assert!(!is_unit_expression("s + 1"));
+        assert!(!is_unit_expression("1 + 2"));
+        assert!(matches!(
+            evaluate("2*s"),
+            Err(ArithmeticError::ParseError(_))
+        ));
+        assert!(matches!(
+            evaluate("3 * min(4)"),
+            Err(ArithmeticError::ParseError(_))
+        ));
+    }
+}
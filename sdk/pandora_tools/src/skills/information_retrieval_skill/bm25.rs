@@ -0,0 +1,208 @@
+//! Chỉ mục ngược BM25 trong bộ nhớ cho stage `TextSearch`.
+//!
+//! Điểm trả về được hiệu chỉnh về [0, 1]: chia cho tổng IDF của các từ truy vấn, tức là
+//! điểm của một tài liệu dài trung bình chứa mỗi từ truy vấn đúng một lần. Nhờ vậy điểm
+//! văn bản so sánh được với độ tương đồng cosine khi hợp nhất kết quả.
+
+use std::collections::HashMap;
+
+/// Tách từ: chữ thường, cắt theo ký tự không phải chữ/số (giữ nguyên chữ có dấu).
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Bm25Index {
+    /// Độ bão hòa tần suất từ
+    pub k1: f32,
+    /// Mức chuẩn hóa theo độ dài tài liệu
+    pub b: f32,
+    /// term → (slot tài liệu → tần suất)
+    postings: HashMap<String, HashMap<usize, u32>>,
+    /// slot → (tần suất từng từ, độ dài), để gỡ bỏ khi tài liệu được thay thế
+    documents: HashMap<usize, (HashMap<String, u32>, u32)>,
+    total_length: u64,
+}
+
+impl Default for Bm25Index {
+    fn default() -> Self {
+        Self {
+            k1: 1.2,
+            b: 0.75,
+            postings: HashMap::new(),
+            documents: HashMap::new(),
+            total_length: 0,
+        }
+    }
+}
+
+impl Bm25Index {
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Đánh chỉ mục (hoặc đánh lại) nội dung của tài liệu ở `slot`.
+    pub fn insert(&mut self, slot: usize, text: &str) {
+        self.remove(slot);
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        let mut length = 0;
+        for term in tokenize(text) {
+            *frequencies.entry(term).or_insert(0) += 1;
+            length += 1;
+        }
+        for (term, tf) in &frequencies {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(slot, *tf);
+        }
+        self.total_length += u64::from(length);
+        self.documents.insert(slot, (frequencies, length));
+    }
+
+    pub fn remove(&mut self, slot: usize) {
+        let Some((frequencies, length)) = self.documents.remove(&slot) else {
+            return;
+        };
+        for term in frequencies.keys() {
+            if let Some(posting) = self.postings.get_mut(term) {
+                posting.remove(&slot);
+                if posting.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= u64::from(length);
+    }
+
+    fn idf(&self, term: &str) -> f32 {
+        let n = self.documents.len() as f32;
+        let df = self.postings.get(term).map_or(0, HashMap::len) as f32;
+        (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+    }
+
+    /// `k` tài liệu có điểm BM25 cao nhất; `boosts` nhân trọng số cho từng từ truy vấn.
+    pub fn search(
+        &self,
+        query: &str,
+        k: usize,
+        boosts: &HashMap<String, f32>,
+    ) -> Vec<(usize, f32)> {
+        if self.documents.is_empty() || k == 0 {
+            return Vec::new();
+        }
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let average_length = self.total_length as f32 / self.documents.len() as f32;
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        let mut bound = 0.0;
+        for term in &terms {
+            let weight = self.idf(term) * boosts.get(term).copied().unwrap_or(1.0);
+            bound += weight;
+            let Some(posting) = self.postings.get(term) else {
+                continue;
+            };
+            for (slot, tf) in posting {
+                let tf = *tf as f32;
+                let length = self.documents[slot].1 as f32;
+                let norm = self.k1 * (1.0 - self.b + self.b * length / average_length.max(1.0));
+                *scores.entry(*slot).or_insert(0.0) += weight * tf * (self.k1 + 1.0) / (tf + norm);
+            }
+        }
+        if bound <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut ranked: Vec<(usize, f32)> = scores
+            .into_iter()
+            .map(|(slot, score)| (slot, (score / bound).min(1.0)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.truncate(k);
+        ranked
+    }
+
+    /// Điểm đã hiệu chỉnh của riêng tài liệu ở `slot` (0 nếu không có trong chỉ mục).
+    pub fn score(&self, query: &str, slot: usize) -> f32 {
+        let Some((frequencies, length)) = self.documents.get(&slot) else {
+            return 0.0;
+        };
+        let mut terms = tokenize(query);
+        terms.sort();
+        terms.dedup();
+
+        let average_length = self.total_length as f32 / self.documents.len() as f32;
+        let norm = self.k1 * (1.0 - self.b + self.b * *length as f32 / average_length.max(1.0));
+        let mut score = 0.0;
+        let mut bound = 0.0;
+        for term in &terms {
+            let idf = self.idf(term);
+            bound += idf;
+            if let Some(tf) = frequencies.get(term) {
+                let tf = *tf as f32;
+                score += idf * tf * (self.k1 + 1.0) / (tf + norm);
+            }
+        }
+        if bound <= 0.0 {
+            0.0
+        } else {
+            (score / bound).min(1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bm25_ranks_rare_terms_and_calibrates_scores() {
+        let mut index = Bm25Index::default();
+        index.insert(0, "Rust là ngôn ngữ lập trình hệ thống");
+        index.insert(1, "Python là ngôn ngữ kịch bản");
+        index.insert(2, "Rust Rust borrow checker");
+        let none = HashMap::new();
+
+        let hits = index.search("rust", 10, &none);
+        assert_eq!(hits.iter().map(|h| h.0).collect::<Vec<_>>(), vec![2, 0]);
+        assert!(hits.iter().all(|h| h.1 > 0.0 && h.1 <= 1.0));
+
+        // "là" xuất hiện ở hầu hết tài liệu nên đóng góp ít hơn "python"
+        let hits = index.search("python là", 10, &none);
+        assert_eq!(hits[0].0, 1);
+        assert!(hits[0].1 > 2.0 * hits[1].1);
+
+        // Cùng IDF: tài liệu ngắn hơn thắng, trừ khi từ còn lại được tăng trọng số
+        assert_eq!(index.search("hệ borrow", 1, &none)[0].0, 2);
+        let boosted = HashMap::from([("hệ".to_string(), 3.0)]);
+        assert_eq!(index.search("hệ borrow", 1, &boosted)[0].0, 0);
+        assert!(index.search("java", 10, &none).is_empty());
+
+        for (slot, score) in index.search("rust hệ", 10, &none) {
+            assert!((index.score("rust hệ", slot) - score).abs() < 1e-6);
+        }
+        assert_eq!(index.score("rust", 1), 0.0);
+        assert_eq!(index.score("rust", 7), 0.0);
+    }
+
+    #[test]
+    fn test_bm25_reindexing_replaces_postings() {
+        let mut index = Bm25Index::default();
+        index.insert(0, "alpha beta");
+        index.insert(0, "gamma");
+        let none = HashMap::new();
+        assert!(index.search("alpha", 10, &none).is_empty());
+        assert_eq!(index.search("gamma", 10, &none), vec![(0, 1.0)]);
+        index.remove(0);
+        assert!(index.is_empty());
+    }
+}
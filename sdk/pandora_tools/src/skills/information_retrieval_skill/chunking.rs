@@ -0,0 +1,192 @@
+//! Chia văn bản dài thành các đoạn chồng lấn để nạp vào kho tài liệu.
+//!
+//! Mỗi đoạn dài tối đa `max_chars` ký tự (tính theo `char`, an toàn với tiếng Việt) và ưu
+//! tiên cắt ở ranh giới đoạn văn, rồi cuối câu, rồi khoảng trắng trong nửa sau của cửa sổ.
+//! Đoạn sau bắt đầu lùi lại khoảng `overlap_chars` ký tự, căn về đầu một từ.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkingConfig {
+    pub max_chars: usize,
+    pub overlap_chars: usize,
+    /// Phần mở rộng (không phân biệt hoa thường) của file được nạp khi quét thư mục
+    pub extensions: Vec<String>,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            max_chars: 1_000,
+            overlap_chars: 150,
+            extensions: ["txt", "md", "markdown", "rst"]
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Kết quả của `ProgressiveSemanticEngine::ingest_directory`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IngestReport {
+    pub files: usize,
+    pub chunks: usize,
+    /// Số đoạn cũ bị xóa vì file đã ngắn đi hoặc không còn trong thư mục
+    pub removed: usize,
+    /// File không đọc được dưới dạng UTF-8
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Các file trong `dir` (đệ quy) có phần mở rộng thuộc `config.extensions`, đã sắp xếp.
+///
+/// Thư mục là symlink vẫn được duyệt, nhưng mỗi thư mục thật (theo đường dẫn chuẩn hóa)
+/// chỉ một lần, theo thứ tự tên, nên vòng symlink không làm duyệt lặp vô hạn và đường dẫn
+/// trả về không đổi giữa các lần quét.
+pub fn collect_files(dir: &Path, config: &ChunkingConfig) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        if !visited.insert(current.canonicalize()?) {
+            continue;
+        }
+        let mut entries = std::fs::read_dir(&current)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+        for path in entries.into_iter().rev() {
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| {
+                let ext = ext.to_string_lossy().to_lowercase();
+                config
+                    .extensions
+                    .iter()
+                    .any(|wanted| wanted.to_lowercase() == ext)
+            }) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Chia `text` thành các đoạn đã cắt khoảng trắng hai đầu; văn bản rỗng cho danh sách rỗng.
+pub fn chunk_text(text: &str, config: &ChunkingConfig) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let max_chars = config.max_chars.max(1);
+    let overlap = config.overlap_chars.min(max_chars / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + max_chars).min(chars.len());
+        if end < chars.len() {
+            end = break_point(&chars, start + max_chars / 2, end);
+        }
+        let chunk: String = chars[start..end].iter().collect();
+        let chunk = chunk.trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        // Lùi lại phần chồng lấn rồi tiến tới đầu từ kế tiếp
+        let mut next = end.saturating_sub(overlap).max(start + 1);
+        if overlap > 0 {
+            while next < end && !chars[next - 1].is_whitespace() {
+                next += 1;
+            }
+        }
+        start = next;
+    }
+    chunks
+}
+
+/// Vị trí cắt tốt nhất trong `(min, end]`: sau dòng trống, sau dấu kết câu, sau khoảng trắng;
+/// không có thì cắt cứng ở `end`.
+fn break_point(chars: &[char], min: usize, end: usize) -> usize {
+    let window = min.max(1)..=end;
+    let after = |pred: &dyn Fn(usize) -> bool| window.clone().rev().find(|&i| pred(i));
+    after(&|i| i >= 2 && chars[i - 1] == '\n' && chars[i - 2] == '\n')
+        .or_else(|| {
+            after(&|i| {
+                i >= 2
+                    && chars[i - 1].is_whitespace()
+                    && matches!(chars[i - 2], '.' | '!' | '?' | '…')
+            })
+        })
+        .or_else(|| after(&|i| chars[i - 1].is_whitespace()))
+        .unwrap_or(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_chars: usize, overlap_chars: usize) -> ChunkingConfig {
+        ChunkingConfig {
+            max_chars,
+            overlap_chars,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chunks_prefer_paragraph_and_sentence_boundaries() {
+        let text = "Đoạn một nói về Hà Nội.\n\nĐoạn hai nói về Huế. Câu thứ hai dài hơn một chút.";
+        let chunks = chunk_text(text, &config(40, 0));
+        assert_eq!(chunks[0], "Đoạn một nói về Hà Nội.");
+        assert_eq!(chunks[1], "Đoạn hai nói về Huế.");
+        assert!(chunks.iter().all(|c| c.chars().count() <= 40));
+        assert_eq!(
+            chunk_text(text, &config(1_000, 100)),
+            vec![text.to_string()]
+        );
+        assert!(chunk_text("  \n ", &config(10, 2)).is_empty());
+    }
+
+    #[test]
+    fn test_chunks_overlap_on_word_boundaries() {
+        let words: Vec<String> = (0..60).map(|i| format!("w{:02}", i)).collect();
+        let text = words.join(" ");
+        let chunks = chunk_text(&text, &config(50, 12));
+        assert!(chunks.len() > 4);
+        for pair in chunks.windows(2) {
+            let last_word = pair[0].split(' ').next_back().unwrap();
+            assert!(pair[1].split(' ').any(|w| w == last_word), "{:?}", pair);
+        }
+        // Mọi từ đều có mặt, không từ nào bị cắt đôi
+        for word in &words {
+            assert!(chunks.iter().any(|c| c.split(' ').any(|w| w == word)));
+        }
+        assert!(chunks
+            .iter()
+            .flat_map(|c| c.split(' '))
+            .all(|w| w.len() == 3));
+
+        // Không có khoảng trắng: cắt cứng
+        assert_eq!(
+            chunk_text("abcdefgh", &config(3, 0)),
+            vec!["abc", "def", "gh"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_collect_files_survives_symlink_cycles() {
+        let root = std::env::temp_dir().join(format!("chunking-symlinks-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("a/b")).unwrap();
+        std::fs::write(root.join("a/b/note.md"), "ghi chú").unwrap();
+        std::os::unix::fs::symlink(&root, root.join("a/b/loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("a"), root.join("alias")).unwrap();
+
+        let files = collect_files(&root, &ChunkingConfig::default()).unwrap();
+        assert_eq!(files, vec![root.join("a/b/note.md")]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
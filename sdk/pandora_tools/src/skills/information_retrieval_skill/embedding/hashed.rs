@@ -0,0 +1,142 @@
+//! Embedding random indexing trên từ và n-gram ký tự, không cần file mô hình.
+//!
+//! Mỗi đặc trưng (một từ, hoặc một n-gram của `<từ>`) được băm thành một vector chỉ mục
+//! thưa: `nonzeros` vị trí với dấu ±1. Embedding của văn bản là tổng các vector chỉ mục,
+//! chuẩn hóa L2. N-gram ký tự giúp các biến thể của cùng một từ ("retrieve", "retrieval")
+//! vẫn gần nhau.
+
+use super::{normalize, Embedder};
+use crate::skills::information_retrieval_skill::bm25::tokenize;
+use crate::skills::information_retrieval_skill::RetrievalError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashedNgramConfig {
+    pub dim: usize,
+    /// Độ dài n-gram ký tự, gồm cả hai đầu; `(0, 0)` để chỉ dùng từ
+    pub ngram_range: (usize, usize),
+    /// Số vị trí khác 0 trong vector chỉ mục của mỗi đặc trưng
+    pub nonzeros: usize,
+    /// Trọng số của đặc trưng cả từ so với mỗi n-gram
+    pub word_weight: f32,
+    pub seed: u64,
+}
+
+impl Default for HashedNgramConfig {
+    fn default() -> Self {
+        Self {
+            dim: 256,
+            ngram_range: (3, 5),
+            nonzeros: 4,
+            word_weight: 2.0,
+            seed: 0x5eed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HashedNgramEmbedder {
+    config: HashedNgramConfig,
+}
+
+impl HashedNgramEmbedder {
+    pub fn new(config: HashedNgramConfig) -> Self {
+        Self { config }
+    }
+
+    /// Cấu hình mặc định với số chiều `dim`
+    pub fn with_dim(dim: usize) -> Self {
+        Self::new(HashedNgramConfig {
+            dim,
+            ..Default::default()
+        })
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let mut state = seahash::hash_seeded(
+            feature.as_bytes(),
+            self.config.seed,
+            0x9e37_79b9_7f4a_7c15,
+            0xbf58_476d_1ce4_e5b9,
+            0x94d0_49bb_1331_11eb,
+        );
+        for _ in 0..self.config.nonzeros {
+            state = splitmix64(state);
+            let position = (state % vector.len() as u64) as usize;
+            let sign = if state >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[position] += sign * weight;
+        }
+    }
+}
+
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Embedder for HashedNgramEmbedder {
+    fn name(&self) -> &str {
+        "hashed-ngram"
+    }
+
+    fn dim(&self) -> usize {
+        self.config.dim
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, RetrievalError> {
+        if self.config.dim == 0 {
+            return Err(RetrievalError::EmbeddingError(
+                "Số chiều embedding phải lớn hơn 0".to_string(),
+            ));
+        }
+        let (min_n, max_n) = self.config.ngram_range;
+        let mut vector = vec![0.0; self.config.dim];
+        for token in tokenize(text) {
+            self.add_feature(
+                &mut vector,
+                &format!("w:{}", token),
+                self.config.word_weight,
+            );
+            if min_n == 0 {
+                continue;
+            }
+            let chars: Vec<char> = format!("<{}>", token).chars().collect();
+            for n in min_n..=max_n.min(chars.len()) {
+                for window in chars.windows(n) {
+                    let gram: String = window.iter().collect();
+                    self.add_feature(&mut vector, &gram, 1.0);
+                }
+            }
+        }
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::cosine;
+    use super::*;
+
+    #[test]
+    fn test_hashed_embedding_is_deterministic_and_morphology_aware() {
+        let embedder = HashedNgramEmbedder::with_dim(256);
+        let a = embedder.embed("information retrieval").unwrap();
+        assert_eq!(a, embedder.embed("Information, retrieval!").unwrap());
+        assert!((cosine(&a, &a) - 1.0).abs() < 1e-5);
+
+        let related = embedder.embed("retrieving information").unwrap();
+        let unrelated = embedder.embed("bánh mì thịt nướng").unwrap();
+        assert!(cosine(&a, &related) > cosine(&a, &unrelated) + 0.2);
+
+        // Văn bản không có từ nào cho vector 0
+        assert!(embedder.embed("  ...  ").unwrap().iter().all(|x| *x == 0.0));
+
+        let other_seed = HashedNgramEmbedder::new(HashedNgramConfig {
+            seed: 7,
+            ..Default::default()
+        });
+        assert_ne!(a, other_seed.embed("information retrieval").unwrap());
+    }
+}
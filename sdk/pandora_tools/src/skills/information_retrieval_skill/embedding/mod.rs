@@ -0,0 +1,105 @@
+//! Sinh embedding cục bộ (chỉ CPU) cho tài liệu và truy vấn.
+//!
+//! - [`HashedNgramEmbedder`]: random indexing trên từ và n-gram ký tự, không cần file mô hình
+//! - [`StaticEmbeddingModel`]: mô hình sentence-embedding nhỏ đọc từ safetensors cục bộ
+//!   qua `candle-core` (feature `local_embeddings`, không bật mặc định)
+//! - [`CachedEmbedder`]: bọc một embedder bất kỳ bằng cache LRU
+
+pub mod hashed;
+#[cfg(feature = "local_embeddings")]
+pub mod static_model;
+
+pub use hashed::{HashedNgramConfig, HashedNgramEmbedder};
+#[cfg(feature = "local_embeddings")]
+pub use static_model::StaticEmbeddingModel;
+
+use super::RetrievalError;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Biến văn bản thành vector có số chiều cố định.
+pub trait Embedder: Send + Sync {
+    /// Tên mô hình, dùng trong reasoning trace
+    fn name(&self) -> &str;
+
+    /// Số chiều của vector sinh ra
+    fn dim(&self) -> usize;
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, RetrievalError>;
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, RetrievalError> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+}
+
+/// Chuẩn hóa L2 tại chỗ; vector 0 giữ nguyên.
+pub(crate) fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+/// Độ tương đồng cosine; 0 nếu một trong hai vector bằng 0.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm =
+        a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        dot / norm
+    } else {
+        0.0
+    }
+}
+
+/// Cache LRU trước một embedder, khóa theo chính văn bản (hai văn bản khác nhau không
+/// bao giờ dùng chung vector dù trùng hash).
+pub struct CachedEmbedder {
+    inner: Arc<dyn Embedder>,
+    cache: Mutex<LruCache<String, Vec<f32>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedEmbedder {
+    pub fn new(inner: Arc<dyn Embedder>, capacity: usize) -> Self {
+        Self {
+            inner,
+            cache: Mutex::new(LruCache::new(NonZeroUsize::new(capacity.max(1)).unwrap())),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// (số lần trúng cache, số lần phải gọi embedder bên trong)
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+impl Embedder for CachedEmbedder {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn dim(&self) -> usize {
+        self.inner.dim()
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, RetrievalError> {
+        if let Some(vector) = self.cache.lock().unwrap().get(text) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(vector.clone());
+        }
+        // Không giữ khóa khi gọi mô hình
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let vector = self.inner.embed(text)?;
+        self.cache.lock().unwrap().put(text.to_string(), vector.clone());
+        Ok(vector)
+    }
+}
@@ -0,0 +1,270 @@
+//! Mô hình sentence-embedding tĩnh (định dạng Model2Vec) chạy CPU qua `candle-core`.
+//!
+//! Thư mục mô hình gồm `model.safetensors` chứa ma trận `embeddings` kích thước
+//! `[vocab, dim]` và một tokenizer WordPiece: `tokenizer.json` (HuggingFace) hoặc
+//! `vocab.txt` (mỗi dòng một token, id là số thứ tự dòng). Embedding của câu là trung bình
+//! các vector token, chuẩn hóa L2. Tokenizer không bỏ dấu tiếng Việt như BERT uncased,
+//! nên cần vocab có sẵn các từ có dấu.
+
+use super::{normalize, Embedder};
+use crate::skills::information_retrieval_skill::RetrievalError;
+use candle_core::{DType, Device, Tensor};
+use std::collections::HashMap;
+use std::path::Path;
+
+const EMBEDDINGS_TENSOR: &str = "embeddings";
+const MAX_WORD_CHARS: usize = 100;
+
+fn model_error(context: &str, e: impl std::fmt::Display) -> RetrievalError {
+    RetrievalError::EmbeddingError(format!("{}: {}", context, e))
+}
+
+/// Tokenizer WordPiece: tách theo khoảng trắng và dấu câu, sau đó khớp tiền tố dài nhất.
+#[derive(Debug, Clone)]
+struct WordPiece {
+    vocab: HashMap<String, u32>,
+    unk_id: Option<u32>,
+    continuing_prefix: String,
+    lowercase: bool,
+}
+
+impl WordPiece {
+    fn from_vocab_txt(content: &str) -> Self {
+        let vocab: HashMap<String, u32> = content
+            .lines()
+            .enumerate()
+            .map(|(id, token)| (token.trim_end().to_string(), id as u32))
+            .collect();
+        Self {
+            unk_id: vocab.get("[UNK]").copied(),
+            vocab,
+            continuing_prefix: "##".to_string(),
+            lowercase: true,
+        }
+    }
+
+    fn from_tokenizer_json(content: &str) -> Result<Self, RetrievalError> {
+        let json: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| model_error("tokenizer.json", e))?;
+        let model = &json["model"];
+        if let Some(kind) = model["type"].as_str() {
+            if kind != "WordPiece" {
+                return Err(RetrievalError::EmbeddingError(format!(
+                    "Chỉ hỗ trợ tokenizer WordPiece, nhận '{}'",
+                    kind
+                )));
+            }
+        }
+        let vocab: HashMap<String, u32> = model["vocab"]
+            .as_object()
+            .ok_or_else(|| model_error("tokenizer.json", "thiếu model.vocab"))?
+            .iter()
+            .filter_map(|(token, id)| Some((token.clone(), id.as_u64()? as u32)))
+            .collect();
+        let unk_token = model["unk_token"].as_str().unwrap_or("[UNK]");
+        Ok(Self {
+            unk_id: vocab.get(unk_token).copied(),
+            continuing_prefix: model["continuing_subword_prefix"]
+                .as_str()
+                .unwrap_or("##")
+                .to_string(),
+            lowercase: json["normalizer"]["lowercase"].as_bool().unwrap_or(true),
+            vocab,
+        })
+    }
+
+    fn encode(&self, text: &str) -> Vec<u32> {
+        let text = if self.lowercase {
+            text.to_lowercase()
+        } else {
+            text.to_string()
+        };
+        let mut ids = Vec::new();
+        for word in pre_tokenize(&text) {
+            match self.encode_word(word) {
+                Some(pieces) => ids.extend(pieces),
+                None => ids.extend(self.unk_id),
+            }
+        }
+        ids
+    }
+
+    /// Khớp tham lam tiền tố dài nhất; `None` nếu từ không tách được hết.
+    fn encode_word(&self, word: &str) -> Option<Vec<u32>> {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() > MAX_WORD_CHARS {
+            return None;
+        }
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let mut end = chars.len();
+            let mut found = None;
+            while end > start {
+                let mut piece: String = chars[start..end].iter().collect();
+                if start > 0 {
+                    piece.insert_str(0, &self.continuing_prefix);
+                }
+                if let Some(id) = self.vocab.get(&piece) {
+                    found = Some(*id);
+                    break;
+                }
+                end -= 1;
+            }
+            pieces.push(found?);
+            start = end;
+        }
+        Some(pieces)
+    }
+}
+
+/// Tách theo khoảng trắng; mỗi ký tự dấu câu là một từ riêng.
+fn pre_tokenize(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    for chunk in text.split_whitespace() {
+        let mut start = 0;
+        for (i, c) in chunk.char_indices() {
+            if !c.is_alphanumeric() {
+                if start < i {
+                    words.push(&chunk[start..i]);
+                }
+                words.push(&chunk[i..i + c.len_utf8()]);
+                start = i + c.len_utf8();
+            }
+        }
+        if start < chunk.len() {
+            words.push(&chunk[start..]);
+        }
+    }
+    words
+}
+
+pub struct StaticEmbeddingModel {
+    name: String,
+    embeddings: Tensor,
+    tokenizer: WordPiece,
+    dim: usize,
+}
+
+impl std::fmt::Debug for StaticEmbeddingModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StaticEmbeddingModel")
+            .field("name", &self.name)
+            .field("vocab", &self.tokenizer.vocab.len())
+            .field("dim", &self.dim)
+            .finish()
+    }
+}
+
+impl StaticEmbeddingModel {
+    /// Đọc `model.safetensors` cùng `tokenizer.json` (ưu tiên) hoặc `vocab.txt` trong `dir`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, RetrievalError> {
+        let dir = dir.as_ref();
+        let tokenizer = ["tokenizer.json", "vocab.txt"]
+            .iter()
+            .map(|file| dir.join(file))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                model_error(
+                    &dir.display().to_string(),
+                    "không tìm thấy tokenizer.json hoặc vocab.txt",
+                )
+            })?;
+        let mut model = Self::from_files(dir.join("model.safetensors"), tokenizer)?;
+        if let Some(name) = dir.file_name() {
+            model.name = name.to_string_lossy().into_owned();
+        }
+        Ok(model)
+    }
+
+    pub fn from_files(
+        weights: impl AsRef<Path>,
+        tokenizer: impl AsRef<Path>,
+    ) -> Result<Self, RetrievalError> {
+        let weights = weights.as_ref();
+        let tokenizer_path = tokenizer.as_ref();
+        let content = std::fs::read_to_string(tokenizer_path)
+            .map_err(|e| model_error(&tokenizer_path.display().to_string(), e))?;
+        let tokenizer = if tokenizer_path.extension().is_some_and(|ext| ext == "json") {
+            WordPiece::from_tokenizer_json(&content)?
+        } else {
+            WordPiece::from_vocab_txt(&content)
+        };
+
+        let mut tensors = candle_core::safetensors::load(weights, &Device::Cpu)
+            .map_err(|e| model_error(&weights.display().to_string(), e))?;
+        let embeddings = match tensors.remove(EMBEDDINGS_TENSOR) {
+            Some(tensor) => tensor,
+            None if tensors.len() == 1 => tensors.into_values().next().unwrap(),
+            None => {
+                return Err(model_error(
+                    &weights.display().to_string(),
+                    format!("thiếu tensor '{}'", EMBEDDINGS_TENSOR),
+                ))
+            }
+        };
+        let embeddings = embeddings
+            .to_dtype(DType::F32)
+            .map_err(|e| model_error("embeddings", e))?;
+        let (rows, dim) = embeddings
+            .dims2()
+            .map_err(|e| model_error("embeddings", e))?;
+        if let Some(max_id) = tokenizer.vocab.values().max() {
+            if *max_id as usize >= rows {
+                return Err(RetrievalError::EmbeddingError(format!(
+                    "Tokenizer có id {} nhưng ma trận embedding chỉ có {} hàng",
+                    max_id, rows
+                )));
+            }
+        }
+
+        Ok(Self {
+            name: weights
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "static-embedding".to_string()),
+            embeddings,
+            tokenizer,
+            dim,
+        })
+    }
+}
+
+impl Embedder for StaticEmbeddingModel {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn embed(&self, text: &str) -> Result<Vec<f32>, RetrievalError> {
+        let ids = self.tokenizer.encode(text);
+        if ids.is_empty() {
+            return Ok(vec![0.0; self.dim]);
+        }
+        let ids = Tensor::new(ids.as_slice(), &Device::Cpu).map_err(|e| model_error("ids", e))?;
+        let mut vector = self
+            .embeddings
+            .index_select(&ids, 0)
+            .and_then(|rows| rows.mean(0))
+            .and_then(|mean| mean.to_vec1::<f32>())
+            .map_err(|e| model_error("mean pooling", e))?;
+        normalize(&mut vector);
+        Ok(vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wordpiece_splits_subwords_and_punctuation() {
+        let tokenizer = WordPiece::from_vocab_txt("[PAD]\n[UNK]\nplay\n##ing\n##ed\n,\nhà\nnội\n");
+        assert_eq!(tokenizer.encode("Playing, played"), vec![2, 3, 5, 2, 4]);
+        assert_eq!(tokenizer.encode("Hà Nội xyz"), vec![6, 7, 1]);
+        assert!(tokenizer.encode("   ").is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+//! Hợp nhất danh sách kết quả của nhiều retriever và ước lượng độ tin cậy.
+//!
+//! Mỗi retriever trả về điểm đã hiệu chỉnh trong [0, 1] (cosine cho vector, BM25 chia
+//! cho cận tham chiếu cho văn bản), nên CombSUM/CombMNZ/WeightedSum cộng trực tiếp điểm
+//! mà không cần chuẩn hóa min-max theo từng danh sách.
+
+use super::{Confidence, FusionStrategy};
+use std::collections::HashMap;
+
+/// Hằng số `k` của Reciprocal Rank Fusion (Cormack et al., 2009)
+pub const RRF_K: f32 = 60.0;
+
+/// Một kết quả của một retriever, điểm trong [0, 1].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub id: String,
+    pub score: f32,
+}
+
+/// Danh sách kết quả có thứ hạng của một nguồn ("vector", "text", ...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedList {
+    pub source: String,
+    pub hits: Vec<SearchHit>,
+}
+
+impl RankedList {
+    pub fn new(source: impl Into<String>, hits: Vec<SearchHit>) -> Self {
+        Self {
+            source: source.into(),
+            hits,
+        }
+    }
+
+    fn score_of(&self, id: &str) -> Option<f32> {
+        self.hits.iter().find(|h| h.id == id).map(|h| h.score)
+    }
+}
+
+/// Kết quả sau hợp nhất: `score` là điểm thô của chiến lược, `normalized` chia cho điểm
+/// tối đa đạt được với các danh sách hiện có nên luôn nằm trong [0, 1].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FusedHit {
+    pub id: String,
+    pub score: f32,
+    pub normalized: f32,
+    /// Số danh sách chứa kết quả này
+    pub sources: usize,
+}
+
+/// Hợp nhất các danh sách theo `strategy`, sắp giảm dần theo điểm (hòa thì theo id).
+pub fn fuse(strategy: &FusionStrategy, lists: &[RankedList]) -> Vec<FusedHit> {
+    let active: Vec<&RankedList> = lists.iter().filter(|l| !l.hits.is_empty()).collect();
+    let weight = |list: &RankedList| match strategy {
+        FusionStrategy::WeightedSum { weights } => {
+            weights.get(&list.source).copied().unwrap_or(0.0)
+        }
+        _ => 1.0,
+    };
+    let n = active.len() as f32;
+    let max_score = match strategy {
+        FusionStrategy::ReciprocalRank => n / (RRF_K + 1.0),
+        FusionStrategy::CombSum => n,
+        FusionStrategy::CombMNZ => n * n,
+        FusionStrategy::WeightedSum { .. } => active.iter().map(|l| weight(l)).sum(),
+    };
+
+    let mut fused: HashMap<&str, (f32, usize)> = HashMap::new();
+    for list in &active {
+        let w = weight(list);
+        for (rank, hit) in list.hits.iter().enumerate() {
+            let contribution = match strategy {
+                FusionStrategy::ReciprocalRank => 1.0 / (RRF_K + rank as f32 + 1.0),
+                FusionStrategy::WeightedSum { .. } => w * hit.score,
+                FusionStrategy::CombSum | FusionStrategy::CombMNZ => hit.score,
+            };
+            let entry = fused.entry(hit.id.as_str()).or_insert((0.0, 0));
+            entry.0 += contribution;
+            entry.1 += 1;
+        }
+    }
+
+    let mut out: Vec<FusedHit> = fused
+        .into_iter()
+        .map(|(id, (sum, sources))| {
+            let score = match strategy {
+                FusionStrategy::CombMNZ => sum * sources as f32,
+                _ => sum,
+            };
+            FusedHit {
+                id: id.to_string(),
+                score,
+                normalized: if max_score > 0.0 {
+                    (score / max_score).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                },
+                sources,
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    out
+}
+
+/// Độ tin cậy suy ra từ phân bố điểm:
+///
+/// - `score` = bằng chứng × (0.5 + 0.5 × khoảng cách), với bằng chứng là điểm hiệu chỉnh
+///   trung bình của kết quả đầu trên các retriever đã chạy, khoảng cách là độ tách biệt
+///   tương đối giữa hạng 1 và hạng 2
+/// - `epistemic_uncertainty` = tỉ lệ retriever không tìm thấy kết quả đầu (bất đồng)
+/// - `aleatoric_uncertainty` = entropy chuẩn hóa của phân bố điểm hợp nhất (truy vấn mơ hồ
+///   khi nhiều kết quả điểm gần nhau)
+pub fn assess(ranked: &[FusedHit], lists: &[RankedList]) -> Confidence {
+    let Some(top) = ranked.first() else {
+        return Confidence {
+            score: 0.0,
+            epistemic_uncertainty: 1.0,
+            aleatoric_uncertainty: 0.0,
+        };
+    };
+    let active: Vec<&RankedList> = lists.iter().filter(|l| !l.hits.is_empty()).collect();
+    let (evidence, agreement) = if active.is_empty() {
+        // Chỉ có kết quả từ cache: dùng điểm hợp nhất đã lưu
+        (top.normalized, 1.0)
+    } else {
+        let scores: Vec<Option<f32>> = active.iter().map(|l| l.score_of(&top.id)).collect();
+        let n = scores.len() as f32;
+        (
+            scores.iter().map(|s| s.unwrap_or(0.0)).sum::<f32>() / n,
+            scores.iter().filter(|s| s.is_some()).count() as f32 / n,
+        )
+    };
+    let margin = match ranked.get(1) {
+        Some(second) if top.score > 0.0 => ((top.score - second.score) / top.score).clamp(0.0, 1.0),
+        Some(_) => 0.0,
+        None => 1.0,
+    };
+
+    Confidence {
+        score: (evidence * (0.5 + 0.5 * margin)).clamp(0.0, 1.0),
+        epistemic_uncertainty: 1.0 - agreement,
+        aleatoric_uncertainty: normalized_entropy(ranked),
+    }
+}
+
+fn normalized_entropy(ranked: &[FusedHit]) -> f32 {
+    let total: f32 = ranked.iter().map(|h| h.score.max(0.0)).sum();
+    if ranked.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+    let entropy: f32 = ranked
+        .iter()
+        .map(|h| h.score.max(0.0) / total)
+        .filter(|p| *p > 0.0)
+        .map(|p| -p * p.ln())
+        .sum();
+    (entropy / (ranked.len() as f32).ln()).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(source: &str, hits: &[(&str, f32)]) -> RankedList {
+        RankedList::new(
+            source,
+            hits.iter()
+                .map(|(id, score)| SearchHit {
+                    id: id.to_string(),
+                    score: *score,
+                })
+                .collect(),
+        )
+    }
+
+    fn ids(hits: &[FusedHit]) -> Vec<&str> {
+        hits.iter().map(|h| h.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fusion_strategies() {
+        let lists = [
+            list("vector", &[("a", 0.9), ("b", 0.8), ("c", 0.1)]),
+            list("text", &[("c", 1.0), ("b", 0.5)]),
+        ];
+
+        let rrf = fuse(&FusionStrategy::ReciprocalRank, &lists);
+        // c: 1/61 + 1/63 nhỉnh hơn b: 2/62 chỉ nhờ hạng, không nhờ điểm
+        assert_eq!(ids(&rrf), vec!["c", "b", "a"]);
+        assert!((rrf[1].score - 2.0 / 62.0).abs() < 1e-6);
+        assert!((rrf[0].normalized - (1.0 / 61.0 + 1.0 / 63.0) * 61.0 / 2.0).abs() < 1e-6);
+
+        let sum = fuse(&FusionStrategy::CombSum, &lists);
+        assert_eq!(ids(&sum), vec!["b", "c", "a"]);
+        assert!((sum[0].score - 1.3).abs() < 1e-6);
+        assert!((sum[0].normalized - 0.65).abs() < 1e-6);
+
+        // CombMNZ thưởng cho kết quả xuất hiện ở nhiều danh sách
+        let single = [
+            list("vector", &[("a", 1.0), ("b", 0.4)]),
+            list("text", &[("b", 0.4)]),
+        ];
+        assert_eq!(ids(&fuse(&FusionStrategy::CombSum, &single))[0], "a");
+        let mnz = fuse(&FusionStrategy::CombMNZ, &single);
+        assert_eq!(ids(&mnz)[0], "b");
+        assert_eq!(mnz[0].sources, 2);
+
+        let weights = HashMap::from([("vector".to_string(), 0.2), ("text".to_string(), 0.8)]);
+        let weighted = fuse(&FusionStrategy::WeightedSum { weights }, &lists);
+        assert_eq!(ids(&weighted)[0], "c");
+        assert!(weighted.iter().all(|h| (0.0..=1.0).contains(&h.normalized)));
+    }
+
+    #[test]
+    fn test_confidence_follows_score_distribution() {
+        let clear = [
+            list("vector", &[("a", 0.95), ("b", 0.2)]),
+            list("text", &[("a", 0.9)]),
+        ];
+        let ambiguous = [
+            list("vector", &[("a", 0.4), ("b", 0.39), ("c", 0.38)]),
+            list("text", &[("d", 0.3)]),
+        ];
+        let strategy = FusionStrategy::CombSum;
+        let clear_conf = assess(&fuse(&strategy, &clear), &clear);
+        let ambiguous_conf = assess(&fuse(&strategy, &ambiguous), &ambiguous);
+
+        assert!(clear_conf.score > 0.7);
+        assert!(ambiguous_conf.score < 0.3);
+        assert_eq!(clear_conf.epistemic_uncertainty, 0.0);
+        assert_eq!(ambiguous_conf.epistemic_uncertainty, 0.5);
+        assert!(ambiguous_conf.aleatoric_uncertainty > clear_conf.aleatoric_uncertainty);
+
+        let empty = assess(&[], &[]);
+        assert_eq!((empty.score, empty.epistemic_uncertainty), (0.0, 1.0));
+    }
+}
@@ -0,0 +1,829 @@
+//! Kho bộ ba (subject, predicate, object) trong bộ nhớ cho stage `KnowledgeGraphQuery`.
+//!
+//! Mỗi bộ ba mang nguồn gốc (file/URL đã nạp) và độ tin cậy. Duyệt đồ thị đi theo cả hai
+//! chiều của cạnh; điểm của một đường đi là điểm của nút xuất phát nhân với độ tin cậy của
+//! từng cạnh và hệ số suy giảm theo số bước.
+
+use super::vector_index::VectorIndex;
+use super::RetrievalError;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Hệ số suy giảm điểm cho mỗi bước đi thêm
+pub const HOP_DECAY: f32 = 0.8;
+/// Số nút tối đa được mở rộng ở mỗi bước duyệt
+pub const TRAVERSAL_FRONTIER: usize = 256;
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const LABEL_PREDICATES: [&str; 3] = [
+    "http://www.w3.org/2000/01/rdf-schema#label",
+    "http://schema.org/name",
+    "name",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triple {
+    pub subject: String,
+    pub predicate: String,
+    pub object: String,
+    /// `object` là giá trị literal (chuỗi, số, ...) chứ không phải một nút
+    pub literal: bool,
+    /// Nguồn của bộ ba (đường dẫn file, URL, ...)
+    pub provenance: Option<String>,
+    /// Độ tin cậy trong [0, 1]
+    pub confidence: f32,
+}
+
+impl Triple {
+    /// Quan hệ giữa hai nút.
+    pub fn new(subject: &str, predicate: &str, object: &str) -> Self {
+        Self {
+            subject: subject.to_string(),
+            predicate: predicate.to_string(),
+            object: object.to_string(),
+            literal: false,
+            provenance: None,
+            confidence: 1.0,
+        }
+    }
+
+    /// Thuộc tính literal của nút `subject`.
+    pub fn attribute(subject: &str, predicate: &str, value: &str) -> Self {
+        Self {
+            literal: true,
+            ..Self::new(subject, predicate, value)
+        }
+    }
+}
+
+/// Một đường đi từ nút được liên kết với truy vấn; `triples` là chỉ số trong kho.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KgPath {
+    pub nodes: Vec<String>,
+    pub triples: Vec<usize>,
+    pub score: f32,
+}
+
+impl KgPath {
+    pub fn end(&self) -> &str {
+        self.nodes.last().map(String::as_str).unwrap_or_default()
+    }
+
+    pub fn hops(&self) -> usize {
+        self.triples.len()
+    }
+}
+
+/// Kho bộ ba. Bộ ba có object là nút tạo thành cạnh của đồ thị; bộ ba literal là thuộc
+/// tính của subject và không tạo nút mới. Bộ ba có vị từ nhãn (`rdfs:label`, `name`, ...)
+/// luôn được coi là literal.
+#[derive(Debug, Default)]
+pub struct TripleStore {
+    triples: Vec<Triple>,
+    /// (subject, predicate, object, literal) → chỉ số, để gộp bộ ba trùng
+    index: HashMap<(String, String, String, bool), usize>,
+    /// nút → chỉ số các cạnh có nút đó là subject hoặc object
+    adjacency: HashMap<String, Vec<usize>>,
+    /// nút → chỉ số các bộ ba literal của nút
+    attributes: HashMap<String, Vec<usize>>,
+    /// Từ đầu tiên của nhãn → các nút có nhãn bắt đầu bằng từ đó (liên kết nguyên văn)
+    label_index: HashMap<String, HashSet<String>>,
+    /// Nhãn đã tách từ của từng nút
+    label_tokens: HashMap<String, Vec<String>>,
+    /// Chỉ mục vector trên embedding nhãn của các nút, dùng cho liên kết thực thể
+    node_vectors: Option<VectorIndex>,
+    /// nút → slot trong `node_vectors`
+    node_slots: HashMap<String, usize>,
+    slot_nodes: Vec<String>,
+    /// Các nút có embedding ứng với nhãn hiện tại
+    embedded: HashSet<String>,
+}
+
+impl TripleStore {
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    pub fn triples(&self) -> &[Triple] {
+        &self.triples
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.adjacency.keys().map(String::as_str)
+    }
+
+    pub fn contains_node(&self, node: &str) -> bool {
+        self.adjacency.contains_key(node)
+    }
+
+    /// Các thuộc tính literal `(vị từ, giá trị)` của nút.
+    pub fn attributes(&self, node: &str) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes.get(node).into_iter().flatten().map(|&i| {
+            (
+                self.triples[i].predicate.as_str(),
+                self.triples[i].object.as_str(),
+            )
+        })
+    }
+
+    /// Thêm bộ ba; bộ ba trùng giữ độ tin cậy cao nhất và nguồn tương ứng.
+    pub fn insert(&mut self, mut triple: Triple) {
+        triple.confidence = triple.confidence.clamp(0.0, 1.0);
+        triple.literal |= LABEL_PREDICATES.contains(&triple.predicate.as_str());
+        let key = (
+            triple.subject.clone(),
+            triple.predicate.clone(),
+            triple.object.clone(),
+            triple.literal,
+        );
+        if let Some(&i) = self.index.get(&key) {
+            if triple.confidence > self.triples[i].confidence {
+                self.triples[i] = triple;
+            }
+            return;
+        }
+        let i = self.triples.len();
+        let subject = triple.subject.clone();
+        let is_label = LABEL_PREDICATES.contains(&triple.predicate.as_str());
+        if triple.literal {
+            self.attributes.entry(subject.clone()).or_default().push(i);
+            self.adjacency.entry(subject.clone()).or_default();
+        } else {
+            self.adjacency.entry(subject.clone()).or_default().push(i);
+            if triple.object != triple.subject {
+                self.adjacency
+                    .entry(triple.object.clone())
+                    .or_default()
+                    .push(i);
+            }
+        }
+        let object = (!triple.literal).then(|| triple.object.clone());
+        self.index.insert(key, i);
+        self.triples.push(triple);
+
+        for node in std::iter::once(subject).chain(object) {
+            if is_label || !self.label_tokens.contains_key(&node) {
+                self.refresh_label(&node);
+            }
+        }
+    }
+
+    /// Chỉ số bộ ba nhãn được dùng cho nút (bộ ba nhãn đầu tiên).
+    fn label_triple(&self, node: &str) -> Option<usize> {
+        self.attributes
+            .get(node)?
+            .iter()
+            .copied()
+            .find(|&i| LABEL_PREDICATES.contains(&self.triples[i].predicate.as_str()))
+    }
+
+    /// Cập nhật chỉ mục nhãn của nút; embedding của nhãn cũ bị bỏ để được embed lại.
+    fn refresh_label(&mut self, node: &str) {
+        let tokens = super::bm25::tokenize(&self.label(node));
+        if let Some(old) = self.label_tokens.insert(node.to_string(), tokens.clone()) {
+            if old == tokens {
+                return;
+            }
+            if let Some(nodes) = old.first().and_then(|t| self.label_index.get_mut(t)) {
+                nodes.remove(node);
+            }
+        }
+        if let Some(first) = tokens.first() {
+            self.label_index
+                .entry(first.clone())
+                .or_default()
+                .insert(node.to_string());
+        }
+        if self.embedded.remove(node) {
+            if let (Some(vectors), Some(&slot)) =
+                (&mut self.node_vectors, self.node_slots.get(node))
+            {
+                vectors.remove(slot);
+            }
+        }
+    }
+
+    /// Nhãn hiển thị của nút: `rdfs:label`/`name` nếu có, nếu không là phần cuối của IRI.
+    pub fn label(&self, node: &str) -> String {
+        self.label_triple(node)
+            .map(|i| self.triples[i].object.clone())
+            .unwrap_or_else(|| local_name(node).to_string())
+    }
+
+    /// Embed nhãn của các nút chưa có embedding hoặc có nhãn mới từ lần embed trước.
+    pub fn index_embeddings(
+        &mut self,
+        embed: impl Fn(&str) -> Result<Vec<f32>, RetrievalError>,
+    ) -> Result<(), RetrievalError> {
+        let mut pending: Vec<String> = self
+            .adjacency
+            .keys()
+            .filter(|node| !self.embedded.contains(*node))
+            .cloned()
+            .collect();
+        pending.sort_unstable();
+        for node in pending {
+            let embedding = embed(&self.label(&node))?;
+            let slot = match self.node_slots.get(&node) {
+                Some(&slot) => slot,
+                None => {
+                    self.slot_nodes.push(node.clone());
+                    self.node_slots
+                        .insert(node.clone(), self.slot_nodes.len() - 1);
+                    self.slot_nodes.len() - 1
+                }
+            };
+            self.node_vectors
+                .get_or_insert_with(|| VectorIndex::new(embedding.len()))
+                .insert(slot, &embedding);
+            self.embedded.insert(node);
+        }
+        Ok(())
+    }
+
+    pub fn clear_embeddings(&mut self) {
+        self.node_vectors = None;
+        self.embedded.clear();
+    }
+
+    /// Liên kết văn bản truy vấn với các nút: nhãn xuất hiện nguyên vẹn trong truy vấn được
+    /// điểm 1, các nút còn lại theo cosine lớn nhất giữa embedding nhãn và `mentions`
+    /// (embedding của các cụm từ trong truy vấn).
+    ///
+    /// Khớp nguyên văn tra chỉ mục theo từ đầu của nhãn và khớp embedding tra chỉ mục
+    /// vector, nên chi phí không tăng tuyến tính theo số nút.
+    pub fn link_entities(
+        &self,
+        text: &str,
+        mentions: &[Vec<f32>],
+        min_similarity: f32,
+        limit: usize,
+    ) -> Vec<(String, f32)> {
+        let query_tokens = super::bm25::tokenize(text);
+        let mut scores: HashMap<&str, f32> = HashMap::new();
+        for (start, token) in query_tokens.iter().enumerate() {
+            for node in self.label_index.get(token).into_iter().flatten() {
+                let label = &self.label_tokens[node];
+                if query_tokens[start..].starts_with(label) {
+                    scores.insert(node, 1.0);
+                }
+            }
+        }
+        if let Some(vectors) = &self.node_vectors {
+            for mention in mentions {
+                for (slot, similarity) in vectors.search(mention, limit) {
+                    if similarity < min_similarity {
+                        continue;
+                    }
+                    let score = scores
+                        .entry(self.slot_nodes[slot].as_str())
+                        .or_insert(similarity);
+                    *score = score.max(similarity);
+                }
+            }
+        }
+        let mut seeds: Vec<(String, f32)> = scores
+            .into_iter()
+            .filter(|&(_, score)| score >= min_similarity)
+            .map(|(node, score)| (node.to_string(), score))
+            .collect();
+        seeds.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        seeds.truncate(limit);
+        seeds
+    }
+
+    /// Duyệt theo chiều rộng tối đa `max_hops` bước từ các nút hạt giống; giữ đường đi tốt
+    /// nhất tới mỗi nút đích (không tính chính các hạt giống), sắp giảm dần theo điểm.
+    ///
+    /// Mỗi nút chỉ được mở rộng lại khi tới được với điểm cao hơn, và mỗi bước chỉ giữ
+    /// `TRAVERSAL_FRONTIER` nút có điểm cao nhất, nên chi phí bị chặn bởi
+    /// `max_hops × TRAVERSAL_FRONTIER × bậc của nút` thay vì số đường đi.
+    pub fn traverse(&self, seeds: &[(String, f32)], max_hops: usize) -> Vec<KgPath> {
+        let mut best: HashMap<&str, KgPath> = HashMap::new();
+        let mut frontier: Vec<&str> = Vec::new();
+        for (node, score) in seeds {
+            if best.get(node.as_str()).is_none_or(|b| *score > b.score) {
+                best.insert(
+                    node.as_str(),
+                    KgPath {
+                        nodes: vec![node.clone()],
+                        triples: Vec::new(),
+                        score: *score,
+                    },
+                );
+                frontier.push(node.as_str());
+            }
+        }
+
+        for _ in 0..max_hops {
+            let mut improved: Vec<&str> = Vec::new();
+            let paths: Vec<KgPath> = frontier.iter().map(|node| best[node].clone()).collect();
+            for path in &paths {
+                let current = path.end();
+                for &i in self.adjacency.get(current).into_iter().flatten() {
+                    let triple = &self.triples[i];
+                    let next = if triple.subject == current {
+                        triple.object.as_str()
+                    } else {
+                        triple.subject.as_str()
+                    };
+                    let score = path.score * triple.confidence * HOP_DECAY;
+                    if score <= 0.0 || best.get(next).is_some_and(|b| score <= b.score) {
+                        continue;
+                    }
+                    let mut extended = path.clone();
+                    extended.nodes.push(next.to_string());
+                    extended.triples.push(i);
+                    extended.score = score;
+                    best.insert(next, extended);
+                    improved.push(next);
+                }
+            }
+            improved.sort_unstable();
+            improved.dedup();
+            improved.sort_by(|a, b| {
+                best[b]
+                    .score
+                    .total_cmp(&best[a].score)
+                    .then_with(|| a.cmp(b))
+            });
+            improved.truncate(TRAVERSAL_FRONTIER);
+            frontier = improved;
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        let seed_nodes: HashSet<&str> = seeds.iter().map(|(node, _)| node.as_str()).collect();
+        let mut paths: Vec<KgPath> = best
+            .into_iter()
+            .filter(|(node, _)| !seed_nodes.contains(node))
+            .map(|(_, path)| path)
+            .collect();
+        paths.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.end().cmp(b.end()))
+        });
+        paths
+    }
+
+    /// Diễn giải đường đi thành câu, ví dụ `Hà Nội —capital of→ Việt Nam`.
+    pub fn describe(&self, path: &KgPath) -> String {
+        let mut text = self.label(&path.nodes[0]);
+        for (step, &i) in path.triples.iter().enumerate() {
+            let triple = &self.triples[i];
+            let arrow = if triple.subject == path.nodes[step] {
+                format!(" —{}→ ", local_name(&triple.predicate))
+            } else {
+                format!(" ←{}— ", local_name(&triple.predicate))
+            };
+            text.push_str(&arrow);
+            text.push_str(&self.label(&path.nodes[step + 1]));
+        }
+        text
+    }
+
+    /// Nạp file `.nt` (N-Triples) hoặc `.jsonld`/`.json` (JSON-LD); trả về số bộ ba mới.
+    pub fn load_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        confidence: f32,
+    ) -> Result<usize, RetrievalError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| RetrievalError::KnowledgeGraph(format!("{}: {}", path.display(), e)))?;
+        let provenance = path.display().to_string();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("nt") => self.load_ntriples(&content, &provenance, confidence),
+            Some("jsonld") | Some("json") => {
+                let json: Value = serde_json::from_str(&content).map_err(|e| {
+                    RetrievalError::KnowledgeGraph(format!("{}: {}", path.display(), e))
+                })?;
+                self.load_jsonld(&json, &provenance, confidence)
+            }
+            _ => Err(RetrievalError::KnowledgeGraph(format!(
+                "Không nhận diện được định dạng của {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Nạp N-Triples: mỗi dòng `<s> <p> <o> .`, bỏ qua dòng trống và chú thích.
+    pub fn load_ntriples(
+        &mut self,
+        content: &str,
+        provenance: &str,
+        confidence: f32,
+    ) -> Result<usize, RetrievalError> {
+        let before = self.len();
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |msg: &str| {
+                RetrievalError::KnowledgeGraph(format!("{}:{}: {}", provenance, line_no + 1, msg))
+            };
+            let mut rest = line;
+            let mut terms = Vec::with_capacity(3);
+            for _ in 0..3 {
+                let (term, remaining) = parse_nt_term(rest.trim_start()).map_err(|m| error(&m))?;
+                terms.push(term);
+                rest = remaining;
+            }
+            if rest.trim() != "." {
+                return Err(error("thiếu dấu '.' cuối bộ ba"));
+            }
+            let (object, literal) = match terms.pop() {
+                Some(NtTerm::Node(iri)) => (iri, false),
+                Some(NtTerm::Literal(value)) => (value, true),
+                None => return Err(error("thiếu object")),
+            };
+            let (Some(NtTerm::Node(predicate)), Some(NtTerm::Node(subject))) =
+                (terms.pop(), terms.pop())
+            else {
+                return Err(error("subject và predicate phải là IRI hoặc blank node"));
+            };
+            self.insert(Triple {
+                subject,
+                predicate,
+                object,
+                literal,
+                provenance: Some(provenance.to_string()),
+                confidence,
+            });
+        }
+        Ok(self.len() - before)
+    }
+
+    /// Nạp JSON-LD dạng rút gọn: node object, mảng node hoặc `@graph`. `@context` chỉ
+    /// dùng để mở rộng tiền tố và `@vocab`; node lồng nhau được nạp đệ quy.
+    pub fn load_jsonld(
+        &mut self,
+        json: &Value,
+        provenance: &str,
+        confidence: f32,
+    ) -> Result<usize, RetrievalError> {
+        let before = self.len();
+        let context = JsonLdContext::from(json.get("@context"));
+        let nodes: Vec<&Value> = match json {
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(obj) => match obj.get("@graph") {
+                Some(Value::Array(items)) => items.iter().collect(),
+                Some(_) => {
+                    return Err(RetrievalError::KnowledgeGraph(
+                        "@graph phải là mảng".to_string(),
+                    ))
+                }
+                None => vec![json],
+            },
+            _ => {
+                return Err(RetrievalError::KnowledgeGraph(
+                    "JSON-LD phải là object hoặc mảng".to_string(),
+                ))
+            }
+        };
+        let mut blank = 0;
+        for node in nodes {
+            self.load_jsonld_node(node, &context, provenance, confidence, &mut blank)?;
+        }
+        Ok(self.len() - before)
+    }
+
+    fn load_jsonld_node(
+        &mut self,
+        node: &Value,
+        context: &JsonLdContext,
+        provenance: &str,
+        confidence: f32,
+        blank: &mut usize,
+    ) -> Result<String, RetrievalError> {
+        let obj = node.as_object().ok_or_else(|| {
+            RetrievalError::KnowledgeGraph(format!("Node JSON-LD không hợp lệ: {}", node))
+        })?;
+        let id = match obj.get("@id").and_then(Value::as_str) {
+            Some(id) => context.expand(id),
+            None => {
+                *blank += 1;
+                format!("_:b{}", blank)
+            }
+        };
+        let add = |store: &mut Self, predicate: String, object: String, literal: bool| {
+            store.insert(Triple {
+                subject: id.clone(),
+                predicate,
+                object,
+                literal,
+                provenance: Some(provenance.to_string()),
+                confidence,
+            })
+        };
+        for (key, value) in obj {
+            if key == "@type" {
+                for ty in one_or_many(value) {
+                    if let Some(ty) = ty.as_str() {
+                        add(self, RDF_TYPE.to_string(), context.expand(ty), false);
+                    }
+                }
+                continue;
+            }
+            if key.starts_with('@') {
+                continue;
+            }
+            let predicate = context.expand(key);
+            for item in one_or_many(value) {
+                let (object, is_literal) = match item {
+                    Value::Object(inner) if inner.contains_key("@value") => {
+                        (literal(&inner["@value"]), true)
+                    }
+                    Value::Object(inner) if inner.len() == 1 && inner.contains_key("@id") => (
+                        context.expand(inner["@id"].as_str().unwrap_or_default()),
+                        false,
+                    ),
+                    Value::Object(_) => (
+                        self.load_jsonld_node(item, context, provenance, confidence, blank)?,
+                        false,
+                    ),
+                    Value::Null => continue,
+                    other => (literal(other), true),
+                };
+                add(self, predicate.clone(), object, is_literal);
+            }
+        }
+        Ok(id)
+    }
+}
+
+fn one_or_many(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    }
+}
+
+fn literal(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Phần cuối của IRI (sau `#` hoặc `/` cuối cùng).
+fn local_name(iri: &str) -> &str {
+    iri.rsplit(['#', '/'])
+        .next()
+        .filter(|s| !s.is_empty())
+        .unwrap_or(iri)
+}
+
+/// Term N-Triples: IRI/blank node, hoặc literal.
+enum NtTerm {
+    Node(String),
+    Literal(String),
+}
+
+/// Đọc một term N-Triples: `<iri>`, `_:blank` hoặc literal `"..."` (bỏ `@lang`/`^^<type>`).
+/// Escape `\uXXXX`/`\UXXXXXXXX` được giải mã trong cả IRI và literal.
+fn parse_nt_term(input: &str) -> Result<(NtTerm, &str), String> {
+    if let Some(rest) = input.strip_prefix('<') {
+        let end = rest.find('>').ok_or("IRI thiếu '>'")?;
+        return Ok((NtTerm::Node(unescape_nt(&rest[..end])?), &rest[end + 1..]));
+    }
+    if input.starts_with("_:") {
+        let end = input.find(char::is_whitespace).unwrap_or(input.len());
+        return Ok((NtTerm::Node(input[..end].to_string()), &input[end..]));
+    }
+    if let Some(rest) = input.strip_prefix('"') {
+        let mut escaped = false;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                let closing = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                closing
+            })
+            .map(|(i, _)| i)
+            .ok_or("literal thiếu dấu '\"' đóng")?;
+        let value = unescape_nt(&rest[..end])?;
+        let mut rest = &rest[end + 1..];
+        if let Some(tagged) = rest.strip_prefix('@') {
+            let end = tagged.find(char::is_whitespace).unwrap_or(tagged.len());
+            rest = &tagged[end..];
+        } else if let Some(typed) = rest.strip_prefix("^^<") {
+            let end = typed.find('>').ok_or("kiểu dữ liệu thiếu '>'")?;
+            rest = &typed[end + 1..];
+        }
+        return Ok((NtTerm::Literal(value), rest));
+    }
+    Err(format!(
+        "term không hợp lệ: '{}'",
+        input.chars().take(20).collect::<String>()
+    ))
+}
+
+/// Giải mã escape của N-Triples: `\t \b \n \r \f \" \' \\`, `\uXXXX` và `\UXXXXXXXX`.
+fn unescape_nt(text: &str) -> Result<String, String> {
+    let mut value = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let decoded = match chars.next() {
+            Some('t') => '\t',
+            Some('b') => '\u{8}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('f') => '\u{c}',
+            Some(c @ ('"' | '\'' | '\\')) => c,
+            Some(kind @ ('u' | 'U')) => {
+                let len = if kind == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(len).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == len && hex.chars().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| format!("escape '\\{}{}' không hợp lệ", kind, hex))?
+            }
+            Some(other) => return Err(format!("escape '\\{}' không hợp lệ", other)),
+            None => return Err("chuỗi kết thúc bằng '\\'".to_string()),
+        };
+        value.push(decoded);
+    }
+    Ok(value)
+}
+
+/// Phần `@context` được hỗ trợ: `@vocab` và ánh xạ tiền tố/thuật ngữ sang IRI.
+#[derive(Debug, Default)]
+struct JsonLdContext {
+    vocab: Option<String>,
+    terms: HashMap<String, String>,
+}
+
+impl JsonLdContext {
+    fn from(context: Option<&Value>) -> Self {
+        let mut out = Self::default();
+        for ctx in context.map(one_or_many).unwrap_or_default() {
+            let Some(obj) = ctx.as_object() else {
+                continue;
+            };
+            for (key, value) in obj {
+                let iri = match value {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Object(def) => {
+                        def.get("@id").and_then(Value::as_str).map(str::to_string)
+                    }
+                    _ => None,
+                };
+                match (key.as_str(), iri) {
+                    ("@vocab", Some(iri)) => out.vocab = Some(iri),
+                    (_, Some(iri)) if !key.starts_with('@') => {
+                        out.terms.insert(key.clone(), iri);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        out
+    }
+
+    fn expand(&self, term: &str) -> String {
+        if let Some(iri) = self.terms.get(term) {
+            return iri.clone();
+        }
+        if let Some((prefix, suffix)) = term.split_once(':') {
+            if let Some(base) = self.terms.get(prefix) {
+                return format!("{}{}", base, suffix);
+            }
+            // IRI tuyệt đối hoặc blank node
+            return term.to_string();
+        }
+        match &self.vocab {
+            Some(vocab) => format!("{}{}", vocab, term),
+            None => term.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntriples_parsing_and_dedup() {
+        let mut store = TripleStore::default();
+        let nt = r#"
+            # thủ đô
+            <http://ex.org/Hanoi> <http://ex.org/capitalOf> <http://ex.org/Vietnam> .
+            <http://ex.org/Hanoi> <http://www.w3.org/2000/01/rdf-schema#label> "Hà Nội"@vi .
+            _:pop <http://ex.org/value> "8053663"^^<http://www.w3.org/2001/XMLSchema#integer> .
+            <http://ex.org/Hanoi> <http://ex.org/capitalOf> <http://ex.org/Vietnam> .
+        "#;
+        assert_eq!(store.load_ntriples(nt, "test.nt", 0.9).unwrap(), 3);
+        assert_eq!(store.label("http://ex.org/Hanoi"), "Hà Nội");
+        assert_eq!(store.label("http://ex.org/Vietnam"), "Vietnam");
+        assert_eq!(store.triples()[2].object, "8053663");
+        assert_eq!(store.triples()[0].provenance.as_deref(), Some("test.nt"));
+        assert!(store.load_ntriples("<a> <b> <c>", "bad.nt", 1.0).is_err());
+    }
+
+    #[test]
+    fn test_traversal_scores_paths_by_confidence_and_hops() {
+        let mut store = TripleStore::default();
+        store.insert(Triple::new("hanoi", "capitalOf", "vietnam"));
+        store.insert(Triple {
+            confidence: 0.5,
+            ..Triple::new("vietnam", "memberOf", "asean")
+        });
+        store.insert(Triple::new("thailand", "memberOf", "asean"));
+
+        let seeds = vec![("hanoi".to_string(), 1.0)];
+        let one_hop = store.traverse(&seeds, 1);
+        assert_eq!(one_hop.len(), 1);
+        assert_eq!(one_hop[0].end(), "vietnam");
+        assert!((one_hop[0].score - HOP_DECAY).abs() < 1e-6);
+
+        let three_hops = store.traverse(&seeds, 3);
+        let thailand = three_hops.iter().find(|p| p.end() == "thailand").unwrap();
+        assert_eq!(thailand.hops(), 3);
+        assert!((thailand.score - 0.5 * HOP_DECAY.powi(3)).abs() < 1e-6);
+        assert_eq!(
+            store.describe(thailand),
+            "hanoi —capitalOf→ vietnam —memberOf→ asean ←memberOf— thailand"
+        );
+    }
+
+    #[test]
+    fn test_ntriples_unicode_escapes_and_literal_attributes() {
+        let mut store = TripleStore::default();
+        let nt = concat!(
+            r#"<http://ex.org/caf\u00E9> <http://www.w3.org/2000/01/rdf-schema#label> "H\u00E0 N\u1ED9i \U0001F600\t\"x\""@vi ."#,
+            "\n",
+            r#"<http://ex.org/café> <http://ex.org/population> "8053663" ."#,
+        );
+        assert_eq!(store.load_ntriples(nt, "u.nt", 1.0).unwrap(), 2);
+        let node = "http://ex.org/café";
+        assert_eq!(store.label(node), "Hà Nội 😀\t\"x\"");
+        // Literal là thuộc tính của nút, không phải nút mới
+        assert_eq!(store.nodes().collect::<Vec<_>>(), vec![node]);
+        let attributes: Vec<(&str, &str)> = store.attributes(node).collect();
+        assert_eq!(attributes[1], ("http://ex.org/population", "8053663"));
+        assert!(store.triples().iter().all(|t| t.literal));
+
+        for bad in [
+            r#"<a> <b> "\u12G4" ."#,
+            r#"<a> <b> "\uD800" ."#,
+            r#"<a> <b> "\q" ."#,
+        ] {
+            assert!(store.load_ntriples(bad, "bad.nt", 1.0).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_relabelled_nodes_are_embedded_again() {
+        let mut store = TripleStore::default();
+        store.insert(Triple::new("a", "rel", "b"));
+        let embedded = std::cell::RefCell::new(Vec::new());
+        let embed = |label: &str| {
+            embedded.borrow_mut().push(label.to_string());
+            Ok(vec![label.len() as f32, 1.0])
+        };
+        store.index_embeddings(embed).unwrap();
+        assert_eq!(*embedded.borrow(), vec!["a", "b"]);
+
+        store.insert(Triple::attribute("a", "name", "Alpha Centauri"));
+        store.index_embeddings(embed).unwrap();
+        assert_eq!(embedded.borrow().len(), 3);
+        assert_eq!(embedded.borrow()[2], "Alpha Centauri");
+        store.index_embeddings(embed).unwrap();
+        assert_eq!(embedded.borrow().len(), 3);
+
+        // Nhãn nhiều từ được khớp nguyên văn qua chỉ mục nhãn
+        let seeds = store.link_entities("near alpha centauri today", &[], 0.5, 5);
+        assert_eq!(seeds, vec![("a".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn test_traversal_on_dense_graph_is_bounded() {
+        let mut store = TripleStore::default();
+        let n = 60;
+        for i in 0..n {
+            for j in 0..n {
+                if i != j {
+                    store.insert(Triple::new(&format!("n{}", i), "near", &format!("n{}", j)));
+                }
+            }
+        }
+        // Liệt kê mọi đường đi đơn sẽ có ~60^6 đường; BFS theo điểm tốt nhất thì không
+        let paths = store.traverse(&[("n0".to_string(), 1.0)], 6);
+        assert_eq!(paths.len(), n - 1);
+        assert!(paths.iter().all(|p| p.hops() == 1 && p.nodes[0] == "n0"));
+    }
+}
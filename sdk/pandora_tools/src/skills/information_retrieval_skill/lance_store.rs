@@ -0,0 +1,681 @@
+//! Tầng lưu trữ bền vững trên LanceDB cho tài liệu của engine.
+//!
+//! Bảng có schema cố định ([`document_schema`]): `id`, `content`, `embedding`
+//! (`FixedSizeList<Float32>`, null khi tài liệu chưa có embedding), `metadata` (JSONB với
+//! extension `lance.json`) và hai mốc thời gian `created_at`/`updated_at`. Ghi theo lô bằng
+//! merge-insert trên `id`; tìm kiếm vector dùng khoảng cách cosine và lọc trước theo
+//! [`MetadataFilter`], được dịch sang biểu thức SQL của Lance (`json_get_*` trên `metadata`).
+
+use super::{Document, RetrievalError};
+use arrow_array::types::Float32Type;
+use arrow_array::{
+    Array, FixedSizeListArray, Float32Array, LargeBinaryArray, RecordBatch, RecordBatchIterator,
+    StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use lance_arrow::json::{decode_json, encode_json, json_field};
+use lancedb::index::vector::IvfPqIndexBuilder;
+use lancedb::index::{Index, IndexType};
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::{Connection, DistanceType, Table};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub const ID_COLUMN: &str = "id";
+pub const CONTENT_COLUMN: &str = "content";
+pub const EMBEDDING_COLUMN: &str = "embedding";
+pub const METADATA_COLUMN: &str = "metadata";
+pub const CREATED_AT_COLUMN: &str = "created_at";
+pub const UPDATED_AT_COLUMN: &str = "updated_at";
+
+/// Số hàng tối đa trong một `RecordBatch` khi ghi
+pub const WRITE_BATCH_SIZE: usize = 1_024;
+/// PQ 8 bit huấn luyện 256 centroid cho mỗi sub-vector nên cần ít nhất chừng ấy hàng
+pub const MIN_ROWS_FOR_PQ: usize = 256;
+/// Số ứng viên đọc thêm (theo bội của `k`) để tính lại khoảng cách chính xác sau PQ
+const REFINE_FACTOR: u32 = 10;
+
+fn query_error(e: impl std::fmt::Display) -> RetrievalError {
+    RetrievalError::LanceDBQuery(e.to_string())
+}
+
+/// Schema của bảng tài liệu với embedding `dim` chiều.
+pub fn document_schema(dim: usize) -> SchemaRef {
+    let timestamp = DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()));
+    Arc::new(Schema::new(vec![
+        Field::new(ID_COLUMN, DataType::Utf8, false),
+        Field::new(CONTENT_COLUMN, DataType::Utf8, false),
+        Field::new(
+            EMBEDDING_COLUMN,
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dim as i32,
+            ),
+            true,
+        ),
+        // Các UDF `json_get_*` của Lance chỉ đọc được cột JSONB
+        json_field(METADATA_COLUMN, false),
+        Field::new(CREATED_AT_COLUMN, timestamp.clone(), false),
+        Field::new(UPDATED_AT_COLUMN, timestamp, false),
+    ]))
+}
+
+/// Một hàng của bảng: tài liệu cùng các mốc thời gian do kho quản lý.
+#[derive(Debug, Clone)]
+pub struct StoredDocument {
+    pub document: Document,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Tham số của chỉ mục IVF-PQ; `None` lấy giá trị tự chọn theo dữ liệu.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IvfPqConfig {
+    /// Số cụm IVF, mặc định √(số hàng)
+    pub num_partitions: Option<u32>,
+    /// Số sub-vector của PQ, phải chia hết số chiều; mặc định mỗi sub-vector 8 chiều
+    pub num_sub_vectors: Option<u32>,
+}
+
+/// Điều kiện trên `metadata` của tài liệu.
+///
+/// Khóa thiếu hoặc khác kiểu cho kết quả sai (không lỗi) khi lọc trong bộ nhớ. Lance chuyển
+/// đổi kiểu chặt chẽ nên một khóa nên giữ cùng kiểu giá trị trên mọi tài liệu, như một cột.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataFilter {
+    /// Bằng một chuỗi, số hoặc bool
+    Eq(String, Value),
+    /// Có khóa và khác giá trị
+    Ne(String, Value),
+    Gt(String, f64),
+    Gte(String, f64),
+    Lt(String, f64),
+    Lte(String, f64),
+    /// Bằng một trong các giá trị
+    In(String, Vec<Value>),
+    Exists(String),
+    And(Vec<MetadataFilter>),
+    Or(Vec<MetadataFilter>),
+    Not(Box<MetadataFilter>),
+}
+
+impl MetadataFilter {
+    pub fn eq(key: &str, value: impl Into<Value>) -> Self {
+        Self::Eq(key.to_string(), value.into())
+    }
+
+    /// Biểu thức SQL cho `only_if` của LanceDB.
+    pub fn to_sql(&self) -> Result<String, RetrievalError> {
+        // Lá so sánh luôn trả true/false (không NULL) để `Not` khớp với lọc trong bộ nhớ
+        let leaf = |key: &str, op: &str, value: &Value| -> Result<String, RetrievalError> {
+            let key = check_key(key)?;
+            let (function, literal) = match value {
+                Value::String(s) => ("json_get_string", format!("'{}'", s.replace('\'', "''"))),
+                Value::Number(n) => (
+                    "json_get_float",
+                    format!("{:?}", n.as_f64().unwrap_or(f64::NAN)),
+                ),
+                Value::Bool(b) => ("json_get_bool", b.to_string()),
+                other => {
+                    return Err(RetrievalError::InvalidInput(format!(
+                        "Chỉ so sánh được metadata với chuỗi, số hoặc bool, nhận {}",
+                        other
+                    )))
+                }
+            };
+            Ok(format!(
+                "coalesce({}({}, '{}') {} {}, false)",
+                function, METADATA_COLUMN, key, op, literal
+            ))
+        };
+        let number = |x: &f64| Value::from(*x);
+        Ok(match self {
+            Self::Eq(key, value) => leaf(key, "=", value)?,
+            Self::Ne(key, value) => leaf(key, "<>", value)?,
+            Self::Gt(key, x) => leaf(key, ">", &number(x))?,
+            Self::Gte(key, x) => leaf(key, ">=", &number(x))?,
+            Self::Lt(key, x) => leaf(key, "<", &number(x))?,
+            Self::Lte(key, x) => leaf(key, "<=", &number(x))?,
+            Self::In(key, values) => Self::Or(
+                values
+                    .iter()
+                    .map(|value| Self::Eq(key.clone(), value.clone()))
+                    .collect(),
+            )
+            .to_sql()?,
+            Self::Exists(key) => format!(
+                "coalesce(json_exists({}, '$.{}'), false)",
+                METADATA_COLUMN,
+                check_key(key)?
+            ),
+            Self::And(filters) | Self::Or(filters) if filters.is_empty() => {
+                matches!(self, Self::And(_)).to_string()
+            }
+            Self::And(filters) => join_sql(filters, " AND ")?,
+            Self::Or(filters) => join_sql(filters, " OR ")?,
+            Self::Not(filter) => format!("NOT ({})", filter.to_sql()?),
+        })
+    }
+
+    /// Đánh giá điều kiện trên metadata trong bộ nhớ, cùng ngữ nghĩa với [`Self::to_sql`].
+    pub fn matches(&self, metadata: &HashMap<String, Value>) -> bool {
+        let number = |key: &str| metadata.get(key).and_then(Value::as_f64);
+        let equals = |key: &str, value: &Value| match (metadata.get(key), value) {
+            (Some(Value::Number(a)), Value::Number(b)) => a.as_f64() == b.as_f64(),
+            (Some(a @ (Value::String(_) | Value::Bool(_))), b) => a == b,
+            _ => false,
+        };
+        match self {
+            Self::Eq(key, value) => equals(key, value),
+            Self::Ne(key, value) => {
+                metadata.get(key).is_some_and(|v| !v.is_null()) && !equals(key, value)
+            }
+            Self::Gt(key, x) => number(key).is_some_and(|v| v > *x),
+            Self::Gte(key, x) => number(key).is_some_and(|v| v >= *x),
+            Self::Lt(key, x) => number(key).is_some_and(|v| v < *x),
+            Self::Lte(key, x) => number(key).is_some_and(|v| v <= *x),
+            Self::In(key, values) => values.iter().any(|value| equals(key, value)),
+            Self::Exists(key) => metadata.contains_key(key),
+            Self::And(filters) => filters.iter().all(|f| f.matches(metadata)),
+            Self::Or(filters) => filters.iter().any(|f| f.matches(metadata)),
+            Self::Not(filter) => !filter.matches(metadata),
+        }
+    }
+}
+
+/// Khóa chỉ gồm chữ, số và `_` để nhúng an toàn vào SQL và JSONPath
+fn check_key(key: &str) -> Result<&str, RetrievalError> {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Ok(key)
+    } else {
+        Err(RetrievalError::InvalidInput(format!(
+            "Khóa metadata '{}' chỉ được gồm chữ, số và '_'",
+            key
+        )))
+    }
+}
+
+fn join_sql(filters: &[MetadataFilter], separator: &str) -> Result<String, RetrievalError> {
+    let parts = filters
+        .iter()
+        .map(|f| Ok(format!("({})", f.to_sql()?)))
+        .collect::<Result<Vec<_>, RetrievalError>>()?;
+    Ok(parts.join(separator))
+}
+
+fn id_list(ids: &[&str]) -> String {
+    let quoted: Vec<String> = ids
+        .iter()
+        .map(|id| format!("'{}'", id.replace('\'', "''")))
+        .collect();
+    format!("{} IN ({})", ID_COLUMN, quoted.join(", "))
+}
+
+/// Bảng tài liệu trong LanceDB.
+#[derive(Clone)]
+pub struct LanceStore {
+    table: Table,
+    schema: SchemaRef,
+    dim: usize,
+}
+
+impl std::fmt::Debug for LanceStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LanceStore")
+            .field("table", &self.table.name())
+            .field("dim", &self.dim)
+            .finish()
+    }
+}
+
+impl LanceStore {
+    /// Mở bảng `name`, tạo bảng rỗng với [`document_schema`] nếu chưa có.
+    pub async fn open(conn: &Connection, name: &str, dim: usize) -> Result<Self, RetrievalError> {
+        let names = conn
+            .table_names()
+            .execute()
+            .await
+            .map_err(|e| RetrievalError::LanceDBConnection(e.to_string()))?;
+        let schema = document_schema(dim);
+        let table = if names.iter().any(|n| n == name) {
+            let table = conn
+                .open_table(name)
+                .execute()
+                .await
+                .map_err(|e| RetrievalError::LanceDBConnection(e.to_string()))?;
+            let existing = table.schema().await.map_err(query_error)?;
+            match existing
+                .field_with_name(EMBEDDING_COLUMN)
+                .map(Field::data_type)
+            {
+                Ok(DataType::FixedSizeList(_, n)) if *n as usize == dim => {}
+                other => {
+                    return Err(RetrievalError::EmbeddingError(format!(
+                        "Bảng '{}' có cột embedding {:?}, engine cần {} chiều",
+                        name, other, dim
+                    )))
+                }
+            }
+            table
+        } else {
+            conn.create_empty_table(name, schema.clone())
+                .execute()
+                .await
+                .map_err(|e| RetrievalError::LanceDBConnection(e.to_string()))?
+        };
+        Ok(Self { table, schema, dim })
+    }
+
+    pub fn table_name(&self) -> &str {
+        self.table.name()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub async fn count(&self) -> Result<usize, RetrievalError> {
+        self.table.count_rows(None).await.map_err(query_error)
+    }
+
+    /// Thêm hoặc thay thế theo `id`; `created_at` của hàng đã có được giữ nguyên.
+    ///
+    /// Tài liệu trùng `id` trong cùng lô thì bản sau thắng. Trả về số hàng đã ghi.
+    pub async fn upsert(&self, documents: &[Document]) -> Result<usize, RetrievalError> {
+        let mut latest: Vec<&Document> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for doc in documents {
+            if !doc.embedding.is_empty() && doc.embedding.len() != self.dim {
+                return Err(RetrievalError::EmbeddingError(format!(
+                    "Tài liệu '{}' có embedding {} chiều, bảng cần {}",
+                    doc.id,
+                    doc.embedding.len(),
+                    self.dim
+                )));
+            }
+            match positions.get(doc.id.as_str()) {
+                Some(&i) => latest[i] = doc,
+                None => {
+                    positions.insert(&doc.id, latest.len());
+                    latest.push(doc);
+                }
+            }
+        }
+
+        let now = Utc::now();
+        let mut batches = Vec::new();
+        for chunk in latest.chunks(WRITE_BATCH_SIZE) {
+            let ids: Vec<&str> = chunk.iter().map(|d| d.id.as_str()).collect();
+            let created = self.created_at(&ids).await?;
+            let rows: Vec<(&Document, DateTime<Utc>)> = chunk
+                .iter()
+                .map(|doc| (*doc, created.get(&doc.id).copied().unwrap_or(now)))
+                .collect();
+            batches.push(self.to_batch(&rows, now).map_err(query_error)?);
+        }
+        if batches.is_empty() {
+            return Ok(0);
+        }
+        let reader = RecordBatchIterator::new(batches.into_iter().map(Ok), self.schema.clone());
+        let mut merge = self.table.merge_insert(&[ID_COLUMN]);
+        merge
+            .when_matched_update_all(None)
+            .when_not_matched_insert_all();
+        merge.execute(Box::new(reader)).await.map_err(query_error)?;
+        Ok(latest.len())
+    }
+
+    /// Xóa theo `id` trong một commit duy nhất; trả về số hàng đã xóa.
+    pub async fn delete(&self, ids: &[&str]) -> Result<usize, RetrievalError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        self.delete_matching(id_list(ids)).await
+    }
+
+    /// Xóa mọi hàng thỏa `filter`; trả về số hàng đã xóa.
+    pub async fn delete_where(&self, filter: &MetadataFilter) -> Result<usize, RetrievalError> {
+        self.delete_matching(filter.to_sql()?).await
+    }
+
+    /// Xóa các hàng thỏa `predicate` trong một commit và đếm chúng từ chính commit đó.
+    ///
+    /// Merge với nguồn rỗng: mọi hàng thỏa `predicate` đều "không khớp nguồn" nên bị xóa,
+    /// và số hàng trả về không lệch khi có ghi đồng thời giữa lúc đếm và lúc xóa.
+    async fn delete_matching(&self, predicate: String) -> Result<usize, RetrievalError> {
+        let empty = RecordBatchIterator::new(std::iter::empty(), self.schema.clone());
+        let mut merge = self.table.merge_insert(&[ID_COLUMN]);
+        merge.when_not_matched_by_source_delete(Some(predicate));
+        let result = merge.execute(Box::new(empty)).await.map_err(query_error)?;
+        Ok(result.num_deleted_rows as usize)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<StoredDocument>, RetrievalError> {
+        let query = self.table.query().only_if(id_list(&[id])).limit(1);
+        let batches: Vec<RecordBatch> = query
+            .execute()
+            .await
+            .map_err(query_error)?
+            .try_collect()
+            .await
+            .map_err(query_error)?;
+        Ok(self
+            .decode(&batches)?
+            .into_iter()
+            .next()
+            .map(|(doc, _)| doc))
+    }
+
+    /// Toàn bộ tài liệu, xếp theo `created_at` rồi `id`.
+    pub async fn scan(&self) -> Result<Vec<StoredDocument>, RetrievalError> {
+        let batches: Vec<RecordBatch> = self
+            .table
+            .query()
+            .execute()
+            .await
+            .map_err(query_error)?
+            .try_collect()
+            .await
+            .map_err(query_error)?;
+        let mut documents: Vec<StoredDocument> = self
+            .decode(&batches)?
+            .into_iter()
+            .map(|(doc, _)| doc)
+            .collect();
+        documents.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.document.id.cmp(&b.document.id))
+        });
+        Ok(documents)
+    }
+
+    /// `k` tài liệu gần `vector` nhất theo cosine trong số tài liệu thỏa `filter`, kèm độ
+    /// tương đồng. Dùng chỉ mục IVF-PQ nếu đã tạo, nếu không thì quét toàn bộ.
+    pub async fn search(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<(StoredDocument, f32)>, RetrievalError> {
+        if vector.len() != self.dim {
+            return Err(RetrievalError::EmbeddingError(format!(
+                "Vector truy vấn có {} chiều, cần {}",
+                vector.len(),
+                self.dim
+            )));
+        }
+        let mut predicate = format!("{} IS NOT NULL", EMBEDDING_COLUMN);
+        if let Some(filter) = filter {
+            predicate = format!("{} AND ({})", predicate, filter.to_sql()?);
+        }
+        let query = self
+            .table
+            .query()
+            .nearest_to(vector)
+            .map_err(query_error)?
+            .column(EMBEDDING_COLUMN)
+            .distance_type(DistanceType::Cosine)
+            .refine_factor(REFINE_FACTOR)
+            .only_if(predicate)
+            .limit(k);
+        let batches: Vec<RecordBatch> = query
+            .execute()
+            .await
+            .map_err(query_error)?
+            .try_collect()
+            .await
+            .map_err(query_error)?;
+        let mut hits: Vec<(StoredDocument, f32)> = self
+            .decode(&batches)?
+            .into_iter()
+            .map(|(doc, distance)| (doc, 1.0 - distance.unwrap_or(1.0)))
+            .collect();
+        hits.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| a.0.document.id.cmp(&b.0.document.id))
+        });
+        Ok(hits)
+    }
+
+    /// Tạo (hoặc tạo lại) chỉ mục IVF-PQ cosine trên cột embedding.
+    pub async fn create_vector_index(&self, config: &IvfPqConfig) -> Result<(), RetrievalError> {
+        let rows = self
+            .table
+            .count_rows(Some(format!("{} IS NOT NULL", EMBEDDING_COLUMN)))
+            .await
+            .map_err(query_error)?;
+        if rows < MIN_ROWS_FOR_PQ {
+            return Err(RetrievalError::LanceDBQuery(format!(
+                "Cần ít nhất {} tài liệu có embedding để huấn luyện IVF-PQ, hiện có {}",
+                MIN_ROWS_FOR_PQ, rows
+            )));
+        }
+        let num_sub_vectors = match config.num_sub_vectors {
+            Some(n) if n == 0 || !self.dim.is_multiple_of(n as usize) => {
+                return Err(RetrievalError::InvalidInput(format!(
+                    "num_sub_vectors = {} không chia hết {} chiều",
+                    n, self.dim
+                )))
+            }
+            Some(n) => n,
+            None => [8, 4, 2, 1]
+                .into_iter()
+                .find(|len| self.dim.is_multiple_of(*len))
+                .map_or(1, |len| (self.dim / len) as u32),
+        };
+        let num_partitions = config
+            .num_partitions
+            .unwrap_or(((rows as f64).sqrt() as u32).max(1));
+        let builder = IvfPqIndexBuilder::default()
+            .distance_type(DistanceType::Cosine)
+            .num_partitions(num_partitions)
+            .num_sub_vectors(num_sub_vectors);
+        self.table
+            .create_index(&[EMBEDDING_COLUMN], Index::IvfPq(builder))
+            .replace(true)
+            .execute()
+            .await
+            .map_err(query_error)
+    }
+
+    pub async fn has_vector_index(&self) -> Result<bool, RetrievalError> {
+        let indices = self.table.list_indices().await.map_err(query_error)?;
+        Ok(indices.iter().any(|index| {
+            index.index_type == IndexType::IvfPq
+                && index.columns.iter().any(|c| c == EMBEDDING_COLUMN)
+        }))
+    }
+
+    /// `created_at` của các `ids` đã có trong bảng
+    async fn created_at(
+        &self,
+        ids: &[&str],
+    ) -> Result<HashMap<String, DateTime<Utc>>, RetrievalError> {
+        let query = self
+            .table
+            .query()
+            .only_if(id_list(ids))
+            .select(Select::columns(&[ID_COLUMN, CREATED_AT_COLUMN]));
+        let batches: Vec<RecordBatch> = query
+            .execute()
+            .await
+            .map_err(query_error)?
+            .try_collect()
+            .await
+            .map_err(query_error)?;
+        let mut created = HashMap::new();
+        for batch in &batches {
+            let ids = column::<StringArray>(batch, ID_COLUMN)?;
+            let times = column::<TimestampMicrosecondArray>(batch, CREATED_AT_COLUMN)?;
+            for i in 0..batch.num_rows() {
+                created.insert(ids.value(i).to_string(), timestamp(times.value(i)));
+            }
+        }
+        Ok(created)
+    }
+
+    fn to_batch(
+        &self,
+        rows: &[(&Document, DateTime<Utc>)],
+        now: DateTime<Utc>,
+    ) -> Result<RecordBatch, ArrowError> {
+        let ids = StringArray::from_iter_values(rows.iter().map(|(d, _)| d.id.as_str()));
+        let contents = StringArray::from_iter_values(rows.iter().map(|(d, _)| d.content.as_str()));
+        let embeddings = FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+            rows.iter().map(|(d, _)| {
+                (!d.embedding.is_empty()).then(|| d.embedding.iter().map(|x| Some(*x)))
+            }),
+            self.dim as i32,
+        );
+        let metadata = rows
+            .iter()
+            .map(|(d, _)| {
+                let json = serde_json::to_string(&d.metadata)
+                    .map_err(|e| ArrowError::JsonError(e.to_string()))?;
+                encode_json(&json).map_err(|e| ArrowError::JsonError(e.to_string()))
+            })
+            .collect::<Result<Vec<Vec<u8>>, ArrowError>>()?;
+        let metadata = LargeBinaryArray::from_iter_values(metadata);
+        let created = TimestampMicrosecondArray::from_iter_values(
+            rows.iter().map(|(_, created)| created.timestamp_micros()),
+        )
+        .with_timezone("UTC");
+        let updated = TimestampMicrosecondArray::from_iter_values(
+            rows.iter().map(|_| now.timestamp_micros()),
+        )
+        .with_timezone("UTC");
+        RecordBatch::try_new(
+            self.schema.clone(),
+            vec![
+                Arc::new(ids),
+                Arc::new(contents),
+                Arc::new(embeddings),
+                Arc::new(metadata),
+                Arc::new(created),
+                Arc::new(updated),
+            ],
+        )
+    }
+
+    /// Đọc các hàng (kèm cột `_distance` nếu có) thành tài liệu
+    fn decode(
+        &self,
+        batches: &[RecordBatch],
+    ) -> Result<Vec<(StoredDocument, Option<f32>)>, RetrievalError> {
+        let mut rows = Vec::new();
+        for batch in batches {
+            let ids = column::<StringArray>(batch, ID_COLUMN)?;
+            let contents = column::<StringArray>(batch, CONTENT_COLUMN)?;
+            let embeddings = column::<FixedSizeListArray>(batch, EMBEDDING_COLUMN)?;
+            let metadata = batch.column_by_name(METADATA_COLUMN);
+            let created = column::<TimestampMicrosecondArray>(batch, CREATED_AT_COLUMN)?;
+            let updated = column::<TimestampMicrosecondArray>(batch, UPDATED_AT_COLUMN)?;
+            let distances = batch
+                .column_by_name("_distance")
+                .and_then(|c| c.as_any().downcast_ref::<Float32Array>());
+            for i in 0..batch.num_rows() {
+                let embedding = if embeddings.is_null(i) {
+                    Vec::new()
+                } else {
+                    let values = embeddings.value(i);
+                    values
+                        .as_any()
+                        .downcast_ref::<Float32Array>()
+                        .ok_or_else(|| query_error("embedding không phải Float32"))?
+                        .values()
+                        .to_vec()
+                };
+                let document = Document {
+                    id: ids.value(i).to_string(),
+                    content: contents.value(i).to_string(),
+                    embedding,
+                    metadata: decode_metadata(metadata, i)?,
+                };
+                rows.push((
+                    StoredDocument {
+                        document,
+                        created_at: timestamp(created.value(i)),
+                        updated_at: timestamp(updated.value(i)),
+                    },
+                    distances.map(|d| d.value(i)),
+                ));
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T, RetrievalError> {
+    batch
+        .column_by_name(name)
+        .and_then(|c| c.as_any().downcast_ref::<T>())
+        .ok_or_else(|| {
+            RetrievalError::LanceDBQuery(format!("Thiếu cột '{}' hoặc sai kiểu dữ liệu", name))
+        })
+}
+
+/// Metadata của hàng `i`: JSONB, hoặc chuỗi JSON nếu Lance đã đổi cột sang `arrow.json` khi đọc
+fn decode_metadata(
+    column: Option<&arrow_array::ArrayRef>,
+    i: usize,
+) -> Result<HashMap<String, Value>, RetrievalError> {
+    let column = column.ok_or_else(|| query_error(format!("Thiếu cột '{}'", METADATA_COLUMN)))?;
+    let json = if let Some(jsonb) = column.as_any().downcast_ref::<LargeBinaryArray>() {
+        decode_json(jsonb.value(i)).map_err(query_error)?
+    } else if let Some(text) = column.as_any().downcast_ref::<StringArray>() {
+        text.value(i).to_string()
+    } else {
+        return Err(query_error(format!(
+            "Cột '{}' có kiểu {:?}",
+            METADATA_COLUMN,
+            column.data_type()
+        )));
+    };
+    serde_json::from_str(&json).map_err(query_error)
+}
+
+fn timestamp(micros: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros(micros).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_metadata_filter_sql_and_in_memory_agree() {
+        let filter = MetadataFilter::And(vec![
+            MetadataFilter::eq("lang", "vi"),
+            MetadataFilter::Or(vec![
+                MetadataFilter::Gte("year".into(), 2020.0),
+                MetadataFilter::Not(Box::new(MetadataFilter::Exists("year".into()))),
+            ]),
+        ]);
+        assert_eq!(
+            filter.to_sql().unwrap(),
+            "(coalesce(json_get_string(metadata, 'lang') = 'vi', false)) AND \
+             ((coalesce(json_get_float(metadata, 'year') >= 2020.0, false)) OR \
+             (NOT (coalesce(json_exists(metadata, '$.year'), false))))"
+        );
+        let metadata =
+            |value: Value| -> HashMap<String, Value> { serde_json::from_value(value).unwrap() };
+        assert!(filter.matches(&metadata(json!({"lang": "vi", "year": 2021}))));
+        assert!(filter.matches(&metadata(json!({"lang": "vi"}))));
+        assert!(!filter.matches(&metadata(json!({"lang": "vi", "year": 2019}))));
+        assert!(!filter.matches(&metadata(json!({"lang": "en"}))));
+
+        let quoted = MetadataFilter::eq("author", "O'Brien").to_sql().unwrap();
+        assert!(quoted.contains("'O''Brien'"));
+        assert!(MetadataFilter::eq("a' OR 1=1 --", 1).to_sql().is_err());
+        assert!(MetadataFilter::eq("tags", json!(["x"])).to_sql().is_err());
+        assert_eq!(MetadataFilter::Or(Vec::new()).to_sql().unwrap(), "false");
+        assert!(!MetadataFilter::Ne("lang".into(), json!("vi")).matches(&HashMap::new()));
+        assert!(MetadataFilter::In("n".into(), vec![json!(1), json!(2)])
+            .matches(&metadata(json!({"n": 2.0}))));
+    }
+}
@@ -0,0 +1,296 @@
+//! Learning-to-rank theo cặp (RankNet tuyến tính) học từ phản hồi của người dùng.
+//!
+//! Điểm của một tài liệu là `s = w·x` trên các đặc trưng trong [`FEATURES`]. Mỗi cặp
+//! (tài liệu tốt hơn `i`, tài liệu kém hơn `j`) của cùng một truy vấn cho một bước SGD trên
+//! log-loss của `P(i ≻ j) = σ(s_i − s_j)` (Burges et al., 2005). Trọng số được lưu theo tên
+//! đặc trưng trong `ResultRanker::feature_weights` nên engine không cần giữ mô hình riêng.
+
+use super::FeedbackRecord;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// Tên các đặc trưng, theo thứ tự trong [`FeatureVector`]
+pub const FEATURES: [&str; 4] = ["bm25", "cosine", "recency", "click_feedback"];
+
+pub type FeatureVector = [f32; FEATURES.len()];
+
+/// Độ chênh relevance tối thiểu để hai phán xét tạo thành một cặp huấn luyện
+pub const MIN_RELEVANCE_GAP: f32 = 0.1;
+/// Relevance từ ngưỡng này trở lên được coi là "liên quan" khi tính MRR
+pub const RELEVANT_THRESHOLD: f32 = 0.5;
+/// Số lượt duyệt tập huấn luyện khi đánh giá trên tập giữ lại
+pub const EVALUATION_EPOCHS: usize = 20;
+/// Chu kỳ bán rã (ngày) của đặc trưng `recency`
+pub const RECENCY_HALF_LIFE_DAYS: f32 = 30.0;
+/// Các khóa metadata chứa thời điểm của tài liệu, theo thứ tự ưu tiên
+pub const RECENCY_KEYS: [&str; 3] = ["updated_at", "timestamp", "created_at"];
+
+/// Mô hình xếp hạng tuyến tính huấn luyện theo cặp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankNet {
+    pub weights: FeatureVector,
+}
+
+impl Default for RankNet {
+    /// Chưa học: chỉ cộng điểm BM25 và cosine, tương tự xếp hạng `Hybrid`
+    fn default() -> Self {
+        Self {
+            weights: [1.0, 1.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl RankNet {
+    /// Đọc trọng số theo tên; đặc trưng thiếu lấy giá trị mặc định.
+    pub fn from_weights(weights: &HashMap<String, f32>) -> Self {
+        let mut model = Self::default();
+        for (i, name) in FEATURES.iter().enumerate() {
+            if let Some(weight) = weights.get(*name) {
+                model.weights[i] = *weight;
+            }
+        }
+        model
+    }
+
+    pub fn to_weights(&self) -> HashMap<String, f32> {
+        FEATURES
+            .iter()
+            .zip(self.weights)
+            .map(|(name, weight)| (name.to_string(), weight))
+            .collect()
+    }
+
+    pub fn score(&self, features: &FeatureVector) -> f32 {
+        self.weights.iter().zip(features).map(|(w, x)| w * x).sum()
+    }
+
+    /// Một bước SGD cho cặp `better ≻ worse` với trọng số `weight`; trả về loss trước bước.
+    pub fn train_pair(
+        &mut self,
+        better: &FeatureVector,
+        worse: &FeatureVector,
+        learning_rate: f32,
+        weight: f32,
+    ) -> f32 {
+        let margin = self.score(better) - self.score(worse);
+        // 1 − σ(margin), tính ổn định cho margin lớn
+        let lambda = 1.0 / (1.0 + margin.exp());
+        for (w, (b, x)) in self.weights.iter_mut().zip(better.iter().zip(worse)) {
+            *w += learning_rate * weight * lambda * (b - x);
+        }
+        // −log σ(margin) = log(1 + e^(−margin))
+        (-margin).exp().ln_1p()
+    }
+
+    /// Huấn luyện trên mọi cặp của một truy vấn; trả về số cặp đã dùng.
+    pub fn train_query(&mut self, judgments: &[Judgment], learning_rate: f32) -> usize {
+        let mut pairs = 0;
+        for a in judgments {
+            for b in judgments {
+                if a.relevance - b.relevance >= MIN_RELEVANCE_GAP {
+                    self.train_pair(
+                        &a.features,
+                        &b.features,
+                        learning_rate,
+                        a.weight.min(b.weight),
+                    );
+                    pairs += 1;
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Relevance của các phán xét theo thứ tự mô hình xếp hạng (hòa điểm giữ thứ tự vào).
+    pub fn ranked_relevances(&self, judgments: &[Judgment]) -> Vec<f32> {
+        let mut ranked: Vec<&Judgment> = judgments.iter().collect();
+        ranked.sort_by(|a, b| self.score(&b.features).total_cmp(&self.score(&a.features)));
+        ranked.iter().map(|j| j.relevance).collect()
+    }
+}
+
+/// Một tài liệu đã được người dùng đánh giá cho một truy vấn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Judgment {
+    pub result_id: String,
+    pub features: FeatureVector,
+    pub relevance: f32,
+    /// Trọng số theo độ mới của phản hồi, trong (0, 1]
+    pub weight: f32,
+}
+
+/// Kết quả đánh giá mô hình trên các truy vấn giữ lại.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankingReport {
+    pub k: usize,
+    pub ndcg_at_k: f32,
+    pub mrr: f32,
+    /// Cùng chỉ số với trọng số chưa học ([`RankNet::default`])
+    pub baseline_ndcg_at_k: f32,
+    pub baseline_mrr: f32,
+    pub train_queries: usize,
+    pub test_queries: usize,
+    pub training_pairs: usize,
+}
+
+/// NDCG@k với gain `2^rel − 1`; danh sách không có tài liệu liên quan cho 0.
+pub fn ndcg_at_k(ranked_relevances: &[f32], k: usize) -> f32 {
+    let dcg = |relevances: &[f32]| -> f32 {
+        relevances
+            .iter()
+            .take(k)
+            .enumerate()
+            .map(|(i, rel)| (rel.exp2() - 1.0) / (i as f32 + 2.0).log2())
+            .sum()
+    };
+    let mut ideal = ranked_relevances.to_vec();
+    ideal.sort_by(|a, b| b.total_cmp(a));
+    let ideal_dcg = dcg(&ideal);
+    if ideal_dcg <= 0.0 {
+        0.0
+    } else {
+        dcg(ranked_relevances) / ideal_dcg
+    }
+}
+
+/// 1 / thứ hạng của tài liệu liên quan đầu tiên, 0 nếu không có.
+pub fn reciprocal_rank(ranked_relevances: &[f32]) -> f32 {
+    ranked_relevances
+        .iter()
+        .position(|rel| *rel >= RELEVANT_THRESHOLD)
+        .map_or(0.0, |i| 1.0 / (i as f32 + 1.0))
+}
+
+/// Độ mới của tài liệu theo metadata: 1 với tài liệu vừa cập nhật, giảm một nửa sau mỗi
+/// [`RECENCY_HALF_LIFE_DAYS`] ngày; 0 nếu không có thời điểm.
+///
+/// Thời điểm là số giây Unix hoặc chuỗi RFC 3339.
+pub fn recency(metadata: &HashMap<String, serde_json::Value>, now: DateTime<Utc>) -> f32 {
+    let timestamp = RECENCY_KEYS
+        .iter()
+        .find_map(|key| match metadata.get(*key)? {
+            serde_json::Value::Number(n) => DateTime::from_timestamp(n.as_f64()? as i64, 0),
+            serde_json::Value::String(s) => DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|t| t.with_timezone(&Utc)),
+            _ => None,
+        });
+    match timestamp {
+        Some(t) => {
+            let age_days = (now - t).num_seconds().max(0) as f32 / 86_400.0;
+            0.5f32.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+        }
+        None => 0.0,
+    }
+}
+
+/// Trung bình relevance (làm trơn về 0) mà người dùng đã cho `result_id`.
+///
+/// Phản hồi cũ hơn nhân thêm `decay` cho mỗi phản hồi đến sau nó. Phản hồi cho truy vấn
+/// `exclude_query` bị bỏ qua để đặc trưng không chứa chính nhãn đang huấn luyện.
+pub fn click_feedback(
+    history: &[FeedbackRecord],
+    result_id: &str,
+    exclude_query: Option<&str>,
+    decay: f32,
+) -> f32 {
+    let mut total = 0.0;
+    let mut weight_sum = 0.0;
+    for (age, record) in history.iter().rev().enumerate() {
+        if record.result_id != result_id
+            || exclude_query.is_some_and(|q| normalize_query(&record.query) == q)
+        {
+            continue;
+        }
+        let weight = decay.powi(age as i32);
+        total += weight * record.relevance_score;
+        weight_sum += weight;
+    }
+    total / (weight_sum + 1.0)
+}
+
+/// Khóa nhóm phản hồi theo truy vấn
+pub fn normalize_query(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranking_metrics() {
+        assert!((ndcg_at_k(&[1.0, 0.5, 0.0], 3) - 1.0).abs() < 1e-6);
+        let swapped = ndcg_at_k(&[0.0, 1.0, 0.5], 3);
+        assert!(swapped > 0.0 && swapped < 0.7);
+        // Chỉ tính k vị trí đầu
+        assert_eq!(ndcg_at_k(&[0.0, 1.0], 1), 0.0);
+        assert_eq!(ndcg_at_k(&[0.0, 0.0], 2), 0.0);
+
+        assert_eq!(reciprocal_rank(&[0.2, 0.4, 0.9]), 1.0 / 3.0);
+        assert_eq!(reciprocal_rank(&[0.2]), 0.0);
+    }
+
+    #[test]
+    fn test_ranknet_learns_pairwise_preference() {
+        let judgments = vec![
+            Judgment {
+                result_id: "spam".into(),
+                features: [0.9, 0.3, 0.0, 0.0],
+                relevance: 0.0,
+                weight: 1.0,
+            },
+            Judgment {
+                result_id: "fresh".into(),
+                features: [0.4, 0.3, 1.0, 0.0],
+                relevance: 1.0,
+                weight: 1.0,
+            },
+        ];
+        let mut model = RankNet::default();
+        assert_eq!(model.ranked_relevances(&judgments), vec![0.0, 1.0]);
+
+        let before = model.train_pair(&judgments[1].features, &judgments[0].features, 0.0, 1.0);
+        for _ in 0..20 {
+            assert_eq!(model.train_query(&judgments, 0.5), 1);
+        }
+        let after = model.train_pair(&judgments[1].features, &judgments[0].features, 0.0, 1.0);
+        assert!(after < before);
+        assert_eq!(model.ranked_relevances(&judgments), vec![1.0, 0.0]);
+        assert!(model.weights[2] > 0.0 && model.weights[0] < 1.0);
+        assert_eq!(RankNet::from_weights(&model.to_weights()), model);
+    }
+
+    #[test]
+    fn test_recency_and_click_features() {
+        let now = DateTime::parse_from_rfc3339("2024-03-31T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let metadata =
+            |key: &str, value: serde_json::Value| HashMap::from([(key.to_string(), value)]);
+        let month_ago = (now.timestamp() - 30 * 86_400) as f64;
+        assert!((recency(&metadata("timestamp", month_ago.into()), now) - 0.5).abs() < 1e-4);
+        assert_eq!(
+            recency(&metadata("updated_at", "2024-03-31T00:00:00Z".into()), now),
+            1.0
+        );
+        assert_eq!(recency(&HashMap::new(), now), 0.0);
+
+        let record = |query: &str, id: &str, relevance: f32| FeedbackRecord {
+            query: query.into(),
+            result_id: id.into(),
+            relevance_score: relevance,
+            timestamp: std::time::Instant::now(),
+        };
+        let history = vec![
+            record("a", "d", 1.0),
+            record("B ", "d", 1.0),
+            record("b", "e", 0.0),
+        ];
+        assert_eq!(click_feedback(&history, "d", None, 1.0), 2.0 / 3.0);
+        // Phản hồi cũ nhất bị giảm trọng số
+        assert!(click_feedback(&history, "d", None, 0.5) < 2.0 / 3.0);
+        assert_eq!(click_feedback(&history, "d", Some("b"), 1.0), 0.5);
+        assert_eq!(click_feedback(&history, "x", None, 1.0), 0.0);
+    }
+}
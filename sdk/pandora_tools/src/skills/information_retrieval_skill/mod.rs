@@ -0,0 +1,1441 @@
+pub mod bm25;
+pub mod chunking;
+pub mod embedding;
+pub mod fusion;
+pub mod knowledge_graph;
+pub mod lance_store;
+pub mod ltr;
+pub mod vector_index;
+
+use bm25::Bm25Index;
+use chrono::{DateTime, Utc};
+use chunking::{ChunkingConfig, IngestReport};
+use embedding::Embedder;
+use fusion::{FusedHit, RankedList, SearchHit};
+use knowledge_graph::{Triple, TripleStore};
+use lance_store::{LanceStore, MetadataFilter};
+use ltr::{FeatureVector, Judgment, RankNet, RankingReport};
+use serde_json::Value as CognitiveInput;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+use vector_index::VectorIndex;
+
+#[derive(Debug, Default, Clone)]
+pub struct Confidence {
+    pub score: f32,
+    pub epistemic_uncertainty: f32,
+    pub aleatoric_uncertainty: f32,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CognitiveOutput {
+    pub content: String,
+    pub confidence: Confidence,
+    pub reasoning_trace: Vec<String>,
+    pub documents: Vec<Document>,
+}
+
+#[derive(Debug, Error)]
+pub enum RetrievalError {
+    #[error("Lỗi kết nối LanceDB: {0}")]
+    LanceDBConnection(String),
+    #[error("Lỗi thực thi truy vấn LanceDB: {0}")]
+    LanceDBQuery(String),
+    #[error("Không tìm thấy document với ID: {0}")]
+    DocumentNotFound(String),
+    #[error("Input không hợp lệ cho việc tìm kiếm: {0}")]
+    InvalidInput(String),
+    #[error("Lỗi embedding: {0}")]
+    EmbeddingError(String),
+    #[error("Lỗi knowledge graph: {0}")]
+    KnowledgeGraph(String),
+}
+
+// ===== SEARCH MODES & CONFIGURATION =====
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SearchMode {
+    UltraLight {
+        max_memory_mb: usize,
+        cache_only: bool,
+        max_results: usize,
+    },
+    Balanced {
+        max_memory_mb: usize,
+        use_vector_search: bool,
+        use_text_search: bool,
+        hybrid_weight: f32,
+    },
+    Full {
+        max_memory_mb: usize,
+        use_all_tiers: bool,
+        enable_kg_reasoning: bool,
+        external_apis_enabled: bool,
+    },
+}
+
+impl Default for SearchMode {
+    fn default() -> Self {
+        SearchMode::Balanced {
+            max_memory_mb: 100,
+            use_vector_search: true,
+            use_text_search: true,
+            hybrid_weight: 0.7,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SearchStage {
+    CacheLookup { max_age: Duration },
+    VectorSearch { k: usize, threshold: f32 },
+    TextSearch { k: usize, boost_factors: HashMap<String, f32> },
+    KnowledgeGraphQuery { max_hops: usize },
+    ExternalApiCall { apis: Vec<String>, timeout: Duration },
+    ResultRanking { algorithm: RankingAlgorithm },
+}
+
+#[derive(Debug, Clone)]
+pub enum RankingAlgorithm {
+    CosineSimilarity,
+    BM25,
+    Hybrid,
+    LearningToRank,
+}
+
+#[derive(Debug, Clone)]
+pub enum FusionStrategy {
+    ReciprocalRank,
+    CombSum,
+    CombMNZ,
+    WeightedSum { weights: HashMap<String, f32> },
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchPipeline {
+    pub stages: Vec<SearchStage>,
+    pub early_exit_conditions: Vec<ExitCondition>,
+    pub result_fusion: FusionStrategy,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExitCondition {
+    pub stage_name: String,
+    pub min_results: usize,
+    pub max_latency: Duration,
+    pub min_confidence: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryAnalyzer {
+    pub query_types: HashMap<String, f32>,
+    pub complexity_threshold: f32,
+    pub intent_classifier: IntentClassifier,
+}
+
+#[derive(Debug, Clone)]
+pub struct IntentClassifier {
+    pub categories: Vec<String>,
+    pub confidence_threshold: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResultRanker {
+    pub algorithm: RankingAlgorithm,
+    pub learning_model: Option<String>,
+    pub feature_weights: HashMap<String, f32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedbackProcessor {
+    pub feedback_history: Vec<FeedbackRecord>,
+    /// Số phản hồi tối đa giữ trong `feedback_history`; phản hồi cũ nhất bị loại trước.
+    pub max_history: usize,
+    pub learning_rate: f32,
+    pub decay_factor: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedbackRecord {
+    pub query: String,
+    pub result_id: String,
+    pub relevance_score: f32,
+    pub timestamp: Instant,
+}
+
+/// Độ tương đồng tối thiểu để liên kết truy vấn với một nút của knowledge graph
+const KG_LINK_THRESHOLD: f32 = 0.5;
+/// Số nút hạt giống tối đa cho mỗi truy vấn
+const KG_MAX_SEEDS: usize = 3;
+/// Số đường đi tối đa trả về từ stage `KnowledgeGraphQuery`
+const KG_MAX_RESULTS: usize = 10;
+/// Độ dài tối đa (số từ) của cụm từ trong truy vấn được embed để liên kết thực thể
+const KG_MAX_MENTION_TOKENS: usize = 3;
+/// Giá trị `ResultRanker::learning_model` sau khi mô hình đã học từ phản hồi
+const LTR_MODEL_NAME: &str = "ranknet-linear";
+/// Giá trị mặc định của `FeedbackProcessor::max_history`
+pub const DEFAULT_MAX_FEEDBACK_HISTORY: usize = 10_000;
+
+/// Đại diện cho một tài liệu trong bộ nhớ
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub id: String,
+    pub content: String,
+    pub embedding: Vec<f32>,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// Truy vấn đã tách từ input: văn bản và (tùy chọn) embedding do người gọi cung cấp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Kết quả đã hợp nhất được lưu cho stage `CacheLookup`.
+#[derive(Debug, Clone)]
+struct CachedResult {
+    stored_at: Instant,
+    hits: Vec<FusedHit>,
+    /// Tài liệu sinh từ knowledge graph (không có trong kho tài liệu)
+    documents: HashMap<String, Document>,
+}
+
+/// Engine tìm kiếm lũy tiến, kết hợp cache, vector DB và knowledge graph.
+///
+/// Toàn bộ tìm kiếm lai (HNSW + BM25 + hợp nhất) chạy trong bộ nhớ; LanceDB là tùy chọn
+/// nên engine tạo bằng `in_memory` chạy được trên thiết bị biên.
+pub struct ProgressiveSemanticEngine {
+    /// Chỉ mục vector HNSW trên embedding của tài liệu.
+    vector_index: VectorIndex,
+
+    /// Chỉ mục ngược BM25 trên nội dung tài liệu.
+    text_index: Bm25Index,
+
+    /// Map từ Document ID sang vị trí trong `inmem_docs`.
+    doc_slots: HashMap<String, usize>,
+
+    /// Map từ Document ID viết thường sang các slot (tăng dần) có ID đó, để `text_hits`
+    /// so khớp ID mà không phải duyệt kho tài liệu.
+    lowercase_ids: HashMap<String, Vec<usize>>,
+
+    /// Kết quả của các truy vấn gần đây, xóa khi có tài liệu mới.
+    query_cache: RwLock<HashMap<String, CachedResult>>,
+
+    /// Bảng tài liệu bền vững trong LanceDB (không có ở chế độ `in_memory`).
+    lance_store: Option<LanceStore>,
+
+    /// Kho bộ ba có cấu trúc cho stage `KnowledgeGraphQuery`.
+    knowledge_graph: TripleStore,
+
+    /// Kích thước của vector embedding.
+    embedding_dim: usize,
+
+    /// Sinh embedding cho tài liệu và truy vấn chưa có sẵn vector.
+    embedder: Option<Arc<dyn Embedder>>,
+
+    /// Kho tài liệu trong bộ nhớ; slot của chỉ mục là vị trí trong vector này, slot của
+    /// tài liệu đã xóa là `None` và được dùng lại qua `free_slots`.
+    inmem_docs: Vec<Option<Document>>,
+
+    /// Các slot trống trong `inmem_docs`.
+    free_slots: Vec<usize>,
+
+    /// Tài liệu được `set_embedder` embed nhưng chưa ghi lại vào LanceDB.
+    unpersisted: HashSet<String>,
+
+    /// Phản hồi mới nhất cho mỗi tài liệu theo truy vấn (đã chuẩn hóa) trong
+    /// `feedback_history`, kèm số thứ tự của phản hồi; cập nhật dần ở `record_feedback`.
+    latest_feedback: HashMap<String, HashMap<String, (u64, FeedbackRecord)>>,
+
+    /// Tổng số phản hồi đã ghi nhận, kể cả phản hồi đã bị loại khỏi lịch sử.
+    feedback_count: u64,
+
+    // ===== NEW COMPONENTS FROM NEURAL SKILLS SPEC =====
+    
+    /// Search mode configuration
+    search_mode: SearchMode,
+    
+    /// Search pipeline with stages
+    search_pipeline: SearchPipeline,
+    
+    /// Query analyzer for intent classification
+    query_analyzer: QueryAnalyzer,
+    
+    /// Result ranker with learning capabilities
+    result_ranker: ResultRanker,
+    
+    /// Feedback processor for learning
+    feedback_processor: FeedbackProcessor,
+    
+    /// Performance metrics
+    performance_metrics: Arc<RwLock<HashMap<String, f32>>>,
+}
+
+impl ProgressiveSemanticEngine {
+    /// Khởi tạo một engine tìm kiếm mới trên bảng `table_name` của LanceDB tại `lance_db_path`.
+    ///
+    /// Bảng được tạo nếu chưa có; tài liệu đã lưu được nạp lại vào các chỉ mục trong bộ nhớ.
+    pub async fn new(
+        lance_db_path: &str,
+        table_name: &str,
+        embedding_dim: usize,
+    ) -> Result<Self, RetrievalError> {
+        let conn = lancedb::connect(lance_db_path)
+            .execute()
+            .await
+            .map_err(|e| RetrievalError::LanceDBConnection(e.to_string()))?;
+        let store = LanceStore::open(&conn, table_name, embedding_dim).await?;
+
+        let mut engine = Self::in_memory(embedding_dim);
+        for stored in store.scan().await? {
+            engine.index_document(stored.document);
+        }
+        engine.lance_store = Some(store);
+        Ok(engine)
+    }
+
+    /// Khởi tạo engine chỉ dùng bộ nhớ trong (không cần LanceDB).
+    pub fn in_memory(embedding_dim: usize) -> Self {
+        // Initialize new components
+        let search_mode = SearchMode::default();
+        let search_pipeline = Self::create_default_pipeline();
+        let query_analyzer = QueryAnalyzer {
+            query_types: HashMap::new(),
+            complexity_threshold: 0.5,
+            intent_classifier: IntentClassifier {
+                categories: vec!["factual".to_string(), "procedural".to_string(), "conceptual".to_string()],
+                confidence_threshold: 0.7,
+            },
+        };
+        let result_ranker = ResultRanker {
+            algorithm: RankingAlgorithm::Hybrid,
+            learning_model: None,
+            feature_weights: HashMap::new(),
+        };
+        let feedback_processor = FeedbackProcessor {
+            feedback_history: Vec::new(),
+            max_history: DEFAULT_MAX_FEEDBACK_HISTORY,
+            learning_rate: 0.1,
+            decay_factor: 0.95,
+        };
+
+        Self {
+            vector_index: VectorIndex::new(embedding_dim),
+            text_index: Bm25Index::default(),
+            doc_slots: HashMap::new(),
+            lowercase_ids: HashMap::new(),
+            query_cache: RwLock::new(HashMap::new()),
+            lance_store: None,
+            knowledge_graph: TripleStore::default(),
+            embedding_dim,
+            embedder: None,
+            inmem_docs: Vec::new(),
+            free_slots: Vec::new(),
+            unpersisted: HashSet::new(),
+            latest_feedback: HashMap::new(),
+            feedback_count: 0,
+            search_mode,
+            search_pipeline,
+            query_analyzer,
+            result_ranker,
+            feedback_processor,
+            performance_metrics: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Khởi tạo engine trong bộ nhớ, số chiều lấy theo `embedder`.
+    pub fn with_embedder(embedder: Arc<dyn Embedder>) -> Self {
+        let mut engine = Self::in_memory(embedder.dim());
+        engine.embedder = Some(embedder);
+        engine
+    }
+
+    /// Gắn embedder; các tài liệu đã thêm mà chưa có embedding được đánh chỉ mục lại.
+    ///
+    /// Với LanceDB, các embedding mới được ghi lại vào bảng cùng lần upsert kế tiếp
+    /// (`add_documents`, `ingest_directory`).
+    pub fn set_embedder(&mut self, embedder: Arc<dyn Embedder>) -> Result<(), RetrievalError> {
+        if embedder.dim() != self.embedding_dim {
+            return Err(RetrievalError::EmbeddingError(format!(
+                "Embedder '{}' sinh {} chiều, engine cần {}",
+                embedder.name(),
+                embedder.dim(),
+                self.embedding_dim
+            )));
+        }
+        let mut slots: Vec<usize> = self.doc_slots.values().copied().collect();
+        slots.sort_unstable();
+        for slot in slots {
+            let Some(doc) = self.inmem_docs[slot].as_mut() else {
+                continue;
+            };
+            if doc.embedding.is_empty() {
+                let embedding = embedder.embed(&doc.content)?;
+                self.vector_index.insert(slot, &embedding);
+                doc.embedding = embedding;
+                if self.lance_store.is_some() {
+                    self.unpersisted.insert(doc.id.clone());
+                }
+            }
+        }
+        self.knowledge_graph.clear_embeddings();
+        self.knowledge_graph
+            .index_embeddings(|label| embedder.embed(label))?;
+        self.embedder = Some(embedder);
+        self.query_cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    pub fn embedder(&self) -> Option<&Arc<dyn Embedder>> {
+        self.embedder.as_ref()
+    }
+
+    pub fn knowledge_graph(&self) -> &TripleStore {
+        &self.knowledge_graph
+    }
+
+    /// Thêm các bộ ba vào knowledge graph; nhãn của nút mới (hoặc vừa đổi nhãn) được embed
+    /// nếu có embedder. Knowledge graph chỉ được dùng khi pipeline có stage
+    /// `KnowledgeGraphQuery`.
+    pub fn add_triples(&mut self, triples: impl IntoIterator<Item = Triple>) -> Result<(), RetrievalError> {
+        for triple in triples {
+            self.knowledge_graph.insert(triple);
+        }
+        self.index_knowledge_graph()
+    }
+
+    /// Nạp knowledge graph từ file N-Triples (`.nt`) hoặc JSON-LD (`.jsonld`, `.json`).
+    ///
+    /// `confidence` gán cho mọi bộ ba của file; trả về số bộ ba mới.
+    pub fn load_knowledge_graph(&mut self, path: impl AsRef<Path>, confidence: f32) -> Result<usize, RetrievalError> {
+        let added = self.knowledge_graph.load_file(path, confidence)?;
+        self.index_knowledge_graph()?;
+        Ok(added)
+    }
+
+    fn index_knowledge_graph(&mut self) -> Result<(), RetrievalError> {
+        if let Some(embedder) = &self.embedder {
+            self.knowledge_graph
+                .index_embeddings(|label| embedder.embed(label))?;
+        }
+        self.query_cache.write().unwrap().clear();
+        Ok(())
+    }
+
+    /// Tạo search pipeline mặc định.
+    ///
+    /// Stage `KnowledgeGraphQuery` không có trong pipeline mặc định để nạp knowledge graph
+    /// không làm đổi kết quả tìm kiếm; bật bằng `update_search_pipeline` với stage đó và
+    /// trọng số `"knowledge_graph"` trong `FusionStrategy::WeightedSum`.
+    fn create_default_pipeline() -> SearchPipeline {
+        SearchPipeline {
+            stages: vec![
+                SearchStage::CacheLookup { max_age: Duration::from_secs(300) },
+                SearchStage::VectorSearch { k: 10, threshold: 0.7 },
+                SearchStage::TextSearch { k: 10, boost_factors: HashMap::new() },
+                SearchStage::ResultRanking { algorithm: RankingAlgorithm::Hybrid },
+            ],
+            early_exit_conditions: vec![
+                ExitCondition {
+                    stage_name: "CacheLookup".to_string(),
+                    min_results: 3,
+                    max_latency: Duration::from_millis(50),
+                    min_confidence: 0.8,
+                },
+            ],
+            result_fusion: FusionStrategy::WeightedSum {
+                weights: {
+                    let mut w = HashMap::new();
+                    w.insert("vector".to_string(), 0.7);
+                    w.insert("text".to_string(), 0.3);
+                    w
+                },
+            },
+        }
+    }
+
+    /// Thêm (hoặc thay thế theo `id`) một tài liệu và đánh chỉ mục BM25 + HNSW cho nó.
+    ///
+    /// Tài liệu có `embedding` rỗng được embed từ `content` nếu engine có embedder.
+    pub async fn add_document(&mut self, doc: Document) -> Result<(), RetrievalError> {
+        self.add_documents(vec![doc]).await.map(|_| ())
+    }
+
+    /// Thêm (hoặc thay thế) nhiều tài liệu; với LanceDB chúng được ghi theo lô trước khi
+    /// đánh chỉ mục trong bộ nhớ, cùng các tài liệu có embedding chưa ghi. Trả về số tài
+    /// liệu đã thêm.
+    pub async fn add_documents(&mut self, mut docs: Vec<Document>) -> Result<usize, RetrievalError> {
+        if let Some(embedder) = &self.embedder {
+            let missing: Vec<usize> = (0..docs.len()).filter(|&i| docs[i].embedding.is_empty()).collect();
+            let texts: Vec<&str> = missing.iter().map(|&i| docs[i].content.as_str()).collect();
+            let embeddings = embedder.embed_batch(&texts)?;
+            for (i, embedding) in missing.into_iter().zip(embeddings) {
+                docs[i].embedding = embedding;
+            }
+        }
+        if let Some(store) = &self.lance_store {
+            let mut pending: Vec<Document> = self
+                .unpersisted
+                .iter()
+                .filter_map(|id| self.doc_slots.get(id))
+                .filter_map(|&slot| self.inmem_docs[slot].clone())
+                .collect();
+            pending.extend(docs.iter().cloned());
+            store.upsert(&pending).await?;
+            self.unpersisted.clear();
+        }
+        let count = docs.len();
+        for doc in docs {
+            self.index_document(doc);
+        }
+        self.query_cache.write().unwrap().clear();
+        Ok(count)
+    }
+
+    /// Xóa tài liệu theo `id` khỏi bộ nhớ và LanceDB; trả về số tài liệu đã xóa.
+    ///
+    /// Slot của tài liệu đã xóa được giải phóng và dùng lại cho tài liệu thêm sau.
+    pub async fn delete_documents(&mut self, ids: &[&str]) -> Result<usize, RetrievalError> {
+        if let Some(store) = &self.lance_store {
+            store.delete(ids).await?;
+        }
+        let mut removed = 0;
+        for id in ids {
+            self.unpersisted.remove(*id);
+            if let Some(slot) = self.doc_slots.remove(*id) {
+                let lowercase = id.to_lowercase();
+                if let Some(slots) = self.lowercase_ids.get_mut(&lowercase) {
+                    slots.retain(|&s| s != slot);
+                    if slots.is_empty() {
+                        self.lowercase_ids.remove(&lowercase);
+                    }
+                }
+                self.text_index.remove(slot);
+                self.vector_index.remove(slot);
+                self.inmem_docs[slot] = None;
+                self.free_slots.push(slot);
+                removed += 1;
+            }
+        }
+        self.query_cache.write().unwrap().clear();
+        Ok(removed)
+    }
+
+    pub fn lance_store(&self) -> Option<&LanceStore> {
+        self.lance_store.as_ref()
+    }
+
+    /// Nạp các file văn bản trong `dir` (đệ quy, theo `config.extensions`), chia đoạn rồi
+    /// thêm thành tài liệu `<đường dẫn chuẩn hóa của dir>/<đường dẫn tương đối>#<số thứ tự đoạn>`,
+    /// nên hai thư mục có file trùng tên không ghi đè lên nhau.
+    ///
+    /// Metadata của mỗi đoạn gồm `root` (đường dẫn chuẩn hóa của `dir`), `source`, `chunk`,
+    /// `chunks` và `updated_at` (thời điểm sửa file). Nạp lại một file thay thế các đoạn của
+    /// nó; đoạn thừa của lần nạp trước và đoạn của file đã bị xóa khỏi `dir` (hoặc không còn
+    /// đọc được) bị xóa. Tài liệu nạp từ thư mục khác không bị ảnh hưởng.
+    pub async fn ingest_directory(&mut self, dir: impl AsRef<Path>, config: &ChunkingConfig) -> Result<IngestReport, RetrievalError> {
+        let dir = dir.as_ref();
+        let invalid = |e: std::io::Error| RetrievalError::InvalidInput(format!("{}: {}", dir.display(), e));
+        let root = dir.canonicalize().map_err(invalid)?.to_string_lossy().into_owned();
+        let files = chunking::collect_files(dir, config).map_err(invalid)?;
+        let mut report = IngestReport::default();
+        let mut sources = HashSet::new();
+        for path in files {
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                report.skipped.push(path);
+                continue;
+            };
+            let source = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let modified = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .map(|t| DateTime::<Utc>::from(t).to_rfc3339())
+                .ok();
+            let chunks = chunking::chunk_text(&text, config);
+            let total = chunks.len();
+            let docs: Vec<Document> = chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, content)| {
+                    let mut metadata = HashMap::from([
+                        ("root".to_string(), serde_json::json!(root)),
+                        ("source".to_string(), serde_json::json!(source)),
+                        ("chunk".to_string(), serde_json::json!(i)),
+                        ("chunks".to_string(), serde_json::json!(total)),
+                    ]);
+                    if let Some(modified) = &modified {
+                        metadata.insert("updated_at".to_string(), serde_json::json!(modified));
+                    }
+                    Document {
+                        id: format!("{}/{}#{}", root, source, i),
+                        content,
+                        embedding: Vec::new(),
+                        metadata,
+                    }
+                })
+                .collect();
+            report.chunks += self.add_documents(docs).await?;
+
+            let stale: Vec<String> = self
+                .inmem_docs
+                .iter()
+                .flatten()
+                .filter(|doc| doc.metadata.get("root").and_then(serde_json::Value::as_str) == Some(root.as_str()))
+                .filter(|doc| doc.metadata.get("source").and_then(serde_json::Value::as_str) == Some(source.as_str()))
+                .filter(|doc| {
+                    doc.metadata
+                        .get("chunk")
+                        .and_then(serde_json::Value::as_u64)
+                        .is_some_and(|chunk| chunk as usize >= total)
+                })
+                .map(|doc| doc.id.clone())
+                .collect();
+            let stale: Vec<&str> = stale.iter().map(String::as_str).collect();
+            report.removed += self.delete_documents(&stale).await?;
+            report.files += 1;
+            sources.insert(source);
+        }
+
+        // File đã biến mất khỏi thư mục: xóa mọi đoạn của nó
+        let gone: Vec<String> = self
+            .inmem_docs
+            .iter()
+            .flatten()
+            .filter(|doc| doc.metadata.get("root").and_then(serde_json::Value::as_str) == Some(root.as_str()))
+            .filter(|doc| {
+                doc.metadata
+                    .get("source")
+                    .and_then(serde_json::Value::as_str)
+                    .is_some_and(|source| !sources.contains(source))
+            })
+            .map(|doc| doc.id.clone())
+            .collect();
+        let gone: Vec<&str> = gone.iter().map(String::as_str).collect();
+        report.removed += self.delete_documents(&gone).await?;
+        Ok(report)
+    }
+
+    /// Đánh chỉ mục một tài liệu trong bộ nhớ (không ghi LanceDB)
+    fn index_document(&mut self, doc: Document) {
+        let slot = match self.doc_slots.get(&doc.id) {
+            Some(&slot) => slot,
+            None => {
+                let slot = self.free_slots.pop().unwrap_or_else(|| {
+                    self.inmem_docs.push(None);
+                    self.inmem_docs.len() - 1
+                });
+                self.doc_slots.insert(doc.id.clone(), slot);
+                let slots = self.lowercase_ids.entry(doc.id.to_lowercase()).or_default();
+                let at = slots.partition_point(|&s| s < slot);
+                slots.insert(at, slot);
+                slot
+            }
+        };
+        self.text_index.insert(slot, &doc.content);
+        self.vector_index.insert(slot, &doc.embedding);
+        self.inmem_docs[slot] = Some(doc);
+    }
+
+    /// Tìm concept theo text: tài liệu có `id` trùng khớp trước, sau đó theo điểm BM25.
+    pub async fn search_by_text(
+        &self,
+        text: &str,
+        top_k: usize,
+    ) -> Result<Vec<Document>, RetrievalError> {
+        Ok(self
+            .text_hits(text, top_k, &HashMap::new())
+            .into_iter()
+            .filter_map(|hit| self.document(&hit.id).cloned())
+            .collect())
+    }
+
+    /// Tìm concept theo vector qua chỉ mục HNSW (cosine).
+    pub async fn search_by_vector(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<Document>, RetrievalError> {
+        if vector.len() != self.embedding_dim {
+            return Err(RetrievalError::EmbeddingError(format!(
+                "Vector truy vấn có {} chiều, cần {}",
+                vector.len(),
+                self.embedding_dim
+            )));
+        }
+        Ok(self
+            .vector_hits(vector, top_k, f32::NEG_INFINITY)
+            .into_iter()
+            .filter_map(|hit| self.document(&hit.id).cloned())
+            .collect())
+    }
+
+    /// Tìm theo vector trong các tài liệu có metadata thỏa `filter`.
+    ///
+    /// Với LanceDB, lọc trước và tìm kiếm chạy trong bảng (qua chỉ mục IVF-PQ nếu đã tạo);
+    /// ở chế độ `in_memory` engine quét tuần tự các tài liệu thỏa điều kiện.
+    pub async fn search_by_vector_filtered(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: &MetadataFilter,
+    ) -> Result<Vec<Document>, RetrievalError> {
+        if vector.len() != self.embedding_dim {
+            return Err(RetrievalError::EmbeddingError(format!(
+                "Vector truy vấn có {} chiều, cần {}",
+                vector.len(),
+                self.embedding_dim
+            )));
+        }
+        // Kiểm tra khóa cả khi không dùng LanceDB để hai chế độ báo lỗi giống nhau
+        filter.to_sql()?;
+        if let Some(store) = &self.lance_store {
+            let hits = store.search(vector, top_k, Some(filter)).await?;
+            return Ok(hits.into_iter().map(|(stored, _)| stored.document).collect());
+        }
+        let mut hits: Vec<(f32, &Document)> = self
+            .doc_slots
+            .values()
+            .filter_map(|&slot| self.inmem_docs[slot].as_ref())
+            .filter(|doc| doc.embedding.len() == self.embedding_dim && filter.matches(&doc.metadata))
+            .map(|doc| (embedding::cosine(vector, &doc.embedding), doc))
+            .collect();
+        hits.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.id.cmp(&b.1.id)));
+        Ok(hits.into_iter().take(top_k).map(|(_, doc)| doc.clone()).collect())
+    }
+
+    fn document(&self, id: &str) -> Option<&Document> {
+        self.doc_slots.get(id).and_then(|slot| self.inmem_docs[*slot].as_ref())
+    }
+
+    fn text_hits(&self, text: &str, k: usize, boosts: &HashMap<String, f32>) -> Vec<SearchHit> {
+        let wanted = text.trim().to_lowercase();
+        let mut hits: Vec<SearchHit> = self
+            .lowercase_ids
+            .get(&wanted)
+            .into_iter()
+            .flatten()
+            .filter_map(|&slot| self.inmem_docs[slot].as_ref())
+            .map(|d| SearchHit { id: d.id.clone(), score: 1.0 })
+            .collect();
+        for (slot, score) in self.text_index.search(text, k, boosts) {
+            let Some(doc) = &self.inmem_docs[slot] else {
+                continue;
+            };
+            let id = &doc.id;
+            if !hits.iter().any(|h| &h.id == id) {
+                hits.push(SearchHit { id: id.clone(), score });
+            }
+        }
+        hits.truncate(k);
+        hits
+    }
+
+    fn vector_hits(&self, vector: &[f32], k: usize, threshold: f32) -> Vec<SearchHit> {
+        self.vector_index
+            .search(vector, k)
+            .into_iter()
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .filter_map(|(slot, similarity)| {
+                Some(SearchHit {
+                    id: self.inmem_docs[slot].as_ref()?.id.clone(),
+                    score: similarity.max(0.0),
+                })
+            })
+            .collect()
+    }
+
+    pub fn update_search_pipeline(&mut self, pipeline: SearchPipeline) {
+        self.search_pipeline = pipeline;
+        self.query_cache.write().unwrap().clear();
+    }
+
+    pub fn update_search_mode(&mut self, mode: SearchMode) {
+        self.search_mode = mode;
+        self.query_cache.write().unwrap().clear();
+    }
+
+    /// Thuật toán xếp hạng khi pipeline không có stage `ResultRanking`.
+    pub fn set_ranking_algorithm(&mut self, algorithm: RankingAlgorithm) {
+        self.result_ranker.algorithm = algorithm;
+    }
+
+    pub fn query_analyzer(&self) -> &QueryAnalyzer {
+        &self.query_analyzer
+    }
+
+    pub fn result_ranker(&self) -> &ResultRanker {
+        &self.result_ranker
+    }
+
+    pub fn feedback_history(&self) -> &[FeedbackRecord] {
+        &self.feedback_processor.feedback_history
+    }
+
+    /// Giới hạn số phản hồi giữ lại (tối thiểu 1); phản hồi cũ nhất vượt giới hạn bị loại
+    /// ngay, cả khỏi đặc trưng phản hồi lẫn các cặp huấn luyện về sau.
+    pub fn set_max_feedback_history(&mut self, max_history: usize) {
+        self.feedback_processor.max_history = max_history.max(1);
+        self.age_out_feedback();
+    }
+
+    /// Loại các phản hồi cũ nhất vượt `max_history` và các phán xét mới nhất thuộc về chúng.
+    fn age_out_feedback(&mut self) {
+        let history = &mut self.feedback_processor.feedback_history;
+        let excess = history.len().saturating_sub(self.feedback_processor.max_history);
+        let first_seq = self.feedback_count - history.len() as u64;
+        for (seq, record) in (first_seq..).zip(history.drain(..excess)) {
+            let key = ltr::normalize_query(&record.query);
+            let Some(results) = self.latest_feedback.get_mut(&key) else {
+                continue;
+            };
+            if results.get(&record.result_id).is_some_and(|(latest, _)| *latest == seq) {
+                results.remove(&record.result_id);
+                if results.is_empty() {
+                    self.latest_feedback.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Ghi nhận mức liên quan (trong [0, 1]) người dùng gán cho một kết quả và cập nhật
+    /// online mô hình learning-to-rank.
+    ///
+    /// `result_id` là id của một tài liệu hoặc một kết quả `kg:<nút>` của stage
+    /// `KnowledgeGraphQuery` (kết quả này chỉ có đặc trưng phản hồi).
+    ///
+    /// Phản hồi mới tạo cặp với phản hồi trước đó của cùng truy vấn; mỗi cặp là một bước
+    /// SGD với `learning_rate`, phản hồi cũ hơn nhân thêm `decay_factor` cho mỗi phản hồi
+    /// đến sau nó. Chỉ phán xét của truy vấn này được tính lại, và lịch sử giữ tối đa
+    /// `max_history` phản hồi, nên mỗi lần gọi không phụ thuộc số phản hồi đã ghi nhận.
+    pub fn record_feedback(&mut self, query: &str, result_id: &str, relevance: f32) -> Result<(), RetrievalError> {
+        if !(0.0..=1.0).contains(&relevance) {
+            return Err(RetrievalError::InvalidInput(format!(
+                "relevance phải nằm trong [0, 1], nhận {}",
+                relevance
+            )));
+        }
+        let kg_node = result_id
+            .strip_prefix("kg:")
+            .is_some_and(|node| self.knowledge_graph.contains_node(node));
+        if self.document(result_id).is_none() && !kg_node {
+            return Err(RetrievalError::DocumentNotFound(result_id.to_string()));
+        }
+        let record = FeedbackRecord {
+            query: query.to_string(),
+            result_id: result_id.to_string(),
+            relevance_score: relevance,
+            timestamp: Instant::now(),
+        };
+        let key = ltr::normalize_query(query);
+        self.latest_feedback
+            .entry(key.clone())
+            .or_default()
+            .insert(result_id.to_string(), (self.feedback_count, record.clone()));
+        self.feedback_processor.feedback_history.push(record);
+        self.feedback_count += 1;
+        self.age_out_feedback();
+
+        let mut latest: Vec<(usize, &FeedbackRecord)> = self
+            .latest_feedback
+            .get(&key)
+            .into_iter()
+            .flat_map(|results| results.values())
+            .map(|(seq, record)| ((self.feedback_count - 1 - seq) as usize, record))
+            .collect();
+        latest.sort_by_key(|(age, _)| *age);
+        let judgments = self.judgments_from(&latest, &key, &self.feedback_processor.feedback_history)?;
+        let mut model = RankNet::from_weights(&self.result_ranker.feature_weights);
+        let learning_rate = self.feedback_processor.learning_rate;
+        if let Some(new) = judgments.iter().find(|j| j.result_id == result_id) {
+            for other in judgments.iter().filter(|j| j.result_id != result_id) {
+                let weight = new.weight.min(other.weight);
+                if new.relevance - other.relevance >= ltr::MIN_RELEVANCE_GAP {
+                    model.train_pair(&new.features, &other.features, learning_rate, weight);
+                } else if other.relevance - new.relevance >= ltr::MIN_RELEVANCE_GAP {
+                    model.train_pair(&other.features, &new.features, learning_rate, weight);
+                }
+            }
+        }
+        self.result_ranker.feature_weights = model.to_weights();
+        self.result_ranker.learning_model = Some(LTR_MODEL_NAME.to_string());
+        Ok(())
+    }
+
+    /// Đánh giá learning-to-rank trên phản hồi đã ghi nhận.
+    ///
+    /// Khoảng `holdout_fraction` số truy vấn (chọn theo hash nên ổn định giữa các lần gọi)
+    /// được giữ lại; một mô hình mới học trên phần còn lại rồi được đo NDCG@k và MRR trên
+    /// các truy vấn giữ lại, cạnh trọng số chưa học làm mốc. Mô hình online không đổi.
+    pub fn evaluate_ranking(&self, k: usize, holdout_fraction: f32) -> Result<RankingReport, RetrievalError> {
+        if !(holdout_fraction > 0.0 && holdout_fraction < 1.0) {
+            return Err(RetrievalError::InvalidInput(format!(
+                "holdout_fraction phải nằm trong (0, 1), nhận {}",
+                holdout_fraction
+            )));
+        }
+        let history = &self.feedback_processor.feedback_history;
+        let mut queries: Vec<String> = history.iter().map(|r| ltr::normalize_query(&r.query)).collect();
+        queries.sort_by_key(|q| (seahash::hash(q.as_bytes()), q.clone()));
+        queries.dedup();
+        if queries.len() < 2 {
+            return Err(RetrievalError::InvalidInput(
+                "Cần phản hồi của ít nhất 2 truy vấn để tách tập giữ lại".to_string(),
+            ));
+        }
+        let test_count = ((queries.len() as f32 * holdout_fraction).round() as usize).clamp(1, queries.len() - 1);
+        let (test_queries, train_queries) = queries.split_at(test_count);
+        let (test_history, train_history): (Vec<FeedbackRecord>, Vec<FeedbackRecord>) = history
+            .iter()
+            .cloned()
+            .partition(|r| test_queries.contains(&ltr::normalize_query(&r.query)));
+
+        let train_judgments = train_queries
+            .iter()
+            .map(|key| self.judgments(&train_history, key, &train_history))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut model = RankNet::default();
+        let mut training_pairs = 0;
+        for _ in 0..ltr::EVALUATION_EPOCHS {
+            training_pairs = train_judgments
+                .iter()
+                .map(|judgments| model.train_query(judgments, self.feedback_processor.learning_rate))
+                .sum();
+        }
+
+        let baseline = RankNet::default();
+        let mut report = RankingReport {
+            k,
+            ndcg_at_k: 0.0,
+            mrr: 0.0,
+            baseline_ndcg_at_k: 0.0,
+            baseline_mrr: 0.0,
+            train_queries: train_queries.len(),
+            test_queries: test_queries.len(),
+            training_pairs,
+        };
+        for key in test_queries {
+            // Đặc trưng phản hồi chỉ lấy từ tập huấn luyện
+            let judgments = self.judgments(&test_history, key, &train_history)?;
+            let ranked = model.ranked_relevances(&judgments);
+            report.ndcg_at_k += ltr::ndcg_at_k(&ranked, k);
+            report.mrr += ltr::reciprocal_rank(&ranked);
+            let ranked = baseline.ranked_relevances(&judgments);
+            report.baseline_ndcg_at_k += ltr::ndcg_at_k(&ranked, k);
+            report.baseline_mrr += ltr::reciprocal_rank(&ranked);
+        }
+        let n = test_queries.len() as f32;
+        report.ndcg_at_k /= n;
+        report.mrr /= n;
+        report.baseline_ndcg_at_k /= n;
+        report.baseline_mrr /= n;
+        Ok(report)
+    }
+
+    /// Phán xét (mới nhất cho mỗi tài liệu) của truy vấn `key` trong `records`; đặc trưng
+    /// phản hồi lấy từ `clicks`, trừ phản hồi của chính truy vấn này.
+    fn judgments(&self, records: &[FeedbackRecord], key: &str, clicks: &[FeedbackRecord]) -> Result<Vec<Judgment>, RetrievalError> {
+        let mut latest: Vec<(usize, &FeedbackRecord)> = Vec::new();
+        for (age, record) in records.iter().rev().enumerate() {
+            if ltr::normalize_query(&record.query) == key
+                && !latest.iter().any(|(_, r)| r.result_id == record.result_id)
+            {
+                latest.push((age, record));
+            }
+        }
+        self.judgments_from(&latest, key, clicks)
+    }
+
+    /// Phán xét từ phản hồi mới nhất cho mỗi tài liệu của truy vấn `key`, `latest` xếp từ
+    /// mới đến cũ kèm tuổi của phản hồi.
+    fn judgments_from(
+        &self,
+        latest: &[(usize, &FeedbackRecord)],
+        key: &str,
+        clicks: &[FeedbackRecord],
+    ) -> Result<Vec<Judgment>, RetrievalError> {
+        let Some((_, newest)) = latest.first() else {
+            return Ok(Vec::new());
+        };
+        let query = SearchQuery {
+            text: newest.query.clone(),
+            embedding: match &self.embedder {
+                Some(embedder) => Some(embedder.embed(&newest.query)?),
+                None => None,
+            },
+        };
+        let now = Utc::now();
+        Ok(latest
+            .iter()
+            .rev()
+            .map(|(age, record)| Judgment {
+                result_id: record.result_id.clone(),
+                features: self.ranking_features(&query, &record.result_id, clicks, Some(key), now),
+                relevance: record.relevance_score,
+                weight: self.feedback_processor.decay_factor.powi(*age as i32),
+            })
+            .collect())
+    }
+
+    /// Đặc trưng learning-to-rank (theo thứ tự `ltr::FEATURES`) của tài liệu `id`; tài liệu
+    /// sinh từ knowledge graph chỉ có đặc trưng phản hồi.
+    fn ranking_features(
+        &self,
+        query: &SearchQuery,
+        id: &str,
+        clicks: &[FeedbackRecord],
+        exclude_query: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> FeatureVector {
+        let click = ltr::click_feedback(clicks, id, exclude_query, self.feedback_processor.decay_factor);
+        let Some((slot, doc)) = self
+            .doc_slots
+            .get(id)
+            .and_then(|&slot| Some((slot, self.inmem_docs[slot].as_ref()?)))
+        else {
+            return [0.0, 0.0, 0.0, click];
+        };
+        let cosine = match &query.embedding {
+            Some(embedding) if embedding.len() == doc.embedding.len() => {
+                embedding::cosine(embedding, &doc.embedding).max(0.0)
+            }
+            _ => 0.0,
+        };
+        [
+            self.text_index.score(&query.text, slot),
+            cosine,
+            ltr::recency(&doc.metadata, now),
+            click,
+        ]
+    }
+
+    /// Thực hiện tìm kiếm lũy tiến với search pipeline.
+    ///
+    /// Input là chuỗi hoặc object `{"query": ..., "embedding": [...]}`; không có embedding
+    /// thì truy vấn được embed bằng embedder của engine (nếu có), nếu không stage
+    /// `VectorSearch` không trả kết quả.
+    pub async fn search(&self, input: &CognitiveInput) -> Result<CognitiveOutput, RetrievalError> {
+        let start_time = Instant::now();
+        let mut reasoning_trace = Vec::new();
+        
+        // 1. Parse input and extract query
+        let mut query = self.extract_query_from_input(input)?;
+        reasoning_trace.push(format!("Extracted query: '{}'", query.text));
+        if query.embedding.is_none() {
+            if let Some(embedder) = &self.embedder {
+                query.embedding = Some(embedder.embed(&query.text)?);
+                reasoning_trace.push(format!("Embedded query with '{}'", embedder.name()));
+            }
+        }
+        
+        // 2. Analyze query complexity and intent
+        let query_complexity = self.analyze_query_complexity(&query.text);
+        let intent = self.classify_intent(&query.text);
+        reasoning_trace.push(format!("Query complexity: {:.2}, Intent: {:?}", query_complexity, intent));
+        
+        // 3. Execute search pipeline
+        let mut lists: Vec<RankedList> = Vec::new();
+        let mut cached: Option<CachedResult> = None;
+        let mut kg_documents: HashMap<String, Document> = HashMap::new();
+        let mut ranking = self.result_ranker.algorithm.clone();
+        
+        for stage in &self.search_pipeline.stages {
+            let stage_start = Instant::now();
+            let stage_name = self.get_stage_name(stage);
+            if !self.stage_enabled(stage) {
+                reasoning_trace.push(format!("Stage '{}' skipped by search mode", stage_name));
+                continue;
+            }
+            
+            // Execute stage
+            let result_count = match stage {
+                SearchStage::CacheLookup { max_age } => {
+                    cached = self.cache_lookup(&query, *max_age);
+                    cached.as_ref().map_or(0, |entry| entry.hits.len())
+                }
+                SearchStage::KnowledgeGraphQuery { max_hops } => {
+                    let (list, documents) = self.knowledge_graph_hits(&query, *max_hops)?;
+                    let count = list.hits.len();
+                    lists.push(list);
+                    kg_documents.extend(documents.into_iter().map(|doc| (doc.id.clone(), doc)));
+                    count
+                }
+                SearchStage::ResultRanking { algorithm } => {
+                    ranking = algorithm.clone();
+                    0
+                }
+                _ => {
+                    let list = self.execute_stage(stage, &query, &intent).await?;
+                    let count = list.hits.len();
+                    lists.push(list);
+                    count
+                }
+            };
+            
+            reasoning_trace.push(format!("Stage '{}' completed in {:?} with {} results", 
+                stage_name, stage_start.elapsed(), result_count));
+            
+            // Check early exit conditions
+            let scores: Vec<f32> = match &cached {
+                Some(entry) if lists.is_empty() => entry.hits.iter().map(|h| h.normalized).collect(),
+                _ => lists.iter().flat_map(|l| l.hits.iter().map(|h| h.score)).collect(),
+            };
+            if self.should_exit_early(&stage_name, &scores, stage_start.elapsed()) {
+                reasoning_trace.push(format!("Early exit at stage: {}", stage_name));
+                break;
+            }
+        }
+        
+        // 4. Fuse results using configured strategy (cache chỉ dùng khi không có stage mới nào chạy)
+        let fused_results = match cached {
+            Some(entry) if lists.iter().all(|l| l.hits.is_empty()) => {
+                reasoning_trace.push(format!("Served {} results from cache", entry.hits.len()));
+                kg_documents.extend(entry.documents);
+                entry.hits
+            }
+            _ => {
+                let fused = self.fuse_results(&lists);
+                reasoning_trace.push(format!("Fused {} results from {} stages",
+                    fused.len(), lists.len()));
+                self.store_in_cache(&query, &fused, &kg_documents);
+                fused
+            }
+        };
+        
+        // 5. Rank results
+        let ranked_results = self.rank_results(fused_results, &lists, &ranking, &query);
+        reasoning_trace.push(format!("Ranked {} results with {:?}", ranked_results.len(), ranking));
+        
+        // 6. Calculate confidence and uncertainty from the score distribution
+        let confidence = self.calculate_confidence(&ranked_results, &lists);
+        reasoning_trace.push(format!(
+            "Confidence {:.3} (epistemic {:.3}, aleatoric {:.3})",
+            confidence.score, confidence.epistemic_uncertainty, confidence.aleatoric_uncertainty
+        ));
+        
+        let documents: Vec<Document> = ranked_results
+            .iter()
+            .filter_map(|hit| self.document(&hit.id).or_else(|| kg_documents.get(&hit.id)).cloned())
+            .collect();
+        
+        // 7. Update performance metrics
+        self.update_performance_metrics(start_time.elapsed(), documents.len() as f32);
+        
+        // 8. Return results
+        Ok(CognitiveOutput {
+            content: match documents.first() {
+                Some(doc) => doc.content.clone(),
+                None => "Không tìm thấy kết quả phù hợp".to_string(),
+            },
+            confidence,
+            reasoning_trace,
+            documents,
+        })
+    }
+
+    /// Extract query from cognitive input
+    fn extract_query_from_input(&self, input: &CognitiveInput) -> Result<SearchQuery, RetrievalError> {
+        match input {
+            CognitiveInput::String(s) => Ok(SearchQuery { text: s.clone(), embedding: None }),
+            CognitiveInput::Object(obj) => {
+                let text = if let Some(query) = obj.get("query").and_then(|v| v.as_str()) {
+                    query.to_string()
+                } else if let Some(text) = obj.get("text").and_then(|v| v.as_str()) {
+                    text.to_string()
+                } else {
+                    return Err(RetrievalError::InvalidInput("No query or text field found".to_string()));
+                };
+                let embedding = match obj.get("embedding") {
+                    None | Some(CognitiveInput::Null) => None,
+                    Some(value) => {
+                        let vector: Vec<f32> = value
+                            .as_array()
+                            .and_then(|items| items.iter().map(|v| v.as_f64().map(|x| x as f32)).collect())
+                            .ok_or_else(|| RetrievalError::InvalidInput("embedding must be an array of numbers".to_string()))?;
+                        if vector.len() != self.embedding_dim {
+                            return Err(RetrievalError::EmbeddingError(format!(
+                                "Embedding có {} chiều, cần {}",
+                                vector.len(),
+                                self.embedding_dim
+                            )));
+                        }
+                        Some(vector)
+                    }
+                };
+                Ok(SearchQuery { text, embedding })
+            }
+            _ => Err(RetrievalError::InvalidInput("Unsupported input type".to_string()))
+        }
+    }
+
+    /// Analyze query complexity
+    fn analyze_query_complexity(&self, query: &str) -> f32 {
+        let mut complexity = 0.0;
+        
+        // Length factor
+        complexity += (query.len() as f32 / 100.0).min(1.0) * 0.3;
+        
+        // Word count factor
+        let word_count = query.split_whitespace().count();
+        complexity += (word_count as f32 / 20.0).min(1.0) * 0.3;
+        
+        // Question mark factor
+        if query.contains('?') {
+            complexity += 0.2;
+        }
+        
+        // Special characters factor
+        let special_chars = query.chars().filter(|c| !c.is_alphanumeric() && !c.is_whitespace()).count();
+        complexity += (special_chars as f32 / 10.0).min(1.0) * 0.2;
+        
+        complexity.min(1.0)
+    }
+
+    /// Classify query intent
+    fn classify_intent(&self, query: &str) -> String {
+        let query_lower = query.to_lowercase();
+        
+        if query_lower.contains("how") || query_lower.contains("what") || query_lower.contains("why") {
+            "factual".to_string()
+        } else if query_lower.contains("how to") || query_lower.contains("step") || query_lower.contains("process") {
+            "procedural".to_string()
+        } else if query_lower.contains("what is") || query_lower.contains("define") || query_lower.contains("concept") {
+            "conceptual".to_string()
+        } else {
+            "factual".to_string()
+        }
+    }
+
+    /// Get stage name for logging
+    fn get_stage_name(&self, stage: &SearchStage) -> String {
+        match stage {
+            SearchStage::CacheLookup { .. } => "CacheLookup".to_string(),
+            SearchStage::VectorSearch { .. } => "VectorSearch".to_string(),
+            SearchStage::TextSearch { .. } => "TextSearch".to_string(),
+            SearchStage::KnowledgeGraphQuery { .. } => "KnowledgeGraphQuery".to_string(),
+            SearchStage::ExternalApiCall { .. } => "ExternalApiCall".to_string(),
+            SearchStage::ResultRanking { .. } => "ResultRanking".to_string(),
+        }
+    }
+
+    /// Stage có được bật trong `search_mode` hiện tại không
+    fn stage_enabled(&self, stage: &SearchStage) -> bool {
+        match (&self.search_mode, stage) {
+            (_, SearchStage::ResultRanking { .. }) => true,
+            (SearchMode::UltraLight { cache_only: true, .. }, stage) => {
+                matches!(stage, SearchStage::CacheLookup { .. })
+            }
+            (SearchMode::Balanced { use_vector_search: false, .. }, SearchStage::VectorSearch { .. }) => false,
+            (SearchMode::Balanced { use_text_search: false, .. }, SearchStage::TextSearch { .. }) => false,
+            (SearchMode::Full { enable_kg_reasoning: false, .. }, SearchStage::KnowledgeGraphQuery { .. }) => false,
+            _ => true,
+        }
+    }
+
+    /// Check if should exit early based on conditions (sau khi stage đã chạy)
+    fn should_exit_early(&self, stage_name: &str, scores: &[f32], elapsed: Duration) -> bool {
+        for condition in &self.search_pipeline.early_exit_conditions {
+            if condition.stage_name == stage_name {
+                return scores.len() >= condition.min_results 
+                    && elapsed <= condition.max_latency
+                    && self.calculate_average_confidence(scores) >= condition.min_confidence;
+            }
+        }
+        false
+    }
+
+    fn cache_key(query: &SearchQuery) -> String {
+        format!("{}|{:?}", query.text.trim().to_lowercase(), query.embedding)
+    }
+
+    fn cache_lookup(&self, query: &SearchQuery, max_age: Duration) -> Option<CachedResult> {
+        let cache = self.query_cache.read().unwrap();
+        cache
+            .get(&Self::cache_key(query))
+            .filter(|entry| entry.stored_at.elapsed() <= max_age && !entry.hits.is_empty())
+            .cloned()
+    }
+
+    fn store_in_cache(&self, query: &SearchQuery, hits: &[FusedHit], documents: &HashMap<String, Document>) {
+        let uses_cache = self
+            .search_pipeline
+            .stages
+            .iter()
+            .any(|stage| matches!(stage, SearchStage::CacheLookup { .. }));
+        if uses_cache && !hits.is_empty() {
+            self.query_cache.write().unwrap().insert(
+                Self::cache_key(query),
+                CachedResult {
+                    stored_at: Instant::now(),
+                    hits: hits.to_vec(),
+                    documents: documents.clone(),
+                },
+            );
+        }
+    }
+
+    /// Execute a search stage, trả về danh sách kết quả có điểm đã hiệu chỉnh
+    async fn execute_stage(&self, stage: &SearchStage, query: &SearchQuery, _intent: &str) -> Result<RankedList, RetrievalError> {
+        let hits = match stage {
+            SearchStage::VectorSearch { k, threshold } => match &query.embedding {
+                Some(embedding) => self.vector_hits(embedding, *k, *threshold),
+                None => Vec::new(),
+            },
+            SearchStage::TextSearch { k, boost_factors } => {
+                let boosts = boost_factors
+                    .iter()
+                    .map(|(term, boost)| (term.to_lowercase(), *boost))
+                    .collect();
+                self.text_hits(&query.text, *k, &boosts)
+            }
+            SearchStage::ExternalApiCall { apis: _, timeout: _ } => {
+                // Mock external API call
+                Vec::new()
+            }
+            // Được xử lý trực tiếp trong `search`
+            SearchStage::CacheLookup { .. }
+            | SearchStage::KnowledgeGraphQuery { .. }
+            | SearchStage::ResultRanking { .. } => Vec::new(),
+        };
+        Ok(RankedList::new(Self::stage_source(stage), hits))
+    }
+
+    /// Liên kết truy vấn với các nút của knowledge graph rồi duyệt tối đa `max_hops` bước.
+    ///
+    /// Liên kết dùng embedder của engine trên các cụm 1–3 từ của truy vấn (và embedding của
+    /// cả truy vấn). Nút đích trùng `id` của một tài liệu được trả về dưới id đó để hợp nhất
+    /// với các stage khác; các nút còn lại thành tài liệu `kg:<nút>` với nội dung là đường đi.
+    fn knowledge_graph_hits(
+        &self,
+        query: &SearchQuery,
+        max_hops: usize,
+    ) -> Result<(RankedList, Vec<Document>), RetrievalError> {
+        let kg = &self.knowledge_graph;
+        if kg.is_empty() {
+            return Ok((RankedList::new("knowledge_graph", Vec::new()), Vec::new()));
+        }
+        let mut mentions: Vec<Vec<f32>> = query.embedding.iter().cloned().collect();
+        if let Some(embedder) = &self.embedder {
+            let tokens = bm25::tokenize(&query.text);
+            for n in 1..=KG_MAX_MENTION_TOKENS.min(tokens.len()) {
+                for window in tokens.windows(n) {
+                    mentions.push(embedder.embed(&window.join(" "))?);
+                }
+            }
+        }
+        let seeds = kg.link_entities(&query.text, &mentions, KG_LINK_THRESHOLD, KG_MAX_SEEDS);
+        let mut hits = Vec::new();
+        let mut documents = Vec::new();
+        for path in kg.traverse(&seeds, max_hops).into_iter().take(KG_MAX_RESULTS) {
+            let node = path.end();
+            let id = if self.doc_slots.contains_key(node) {
+                node.to_string()
+            } else {
+                let id = format!("kg:{}", node);
+                let provenance: Vec<&str> = path
+                    .triples
+                    .iter()
+                    .filter_map(|&i| kg.triples()[i].provenance.as_deref())
+                    .collect();
+                let metadata = HashMap::from([
+                    ("source".to_string(), serde_json::json!("knowledge_graph")),
+                    ("node".to_string(), serde_json::json!(node)),
+                    ("path".to_string(), serde_json::json!(path.nodes)),
+                    ("hops".to_string(), serde_json::json!(path.hops())),
+                    ("provenance".to_string(), serde_json::json!(provenance)),
+                ]);
+                documents.push(Document {
+                    id: id.clone(),
+                    content: kg.describe(&path),
+                    embedding: Vec::new(),
+                    metadata,
+                });
+                id
+            };
+            hits.push(SearchHit { id, score: path.score });
+        }
+        Ok((RankedList::new("knowledge_graph", hits), documents))
+    }
+
+    /// Tên nguồn dùng làm khóa trọng số của `FusionStrategy::WeightedSum`
+    fn stage_source(stage: &SearchStage) -> &'static str {
+        match stage {
+            SearchStage::CacheLookup { .. } => "cache",
+            SearchStage::VectorSearch { .. } => "vector",
+            SearchStage::TextSearch { .. } => "text",
+            SearchStage::KnowledgeGraphQuery { .. } => "knowledge_graph",
+            SearchStage::ExternalApiCall { .. } => "external",
+            SearchStage::ResultRanking { .. } => "ranking",
+        }
+    }
+
+    /// Fuse results from different stages
+    fn fuse_results(&self, lists: &[RankedList]) -> Vec<FusedHit> {
+        fusion::fuse(&self.search_pipeline.result_fusion, lists)
+    }
+
+    /// Rank results using configured algorithm; `Hybrid` giữ thứ tự hợp nhất
+    fn rank_results(&self, mut results: Vec<FusedHit>, lists: &[RankedList], algorithm: &RankingAlgorithm, query: &SearchQuery) -> Vec<FusedHit> {
+        let source = match algorithm {
+            RankingAlgorithm::CosineSimilarity => "vector",
+            RankingAlgorithm::BM25 => "text",
+            RankingAlgorithm::Hybrid => return results,
+            RankingAlgorithm::LearningToRank => return self.rank_by_model(results, query),
+        };
+        let scores: HashMap<&str, f32> = lists
+            .iter()
+            .filter(|l| l.source == source)
+            .flat_map(|l| l.hits.iter().map(|h| (h.id.as_str(), h.score)))
+            .collect();
+        let score = |hit: &FusedHit| scores.get(hit.id.as_str()).copied().unwrap_or(0.0);
+        // sort ổn định: hòa điểm thì giữ thứ tự hợp nhất
+        results.sort_by(|a, b| score(b).total_cmp(&score(a)));
+        results
+    }
+
+    /// Xếp hạng theo mô hình learning-to-rank; chưa có phản hồi nào thì giữ thứ tự hợp nhất
+    fn rank_by_model(&self, results: Vec<FusedHit>, query: &SearchQuery) -> Vec<FusedHit> {
+        if self.result_ranker.learning_model.is_none() {
+            return results;
+        }
+        let model = RankNet::from_weights(&self.result_ranker.feature_weights);
+        let history = &self.feedback_processor.feedback_history;
+        let now = Utc::now();
+        let mut scored: Vec<(f32, FusedHit)> = results
+            .into_iter()
+            .map(|hit| (model.score(&self.ranking_features(query, &hit.id, history, None, now)), hit))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, hit)| hit).collect()
+    }
+
+    /// Calculate confidence and uncertainty from the score distribution
+    fn calculate_confidence(&self, results: &[FusedHit], lists: &[RankedList]) -> Confidence {
+        fusion::assess(results, lists)
+    }
+
+    /// Calculate average confidence of results
+    fn calculate_average_confidence(&self, scores: &[f32]) -> f32 {
+        if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().sum::<f32>() / scores.len() as f32
+        }
+    }
+
+    /// Update performance metrics
+    fn update_performance_metrics(&self, duration: Duration, result_count: f32) {
+        let mut metrics = self.performance_metrics.write().unwrap();
+        metrics.insert("avg_response_time_ms".to_string(), duration.as_millis() as f32);
+        metrics.insert("avg_result_count".to_string(), result_count);
+        
+        let current_total = metrics.get("total_queries").copied().unwrap_or(0.0);
+        metrics.insert("total_queries".to_string(), current_total + 1.0);
+    }
+}
@@ -0,0 +1,264 @@
+//! Chỉ mục vector HNSW (`hnsw_rs`) cho stage `VectorSearch`.
+//!
+//! HNSW không hỗ trợ xóa, nên khi một tài liệu được thêm lại hoặc bị xóa thì điểm cũ trở
+//! thành "cũ" và bị lọc khi tìm kiếm. Khi số điểm cũ vượt `COMPACTION_MIN_STALE` và vượt số
+//! điểm còn hiệu lực, đồ thị được dựng lại chỉ từ các điểm còn hiệu lực. Vector có chiều sai hoặc chuẩn bằng 0 không được đánh
+//! chỉ mục (`DistCosine` coi vector 0 là trùng với mọi vector).
+//!
+//! Khi `hnsw_rs` cập nhật cạnh ngược, danh sách láng giềng đầy sẽ bỏ điểm xa nhất, nên một
+//! điểm thêm muộn có thể không còn cạnh nào trỏ tới và không được tìm thấy. Khi số điểm còn
+//! hiệu lực ≤ `EXACT_SEARCH_LIMIT`, chỉ mục quét tuần tự các vector mà HNSW đã lưu (không
+//! giữ bản sao), nên kết quả chính xác. Trên ngưỡng đó tìm kiếm đi qua HNSW và là xấp xỉ:
+//! một phần nhỏ điểm (kể cả điểm thêm muộn) có thể không được trả về.
+
+use hnsw_rs::prelude::*;
+use std::collections::HashMap;
+
+const MAX_NB_CONNECTION: usize = 16;
+const MAX_LAYER: usize = 16;
+const EF_CONSTRUCTION: usize = 200;
+const MIN_EF_SEARCH: usize = 64;
+const EXPECTED_ELEMENTS: usize = 10_000;
+/// Số điểm còn hiệu lực tối đa để tìm kiếm chính xác thay vì qua HNSW
+pub const EXACT_SEARCH_LIMIT: usize = 1_024;
+/// Số điểm cũ tối thiểu trước khi dựng lại đồ thị HNSW
+pub const COMPACTION_MIN_STALE: usize = 1_024;
+
+pub struct VectorIndex {
+    dim: usize,
+    hnsw: Hnsw<'static, f32, DistCosine>,
+    /// data id của HNSW → slot tài liệu
+    slots: Vec<usize>,
+    /// slot tài liệu → data id hiện hành
+    current: HashMap<usize, usize>,
+}
+
+impl std::fmt::Debug for VectorIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VectorIndex")
+            .field("dim", &self.dim)
+            .field("points", &self.slots.len())
+            .field("live", &self.current.len())
+            .finish()
+    }
+}
+
+fn is_indexable(vector: &[f32], dim: usize) -> bool {
+    vector.len() == dim && vector.iter().all(|x| x.is_finite()) && vector.iter().any(|x| *x != 0.0)
+}
+
+fn new_hnsw() -> Hnsw<'static, f32, DistCosine> {
+    let mut hnsw = Hnsw::new(
+        MAX_NB_CONNECTION,
+        EXPECTED_ELEMENTS,
+        MAX_LAYER,
+        EF_CONSTRUCTION,
+        DistCosine,
+    );
+    // Giảm số điểm mất cạnh ngược khi bộ nhớ còn nhỏ
+    hnsw.set_keeping_pruned(true);
+    hnsw.set_extend_candidates(true);
+    hnsw
+}
+
+impl VectorIndex {
+    pub fn new(dim: usize) -> Self {
+        Self {
+            dim,
+            hnsw: new_hnsw(),
+            slots: Vec::new(),
+            current: HashMap::new(),
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn len(&self) -> usize {
+        self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current.is_empty()
+    }
+
+    /// Đánh chỉ mục vector của tài liệu ở `slot`; trả `false` nếu vector không dùng được
+    /// (khi đó điểm cũ của `slot`, nếu có, cũng bị vô hiệu).
+    pub fn insert(&mut self, slot: usize, vector: &[f32]) -> bool {
+        if !is_indexable(vector, self.dim) {
+            self.current.remove(&slot);
+            self.compact_if_stale();
+            return false;
+        }
+        let data_id = self.slots.len();
+        self.hnsw.insert_slice((vector, data_id));
+        self.slots.push(slot);
+        self.current.insert(slot, data_id);
+        self.compact_if_stale();
+        true
+    }
+
+    /// Vô hiệu điểm của `slot`; điểm vẫn nằm trong đồ thị HNSW (và bị lọc khi tìm kiếm)
+    /// cho tới lần dựng lại kế tiếp.
+    pub fn remove(&mut self, slot: usize) -> bool {
+        let removed = self.current.remove(&slot).is_some();
+        self.compact_if_stale();
+        removed
+    }
+
+    /// Số điểm trong đồ thị HNSW không còn hiệu lực.
+    pub fn stale(&self) -> usize {
+        self.slots.len() - self.current.len()
+    }
+
+    /// Dựng lại đồ thị từ các điểm còn hiệu lực khi điểm cũ chiếm đa số, để chúng không
+    /// làm phình số láng giềng phải lấy dư khi tìm kiếm. Chi phí được chia đều cho các lần
+    /// thêm/xóa đã tạo ra các điểm cũ đó.
+    fn compact_if_stale(&mut self) {
+        let stale = self.stale();
+        if stale < COMPACTION_MIN_STALE || stale <= self.current.len() {
+            return;
+        }
+        let mut live: Vec<(usize, Vec<f32>)> = self
+            .hnsw
+            .get_point_indexation()
+            .into_iter()
+            .filter_map(|point| {
+                let data_id = point.get_origin_id();
+                let slot = *self.slots.get(data_id)?;
+                (self.current.get(&slot) == Some(&data_id)).then(|| (slot, point.get_v().to_vec()))
+            })
+            .collect();
+        live.sort_unstable_by_key(|(slot, _)| *slot);
+        self.hnsw = new_hnsw();
+        self.slots.clear();
+        self.current.clear();
+        for (slot, vector) in live {
+            let data_id = self.slots.len();
+            self.hnsw.insert_slice((&vector, data_id));
+            self.slots.push(slot);
+            self.current.insert(slot, data_id);
+        }
+    }
+
+    /// `k` slot gần nhất theo cosine, kèm độ tương đồng cosine.
+    pub fn search(&self, query: &[f32], k: usize) -> Vec<(usize, f32)> {
+        if k == 0 || self.current.is_empty() || !is_indexable(query, self.dim) {
+            return Vec::new();
+        }
+        if self.current.len() <= EXACT_SEARCH_LIMIT {
+            return self.exact_search(query, k);
+        }
+        // Lấy dư để bù các điểm cũ bị lọc
+        let stale = self.slots.len() - self.current.len();
+        let wanted = (k + stale).min(self.slots.len());
+        let ef = wanted.max(MIN_EF_SEARCH);
+        let mut hits: Vec<(usize, f32)> = self
+            .hnsw
+            .search(query, wanted, ef)
+            .into_iter()
+            .filter_map(|neighbour| {
+                let slot = *self.slots.get(neighbour.d_id)?;
+                (self.current.get(&slot) == Some(&neighbour.d_id))
+                    .then_some((slot, 1.0 - neighbour.distance))
+            })
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        hits.truncate(k);
+        hits
+    }
+
+    /// Quét mọi điểm còn hiệu lực trên các vector lưu trong đồ thị HNSW.
+    fn exact_search(&self, query: &[f32], k: usize) -> Vec<(usize, f32)> {
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let query_norm = norm(query);
+        let mut hits: Vec<(usize, f32)> = self
+            .hnsw
+            .get_point_indexation()
+            .into_iter()
+            .filter_map(|point| {
+                let data_id = point.get_origin_id();
+                let slot = *self.slots.get(data_id)?;
+                if self.current.get(&slot) != Some(&data_id) {
+                    return None;
+                }
+                let vector = point.get_v();
+                let dot: f32 = vector.iter().zip(query).map(|(a, b)| a * b).sum();
+                Some((slot, dot / (norm(vector) * query_norm)))
+            })
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        hits.truncate(k);
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_search_below_limit_skips_stale_points() {
+        let mut index = VectorIndex::new(2);
+        assert!(index.insert(0, &[1.0, 0.0]));
+        assert!(index.insert(1, &[0.0, 2.0]));
+        assert!(index.insert(0, &[0.0, -1.0]));
+        let hits = index.search(&[1.0, 0.1], 2);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].0, 1);
+        assert!((hits[0].1 - 0.1 / 1.01f32.sqrt()).abs() < 1e-6);
+        assert!(index.remove(1));
+        assert_eq!(index.search(&[0.0, 1.0], 5).len(), 1);
+    }
+
+    #[test]
+    fn test_hnsw_recall_above_exact_search_limit() {
+        let dim = 16;
+        let mut index = VectorIndex::new(dim);
+        let mut state = 7u64;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+        };
+        let vectors: Vec<Vec<f32>> = (0..EXACT_SEARCH_LIMIT + 500)
+            .map(|_| (0..dim).map(|_| next()).collect())
+            .collect();
+        for (slot, vector) in vectors.iter().enumerate() {
+            assert!(index.insert(slot, vector));
+        }
+        // HNSW là xấp xỉ: một số ít điểm có thể không tới được
+        let found = vectors
+            .iter()
+            .enumerate()
+            .step_by(10)
+            .filter(|(slot, vector)| index.search(vector, 1).first().map(|h| h.0) == Some(*slot))
+            .count();
+        let queried = vectors.len().div_ceil(10);
+        assert!(found * 100 >= queried * 95, "{}/{}", found, queried);
+        assert!(!index.insert(0, &vec![0.0; dim]));
+        assert_eq!(index.len(), vectors.len() - 1);
+
+        assert!(index.remove(1));
+        assert!(!index.remove(1));
+        assert!(index.search(&vectors[1], 5).iter().all(|h| h.0 != 1));
+    }
+
+    #[test]
+    fn test_replaced_points_are_compacted() {
+        let mut index = VectorIndex::new(2);
+        assert!(index.insert(0, &[1.0, 0.0]));
+        assert!(index.insert(1, &[0.0, 1.0]));
+        for i in 0..3 * COMPACTION_MIN_STALE {
+            let angle = i as f32 * 0.001;
+            assert!(index.insert(2, &[angle.cos(), angle.sin()]));
+            assert!(index.stale() <= COMPACTION_MIN_STALE, "{:?}", index);
+        }
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.search(&[0.0, 1.0], 1)[0].0, 1);
+        assert!(index.remove(0));
+        assert_eq!(index.search(&[1.0, 0.0], 3).len(), 2);
+    }
+}
@@ -0,0 +1,894 @@
+//! Chế độ suy diễn Horn-clause (Datalog/Prolog) cho `LogicalReasoningSkill`.
+//!
+//! Chương trình gồm các mệnh đề `head :- body1, body2.` và fact `head.`; biến viết hoa
+//! hoặc bắt đầu bằng `_`. Truy vấn là một hội các goal, `?` là một biến ẩn danh được
+//! trả về trong kết quả (`ancestor(a, ?)`).
+//!
+//! Giải bằng SLD resolution có tabling: mỗi lời gọi (theo variant) có một bảng answer;
+//! lời gọi lặp lại khi goal đó đang được giải sẽ dùng các answer hiện có thay vì đệ quy,
+//! và cả thành phần liên thông được lặp đến điểm bất động. Nhờ vậy các quy tắc đệ quy
+//! trái như `path(X,Z) :- path(X,Y), edge(Y,Z)` vẫn dừng trên đồ thị có chu trình.
+//! Mỗi answer kèm cây chứng minh (`Proof`).
+
+use super::LogicalError;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Var(usize),
+    Atom(Arc<str>),
+    Compound(Arc<str>, Vec<Term>),
+}
+
+impl Term {
+    fn functor(&self) -> Option<(&str, usize)> {
+        match self {
+            Term::Atom(name) => Some((name, 0)),
+            Term::Compound(name, args) => Some((name, args.len())),
+            Term::Var(_) => None,
+        }
+    }
+
+    fn offset_vars(&self, offset: usize) -> Term {
+        match self {
+            Term::Var(v) => Term::Var(v + offset),
+            Term::Atom(_) => self.clone(),
+            Term::Compound(name, args) => Term::Compound(
+                name.clone(),
+                args.iter().map(|a| a.offset_vars(offset)).collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Var(v) => write!(f, "_G{}", v),
+            Term::Atom(name) => write_atom(f, name),
+            Term::Compound(name, args) => {
+                write_atom(f, name)?;
+                write!(f, "(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn write_atom(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    let plain = name.parse::<f64>().is_ok()
+        || (name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_'));
+    if plain {
+        write!(f, "{}", name)
+    } else {
+        write!(f, "'{}'", name.replace('\'', "\\'"))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Clause {
+    head: Term,
+    body: Vec<Term>,
+    num_vars: usize,
+    source: String,
+}
+
+/// Cây chứng minh của một answer: goal đã được chứng minh, mệnh đề đã dùng và
+/// chứng minh của các goal trong thân mệnh đề.
+#[derive(Debug)]
+pub struct Proof {
+    pub goal: String,
+    pub clause: String,
+    pub premises: Vec<Arc<Proof>>,
+}
+
+impl Proof {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "goal": self.goal,
+            "clause": self.clause,
+            "premises": self.premises.iter().map(|p| p.to_json()).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Answer {
+    /// Giá trị của các biến có tên trong truy vấn (kể cả `?1`, `?2`, ...)
+    pub bindings: BTreeMap<String, String>,
+    /// Các goal của truy vấn sau khi thế
+    pub goals: Vec<String>,
+    /// Một chứng minh cho mỗi goal
+    pub proofs: Vec<Arc<Proof>>,
+}
+
+impl Answer {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "bindings": self.bindings,
+            "goals": self.goals,
+            "proofs": self.proofs.iter().map(|p| p.to_json()).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SolverLimits {
+    /// Số bước resolution tối đa cho một truy vấn
+    pub max_steps: usize,
+    /// Số answer tối đa trong một bảng
+    pub max_answers: usize,
+}
+
+impl Default for SolverLimits {
+    fn default() -> Self {
+        Self {
+            max_steps: 1_000_000,
+            max_answers: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct KnowledgeBase {
+    clauses: Vec<Clause>,
+}
+
+impl KnowledgeBase {
+    pub fn parse(program: &str) -> Result<Self, LogicalError> {
+        let mut kb = Self::default();
+        kb.add_program(program)?;
+        Ok(kb)
+    }
+
+    /// Thêm các mệnh đề, mỗi mệnh đề kết thúc bằng `.`
+    pub fn add_program(&mut self, program: &str) -> Result<(), LogicalError> {
+        let mut parser = Parser::new(program)?;
+        while !parser.at_end() {
+            let clause = parser.clause()?;
+            self.clauses.push(clause);
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.clauses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn query(&self, query: &str) -> Result<Vec<Answer>, LogicalError> {
+        self.query_with_limits(query, SolverLimits::default())
+    }
+
+    pub fn query_with_limits(
+        &self,
+        query: &str,
+        limits: SolverLimits,
+    ) -> Result<Vec<Answer>, LogicalError> {
+        let mut parser = Parser::new(query)?;
+        let (goals, names) = parser.query()?;
+        let mut solver = Solver {
+            kb: self,
+            tables: HashMap::new(),
+            stack: Vec::new(),
+            incomplete: Vec::new(),
+            next_var: names.len(),
+            steps: 0,
+            answers_added: 0,
+            limits,
+        };
+        let mut solutions = Vec::new();
+        solver.solve_conjunction(&goals, Subst::new(), Vec::new(), &mut solutions)?;
+
+        let mut seen = HashSet::new();
+        let mut answers = Vec::new();
+        for (subst, proofs) in solutions {
+            let instantiated: Vec<String> =
+                goals.iter().map(|g| subst.resolve(g).to_string()).collect();
+            if !seen.insert(instantiated.clone()) {
+                continue;
+            }
+            let bindings = names
+                .iter()
+                .enumerate()
+                .filter(|(_, name)| !name.starts_with('_'))
+                .map(|(v, name)| (name.clone(), subst.resolve(&Term::Var(v)).to_string()))
+                .collect();
+            answers.push(Answer {
+                bindings,
+                goals: instantiated,
+                proofs,
+            });
+        }
+        Ok(answers)
+    }
+}
+
+// ===== Unification =====
+
+#[derive(Debug, Clone, Default)]
+struct Subst {
+    bindings: HashMap<usize, Term>,
+}
+
+impl Subst {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn walk<'t>(&'t self, term: &'t Term) -> &'t Term {
+        let mut term = term;
+        while let Term::Var(v) = term {
+            match self.bindings.get(v) {
+                Some(bound) => term = bound,
+                None => break,
+            }
+        }
+        term
+    }
+
+    fn resolve(&self, term: &Term) -> Term {
+        match self.walk(term) {
+            Term::Compound(name, args) => {
+                Term::Compound(name.clone(), args.iter().map(|a| self.resolve(a)).collect())
+            }
+            other => other.clone(),
+        }
+    }
+
+    fn occurs(&self, var: usize, term: &Term) -> bool {
+        match self.walk(term) {
+            Term::Var(v) => *v == var,
+            Term::Atom(_) => false,
+            Term::Compound(_, args) => args.iter().any(|a| self.occurs(var, a)),
+        }
+    }
+
+    /// Hợp nhất có occurs check; trả về `false` nếu không hợp nhất được.
+    fn unify(&mut self, a: &Term, b: &Term) -> bool {
+        let (a, b) = (self.walk(a).clone(), self.walk(b).clone());
+        match (&a, &b) {
+            (Term::Var(x), Term::Var(y)) if x == y => true,
+            (Term::Var(x), other) | (other, Term::Var(x)) => {
+                if self.occurs(*x, other) {
+                    return false;
+                }
+                self.bindings.insert(*x, other.clone());
+                true
+            }
+            (Term::Atom(x), Term::Atom(y)) => x == y,
+            (Term::Compound(f, xs), Term::Compound(g, ys)) => {
+                f == g && xs.len() == ys.len() && xs.iter().zip(ys).all(|(x, y)| self.unify(x, y))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Khóa variant: hai goal giống nhau sau khi đổi tên biến có cùng khóa.
+fn variant_key(term: &Term) -> String {
+    fn canonical(term: &Term, names: &mut HashMap<usize, usize>) -> Term {
+        match term {
+            Term::Var(v) => {
+                let next = names.len();
+                Term::Var(*names.entry(*v).or_insert(next))
+            }
+            Term::Atom(_) => term.clone(),
+            Term::Compound(name, args) => Term::Compound(
+                name.clone(),
+                args.iter().map(|a| canonical(a, names)).collect(),
+            ),
+        }
+    }
+    canonical(term, &mut HashMap::new()).to_string()
+}
+
+fn max_var(term: &Term) -> Option<usize> {
+    match term {
+        Term::Var(v) => Some(*v),
+        Term::Atom(_) => None,
+        Term::Compound(_, args) => args.iter().filter_map(max_var).max(),
+    }
+}
+
+// ===== SLD resolution with tabling =====
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TableState {
+    /// Đang giải ở vị trí `depth` của stack; `lowlink` là frame thấp nhất mà nó phụ thuộc
+    Evaluating {
+        depth: usize,
+        lowlink: usize,
+    },
+    /// Đã đạt điểm bất động riêng nhưng phụ thuộc một goal thấp hơn vẫn đang giải
+    Incomplete,
+    Complete,
+}
+
+struct Table {
+    state: TableState,
+    answers: Vec<(Term, Arc<Proof>)>,
+    keys: HashSet<String>,
+}
+
+type Solution = (Subst, Vec<Arc<Proof>>);
+
+struct Solver<'kb> {
+    kb: &'kb KnowledgeBase,
+    tables: HashMap<String, Table>,
+    /// Khóa của các goal đang giải
+    stack: Vec<String>,
+    /// Các bảng `Incomplete`, hoàn tất cùng leader của thành phần liên thông
+    incomplete: Vec<String>,
+    next_var: usize,
+    steps: usize,
+    answers_added: usize,
+    limits: SolverLimits,
+}
+
+impl Solver<'_> {
+    fn solve_conjunction(
+        &mut self,
+        goals: &[Term],
+        subst: Subst,
+        proofs: Vec<Arc<Proof>>,
+        out: &mut Vec<Solution>,
+    ) -> Result<(), LogicalError> {
+        let Some((goal, rest)) = goals.split_first() else {
+            out.push((subst, proofs));
+            return Ok(());
+        };
+        let goal = subst.resolve(goal);
+        for (answer, proof) in self.call(&goal)? {
+            let answer = self.rename(&answer);
+            let mut next = subst.clone();
+            if next.unify(&goal, &answer) {
+                let mut proofs = proofs.clone();
+                proofs.push(proof);
+                self.solve_conjunction(rest, next, proofs, out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Các answer của `goal` (đã đầy đủ nếu bảng hoàn tất, hoặc những answer đã biết
+    /// nếu goal đang được giải ở tầng dưới).
+    fn call(&mut self, goal: &Term) -> Result<Vec<(Term, Arc<Proof>)>, LogicalError> {
+        let key = variant_key(goal);
+        match self.tables.get(&key).map(|t| t.state) {
+            Some(TableState::Complete) => return Ok(self.tables[&key].answers.clone()),
+            Some(TableState::Evaluating { depth, .. }) => {
+                // Lời gọi lặp: mọi frame phía trên phụ thuộc vào frame `depth`
+                for frame in &self.stack[depth + 1..] {
+                    if let Some(table) = self.tables.get_mut(frame) {
+                        if let TableState::Evaluating { lowlink, .. } = &mut table.state {
+                            *lowlink = (*lowlink).min(depth);
+                        }
+                    }
+                }
+                return Ok(self.tables[&key].answers.clone());
+            }
+            Some(TableState::Incomplete) | None => {}
+        }
+
+        let depth = self.stack.len();
+        let incomplete_mark = self.incomplete.len();
+        self.stack.push(key.clone());
+        let table = self.tables.entry(key.clone()).or_insert_with(|| Table {
+            state: TableState::Complete,
+            answers: Vec::new(),
+            keys: HashSet::new(),
+        });
+        table.state = TableState::Evaluating {
+            depth,
+            lowlink: depth,
+        };
+
+        // Lặp đến khi không còn answer mới ở bất kỳ bảng nào
+        loop {
+            let before = self.answers_added;
+            self.resolve_clauses(goal, &key)?;
+            if self.answers_added == before {
+                break;
+            }
+        }
+
+        self.stack.pop();
+        let lowlink = match self.tables[&key].state {
+            TableState::Evaluating { lowlink, .. } => lowlink,
+            _ => depth,
+        };
+        if lowlink < depth {
+            if let Some(table) = self.tables.get_mut(&key) {
+                table.state = TableState::Incomplete;
+            }
+            self.incomplete.push(key.clone());
+            if let Some(parent) = self.stack.last().and_then(|k| self.tables.get_mut(k)) {
+                if let TableState::Evaluating {
+                    lowlink: parent_low,
+                    ..
+                } = &mut parent.state
+                {
+                    *parent_low = (*parent_low).min(lowlink);
+                }
+            }
+        } else {
+            // Leader: cả thành phần liên thông đã đạt điểm bất động
+            for member in self.incomplete.drain(incomplete_mark..) {
+                if let Some(table) = self.tables.get_mut(&member) {
+                    table.state = TableState::Complete;
+                }
+            }
+            if let Some(table) = self.tables.get_mut(&key) {
+                table.state = TableState::Complete;
+            }
+        }
+        Ok(self.tables[&key].answers.clone())
+    }
+
+    fn resolve_clauses(&mut self, goal: &Term, key: &str) -> Result<(), LogicalError> {
+        let functor = goal.functor();
+        for clause in &self.kb.clauses {
+            if clause.head.functor() != functor {
+                continue;
+            }
+            self.steps += 1;
+            if self.steps > self.limits.max_steps {
+                return Err(LogicalError::LimitExceeded(format!(
+                    "quá {} bước resolution",
+                    self.limits.max_steps
+                )));
+            }
+            let offset = self.next_var;
+            self.next_var += clause.num_vars;
+            let head = clause.head.offset_vars(offset);
+            let body: Vec<Term> = clause.body.iter().map(|t| t.offset_vars(offset)).collect();
+
+            let mut subst = Subst::new();
+            if !subst.unify(goal, &head) {
+                continue;
+            }
+            let mut solutions = Vec::new();
+            self.solve_conjunction(&body, subst, Vec::new(), &mut solutions)?;
+            for (subst, premises) in solutions {
+                let answer = subst.resolve(goal);
+                let proof = Arc::new(Proof {
+                    goal: answer.to_string(),
+                    clause: clause.source.clone(),
+                    premises,
+                });
+                self.add_answer(key, answer, proof)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_answer(
+        &mut self,
+        key: &str,
+        answer: Term,
+        proof: Arc<Proof>,
+    ) -> Result<(), LogicalError> {
+        let max_answers = self.limits.max_answers;
+        let Some(table) = self.tables.get_mut(key) else {
+            return Ok(());
+        };
+        if table.keys.insert(variant_key(&answer)) {
+            if table.answers.len() >= max_answers {
+                return Err(LogicalError::LimitExceeded(format!(
+                    "quá {} answer cho {}",
+                    max_answers, key
+                )));
+            }
+            table.answers.push((answer, proof));
+            self.answers_added += 1;
+        }
+        Ok(())
+    }
+
+    /// Đổi tên biến của answer sang biến mới để không trùng với goal đang giải.
+    fn rename(&mut self, term: &Term) -> Term {
+        match max_var(term) {
+            None => term.clone(),
+            Some(max) => {
+                let offset = self.next_var;
+                self.next_var += max + 1;
+                term.offset_vars(offset)
+            }
+        }
+    }
+}
+
+// ===== Parser =====
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Var(String),
+    Quoted(String),
+    Query,
+    LParen,
+    RParen,
+    Comma,
+    Neck,
+    Dot,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, LogicalError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '%' => {
+                // Chú thích đến hết dòng
+                while chars.next().is_some_and(|c| c != '\n') {}
+            }
+            '(' | ')' | ',' | '.' | '?' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    ',' => Token::Comma,
+                    '.' => Token::Dot,
+                    _ => Token::Query,
+                });
+            }
+            ':' => {
+                chars.next();
+                if chars.next() != Some('-') {
+                    return Err(LogicalError::InvalidRule("Mong đợi ':-'".to_string()));
+                }
+                tokens.push(Token::Neck);
+            }
+            '\'' | '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => text.extend(chars.next()),
+                        Some(q) if q == c => break,
+                        Some(other) => text.push(other),
+                        None => {
+                            return Err(LogicalError::InvalidRule("Chuỗi chưa đóng".to_string()))
+                        }
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let mut text = String::new();
+                text.push(c);
+                chars.next();
+                while let Some(&next) = chars.peek() {
+                    // Dấu chấm thập phân chỉ thuộc số khi theo sau là chữ số
+                    let decimal = next == '.'
+                        && text.chars().all(|c| c.is_ascii_digit() || c == '-')
+                        && chars.clone().nth(1).is_some_and(|d| d.is_ascii_digit());
+                    if next.is_alphanumeric() || next == '_' || decimal {
+                        text.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if text.starts_with(|c: char| c.is_uppercase() || c == '_') {
+                    tokens.push(Token::Var(text));
+                } else {
+                    tokens.push(Token::Name(text));
+                }
+            }
+            other => {
+                return Err(LogicalError::InvalidRule(format!(
+                    "Ký tự không hợp lệ '{}'",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Tên biến của mệnh đề / truy vấn đang parse, theo chỉ số biến
+    vars: Vec<String>,
+    anonymous: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, LogicalError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            pos: 0,
+            vars: Vec::new(),
+            anonymous: 0,
+        })
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), LogicalError> {
+        match self.tokens.get(self.pos) {
+            Some(t) if *t == token => {
+                self.pos += 1;
+                Ok(())
+            }
+            other => Err(LogicalError::InvalidRule(format!(
+                "Mong đợi {:?}, gặp {:?}",
+                token, other
+            ))),
+        }
+    }
+
+    fn clause(&mut self) -> Result<Clause, LogicalError> {
+        self.vars.clear();
+        let head = self.term()?;
+        if matches!(head, Term::Var(_)) {
+            return Err(LogicalError::InvalidRule(
+                "Đầu mệnh đề không thể là biến".to_string(),
+            ));
+        }
+        let body = if self.peek() == Some(&Token::Neck) {
+            self.pos += 1;
+            self.goals()?
+        } else {
+            Vec::new()
+        };
+        self.expect(Token::Dot)?;
+        let mut source = head.to_string();
+        if !body.is_empty() {
+            let body: Vec<String> = body.iter().map(|g| g.to_string()).collect();
+            source = format!("{} :- {}", source, body.join(", "));
+        }
+        // Hiển thị biến bằng tên gốc trong mệnh đề
+        for (v, name) in self.vars.iter().enumerate().rev() {
+            source = source.replace(&format!("_G{}", v), name);
+        }
+        Ok(Clause {
+            head,
+            body,
+            num_vars: self.vars.len(),
+            source,
+        })
+    }
+
+    /// Hội các goal, có thể kết thúc bằng `.`; trả về goal cùng tên biến theo chỉ số.
+    fn query(&mut self) -> Result<(Vec<Term>, Vec<String>), LogicalError> {
+        self.vars.clear();
+        let goals = self.goals()?;
+        if self.peek() == Some(&Token::Dot) {
+            self.pos += 1;
+        }
+        if !self.at_end() {
+            return Err(LogicalError::InvalidRule(format!(
+                "Thừa token sau truy vấn: {:?}",
+                self.peek()
+            )));
+        }
+        Ok((goals, self.vars.clone()))
+    }
+
+    fn goals(&mut self) -> Result<Vec<Term>, LogicalError> {
+        let mut goals = vec![self.goal()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            goals.push(self.goal()?);
+        }
+        Ok(goals)
+    }
+
+    fn goal(&mut self) -> Result<Term, LogicalError> {
+        let term = self.term()?;
+        if matches!(term, Term::Var(_)) {
+            return Err(LogicalError::InvalidRule(
+                "Goal không thể là biến".to_string(),
+            ));
+        }
+        Ok(term)
+    }
+
+    fn term(&mut self) -> Result<Term, LogicalError> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Var(name)) => Ok(self.var(name)),
+            Some(Token::Query) => {
+                self.anonymous += 1;
+                Ok(self.var(format!("?{}", self.anonymous)))
+            }
+            Some(Token::Name(name)) | Some(Token::Quoted(name)) => {
+                let name: Arc<str> = Arc::from(name);
+                if self.peek() != Some(&Token::LParen) {
+                    return Ok(Term::Atom(name));
+                }
+                self.pos += 1;
+                let mut args = vec![self.term()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.term()?);
+                }
+                self.expect(Token::RParen)?;
+                Ok(Term::Compound(name, args))
+            }
+            other => Err(LogicalError::InvalidRule(format!(
+                "Mong đợi term, gặp {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn var(&mut self, name: String) -> Term {
+        // `_` là biến ẩn danh: mỗi lần xuất hiện là một biến mới
+        if name != "_" {
+            if let Some(v) = self.vars.iter().position(|n| *n == name) {
+                return Term::Var(v);
+            }
+        }
+        self.vars.push(name);
+        Term::Var(self.vars.len() - 1)
+    }
+}
+
+/// Chạy truy vấn Horn-clause theo hợp đồng JSON của skill:
+/// `{"program": "...", "facts": [...], "rules": [...], "query": "..."}`.
+pub(crate) fn execute_query(input: &Map<String, Value>) -> Result<Value, LogicalError> {
+    let query = input
+        .get("query")
+        .and_then(Value::as_str)
+        .ok_or(LogicalError::InvalidInput)?;
+    let mut kb = KnowledgeBase::default();
+    if let Some(program) = input.get("program") {
+        kb.add_program(program.as_str().ok_or(LogicalError::InvalidInput)?)?;
+    }
+    for field in ["facts", "rules"] {
+        let Some(clauses) = input.get(field) else {
+            continue;
+        };
+        for clause in clauses.as_array().ok_or(LogicalError::InvalidInput)? {
+            let clause = clause.as_str().ok_or(LogicalError::InvalidInput)?.trim();
+            let terminated = if clause.ends_with('.') {
+                clause.to_string()
+            } else {
+                format!("{}.", clause)
+            };
+            kb.add_program(&terminated)?;
+        }
+    }
+    let answers = kb.query(query)?;
+    Ok(json!({
+        "result": !answers.is_empty(),
+        "answers": answers.iter().map(Answer::to_json).collect::<Vec<_>>(),
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn bindings(answers: &[Answer], var: &str) -> Vec<String> {
+        let mut values: Vec<String> = answers.iter().map(|a| a.bindings[var].clone()).collect();
+        values.sort();
+        values
+    }
+
+    const FAMILY: &str = "
+        parent(a, b). parent(b, c). parent(c, d).
+        ancestor(X, Y) :- parent(X, Y).
+        ancestor(X, Y) :- parent(X, Z), ancestor(Z, Y).
+    ";
+
+    #[test]
+    fn test_ancestor_query_returns_all_bindings() {
+        let kb = KnowledgeBase::parse(FAMILY).unwrap();
+        let answers = kb.query("ancestor(a, ?)").unwrap();
+        assert_eq!(bindings(&answers, "?1"), vec!["b", "c", "d"]);
+        assert!(kb.query("ancestor(d, X)").unwrap().is_empty());
+        let answers = kb.query("ancestor(X, d), parent(a, X)").unwrap();
+        assert_eq!(bindings(&answers, "X"), vec!["b"]);
+    }
+
+    #[test]
+    fn test_left_recursion_on_cyclic_graph_terminates() {
+        let kb = KnowledgeBase::parse(
+            "edge(1, 2). edge(2, 3). edge(3, 1). edge(3, 4).
+             path(X, Y) :- path(X, Z), edge(Z, Y).
+             path(X, Y) :- edge(X, Y).",
+        )
+        .unwrap();
+        let answers = kb.query("path(1, Y)").unwrap();
+        assert_eq!(bindings(&answers, "Y"), vec!["1", "2", "3", "4"]);
+        assert_eq!(kb.query("path(X, Y)").unwrap().len(), 12);
+        assert!(kb.query("path(4, Y)").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_mutual_recursion_reaches_fixpoint() {
+        let kb = KnowledgeBase::parse(
+            "succ(z, s1). succ(s1, s2). succ(s2, s3). succ(s3, s4).
+             even(z).
+             even(Y) :- odd(X), succ(X, Y).
+             odd(Y) :- even(X), succ(X, Y).",
+        )
+        .unwrap();
+        assert_eq!(
+            bindings(&kb.query("even(N)").unwrap(), "N"),
+            vec!["s2", "s4", "z"]
+        );
+        assert_eq!(
+            bindings(&kb.query("odd(N)").unwrap(), "N"),
+            vec!["s1", "s3"]
+        );
+    }
+
+    #[test]
+    fn test_unification_with_compound_terms() {
+        let kb = KnowledgeBase::parse(
+            "owns(alice, book('Dune', herbert)). owns(bob, book(neuromancer, gibson)).
+             reads(P, T) :- owns(P, book(T, _)).",
+        )
+        .unwrap();
+        let answers = kb.query("reads(Who, 'Dune')").unwrap();
+        assert_eq!(bindings(&answers, "Who"), vec!["alice"]);
+        let answers = kb.query("owns(bob, book(T, A))").unwrap();
+        assert_eq!(answers[0].bindings["T"], "neuromancer");
+        assert_eq!(answers[0].bindings["A"], "gibson");
+        // Occurs check: X không thể hợp nhất với f(X)
+        let kb = KnowledgeBase::parse("same(X, X).").unwrap();
+        assert!(kb.query("same(Y, f(Y))").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_proof_trace_follows_the_rules_used() {
+        let kb = KnowledgeBase::parse(FAMILY).unwrap();
+        let answers = kb.query("ancestor(a, c)").unwrap();
+        assert_eq!(answers.len(), 1);
+        let proof = &answers[0].proofs[0];
+        assert_eq!(proof.goal, "ancestor(a,c)");
+        assert_eq!(proof.clause, "ancestor(X,Y) :- parent(X,Z), ancestor(Z,Y)");
+        let premises: Vec<&str> = proof.premises.iter().map(|p| p.goal.as_str()).collect();
+        assert_eq!(premises, vec!["parent(a,b)", "ancestor(b,c)"]);
+        assert_eq!(proof.premises[0].clause, "parent(a,b)");
+        assert!(proof.premises[0].premises.is_empty());
+        assert_eq!(proof.premises[1].clause, "ancestor(X,Y) :- parent(X,Y)");
+    }
+
+    #[test]
+    fn test_limits_and_parse_errors() {
+        let kb = KnowledgeBase::parse("nat(z). nat(s(X)) :- nat(X).").unwrap();
+        let limits = SolverLimits {
+            max_steps: 1_000,
+            max_answers: 50,
+        };
+        assert!(matches!(
+            kb.query_with_limits("nat(N)", limits),
+            Err(LogicalError::LimitExceeded(_))
+        ));
+        assert!(KnowledgeBase::parse("parent(a, b)").is_err());
+        assert!(KnowledgeBase::parse("X :- parent(X, b).").is_err());
+        assert!(kb.query("nat(").is_err());
+    }
+}
@@ -0,0 +1,817 @@
+#[cfg(test)]
+#[allow(clippy::items_after_test_module, clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_simple_and_true() {
+        let skill = LogicalReasoningSkill;
+        let context = json!({"is_raining": true, "time": 18});
+        let ast = json!({
+            "type": "AND",
+            "children": [
+                {"type": "VAR", "name": "is_raining"},
+                {"type": "CONST", "value": true}
+            ]
+        });
+        let input = json!({"ast": ast, "context": context});
+        assert_eq!(skill.execute(input).await.unwrap(), json!({"result": true}));
+    }
+
+    #[tokio::test]
+    async fn test_simple_and_false() {
+        let skill = LogicalReasoningSkill;
+        let context = json!({"is_raining": false, "time": 18});
+        let ast = json!({
+            "type": "AND",
+            "children": [
+                {"type": "VAR", "name": "is_raining"},
+                {"type": "CONST", "value": true}
+            ]
+        });
+        let input = json!({"ast": ast, "context": context});
+        assert_eq!(
+            skill.execute(input).await.unwrap(),
+            json!({"result": false})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_simple_or_true() {
+        let skill = LogicalReasoningSkill;
+        let context = json!({"is_raining": false, "time": 18});
+        let ast = json!({
+            "type": "OR",
+            "children": [
+                {"type": "VAR", "name": "is_raining"},
+                {"type": "CONST", "value": true}
+            ]
+        });
+        let input = json!({"ast": ast, "context": context});
+        assert_eq!(skill.execute(input).await.unwrap(), json!({"result": true}));
+    }
+
+    fn eval(ast: Value, facts: Value) -> Truth {
+        OptimizedJsonAstEngine::new(8)
+            .infer(&facts, &ast.to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_comparisons_over_numbers_and_strings() {
+        let facts = json!({"time": 18, "location": "indoor", "limit": 17.5});
+        let cmp = |op: &str, lhs: Value, rhs: Value| json!({"type": op, "children": [lhs, rhs]});
+        let time = json!({"type": "VAR", "name": "time"});
+        let const_ = |v: Value| json!({"type": "CONST", "value": v});
+
+        assert_eq!(
+            eval(cmp("GT", time.clone(), const_(json!(17))), facts.clone()),
+            Truth::True
+        );
+        assert_eq!(
+            eval(cmp("LTE", time.clone(), const_(json!(17))), facts.clone()),
+            Truth::False
+        );
+        assert_eq!(
+            eval(
+                cmp("GT", time.clone(), json!({"type": "VAR", "name": "limit"})),
+                facts.clone()
+            ),
+            Truth::True
+        );
+        assert_eq!(
+            eval(cmp("EQ", time.clone(), const_(json!(18.0))), facts.clone()),
+            Truth::True
+        );
+        let location = json!({"type": "VAR", "name": "location"});
+        assert_eq!(
+            eval(
+                cmp("EQ", location.clone(), const_(json!("indoor"))),
+                facts.clone()
+            ),
+            Truth::True
+        );
+        assert_eq!(
+            eval(
+                cmp("LT", location.clone(), const_(json!("outdoor"))),
+                facts.clone()
+            ),
+            Truth::True
+        );
+        assert_eq!(
+            eval(
+                cmp("NEQ", location.clone(), const_(json!(1))),
+                facts.clone()
+            ),
+            Truth::True
+        );
+
+        // Thứ tự giữa số và chuỗi là lỗi kiểu, không phải false
+        let mixed = cmp("LT", location, time);
+        assert!(matches!(
+            OptimizedJsonAstEngine::new(8).infer(&facts, &mixed.to_string()),
+            Err(LogicalError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_implies_xor_iff_truth_tables() {
+        let b = |v: bool| json!({"type": "CONST", "value": v});
+        for (p, q) in [(true, true), (true, false), (false, true), (false, false)] {
+            let node = |op: &str| json!({"type": op, "children": [b(p), b(q)]});
+            assert_eq!(eval(node("IMPLIES"), json!({})), Truth::from(!p || q));
+            assert_eq!(eval(node("XOR"), json!({})), Truth::from(p != q));
+            assert_eq!(eval(node("IFF"), json!({})), Truth::from(p == q));
+        }
+    }
+
+    #[test]
+    fn test_quantifiers_over_arrays_with_nested_paths() {
+        let facts = json!({
+            "user": {"profile": {"age": 34}},
+            "orders": [
+                {"total": 120, "status": "paid"},
+                {"total": 80, "status": "paid"},
+                {"total": 45, "status": "pending"}
+            ]
+        });
+        let adult = json!({">=": [{"var": "user.profile.age"}, 18]});
+        assert_eq!(eval(adult, facts.clone()), Truth::True);
+        assert_eq!(
+            eval(
+                json!({"==": [{"var": "orders.2.status"}, "pending"]}),
+                facts.clone()
+            ),
+            Truth::True
+        );
+
+        let quantified = |q: &str, body: Value| json!({"type": q, "var": "o", "collection": {"type": "VAR", "name": "orders"}, "body": body});
+        let big = json!({"type": "GT", "children": [{"type": "VAR", "name": "o.total"}, {"type": "CONST", "value": 100}]});
+        let positive = json!({"type": "GT", "children": [{"type": "VAR", "name": "o.total"}, {"type": "CONST", "value": 0}]});
+        assert_eq!(
+            eval(quantified("EXISTS", big.clone()), facts.clone()),
+            Truth::True
+        );
+        assert_eq!(eval(quantified("FORALL", big), facts.clone()), Truth::False);
+        assert_eq!(
+            eval(quantified("FORALL", positive), facts.clone()),
+            Truth::True
+        );
+        // Dạng rút gọn: [biến, tập hợp, thân]
+        let all_paid =
+            json!({"forall": ["o", {"var": "orders"}, {"==": [{"var": "o.status"}, "paid"]}]});
+        assert_eq!(eval(all_paid, facts.clone()), Truth::False);
+        let none = json!({"exists": ["o", [], true]});
+        assert_eq!(eval(none, facts), Truth::False);
+    }
+
+    #[test]
+    fn test_missing_facts_are_unknown() {
+        let facts = json!({"is_raining": true, "user": {"name": "an"}, "nothing": null});
+        assert_eq!(
+            eval(json!({"var": "is_windy"}), facts.clone()),
+            Truth::Unknown
+        );
+        assert_eq!(
+            eval(
+                json!({">": [{"var": "user.profile.age"}, 18]}),
+                facts.clone()
+            ),
+            Truth::Unknown
+        );
+        assert_eq!(
+            eval(json!({"var": "nothing"}), facts.clone()),
+            Truth::Unknown
+        );
+        // Kleene: unknown chỉ lan truyền khi kết quả phụ thuộc vào nó
+        assert_eq!(
+            eval(
+                json!({"or": [{"var": "is_windy"}, {"var": "is_raining"}]}),
+                facts.clone()
+            ),
+            Truth::True
+        );
+        assert_eq!(
+            eval(
+                json!({"and": [{"var": "is_windy"}, {"var": "is_raining"}]}),
+                facts.clone()
+            ),
+            Truth::Unknown
+        );
+        assert_eq!(
+            eval(json!({"!": {"var": "is_windy"}}), facts.clone()),
+            Truth::Unknown
+        );
+        assert_eq!(
+            eval(
+                json!({"implies": [false, {"var": "is_windy"}]}),
+                facts.clone()
+            ),
+            Truth::True
+        );
+        assert_eq!(
+            eval(json!({"exists": ["x", {"var": "missing"}, true]}), facts),
+            Truth::Unknown
+        );
+    }
+
+    #[tokio::test]
+    async fn test_skill_reports_unknown_as_null() {
+        let skill = LogicalReasoningSkill;
+        let ast = json!({"type": "GT", "children": [{"type": "VAR", "name": "time"}, {"type": "CONST", "value": 17}]});
+        let output = skill
+            .execute(json!({"ast": ast.clone(), "context": {"time": 18}}))
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"result": true}));
+        let output = skill
+            .execute(json!({"ast": ast, "context": {}}))
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"result": null}));
+    }
+
+    #[tokio::test]
+    async fn test_skill_answers_horn_clause_queries() {
+        let skill = LogicalReasoningSkill;
+        let output = skill
+            .execute(json!({
+                "facts": ["parent(an, binh)", "parent(binh, chi)"],
+                "rules": ["ancestor(X, Y) :- parent(X, Y)", "ancestor(X, Y) :- ancestor(X, Z), parent(Z, Y)"],
+                "query": "ancestor(an, Who)"
+            }))
+            .await
+            .unwrap();
+        assert_eq!(output["result"], json!(true));
+        let answers = output["answers"].as_array().unwrap();
+        assert_eq!(answers.len(), 2);
+        assert_eq!(answers[0]["bindings"], json!({"Who": "binh"}));
+        assert_eq!(answers[0]["proofs"][0]["clause"], json!("ancestor(X,Y) :- parent(X,Y)"));
+
+        let output = skill
+            .execute(json!({"program": "parent(an, binh).", "query": "parent(binh, ?)"}))
+            .await
+            .unwrap();
+        assert_eq!(output, json!({"result": false, "answers": []}));
+        assert!(skill
+            .execute(json!({"program": "parent(an binh).", "query": "parent(X, Y)"}))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_rules_fail_to_compile() {
+        let engine = OptimizedJsonAstEngine::new(8);
+        for rule in [
+            json!({"type": "IMPLIES", "children": [true]}),
+            json!({"type": "CONST", "value": 3}),
+            json!({"type": "FORALL", "var": "x", "body": true}),
+            json!({"type": "MAYBE"}),
+            json!({"==": [{"var": "a"}, {"and": [true]}]}),
+        ] {
+            assert!(matches!(
+                engine.infer(&json!({}), &rule.to_string()),
+                Err(LogicalError::InvalidRule(_))
+            ));
+        }
+        assert_eq!(engine.cached_rules(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_inference_shares_the_cached_rule() {
+        let engine = Arc::new(OptimizedJsonAstEngine::new(8));
+        let rule = json!({">": [{"var": "n"}, 10]}).to_string();
+        assert_eq!(engine.infer(&json!({"n": 11}), &rule).unwrap(), Truth::True);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let engine = Arc::clone(&engine);
+                let rule = rule.clone();
+                std::thread::spawn(move || engine.infer(&json!({ "n": i * 10 }), &rule).unwrap())
+            })
+            .collect();
+        let results: Vec<Truth> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(
+            results,
+            vec![Truth::False, Truth::False, Truth::True, Truth::True]
+        );
+        assert_eq!(engine.cached_rules(), 1);
+    }
+}
+pub mod horn;
+
+use async_trait::async_trait;
+use lru::LruCache;
+use pandora_core::interfaces::skills::{SkillDescriptor, SkillModule, SkillOutput};
+use pandora_error::PandoraError;
+use serde_json::Value as SkillInput;
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, OnceLock};
+use thiserror::Error;
+
+pub struct LogicalReasoningSkill;
+
+#[derive(Debug, Error)]
+pub enum LogicalError {
+    #[error("Lỗi parse JSON: {0}")]
+    JsonParse(#[from] serde_json::Error),
+    #[error("Quy tắc không hợp lệ: {0}")]
+    InvalidRule(String),
+    #[error("Sai kiểu dữ liệu: {0}")]
+    TypeMismatch(String),
+    #[error("Vượt giới hạn suy diễn: {0}")]
+    LimitExceeded(String),
+    #[error("Input không hợp lệ")]
+    InvalidInput,
+}
+
+/// Logic ba giá trị (Kleene): `Unknown` khi thiếu fact cần thiết để kết luận.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Truth {
+    True,
+    False,
+    Unknown,
+}
+
+impl Truth {
+    pub fn as_bool(self) -> Option<bool> {
+        match self {
+            Truth::True => Some(true),
+            Truth::False => Some(false),
+            Truth::Unknown => None,
+        }
+    }
+
+    pub fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Truth::False, _) | (_, Truth::False) => Truth::False,
+            (Truth::True, Truth::True) => Truth::True,
+            _ => Truth::Unknown,
+        }
+    }
+
+    pub fn or(self, other: Self) -> Self {
+        !(!self).and(!other)
+    }
+
+    pub fn implies(self, other: Self) -> Self {
+        (!self).or(other)
+    }
+
+    pub fn xor(self, other: Self) -> Self {
+        match (self.as_bool(), other.as_bool()) {
+            (Some(a), Some(b)) => Truth::from(a != b),
+            _ => Truth::Unknown,
+        }
+    }
+
+    pub fn iff(self, other: Self) -> Self {
+        !self.xor(other)
+    }
+}
+
+impl std::ops::Not for Truth {
+    type Output = Truth;
+
+    fn not(self) -> Self {
+        match self {
+            Truth::True => Truth::False,
+            Truth::False => Truth::True,
+            Truth::Unknown => Truth::Unknown,
+        }
+    }
+}
+
+impl From<bool> for Truth {
+    fn from(value: bool) -> Self {
+        if value {
+            Truth::True
+        } else {
+            Truth::False
+        }
+    }
+}
+
+pub type CompiledRule = Box<dyn Fn(&Value) -> Result<Truth, LogicalError> + Send + Sync>;
+
+/// Node đã compile: đánh giá một công thức trong một scope.
+type CompiledNode = Box<dyn for<'a> Fn(&Scope<'a>) -> Result<Truth, LogicalError> + Send + Sync>;
+
+/// Facts cùng các biến đã được lượng từ ràng buộc (trong cùng nhất ở đầu chuỗi).
+struct Scope<'a> {
+    facts: &'a Value,
+    binding: Option<(&'a str, &'a Value)>,
+    parent: Option<&'a Scope<'a>>,
+}
+
+impl<'a> Scope<'a> {
+    fn root(facts: &'a Value) -> Self {
+        Self {
+            facts,
+            binding: None,
+            parent: None,
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&'a Value> {
+        match self.binding {
+            Some((bound, value)) if bound == name => Some(value),
+            _ => match self.parent {
+                Some(parent) => parent.lookup(name),
+                None => self.facts.get(name),
+            },
+        }
+    }
+
+    /// Giải path `a.b.0.c`; segment đầu có thể là biến lượng từ. `null` được coi như thiếu.
+    fn resolve(&self, path: &[String]) -> Option<&'a Value> {
+        let (head, rest) = path.split_first()?;
+        let mut value = self.lookup(head)?;
+        for segment in rest {
+            value = match value {
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                _ => value.get(segment.as_str())?,
+            };
+        }
+        (!value.is_null()).then_some(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Toán hạng của phép so sánh hoặc tập hợp của lượng từ.
+enum Term {
+    Const(Value),
+    Var(Vec<String>),
+}
+
+impl Term {
+    fn eval<'v>(&'v self, scope: &Scope<'v>) -> Option<&'v Value> {
+        match self {
+            Term::Const(value) => (!value.is_null()).then_some(value),
+            Term::Var(path) => scope.resolve(path),
+        }
+    }
+}
+
+pub struct OptimizedJsonAstEngine {
+    rule_cache: Arc<Mutex<LruCache<u64, Arc<CompiledRule>>>>,
+}
+
+impl OptimizedJsonAstEngine {
+    pub fn new(cache_capacity: usize) -> Self {
+        Self {
+            rule_cache: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(cache_capacity).unwrap(),
+            ))),
+        }
+    }
+
+    pub fn infer(&self, facts: &Value, rules_json: &str) -> Result<Truth, LogicalError> {
+        let rule_hash = seahash::hash(rules_json.as_bytes());
+        // Chỉ giữ khóa cache khi tra cứu/ghi, không giữ trong lúc compile hay đánh giá,
+        // để các lần suy luận đồng thời trên engine dùng chung không phải xếp hàng.
+        let cached = self.rule_cache.lock().unwrap().get(&rule_hash).cloned();
+        let compiled_rule = match cached {
+            Some(compiled_rule) => compiled_rule,
+            None => {
+                let ast = serde_json::from_str(rules_json)?;
+                let compiled_rule = Arc::new(self.compile_ast_to_closure(ast)?);
+                self.rule_cache
+                    .lock()
+                    .unwrap()
+                    .put(rule_hash, Arc::clone(&compiled_rule));
+                compiled_rule
+            }
+        };
+        compiled_rule(facts)
+    }
+
+    /// Số quy tắc đã compile đang nằm trong cache.
+    pub fn cached_rules(&self) -> usize {
+        self.rule_cache.lock().unwrap().len()
+    }
+
+    fn compile_ast_to_closure(&self, ast: Value) -> Result<CompiledRule, LogicalError> {
+        let root = compile_formula(&ast)?;
+        let rule_logic = move |facts: &Value| -> Result<Truth, LogicalError> {
+            if !facts.is_object() {
+                return Err(LogicalError::InvalidInput);
+            }
+            root(&Scope::root(facts))
+        };
+        Ok(Box::new(rule_logic))
+    }
+}
+
+/// Engine dùng chung cho mọi lần gọi skill, để cache quy tắc đã compile có hiệu lực.
+fn shared_engine() -> &'static OptimizedJsonAstEngine {
+    static ENGINE: OnceLock<OptimizedJsonAstEngine> = OnceLock::new();
+    ENGINE.get_or_init(|| OptimizedJsonAstEngine::new(128))
+}
+
+#[async_trait]
+impl SkillModule for LogicalReasoningSkill {
+    fn descriptor(&self) -> SkillDescriptor {
+        SkillDescriptor {
+			name: "logical_reasoning".to_string(),
+			description: "Đánh giá biểu thức logic dạng cây AST (AND, OR, NOT, IMPLIES, XOR, IFF, so sánh, FORALL/EXISTS, VAR theo path, CONST); result là null khi thiếu fact. Với 'query', suy diễn lùi trên Horn clause (program/facts/rules) và trả về bindings kèm proof.".to_string(),
+			input_schema: r#"{"type":"object","properties":{"ast":{"type":"object"},"context":{"type":"object"},"program":{"type":"string"},"facts":{"type":"array","items":{"type":"string"}},"rules":{"type":"array","items":{"type":"string"}},"query":{"type":"string"}},"oneOf":[{"required":["ast","context"]},{"required":["query"]}]}"#.to_string(),
+			output_schema: r#"{"type":"object","properties":{"result":{"type":["boolean","null"]},"answers":{"type":"array","items":{"type":"object","properties":{"bindings":{"type":"object"},"goals":{"type":"array"},"proofs":{"type":"array"}}}}}}"#.to_string(),
+		}
+    }
+
+    async fn execute(&self, input: SkillInput) -> SkillOutput {
+        if let Some(query_input) = input.as_object().filter(|o| o.contains_key("query")) {
+            return horn::execute_query(query_input)
+                .map_err(|e| PandoraError::skill_exec("logical_reasoning", e.to_string()));
+        }
+        let rules_json = input
+            .get("ast")
+            .and_then(|v| serde_json::to_string(v).ok())
+            .ok_or_else(|| PandoraError::InvalidSkillInput {
+                skill_name: "logical_reasoning".into(),
+                message: "Missing 'ast' field".into(),
+            })?;
+        let facts = input
+            .get("context")
+            .ok_or_else(|| PandoraError::InvalidSkillInput {
+                skill_name: "logical_reasoning".into(),
+                message: "Missing or invalid 'context' field".into(),
+            })?;
+        shared_engine()
+            .infer(facts, &rules_json)
+            .map(|result| json!({"result": result.as_bool()}))
+            .map_err(|e| PandoraError::skill_exec("logical_reasoning", e.to_string()))
+    }
+}
+
+// ===== Compiler =====
+//
+// Hai cú pháp tương đương được chấp nhận:
+// - dạng có tag: `{"type": "GT", "children": [{"type": "VAR", "name": "time"}, {"type": "CONST", "value": 17}]}`
+// - dạng rút gọn: `{">": [{"var": "time"}, 17]}`, giá trị JSON trần là hằng.
+
+fn invalid(message: impl Into<String>) -> LogicalError {
+    LogicalError::InvalidRule(message.into())
+}
+
+/// Tên toán tử và danh sách đối số của một node.
+fn operator(node: &Value) -> Result<(String, Vec<Value>), LogicalError> {
+    let object = node
+        .as_object()
+        .ok_or_else(|| invalid(format!("Node không hợp lệ: {}", node)))?;
+    if let Some(node_type) = object.get("type") {
+        let node_type = node_type
+            .as_str()
+            .ok_or_else(|| invalid("Trường 'type' phải là chuỗi"))?
+            .to_ascii_uppercase();
+        let field = |name: &str| {
+            object
+                .get(name)
+                .cloned()
+                .ok_or_else(|| invalid(format!("Thiếu trường '{}' cho {}", name, node_type)))
+        };
+        let args = match node_type.as_str() {
+            "CONST" => vec![field("value")?],
+            "VAR" => vec![field("name")?],
+            "NOT" => vec![field("child")?],
+            "FORALL" | "EXISTS" => vec![field("var")?, field("collection")?, field("body")?],
+            "AND" | "OR" | "IMPLIES" | "XOR" | "IFF" | "EQ" | "NEQ" | "LT" | "LTE" | "GT"
+            | "GTE" => match field("children")? {
+                Value::Array(children) => children,
+                _ => {
+                    return Err(invalid(format!(
+                        "'children' của {} phải là mảng",
+                        node_type
+                    )))
+                }
+            },
+            _ => return Err(invalid(format!("Không hỗ trợ node type: {}", node_type))),
+        };
+        return Ok((node_type, args));
+    }
+    match object.iter().next() {
+        Some((op, args)) if object.len() == 1 => {
+            let args = match args {
+                Value::Array(args) if op != "var" => args.clone(),
+                other => vec![other.clone()],
+            };
+            Ok((op.to_ascii_uppercase(), args))
+        }
+        _ => Err(invalid(format!("Node không hợp lệ: {}", node))),
+    }
+}
+
+fn arity<const N: usize>(op: &str, args: Vec<Value>) -> Result<[Value; N], LogicalError> {
+    let count = args.len();
+    args.try_into()
+        .map_err(|_| invalid(format!("{} cần {} đối số, nhận {}", op, N, count)))
+}
+
+fn compile_formula(node: &Value) -> Result<CompiledNode, LogicalError> {
+    if let Value::Bool(value) = node {
+        let truth = Truth::from(*value);
+        return Ok(Box::new(move |_| Ok(truth)));
+    }
+    if !node.is_object() {
+        return Err(invalid(format!("Hằng {} không phải boolean", node)));
+    }
+    let (op, args) = operator(node)?;
+    match op.as_str() {
+        "CONST" => {
+            let [value] = arity(&op, args)?;
+            let value = value
+                .as_bool()
+                .ok_or_else(|| invalid("Thiếu hoặc sai kiểu 'value' cho CONST"))?;
+            let truth = Truth::from(value);
+            Ok(Box::new(move |_| Ok(truth)))
+        }
+        "VAR" => {
+            let [name] = arity(&op, args)?;
+            let path = parse_path(&name)?;
+            Ok(Box::new(move |scope| match scope.resolve(&path) {
+                None => Ok(Truth::Unknown),
+                Some(Value::Bool(value)) => Ok(Truth::from(*value)),
+                Some(other) => Err(LogicalError::TypeMismatch(format!(
+                    "biến '{}' không phải boolean: {}",
+                    path.join("."),
+                    other
+                ))),
+            }))
+        }
+        "NOT" | "!" => {
+            let [child] = arity(&op, args)?;
+            let child = compile_formula(&child)?;
+            Ok(Box::new(move |scope| Ok(!child(scope)?)))
+        }
+        "AND" | "OR" => {
+            let children = args
+                .iter()
+                .map(compile_formula)
+                .collect::<Result<Vec<_>, _>>()?;
+            let is_and = op == "AND";
+            Ok(Box::new(move |scope| {
+                // Dừng sớm khi gặp phần tử quyết định (false cho AND, true cho OR)
+                let (identity, decisive) = if is_and {
+                    (Truth::True, Truth::False)
+                } else {
+                    (Truth::False, Truth::True)
+                };
+                let mut result = identity;
+                for child in &children {
+                    match child(scope)? {
+                        truth if truth == decisive => return Ok(decisive),
+                        Truth::Unknown => result = Truth::Unknown,
+                        _ => {}
+                    }
+                }
+                Ok(result)
+            }))
+        }
+        "IMPLIES" | "XOR" | "IFF" => {
+            let [lhs, rhs] = arity(&op, args)?;
+            let (lhs, rhs) = (compile_formula(&lhs)?, compile_formula(&rhs)?);
+            let combine: fn(Truth, Truth) -> Truth = match op.as_str() {
+                "IMPLIES" => Truth::implies,
+                "XOR" => Truth::xor,
+                _ => Truth::iff,
+            };
+            Ok(Box::new(move |scope| Ok(combine(lhs(scope)?, rhs(scope)?))))
+        }
+        "EQ" | "==" | "NEQ" | "!=" | "LT" | "<" | "LTE" | "<=" | "GT" | ">" | "GTE" | ">=" => {
+            let comparison = match op.as_str() {
+                "EQ" | "==" => Comparison::Eq,
+                "NEQ" | "!=" => Comparison::Ne,
+                "LT" | "<" => Comparison::Lt,
+                "LTE" | "<=" => Comparison::Le,
+                "GT" | ">" => Comparison::Gt,
+                _ => Comparison::Ge,
+            };
+            let [lhs, rhs] = arity(&op, args)?;
+            let (lhs, rhs) = (compile_term(&lhs)?, compile_term(&rhs)?);
+            Ok(Box::new(move |scope| {
+                match (lhs.eval(scope), rhs.eval(scope)) {
+                    (Some(a), Some(b)) => compare(comparison, a, b),
+                    _ => Ok(Truth::Unknown),
+                }
+            }))
+        }
+        "FORALL" | "EXISTS" => {
+            let [var, collection, body] = arity(&op, args)?;
+            let var = var
+                .as_str()
+                .ok_or_else(|| invalid(format!("Biến của {} phải là chuỗi", op)))?
+                .to_string();
+            let collection = compile_term(&collection)?;
+            let body = compile_formula(&body)?;
+            let is_forall = op == "FORALL";
+            Ok(Box::new(move |scope| {
+                let items = match collection.eval(scope) {
+                    None => return Ok(Truth::Unknown),
+                    Some(Value::Array(items)) => items,
+                    Some(other) => {
+                        return Err(LogicalError::TypeMismatch(format!(
+                            "lượng từ cần mảng, nhận {}",
+                            other
+                        )))
+                    }
+                };
+                let mut result = Truth::from(is_forall);
+                for item in items {
+                    let inner = Scope {
+                        facts: scope.facts,
+                        binding: Some((var.as_str(), item)),
+                        parent: Some(scope),
+                    };
+                    let truth = body(&inner)?;
+                    result = if is_forall {
+                        result.and(truth)
+                    } else {
+                        result.or(truth)
+                    };
+                    if result == Truth::from(!is_forall) {
+                        break;
+                    }
+                }
+                Ok(result)
+            }))
+        }
+        _ => Err(invalid(format!("Không hỗ trợ node type: {}", op))),
+    }
+}
+
+fn compile_term(node: &Value) -> Result<Term, LogicalError> {
+    if !node.is_object() {
+        return Ok(Term::Const(node.clone()));
+    }
+    let (op, args) = operator(node)?;
+    match op.as_str() {
+        "CONST" => {
+            let [value] = arity(&op, args)?;
+            Ok(Term::Const(value))
+        }
+        "VAR" => {
+            let [name] = arity(&op, args)?;
+            Ok(Term::Var(parse_path(&name)?))
+        }
+        _ => Err(invalid(format!(
+            "{} không phải là giá trị so sánh được",
+            op
+        ))),
+    }
+}
+
+fn parse_path(name: &Value) -> Result<Vec<String>, LogicalError> {
+    let name = name
+        .as_str()
+        .ok_or_else(|| invalid("Thiếu trường 'name' cho VAR"))?;
+    let path: Vec<String> = name.split('.').map(str::to_string).collect();
+    if path.iter().any(String::is_empty) {
+        return Err(invalid(format!("Path không hợp lệ: '{}'", name)));
+    }
+    Ok(path)
+}
+
+/// So sánh số với số, chuỗi với chuỗi; `==`/`!=` so sánh mọi giá trị JSON.
+fn compare(comparison: Comparison, lhs: &Value, rhs: &Value) -> Result<Truth, LogicalError> {
+    let ordering = match (lhs, rhs) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (
+                a.as_f64().unwrap_or(f64::NAN),
+                b.as_f64().unwrap_or(f64::NAN),
+            );
+            a.partial_cmp(&b)
+        }
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+    let result = match (comparison, ordering) {
+        (Comparison::Eq, Some(ordering)) => ordering == Ordering::Equal,
+        (Comparison::Ne, Some(ordering)) => ordering != Ordering::Equal,
+        (Comparison::Eq, None) => lhs == rhs,
+        (Comparison::Ne, None) => lhs != rhs,
+        (Comparison::Lt, Some(ordering)) => ordering == Ordering::Less,
+        (Comparison::Le, Some(ordering)) => ordering != Ordering::Greater,
+        (Comparison::Gt, Some(ordering)) => ordering == Ordering::Greater,
+        (Comparison::Ge, Some(ordering)) => ordering != Ordering::Less,
+        (_, None) => {
+            return Err(LogicalError::TypeMismatch(format!(
+                "không thể so sánh thứ tự {} với {}",
+                lhs, rhs
+            )))
+        }
+    };
+    Ok(Truth::from(result))
+}
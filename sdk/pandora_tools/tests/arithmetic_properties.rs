@@ -1,4 +1,11 @@
-use pandora_tools::skills::arithmetic_skill::AdaptiveArithmeticEngine;
+use pandora_tools::skills::arithmetic_skill::decimal::BigDecimal;
+use pandora_tools::skills::arithmetic_skill::symbolic::{Rational, SymbolicResult};
+use pandora_tools::skills::arithmetic_skill::{
+    AdaptiveArithmeticEngine, ArithmeticError, ComplexityClassifier, ComplexityLevel,
+    SandboxConfig,
+};
+use std::str::FromStr;
+use std::time::Duration;
 use proptest::prelude::*;
 
 proptest! {
@@ -57,4 +64,186 @@ proptest! {
         let result2 = engine.evaluate(&with_parens).unwrap();
         if a > 1 && b > 0 && c > 0 { prop_assert_ne!(result1, result2); }
     }
+
+    #[test]
+    fn linear_equations_are_solved_exactly(a in -50i64..50, b in -1000i64..1000, c in -1000i64..1000) {
+        prop_assume!(a != 0);
+        let engine = AdaptiveArithmeticEngine::new();
+        let equation = format!("{} * x + {} = {}", a, b, c);
+        prop_assert_eq!(ComplexityClassifier::new().classify(&equation), ComplexityLevel::Symbolic);
+        let expected = Rational::new((c - b) as i128, a as i128).unwrap();
+        match engine.evaluate_symbolic(&equation).unwrap() {
+            SymbolicResult::Solutions { roots, .. } => {
+                prop_assert_eq!(roots.len(), 1);
+                prop_assert_eq!(roots[0].exact.as_rational(), Some(expected));
+            }
+            other => prop_assert!(false, "unexpected {:?}", other),
+        }
+        let numeric = engine.evaluate(&equation).unwrap();
+        prop_assert!((numeric - expected.to_f64()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn factor_then_expand_round_trips(r1 in -9i64..9, r2 in -9i64..9, k in 1i64..5) {
+        let engine = AdaptiveArithmeticEngine::new();
+        let poly = format!("expand({} * (x - {}) * (x - {}))", k, r1, r2);
+        let expanded = engine.evaluate_symbolic(&poly).unwrap().to_string();
+        let factored = engine.evaluate_symbolic(&format!("factor({})", expanded)).unwrap().to_string();
+        let round_trip = engine.evaluate_symbolic(&format!("expand({})", factored)).unwrap().to_string();
+        prop_assert_eq!(round_trip, expanded);
+    }
+
+    #[test]
+    fn decimal_sums_of_cents_are_exact(a in 0i64..1_000_000, b in 0i64..1_000_000) {
+        let engine = AdaptiveArithmeticEngine::new();
+        let expr = format!("{}.{:02} + {}.{:02}", a / 100, a % 100, b / 100, b % 100);
+        let cents = a + b;
+        let expected = BigDecimal::from_str(&format!("{}.{:02}", cents / 100, cents % 100)).unwrap();
+        prop_assert_eq!(engine.evaluate_decimal(&expr).unwrap(), expected);
+    }
+
+    #[test]
+    fn unit_conversions_round_trip(v in 0.001f64..1e6) {
+        let engine = AdaptiveArithmeticEngine::new();
+        let forward = format!("{} km/h to m/s", v);
+        prop_assert_eq!(ComplexityClassifier::new().classify(&forward), ComplexityLevel::Units);
+        let there = engine.evaluate(&forward).unwrap();
+        let back = engine.evaluate(&format!("{} m/s to km/h", there)).unwrap();
+        prop_assert!((back - v).abs() <= 1e-9 * v.max(1.0));
+    }
+
+    #[test]
+    fn adding_different_dimensions_is_rejected(a in 1u32..1000, b in 1u32..1000) {
+        let engine = AdaptiveArithmeticEngine::new();
+        let result = engine.evaluate(&format!("{} m + {} s", a, b));
+        prop_assert!(matches!(result, Err(ArithmeticError::DimensionMismatch { .. })), "{:?}", result);
+    }
+
+    #[test]
+    fn pathological_inputs_never_hang(expr in r"[0-9xe()+\-*/^%.=, ]{0,120}|(expand|factor|diff|solve|sqrt)\([0-9x()+\-*/^ ]{0,60}\)") {
+        // Không có hạn thời gian: chỉ nhiên liệu chặn các vòng lặp, nên mọi lần tính phải
+        // kết thúc bằng kết quả hoặc lỗi, không phụ thuộc tốc độ máy
+        let mut engine = AdaptiveArithmeticEngine::new();
+        engine.update_sandbox_config(SandboxConfig {
+            max_execution_time: Duration::MAX,
+            max_fuel: 200_000,
+            ..SandboxConfig::default()
+        });
+        let _ = engine.evaluate(&expr);
+        let _ = engine.evaluate_symbolic(&expr);
+        let _ = engine.evaluate_decimal(&expr);
+        let _ = engine.evaluate_units(&expr);
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected_by_depth_budget(depth in 65usize..100, minus in any::<bool>()) {
+        let engine = AdaptiveArithmeticEngine::new();
+        // `-(` lồng hai mức mỗi lần (toán tử một ngôi + ngoặc); vẫn nằm trong giới hạn độ dài
+        let (open, count) = if minus { ("-(", depth / 2 + 1) } else { ("(", depth) };
+        let expr = format!("{}1{}", open.repeat(count), ")".repeat(count));
+        let result = engine.evaluate(&expr);
+        prop_assert!(matches!(result, Err(ArithmeticError::AstTooDeep { limit: 64, .. })), "{:?}", result);
+    }
+
+    #[test]
+    fn runaway_expansion_hits_fuel_or_memory_limit(degree in 40u32..64, tight_memory in any::<bool>()) {
+        let mut engine = AdaptiveArithmeticEngine::new();
+        engine.update_sandbox_config(SandboxConfig {
+            max_fuel: 5_000,
+            memory_limit: if tight_memory { 4 * 1024 } else { 1024 * 1024 },
+            max_execution_time: Duration::from_secs(5),
+            ..SandboxConfig::default()
+        });
+        let result = engine.evaluate_symbolic(&format!("expand((x + y + 1)^{})", degree));
+        if tight_memory {
+            prop_assert!(matches!(result, Err(ArithmeticError::MemoryLimitExceeded { limit: 4096, .. })), "{:?}", result);
+        } else {
+            prop_assert!(matches!(result, Err(ArithmeticError::FuelExhausted { limit: 5_000 })), "{:?}", result);
+        }
+    }
+}
+
+#[test]
+fn divisor_search_is_bounded_by_fuel_and_deadline() {
+    // Hằng số gần 1e12 buộc phép tìm ước chạy ~1e6 vòng
+    let expr = "factor(x^3 - 999999999989)";
+    let mut engine = AdaptiveArithmeticEngine::new();
+    engine.update_sandbox_config(SandboxConfig {
+        max_fuel: 10_000,
+        ..SandboxConfig::default()
+    });
+    let result = engine.evaluate_symbolic(expr);
+    assert!(matches!(result, Err(ArithmeticError::FuelExhausted { limit: 10_000 })), "{:?}", result);
+
+    engine.update_sandbox_config(SandboxConfig {
+        max_fuel: u64::MAX,
+        // Hạn đã qua: dừng ở điểm kiểm tra đầu tiên thay vì chạy hết ~1e6 vòng
+        max_execution_time: Duration::ZERO,
+        ..SandboxConfig::default()
+    });
+    let result = engine.evaluate_symbolic(expr);
+    assert!(matches!(result, Err(ArithmeticError::TimeoutError(_))), "{:?}", result);
+}
+
+#[test]
+fn oversized_expressions_are_rejected_by_node_budget() {
+    let mut engine = AdaptiveArithmeticEngine::new();
+    engine.update_sandbox_config(SandboxConfig {
+        max_ast_nodes: 32,
+        ..SandboxConfig::default()
+    });
+    let expr = ["1"; 20].join(" + ");
+    let result = engine.evaluate(&expr);
+    assert!(matches!(result, Err(ArithmeticError::AstTooLarge { nodes: 39, limit: 32 })), "{:?}", result);
+    assert_eq!(engine.evaluate("1 + 2 * 3").unwrap(), 7.0);
+}
+
+#[test]
+fn fasteval_builtins_and_comparisons_stay_numeric() {
+    let engine = AdaptiveArithmeticEngine::new();
+    let classifier = ComplexityClassifier::new();
+    for (expr, expected) in [
+        ("max(1,2)", 2.0),
+        ("round(2.6)", 3.0),
+        ("sign(-3)", -1.0),
+        ("asin(0.5)", 0.5f64.asin()),
+        ("int(3.7)", 3.0),
+        ("1 == 1", 1.0),
+        ("2 <= 1", 0.0),
+    ] {
+        assert!(!classifier.requires_symbolic(expr), "{}", expr);
+        let value = engine.evaluate(expr).unwrap_or_else(|err| panic!("{}: {}", expr, err));
+        assert!((value - expected).abs() < 1e-12, "{} = {}", expr, value);
+    }
+    // Biến tự do, phương trình và hàm chỉ engine ký hiệu có vẫn đi engine ký hiệu
+    assert!(classifier.requires_symbolic("max(x, 2)"));
+    assert!(classifier.requires_symbolic("2 * y = 4"));
+    assert!(classifier.requires_symbolic("sqrt(16)"));
+    assert_eq!(engine.evaluate("sqrt(16) + x = 6").unwrap(), 2.0);
+
+    // Biểu thức phức tạp được gửi sang engine ký hiệu (đã có hiệu năng ghi nhận)
+    // nhưng dùng `max` thì quay về fasteval
+    let expr = "sin(1)+cos(1)+tan(1)+sin(2)+cos(2)+tan(2)+sin(3)+cos(3)+tan(3)+max(1,2)+1+2+3+4+5+6+7+8+9+10+11";
+    assert_eq!(classifier.classify(expr), ComplexityLevel::Complex);
+    let expected = (1..=3).map(f64::from).map(|x| x.sin() + x.cos() + x.tan()).sum::<f64>() + 2.0 + 66.0;
+    assert!((engine.evaluate(expr).unwrap() - expected).abs() < 1e-9);
+}
+
+#[test]
+fn units_are_only_read_after_numbers() {
+    let engine = AdaptiveArithmeticEngine::new();
+    let classifier = ComplexityClassifier::new();
+    assert_ne!(classifier.classify("min(3,4)"), ComplexityLevel::Units);
+    assert_eq!(engine.evaluate("min(3,4)").unwrap(), 3.0);
+    assert_ne!(classifier.classify("2*s"), ComplexityLevel::Units);
+    assert!(engine.evaluate("2*s").is_err());
+    assert!(engine.evaluate_units("2*s").is_err());
+
+    // `evaluate` trả về đơn vị SI cơ bản, `evaluate_units` giữ đơn vị hiển thị
+    assert_eq!(classifier.classify("3 m/s * 2 h"), ComplexityLevel::Units);
+    assert_eq!(engine.evaluate("3 m/s * 2 h").unwrap(), 21600.0);
+    let value = engine.evaluate_units("3 m/s * 2 h").unwrap();
+    assert_eq!(value.quantity.value, 21600.0);
+    assert_eq!(value.to_string(), "21.6 km");
+    assert!((engine.evaluate("3 m/s * 2 h to km").unwrap() - 21.6).abs() < 1e-9);
 }
@@ -0,0 +1,65 @@
+// sdk/pandora_tools/tests/embedding_tests.rs
+
+use pandora_tools::skills::information_retrieval_skill::embedding::*;
+use pandora_tools::skills::information_retrieval_skill::*;
+use serde_json::json;
+use std::sync::Arc;
+
+fn doc(id: &str, content: &str) -> Document {
+    Document {
+        id: id.into(),
+        content: content.into(),
+        embedding: Vec::new(),
+        metadata: Default::default(),
+    }
+}
+
+#[test]
+fn test_cached_embedder_reuses_vectors() {
+    let cached = CachedEmbedder::new(Arc::new(HashedNgramEmbedder::with_dim(64)), 2);
+    let first = cached.embed("xin chào").unwrap();
+    assert_eq!(cached.embed("xin chào").unwrap(), first);
+    assert_eq!(cached.stats(), (1, 1));
+
+    // Dung lượng 2: "xin chào" bị đẩy ra sau hai văn bản mới
+    cached.embed_batch(&["một", "hai"]).unwrap();
+    cached.embed("xin chào").unwrap();
+    assert_eq!(cached.stats(), (1, 4));
+}
+
+#[tokio::test]
+async fn test_engine_embeds_documents_and_queries_automatically() {
+    let embedder = Arc::new(HashedNgramEmbedder::with_dim(128));
+    let mut engine = ProgressiveSemanticEngine::with_embedder(embedder);
+    engine.update_search_pipeline(SearchPipeline {
+        stages: vec![SearchStage::VectorSearch {
+            k: 3,
+            threshold: 0.0,
+        }],
+        early_exit_conditions: Vec::new(),
+        result_fusion: FusionStrategy::CombSum,
+    });
+    for d in [
+        doc("ir", "information retrieval with vector indexes"),
+        doc("cook", "cooking noodles with beef broth"),
+    ] {
+        engine.add_document(d).await.unwrap();
+    }
+
+    // Chỉ stage vector: kết quả phải đến từ embedding của truy vấn
+    let out = engine
+        .search(&json!("retrieving information"))
+        .await
+        .unwrap();
+    assert_eq!(out.documents[0].id, "ir");
+    assert_eq!(out.documents[0].embedding.len(), 128);
+    assert!(out
+        .reasoning_trace
+        .iter()
+        .any(|t| t.contains("hashed-ngram")));
+
+    let mut mismatched = ProgressiveSemanticEngine::in_memory(3);
+    assert!(mismatched
+        .set_embedder(Arc::new(HashedNgramEmbedder::with_dim(4)))
+        .is_err());
+}
@@ -0,0 +1,269 @@
+// sdk/pandora_tools/tests/information_retrieval_tests.rs
+
+use pandora_tools::skills::information_retrieval_skill::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn doc(id: &str, content: &str, embedding: Vec<f32>) -> Document {
+    Document {
+        id: id.into(),
+        content: content.into(),
+        embedding,
+        metadata: Default::default(),
+    }
+}
+
+async fn seed_engine() -> ProgressiveSemanticEngine {
+    let mut engine = ProgressiveSemanticEngine::in_memory(3);
+    let docs = [
+        doc(
+            "rust",
+            "Rust is a systems programming language with a borrow checker",
+            vec![0.9, 0.1, 0.0],
+        ),
+        doc(
+            "python",
+            "Python is a scripting language popular for data science",
+            vec![0.1, 0.9, 0.0],
+        ),
+        doc(
+            "cooking",
+            "A recipe for pho: beef broth, rice noodles and herbs",
+            vec![0.0, 0.1, 0.9],
+        ),
+        doc(
+            "go",
+            "Go is a programming language with garbage collection",
+            vec![0.7, 0.3, 0.0],
+        ),
+    ];
+    for d in docs {
+        engine.add_document(d).await.unwrap();
+    }
+    engine
+}
+
+fn pipeline(fusion: FusionStrategy) -> SearchPipeline {
+    SearchPipeline {
+        stages: vec![
+            SearchStage::VectorSearch {
+                k: 10,
+                threshold: 0.0,
+            },
+            SearchStage::TextSearch {
+                k: 10,
+                boost_factors: HashMap::new(),
+            },
+            SearchStage::ResultRanking {
+                algorithm: RankingAlgorithm::Hybrid,
+            },
+        ],
+        early_exit_conditions: Vec::new(),
+        result_fusion: fusion,
+    }
+}
+
+#[tokio::test]
+async fn test_hybrid_search_combines_vector_and_text() {
+    let engine = seed_engine().await;
+    let out = engine
+        .search(&json!({"query": "borrow checker", "embedding": [0.85, 0.15, 0.0]}))
+        .await
+        .unwrap();
+    assert_eq!(out.documents[0].id, "rust");
+    assert!(out.confidence.score > 0.6, "{:?}", out.confidence);
+    assert_eq!(out.confidence.epistemic_uncertainty, 0.0);
+
+    // Vector và văn bản bất đồng: độ tin cậy thấp hơn, bất định nhận thức cao hơn
+    let conflicting = engine
+        .search(&json!({"query": "pho broth", "embedding": [0.85, 0.15, 0.0]}))
+        .await
+        .unwrap();
+    assert!(conflicting.confidence.score < out.confidence.score);
+    assert!(conflicting.confidence.epistemic_uncertainty > 0.0);
+
+    let nothing = engine
+        .search(&json!("quantum chromodynamics"))
+        .await
+        .unwrap();
+    assert!(nothing.documents.is_empty());
+    assert_eq!(nothing.confidence.score, 0.0);
+}
+
+#[tokio::test]
+async fn test_fusion_strategies_are_configurable() {
+    let mut engine = seed_engine().await;
+    let input = json!({"query": "programming language", "embedding": [0.75, 0.25, 0.0]});
+    for fusion in [
+        FusionStrategy::ReciprocalRank,
+        FusionStrategy::CombSum,
+        FusionStrategy::CombMNZ,
+    ] {
+        engine.update_search_pipeline(pipeline(fusion.clone()));
+        let out = engine.search(&input).await.unwrap();
+        assert_eq!(out.documents[0].id, "go", "{:?}", fusion);
+        assert!(out
+            .reasoning_trace
+            .iter()
+            .any(|t| t.contains("from 2 stages")));
+    }
+
+    // Chỉ tin vào văn bản: trọng số vector bằng 0
+    let weights = HashMap::from([("vector".to_string(), 0.0), ("text".to_string(), 1.0)]);
+    engine.update_search_pipeline(pipeline(FusionStrategy::WeightedSum { weights }));
+    let out = engine
+        .search(&json!({"query": "scripting", "embedding": [0.9, 0.1, 0.0]}))
+        .await
+        .unwrap();
+    assert_eq!(out.documents[0].id, "python");
+}
+
+#[tokio::test]
+async fn test_cache_lookup_serves_and_invalidates() {
+    let mut engine = seed_engine().await;
+    let mut cached_pipeline = pipeline(FusionStrategy::CombSum);
+    cached_pipeline.stages.insert(
+        0,
+        SearchStage::CacheLookup {
+            max_age: Duration::from_secs(60),
+        },
+    );
+    cached_pipeline.early_exit_conditions.push(ExitCondition {
+        stage_name: "CacheLookup".into(),
+        min_results: 1,
+        max_latency: Duration::from_secs(1),
+        min_confidence: 0.0,
+    });
+    engine.update_search_pipeline(cached_pipeline);
+
+    let first = engine.search(&json!("garbage collection")).await.unwrap();
+    let second = engine.search(&json!("garbage collection")).await.unwrap();
+    assert_eq!(first.documents[0].id, "go");
+    assert_eq!(second.documents[0].id, "go");
+    assert!(second
+        .reasoning_trace
+        .iter()
+        .any(|t| t.contains("from cache")));
+
+    engine
+        .add_document(doc(
+            "java",
+            "Java has garbage collection too, garbage collection everywhere",
+            vec![0.6, 0.4, 0.0],
+        ))
+        .await
+        .unwrap();
+    let third = engine.search(&json!("garbage collection")).await.unwrap();
+    assert!(!third
+        .reasoning_trace
+        .iter()
+        .any(|t| t.contains("from cache")));
+    assert_eq!(third.documents.len(), 2);
+}
+
+#[tokio::test]
+async fn test_hnsw_index_matches_exact_search_and_handles_updates() {
+    use pandora_tools::skills::information_retrieval_skill::embedding::cosine;
+    use pandora_tools::skills::information_retrieval_skill::vector_index::EXACT_SEARCH_LIMIT;
+
+    let dim = 16;
+    let mut engine = ProgressiveSemanticEngine::in_memory(dim);
+    // Vector giả ngẫu nhiên xác định
+    let mut state = 42u64;
+    let mut next = || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+    };
+    // Vượt ngưỡng quét tuần tự để tìm kiếm đi qua đồ thị HNSW
+    let vectors: Vec<Vec<f32>> = (0..EXACT_SEARCH_LIMIT + 300)
+        .map(|_| (0..dim).map(|_| next()).collect())
+        .collect();
+    let docs = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| doc(&format!("d{}", i), "filler", v.clone()))
+        .collect();
+    engine.add_documents(docs).await.unwrap();
+
+    let k = 10;
+    let (mut matched, mut expected) = (0, 0);
+    for query in vectors.iter().step_by(37) {
+        let mut exact: Vec<(usize, f32)> = vectors
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, cosine(query, v)))
+            .collect();
+        exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let exact: Vec<String> = exact[..k].iter().map(|(i, _)| format!("d{}", i)).collect();
+        let found = engine.search_by_vector(query, k).await.unwrap();
+        matched += found.iter().filter(|c| exact.contains(&c.id)).count();
+        expected += k;
+    }
+    // HNSW là xấp xỉ: so với quét chính xác, recall@10 phải cao
+    assert!(matched * 100 >= expected * 90, "{}/{}", matched, expected);
+
+    // Thêm lại cùng id với vector mới: vector cũ không bao giờ được trả về, vector mới
+    // (điểm thêm muộn trong đồ thị) tìm được với cùng mức recall
+    let updated: Vec<usize> = (0..vectors.len()).step_by(53).collect();
+    let replacements: Vec<Document> = updated
+        .iter()
+        .map(|&i| doc(&format!("d{}", i), "updated", vectors[i].iter().map(|x| -x).collect()))
+        .collect();
+    engine.add_documents(replacements.clone()).await.unwrap();
+    let mut reachable = 0;
+    for (&i, replacement) in updated.iter().zip(&replacements) {
+        let id = format!("d{}", i);
+        let stale = engine.search_by_vector(&vectors[i], k).await.unwrap();
+        assert!(stale.iter().all(|c| c.id != id), "{}", id);
+        let found = engine.search_by_vector(&replacement.embedding, k).await.unwrap();
+        reachable += found.iter().any(|c| c.id == id) as usize;
+    }
+    assert!(reachable * 100 >= updated.len() * 90, "{}/{}", reachable, updated.len());
+    assert_eq!(
+        engine.search_by_text("updated", updated.len()).await.unwrap().len(),
+        updated.len()
+    );
+    assert!(engine.search_by_vector(&[1.0, 2.0], 1).await.is_err());
+}
+
+#[tokio::test]
+async fn test_deleted_documents_are_dropped_and_slots_reused() {
+    let mut engine = seed_engine().await;
+    assert_eq!(engine.delete_documents(&["rust", "missing"]).await.unwrap(), 1);
+    assert!(engine.search_by_text("rust", 5).await.unwrap().is_empty());
+    assert!(engine
+        .search_by_vector(&[0.9, 0.1, 0.0], 5)
+        .await
+        .unwrap()
+        .iter()
+        .all(|d| d.id != "rust"));
+
+    // Tài liệu mới dùng lại slot vừa giải phóng mà không lẫn với tài liệu đã xóa
+    engine
+        .add_document(doc("zig", "Zig is a systems language", vec![0.9, 0.1, 0.0]))
+        .await
+        .unwrap();
+    let found = engine.search_by_vector(&[0.9, 0.1, 0.0], 1).await.unwrap();
+    assert_eq!(found[0].id, "zig");
+    assert_eq!(engine.search_by_text("borrow", 5).await.unwrap().len(), 0);
+    assert_eq!(engine.search_by_text("systems", 5).await.unwrap()[0].id, "zig");
+
+    // So khớp ID không phân biệt hoa thường vẫn đúng sau khi xóa và thêm lại
+    engine
+        .add_documents(vec![
+            doc("Rust", "A language", vec![0.9, 0.1, 0.0]),
+            doc("RUST", "Another language", vec![0.9, 0.1, 0.0]),
+        ])
+        .await
+        .unwrap();
+    let ids = |docs: Vec<Document>| docs.into_iter().map(|d| d.id).collect::<Vec<_>>();
+    assert_eq!(
+        ids(engine.search_by_text(" rust ", 5).await.unwrap()),
+        ["Rust", "RUST"]
+    );
+    engine.delete_documents(&["Rust"]).await.unwrap();
+    assert_eq!(ids(engine.search_by_text("rust", 5).await.unwrap()), ["RUST"]);
+}
@@ -0,0 +1,219 @@
+// sdk/pandora_tools/tests/knowledge_graph_tests.rs
+
+use pandora_tools::skills::information_retrieval_skill::embedding::HashedNgramEmbedder;
+use pandora_tools::skills::information_retrieval_skill::knowledge_graph::Triple;
+use pandora_tools::skills::information_retrieval_skill::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const EX: &str = "http://example.org/";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn kg_pipeline(max_hops: usize) -> SearchPipeline {
+    SearchPipeline {
+        stages: vec![SearchStage::KnowledgeGraphQuery { max_hops }],
+        early_exit_conditions: Vec::new(),
+        result_fusion: FusionStrategy::CombSum,
+    }
+}
+
+fn geography() -> Vec<Triple> {
+    vec![
+        Triple::new("hanoi", "capitalOf", "vietnam"),
+        Triple::new("hanoi", "name", "Hà Nội"),
+        Triple::new("vietnam", "name", "Vietnam"),
+        Triple {
+            confidence: 0.9,
+            ..Triple::new("vietnam", "memberOf", "asean")
+        },
+        Triple::new("thailand", "memberOf", "asean"),
+    ]
+}
+
+#[test]
+fn test_load_ntriples_and_jsonld_files() {
+    let dir = temp_dir("kg-load");
+    let nt = dir.join("geo.nt");
+    std::fs::write(
+        &nt,
+        format!(
+            "<{ex}hanoi> <{ex}capitalOf> <{ex}vietnam> .\n\
+             <{ex}hanoi> <http://www.w3.org/2000/01/rdf-schema#label> \"Hà Nội\"@vi .\n",
+            ex = EX
+        ),
+    )
+    .unwrap();
+    let jsonld = dir.join("org.jsonld");
+    std::fs::write(
+        &jsonld,
+        json!({
+            "@context": {"ex": EX, "@vocab": "http://schema.org/"},
+            "@graph": [{
+                "@id": "ex:vietnam",
+                "@type": "Country",
+                "name": "Vietnam",
+                "memberOf": {"@id": "ex:asean", "name": "ASEAN"}
+            }]
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let mut engine = ProgressiveSemanticEngine::in_memory(8);
+    assert_eq!(engine.load_knowledge_graph(&nt, 0.9).unwrap(), 2);
+    assert_eq!(engine.load_knowledge_graph(&jsonld, 1.0).unwrap(), 4);
+    let kg = engine.knowledge_graph();
+    assert_eq!(kg.label(&format!("{}hanoi", EX)), "Hà Nội");
+    assert_eq!(kg.label(&format!("{}asean", EX)), "ASEAN");
+    let member = kg
+        .triples()
+        .iter()
+        .find(|t| t.predicate == "http://schema.org/memberOf")
+        .unwrap();
+    assert_eq!(member.object, format!("{}asean", EX));
+    assert_eq!(member.provenance.as_deref(), Some(jsonld.to_str().unwrap()));
+    assert_eq!(kg.triples()[0].confidence, 0.9);
+
+    assert!(engine
+        .load_knowledge_graph(dir.join("geo.csv"), 1.0)
+        .is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_kg_stage_traversal_is_bounded_by_max_hops() {
+    let mut engine = ProgressiveSemanticEngine::in_memory(8);
+    engine.add_triples(geography()).unwrap();
+
+    engine.update_search_pipeline(kg_pipeline(1));
+    let one_hop = engine.search(&json!("Hà Nội")).await.unwrap();
+    let ids: Vec<&str> = one_hop.documents.iter().map(|d| d.id.as_str()).collect();
+    assert_eq!(ids, vec!["kg:vietnam"]);
+    assert_eq!(one_hop.documents[0].content, "Hà Nội —capitalOf→ Vietnam");
+    assert_eq!(one_hop.documents[0].metadata["hops"], json!(1));
+
+    engine.update_search_pipeline(kg_pipeline(3));
+    let three_hops = engine.search(&json!("Hà Nội")).await.unwrap();
+    let ids: Vec<&str> = three_hops.documents.iter().map(|d| d.id.as_str()).collect();
+    // Điểm giảm theo số bước và độ tin cậy của cạnh
+    assert_eq!(ids, vec!["kg:vietnam", "kg:asean", "kg:thailand"]);
+
+    engine.update_search_mode(SearchMode::Full {
+        max_memory_mb: 100,
+        use_all_tiers: true,
+        enable_kg_reasoning: false,
+        external_apis_enabled: false,
+    });
+    assert!(engine
+        .search(&json!("Hà Nội"))
+        .await
+        .unwrap()
+        .documents
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_kg_results_join_fusion_with_documents() {
+    let mut engine = ProgressiveSemanticEngine::in_memory(8);
+    engine.add_triples(geography()).unwrap();
+    engine
+        .add_document(Document {
+            id: "vietnam".into(),
+            content: "Vietnam is a country in Southeast Asia".into(),
+            embedding: Vec::new(),
+            metadata: Default::default(),
+        })
+        .await
+        .unwrap();
+    engine.update_search_pipeline(SearchPipeline {
+        stages: vec![
+            SearchStage::TextSearch {
+                k: 5,
+                boost_factors: HashMap::new(),
+            },
+            SearchStage::KnowledgeGraphQuery { max_hops: 1 },
+        ],
+        early_exit_conditions: Vec::new(),
+        result_fusion: FusionStrategy::CombMNZ,
+    });
+
+    // "Hà Nội" chỉ có trong KG, "country" chỉ có trong tài liệu: nút `vietnam` khớp cả hai
+    let out = engine.search(&json!("Hà Nội country")).await.unwrap();
+    assert_eq!(out.documents[0].id, "vietnam");
+    assert!(out.documents[0].content.contains("Southeast Asia"));
+    assert!(out
+        .reasoning_trace
+        .iter()
+        .any(|t| t.contains("from 2 stages")));
+}
+
+#[tokio::test]
+async fn test_entity_linking_reuses_embedder() {
+    let triples = vec![
+        Triple::new("vietnam", "name", "Vietnam"),
+        Triple::new("hanoi", "capitalOf", "vietnam"),
+    ];
+    let query = json!("capital of the vietnamese state");
+
+    // Không có embedder: "vietnamese" không khớp nguyên văn nhãn "Vietnam"
+    let mut plain = ProgressiveSemanticEngine::in_memory(64);
+    plain.add_triples(triples.clone()).unwrap();
+    plain.update_search_pipeline(kg_pipeline(1));
+    assert!(plain.search(&query).await.unwrap().documents.is_empty());
+
+    let mut engine =
+        ProgressiveSemanticEngine::with_embedder(Arc::new(HashedNgramEmbedder::with_dim(64)));
+    engine.add_triples(triples).unwrap();
+    engine.update_search_pipeline(kg_pipeline(1));
+    let out = engine.search(&query).await.unwrap();
+    assert_eq!(out.documents[0].id, "kg:hanoi");
+}
+
+#[tokio::test]
+async fn test_default_pipeline_ignores_knowledge_graph() {
+    let mut engine = ProgressiveSemanticEngine::in_memory(8);
+    engine
+        .add_document(Document {
+            id: "doc".into(),
+            content: "Hà Nội is a city".into(),
+            embedding: Vec::new(),
+            metadata: Default::default(),
+        })
+        .await
+        .unwrap();
+    let before = engine.search(&json!("Hà Nội")).await.unwrap();
+    engine.add_triples(geography()).unwrap();
+    let after = engine.search(&json!("Hà Nội")).await.unwrap();
+    let ids = |out: &CognitiveOutput| {
+        out.documents
+            .iter()
+            .map(|d| d.id.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&after), ids(&before));
+    assert_eq!(ids(&after), vec!["doc"]);
+}
+
+#[tokio::test]
+async fn test_feedback_accepts_kg_results() {
+    let mut engine = ProgressiveSemanticEngine::in_memory(8);
+    engine.add_triples(geography()).unwrap();
+    engine.update_search_pipeline(kg_pipeline(1));
+    let out = engine.search(&json!("Hà Nội")).await.unwrap();
+    let id = out.documents[0].id.clone();
+    assert_eq!(id, "kg:vietnam");
+
+    engine.record_feedback("Hà Nội", &id, 1.0).unwrap();
+    assert_eq!(engine.feedback_history()[0].result_id, id);
+    assert!(matches!(
+        engine.record_feedback("Hà Nội", "kg:atlantis", 1.0),
+        Err(RetrievalError::DocumentNotFound(_))
+    ));
+}
@@ -0,0 +1,490 @@
+// sdk/pandora_tools/tests/lance_store_tests.rs
+
+use pandora_tools::skills::information_retrieval_skill::chunking::ChunkingConfig;
+use pandora_tools::skills::information_retrieval_skill::embedding::{
+    Embedder, HashedNgramEmbedder,
+};
+use pandora_tools::skills::information_retrieval_skill::lance_store::{
+    IvfPqConfig, MetadataFilter, WRITE_BATCH_SIZE,
+};
+use pandora_tools::skills::information_retrieval_skill::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Thư mục rỗng trên đĩa cục bộ, riêng cho mỗi test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+async fn open(dir: &Path, dim: usize) -> ProgressiveSemanticEngine {
+    ProgressiveSemanticEngine::new(dir.to_str().unwrap(), "documents", dim)
+        .await
+        .unwrap()
+}
+
+/// Id của đoạn `chunk` trong file `source` khi nạp thư mục `dir`
+fn chunk_id(dir: &Path, source: &str, chunk: usize) -> String {
+    format!(
+        "{}/{}#{}",
+        dir.canonicalize().unwrap().to_string_lossy(),
+        source,
+        chunk
+    )
+}
+
+/// Vector giả ngẫu nhiên tất định
+fn vector(seed: u64, dim: usize) -> Vec<f32> {
+    let mut state = seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    (0..dim)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+        })
+        .collect()
+}
+
+fn doc(i: usize, dim: usize) -> Document {
+    Document {
+        id: format!("doc-{:04}", i),
+        content: format!("tài liệu số {}", i),
+        embedding: vector(i as u64, dim),
+        metadata: HashMap::from([
+            (
+                "lang".to_string(),
+                json!(if i.is_multiple_of(3) { "vi" } else { "en" }),
+            ),
+            ("year".to_string(), json!(2000 + (i % 25) as i64)),
+            ("draft".to_string(), json!(i.is_multiple_of(2))),
+        ]),
+    }
+}
+
+fn ids(docs: &[Document]) -> Vec<String> {
+    docs.iter().map(|d| d.id.clone()).collect()
+}
+
+#[tokio::test]
+async fn test_documents_persist_across_reopen() {
+    let dir = temp_dir("lance-persist");
+    let dim = 8;
+    let docs: Vec<Document> = (0..WRITE_BATCH_SIZE + 100).map(|i| doc(i, dim)).collect();
+    {
+        let mut engine = open(&dir, dim).await;
+        assert_eq!(
+            engine.add_documents(docs.clone()).await.unwrap(),
+            docs.len()
+        );
+        let store = engine.lance_store().unwrap();
+        assert_eq!(store.table_name(), "documents");
+        assert_eq!(store.count().await.unwrap(), docs.len());
+    }
+
+    let mut engine = open(&dir, dim).await;
+    let store = engine.lance_store().unwrap().clone();
+    assert_eq!(store.count().await.unwrap(), docs.len());
+    let hits = engine
+        .search_by_vector(&docs[7].embedding, 1)
+        .await
+        .unwrap();
+    assert_eq!(hits[0].id, "doc-0007");
+    let stored = store.get("doc-0007").await.unwrap().unwrap();
+    assert_eq!(stored.document.content, docs[7].content);
+    assert_eq!(stored.document.metadata, docs[7].metadata);
+    assert_eq!(stored.document.embedding, docs[7].embedding);
+
+    // Cập nhật giữ created_at, đổi updated_at
+    let mut edited = docs[7].clone();
+    edited.content = "nội dung mới".into();
+    engine.add_document(edited).await.unwrap();
+    let updated = store.get("doc-0007").await.unwrap().unwrap();
+    assert_eq!(updated.document.content, "nội dung mới");
+    assert_eq!(updated.created_at, stored.created_at);
+    assert!(updated.updated_at > stored.updated_at);
+    assert_eq!(store.count().await.unwrap(), docs.len());
+
+    assert_eq!(
+        engine
+            .delete_documents(&["doc-0001", "doc-0002", "missing"])
+            .await
+            .unwrap(),
+        2
+    );
+    drop(engine);
+
+    let engine = open(&dir, dim).await;
+    let store = engine.lance_store().unwrap();
+    assert_eq!(store.count().await.unwrap(), docs.len() - 2);
+    assert!(store.get("doc-0001").await.unwrap().is_none());
+    let hits = engine
+        .search_by_vector(&docs[1].embedding, 3)
+        .await
+        .unwrap();
+    assert!(hits.iter().all(|d| d.id != "doc-0001"));
+    assert!(engine
+        .search_by_text("số 2", 50)
+        .await
+        .unwrap()
+        .iter()
+        .all(|d| d.id != "doc-0002"));
+    let content = store
+        .get("doc-0007")
+        .await
+        .unwrap()
+        .unwrap()
+        .document
+        .content;
+    assert_eq!(content, "nội dung mới");
+
+    // Xóa nhiều hơn một lô ghi trong cùng một lệnh
+    let remaining: Vec<String> = docs[3..].iter().map(|d| d.id.clone()).collect();
+    let remaining: Vec<&str> = remaining.iter().map(String::as_str).collect();
+    assert!(remaining.len() > WRITE_BATCH_SIZE);
+    assert_eq!(store.delete(&remaining).await.unwrap(), remaining.len());
+    assert_eq!(store.count().await.unwrap(), 1);
+
+    // Bảng đã có với số chiều khác
+    assert!(
+        ProgressiveSemanticEngine::new(dir.to_str().unwrap(), "documents", 4)
+            .await
+            .is_err()
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_filtered_search_matches_in_memory() {
+    let dir = temp_dir("lance-filter");
+    let dim = 8;
+    let docs: Vec<Document> = (0..120).map(|i| doc(i, dim)).collect();
+    let mut persistent = open(&dir, dim).await;
+    let mut in_memory = ProgressiveSemanticEngine::in_memory(dim);
+    persistent.add_documents(docs.clone()).await.unwrap();
+    in_memory.add_documents(docs.clone()).await.unwrap();
+
+    let filters = vec![
+        MetadataFilter::eq("lang", "vi"),
+        MetadataFilter::Ne("lang".into(), json!("vi")),
+        MetadataFilter::Gte("year".into(), 2020.0),
+        MetadataFilter::And(vec![
+            MetadataFilter::eq("draft", true),
+            MetadataFilter::Lt("year".into(), 2005.0),
+        ]),
+        MetadataFilter::Or(vec![
+            MetadataFilter::eq("year", 2003),
+            MetadataFilter::In("lang".into(), vec![json!("fr"), json!("vi")]),
+        ]),
+        MetadataFilter::Not(Box::new(MetadataFilter::eq("lang", "en"))),
+        MetadataFilter::Exists("missing".into()),
+    ];
+    let query = vector(10_000, dim);
+    for filter in &filters {
+        let expected = in_memory
+            .search_by_vector_filtered(&query, 10, filter)
+            .await
+            .unwrap();
+        let actual = persistent
+            .search_by_vector_filtered(&query, 10, filter)
+            .await
+            .unwrap();
+        assert_eq!(ids(&actual), ids(&expected), "{:?}", filter);
+        assert!(actual.iter().all(|d| filter.matches(&d.metadata)));
+    }
+    let vi = persistent
+        .search_by_vector_filtered(&query, 200, &filters[0])
+        .await
+        .unwrap();
+    assert_eq!(vi.len(), 40);
+    assert!(persistent
+        .search_by_vector_filtered(&query, 10, &filters[6])
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Khóa không hợp lệ bị từ chối ở cả hai chế độ
+    let injection = MetadataFilter::eq("lang') OR true --", "vi");
+    assert!(persistent
+        .search_by_vector_filtered(&query, 10, &injection)
+        .await
+        .is_err());
+    assert!(in_memory
+        .search_by_vector_filtered(&query, 10, &injection)
+        .await
+        .is_err());
+
+    let store = persistent.lance_store().unwrap();
+    assert_eq!(
+        store
+            .delete_where(&MetadataFilter::eq("draft", true))
+            .await
+            .unwrap(),
+        60
+    );
+    assert_eq!(store.count().await.unwrap(), 60);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_ivf_pq_index_search() {
+    let dir = temp_dir("lance-ivfpq");
+    let dim = 16;
+    let mut engine = open(&dir, dim).await;
+    engine
+        .add_documents((0..100).map(|i| doc(i, dim)).collect())
+        .await
+        .unwrap();
+    let store = engine.lance_store().unwrap().clone();
+    // Quá ít dữ liệu để huấn luyện codebook
+    assert!(store
+        .create_vector_index(&IvfPqConfig::default())
+        .await
+        .is_err());
+    assert!(!store.has_vector_index().await.unwrap());
+
+    engine
+        .add_documents((100..400).map(|i| doc(i, dim)).collect())
+        .await
+        .unwrap();
+    let bad = IvfPqConfig {
+        num_sub_vectors: Some(5),
+        ..Default::default()
+    };
+    assert!(store.create_vector_index(&bad).await.is_err());
+    store
+        .create_vector_index(&IvfPqConfig::default())
+        .await
+        .unwrap();
+    assert!(store.has_vector_index().await.unwrap());
+
+    // Refine trên vector gốc: chính tài liệu luôn đứng đầu
+    for i in (0..400).step_by(37) {
+        let target = vector(i as u64, dim);
+        let hits = store.search(&target, 3, None).await.unwrap();
+        assert_eq!(hits[0].0.document.id, format!("doc-{:04}", i));
+        assert!(hits[0].1 > 0.99);
+    }
+    let filter = MetadataFilter::eq("lang", "vi");
+    let hits = engine
+        .search_by_vector_filtered(&vector(1, dim), 5, &filter)
+        .await
+        .unwrap();
+    assert_eq!(hits.len(), 5);
+    assert!(hits.iter().all(|d| d.metadata["lang"] == "vi"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_ingest_directory_chunks_and_replaces_files() {
+    let root = temp_dir("lance-ingest");
+    let db = root.join("db");
+    let corpus = root.join("corpus");
+    std::fs::create_dir_all(corpus.join("notes")).unwrap();
+    let paragraph = |topic: &str| {
+        format!(
+            "{t} là một chủ đề quan trọng. Bài viết này giải thích {t} một cách chi tiết.\n\n",
+            t = topic
+        )
+    };
+    let long: String = ["Hà Nội", "Huế", "Đà Nẵng", "Sài Gòn", "Cần Thơ"]
+        .iter()
+        .map(|t| paragraph(t))
+        .collect();
+    std::fs::write(corpus.join("cities.md"), &long).unwrap();
+    std::fs::write(
+        corpus.join("notes/pho.txt"),
+        "Phở bò là món ăn sáng phổ biến.",
+    )
+    .unwrap();
+    std::fs::write(corpus.join("image.png"), [0u8, 1, 2]).unwrap();
+    std::fs::write(corpus.join("notes/broken.txt"), [0xffu8, 0xfe, 0x00]).unwrap();
+
+    let config = ChunkingConfig {
+        max_chars: 120,
+        overlap_chars: 20,
+        ..Default::default()
+    };
+    let embedder = Arc::new(HashedNgramEmbedder::with_dim(64));
+    let mut engine = open(&db, 64).await;
+    engine.set_embedder(embedder.clone()).unwrap();
+    let report = engine.ingest_directory(&corpus, &config).await.unwrap();
+    assert_eq!(report.files, 2);
+    assert_eq!(report.skipped, vec![corpus.join("notes/broken.txt")]);
+    assert_eq!(report.removed, 0);
+    let city_chunks = report.chunks - 1;
+    assert!(city_chunks >= 4, "{:?}", report);
+
+    let store = engine.lance_store().unwrap().clone();
+    assert_eq!(store.count().await.unwrap(), report.chunks);
+    let chunk = store
+        .get(&chunk_id(&corpus, "cities.md", 0))
+        .await
+        .unwrap()
+        .unwrap()
+        .document;
+    assert_eq!(chunk.metadata["source"], "cities.md");
+    assert_eq!(chunk.metadata["chunk"], 0);
+    assert_eq!(chunk.metadata["chunks"], city_chunks);
+    assert!(chunk.metadata.contains_key("updated_at"));
+    assert_eq!(chunk.embedding.len(), 64);
+    assert!(store
+        .get(&chunk_id(&corpus, "notes/pho.txt", 0))
+        .await
+        .unwrap()
+        .is_some());
+
+    let hits = engine.search_by_text("phở bò", 1).await.unwrap();
+    assert_eq!(hits[0].id, chunk_id(&corpus, "notes/pho.txt", 0));
+    let from_cities = engine
+        .search_by_vector_filtered(
+            &embedder.embed("Huế").unwrap(),
+            3,
+            &MetadataFilter::eq("source", "cities.md"),
+        )
+        .await
+        .unwrap();
+    assert!(from_cities
+        .iter()
+        .all(|d| d.metadata["source"] == "cities.md"));
+
+    // Thư mục khác nạp vào cùng bảng không bị lần nạp lại của `corpus` đụng tới
+    let other = root.join("other");
+    std::fs::create_dir_all(&other).unwrap();
+    std::fs::write(other.join("tea.txt"), "Trà sen Tây Hồ.").unwrap();
+    engine.ingest_directory(&other, &config).await.unwrap();
+
+    // File ngắn đi: các đoạn thừa bị xóa; file bị xóa: mọi đoạn của nó bị xóa
+    std::fs::write(corpus.join("cities.md"), paragraph("Hà Nội")).unwrap();
+    std::fs::remove_file(corpus.join("notes/pho.txt")).unwrap();
+    let report = engine.ingest_directory(&corpus, &config).await.unwrap();
+    assert_eq!(report.files, 1);
+    assert_eq!(report.chunks, 1);
+    assert_eq!(report.removed, city_chunks);
+    drop(engine);
+
+    let engine = open(&db, 64).await;
+    let store = engine.lance_store().unwrap();
+    assert_eq!(store.count().await.unwrap(), 2);
+    assert!(store
+        .get(&chunk_id(&corpus, "cities.md", 1))
+        .await
+        .unwrap()
+        .is_none());
+    assert!(store
+        .get(&chunk_id(&corpus, "notes/pho.txt", 0))
+        .await
+        .unwrap()
+        .is_none());
+    assert!(store
+        .get(&chunk_id(&other, "tea.txt", 0))
+        .await
+        .unwrap()
+        .is_some());
+    assert!(engine.search_by_text("Huế", 5).await.unwrap().is_empty());
+    assert!(engine.search_by_text("phở", 5).await.unwrap().is_empty());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn test_ingest_directories_sharing_a_filename_do_not_collide() {
+    let root = temp_dir("lance-ingest-shared");
+    let (first, second) = (root.join("first"), root.join("second"));
+    let paragraph = |topic: &str| {
+        format!(
+            "{t} là một chủ đề quan trọng. Bài viết này giải thích {t} một cách chi tiết.\n\n",
+            t = topic
+        )
+    };
+    for (dir, topics) in [(&first, ["Hà Nội", "Huế"]), (&second, ["Phở", "Bún chả"])] {
+        std::fs::create_dir_all(dir).unwrap();
+        let text: String = topics.iter().map(|t| paragraph(t)).collect();
+        std::fs::write(dir.join("README.md"), text).unwrap();
+    }
+    let config = ChunkingConfig {
+        max_chars: 120,
+        overlap_chars: 20,
+        ..Default::default()
+    };
+    let mut engine = open(&root.join("db"), 64).await;
+    engine
+        .set_embedder(Arc::new(HashedNgramEmbedder::with_dim(64)))
+        .unwrap();
+    let first_chunks = engine
+        .ingest_directory(&first, &config)
+        .await
+        .unwrap()
+        .chunks;
+    let second_chunks = engine
+        .ingest_directory(&second, &config)
+        .await
+        .unwrap()
+        .chunks;
+    assert!(second_chunks >= 2);
+    let store = engine.lance_store().unwrap().clone();
+    assert_eq!(store.count().await.unwrap(), first_chunks + second_chunks);
+
+    // Nạp lại `first` với README ngắn hơn chỉ xóa đoạn thừa của chính nó
+    std::fs::write(first.join("README.md"), paragraph("Hà Nội")).unwrap();
+    let report = engine.ingest_directory(&first, &config).await.unwrap();
+    assert_eq!(report.chunks, 1);
+    assert_eq!(report.removed, first_chunks - 1);
+    assert_eq!(store.count().await.unwrap(), 1 + second_chunks);
+    for chunk in 0..second_chunks {
+        let doc = store
+            .get(&chunk_id(&second, "README.md", chunk))
+            .await
+            .unwrap()
+            .unwrap()
+            .document;
+        assert_eq!(doc.metadata["source"], "README.md");
+    }
+    let hits = engine.search_by_text("Bún chả", 1).await.unwrap();
+    assert_eq!(
+        hits[0].metadata["root"],
+        json!(second.canonicalize().unwrap().to_string_lossy())
+    );
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn test_embeddings_from_set_embedder_are_persisted_on_upsert() {
+    let dir = temp_dir("lance-embedder");
+    let text_doc = |id: &str, content: &str| Document {
+        id: id.into(),
+        content: content.into(),
+        embedding: Vec::new(),
+        metadata: HashMap::new(),
+    };
+    let mut engine = open(&dir, 32).await;
+    engine
+        .add_document(text_doc("plain", "Văn bản chưa có embedding"))
+        .await
+        .unwrap();
+    let store = engine.lance_store().unwrap().clone();
+    let stored = store.get("plain").await.unwrap().unwrap().document;
+    assert!(stored.embedding.is_empty());
+
+    let embedder = Arc::new(HashedNgramEmbedder::with_dim(32));
+    engine.set_embedder(embedder.clone()).unwrap();
+    engine
+        .add_document(text_doc("next", "Tài liệu kế tiếp"))
+        .await
+        .unwrap();
+    let stored = store.get("plain").await.unwrap().unwrap().document;
+    assert_eq!(stored.embedding, embedder.embed(&stored.content).unwrap());
+    drop(engine);
+
+    // Mở lại không cần embedder: vector đã nằm trong bảng
+    let engine = open(&dir, 32).await;
+    let hits = engine
+        .search_by_vector(&embedder.embed("Văn bản chưa có embedding").unwrap(), 1)
+        .await
+        .unwrap();
+    assert_eq!(hits[0].id, "plain");
+    let _ = std::fs::remove_dir_all(&dir);
+}
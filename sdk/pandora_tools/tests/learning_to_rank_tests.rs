@@ -0,0 +1,216 @@
+// sdk/pandora_tools/tests/learning_to_rank_tests.rs
+
+use pandora_tools::skills::information_retrieval_skill::embedding::HashedNgramEmbedder;
+use pandora_tools::skills::information_retrieval_skill::*;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const DAY: i64 = 86_400;
+
+fn doc(id: &str, content: &str, age_days: i64) -> Document {
+    let timestamp = chrono::Utc::now().timestamp() - age_days * DAY;
+    Document {
+        id: id.into(),
+        content: content.into(),
+        embedding: Vec::new(),
+        metadata: HashMap::from([("timestamp".to_string(), json!(timestamp))]),
+    }
+}
+
+/// Mỗi chủ đề có ba tài liệu: một bài nhồi từ khóa cũ (BM25 và cosine cao nhất), một ghi
+/// chú cũ và một hướng dẫn mới mà người dùng thực sự chọn.
+async fn add_topic(engine: &mut ProgressiveSemanticEngine, topic: &str) {
+    let spam = format!("{t} {t} {t} {t}", t = topic);
+    let fresh = format!("a practical guide to {} with worked examples", topic);
+    let old = format!("notes about {} and other things", topic);
+    for d in [
+        doc(&format!("{}-spam", topic), &spam, 1_000),
+        doc(&format!("{}-fresh", topic), &fresh, 1),
+        doc(&format!("{}-old", topic), &old, 700),
+    ] {
+        engine.add_document(d).await.unwrap();
+    }
+}
+
+async fn trained_engine(topics: usize) -> ProgressiveSemanticEngine {
+    let mut engine =
+        ProgressiveSemanticEngine::with_embedder(Arc::new(HashedNgramEmbedder::with_dim(64)));
+    for i in 0..topics {
+        let topic = format!("topic{}", i);
+        add_topic(&mut engine, &topic).await;
+        for (suffix, relevance) in [("fresh", 1.0), ("old", 0.3), ("spam", 0.0)] {
+            engine
+                .record_feedback(&topic, &format!("{}-{}", topic, suffix), relevance)
+                .unwrap();
+        }
+    }
+    engine
+}
+
+#[tokio::test]
+async fn test_ltr_beats_untrained_baseline_on_held_out_queries() {
+    let engine = trained_engine(30).await;
+    assert_eq!(engine.feedback_history().len(), 90);
+
+    let report = engine.evaluate_ranking(3, 0.3).unwrap();
+    assert_eq!(report.test_queries, 9);
+    assert_eq!(report.train_queries, 21);
+    assert_eq!(report.training_pairs, 21 * 3);
+    // Trọng số chưa học xếp bài nhồi từ khóa lên đầu
+    assert!(report.baseline_mrr < 1.0);
+    assert_eq!(report.mrr, 1.0);
+    assert!(report.ndcg_at_k > report.baseline_ndcg_at_k + 0.2);
+    assert_eq!(engine.evaluate_ranking(3, 0.3).unwrap(), report);
+
+    // Mô hình online học được rằng độ mới quan trọng hơn BM25
+    let ranker = engine.result_ranker();
+    assert_eq!(ranker.learning_model.as_deref(), Some("ranknet-linear"));
+    assert!(ranker.feature_weights["recency"] > 0.5);
+    assert!(ranker.feature_weights["bm25"] < 1.0);
+}
+
+#[tokio::test]
+async fn test_ltr_learns_conflicting_features_not_just_recency() {
+    // Độ mới và độ khớp văn bản kéo ngược chiều nhau: bài nhồi từ khóa cũ thắng về BM25,
+    // bản tin mới nhất (không nói về chủ đề) thắng về độ mới; chỉ hướng dẫn vừa mới vừa
+    // khớp là liên quan.
+    let mut engine =
+        ProgressiveSemanticEngine::with_embedder(Arc::new(HashedNgramEmbedder::with_dim(64)));
+    for i in 0..30 {
+        let topic = format!("subject{}", i);
+        let docs = [
+            (
+                format!("{}-spam", topic),
+                format!("{t} {t} {t} {t}", t = topic),
+                1_000,
+                0.0,
+            ),
+            (
+                format!("{}-guide", topic),
+                format!("a practical guide to {} with worked examples", topic),
+                3,
+                1.0,
+            ),
+            (
+                format!("{}-news", topic),
+                "weekly roundup of weather, traffic and sports".to_string(),
+                0,
+                0.0,
+            ),
+        ];
+        for (id, content, age, relevance) in docs {
+            engine.add_document(doc(&id, &content, age)).await.unwrap();
+            engine.record_feedback(&topic, &id, relevance).unwrap();
+        }
+    }
+
+    // Trên truy vấn giữ lại: mốc chưa học chọn bài nhồi từ khóa, xếp theo độ mới sẽ chọn
+    // bản tin; mô hình đã học chọn đúng hướng dẫn
+    let report = engine.evaluate_ranking(3, 0.3).unwrap();
+    assert!(report.baseline_mrr < 1.0);
+    assert_eq!(report.mrr, 1.0);
+
+    // Cần cả hai loại trọng số: độ mới để vượt bài nhồi từ khóa, văn bản để vượt bản tin
+    let weights = &engine.result_ranker().feature_weights;
+    assert!(weights["recency"] > 0.0, "{:?}", weights);
+    assert!(weights["bm25"] + weights["cosine"] > 0.0, "{:?}", weights);
+}
+
+#[tokio::test]
+async fn test_learning_to_rank_stage_uses_trained_model() {
+    let mut engine = trained_engine(20).await;
+    add_topic(&mut engine, "unseen").await;
+    let pipeline = |algorithm: RankingAlgorithm| SearchPipeline {
+        stages: vec![
+            SearchStage::VectorSearch {
+                k: 10,
+                threshold: 0.0,
+            },
+            SearchStage::TextSearch {
+                k: 10,
+                boost_factors: HashMap::new(),
+            },
+            SearchStage::ResultRanking { algorithm },
+        ],
+        early_exit_conditions: Vec::new(),
+        result_fusion: FusionStrategy::CombSum,
+    };
+    let unseen_order = |out: &CognitiveOutput| -> Vec<String> {
+        out.documents
+            .iter()
+            .map(|d| d.id.clone())
+            .filter(|id| id.starts_with("unseen-"))
+            .collect()
+    };
+
+    engine.update_search_pipeline(pipeline(RankingAlgorithm::Hybrid));
+    let hybrid = engine.search(&json!("unseen")).await.unwrap();
+    assert_eq!(hybrid.documents[0].id, "unseen-spam");
+
+    engine.update_search_pipeline(pipeline(RankingAlgorithm::LearningToRank));
+    let ltr = engine.search(&json!("unseen")).await.unwrap();
+    assert_eq!(
+        unseen_order(&ltr),
+        vec!["unseen-fresh", "unseen-old", "unseen-spam"]
+    );
+    assert!(ltr
+        .reasoning_trace
+        .iter()
+        .any(|t| t.contains("LearningToRank")));
+
+    // Chưa có phản hồi: giữ thứ tự hợp nhất
+    let mut untrained =
+        ProgressiveSemanticEngine::with_embedder(Arc::new(HashedNgramEmbedder::with_dim(64)));
+    add_topic(&mut untrained, "unseen").await;
+    untrained.update_search_pipeline(pipeline(RankingAlgorithm::LearningToRank));
+    let out = untrained.search(&json!("unseen")).await.unwrap();
+    assert_eq!(out.documents[0].id, "unseen-spam");
+}
+
+#[tokio::test]
+async fn test_feedback_history_is_capped() {
+    let mut engines = Vec::new();
+    for cap in [4, DEFAULT_MAX_FEEDBACK_HISTORY] {
+        let mut engine =
+            ProgressiveSemanticEngine::with_embedder(Arc::new(HashedNgramEmbedder::with_dim(64)));
+        engine.set_max_feedback_history(cap);
+        add_topic(&mut engine, "alpha").await;
+        add_topic(&mut engine, "beta").await;
+        engine.record_feedback("alpha", "alpha-fresh", 1.0).unwrap();
+        for (suffix, relevance) in [("fresh", 1.0), ("old", 0.3), ("spam", 0.0), ("fresh", 0.9)] {
+            engine
+                .record_feedback("beta", &format!("beta-{}", suffix), relevance)
+                .unwrap();
+        }
+        let before = engine.result_ranker().feature_weights.clone();
+        engine.record_feedback("Alpha ", "alpha-spam", 0.0).unwrap();
+        engines.push((engine, before));
+    }
+
+    let (capped, before) = &engines[0];
+    let history = capped.feedback_history();
+    assert_eq!(history.len(), 4);
+    assert_eq!(history[0].result_id, "beta-old");
+    assert_eq!(history[3].result_id, "alpha-spam");
+    // Phản hồi "alpha-fresh" đã bị loại nên phản hồi mới không còn cặp nào để học
+    assert_eq!(&capped.result_ranker().feature_weights, before);
+    let (uncapped, before) = &engines[1];
+    assert_eq!(uncapped.feedback_history().len(), 6);
+    assert_ne!(&uncapped.result_ranker().feature_weights, before);
+}
+
+#[tokio::test]
+async fn test_feedback_validation() {
+    let mut engine = ProgressiveSemanticEngine::in_memory(4);
+    engine.add_document(doc("a", "alpha", 0)).await.unwrap();
+    assert!(matches!(
+        engine.record_feedback("alpha", "missing", 1.0),
+        Err(RetrievalError::DocumentNotFound(_))
+    ));
+    assert!(engine.record_feedback("alpha", "a", 1.5).is_err());
+    engine.record_feedback("alpha", "a", 1.0).unwrap();
+    // Một truy vấn không đủ để tách tập giữ lại
+    assert!(engine.evaluate_ranking(5, 0.2).is_err());
+    assert!(engine.evaluate_ranking(5, 1.0).is_err());
+}
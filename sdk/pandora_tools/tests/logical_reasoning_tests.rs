@@ -3,28 +3,38 @@ use serde_json::json;
 
 #[test]
 fn test_simple_inference() {
-    let _engine = OptimizedJsonAstEngine::new(10);
-    let _facts = json!({ "temp": 80, "location": "indoor" });
-    let _rule = r#"{ "and": [ { ">": [ { "var": "temp" }, 70 ] }, { "==": [ { "var": "location" }, "indoor" ] } ] }"#;
-    // TODO: enable after compile_ast_to_closure is fully implemented
-    // let result = engine.infer(&facts, rule).unwrap();
-    // assert!(result);
+    let engine = OptimizedJsonAstEngine::new(10);
+    let facts = json!({ "temp": 80, "location": "indoor" });
+    let rule = r#"{ "and": [ { ">": [ { "var": "temp" }, 70 ] }, { "==": [ { "var": "location" }, "indoor" ] } ] }"#;
+    let result = engine.infer(&facts, rule).unwrap();
+    assert_eq!(result, Truth::True);
 }
 
 #[test]
 fn test_inference_failure() {
-    let _engine = OptimizedJsonAstEngine::new(10);
-    let _facts = json!({ "temp": 60 });
-    let _rule = r#"{ ">": [ { "var": "temp" }, 70 ] }"#;
-    // TODO: enable after compile_ast_to_closure is fully implemented
-    // let result = engine.infer(&facts, rule).unwrap();
-    // assert!(!result);
+    let engine = OptimizedJsonAstEngine::new(10);
+    let facts = json!({ "temp": 60 });
+    let rule = r#"{ ">": [ { "var": "temp" }, 70 ] }"#;
+    let result = engine.infer(&facts, rule).unwrap();
+    assert_eq!(result, Truth::False);
 }
 
 #[test]
 fn test_caching_mechanism() {
-    let _engine = OptimizedJsonAstEngine::new(10);
-    let _facts = json!({ "value": 10 });
-    let _rule = r#"{ "==": [ { "var": "value" }, 10 ] }"#;
-    // TODO: add a counter to validate caching once compile is implemented
+    let engine = OptimizedJsonAstEngine::new(10);
+    let rule = r#"{ "==": [ { "var": "value" }, 10 ] }"#;
+    assert_eq!(
+        engine.infer(&json!({ "value": 10 }), rule).unwrap(),
+        Truth::True
+    );
+    // Cùng quy tắc, facts khác: dùng lại closure đã compile
+    assert_eq!(
+        engine.infer(&json!({ "value": 11 }), rule).unwrap(),
+        Truth::False
+    );
+    assert_eq!(engine.infer(&json!({}), rule).unwrap(), Truth::Unknown);
+    assert_eq!(engine.cached_rules(), 1);
+
+    engine.infer(&json!({}), r#"{ "var": "flag" }"#).unwrap();
+    assert_eq!(engine.cached_rules(), 2);
 }
@@ -0,0 +1,84 @@
+// sdk/pandora_tools/tests/static_model_tests.rs
+#![cfg(feature = "local_embeddings")]
+
+use candle_core::{Device, Tensor};
+use pandora_tools::skills::analogy_reasoning_skill::*;
+use pandora_tools::skills::information_retrieval_skill::embedding::*;
+use pandora_tools::skills::information_retrieval_skill::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+fn doc(id: &str, content: &str) -> Document {
+    Document {
+        id: id.into(),
+        content: content.into(),
+        embedding: Vec::new(),
+        metadata: Default::default(),
+    }
+}
+
+/// Mô hình tĩnh 2 chiều: man, king, woman, queen lần lượt ở góc 10°, 40°, 70°, 100°.
+fn write_static_model(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let tokens = [
+        ("[UNK]", [0.0, 0.0]),
+        ("king", [0.766, 0.643]),
+        ("queen", [-0.174, 0.985]),
+        ("man", [0.985, 0.174]),
+        ("woman", [0.342, 0.940]),
+        ("royal", [0.7, 0.7]),
+        ("##ty", [0.8, 0.6]),
+    ];
+    let vocab: Vec<&str> = tokens.iter().map(|(t, _)| *t).collect();
+    std::fs::write(dir.join("vocab.txt"), vocab.join("\n")).unwrap();
+    let data: Vec<f32> = tokens.iter().flat_map(|(_, v)| *v).collect();
+    let embeddings = Tensor::from_vec(data, (tokens.len(), 2), &Device::Cpu).unwrap();
+    candle_core::safetensors::save(
+        &HashMap::from([("embeddings", embeddings)]),
+        dir.join("model.safetensors"),
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_static_model_loads_safetensors_and_mean_pools() {
+    let dir = write_static_model("static-model-pooling");
+    let model = StaticEmbeddingModel::from_dir(&dir).unwrap();
+    assert_eq!(model.dim(), 2);
+
+    let king = model.embed("King").unwrap();
+    assert!((cosine(&king, &[0.766, 0.643]) - 1.0).abs() < 1e-3);
+    // "royalty" = royal + ##ty, trung bình rồi chuẩn hóa
+    let royalty = model.embed("royalty").unwrap();
+    assert!(cosine(&royalty, &king) > 0.95);
+    assert!(model.embed("").unwrap().iter().all(|x| *x == 0.0));
+
+    assert!(StaticEmbeddingModel::from_dir(dir.join("missing")).is_err());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_analogy_over_model_embeddings() {
+    let dir = write_static_model("static-model-analogy");
+    let model = Arc::new(StaticEmbeddingModel::from_dir(&dir).unwrap());
+    let mut engine = ProgressiveSemanticEngine::in_memory(2);
+    for word in ["king", "queen", "man", "woman"] {
+        engine.add_document(doc(word, word)).await.unwrap();
+    }
+    let engine = Arc::new(RwLock::new(engine));
+    let analogy = AnalogyEngine::new(engine.clone());
+    assert!(matches!(
+        analogy.solve_analogy("man", "king", "woman").await,
+        Err(AnalogyError::EmbeddingNotFound(_))
+    ));
+
+    // Gắn mô hình sau: tài liệu chưa có embedding được embed lại
+    engine.write().await.set_embedder(model).unwrap();
+    let out = analogy.solve_analogy("man", "king", "woman").await.unwrap();
+    assert_eq!(out.content, "queen");
+    std::fs::remove_dir_all(dir).unwrap();
+}